@@ -227,3 +227,57 @@ impl Drop for Encoder {
 }
 
 unsafe impl Send for Encoder {}
+
+#[derive(Debug)]
+pub struct Decoder {
+    ptr: std::ptr::NonNull<::std::os::raw::c_void>,
+    channels: usize,
+}
+
+impl Decoder {
+    pub fn new(sample_rate: SampleRate, channels: Channels) -> Result<Decoder, OpusError> {
+        let mut err = 0;
+        let p_dec = unsafe {
+            inner::_opus_decoder_create(sample_rate.as_int32(), channels.as_int32(), &mut err)
+        };
+
+        if err == 0 && !p_dec.is_null() {
+            Ok(Decoder {
+                ptr: unsafe { std::ptr::NonNull::new_unchecked(p_dec) },
+                channels: channels.as_int32() as usize,
+            })
+        } else {
+            Err(OpusError::from(err))
+        }
+    }
+
+    /// Decodes one packet into `output` (interleaved) and returns the samples per channel.
+    /// `output` must hold the packet's whole frame, up to 120 ms.
+    pub fn decode_float(&mut self, input: &[u8], output: &mut [f32]) -> Result<usize, OpusError> {
+        let rc: i32 = unsafe {
+            inner::_opus_decode_float(
+                self.ptr.as_ptr(),
+                input.as_ptr(),
+                input.len(),
+                output.as_mut_ptr(),
+                output.len() / self.channels,
+                0,
+            )
+        };
+        if rc >= 0 {
+            Ok(rc as usize)
+        } else {
+            Err(rc.into())
+        }
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        unsafe {
+            inner::_opus_decoder_destroy(self.ptr.as_ptr());
+        }
+    }
+}
+
+unsafe impl Send for Decoder {}
@@ -0,0 +1,236 @@
+use super::{
+    crc16, with_parity, Message, BAUD, ETB, ETX, HEADER_LEN, HIGH_HZ, LOW_HZ, MAX_TEXT_LEN, SOH,
+    STX, SYN,
+};
+use num_complex::Complex32;
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+
+/// Two SYN characters, as the last 16 bits received.
+const SYNC_BITS: u16 = (SYN as u16) << 8 | SYN as u16;
+/// Share of the timing error corrected at every tone change.
+const CLOCK_GAIN: f64 = 0.2;
+/// Header, STX, text and the closing ETX or ETB.
+const MAX_BLOCK_LEN: usize = HEADER_LEN + MAX_TEXT_LEN + 2;
+
+/// Mean of the last `len` samples.
+struct Boxcar {
+    history: VecDeque<Complex32>,
+    len: usize,
+    sum: Complex32,
+}
+
+impl Boxcar {
+    fn new(len: usize) -> Self {
+        Self {
+            history: VecDeque::with_capacity(len),
+            len,
+            sum: Complex32::new(0.0, 0.0),
+        }
+    }
+
+    fn push(&mut self, z: Complex32) -> Complex32 {
+        if self.history.len() == self.len {
+            self.sum -= self.history.pop_front().unwrap_or_default();
+        }
+        self.history.push_back(z);
+        self.sum += z;
+        self.sum / self.len as f32
+    }
+}
+
+enum State {
+    /// Looking for the two SYN characters.
+    Hunting,
+    Soh,
+    Block,
+    /// Receiving the two block check characters.
+    Check,
+}
+
+/// Decodes ACARS blocks from AM-demodulated audio. The tones are mixed down around their
+/// centre, where the high tone turns the phase forward and the low tone back; bits are sampled
+/// at a clock that follows the tone changes.
+pub struct Decoder {
+    mix_step: f64,
+    mix_phase: f64,
+    filters: [Boxcar; 2],
+    previous: Complex32,
+    samples_per_bit: f64,
+    /// Fraction of the current bit elapsed.
+    clock: f64,
+    /// Phase steps of the two halves of the current bit, weighted by amplitude.
+    halves: [Complex32; 2],
+    /// Second half and whole of the previous bit.
+    previous_half: f32,
+    previous_turn: f32,
+    bit: u8,
+    inverted: bool,
+    sync: u16,
+    byte: u8,
+    bits: usize,
+    state: State,
+    block: Vec<u8>,
+    check: Vec<u8>,
+    messages: VecDeque<Message>,
+}
+
+impl Decoder {
+    pub fn new(sample_rate: f64) -> Self {
+        let samples_per_bit = sample_rate / BAUD;
+        let filter_len = (samples_per_bit as usize).max(1);
+        Self {
+            mix_step: TAU * (LOW_HZ + HIGH_HZ) / 2.0 / sample_rate,
+            mix_phase: 0.0,
+            filters: [Boxcar::new(filter_len), Boxcar::new(filter_len)],
+            previous: Complex32::new(0.0, 0.0),
+            samples_per_bit,
+            clock: 0.0,
+            halves: [Complex32::new(0.0, 0.0); 2],
+            previous_half: 0.0,
+            previous_turn: 0.0,
+            bit: 1,
+            inverted: false,
+            sync: 0,
+            byte: 0,
+            bits: 0,
+            state: State::Hunting,
+            block: Vec::with_capacity(MAX_BLOCK_LEN),
+            check: Vec::with_capacity(2),
+            messages: VecDeque::new(),
+        }
+    }
+
+    pub fn process(&mut self, audio: &[f32]) {
+        for &x in audio {
+            let (sin, cos) = self.mix_phase.sin_cos();
+            self.mix_phase = (self.mix_phase + self.mix_step) % TAU;
+            let mixed = Complex32::new(x * cos as f32, -x * sin as f32);
+            let smoothed = self.filters[0].push(mixed);
+            let z = self.filters[1].push(smoothed);
+            self.halves[usize::from(self.clock >= 0.5)] += z * self.previous.conj();
+            self.previous = z;
+            self.clock += 1.0 / self.samples_per_bit;
+            if self.clock >= 1.0 {
+                self.clock -= 1.0;
+                self.end_bit();
+            }
+        }
+    }
+
+    fn end_bit(&mut self) {
+        let [first, second] = std::mem::take(&mut self.halves);
+        let turn = (first + second).im;
+        // Where the tone changes, the half bits either side of the edge turn the phase equally
+        // far both ways; what is left over is how late the clock is, in bits.
+        if (turn > 0.0) != (self.previous_turn > 0.0) {
+            let around_edge = self.previous_half + first.im;
+            let scale = self.previous_turn.abs() + turn.abs();
+            if scale > 0.0 {
+                let late = f64::from(around_edge * turn.signum() / scale);
+                self.clock += CLOCK_GAIN * late.clamp(-0.5, 0.5);
+            }
+        }
+        self.previous_half = second.im;
+        self.previous_turn = turn;
+        // The high tone repeats the previous bit.
+        if turn < 0.0 {
+            self.bit ^= 1;
+        }
+        self.push_bit(self.bit);
+    }
+
+    pub fn pop_message(&mut self) -> Option<Message> {
+        self.messages.pop_front()
+    }
+
+    fn push_bit(&mut self, bit: u8) {
+        let bit = bit ^ u8::from(self.inverted);
+        if let State::Hunting = self.state {
+            self.sync = self.sync >> 1 | u16::from(bit) << 15;
+            if self.sync == SYNC_BITS || self.sync == !SYNC_BITS {
+                // Which tone run the bits started from is not known; SYN tells.
+                self.inverted ^= self.sync != SYNC_BITS;
+                self.state = State::Soh;
+                self.bits = 0;
+            }
+            return;
+        }
+        self.byte = self.byte >> 1 | bit << 7;
+        self.bits += 1;
+        if self.bits == 8 {
+            self.bits = 0;
+            self.on_byte(self.byte);
+        }
+    }
+
+    fn on_byte(&mut self, byte: u8) {
+        match self.state {
+            State::Hunting => {}
+            State::Soh if byte == with_parity(SOH) => {
+                self.block.clear();
+                self.state = State::Block;
+            }
+            State::Soh => self.hunt(),
+            State::Block => {
+                self.block.push(byte);
+                if matches!(byte & 0x7f, ETX | ETB) {
+                    self.check.clear();
+                    self.state = State::Check;
+                } else if self.block.len() >= MAX_BLOCK_LEN {
+                    self.hunt();
+                }
+            }
+            State::Check => {
+                self.check.push(byte);
+                if self.check.len() == 2 {
+                    self.block.extend_from_slice(&self.check);
+                    if crc16(&self.block) == 0 {
+                        if let Some(message) = parse(&self.block[..self.block.len() - 2]) {
+                            self.messages.push_back(message);
+                        }
+                    }
+                    self.hunt();
+                }
+            }
+        }
+    }
+
+    fn hunt(&mut self) {
+        self.state = State::Hunting;
+        self.sync = 0;
+    }
+}
+
+/// Fields of a block from its mode character to its ETX or ETB.
+fn parse(block: &[u8]) -> Option<Message> {
+    let chars: Vec<u8> = block.iter().map(|b| b & 0x7f).collect();
+    let (&end, body) = chars.split_last()?;
+    if body.len() < HEADER_LEN {
+        return None;
+    }
+    let (header, rest) = body.split_at(HEADER_LEN);
+    let text = match rest.split_first() {
+        None => &[][..],
+        Some((&STX, text)) => text,
+        Some(_) => return None,
+    };
+    let string = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    let mut message = Message {
+        mode: header[0] as char,
+        registration: string(&header[1..8]).trim_start_matches('.').to_string(),
+        ack: (header[8] != super::NAK).then_some(header[8] as char),
+        label: string(&header[9..11]),
+        block_id: header[11] as char,
+        message_number: None,
+        flight: None,
+        text: string(text),
+        more: end == ETB,
+    };
+    if message.is_downlink() && text.len() >= 10 {
+        message.message_number = Some(string(&text[..4]));
+        message.flight = Some(string(&text[4..10]));
+        message.text = string(&text[10..]);
+    }
+    Some(message)
+}
@@ -0,0 +1,66 @@
+use super::{
+    crc16, with_parity, Message, BAUD, DEL, ETB, ETX, HIGH_HZ, LOW_HZ, NAK, SOH, STX, SYN,
+};
+use std::f64::consts::TAU;
+
+/// Carrier keyed with ones before the frame, for the receiver to settle on.
+const PRE_KEY_BYTES: usize = 16;
+/// Bit and character sync.
+const SYNC: [u8; 4] = [b'+', b'*', SYN, SYN];
+
+/// `message` as the audio an AM receiver demodulates from an ACARS transmitter, at
+/// `sample_rate`.
+pub fn encode(message: &Message, sample_rate: f64) -> Vec<f32> {
+    let mut block = vec![with_parity(message.mode as u8)];
+    let registration = format!("{:.>7}", message.registration);
+    block.extend(registration.bytes().take(7).map(with_parity));
+    block.push(with_parity(message.ack.map_or(NAK, |c| c as u8)));
+    block.extend(message.label.bytes().take(2).map(with_parity));
+    block.push(with_parity(message.block_id as u8));
+    let mut text = String::new();
+    if message.is_downlink() {
+        text.push_str(message.message_number.as_deref().unwrap_or_default());
+        text.push_str(message.flight.as_deref().unwrap_or_default());
+    }
+    text.push_str(&message.text);
+    if !text.is_empty() {
+        block.push(with_parity(STX));
+        block.extend(text.bytes().map(with_parity));
+    }
+    block.push(with_parity(if message.more { ETB } else { ETX }));
+    let crc = crc16(&block);
+
+    let mut bytes = vec![0xff; PRE_KEY_BYTES];
+    bytes.extend(SYNC.iter().map(|&c| with_parity(c)));
+    bytes.push(with_parity(SOH));
+    bytes.extend(block);
+    bytes.extend(crc.to_le_bytes());
+    bytes.push(with_parity(DEL));
+
+    let bits: Vec<u8> = bytes
+        .iter()
+        .flat_map(|&b| (0..8).map(move |i| b >> i & 1))
+        .collect();
+    // Tone of every bit and the phase it starts at, so the phase runs on continuously.
+    let mut previous = 1;
+    let mut phase = 0.0;
+    let tones: Vec<(f64, f64)> = bits
+        .iter()
+        .map(|&bit| {
+            let hz = if bit == previous { HIGH_HZ } else { LOW_HZ };
+            previous = bit;
+            let start = phase;
+            phase = (phase + TAU * hz / BAUD) % TAU;
+            (hz, start)
+        })
+        .collect();
+    let len = (tones.len() as f64 * sample_rate / BAUD).round() as usize;
+    (0..len)
+        .map(|n| {
+            let t = n as f64 / sample_rate;
+            let k = ((t * BAUD) as usize).min(tones.len() - 1);
+            let (hz, start) = tones[k];
+            (start + TAU * hz * (t - k as f64 / BAUD)).sin() as f32
+        })
+        .collect()
+}
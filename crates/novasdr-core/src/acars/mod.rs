@@ -0,0 +1,77 @@
+//! ACARS, the aircraft datalink on the VHF airband: 2400 bit/s MSK on an AM carrier, with
+//! tones of [`LOW_HZ`] and [`HIGH_HZ`]. The high tone repeats the previous bit and the low tone
+//! inverts it. Characters are 7-bit ASCII with odd parity, least significant bit first.
+
+mod decode;
+mod encode;
+
+pub use decode::Decoder;
+pub use encode::encode;
+
+pub const BAUD: f64 = 2400.0;
+pub const LOW_HZ: f64 = 1200.0;
+pub const HIGH_HZ: f64 = 2400.0;
+
+const SOH: u8 = 0x01;
+const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+/// Ends a block when more blocks of the message follow.
+const ETB: u8 = 0x17;
+const SYN: u8 = 0x16;
+const NAK: u8 = 0x15;
+const DEL: u8 = 0x7f;
+/// Mode, address, acknowledgement, label and block id.
+const HEADER_LEN: usize = 12;
+const MAX_TEXT_LEN: usize = 220;
+
+/// One decoded block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub mode: char,
+    /// Aircraft registration, without the dots that pad it to seven characters.
+    pub registration: String,
+    /// Block id of the message this one acknowledges; `None` for a negative acknowledgement.
+    pub ack: Option<char>,
+    pub label: String,
+    pub block_id: char,
+    /// Message number and flight id, which lead the text of downlinks.
+    pub message_number: Option<String>,
+    pub flight: Option<String>,
+    pub text: String,
+    /// Ended with ETB: further blocks of the message follow.
+    pub more: bool,
+}
+
+impl Message {
+    /// Sent by an aircraft; uplinks from the ground have a letter as block id.
+    pub fn is_downlink(&self) -> bool {
+        self.block_id.is_ascii_digit()
+    }
+}
+
+/// `c` with the parity bit that makes its count of ones odd.
+fn with_parity(c: u8) -> u8 {
+    let c = c & 0x7f;
+    if c.count_ones().is_multiple_of(2) {
+        c | 0x80
+    } else {
+        c
+    }
+}
+
+/// Block check sequence: CRC-16/KERMIT over the characters as sent, parity included. Appending
+/// it low byte first makes the CRC of the whole block zero.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &b in bytes {
+        crc ^= u16::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
@@ -0,0 +1,111 @@
+//! Wall-clock rules for everything that runs at set times. Instants are always UTC; a local
+//! time only exists at the edges, as configured, and is turned into UTC here so daylight saving
+//! changes are handled in one place.
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+/// The current instant. Timestamps the server stores or sends to others, such as signed
+/// directory listings, are taken from here.
+pub fn now() -> DateTime<Utc> {
+    Utc::now()
+}
+
+/// Time zone of configured wall-clock times: UTC, or an IANA zone such as `Europe/Berlin`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Zone {
+    #[default]
+    Utc,
+    Named(Tz),
+}
+
+impl Zone {
+    /// `UTC` (or empty) or an IANA zone name.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name.is_empty() || name.eq_ignore_ascii_case("utc") || name == "Z" {
+            return Ok(Self::Utc);
+        }
+        name.parse::<Tz>()
+            .map(Self::Named)
+            .map_err(|_| format!("unknown time zone {name:?}"))
+    }
+
+    /// The instant a local wall-clock time stands for. A time skipped by a spring-forward change
+    /// is moved forward by the gap (02:30 becomes 03:30); a time repeated by a fall-back change
+    /// is its first instant, so a daily event still happens once.
+    pub fn to_utc(self, local: NaiveDateTime) -> DateTime<Utc> {
+        let Self::Named(tz) = self else {
+            return local.and_utc();
+        };
+        match tz.from_local_datetime(&local) {
+            LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => t.with_timezone(&Utc),
+            LocalResult::None => {
+                // Read with the offset from before the change; transitions are months apart.
+                let before = tz
+                    .from_local_datetime(&(local - Duration::days(1)))
+                    .earliest()
+                    .map_or(0, |t| t.offset().fix().local_minus_utc());
+                (local - Duration::seconds(i64::from(before))).and_utc()
+            }
+        }
+    }
+
+    /// Local calendar date at `at`.
+    pub fn date(self, at: DateTime<Utc>) -> NaiveDate {
+        match self {
+            Self::Utc => at.date_naive(),
+            Self::Named(tz) => at.with_timezone(&tz).date_naive(),
+        }
+    }
+}
+
+/// A time of day in a zone, every day or on some weekdays of the local calendar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyTime {
+    pub time: NaiveTime,
+    pub zone: Zone,
+    /// `None` for every day.
+    pub days: Option<Vec<Weekday>>,
+}
+
+impl DailyTime {
+    /// Start of the occurrence running at `now`, for occurrences lasting `duration` (at most a
+    /// day).
+    pub fn running(&self, duration: Duration, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let today = self.zone.date(now);
+        // A day-long occurrence may have started the day before yesterday when yesterday was
+        // shortened by a spring-forward change.
+        let yesterday = today.pred_opt()?;
+        [today, yesterday, yesterday.pred_opt()?]
+            .into_iter()
+            .filter(|d| {
+                self.days
+                    .as_ref()
+                    .is_none_or(|days| days.contains(&d.weekday()))
+            })
+            .map(|d| self.zone.to_utc(d.and_time(self.time)))
+            .find(|&start| start <= now && now < start + duration)
+    }
+    /// Start of the latest occurrence at or before `now`, looking back at most a week.
+    pub fn last_start(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut day = self.zone.date(now);
+        for _ in 0..8 {
+            let wanted = self
+                .days
+                .as_ref()
+                .is_none_or(|days| days.contains(&day.weekday()));
+            if wanted {
+                let start = self.zone.to_utc(day.and_time(self.time));
+                if start <= now {
+                    return Some(start);
+                }
+            }
+            day = day.pred_opt()?;
+        }
+        None
+    }
+}
@@ -0,0 +1,141 @@
+//! IMA-ADPCM audio blocks, the default `adpcm` audio compression and the codec the C++ server
+//! used, kept for migrated configs and servers too small for Opus.
+//!
+//! A block is the first sample (`i16` LE), the initial step index, a reserved zero byte, the
+//! sample count (`u16` LE), then one 4-bit code per remaining sample, low nibble first.
+
+const INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+/// Encodes one mono block; empty input gives an empty block.
+pub fn encode_block_i16_mono(samples: &[i16]) -> Vec<u8> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let mut predictor = samples[0] as i32;
+    let mut index = if samples.len() >= 2 {
+        let diff = (samples[1] as i32 - samples[0] as i32).abs();
+        let mut best = 0usize;
+        for (i, &step) in STEP_TABLE.iter().enumerate() {
+            if step >= diff {
+                best = i;
+                break;
+            }
+            best = i;
+        }
+        best as i32
+    } else {
+        0i32
+    };
+
+    let codes = samples.len().saturating_sub(1);
+    let mut out = Vec::with_capacity(6 + codes.div_ceil(2));
+    out.extend_from_slice(&(samples[0]).to_le_bytes());
+    out.push(index as u8);
+    out.push(0);
+    out.extend_from_slice(&(samples.len() as u16).to_le_bytes());
+
+    let mut pending: Option<u8> = None;
+
+    for &sample in &samples[1..] {
+        let step = STEP_TABLE[index as usize];
+        let diff = (sample as i32) - predictor;
+        let sign = if diff < 0 { 8 } else { 0 };
+        let mut delta = diff.abs();
+
+        let mut code = 0i32;
+        let mut vpdiff = step >> 3;
+        if delta >= step {
+            code |= 4;
+            delta -= step;
+            vpdiff += step;
+        }
+        if delta >= (step >> 1) {
+            code |= 2;
+            delta -= step >> 1;
+            vpdiff += step >> 1;
+        }
+        if delta >= (step >> 2) {
+            code |= 1;
+            vpdiff += step >> 2;
+        }
+
+        if sign != 0 {
+            predictor -= vpdiff;
+        } else {
+            predictor += vpdiff;
+        }
+        predictor = predictor.clamp(i16::MIN as i32, i16::MAX as i32);
+
+        code |= sign;
+        index += INDEX_TABLE[code as usize];
+        index = index.clamp(0, (STEP_TABLE.len() - 1) as i32);
+
+        let nibble = (code as u8) & 0x0f;
+        match pending.take() {
+            Some(low) => out.push(low | (nibble << 4)),
+            None => pending = Some(nibble),
+        }
+    }
+
+    if let Some(low) = pending {
+        out.push(low);
+    }
+
+    out
+}
+
+/// Decodes a block produced by [`encode_block_i16_mono`].
+pub fn decode_block_i16_mono(block: &[u8]) -> anyhow::Result<Vec<i16>> {
+    if block.is_empty() {
+        return Ok(Vec::new());
+    }
+    anyhow::ensure!(block.len() >= 6, "ADPCM block header truncated");
+    let mut predictor = i16::from_le_bytes([block[0], block[1]]) as i32;
+    let mut index = block[2] as i32;
+    anyhow::ensure!(
+        (index as usize) < STEP_TABLE.len(),
+        "ADPCM step index {index} out of range"
+    );
+    let count = u16::from_le_bytes([block[4], block[5]]) as usize;
+    let codes = &block[6..];
+    anyhow::ensure!(
+        codes.len() >= count.saturating_sub(1).div_ceil(2),
+        "ADPCM block holds fewer codes than its {count} samples"
+    );
+
+    let mut out = Vec::with_capacity(count);
+    out.push(predictor as i16);
+    for i in 0..count.saturating_sub(1) {
+        let byte = codes[i / 2];
+        let code = if i % 2 == 0 { byte & 0x0f } else { byte >> 4 } as i32;
+        let step = STEP_TABLE[index as usize];
+        let mut vpdiff = step >> 3;
+        if code & 4 != 0 {
+            vpdiff += step;
+        }
+        if code & 2 != 0 {
+            vpdiff += step >> 1;
+        }
+        if code & 1 != 0 {
+            vpdiff += step >> 2;
+        }
+        if code & 8 != 0 {
+            predictor -= vpdiff;
+        } else {
+            predictor += vpdiff;
+        }
+        predictor = predictor.clamp(i16::MIN as i32, i16::MAX as i32);
+        index = (index + INDEX_TABLE[code as usize]).clamp(0, (STEP_TABLE.len() - 1) as i32);
+        out.push(predictor as i16);
+    }
+    Ok(out)
+}
@@ -1,2 +1,7 @@
 pub mod flac_stream;
+pub mod ima_adpcm;
+pub mod lz4_block;
+pub mod ogg_opus;
+pub mod png;
+pub mod wav;
 pub mod zstd_stream;
@@ -0,0 +1,90 @@
+//! Ogg Opus framing (RFC 7845) for audio served as a continuous HTTP stream.
+//!
+//! Each Opus packet goes out in a page of its own, so a player that joins late or drops a page
+//! only loses that packet. Granule positions count 48 kHz samples whatever the input rate.
+
+/// Samples the decoder drops at the start, at 48 kHz. The `LowDelay` encoder looks 2.5 ms ahead.
+const PRE_SKIP: u16 = 120;
+const HEADER_BOS: u8 = 0x02;
+
+pub struct OggOpusWriter {
+    serial: u32,
+    sequence: u32,
+    granule: u64,
+    input_rate: u32,
+    channels: u8,
+}
+
+impl OggOpusWriter {
+    /// `input_rate` is the rate the Opus encoder was fed at, recorded for players.
+    pub fn new(serial: u32, input_rate: u32, channels: u8) -> Self {
+        Self {
+            serial,
+            sequence: 0,
+            granule: 0,
+            input_rate,
+            channels,
+        }
+    }
+
+    /// The `OpusHead` and `OpusTags` pages that start the stream.
+    pub fn headers(&mut self, vendor: &str) -> Vec<u8> {
+        let mut head = Vec::with_capacity(19);
+        head.extend_from_slice(b"OpusHead");
+        head.push(1);
+        head.push(self.channels);
+        head.extend_from_slice(&PRE_SKIP.to_le_bytes());
+        head.extend_from_slice(&self.input_rate.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes());
+        head.push(0);
+
+        let mut tags = Vec::with_capacity(16 + vendor.len());
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor.as_bytes());
+        tags.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut out = self.page(HEADER_BOS, 0, &head);
+        out.extend(self.page(0, 0, &tags));
+        out
+    }
+
+    /// One page holding `packet`, which encodes `samples` samples per channel at the input rate.
+    pub fn packet(&mut self, packet: &[u8], samples: usize) -> Vec<u8> {
+        self.granule += samples as u64 * 48_000 / u64::from(self.input_rate.max(1));
+        self.page(0, self.granule, packet)
+    }
+
+    fn page(&mut self, header_type: u8, granule: u64, data: &[u8]) -> Vec<u8> {
+        let mut lacing = vec![255u8; data.len() / 255];
+        lacing.push((data.len() % 255) as u8);
+        let mut page = Vec::with_capacity(27 + lacing.len() + data.len());
+        page.extend_from_slice(b"OggS");
+        page.push(0);
+        page.push(header_type);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&self.serial.to_le_bytes());
+        page.extend_from_slice(&self.sequence.to_le_bytes());
+        page.extend_from_slice(&0u32.to_le_bytes());
+        page.push(lacing.len() as u8);
+        page.extend_from_slice(&lacing);
+        page.extend_from_slice(data);
+        let crc = crc32(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        self.sequence = self.sequence.wrapping_add(1);
+        page
+    }
+}
+
+/// Ogg's CRC-32: polynomial 0x04C11DB7, not reflected, zero initial value.
+pub fn crc32(data: &[u8]) -> u32 {
+    data.iter().fold(0u32, |crc, &b| {
+        (0..8).fold(crc ^ (u32::from(b) << 24), |c, _| {
+            if c & 0x8000_0000 != 0 {
+                (c << 1) ^ 0x04C1_1DB7
+            } else {
+                c << 1
+            }
+        })
+    })
+}
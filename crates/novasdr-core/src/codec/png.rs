@@ -0,0 +1,49 @@
+use anyhow::Context;
+use flate2::{write::ZlibEncoder, Compression};
+use std::io::Write;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const BIT_DEPTH: u8 = 8;
+const COLOR_TYPE_RGB: u8 = 2;
+const FILTER_NONE: u8 = 0;
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32fast::hash(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encodes 8-bit RGB pixels (row-major, `width * height * 3` bytes) as a PNG file.
+pub fn encode_rgb(width: u32, height: u32, rgb: &[u8]) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(width > 0 && height > 0, "png dimensions must be non-zero");
+    let stride = width as usize * 3;
+    anyhow::ensure!(
+        rgb.len() == stride * height as usize,
+        "png pixel buffer has {} bytes, expected {}",
+        rgb.len(),
+        stride * height as usize
+    );
+
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in rgb.chunks_exact(stride) {
+        zlib.write_all(&[FILTER_NONE]).context("deflate png row")?;
+        zlib.write_all(row).context("deflate png row")?;
+    }
+    let idat = zlib.finish().context("finish png deflate stream")?;
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // Bit depth, colour type, compression, filter method, no interlace.
+    ihdr.extend_from_slice(&[BIT_DEPTH, COLOR_TYPE_RGB, 0, 0, 0]);
+
+    let mut out = Vec::with_capacity(SIGNATURE.len() + idat.len() + 64);
+    out.extend_from_slice(&SIGNATURE);
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    write_chunk(&mut out, b"IDAT", &idat);
+    write_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
@@ -0,0 +1,38 @@
+//! WAV headers for 16-bit PCM: of unknown length, as sent ahead of an endless HTTP stream, or
+//! of a known length, as rewritten into a recording once it grew.
+
+/// Length written into the RIFF and data chunk sizes; players read on until the stream ends.
+const UNKNOWN_LEN: u32 = u32::MAX;
+
+/// Bytes before the samples.
+pub const HEADER_LEN: usize = 44;
+
+pub fn streaming_header(sample_rate: u32, channels: u16) -> Vec<u8> {
+    build(sample_rate, channels, UNKNOWN_LEN, UNKNOWN_LEN)
+}
+
+/// Header for `data_len` bytes of samples; lengths past 4 GiB are clamped.
+pub fn header(sample_rate: u32, channels: u16, data_len: u64) -> Vec<u8> {
+    let data_len = u32::try_from(data_len)
+        .unwrap_or(u32::MAX)
+        .min(u32::MAX - 36);
+    build(sample_rate, channels, data_len + 36, data_len)
+}
+
+fn build(sample_rate: u32, channels: u16, riff_len: u32, data_len: u32) -> Vec<u8> {
+    let block_align = channels * 2;
+    let mut h = Vec::with_capacity(HEADER_LEN);
+    h.extend_from_slice(b"RIFF");
+    h.extend_from_slice(&riff_len.to_le_bytes());
+    h.extend_from_slice(b"WAVEfmt ");
+    h.extend_from_slice(&16u32.to_le_bytes());
+    h.extend_from_slice(&1u16.to_le_bytes());
+    h.extend_from_slice(&channels.to_le_bytes());
+    h.extend_from_slice(&sample_rate.to_le_bytes());
+    h.extend_from_slice(&(sample_rate * u32::from(block_align)).to_le_bytes());
+    h.extend_from_slice(&block_align.to_le_bytes());
+    h.extend_from_slice(&16u16.to_le_bytes());
+    h.extend_from_slice(b"data");
+    h.extend_from_slice(&data_len.to_le_bytes());
+    h
+}
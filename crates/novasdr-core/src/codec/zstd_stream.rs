@@ -15,6 +15,17 @@ impl ZstdStreamEncoder {
         Ok(Self { cctx, level })
     }
 
+    /// Like [`Self::new`], with every frame compressed against `dictionary` (see
+    /// [`train_dictionary`]); the decoder must load the same one.
+    pub fn with_dictionary(level: i32, dictionary: &[u8]) -> anyhow::Result<Self> {
+        let mut encoder = Self::new(level)?;
+        map_zstd(
+            encoder.cctx.load_dictionary(dictionary),
+            "load zstd dictionary",
+        )?;
+        Ok(encoder)
+    }
+
     pub fn compress_flush(&mut self, input: &[u8]) -> anyhow::Result<Vec<u8>> {
         let max = zstd_safe::compress_bound(input.len());
         let mut out = vec![0u8; max.max(64)];
@@ -36,11 +47,42 @@ impl ZstdStreamEncoder {
         Ok(out)
     }
 
+    /// Compresses `input` as one complete frame that decodes without this encoder's earlier
+    /// output: the same input always gives the same bytes, so one result can go to every
+    /// connection that sends it. A streaming decoder reads such frames back to back.
+    pub fn compress_frame(&mut self, input: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut out = vec![0u8; zstd_safe::compress_bound(input.len())];
+        let len = map_zstd(
+            self.cctx.compress2(&mut out[..], input),
+            "zstd compress2 frame",
+        )?;
+        out.truncate(len);
+        Ok(out)
+    }
+
     pub fn level(&self) -> i32 {
         self.level
     }
 }
 
+/// Trains a zstd dictionary of at most `max_size` bytes from sample messages.
+pub fn train_dictionary(samples: &[Vec<u8>], max_size: usize) -> anyhow::Result<Vec<u8>> {
+    let sizes: Vec<usize> = samples.iter().map(Vec::len).collect();
+    let joined = samples.concat();
+    let mut dictionary = vec![0u8; max_size];
+    let len = map_zstd(
+        zstd_safe::train_from_buffer(&mut dictionary[..], &joined, &sizes),
+        "train zstd dictionary",
+    )?;
+    dictionary.truncate(len);
+    Ok(dictionary)
+}
+
+/// ID the decoder uses to match frames to a dictionary; `0` for a raw-content dictionary.
+pub fn dictionary_id(dictionary: &[u8]) -> u32 {
+    zstd_safe::get_dict_id(dictionary).map_or(0, |id| id.get())
+}
+
 fn map_zstd(res: zstd_safe::SafeResult, ctx: &'static str) -> anyhow::Result<usize> {
     res.map_err(|code| anyhow::anyhow!("{ctx} (zstd error code {code:?})"))
 }
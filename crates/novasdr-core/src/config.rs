@@ -1,21 +1,40 @@
+use crate::clock::{DailyTime, Zone};
+use crate::dsp::demod::DemodulationMode;
 use anyhow::Context;
 use serde::Deserialize;
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     path::Path,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
     pub server: Server,
     pub websdr: WebSdr,
     pub limits: Limits,
     pub updates: Updates,
+    pub admin: Admin,
+    pub pskreporter: PskReporter,
+    pub wsprnet: Wsprnet,
+    pub rbn: Rbn,
+    pub capture: Capture,
+    pub geoip: GeoIp,
+    pub export: Export,
+    pub freqdb: FreqDb,
+    pub accounts: Accounts,
+    pub rtl_tcp: RtlTcp,
+    pub crash_reports: CrashReports,
+    pub streams: Streams,
+    pub iq: IqOutput,
+    pub diversity: Diversity,
+    pub recordings: Recordings,
+    pub time_shift: TimeShift,
+    pub disk_guard: DiskGuard,
     pub receivers: Vec<ReceiverConfig>,
     pub active_receiver_id: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Updates {
     #[serde(default = "default_updates_check_on_startup")]
     pub check_on_startup: bool,
@@ -23,7 +42,315 @@ pub struct Updates {
     pub github_repo: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Admin {
+    /// Static bearer token for the admin API (scripts). Empty disables token access.
+    #[serde(default)]
+    pub token: String,
+    /// Operator password exchanged for session tokens at `/api/login`. Empty disables login.
+    #[serde(default)]
+    pub password: String,
+    #[serde(default = "default_admin_session_ttl_secs")]
+    pub session_ttl_secs: u64,
+}
+
+impl Admin {
+    /// The admin API is served only when some way to authenticate is configured.
+    pub fn enabled(&self) -> bool {
+        !self.token.is_empty() || !self.password.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PskReporter {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Receiver callsign reported with every spot.
+    #[serde(default)]
+    pub callsign: String,
+    /// Receiver locator; empty falls back to `websdr.grid_locator`.
+    #[serde(default)]
+    pub locator: String,
+    /// Antenna description; empty falls back to `websdr.antenna`.
+    #[serde(default)]
+    pub antenna: String,
+    #[serde(default = "default_pskreporter_host")]
+    pub host: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Wsprnet {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Reporter callsign sent with every spot.
+    #[serde(default)]
+    pub callsign: String,
+    /// Reporter locator; empty falls back to `websdr.grid_locator`.
+    #[serde(default)]
+    pub locator: String,
+    #[serde(default = "default_wsprnet_url")]
+    pub url: String,
+}
+
+/// Telnet spot feed in the format of CW Skimmer, for the Reverse Beacon Network aggregator.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rbn {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Skimmer callsign, shown as `CALL-#` in every spot line.
+    #[serde(default)]
+    pub callsign: String,
+    #[serde(default = "default_rbn_host")]
+    pub host: String,
+    #[serde(default = "default_rbn_port")]
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Capture {
+    /// Directory event screenshots and their metadata are written to.
+    #[serde(default = "default_capture_dir")]
+    pub dir: String,
+    /// Optional URL that receives a JSON POST for every saved screenshot.
+    #[serde(default)]
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct GeoIp {
+    /// MaxMind GeoLite2/GeoIP2 Country or City database (`.mmdb`). Empty disables country
+    /// lookups. Relative paths are resolved against the directory of `receivers.json`.
+    #[serde(default)]
+    pub database: String,
+    /// ISO 3166-1 alpha-2 codes. When non-empty, only clients from these countries may connect.
+    #[serde(default)]
+    pub allow_countries: Vec<String>,
+    /// ISO 3166-1 alpha-2 codes refused at WebSocket upgrade.
+    #[serde(default)]
+    pub deny_countries: Vec<String>,
+    /// Refuse addresses the database has no country for (LAN, some VPN and satellite ranges).
+    #[serde(default)]
+    pub deny_unknown: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    None,
+    /// InfluxDB line protocol, POSTed to an HTTP write endpoint.
+    Influxdb,
+    /// Graphite plaintext protocol over TCP.
+    Graphite,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Export {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// InfluxDB write URL including its query (`http://host:8086/api/v2/write?org=o&bucket=b`,
+    /// or `http://host:8086/write?db=novasdr` for 1.x), or Graphite `host:port`.
+    #[serde(default)]
+    pub target: String,
+    /// InfluxDB API token sent as `Authorization: Token <token>`. Empty sends none.
+    #[serde(default)]
+    pub token: String,
+    #[serde(default = "default_export_interval_secs")]
+    pub interval_secs: u64,
+    /// Measurement name prefix (InfluxDB) or metric path prefix (Graphite).
+    #[serde(default = "default_export_prefix")]
+    pub prefix: String,
+}
+
+/// "What is this?" lookups of the tuned frequency, from a CSV file and/or an HTTP database.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FreqDb {
+    /// CSV of `start_hz,end_hz,name,notes` (see `crate::freqdb`). Empty disables it. Relative
+    /// paths are resolved against the directory of `receivers.json`.
+    #[serde(default)]
+    pub csv: String,
+    /// URL queried per lookup, with `{hz}`, `{khz}` or `{mhz}` replaced by the frequency. The
+    /// response must be CSV in the same layout. Empty disables it.
+    #[serde(default)]
+    pub url_template: String,
+    /// How far from the tuned frequency an entry may be and still match.
+    #[serde(default = "default_freqdb_tolerance_hz")]
+    pub tolerance_hz: i64,
+    #[serde(default = "default_freqdb_max_results")]
+    pub max_results: usize,
+    /// How long an answer from `url_template` is reused for nearby lookups.
+    #[serde(default = "default_freqdb_cache_secs")]
+    pub cache_secs: u64,
+    /// Queries sent to `url_template` per minute, across all listeners.
+    #[serde(default = "default_freqdb_upstream_per_minute")]
+    pub upstream_per_minute: u32,
+    /// Lookups a single client IP may make per minute.
+    #[serde(default = "default_freqdb_lookups_per_minute")]
+    pub lookups_per_minute: u32,
+}
+
+impl FreqDb {
+    pub fn enabled(&self) -> bool {
+        !self.csv.trim().is_empty() || !self.url_template.trim().is_empty()
+    }
+}
+
+/// Listener accounts at `/api/user`: preferences, bookmarks and chat identity kept server-side
+/// so they follow a listener across devices.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Accounts {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Accounts kept at most; sign-ups are refused beyond this.
+    #[serde(default = "default_accounts_max_accounts")]
+    pub max_accounts: usize,
+    /// Accounts a single client IP may create per hour.
+    #[serde(default = "default_accounts_signups_per_hour")]
+    pub signups_per_hour: u32,
+}
+
+/// rtl_tcp-compatible TCP listener, so desktop SDR programs can take IQ from a slice of a
+/// receiver's band next to the web listeners.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RtlTcp {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_rtl_tcp_host")]
+    pub host: String,
+    #[serde(default = "default_rtl_tcp_port")]
+    pub port: u16,
+    /// Receiver served; empty for the active receiver.
+    #[serde(default)]
+    pub receiver_id: String,
+    /// Clients connected at once; further connections are closed right away.
+    #[serde(default = "default_rtl_tcp_max_clients")]
+    pub max_clients: usize,
+    /// Highest IQ sample rate a client can ask for, in samples per second.
+    #[serde(default = "default_rtl_tcp_max_sample_rate")]
+    pub max_sample_rate: u32,
+}
+
+/// Plain-HTTP audio streams (`/stream/<receiver>/<frequency>/<mode>.<format>`) for media players
+/// and stream relays.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Streams {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Streams served at once; each one is an audio listener on its receiver.
+    #[serde(default = "default_streams_max_clients")]
+    pub max_clients: usize,
+}
+
+/// Raw IQ of a client-chosen passband over the `/iq` WebSocket, for external decoders.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IqOutput {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Clients connected at once; further connections are refused.
+    #[serde(default = "default_iq_max_clients")]
+    pub max_clients: usize,
+    /// Highest IQ sample rate a client can ask for, in samples per second.
+    #[serde(default = "default_iq_max_sample_rate")]
+    pub max_sample_rate: u32,
+}
+
+/// Receivers on the same band with different antennas whose audio a `/diversity` client can
+/// hear together, aligned in time.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Diversity {
+    #[serde(default)]
+    pub pairs: Vec<DiversityPair>,
+    /// Largest time offset between the two receivers' audio that alignment corrects, in ms.
+    #[serde(default = "default_diversity_max_offset_ms")]
+    pub max_offset_ms: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DiversityPair {
+    /// Receiver ids; `a` plays on the left in stereo.
+    pub a: String,
+    pub b: String,
+}
+
+impl Diversity {
+    /// Whether `a` and `b` were configured as a pair, in either order.
+    pub fn allows(&self, a: &str, b: &str) -> bool {
+        self.pairs
+            .iter()
+            .any(|p| (p.a == a && p.b == b) || (p.a == b && p.b == a))
+    }
+}
+
+/// Downloads of the files under `capture.dir` and how long they are kept.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct Recordings {
+    /// Serves `/api/recordings` and `/recordings/<id>`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Files older than this are deleted; `0` keeps them regardless of age.
+    #[serde(default)]
+    pub max_age_days: u64,
+    /// Oldest files are deleted while the total is above this; `0` disables the limit.
+    #[serde(default)]
+    pub max_total_mb: u64,
+}
+
+/// Free-space watch over `capture.dir` and the log directory: above `high_water_percent` used,
+/// no new recordings start and files are deleted per `cleanup` until usage is back under
+/// `low_water_percent`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DiskGuard {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_disk_high_water_percent")]
+    pub high_water_percent: f64,
+    #[serde(default = "default_disk_low_water_percent")]
+    pub low_water_percent: f64,
+    #[serde(default)]
+    pub cleanup: DiskCleanup,
+    #[serde(default = "default_disk_check_secs")]
+    pub check_secs: u64,
+    /// Optional URL that receives a JSON POST whenever a filesystem crosses a water mark.
+    #[serde(default)]
+    pub webhook_url: String,
+}
+
+/// What the disk guard deletes, oldest first, when a filesystem is above its high water mark.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskCleanup {
+    /// Nothing: recordings stay paused until space is freed by hand.
+    #[default]
+    None,
+    /// Files under `capture.dir`.
+    Recordings,
+    /// Files under `capture.dir`, then rotated log files.
+    RecordingsAndLogs,
+}
+
+/// Rewind buffer of each `/audio` client, holding the packets it was sent.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TimeShift {
+    /// How far back a listener can seek; `0` (the default) disables the buffer.
+    #[serde(default = "default_time_shift_minutes")]
+    pub minutes: u32,
+}
+
+/// What the server keeps when it panics: a JSON report with the backtrace, build and a config
+/// summary without secrets.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CrashReports {
+    #[serde(default = "default_crash_reports_enabled")]
+    pub enabled: bool,
+    /// Where reports are written; empty for `crashes/` in the log directory.
+    #[serde(default)]
+    pub dir: String,
+    /// Reports still on disk are POSTed here at the next start. Empty uploads nothing.
+    #[serde(default)]
+    pub upload_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Server {
     #[serde(default = "default_port")]
     pub port: u16,
@@ -37,7 +364,7 @@ pub struct Server {
     pub threads: usize,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct WebSdr {
     #[serde(default)]
     pub register_online: bool,
@@ -59,9 +386,14 @@ pub struct WebSdr {
     pub email: String,
     #[serde(default = "default_chat_enabled")]
     pub chat_enabled: bool,
+    /// Ed25519 identity key (PKCS#8) used to sign registration payloads. Empty disables signing;
+    /// a missing file is generated on startup. Relative paths are resolved against the directory
+    /// of `receivers.json`.
+    #[serde(default)]
+    pub identity_key: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Limits {
     #[serde(default = "default_limit")]
     pub audio: usize,
@@ -71,23 +403,546 @@ pub struct Limits {
     pub events: usize,
     #[serde(default = "default_ws_per_ip")]
     pub ws_per_ip: usize,
+    /// Concurrent audio streams per client IP; `0` disables the limit.
+    #[serde(default)]
+    pub audio_per_ip: usize,
+    /// Audio listening minutes per client IP and UTC day; `0` disables the quota.
+    #[serde(default)]
+    pub listen_minutes_per_day: u64,
+    /// Whether an IP that used up `listen_minutes_per_day` may still open the waterfall.
+    #[serde(default = "default_true")]
+    pub quota_waterfall_only: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct ReceiverConfig {
     pub id: String,
     #[serde(default = "default_true")]
     pub enabled: bool,
     #[serde(default)]
     pub name: String,
+    #[serde(default)]
+    pub maintenance: ReceiverMaintenance,
+    #[serde(default)]
+    pub skimmer: SkimmerConfig,
+    #[serde(default)]
+    pub capture_triggers: Vec<CaptureTrigger>,
+    #[serde(default)]
+    pub limits: ReceiverLimits,
+    #[serde(default)]
+    pub fft_tap: FftTap,
+    #[serde(default)]
+    pub acars: Acars,
+    #[serde(default)]
+    pub replay: Replay,
+    #[serde(default)]
+    pub channel_dump: ChannelDump,
+    #[serde(default)]
+    pub black_box: BlackBox,
+    /// Centre frequencies the input is retuned to at fixed times of day, such as 40m at night
+    /// and 20m during the day.
+    #[serde(default)]
+    pub band_schedule: Vec<BandSwitch>,
+    #[serde(default)]
+    pub accuracy: Accuracy,
+    #[serde(default)]
+    pub tx: Transmit,
+    #[serde(default)]
+    pub udp_output: UdpOutput,
     pub input: ReceiverInput,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// What listeners are told about the receiver's frequency accuracy, so they can tell a small
+/// offset against their own rig from a mistuned station.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Accuracy {
+    #[serde(default)]
+    pub reference: ClockReference,
+    /// Stated worst-case frequency error of the reference, in parts per million.
+    #[serde(default)]
+    pub ppm: Option<f64>,
+    /// Carrier of known frequency in Hz, such as a time-signal station, whose position in the
+    /// spectrum estimates the actual error; `None` disables the estimate.
+    #[serde(default)]
+    pub drift_carrier: Option<i64>,
+    /// How far from `drift_carrier` the carrier is searched for, in Hz.
+    #[serde(default = "default_drift_search_hz")]
+    pub drift_search_hz: i64,
+}
+
+impl Default for Accuracy {
+    fn default() -> Self {
+        Self {
+            reference: ClockReference::default(),
+            ppm: None,
+            drift_carrier: None,
+            drift_search_hz: default_drift_search_hz(),
+        }
+    }
+}
+
+fn default_drift_search_hz() -> i64 {
+    200
+}
+
+/// Frequency reference the receiver's clock is derived from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockReference {
+    #[default]
+    Unknown,
+    /// Plain crystal oscillator.
+    Xtal,
+    Tcxo,
+    Ocxo,
+    /// GPS-disciplined oscillator.
+    Gpsdo,
+}
+
+impl ClockReference {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Xtal => "xtal",
+            Self::Tcxo => "tcxo",
+            Self::Ocxo => "ocxo",
+            Self::Gpsdo => "gpsdo",
+        }
+    }
+}
+
+/// From `at` (`HH:MM` in `timezone`) on, the input is tuned to `frequency`, until the next
+/// entry.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BandSwitch {
+    pub at: String,
+    /// IANA zone `at` is read in, such as `Europe/Berlin`; UTC when empty.
+    #[serde(default)]
+    pub timezone: String,
+    /// Centre frequency in Hz, in place of `input.frequency`.
+    pub frequency: i64,
+}
+
+impl BandSwitch {
+    /// `at` as a daily time in `timezone`.
+    pub fn daily_time(&self) -> Result<DailyTime, String> {
+        let zone = Zone::parse(&self.timezone)?;
+        let time = chrono::NaiveTime::parse_from_str(self.at.trim(), "%H:%M")
+            .map_err(|_| format!("at {:?} is not HH:MM", self.at))?;
+        Ok(DailyTime {
+            time,
+            zone,
+            days: None,
+        })
+    }
+}
+
+impl ReceiverConfig {
+    /// Centre frequency `band_schedule` asks for at `now`: that of the entry that last came
+    /// round, wrapping around midnight. `None` without a schedule.
+    pub fn scheduled_frequency(&self, now: chrono::DateTime<chrono::Utc>) -> Option<i64> {
+        self.band_schedule
+            .iter()
+            .filter_map(|e| Some((e.daily_time().ok()?.last_start(now)?, e.frequency)))
+            .max_by_key(|(started, _)| *started)
+            .map(|(_, frequency)| frequency)
+    }
+}
+
+/// Fixed channels demodulated around the clock into one multichannel WAV file, independent of
+/// connected listeners.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChannelDump {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub channels: Vec<DumpChannel>,
+    /// Length of each file; a new one is started when it is reached.
+    #[serde(default = "default_channel_dump_segment_minutes")]
+    pub segment_minutes: u32,
+}
+
+impl Default for ChannelDump {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            channels: Vec::new(),
+            segment_minutes: default_channel_dump_segment_minutes(),
+        }
+    }
+}
+
+/// Wideband IQ written to disk around the clock in fixed-length segments, the oldest deleted
+/// once they fall out of the look-back window, so an event can be cut out after the fact.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BlackBox {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Centre of the recorded band in Hz; the centre of the receiver when unset.
+    #[serde(default)]
+    pub frequency: Option<i64>,
+    /// IQ samples per second, rounded to the receiver's bins and capped at its bandwidth.
+    #[serde(default = "default_black_box_sample_rate")]
+    pub sample_rate: u32,
+    /// Length of each segment file.
+    #[serde(default = "default_black_box_segment_seconds")]
+    pub segment_seconds: u32,
+    /// How far back the segments on disk reach.
+    #[serde(default = "default_black_box_minutes")]
+    pub minutes: u32,
+}
+
+impl Default for BlackBox {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            frequency: None,
+            sample_rate: default_black_box_sample_rate(),
+            segment_seconds: default_black_box_segment_seconds(),
+            minutes: default_black_box_minutes(),
+        }
+    }
+}
+
+/// Transmitting through the receiver's SoapySDR device over `/tx` (operator only; needs the
+/// `tx` Cargo feature). Only inside `bands`, at most at their gain limit and for at most
+/// `max_seconds` per transmission.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Transmit {
+    #[serde(default)]
+    pub enabled: bool,
+    /// TX channel of the device.
+    #[serde(default)]
+    pub channel: usize,
+    /// TX antenna port; the device's default when unset.
+    #[serde(default)]
+    pub antenna: Option<String>,
+    /// Baseband samples per second written to the device; a multiple of 12000.
+    #[serde(default = "default_tx_sample_rate")]
+    pub sample_rate: u32,
+    /// Highest TX gain in dB, for bands without their own limit.
+    #[serde(default)]
+    pub max_gain_db: f64,
+    /// Longest single transmission (time-out timer).
+    #[serde(default = "default_tx_max_seconds")]
+    pub max_seconds: u32,
+    /// Device setting switched to `"true"` while transmitting and `"false"` after, for drivers
+    /// that expose a PTT or amplifier control line.
+    #[serde(default)]
+    pub ptt_setting: Option<String>,
+    /// Frequency ranges transmissions must stay inside.
+    #[serde(default)]
+    pub bands: Vec<TxBand>,
+}
+
+impl Default for Transmit {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            channel: 0,
+            antenna: None,
+            sample_rate: default_tx_sample_rate(),
+            max_gain_db: 0.0,
+            max_seconds: default_tx_max_seconds(),
+            ptt_setting: None,
+            bands: Vec::new(),
+        }
+    }
+}
+
+impl Transmit {
+    /// The band that holds all of `low..=high` (Hz), if any.
+    pub fn band_for(&self, low: i64, high: i64) -> Option<&TxBand> {
+        self.bands.iter().find(|b| b.start <= low && high <= b.end)
+    }
+
+    /// Highest TX gain in `band`, in dB.
+    pub fn gain_limit(&self, band: &TxBand) -> f64 {
+        band.max_gain_db.unwrap_or(self.max_gain_db)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TxBand {
+    #[serde(default)]
+    pub name: String,
+    /// Lower and upper edge in Hz.
+    pub start: i64,
+    pub end: i64,
+    /// Highest TX gain in dB in this band, below `tx.max_gain_db` for example where the licence
+    /// allows less power.
+    #[serde(default)]
+    pub max_gain_db: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DumpChannel {
+    /// Carrier frequency in Hz.
+    pub frequency: i64,
+    /// Demodulation (`USB`, `AM`, `FM`, ...); the receiver's default mode when unset.
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Label in the recording's sidecar.
+    #[serde(default)]
+    pub name: String,
+}
+
+impl DumpChannel {
+    /// `mode`, in any case; `None` when unset or not a demodulation mode.
+    pub fn demodulation(&self) -> Option<DemodulationMode> {
+        DemodulationMode::from_str_upper(&self.mode.as_deref()?.trim().to_ascii_uppercase())
+    }
+}
+
+/// Demodulated audio of one fixed channel sent as raw 16-bit PCM over UDP, for a decoder such
+/// as direwolf or multimon-ng on the same machine or network.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct UdpOutput {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `address:port` the datagrams are sent to.
+    #[serde(default = "default_udp_output_target")]
+    pub target: String,
+    /// Carrier frequency in Hz; the receiver's default frequency when unset.
+    #[serde(default)]
+    pub frequency: Option<i64>,
+    /// Demodulation (`USB`, `FM`, ...); the receiver's default mode when unset.
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Audio per datagram.
+    #[serde(default = "default_udp_output_packet_ms")]
+    pub packet_ms: u32,
+}
+
+impl Default for UdpOutput {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: default_udp_output_target(),
+            frequency: None,
+            mode: None,
+            packet_ms: default_udp_output_packet_ms(),
+        }
+    }
+}
+
+impl UdpOutput {
+    /// `mode`, in any case; `None` when unset or not a demodulation mode.
+    pub fn demodulation(&self) -> Option<DemodulationMode> {
+        DemodulationMode::from_str_upper(&self.mode.as_deref()?.trim().to_ascii_uppercase())
+    }
+
+    /// Why the settings cannot be used, if they cannot.
+    pub fn check(&self) -> Result<(), String> {
+        if self.target.trim().parse::<std::net::SocketAddr>().is_err() {
+            return Err(format!(
+                "target {:?} is not an address:port such as 127.0.0.1:7355",
+                self.target
+            ));
+        }
+        if self.mode.is_some() && self.demodulation().is_none() {
+            return Err(format!(
+                "mode {:?} is not a demodulation mode",
+                self.mode.as_deref().unwrap_or_default()
+            ));
+        }
+        if !(1..=MAX_UDP_OUTPUT_PACKET_MS).contains(&self.packet_ms) {
+            return Err(format!(
+                "packet_ms must be between 1 and {MAX_UDP_OUTPUT_PACKET_MS}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Keeps a datagram of 48 kHz audio under the 64 KiB UDP limit.
+const MAX_UDP_OUTPUT_PACKET_MS: u32 = 500;
+
+/// What it takes to move a running receiver to a reloaded `receivers.json` entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiverReload {
+    Unchanged,
+    /// Only `name`, `limits`, `maintenance`, `input.defaults`, `band_schedule`, the stated
+    /// `accuracy` or `udp_output` changed; they apply in place.
+    Settings,
+    /// `input.driver` changed too: the input is reopened, and the spectrum layout stays.
+    Input,
+    /// Anything else changed; it takes a server restart.
+    Restart,
+}
+
+impl ReceiverConfig {
+    pub fn reload_to(&self, new: &ReceiverConfig) -> ReceiverReload {
+        if self == new {
+            return ReceiverReload::Unchanged;
+        }
+        let without_settings = |r: &ReceiverConfig| {
+            let mut r = r.clone();
+            r.name.clear();
+            r.limits = ReceiverLimits::default();
+            r.maintenance = ReceiverMaintenance::default();
+            r.input.defaults = ReceiverDefaults::default();
+            r.band_schedule.clear();
+            r.accuracy.reference = ClockReference::default();
+            r.accuracy.ppm = None;
+            r.udp_output = UdpOutput::default();
+            r
+        };
+        let mut old = without_settings(self);
+        let new = without_settings(new);
+        if old == new {
+            return ReceiverReload::Settings;
+        }
+        old.input.driver = new.input.driver.clone();
+        if old == new {
+            ReceiverReload::Input
+        } else {
+            ReceiverReload::Restart
+        }
+    }
+}
+
+/// ACARS decoding of airband channels, independent of connected listeners.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct Acars {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Channel centre frequencies in Hz, such as 131550000.
+    #[serde(default)]
+    pub frequencies: Vec<i64>,
+}
+
+/// Rolling buffer of this receiver's most recent FFT frames, so a recording started now can
+/// begin a few seconds in the past.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Replay {
+    /// Seconds kept; `0` disables the buffer.
+    #[serde(default)]
+    pub seconds: u32,
+    /// Upper bound on the buffer's memory; at high sample rates it holds fewer seconds.
+    #[serde(default = "default_replay_max_mb")]
+    pub max_mb: u32,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self {
+            seconds: 0,
+            max_mb: default_replay_max_mb(),
+        }
+    }
+}
+
+/// TCP publisher of this receiver's raw FFT frames, so GNU Radio flowgraphs and research code can
+/// use the spectrum without computing it again.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FftTap {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_fft_tap_host")]
+    pub host: String,
+    #[serde(default = "default_fft_tap_port")]
+    pub port: u16,
+    /// Secret a client sends before it is sent any frame.
+    #[serde(default)]
+    pub token: String,
+    /// Only every Nth FFT frame is sent.
+    #[serde(default = "default_fft_tap_decimation")]
+    pub decimation: u32,
+    /// Clients connected at once; further connections are closed right away.
+    #[serde(default = "default_fft_tap_max_clients")]
+    pub max_clients: usize,
+}
+
+impl Default for FftTap {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: default_fft_tap_host(),
+            port: default_fft_tap_port(),
+            token: String::new(),
+            decimation: default_fft_tap_decimation(),
+            max_clients: default_fft_tap_max_clients(),
+        }
+    }
+}
+
+/// Caps on this receiver's own clients, on top of the global `limits`; unset means only the
+/// global limit applies.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct ReceiverLimits {
+    /// Audio listeners, headless ones (streams, recordings) included.
+    #[serde(default)]
+    pub audio: Option<usize>,
+    /// Waterfall connections; extra windows of a connection are not counted.
+    #[serde(default)]
+    pub waterfall: Option<usize>,
+}
+
+/// Saves a waterfall screenshot when a new signal rises `threshold_db` above the noise floor
+/// inside `start_hz..end_hz`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CaptureTrigger {
+    pub name: String,
+    pub start_hz: i64,
+    pub end_hz: i64,
+    #[serde(default = "default_capture_threshold_db")]
+    pub threshold_db: f32,
+    /// Minimum time between two captures of this trigger.
+    #[serde(default = "default_capture_holdoff_secs")]
+    pub holdoff_secs: u64,
+}
+
+/// Built-in FT8/FT4/WSPR and CW skimmer fed from the receiver's wideband FFT.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SkimmerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Band names (`"40m"`, `"20m"`, ...). Empty selects every band inside the receiver range.
+    #[serde(default)]
+    pub bands: Vec<String>,
+    #[serde(default = "default_skimmer_modes")]
+    pub modes: Vec<SkimmerMode>,
+}
+
+impl Default for SkimmerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bands: Vec::new(),
+            modes: default_skimmer_modes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SkimmerMode {
+    Ft8,
+    Ft4,
+    Wspr,
+    Cw,
+}
+
+/// Planned-work state for a receiver. The DSP pipeline keeps running; clients and directory
+/// listings are told the receiver is degraded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, serde::Serialize)]
+pub struct ReceiverMaintenance {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct ReceiverInput {
     pub sps: i64,
     pub frequency: i64,
     pub signal: SignalType,
+    /// Power-of-two factor the input is decimated by (halfband filters) before the main FFT;
+    /// everything downstream sees `sps / decimation`.
+    #[serde(default = "default_decimation")]
+    pub decimation: usize,
     #[serde(default = "default_fft_size")]
     pub fft_size: usize,
     #[serde(default)]
@@ -96,20 +951,45 @@ pub struct ReceiverInput {
     pub audio_sps: i64,
     #[serde(default = "default_waterfall_size")]
     pub waterfall_size: usize,
+    /// Upper bound for waterfall frames per second; intermediate FFT frames are not quantized or
+    /// sent.
+    #[serde(default = "default_waterfall_max_fps")]
+    pub waterfall_max_fps: f64,
     #[serde(default = "default_waterfall_compression")]
     pub waterfall_compression: WaterfallCompression,
     #[serde(default = "default_audio_compression")]
     pub audio_compression: AudioCompression,
+    /// Listeners tuned to the same bins with the same demodulator settings share one
+    /// demodulated channel instead of each running their own.
+    #[serde(default = "default_shared_channels")]
+    pub shared_channels: bool,
     #[serde(default)]
     pub smeter_offset: i32,
+    /// S-meter calibration table (JSON, see `crate::smeter`); relative paths are resolved
+    /// against the directory of `receivers.json`.
+    #[serde(default)]
+    pub smeter_calibration: Option<String>,
     #[serde(default)]
     pub accelerator: Accelerator,
+    /// Accelerator from receivers.json that this platform cannot run; `load_from_files` moved
+    /// the receiver to the CPU instead.
+    #[serde(skip)]
+    pub accelerator_fallback: Option<Accelerator>,
+    /// Vulkan device for the vkfft backend; overrides `NOVASDR_VULKAN_DEVICE` for this receiver.
+    #[serde(default)]
+    pub vulkan_device: Option<VulkanDevice>,
+    /// Hides the LO leakage spike of zero-IF hardware at the centre frequency (IQ input only).
+    #[serde(default)]
+    pub dc_suppression: DcSuppression,
+    /// Window applied to each FFT frame before the transform.
+    #[serde(default)]
+    pub window: FftWindow,
     pub driver: InputDriver,
     #[serde(default)]
     pub defaults: ReceiverDefaults,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct ReceiverDefaults {
     #[serde(default = "default_default_frequency")]
     pub frequency: i64,
@@ -142,7 +1022,7 @@ impl Default for ReceiverDefaults {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(tag = "kind")]
 pub enum InputDriver {
     #[serde(rename = "stdin")]
@@ -151,6 +1031,16 @@ pub enum InputDriver {
     Fifo { format: SampleFormat, path: String },
     #[serde(rename = "soapysdr")]
     SoapySdr(SoapySdrDriver),
+    #[serde(rename = "ka9q")]
+    Ka9q(Ka9qDriver),
+    #[serde(rename = "rx888")]
+    Rx888(Rx888Driver),
+    /// Generated signals instead of hardware, for demos and frontend development.
+    #[serde(rename = "synthetic")]
+    Synthetic {
+        #[serde(default)]
+        scenario: SyntheticScenario,
+    },
 }
 
 impl InputDriver {
@@ -159,6 +1049,9 @@ impl InputDriver {
             InputDriver::Stdin { .. } => "stdin",
             InputDriver::Fifo { .. } => "fifo",
             InputDriver::SoapySdr(_) => "soapysdr",
+            InputDriver::Ka9q(_) => "ka9q",
+            InputDriver::Rx888(_) => "rx888",
+            InputDriver::Synthetic { .. } => "synthetic",
         }
     }
 
@@ -167,11 +1060,15 @@ impl InputDriver {
             InputDriver::Stdin { format } => *format,
             InputDriver::Fifo { format, path: _ } => *format,
             InputDriver::SoapySdr(d) => d.format,
+            InputDriver::Ka9q(d) => d.format,
+            InputDriver::Rx888(_) => SampleFormat::S16,
+            // Native f32, interleaved I/Q when `input.signal` is `iq`.
+            InputDriver::Synthetic { .. } => SampleFormat::F32,
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct SoapySdrDriver {
     pub device: String,
     #[serde(default)]
@@ -191,6 +1088,162 @@ pub struct SoapySdrDriver {
     pub stream_args: BTreeMap<String, String>,
     #[serde(default = "default_soapysdr_rx_buffer_samples")]
     pub rx_buffer_samples: usize,
+    /// Frequency correction of the device's clock in parts per million, such as the drift of an
+    /// RTL-SDR's crystal; positive when the device tunes high.
+    #[serde(default)]
+    pub ppm_correction: Option<f64>,
+    /// Device sensors the admin API reports next to the correction, such as `ref_locked` or
+    /// `gps_locked` of a GPSDO.
+    #[serde(default)]
+    pub reference_sensors: Vec<String>,
+    /// Device setting that powers the bias tee, for the admin API. By default the first of
+    /// [`BIAS_TEE_SETTINGS`] the device reports.
+    #[serde(default)]
+    pub bias_tee_setting: Option<String>,
+}
+
+/// Names SoapySDR drivers give their bias tee setting: RTL-SDR, Airspy and others use
+/// `biastee`, SDRplay `biasT_ctrl`.
+pub const BIAS_TEE_SETTINGS: [&str; 3] = ["biastee", "bias_tee", "biasT_ctrl"];
+
+/// Largest `ppm_correction`; a worse clock points at a wrong value rather than a crystal.
+pub const MAX_PPM_CORRECTION: f64 = 500.0;
+
+/// RTP data stream of a ka9q-radio `radiod` channel.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Ka9qDriver {
+    /// Data stream address as `host:port`: the channel's multicast group, or a name radiod
+    /// announces over mDNS such as `hf-iq.local:5004`.
+    pub group: String,
+    /// Local IPv4 address of the interface to join the group on; the OS picks one when unset.
+    #[serde(default)]
+    pub interface: Option<std::net::Ipv4Addr>,
+    /// Only accept this RTP SSRC; radiod tells channels sharing a group apart by SSRC.
+    #[serde(default)]
+    pub ssrc: Option<u32>,
+    /// Only accept this RTP payload type. When unset, the first payload type seen is locked in.
+    #[serde(default)]
+    pub payload_type: Option<u8>,
+    /// Sample layout of the payload: `cs16`/`cf32` for IQ channels, `s16`/`f32` for PCM.
+    pub format: SampleFormat,
+    /// Payload byte order. Defaults to radiod's encodings: big-endian (`s16be`) for 16-bit
+    /// samples and little-endian for `f32`.
+    #[serde(default)]
+    pub byte_order: Option<ByteOrder>,
+}
+
+/// RX888 / RX888 MK2 direct sampling: the ADC's 16-bit real samples, read from the standard
+/// output of a capture program that loads the firmware and runs the USB stream.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rx888Driver {
+    /// Capture program writing little-endian `s16` samples to stdout, e.g. `rx888_stream`.
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// The ADC's randomizer (`RAND`) is on; samples are de-randomized before use.
+    #[serde(default)]
+    pub randomizer: bool,
+}
+
+/// What a `synthetic` input generates: a noise floor, bands of extra noise and signals, the
+/// same on every start for one `seed`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SyntheticScenario {
+    /// RMS of the white noise across the whole input, in dBFS.
+    #[serde(default = "default_synthetic_noise_floor_db")]
+    pub noise_floor_db: f32,
+    /// Bands with more noise than the floor, such as a noisy low band or a switching supply.
+    #[serde(default)]
+    pub noise_bands: Vec<SyntheticNoiseBand>,
+    #[serde(default)]
+    pub signals: Vec<SyntheticSignal>,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl Default for SyntheticScenario {
+    fn default() -> Self {
+        Self {
+            noise_floor_db: default_synthetic_noise_floor_db(),
+            noise_bands: Vec::new(),
+            signals: Vec::new(),
+            seed: 0,
+        }
+    }
+}
+
+fn default_synthetic_noise_floor_db() -> f32 {
+    -80.0
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SyntheticNoiseBand {
+    /// Edges in Hz.
+    pub start: i64,
+    pub end: i64,
+    /// RMS of the band's noise, in dBFS.
+    pub level_db: f32,
+}
+
+/// One generated signal; `frequency` is the carrier in Hz and `level_db` its peak in dBFS.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SyntheticSignal {
+    /// Unmodulated carrier.
+    Carrier { frequency: i64, level_db: f32 },
+    /// SSB voice: a built-in synthesized voice, or `audio` (mono s16le at 12 kHz) looped.
+    Ssb {
+        frequency: i64,
+        level_db: f32,
+        #[serde(default)]
+        sideband: Sideband,
+        #[serde(default)]
+        audio: Option<String>,
+    },
+    /// Narrow FM (2.5 kHz deviation) modulated by a tone.
+    Fm {
+        frequency: i64,
+        level_db: f32,
+        #[serde(default = "default_synthetic_tone_hz")]
+        tone_hz: f32,
+    },
+}
+
+impl SyntheticSignal {
+    pub fn frequency(&self) -> i64 {
+        match self {
+            SyntheticSignal::Carrier { frequency, .. }
+            | SyntheticSignal::Ssb { frequency, .. }
+            | SyntheticSignal::Fm { frequency, .. } => *frequency,
+        }
+    }
+
+    pub fn level_db(&self) -> f32 {
+        match self {
+            SyntheticSignal::Carrier { level_db, .. }
+            | SyntheticSignal::Ssb { level_db, .. }
+            | SyntheticSignal::Fm { level_db, .. } => *level_db,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sideband {
+    #[default]
+    Usb,
+    Lsb,
+}
+
+fn default_synthetic_tone_hz() -> f32 {
+    1_000.0
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ByteOrder {
+    Big,
+    Little,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
@@ -201,12 +1254,33 @@ pub enum SignalType {
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "kebab-case")]
 pub enum WaterfallCompression {
+    /// One zstd stream per connection.
     Zstd,
+    /// Independent LZ4 block per packet; lower CPU, larger frames.
+    Lz4,
+    /// zstd stream against a dictionary trained from the receiver's own waterfall.
+    ZstdDict,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+impl WaterfallCompression {
+    pub const ALL: [Self; 3] = [Self::Zstd, Self::Lz4, Self::ZstdDict];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Zstd => "zstd",
+            Self::Lz4 => "lz4",
+            Self::ZstdDict => "zstd-dict",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == raw)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum AudioCompression {
     Adpcm,
@@ -221,10 +1295,153 @@ pub enum Accelerator {
     None,
     Clfft,
     Vkfft,
+    /// Probe the GPU backends built into the binary (vkfft, then clfft) and use the first that
+    /// initialises, else the CPU.
+    Auto,
     #[serde(other)]
     Unsupported,
 }
 
+/// Vulkan device selector: an index into the Vulkan device list, or a PCI `vendor:device` id
+/// in hex (e.g. `"10de:2204"`). A PCI id picks the first matching device.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum VulkanDevice {
+    Index(usize),
+    PciId(String),
+}
+
+impl VulkanDevice {
+    /// Parses a `vendor:device` PCI id.
+    pub fn parse_pci_id(raw: &str) -> anyhow::Result<(u32, u32)> {
+        let (vendor, device) = raw.trim().split_once(':').with_context(|| {
+            format!("vulkan_device {raw:?}: expected index or \"vendor:device\"")
+        })?;
+        let parse = |part: &str| {
+            u16::from_str_radix(part, 16)
+                .map(u32::from)
+                .with_context(|| format!("vulkan_device {raw:?}: {part:?} is not a 16-bit hex id"))
+        };
+        Ok((parse(vendor)?, parse(device)?))
+    }
+}
+
+impl std::fmt::Display for VulkanDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Index(idx) => write!(f, "{idx}"),
+            Self::PciId(id) => f.write_str(id),
+        }
+    }
+}
+
+impl Accelerator {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Clfft => "clfft",
+            Self::Vkfft => "vkfft",
+            Self::Auto => "auto",
+            Self::Unsupported => "unsupported",
+        }
+    }
+
+    /// Whether this binary can run the accelerator; GPU backends need their cargo feature.
+    pub fn is_built_in(self) -> bool {
+        match self {
+            Self::None | Self::Auto => true,
+            Self::Clfft => cfg!(feature = "clfft"),
+            Self::Vkfft => cfg!(feature = "vkfft"),
+            Self::Unsupported => false,
+        }
+    }
+
+    /// Accelerators this binary can run, for capability reports.
+    pub fn built_in() -> Vec<Self> {
+        [Self::None, Self::Clfft, Self::Vkfft, Self::Auto]
+            .into_iter()
+            .filter(|a| a.is_built_in())
+            .collect()
+    }
+
+    /// The accelerator to run on the platform. Off Linux, where the GPU backends are usually
+    /// not built, an accelerator the binary cannot run falls back to the CPU instead of
+    /// stopping startup.
+    pub fn for_platform(self, linux: bool) -> Self {
+        if linux || self.is_built_in() {
+            self
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// FFT window function. Written as a string (`"blackman-harris"`), or as
+/// `{ "kaiser": { "beta": 8.6 } }` for a Kaiser window.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum FftWindow {
+    #[default]
+    Hann,
+    /// 4-term Blackman-Harris: about 92 dB sidelobes, for weak signals next to strong ones.
+    BlackmanHarris,
+    /// Flat-top: amplitude accurate to about 0.01 dB wherever a carrier falls between bins.
+    FlatTop,
+    /// Kaiser; larger `beta` trades a wider main lobe for lower sidelobes.
+    Kaiser {
+        #[serde(default = "default_kaiser_beta")]
+        beta: f32,
+    },
+}
+
+impl FftWindow {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hann => "hann",
+            Self::BlackmanHarris => "blackman-harris",
+            Self::FlatTop => "flat-top",
+            Self::Kaiser { .. } => "kaiser",
+        }
+    }
+}
+
+fn default_kaiser_beta() -> f32 {
+    8.6
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DcSuppressionMode {
+    #[default]
+    Off,
+    /// Zero the bins around DC.
+    Blank,
+    /// Replace the bins around DC with the level of their neighbours.
+    Interpolate,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+pub struct DcSuppression {
+    #[serde(default)]
+    pub mode: DcSuppressionMode,
+    /// Bins replaced on each side of DC; the DC bin itself is always included.
+    #[serde(default = "default_dc_suppression_bins")]
+    pub bins: usize,
+}
+
+impl Default for DcSuppression {
+    fn default() -> Self {
+        Self {
+            mode: DcSuppressionMode::Off,
+            bins: default_dc_suppression_bins(),
+        }
+    }
+}
+
+fn default_dc_suppression_bins() -> usize {
+    2
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum SampleFormat {
@@ -238,6 +1455,22 @@ pub enum SampleFormat {
     F64,
 }
 
+impl SampleFormat {
+    /// Name as written in `receivers.json`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SampleFormat::U8 => "u8",
+            SampleFormat::S8 => "s8",
+            SampleFormat::U16 => "u16",
+            SampleFormat::S16 => "s16",
+            SampleFormat::Cs16 => "cs16",
+            SampleFormat::F32 => "f32",
+            SampleFormat::Cf32 => "cf32",
+            SampleFormat::F64 => "f64",
+        }
+    }
+}
+
 fn default_true() -> bool {
     true
 }
@@ -245,59 +1478,203 @@ fn default_true() -> bool {
 fn default_port() -> u16 {
     9002
 }
-fn default_host() -> String {
-    "[::]".to_string()
+fn default_host() -> String {
+    "[::]".to_string()
+}
+fn default_html_root() -> String {
+    "frontend/dist/".to_string()
+}
+fn default_threads() -> usize {
+    0
+}
+fn default_soapysdr_rx_buffer_samples() -> usize {
+    65536
+}
+fn default_name() -> String {
+    "NovaSDR".to_string()
+}
+fn default_grid() -> String {
+    "-".to_string()
+}
+fn default_sdr_list_url() -> String {
+    "https://sdr-list.xyz/api/update_websdr".to_string()
+}
+fn default_chat_enabled() -> bool {
+    true
+}
+fn default_limit() -> usize {
+    1000
+}
+fn default_ws_per_ip() -> usize {
+    50
+}
+
+fn default_admin_session_ttl_secs() -> u64 {
+    12 * 60 * 60
+}
+
+fn default_updates_check_on_startup() -> bool {
+    true
+}
+
+fn default_updates_github_repo() -> String {
+    "Steven9101/NovaSDR".to_string()
+}
+fn default_capture_dir() -> String {
+    "captures".to_string()
+}
+fn default_capture_threshold_db() -> f32 {
+    20.0
+}
+fn default_capture_holdoff_secs() -> u64 {
+    300
+}
+fn default_pskreporter_host() -> String {
+    "report.pskreporter.info:4739".to_string()
+}
+fn default_export_interval_secs() -> u64 {
+    10
+}
+fn default_export_prefix() -> String {
+    "novasdr".to_string()
+}
+fn default_freqdb_tolerance_hz() -> i64 {
+    1_000
+}
+fn default_freqdb_max_results() -> usize {
+    10
+}
+fn default_freqdb_cache_secs() -> u64 {
+    86_400
+}
+fn default_freqdb_upstream_per_minute() -> u32 {
+    30
+}
+fn default_freqdb_lookups_per_minute() -> u32 {
+    12
+}
+fn default_accounts_max_accounts() -> usize {
+    1_000
+}
+fn default_accounts_signups_per_hour() -> u32 {
+    3
+}
+fn default_rtl_tcp_host() -> String {
+    "0.0.0.0".to_string()
+}
+fn default_rtl_tcp_port() -> u16 {
+    1234
+}
+fn default_rtl_tcp_max_clients() -> usize {
+    2
+}
+fn default_rtl_tcp_max_sample_rate() -> u32 {
+    2_048_000
+}
+fn default_fft_tap_host() -> String {
+    "127.0.0.1".to_string()
+}
+fn default_fft_tap_port() -> u16 {
+    5555
+}
+fn default_fft_tap_decimation() -> u32 {
+    1
+}
+fn default_fft_tap_max_clients() -> usize {
+    2
+}
+fn default_replay_max_mb() -> u32 {
+    64
+}
+fn default_udp_output_target() -> String {
+    "127.0.0.1:7355".to_string()
+}
+
+fn default_udp_output_packet_ms() -> u32 {
+    20
+}
+
+fn default_channel_dump_segment_minutes() -> u32 {
+    60
+}
+fn default_black_box_sample_rate() -> u32 {
+    192_000
+}
+fn default_black_box_segment_seconds() -> u32 {
+    60
 }
-fn default_html_root() -> String {
-    "frontend/dist/".to_string()
+fn default_black_box_minutes() -> u32 {
+    30
 }
-fn default_threads() -> usize {
-    0
+fn default_tx_sample_rate() -> u32 {
+    2_400_000
 }
-fn default_soapysdr_rx_buffer_samples() -> usize {
-    65536
+fn default_tx_max_seconds() -> u32 {
+    180
 }
-fn default_name() -> String {
-    "NovaSDR".to_string()
+fn default_diversity_max_offset_ms() -> u32 {
+    250
 }
-fn default_grid() -> String {
-    "-".to_string()
+fn default_iq_max_clients() -> usize {
+    4
 }
-fn default_sdr_list_url() -> String {
-    "https://sdr-list.xyz/api/update_websdr".to_string()
+fn default_iq_max_sample_rate() -> u32 {
+    192_000
 }
-fn default_chat_enabled() -> bool {
+fn default_streams_max_clients() -> usize {
+    4
+}
+fn default_time_shift_minutes() -> u32 {
+    0
+}
+fn default_crash_reports_enabled() -> bool {
     true
 }
-fn default_limit() -> usize {
-    1000
+fn default_disk_high_water_percent() -> f64 {
+    95.0
 }
-fn default_ws_per_ip() -> usize {
-    50
+fn default_disk_low_water_percent() -> f64 {
+    90.0
 }
-
-fn default_updates_check_on_startup() -> bool {
-    true
+fn default_disk_check_secs() -> u64 {
+    60
 }
-
-fn default_updates_github_repo() -> String {
-    "Steven9101/NovaSDR".to_string()
+fn default_wsprnet_url() -> String {
+    "http://wsprnet.org/post".to_string()
+}
+fn default_rbn_host() -> String {
+    "0.0.0.0".to_string()
+}
+fn default_rbn_port() -> u16 {
+    7300
+}
+fn default_skimmer_modes() -> Vec<SkimmerMode> {
+    vec![SkimmerMode::Ft8]
 }
 fn default_fft_size() -> usize {
     131_072
 }
+fn default_decimation() -> usize {
+    1
+}
 fn default_audio_sps() -> i64 {
     12_000
 }
 fn default_waterfall_size() -> usize {
     1024
 }
+fn default_waterfall_max_fps() -> f64 {
+    10.0
+}
 fn default_waterfall_compression() -> WaterfallCompression {
     WaterfallCompression::Zstd
 }
 fn default_audio_compression() -> AudioCompression {
     AudioCompression::Adpcm
 }
+fn default_shared_channels() -> bool {
+    true
+}
 fn default_default_frequency() -> i64 {
     -1
 }
@@ -330,6 +1707,7 @@ impl Default for WebSdr {
             operator: String::new(),
             email: String::new(),
             chat_enabled: default_chat_enabled(),
+            identity_key: String::new(),
         }
     }
 }
@@ -341,6 +1719,170 @@ impl Default for Limits {
             waterfall: default_limit(),
             events: default_limit(),
             ws_per_ip: default_ws_per_ip(),
+            audio_per_ip: 0,
+            listen_minutes_per_day: 0,
+            quota_waterfall_only: true,
+        }
+    }
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self {
+            dir: default_capture_dir(),
+            webhook_url: String::new(),
+        }
+    }
+}
+
+impl Default for Export {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::None,
+            target: String::new(),
+            token: String::new(),
+            interval_secs: default_export_interval_secs(),
+            prefix: default_export_prefix(),
+        }
+    }
+}
+
+impl Default for FreqDb {
+    fn default() -> Self {
+        Self {
+            csv: String::new(),
+            url_template: String::new(),
+            tolerance_hz: default_freqdb_tolerance_hz(),
+            max_results: default_freqdb_max_results(),
+            cache_secs: default_freqdb_cache_secs(),
+            upstream_per_minute: default_freqdb_upstream_per_minute(),
+            lookups_per_minute: default_freqdb_lookups_per_minute(),
+        }
+    }
+}
+
+impl Default for Accounts {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_accounts: default_accounts_max_accounts(),
+            signups_per_hour: default_accounts_signups_per_hour(),
+        }
+    }
+}
+
+impl Default for RtlTcp {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: default_rtl_tcp_host(),
+            port: default_rtl_tcp_port(),
+            receiver_id: String::new(),
+            max_clients: default_rtl_tcp_max_clients(),
+            max_sample_rate: default_rtl_tcp_max_sample_rate(),
+        }
+    }
+}
+
+impl Default for Streams {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_clients: default_streams_max_clients(),
+        }
+    }
+}
+
+impl Default for IqOutput {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_clients: default_iq_max_clients(),
+            max_sample_rate: default_iq_max_sample_rate(),
+        }
+    }
+}
+
+impl Default for Diversity {
+    fn default() -> Self {
+        Self {
+            pairs: Vec::new(),
+            max_offset_ms: default_diversity_max_offset_ms(),
+        }
+    }
+}
+
+impl Default for TimeShift {
+    fn default() -> Self {
+        Self {
+            minutes: default_time_shift_minutes(),
+        }
+    }
+}
+
+impl Default for DiskGuard {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            high_water_percent: default_disk_high_water_percent(),
+            low_water_percent: default_disk_low_water_percent(),
+            cleanup: DiskCleanup::None,
+            check_secs: default_disk_check_secs(),
+            webhook_url: String::new(),
+        }
+    }
+}
+
+impl Default for CrashReports {
+    fn default() -> Self {
+        Self {
+            enabled: default_crash_reports_enabled(),
+            dir: String::new(),
+            upload_url: String::new(),
+        }
+    }
+}
+
+impl Default for PskReporter {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            callsign: String::new(),
+            locator: String::new(),
+            antenna: String::new(),
+            host: default_pskreporter_host(),
+        }
+    }
+}
+
+impl Default for Wsprnet {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            callsign: String::new(),
+            locator: String::new(),
+            url: default_wsprnet_url(),
+        }
+    }
+}
+
+impl Default for Rbn {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            callsign: String::new(),
+            host: default_rbn_host(),
+            port: default_rbn_port(),
+        }
+    }
+}
+
+impl Default for Admin {
+    fn default() -> Self {
+        Self {
+            token: String::new(),
+            password: String::new(),
+            session_ttl_secs: default_admin_session_ttl_secs(),
         }
     }
 }
@@ -365,6 +1907,40 @@ struct GlobalConfigFile {
     #[serde(default)]
     pub updates: Updates,
     #[serde(default)]
+    pub admin: Admin,
+    #[serde(default)]
+    pub pskreporter: PskReporter,
+    #[serde(default)]
+    pub wsprnet: Wsprnet,
+    #[serde(default)]
+    pub rbn: Rbn,
+    #[serde(default)]
+    pub capture: Capture,
+    #[serde(default)]
+    pub geoip: GeoIp,
+    #[serde(default)]
+    pub export: Export,
+    #[serde(default)]
+    pub freqdb: FreqDb,
+    #[serde(default)]
+    pub accounts: Accounts,
+    #[serde(default)]
+    pub rtl_tcp: RtlTcp,
+    #[serde(default)]
+    pub crash_reports: CrashReports,
+    #[serde(default)]
+    pub streams: Streams,
+    #[serde(default)]
+    pub iq: IqOutput,
+    #[serde(default)]
+    pub diversity: Diversity,
+    #[serde(default)]
+    pub recordings: Recordings,
+    #[serde(default)]
+    pub time_shift: TimeShift,
+    #[serde(default)]
+    pub disk_guard: DiskGuard,
+    #[serde(default)]
     pub active_receiver_id: Option<String>,
 }
 
@@ -389,7 +1965,8 @@ fn migrate_global_config_json(value: &mut serde_json::Value) -> bool {
     changed
 }
 
-fn write_json_atomic(path: &Path, value: &serde_json::Value) -> anyhow::Result<()> {
+/// Writes `value` as pretty JSON through a temporary file next to `path`.
+pub fn write_json_atomic(path: &Path, value: &serde_json::Value) -> anyhow::Result<()> {
     let tmp = path.with_extension("tmp");
     let mut s = serde_json::to_string_pretty(value).context("serialize json")?;
     s.push('\n');
@@ -402,6 +1979,30 @@ fn write_json_atomic(path: &Path, value: &serde_json::Value) -> anyhow::Result<(
     Ok(())
 }
 
+/// Applies `update` to the raw JSON object of `receiver_id` in `receivers.json` and persists the
+/// file. Unknown keys and formatting of other receivers are preserved.
+pub fn update_receiver_in_file(
+    receivers_json: &Path,
+    receiver_id: &str,
+    update: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>),
+) -> anyhow::Result<()> {
+    let raw = std::fs::read_to_string(receivers_json)
+        .with_context(|| format!("read {}", receivers_json.display()))?;
+    let mut root: serde_json::Value = serde_json::from_str(&raw)
+        .with_context(|| format!("parse {}", receivers_json.display()))?;
+    let entry = root
+        .get_mut("receivers")
+        .and_then(|v| v.as_array_mut())
+        .and_then(|arr| {
+            arr.iter_mut()
+                .find(|r| r.get("id").and_then(|id| id.as_str()) == Some(receiver_id))
+        })
+        .and_then(|r| r.as_object_mut())
+        .with_context(|| format!("receiver {receiver_id:?} not found in receivers.json"))?;
+    update(entry);
+    write_json_atomic(receivers_json, &root)
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct ReceiversFile {
     pub receivers: Vec<ReceiverConfig>,
@@ -438,11 +2039,81 @@ pub fn load_from_files(config_json: &Path, receivers_json: &Path) -> anyhow::Res
         }
     }
 
+    let mut fallbacks = Vec::new();
+    for r in receivers.receivers.iter_mut() {
+        let configured = r.input.accelerator;
+        r.input.accelerator = configured.for_platform(cfg!(target_os = "linux"));
+        if r.input.accelerator != configured {
+            r.input.accelerator_fallback = Some(configured);
+            if r.enabled {
+                fallbacks.push(format!("{} ({})", r.id, configured.as_str()));
+            }
+        }
+    }
+    static FALLBACK_WARNED: std::sync::Once = std::sync::Once::new();
+    if !fallbacks.is_empty() {
+        FALLBACK_WARNED.call_once(|| {
+            tracing::warn!(
+                os = std::env::consts::OS,
+                receivers = %fallbacks.join(", "),
+                built_in = %Accelerator::built_in().iter().map(|a| a.as_str()).collect::<Vec<_>>().join(", "),
+                "GPU accelerator not available on this platform; these receivers use the CPU FFT"
+            );
+        });
+    }
+
+    if global.export.format != ExportFormat::None {
+        anyhow::ensure!(
+            !global.export.target.trim().is_empty(),
+            "export.target must be set when export.format is enabled"
+        );
+        anyhow::ensure!(
+            global.export.interval_secs > 0,
+            "export.interval_secs must be > 0"
+        );
+    }
+    let url_template = global.freqdb.url_template.trim();
+    if !url_template.is_empty() {
+        anyhow::ensure!(
+            url_template.starts_with("http://") || url_template.starts_with("https://"),
+            "freqdb.url_template must be an http(s) URL"
+        );
+        anyhow::ensure!(
+            ["{hz}", "{khz}", "{mhz}"]
+                .iter()
+                .any(|p| url_template.contains(p)),
+            "freqdb.url_template must contain {{hz}}, {{khz}} or {{mhz}}"
+        );
+    }
+    anyhow::ensure!(
+        global.freqdb.tolerance_hz >= 0,
+        "freqdb.tolerance_hz must be >= 0"
+    );
+    if global.iq.enabled {
+        anyhow::ensure!(
+            global.iq.max_sample_rate > 0,
+            "iq.max_sample_rate must be > 0"
+        );
+    }
+    if global.disk_guard.enabled {
+        let guard = &global.disk_guard;
+        anyhow::ensure!(
+            0.0 < guard.low_water_percent
+                && guard.low_water_percent < guard.high_water_percent
+                && guard.high_water_percent <= 100.0,
+            "disk_guard needs 0 < low_water_percent < high_water_percent <= 100"
+        );
+        anyhow::ensure!(guard.check_secs > 0, "disk_guard.check_secs must be > 0");
+    }
+
     let enabled_receivers: Vec<&ReceiverConfig> =
         receivers.receivers.iter().filter(|r| r.enabled).collect();
 
     let mut ids = HashSet::<String>::new();
+    let mut tap_ports = HashSet::<u16>::new();
     let mut stdin_receivers = 0usize;
+    // SoapySDR device string -> (sample rate, channels, first receiver using it).
+    let mut soapy_devices = HashMap::<&str, (i64, HashSet<usize>, &str)>::new();
     for r in enabled_receivers.iter() {
         let id_trimmed = r.id.trim();
         anyhow::ensure!(!id_trimmed.is_empty(), "receivers[].id must not be empty");
@@ -452,11 +2123,257 @@ pub fn load_from_files(config_json: &Path, receivers_json: &Path) -> anyhow::Res
         if matches!(r.input.driver, InputDriver::Stdin { .. }) {
             stdin_receivers += 1;
         }
+        if let InputDriver::SoapySdr(d) = &r.input.driver {
+            let (sps, channels, first) = soapy_devices
+                .entry(d.device.trim())
+                .or_insert_with(|| (r.input.sps, HashSet::new(), id_trimmed));
+            anyhow::ensure!(
+                *sps == r.input.sps,
+                "receivers[{id_trimmed:?}] shares SoapySDR device {:?} with {first:?}, so input.sps must match ({} != {})",
+                d.device,
+                r.input.sps,
+                sps
+            );
+            anyhow::ensure!(
+                channels.insert(d.channel),
+                "receivers[{id_trimmed:?}] uses channel {} of SoapySDR device {:?}, which another receiver already uses",
+                d.channel,
+                d.device
+            );
+            if let Some(ppm) = d.ppm_correction {
+                anyhow::ensure!(
+                    ppm.is_finite() && ppm.abs() <= MAX_PPM_CORRECTION,
+                    "receivers[{id_trimmed:?}].input.driver.ppm_correction must be within ±{MAX_PPM_CORRECTION}"
+                );
+            }
+            if let Some(key) = d.bias_tee_setting.as_deref() {
+                anyhow::ensure!(
+                    !key.trim().is_empty() && !key.contains('\0'),
+                    "receivers[{id_trimmed:?}].input.driver.bias_tee_setting must be a setting name"
+                );
+            }
+        }
+        if let InputDriver::Synthetic { scenario } = &r.input.driver {
+            let (low, high) = if r.input.signal == SignalType::Real {
+                (r.input.frequency, r.input.frequency + r.input.sps / 2)
+            } else {
+                (
+                    r.input.frequency - r.input.sps / 2,
+                    r.input.frequency + r.input.sps / 2,
+                )
+            };
+            anyhow::ensure!(
+                scenario.noise_floor_db.is_finite() && scenario.noise_floor_db <= 0.0,
+                "receivers[{id_trimmed:?}].input.driver.scenario.noise_floor_db must be <= 0"
+            );
+            for band in scenario.noise_bands.iter() {
+                anyhow::ensure!(
+                    low <= band.start && band.start < band.end && band.end <= high,
+                    "receivers[{id_trimmed:?}] synthetic noise band {}..{} must lie within the input's {low}..{high} Hz",
+                    band.start,
+                    band.end
+                );
+                anyhow::ensure!(
+                    band.level_db.is_finite() && band.level_db <= 0.0,
+                    "receivers[{id_trimmed:?}] synthetic noise band level_db must be <= 0"
+                );
+            }
+            for signal in scenario.signals.iter() {
+                let frequency = signal.frequency();
+                anyhow::ensure!(
+                    (low..high).contains(&frequency),
+                    "receivers[{id_trimmed:?}] synthetic signal at {frequency} Hz is outside the input's {low}..{high} Hz"
+                );
+                anyhow::ensure!(
+                    signal.level_db().is_finite() && signal.level_db() <= 0.0,
+                    "receivers[{id_trimmed:?}] synthetic signal at {frequency} Hz needs level_db <= 0"
+                );
+                if let SyntheticSignal::Fm { tone_hz, .. } = signal {
+                    anyhow::ensure!(
+                        *tone_hz > 0.0 && *tone_hz <= 3_000.0,
+                        "receivers[{id_trimmed:?}] synthetic FM signal at {frequency} Hz needs 0 < tone_hz <= 3000"
+                    );
+                }
+            }
+        }
+        for t in r.capture_triggers.iter() {
+            anyhow::ensure!(
+                !t.name.trim().is_empty(),
+                "receivers[{id_trimmed:?}].capture_triggers[].name must not be empty"
+            );
+            anyhow::ensure!(
+                t.end_hz > t.start_hz,
+                "capture trigger {:?} of receiver {id_trimmed:?} needs end_hz > start_hz",
+                t.name
+            );
+            anyhow::ensure!(
+                t.threshold_db > 0.0,
+                "capture trigger {:?} of receiver {id_trimmed:?} needs threshold_db > 0",
+                t.name
+            );
+        }
+        anyhow::ensure!(
+            r.replay.seconds == 0 || r.replay.max_mb > 0,
+            "receivers[{id_trimmed:?}].replay.max_mb must be > 0 when replay.seconds is set"
+        );
+        anyhow::ensure!(
+            !r.acars.enabled || !r.acars.frequencies.is_empty(),
+            "receivers[{id_trimmed:?}].acars.frequencies is required when acars.enabled = true"
+        );
+        if r.channel_dump.enabled {
+            anyhow::ensure!(
+                !r.channel_dump.channels.is_empty(),
+                "receivers[{id_trimmed:?}].channel_dump.channels is required when channel_dump.enabled = true"
+            );
+            anyhow::ensure!(
+                r.channel_dump.segment_minutes > 0,
+                "receivers[{id_trimmed:?}].channel_dump.segment_minutes must be > 0"
+            );
+            for ch in r.channel_dump.channels.iter() {
+                anyhow::ensure!(
+                    ch.mode.is_none() || ch.demodulation().is_some(),
+                    "receivers[{id_trimmed:?}].channel_dump.channels[].mode {:?} is not a demodulation mode",
+                    ch.mode.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        if r.udp_output.enabled {
+            if let Err(reason) = r.udp_output.check() {
+                anyhow::bail!("receivers[{id_trimmed:?}].udp_output: {reason}");
+            }
+        }
+        if r.black_box.enabled {
+            anyhow::ensure!(
+                r.black_box.sample_rate > 0,
+                "receivers[{id_trimmed:?}].black_box.sample_rate must be > 0"
+            );
+            anyhow::ensure!(
+                r.black_box.segment_seconds > 0,
+                "receivers[{id_trimmed:?}].black_box.segment_seconds must be > 0"
+            );
+            anyhow::ensure!(
+                u64::from(r.black_box.minutes) * 60 >= u64::from(r.black_box.segment_seconds),
+                "receivers[{id_trimmed:?}].black_box.minutes must cover at least one segment"
+            );
+        }
+        if !r.band_schedule.is_empty() {
+            anyhow::ensure!(
+                matches!(r.input.driver, InputDriver::SoapySdr(_)),
+                "receivers[{id_trimmed:?}].band_schedule needs input.driver.kind = \"soapysdr\""
+            );
+            let mut times = Vec::new();
+            for e in r.band_schedule.iter() {
+                let daily = e.daily_time().map_err(|err| {
+                    anyhow::anyhow!("receivers[{id_trimmed:?}].band_schedule[]: {err}")
+                })?;
+                anyhow::ensure!(
+                    !times.contains(&daily),
+                    "receivers[{id_trimmed:?}].band_schedule has two entries at {}",
+                    e.at
+                );
+                times.push(daily);
+                anyhow::ensure!(
+                    e.frequency > 0,
+                    "receivers[{id_trimmed:?}].band_schedule[].frequency must be > 0"
+                );
+            }
+        }
+        if r.tx.enabled {
+            let tx = &r.tx;
+            anyhow::ensure!(
+                matches!(r.input.driver, InputDriver::SoapySdr(_)),
+                "receivers[{id_trimmed:?}].tx needs input.driver.kind = \"soapysdr\""
+            );
+            anyhow::ensure!(
+                tx.sample_rate > 0
+                    && (tx.sample_rate as usize).is_multiple_of(crate::dsp::tx::TX_AUDIO_RATE),
+                "receivers[{id_trimmed:?}].tx.sample_rate must be a multiple of {}",
+                crate::dsp::tx::TX_AUDIO_RATE
+            );
+            anyhow::ensure!(
+                tx.max_seconds > 0,
+                "receivers[{id_trimmed:?}].tx.max_seconds must be > 0"
+            );
+            anyhow::ensure!(
+                !tx.bands.is_empty(),
+                "receivers[{id_trimmed:?}].tx.bands must list the ranges transmissions may use"
+            );
+            for b in tx.bands.iter() {
+                anyhow::ensure!(
+                    0 < b.start && b.start < b.end,
+                    "receivers[{id_trimmed:?}].tx.bands[{:?}] needs 0 < start < end",
+                    b.name
+                );
+                anyhow::ensure!(
+                    tx.gain_limit(b).is_finite(),
+                    "receivers[{id_trimmed:?}].tx.bands[{:?}].max_gain_db must be a number",
+                    b.name
+                );
+            }
+            anyhow::ensure!(
+                tx.max_gain_db.is_finite(),
+                "receivers[{id_trimmed:?}].tx.max_gain_db must be a number"
+            );
+            if let Some(key) = tx.ptt_setting.as_deref() {
+                anyhow::ensure!(
+                    !key.trim().is_empty() && !key.contains('\0'),
+                    "receivers[{id_trimmed:?}].tx.ptt_setting must be a setting name"
+                );
+            }
+        }
+        if let Some(ppm) = r.accuracy.ppm {
+            anyhow::ensure!(
+                ppm.is_finite() && ppm >= 0.0,
+                "receivers[{id_trimmed:?}].accuracy.ppm must be >= 0"
+            );
+        }
+        if let Some(carrier) = r.accuracy.drift_carrier {
+            anyhow::ensure!(
+                carrier > 0,
+                "receivers[{id_trimmed:?}].accuracy.drift_carrier must be > 0"
+            );
+            anyhow::ensure!(
+                r.accuracy.drift_search_hz > 0,
+                "receivers[{id_trimmed:?}].accuracy.drift_search_hz must be > 0"
+            );
+        }
+        if r.fft_tap.enabled {
+            anyhow::ensure!(
+                !r.fft_tap.token.trim().is_empty(),
+                "receivers[{id_trimmed:?}].fft_tap.token is required when fft_tap.enabled = true"
+            );
+            anyhow::ensure!(
+                r.fft_tap.decimation >= 1,
+                "receivers[{id_trimmed:?}].fft_tap.decimation must be >= 1"
+            );
+            anyhow::ensure!(
+                tap_ports.insert(r.fft_tap.port),
+                "receivers[{id_trimmed:?}].fft_tap.port {} is used by another receiver",
+                r.fft_tap.port
+            );
+        }
     }
     anyhow::ensure!(
         stdin_receivers <= 1,
         "only one enabled receiver may use input.driver.kind = \"stdin\" (found {stdin_receivers})"
     );
+    for pair in global.diversity.pairs.iter() {
+        anyhow::ensure!(
+            pair.a != pair.b,
+            "diversity.pairs needs two different receivers (got {:?} twice)",
+            pair.a
+        );
+        for id in [&pair.a, &pair.b] {
+            anyhow::ensure!(
+                ids.contains(id),
+                "diversity.pairs names {id:?}, which is not an enabled receiver"
+            );
+        }
+    }
+    anyhow::ensure!(
+        global.diversity.max_offset_ms > 0,
+        "diversity.max_offset_ms must be > 0"
+    );
 
     let active_id = match global.active_receiver_id.as_deref().map(str::trim) {
         Some(id) if !id.is_empty() => id.to_string(),
@@ -466,6 +2383,19 @@ pub fn load_from_files(config_json: &Path, receivers_json: &Path) -> anyhow::Res
         ),
     };
 
+    anyhow::ensure!(
+        !global.pskreporter.enabled || !global.pskreporter.callsign.trim().is_empty(),
+        "pskreporter.callsign is required when pskreporter.enabled = true"
+    );
+    anyhow::ensure!(
+        !global.wsprnet.enabled || !global.wsprnet.callsign.trim().is_empty(),
+        "wsprnet.callsign is required when wsprnet.enabled = true"
+    );
+    anyhow::ensure!(
+        !global.rbn.enabled || !global.rbn.callsign.trim().is_empty(),
+        "rbn.callsign is required when rbn.enabled = true"
+    );
+
     if !enabled_receivers.iter().any(|r| r.id == active_id) {
         anyhow::bail!(
             "active_receiver_id {active_id:?} not found amoung enabled receivers in receivers.json"
@@ -477,6 +2407,23 @@ pub fn load_from_files(config_json: &Path, receivers_json: &Path) -> anyhow::Res
         websdr: global.websdr,
         limits: global.limits,
         updates: global.updates,
+        admin: global.admin,
+        pskreporter: global.pskreporter,
+        wsprnet: global.wsprnet,
+        rbn: global.rbn,
+        capture: global.capture,
+        geoip: global.geoip,
+        export: global.export,
+        freqdb: global.freqdb,
+        accounts: global.accounts,
+        rtl_tcp: global.rtl_tcp,
+        crash_reports: global.crash_reports,
+        streams: global.streams,
+        iq: global.iq,
+        diversity: global.diversity,
+        recordings: global.recordings,
+        time_shift: global.time_shift,
+        disk_guard: global.disk_guard,
         receivers: receivers.receivers,
         active_receiver_id: active_id,
     })
@@ -484,7 +2431,10 @@ pub fn load_from_files(config_json: &Path, receivers_json: &Path) -> anyhow::Res
 
 #[derive(Debug, Clone)]
 pub struct Runtime {
+    /// Sample rate the FFT runs at: the input rate after `decimation`.
     pub sps: i64,
+    /// Sample rate of the hardware or stream, before decimation.
+    pub input_sps: i64,
     pub fft_size: usize,
     pub fft_result_size: usize,
     pub is_real: bool,
@@ -493,7 +2443,13 @@ pub struct Runtime {
     pub downsample_levels: usize,
     pub audio_max_sps: i64,
     pub audio_max_fft_size: usize,
+    /// Ratio of the WBFM multiplex rate to the audio rate; the WBFM path consumes
+    /// `audio_max_fft_size * wbfm_decimation` bins per frame.
+    pub wbfm_decimation: usize,
     pub min_waterfall_fft: usize,
+    /// The waterfall is produced on every Nth FFT frame, keeping it at or below
+    /// `waterfall_max_fps`.
+    pub waterfall_frame_skip: u64,
     pub brightness_offset: i32,
     pub show_other_users: bool,
     pub default_frequency: i64,
@@ -501,6 +2457,9 @@ pub struct Runtime {
     pub default_l: i32,
     pub default_r: i32,
     pub default_mode_str: String,
+    /// Default SSB passband edges, used for the USB/LSB windows in [`Runtime::mode_window`].
+    pub ssb_lowcut_hz: i64,
+    pub ssb_highcut_hz: i64,
     pub waterfall_compression_str: String,
     pub audio_compression_str: String,
 }
@@ -520,6 +2479,43 @@ impl Config {
             })
     }
 
+    /// Top-level keys of `config.json` (and `active_receiver_id`) whose settings differ in
+    /// `other`.
+    pub fn changed_sections(&self, other: &Config) -> Vec<&'static str> {
+        let sections = [
+            ("server", self.server != other.server),
+            ("websdr", self.websdr != other.websdr),
+            ("limits", self.limits != other.limits),
+            ("updates", self.updates != other.updates),
+            ("admin", self.admin != other.admin),
+            ("pskreporter", self.pskreporter != other.pskreporter),
+            ("wsprnet", self.wsprnet != other.wsprnet),
+            ("rbn", self.rbn != other.rbn),
+            ("capture", self.capture != other.capture),
+            ("geoip", self.geoip != other.geoip),
+            ("export", self.export != other.export),
+            ("freqdb", self.freqdb != other.freqdb),
+            ("accounts", self.accounts != other.accounts),
+            ("rtl_tcp", self.rtl_tcp != other.rtl_tcp),
+            ("crash_reports", self.crash_reports != other.crash_reports),
+            ("streams", self.streams != other.streams),
+            ("iq", self.iq != other.iq),
+            ("diversity", self.diversity != other.diversity),
+            ("recordings", self.recordings != other.recordings),
+            ("time_shift", self.time_shift != other.time_shift),
+            ("disk_guard", self.disk_guard != other.disk_guard),
+            (
+                "active_receiver_id",
+                self.active_receiver_id != other.active_receiver_id,
+            ),
+        ];
+        sections
+            .into_iter()
+            .filter(|&(_, changed)| changed)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
     pub fn runtime(&self) -> anyhow::Result<Runtime> {
         self.runtime_for(self.active_receiver_id.as_str())
     }
@@ -532,8 +2528,19 @@ impl Config {
     }
 
     fn runtime_from_input(&self, input: &ReceiverInput) -> anyhow::Result<Runtime> {
-        let sps = input.sps;
-        anyhow::ensure!(sps > 0, "receiver.input.sps must be > 0");
+        let input_sps = input.sps;
+        anyhow::ensure!(input_sps > 0, "receiver.input.sps must be > 0");
+        let decimation = input.decimation;
+        anyhow::ensure!(
+            decimation.is_power_of_two() && decimation <= crate::dsp::decimate::MAX_DECIMATION,
+            "receiver.input.decimation must be a power of two up to {}",
+            crate::dsp::decimate::MAX_DECIMATION
+        );
+        anyhow::ensure!(
+            input_sps % decimation as i64 == 0,
+            "receiver.input.sps must be a multiple of receiver.input.decimation"
+        );
+        let sps = input_sps / decimation as i64;
 
         let fft_size = input.fft_size;
         anyhow::ensure!(
@@ -560,6 +2567,24 @@ impl Config {
             "waterfall_size too large for fft_result_size"
         );
 
+        if let FftWindow::Kaiser { beta } = input.window {
+            anyhow::ensure!(
+                beta.is_finite() && (0.0..=40.0).contains(&beta),
+                "receiver.input.window.kaiser.beta must be within 0..=40"
+            );
+        }
+
+        if let Some(VulkanDevice::PciId(id)) = &input.vulkan_device {
+            VulkanDevice::parse_pci_id(id).context("receiver.input.vulkan_device")?;
+        }
+
+        anyhow::ensure!(
+            input.waterfall_max_fps.is_finite() && input.waterfall_max_fps > 0.0,
+            "receiver.input.waterfall_max_fps must be > 0"
+        );
+        let frame_rate = sps as f64 / (fft_size / 2) as f64;
+        let waterfall_frame_skip = ((frame_rate / input.waterfall_max_fps).ceil() as u64).max(1);
+
         let audio_max_sps = input.audio_sps;
         anyhow::ensure!(audio_max_sps > 0, "receiver.input.audio_sps must be > 0");
         let max_audio_sps = if is_real { sps / 2 } else { sps };
@@ -572,6 +2597,10 @@ impl Config {
             ((((audio_max_sps as f64) * (fft_size as f64) / (sps as f64) / 4.0).ceil() as usize)
                 * 4)
             .max(32);
+        let wbfm_decimation = ((crate::dsp::wbfm::TARGET_MPX_RATE as i64 + audio_max_sps - 1)
+            / audio_max_sps)
+            .clamp(1, (fft_result_size / audio_max_fft_size).max(1) as i64)
+            as usize;
 
         let show_other_users = self.server.otherusers > 0;
 
@@ -580,26 +2609,12 @@ impl Config {
             default_frequency = basefreq + total_bandwidth / 2;
         }
 
-        let mut default_m = if is_real {
+        let default_m = if is_real {
             (default_frequency - basefreq) as f64 * (fft_result_size as f64) * 2.0 / (sps as f64)
         } else {
             (default_frequency - basefreq) as f64 * (fft_result_size as f64) / (sps as f64)
         };
 
-        // Convert Hz offsets into FFT bins. For real-input receivers, `total_bandwidth = sps/2`,
-        // so the bin->Hz scale is doubled vs complex input.
-        let hz_to_bins = |hz: i64| -> i64 {
-            let scale = if is_real { 2_i128 } else { 1_i128 };
-            let hz = hz as i128;
-            let fft = fft_result_size as i128;
-            let sps = sps as i128;
-            ((hz * fft * scale) / sps) as i64
-        };
-
-        let offsets_3 = hz_to_bins(3000);
-        let offsets_5 = hz_to_bins(5000);
-        let offsets_96 = hz_to_bins(96000);
-
         let ssb_lowcut_hz = input.defaults.ssb_lowcut_hz.unwrap_or(100);
         let ssb_highcut_hz = input.defaults.ssb_highcut_hz.unwrap_or(2800);
         anyhow::ensure!(
@@ -610,54 +2625,19 @@ impl Config {
             ssb_highcut_hz > ssb_lowcut_hz,
             "receiver.input.defaults.ssb_highcut_hz must be > receiver.input.defaults.ssb_lowcut_hz"
         );
-        let offsets_ssb_low = hz_to_bins(ssb_lowcut_hz);
-        let offsets_ssb_high = hz_to_bins(ssb_highcut_hz);
 
         let default_mode_str = input.defaults.modulation.to_uppercase();
-        let (default_l, default_r) = match default_mode_str.as_str() {
-            "LSB" => (
-                (default_m as i64 - offsets_ssb_high) as i32,
-                (default_m as i64 - offsets_ssb_low) as i32,
-            ),
-            "AM" | "SAM" | "FM" | "FMC" => (
-                (default_m as i64 - offsets_5) as i32,
-                (default_m as i64 + offsets_5) as i32,
-            ),
-            "WBFM" => (
-                (default_m as i64 - offsets_96) as i32,
-                (default_m as i64 + offsets_96) as i32,
-            ),
-            "USB" => (
-                (default_m as i64 + offsets_ssb_low) as i32,
-                (default_m as i64 + offsets_ssb_high) as i32,
-            ),
-            _ => (default_m as i32, (default_m as i64 + offsets_3) as i32),
-        };
-
-        default_m = default_m.clamp(0.0, fft_result_size as f64);
-        let mut default_l = default_l.clamp(0, fft_result_size as i32);
-        let mut default_r = default_r.clamp(0, fft_result_size as i32);
 
-        let max_window = audio_max_fft_size.min(fft_result_size) as i32;
-        if max_window > 0 && (default_r - default_l) > max_window {
-            let center = default_m.round() as i32;
-            let half = max_window / 2;
-            default_l =
-                (center - half).clamp(0, (fft_result_size as i32).saturating_sub(max_window));
-            default_r = default_l + max_window;
-        }
-
-        let waterfall_compression_str = match input.waterfall_compression {
-            WaterfallCompression::Zstd => "zstd".to_string(),
-        };
+        let waterfall_compression_str = input.waterfall_compression.as_str().to_string();
         let audio_compression_str = match input.audio_compression {
             AudioCompression::Adpcm => "adpcm".to_string(),
             AudioCompression::Flac => "flac".to_string(),
             AudioCompression::Opus => "opus".to_string(),
         };
 
-        Ok(Runtime {
+        let mut rt = Runtime {
             sps,
+            input_sps,
             fft_size,
             fft_result_size,
             is_real,
@@ -666,16 +2646,106 @@ impl Config {
             downsample_levels,
             audio_max_sps,
             audio_max_fft_size,
+            wbfm_decimation,
             min_waterfall_fft,
+            waterfall_frame_skip,
             brightness_offset: input.brightness_offset,
             show_other_users,
             default_frequency,
-            default_m,
-            default_l,
-            default_r,
+            default_m: default_m.clamp(0.0, fft_result_size as f64),
+            default_l: 0,
+            default_r: 0,
             default_mode_str,
+            ssb_lowcut_hz,
+            ssb_highcut_hz,
             waterfall_compression_str,
             audio_compression_str,
-        })
+        };
+        (rt.default_l, rt.default_r) = rt.mode_window(&rt.default_mode_str, default_m);
+        Ok(rt)
+    }
+}
+
+/// Widest SSB passband kept when switching into USB/LSB.
+const MAX_SSB_WINDOW_HZ: i64 = 6_000;
+/// Widest AM/SAM/FM passband kept when switching into those modes.
+const MAX_AM_FM_WINDOW_HZ: i64 = 24_000;
+
+impl Runtime {
+    /// Converts a frequency offset in Hz into FFT bins. For real-input receivers
+    /// `total_bandwidth = sps/2`, so the bin->Hz scale is doubled vs complex input.
+    pub fn hz_to_bins(&self, hz: i64) -> i64 {
+        let scale = if self.is_real { 2_i128 } else { 1_i128 };
+        ((hz as i128 * self.fft_result_size as i128 * scale) / self.sps as i128) as i64
+    }
+
+    /// Default audio window `(l, r)` for `mode` tuned to bin `m`, clamped to the spectrum and
+    /// limited to `audio_max_fft_size` bins.
+    pub fn mode_window(&self, mode: &str, m: f64) -> (i32, i32) {
+        let offsets_3 = self.hz_to_bins(3000);
+        let offsets_5 = self.hz_to_bins(5000);
+        let offsets_96 = self.hz_to_bins(96000);
+        let offsets_ssb_low = self.hz_to_bins(self.ssb_lowcut_hz);
+        let offsets_ssb_high = self.hz_to_bins(self.ssb_highcut_hz);
+
+        let (l, r) = match mode {
+            "LSB" => (
+                (m as i64 - offsets_ssb_high) as i32,
+                (m as i64 - offsets_ssb_low) as i32,
+            ),
+            "AM" | "SAM" | "FM" | "FMC" => {
+                ((m as i64 - offsets_5) as i32, (m as i64 + offsets_5) as i32)
+            }
+            "WBFM" => (
+                (m as i64 - offsets_96) as i32,
+                (m as i64 + offsets_96) as i32,
+            ),
+            "USB" => (
+                (m as i64 + offsets_ssb_low) as i32,
+                (m as i64 + offsets_ssb_high) as i32,
+            ),
+            _ => (m as i32, (m as i64 + offsets_3) as i32),
+        };
+
+        let size = self.fft_result_size as i32;
+        let mut l = l.clamp(0, size);
+        let mut r = r.clamp(0, size);
+
+        let max_window = self.audio_max_fft_size.min(self.fft_result_size) as i32;
+        if max_window > 0 && (r - l) > max_window {
+            let center = m.clamp(0.0, self.fft_result_size as f64).round() as i32;
+            let half = max_window / 2;
+            l = (center - half).clamp(0, size.saturating_sub(max_window));
+            r = l + max_window;
+        }
+        (l, r)
+    }
+
+    /// Checks the audio window `l..r` around bin `m` against what `mode` can demodulate and
+    /// returns the mode's default window when it does not fit: SSB needs a window of at most
+    /// 6 kHz on the correct side of `m`, AM/SAM/FM one of at most 24 kHz containing `m`. WBFM
+    /// selects its own bins and accepts any window.
+    pub fn fit_window_to_mode(
+        &self,
+        mode: DemodulationMode,
+        l: i32,
+        r: i32,
+        m: f64,
+    ) -> Option<(i32, i32)> {
+        let m0 = m.floor() as i32;
+        let width = i64::from(r - l);
+        let fits = match mode {
+            DemodulationMode::Usb => r > m0 && width <= self.hz_to_bins(MAX_SSB_WINDOW_HZ),
+            DemodulationMode::Lsb => l < m0 && width <= self.hz_to_bins(MAX_SSB_WINDOW_HZ),
+            DemodulationMode::Am | DemodulationMode::Sam | DemodulationMode::Fm => {
+                l <= m0 && m0 <= r && width <= self.hz_to_bins(MAX_AM_FM_WINDOW_HZ)
+            }
+            DemodulationMode::Wbfm => true,
+        };
+        if fits {
+            None
+        } else {
+            Some(self.mode_window(mode.as_str(), m))
+        }
     }
 }
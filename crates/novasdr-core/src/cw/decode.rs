@@ -0,0 +1,232 @@
+use super::{character, dit_ms};
+use std::collections::VecDeque;
+
+/// Frames averaged into one level, against the fluctuation of noise in a narrow bin.
+const SMOOTHING_FRAMES: usize = 3;
+/// Frames the key must stay in its new state before the change counts.
+const DEBOUNCE_FRAMES: usize = 2;
+/// Key-down threshold above the noise while no signal has been heard, and the least it may
+/// drop to; with a signal it sits halfway between noise and signal.
+const MIN_THRESHOLD_DB: f32 = 10.0;
+const HYSTERESIS_DB: f32 = 3.0;
+/// Noise tracking rates while the key is up. Rising slowly keeps the tracker under a signal
+/// heard before it settled.
+const NOISE_RISE_RATE: f32 = 0.005;
+const NOISE_FALL_RATE: f32 = 0.05;
+const PEAK_RATE: f32 = 0.1;
+/// Decay of the signal level while the key is up, per frame.
+const PEAK_DECAY_DB: f32 = 0.01;
+/// Key-down longer than this is a carrier, not Morse; it is taken into the noise.
+const MAX_MARK_MS: f64 = 1000.0;
+/// Marks the speed is estimated from.
+const SPEED_MARKS: usize = 16;
+/// Speeds outside this range are clamped.
+const MIN_WPM: f64 = 8.0;
+const MAX_WPM: f64 = 50.0;
+/// Dit length assumed until the first marks are heard.
+const DEFAULT_WPM: f64 = 20.0;
+/// Elements of the longest character.
+const MAX_ELEMENTS: usize = 7;
+
+/// Decodes one keyed tone from its power, one value per analysis frame. Speed and levels adapt
+/// to the signal.
+pub struct Decoder {
+    frame_ms: f64,
+    recent: VecDeque<f32>,
+    /// Mean noise power.
+    noise: Option<f32>,
+    peak_db: f32,
+    key_down: bool,
+    /// Frames since the key last changed state, and of those the trailing ones that disagree.
+    run: usize,
+    flip: usize,
+    marks: VecDeque<f64>,
+    dit_ms: Option<f64>,
+    /// Marks shorter than this are dits.
+    split_ms: f64,
+    /// Marks of the character being received; told apart once it ends, with the speed
+    /// estimate they helped to make.
+    elements: Vec<f64>,
+    /// Whether a character was decoded since the last word space.
+    in_word: bool,
+}
+
+impl Decoder {
+    pub fn new(frame_ms: f64) -> Self {
+        Self {
+            frame_ms,
+            recent: VecDeque::with_capacity(SMOOTHING_FRAMES),
+            noise: None,
+            peak_db: f32::NEG_INFINITY,
+            key_down: false,
+            run: 0,
+            flip: 0,
+            marks: VecDeque::with_capacity(SPEED_MARKS),
+            dit_ms: None,
+            split_ms: 2.0 * dit_ms(DEFAULT_WPM),
+            elements: Vec::with_capacity(MAX_ELEMENTS),
+            in_word: false,
+        }
+    }
+
+    /// Speed of the signal once some marks were heard.
+    pub fn wpm(&self) -> Option<f64> {
+        self.dit_ms.map(|dit| 1200.0 / dit)
+    }
+
+    /// Signal over noise in the analysis bin.
+    pub fn snr_db(&self) -> f32 {
+        self.noise
+            .map_or(0.0, |noise| (self.peak_db - db(noise)).max(0.0))
+    }
+
+    fn dit(&self) -> f64 {
+        self.dit_ms.unwrap_or(dit_ms(DEFAULT_WPM))
+    }
+
+    /// Feeds the power of the next frame; returns a decoded character, or `' '` at the end of a
+    /// word.
+    pub fn push(&mut self, power: f32) -> Option<char> {
+        if self.recent.len() == SMOOTHING_FRAMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(power);
+        let mean = self.recent.iter().sum::<f32>() / self.recent.len() as f32;
+        let level = db(mean);
+        let noise = db(*self.noise.get_or_insert(mean));
+
+        let threshold = MIN_THRESHOLD_DB.max((self.peak_db - noise) / 2.0);
+        let above = level - noise;
+        let keyed = if self.key_down {
+            above > threshold - HYSTERESIS_DB
+        } else {
+            above > threshold
+        };
+
+        self.run += 1;
+        let carrier = self.key_down && self.run as f64 * self.frame_ms > MAX_MARK_MS;
+        if let Some(noise) = self.noise.as_mut().filter(|_| !self.key_down || carrier) {
+            let rate = if mean > *noise {
+                NOISE_RISE_RATE
+            } else {
+                NOISE_FALL_RATE
+            };
+            *noise += (mean - *noise) * rate;
+        }
+        if self.key_down {
+            self.peak_db += (level - self.peak_db) * PEAK_RATE;
+        } else {
+            self.peak_db = (self.peak_db - PEAK_DECAY_DB).max(noise);
+        }
+
+        if keyed == self.key_down {
+            self.flip = 0;
+            return if self.key_down {
+                None
+            } else {
+                self.space(self.run)
+            };
+        }
+        self.flip += 1;
+        if self.flip < DEBOUNCE_FRAMES {
+            return None;
+        }
+        let len_ms = (self.run - self.flip) as f64 * self.frame_ms;
+        (self.run, self.flip) = (self.flip, 0);
+        self.key_down = keyed;
+        if keyed {
+            if self.peak_db < level {
+                self.peak_db = level;
+            }
+            None
+        } else {
+            self.mark(len_ms);
+            None
+        }
+    }
+
+    /// A space of `frames` so far: ends the character after two dits and the word after five.
+    fn space(&mut self, frames: usize) -> Option<char> {
+        let dits = frames as f64 * self.frame_ms / self.dit();
+        if dits >= 2.0 && !self.elements.is_empty() {
+            let code: String = self
+                .elements
+                .drain(..)
+                .map(|len| if len < self.split_ms { '.' } else { '-' })
+                .collect();
+            let c = character(&code);
+            if c.is_some() {
+                self.in_word = true;
+            }
+            return c;
+        }
+        if dits >= 5.0 && self.in_word {
+            self.in_word = false;
+            return Some(' ');
+        }
+        None
+    }
+
+    fn mark(&mut self, len_ms: f64) {
+        let dit = self.dit();
+        if len_ms > 5.0 * dit || len_ms > MAX_MARK_MS {
+            // A carrier or a burst of noise.
+            self.elements.clear();
+            return;
+        }
+        if len_ms < dit_ms(MAX_WPM) / 2.0 {
+            return;
+        }
+        if self.marks.len() == SPEED_MARKS {
+            self.marks.pop_front();
+        }
+        self.marks.push_back(len_ms);
+        self.estimate_speed();
+        if self.elements.len() == MAX_ELEMENTS {
+            // Longer than any character.
+            self.elements.clear();
+        }
+        self.elements.push(len_ms);
+    }
+
+    /// Dit length from the recent marks, which form two groups: dits and dahs. Their
+    /// difference is two dits however much the threshold lengthens every mark.
+    fn estimate_speed(&mut self) {
+        let min = self.marks.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = self.marks.iter().copied().fold(0.0, f64::max);
+        if max < 2.0 * min {
+            // All one kind; until both are heard, the default speed tells which.
+            if self.dit_ms.is_none() {
+                let mean = self.marks.iter().sum::<f64>() / self.marks.len() as f64;
+                let dit = if mean < self.split_ms {
+                    mean
+                } else {
+                    mean / 3.0
+                };
+                self.dit_ms = Some(dit.clamp(dit_ms(MAX_WPM), dit_ms(MIN_WPM)));
+                self.split_ms = 2.0 * dit;
+            }
+            return;
+        }
+        let mut split = (min * max).sqrt();
+        let (mut dit, mut dah) = (min, max);
+        for _ in 0..3 {
+            let mean = |long: bool| {
+                let (sum, n) = self
+                    .marks
+                    .iter()
+                    .filter(|&&len| (len >= split) == long)
+                    .fold((0.0, 0), |(sum, n), &len| (sum + len, n + 1));
+                sum / f64::from(n)
+            };
+            (dit, dah) = (mean(false), mean(true));
+            split = (dit + dah) / 2.0;
+        }
+        self.split_ms = split;
+        self.dit_ms = Some(((dah - dit) / 2.0).clamp(dit_ms(MAX_WPM), dit_ms(MIN_WPM)));
+    }
+}
+
+fn db(power: f32) -> f32 {
+    10.0 * (power + 1e-20).log10()
+}
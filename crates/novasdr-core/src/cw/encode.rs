@@ -0,0 +1,48 @@
+use super::{code, dit_ms};
+use std::f64::consts::{PI, TAU};
+
+/// Rise and fall time of every element, so the keying does not click.
+const EDGE_MS: f64 = 5.0;
+
+/// `text` keyed at `wpm` on a `tone_hz` tone, as audio at `sample_rate`. Characters without a
+/// Morse code are skipped; spaces separate words.
+pub fn encode(text: &str, wpm: f64, tone_hz: f64, sample_rate: f64) -> Vec<f32> {
+    // Key state per dit: one dit between elements, three between characters, seven between
+    // words.
+    let mut keying: Vec<bool> = Vec::new();
+    for word in text.split_whitespace() {
+        if !keying.is_empty() {
+            keying.extend([false; 7]);
+        }
+        for (i, elements) in word.chars().filter_map(code).enumerate() {
+            if i > 0 {
+                keying.extend([false; 3]);
+            }
+            for (j, element) in elements.chars().enumerate() {
+                if j > 0 {
+                    keying.push(false);
+                }
+                let dits = if element == '-' { 3 } else { 1 };
+                keying.extend(std::iter::repeat_n(true, dits));
+            }
+        }
+    }
+
+    let dit = dit_ms(wpm) * sample_rate / 1000.0;
+    let edge = EDGE_MS * sample_rate / 1000.0;
+    let total = (keying.len() as f64 * dit).round() as usize;
+    let key = |i: usize| keying.get((i as f64 / dit) as usize).copied() == Some(true);
+    let mut envelope = 0.0f64;
+    (0..total)
+        .map(|i| {
+            let step = 1.0 / edge;
+            envelope = if key(i) {
+                (envelope + step).min(1.0)
+            } else {
+                (envelope - step).max(0.0)
+            };
+            let shaped = 0.5 - 0.5 * (PI * envelope).cos();
+            (shaped * (TAU * tone_hz * i as f64 / sample_rate).sin()) as f32
+        })
+        .collect()
+}
@@ -0,0 +1,108 @@
+//! Morse code: keying a text into audio, decoding a keyed tone, and a skimmer that decodes every
+//! signal in a band of audio at once and picks out the callsigns calling CQ.
+
+mod decode;
+mod encode;
+mod skimmer;
+
+pub use decode::Decoder;
+pub use encode::encode;
+pub use skimmer::{Skimmer, Spot, SpotKind};
+
+/// Characters and their dits (`.`) and dahs (`-`). Prosigns are left out; they decode as the
+/// letters they are sent as.
+const MORSE: [(char, &str); 47] = [
+    ('A', ".-"),
+    ('B', "-..."),
+    ('C', "-.-."),
+    ('D', "-.."),
+    ('E', "."),
+    ('F', "..-."),
+    ('G', "--."),
+    ('H', "...."),
+    ('I', ".."),
+    ('J', ".---"),
+    ('K', "-.-"),
+    ('L', ".-.."),
+    ('M', "--"),
+    ('N', "-."),
+    ('O', "---"),
+    ('P', ".--."),
+    ('Q', "--.-"),
+    ('R', ".-."),
+    ('S', "..."),
+    ('T', "-"),
+    ('U', "..-"),
+    ('V', "...-"),
+    ('W', ".--"),
+    ('X', "-..-"),
+    ('Y', "-.--"),
+    ('Z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('/', "-..-."),
+    ('?', "..--.."),
+    ('=', "-...-"),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    ('+', ".-.-."),
+    ('-', "-....-"),
+    ('(', "-.--."),
+    (')', "-.--.-"),
+    ('"', ".-..-."),
+    ('@', ".--.-."),
+];
+
+/// Dits and dahs of `c`, case-insensitively.
+pub fn code(c: char) -> Option<&'static str> {
+    let c = c.to_ascii_uppercase();
+    MORSE.iter().find(|(m, _)| *m == c).map(|(_, code)| *code)
+}
+
+pub fn character(code: &str) -> Option<char> {
+    MORSE.iter().find(|(_, m)| *m == code).map(|(c, _)| *c)
+}
+
+/// Length of a dit at `wpm` words per minute ("PARIS" is 50 dits).
+pub fn dit_ms(wpm: f64) -> f64 {
+    1200.0 / wpm
+}
+
+/// Whether `word` looks like an amateur callsign: a prefix of one to three characters with a
+/// letter in it, a digit, and a suffix of one to four letters. A portable or country indicator
+/// (`/P`, `DL/`) may be attached to it.
+pub fn is_callsign(word: &str) -> bool {
+    if !(3..=12).contains(&word.len()) {
+        return false;
+    }
+    let parts: Vec<&str> = word.split('/').collect();
+    if parts.len() > 3 || parts.iter().any(|p| p.is_empty()) {
+        return false;
+    }
+    let Some(base) = parts.iter().copied().max_by_key(|p| p.len()) else {
+        return false;
+    };
+    let bytes = base.as_bytes();
+    // The last digit separates prefix and suffix.
+    let Some(digit) = bytes.iter().rposition(u8::is_ascii_digit) else {
+        return false;
+    };
+    let (prefix, suffix) = (&bytes[..digit], &bytes[digit + 1..]);
+    (1..=3).contains(&prefix.len())
+        && prefix.iter().all(u8::is_ascii_alphanumeric)
+        && prefix.iter().any(u8::is_ascii_uppercase)
+        && (1..=4).contains(&suffix.len())
+        && suffix.iter().all(u8::is_ascii_uppercase)
+        && parts.iter().all(|p| {
+            p.bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        })
+}
@@ -0,0 +1,186 @@
+use super::{is_callsign, Decoder};
+use num_complex::Complex32;
+use realfft::{RealFftPlanner, RealToComplex};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Analysis bins are about this wide: narrower than CW signals are spaced in a busy contest,
+/// wide enough for 40 WPM keying.
+const BIN_HZ: f64 = 50.0;
+/// Frames overlap so the key is sampled every quarter window (about 5 ms).
+const HOPS_PER_WINDOW: usize = 4;
+/// Words kept per bin to find the callsign's context.
+const CONTEXT_WORDS: usize = 4;
+const MAX_WORD_LEN: usize = 16;
+/// A spot is moved to the strongest bin this close to where it was decoded.
+const PEAK_SEARCH_BINS: usize = 2;
+
+/// What a station was heard doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpotKind {
+    /// Calling CQ or TEST.
+    Cq,
+    /// Signing or repeating its callsign in a contact.
+    Dx,
+}
+
+impl SpotKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cq => "CQ",
+            Self::Dx => "DX",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Spot {
+    /// Audio frequency of the signal.
+    pub freq_hz: f64,
+    pub callsign: String,
+    pub wpm: u32,
+    pub snr_db: i32,
+    pub kind: SpotKind,
+    /// The words the callsign was decoded in.
+    pub text: String,
+}
+
+struct Bin {
+    decoder: Decoder,
+    word: String,
+    words: VecDeque<String>,
+}
+
+/// Decodes every CW signal between two audio frequencies at once, one [`Decoder`] per analysis
+/// bin, and spots the callsigns heard in them.
+pub struct Skimmer {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    hop: usize,
+    input: Vec<f32>,
+    frame: Vec<f32>,
+    spectrum: Vec<Complex32>,
+    scratch: Vec<Complex32>,
+    bin_hz: f64,
+    first_bin: usize,
+    bins: Vec<Bin>,
+    spots: VecDeque<Spot>,
+}
+
+impl Skimmer {
+    /// Covers `low_hz..high_hz` of audio sampled at `sample_rate`.
+    pub fn new(sample_rate: f64, low_hz: f64, high_hz: f64) -> Self {
+        let n = ((sample_rate / BIN_HZ) as usize)
+            .next_power_of_two()
+            .max(HOPS_PER_WINDOW);
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(n);
+        let bin_hz = sample_rate / n as f64;
+        let hop = n / HOPS_PER_WINDOW;
+        let frame_ms = hop as f64 * 1000.0 / sample_rate;
+        let first_bin = ((low_hz / bin_hz).ceil() as usize).max(1);
+        let last_bin = ((high_hz / bin_hz).floor() as usize).min(n / 2 - 1);
+        let bins = (first_bin..=last_bin)
+            .map(|_| Bin {
+                decoder: Decoder::new(frame_ms),
+                word: String::new(),
+                words: VecDeque::with_capacity(CONTEXT_WORDS + 1),
+            })
+            .collect();
+        Self {
+            window: crate::dsp::window::hann_window(n),
+            hop,
+            input: Vec::with_capacity(2 * n),
+            frame: vec![0.0; n],
+            spectrum: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            fft,
+            bin_hz,
+            first_bin,
+            bins,
+            spots: VecDeque::new(),
+        }
+    }
+
+    pub fn process(&mut self, audio: &[f32]) {
+        self.input.extend_from_slice(audio);
+        let n = self.window.len();
+        let mut start = 0;
+        while start + n <= self.input.len() {
+            for ((dst, &x), &w) in self
+                .frame
+                .iter_mut()
+                .zip(&self.input[start..start + n])
+                .zip(&self.window)
+            {
+                *dst = x * w;
+            }
+            let _ = self.fft.process_with_scratch(
+                &mut self.frame,
+                &mut self.spectrum,
+                &mut self.scratch,
+            );
+            for i in 0..self.bins.len() {
+                let power = self.spectrum[self.first_bin + i].norm_sqr();
+                if let Some(c) = self.bins[i].decoder.push(power) {
+                    self.on_char(i, c);
+                }
+            }
+            start += self.hop;
+        }
+        self.input.drain(..start);
+    }
+
+    pub fn pop_spot(&mut self) -> Option<Spot> {
+        self.spots.pop_front()
+    }
+
+    fn on_char(&mut self, i: usize, c: char) {
+        let bin = &mut self.bins[i];
+        if c != ' ' {
+            if bin.word.len() < MAX_WORD_LEN {
+                bin.word.push(c);
+            }
+            return;
+        }
+        let word = std::mem::take(&mut bin.word);
+        if word.is_empty() {
+            return;
+        }
+        let kind = if !is_callsign(&word) {
+            None
+        } else if bin.words.iter().any(|w| w == "CQ" || w == "TEST") {
+            Some(SpotKind::Cq)
+        } else if bin.words.iter().any(|w| *w == word || w == "DE") {
+            Some(SpotKind::Dx)
+        } else {
+            None
+        };
+        if bin.words.len() == CONTEXT_WORDS {
+            bin.words.pop_front();
+        }
+        bin.words.push_back(word.clone());
+        let Some(kind) = kind else {
+            return;
+        };
+        let text = bin.words.iter().cloned().collect::<Vec<_>>().join(" ");
+
+        // Neighbouring bins hear the same signal a little weaker.
+        let lo = i.saturating_sub(PEAK_SEARCH_BINS);
+        let hi = (i + PEAK_SEARCH_BINS).min(self.bins.len() - 1);
+        let peak = (lo..=hi)
+            .max_by(|&a, &b| {
+                let (a, b) = (&self.bins[a].decoder, &self.bins[b].decoder);
+                a.snr_db().total_cmp(&b.snr_db())
+            })
+            .unwrap_or(i);
+        let decoder = &self.bins[i].decoder;
+        self.spots.push_back(Spot {
+            freq_hz: (self.first_bin + peak) as f64 * self.bin_hz,
+            callsign: word,
+            wpm: decoder.wpm().unwrap_or_default().round() as u32,
+            snr_db: self.bins[peak].decoder.snr_db().round() as i32,
+            kind,
+            text,
+        });
+    }
+}
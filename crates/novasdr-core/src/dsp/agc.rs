@@ -11,6 +11,8 @@ pub struct Agc {
     gains: Vec<f32>,
     stage_root: f32,
     ring: Vec<f32>,
+    /// Delay line for the second channel of [`Self::process_stereo`].
+    ring_right: Vec<f32>,
     ring_pos: usize,
     filled: usize,
     max_queue: VecDeque<(usize, f32)>,
@@ -49,6 +51,7 @@ impl Agc {
             gains,
             stage_root,
             ring: vec![0.0; look_ahead_samples],
+            ring_right: vec![0.0; look_ahead_samples],
             ring_pos: 0,
             filled: 0,
             max_queue: VecDeque::new(),
@@ -73,6 +76,7 @@ impl Agc {
     pub fn reset(&mut self) {
         self.gains.fill(1.0);
         self.ring.fill(0.0);
+        self.ring_right.fill(0.0);
         self.ring_pos = 0;
         self.filled = 0;
         self.max_queue.clear();
@@ -82,36 +86,47 @@ impl Agc {
 
     pub fn process(&mut self, samples: &mut [f32]) {
         for s in samples.iter_mut() {
-            let input = *s;
-            let idx = self.sample_index;
-            self.sample_index = self.sample_index.wrapping_add(1);
+            *s = self.step(*s, 0.0).0;
+        }
+    }
+
+    /// Applies one gain to both channels, driven by the louder of the two, so the stereo image
+    /// does not shift with level.
+    pub fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            (*l, *r) = self.step(*l, *r);
+        }
+    }
+
+    fn step(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let idx = self.sample_index;
+        self.sample_index = self.sample_index.wrapping_add(1);
 
-            self.push_sample(idx, input);
+        self.push_sample(idx, left, right);
 
+        if self.filled < self.look_ahead_samples {
+            self.filled += 1;
             if self.filled < self.look_ahead_samples {
-                self.filled += 1;
-                if self.filled < self.look_ahead_samples {
-                    *s = 0.0;
-                    continue;
-                }
+                return (0.0, 0.0);
             }
+        }
 
-            let delayed = self.ring[self.ring_pos];
-            let peak = self.current_peak();
-            let desired_gain = ((self.desired_level / (peak + 1e-15)) * 100.0).min(self.max_gain);
-            self.apply_progressive_agc(desired_gain);
+        let delayed = (self.ring[self.ring_pos], self.ring_right[self.ring_pos]);
+        let peak = self.current_peak();
+        let desired_gain = ((self.desired_level / (peak + 1e-15)) * 100.0).min(self.max_gain);
+        self.apply_progressive_agc(desired_gain);
 
-            let mut total_gain = 1.0f32;
-            for g in self.gains.iter() {
-                total_gain *= *g;
-            }
-            total_gain = total_gain.min(self.max_gain);
-            *s = delayed * (total_gain * 0.01);
+        let mut total_gain = 1.0f32;
+        for g in self.gains.iter() {
+            total_gain *= *g;
         }
+        total_gain = total_gain.min(self.max_gain);
+        let gain = total_gain * 0.01;
+        (delayed.0 * gain, delayed.1 * gain)
     }
 
-    fn push_sample(&mut self, idx: usize, sample: f32) {
-        let abs = sample.abs();
+    fn push_sample(&mut self, idx: usize, left: f32, right: f32) {
+        let abs = left.abs().max(right.abs());
 
         while let Some((_, back_abs)) = self.max_queue.back().copied() {
             if back_abs < abs {
@@ -131,7 +146,8 @@ impl Agc {
             }
         }
 
-        self.ring[self.ring_pos] = sample;
+        self.ring[self.ring_pos] = left;
+        self.ring_right[self.ring_pos] = right;
         self.ring_pos += 1;
         if self.ring_pos >= self.ring.len() {
             self.ring_pos = 0;
@@ -0,0 +1,135 @@
+use super::iq_extract::IqExtractor;
+use num_complex::Complex32;
+use std::collections::VecDeque;
+
+/// Frames of baseband a [`ChannelDdc`] keeps, so listeners a few frames behind the one that
+/// computed them still find theirs.
+const KEPT_FRAMES: usize = 4;
+
+/// Fixed grid of overlapping channels over the receiver's spectrum, each `size` bins wide (the
+/// audio FFT size) and a quarter of that apart.
+///
+/// Together with [`ChannelDdc`] and [`ChannelTuner`] this is a fast-convolution filter bank: the
+/// decimating DDC of a channel (inverse FFT of its bins and overlap-add at the audio rate) runs
+/// once per frame however many listeners are tuned inside it, and each listener only shifts
+/// that baseband to its own bin and band-limits it with the passband filter it runs anyway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelGrid {
+    size: usize,
+}
+
+impl ChannelGrid {
+    /// `size` must be a multiple of 4.
+    pub fn new(size: usize) -> Self {
+        assert!(
+            size >= 4 && size.is_multiple_of(4),
+            "channel size must be a multiple of 4"
+        );
+        Self { size }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Centre bin of the channel that holds bins `l..r` tuned from bin `mid`, with `guard` bins
+    /// to spare on either side for the passband filter's skirts. `None` when no channel does:
+    /// the window is too wide, or reaches further from `mid` than the audio rate represents.
+    pub fn centre_for(&self, l: i64, r: i64, mid: i64, guard: i64) -> Option<i64> {
+        let half = (self.size / 2) as i64;
+        let stride = (self.size / 4) as i64;
+        let centre = ((l + r) / 2 + stride / 2).div_euclid(stride) * stride;
+        // Content from beyond the channel's far edge aliases onto the near side of `mid` after
+        // the shift, so the window has to stay clear of both the channel's and `mid`'s edges.
+        let fits = |c: i64| l - guard >= c - half && r + guard <= c + half;
+        (r > l && fits(centre) && fits(mid)).then_some(centre)
+    }
+}
+
+/// The DDC of one channel of a [`ChannelGrid`], shared by the listeners tuned inside it.
+pub struct ChannelDdc {
+    centre: i64,
+    extractor: IqExtractor,
+    /// Baseband of the latest frames, oldest first.
+    frames: VecDeque<(u64, Vec<Complex32>)>,
+}
+
+impl ChannelDdc {
+    pub fn new(grid: ChannelGrid, centre: i64) -> Self {
+        Self {
+            centre,
+            // A scale of one keeps the raw bin amplitude the audio pipeline works in.
+            extractor: IqExtractor::new(grid.size, 1),
+            frames: VecDeque::with_capacity(KEPT_FRAMES),
+        }
+    }
+
+    pub fn centre(&self) -> i64 {
+        self.centre
+    }
+
+    /// Baseband of `frame_num` around the channel centre, `size / 2` samples, computed from
+    /// `bins` (the rotated spectrum) unless a listener already did. `None` when the frame is
+    /// older than any kept; the caller then demodulates from the bins on its own.
+    pub fn baseband(
+        &mut self,
+        bins: &[Complex32],
+        is_real_input: bool,
+        frame_num: u64,
+    ) -> Option<&[Complex32]> {
+        let latest = self.frames.back().map(|(f, _)| *f);
+        if latest.is_none_or(|latest| frame_num > latest) {
+            let mut out = if self.frames.len() == KEPT_FRAMES {
+                self.frames
+                    .pop_front()
+                    .map(|(_, buf)| buf)
+                    .unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            out.clear();
+            self.extractor
+                .process(bins, self.centre, is_real_input, frame_num, &mut out);
+            self.frames.push_back((frame_num, out));
+        }
+        self.frames
+            .iter()
+            .find(|(f, _)| *f == frame_num)
+            .map(|(_, buf)| buf.as_slice())
+    }
+}
+
+/// Moves a channel's baseband from the channel centre to a listener's bin.
+pub struct ChannelTuner {
+    /// One turn of the oscillator, `size` steps.
+    nco: Vec<Complex32>,
+}
+
+impl ChannelTuner {
+    pub fn new(size: usize) -> Self {
+        let step = -std::f64::consts::TAU / size as f64;
+        let nco = (0..size)
+            .map(|k| {
+                let (sin, cos) = (step * k as f64).sin_cos();
+                Complex32::new(cos as f32, sin as f32)
+            })
+            .collect();
+        Self { nco }
+    }
+
+    /// Writes frame `frame_num` of `channel` shifted down by `offset` bins (listener bin minus
+    /// channel centre) into `out`. This is the baseband the audio path's own inverse FFT would
+    /// give for the listener's bin, before its bin window: shifting by a whole bin turns the
+    /// half-frame hop into a `(-1)^offset` step, taken on odd frames like the DDC's own.
+    pub fn tune(&self, channel: &[Complex32], offset: i64, frame_num: u64, out: &mut [Complex32]) {
+        let size = self.nco.len() as i64;
+        let step = offset.rem_euclid(size) as usize;
+        let flip = frame_num % 2 == 1 && offset % 2 != 0;
+        let mut k = 0usize;
+        for (o, &x) in out.iter_mut().zip(channel) {
+            let y = x * self.nco[k];
+            *o = if flip { -y } else { y };
+            k = (k + step) % self.nco.len();
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use crate::dsp::window::hann_window;
 use anyhow::Context;
 use num_complex::Complex32;
 use opencl3::{
@@ -162,11 +161,13 @@ pub struct ClfftComplexFft {
     window_complex: Kernel,
     window_buf: Buffer<f32>,
     waterfall: WaterfallGpuQuantizer,
+    device_name: String,
 }
 
 impl ClfftComplexFft {
-    pub fn new(n: usize) -> anyhow::Result<Self> {
+    pub fn new(n: usize, window: &[f32]) -> anyhow::Result<Self> {
         ensure_setup()?;
+        anyhow::ensure!(window.len() == n, "window length mismatch");
 
         let (platform_idx, device_idx) = select_indices_from_env()?;
         let (platform, device_id) = select_platform_device(platform_idx, device_idx)?;
@@ -192,10 +193,9 @@ impl ClfftComplexFft {
         let mut window_buf =
             unsafe { Buffer::<f32>::create(&ctx, CL_MEM_READ_WRITE, n, std::ptr::null_mut()) }
                 .context("create OpenCL window buffer")?;
-        let window = hann_window(n);
         unsafe {
             queue
-                .enqueue_write_buffer(&mut window_buf, CL_BLOCKING, 0, &window, &[])
+                .enqueue_write_buffer(&mut window_buf, CL_BLOCKING, 0, window, &[])
                 .context("OpenCL write window")?;
         }
 
@@ -260,6 +260,7 @@ impl ClfftComplexFft {
             window_complex,
             window_buf,
             waterfall,
+            device_name,
         })
     }
 
@@ -357,6 +358,10 @@ impl ClfftComplexFft {
         Ok(max_p)
     }
 
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
     pub fn process_inplace(&mut self, data: &mut [Complex32]) -> anyhow::Result<()> {
         anyhow::ensure!(data.len() == self.n, "clFFT input length mismatch");
 
@@ -615,6 +620,96 @@ impl WaterfallGpuQuantizer {
     }
 }
 
+/// Standalone OpenCL waterfall quantizer for the waterfall worker thread.
+///
+/// Takes a spectrum that was already computed (on the CPU or by a clFFT plan on another queue),
+/// uploads it and runs the same power/quantize/downsample kernels as the in-engine clFFT path.
+pub struct ClfftWaterfallQuantizer {
+    _ctx: ClContext,
+    queue: CommandQueue,
+    spectrum_buf: Buffer<f32>,
+    waterfall: WaterfallGpuQuantizer,
+    fft_size: usize,
+}
+
+impl ClfftWaterfallQuantizer {
+    pub fn new(fft_size: usize) -> anyhow::Result<Self> {
+        anyhow::ensure!(fft_size >= 8, "fft_size too small");
+
+        let (platform_idx, device_idx) = select_indices_from_env()?;
+        let (_platform, device_id) = select_platform_device(platform_idx, device_idx)?;
+        let device = Device::new(device_id);
+
+        let device_name = device.name().unwrap_or_else(|_| "<unknown>".to_string());
+        tracing::info!(opencl_device = %device_name, fft_size, "OpenCL waterfall quantizer enabled");
+
+        let ctx = ClContext::from_device(&device).context("create OpenCL context")?;
+        let queue = unsafe { CommandQueue::create(&ctx, device_id, 0) }
+            .context("create OpenCL command queue")?;
+
+        let spectrum_buf = unsafe {
+            Buffer::<f32>::create(&ctx, CL_MEM_READ_WRITE, fft_size * 2, std::ptr::null_mut())
+        }
+        .context("create OpenCL spectrum buffer")?;
+
+        let waterfall =
+            WaterfallGpuQuantizer::new(&ctx, fft_size).context("init waterfall kernels")?;
+
+        Ok(Self {
+            _ctx: ctx,
+            queue,
+            spectrum_buf,
+            waterfall,
+            fft_size,
+        })
+    }
+
+    pub fn quantize_and_downsample(
+        &mut self,
+        spectrum: &[Complex32],
+        base_idx: usize,
+        downsample_levels: usize,
+        size_log2: i32,
+        normalize: f32,
+    ) -> anyhow::Result<(Vec<i8>, Vec<usize>)> {
+        anyhow::ensure!(spectrum.len() == self.fft_size, "spectrum length mismatch");
+        anyhow::ensure!(downsample_levels >= 1, "downsample_levels must be >= 1");
+        anyhow::ensure!(
+            base_idx < self.fft_size,
+            "clFFT base_idx out of range (base_idx={base_idx}, fft_size={})",
+            self.fft_size
+        );
+        anyhow::ensure!(
+            normalize.is_finite() && normalize > 0.0,
+            "invalid normalize value"
+        );
+
+        unsafe {
+            self.queue
+                .enqueue_write_buffer(
+                    &mut self.spectrum_buf,
+                    CL_BLOCKING,
+                    0,
+                    complex_as_f32_slice(spectrum),
+                    &[],
+                )
+                .context("OpenCL write spectrum")?;
+        }
+
+        self.waterfall.quantize_and_downsample_complexbuf(
+            &self.queue,
+            &self.spectrum_buf,
+            WaterfallQuantizeArgs {
+                outbuf_len: self.fft_size,
+                base_idx,
+                levels: downsample_levels,
+                size_log2,
+                normalize,
+            },
+        )
+    }
+}
+
 pub struct ClfftRealFft {
     n: usize,
     _ctx: ClContext,
@@ -627,6 +722,7 @@ pub struct ClfftRealFft {
     out_buf: Buffer<f32>,
     waterfall: WaterfallGpuQuantizer,
     plan: ffi::clfftPlanHandle,
+    device_name: String,
 }
 
 impl ClfftRealFft {
@@ -750,9 +846,14 @@ impl ClfftRealFft {
             out_buf,
             waterfall,
             plan,
+            device_name,
         })
     }
 
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
     pub fn load_real_input(&mut self, half_a: &[f32], half_b: &[f32]) -> anyhow::Result<()> {
         let half_len = self.n / 2;
         anyhow::ensure!(
@@ -0,0 +1,87 @@
+use crate::config::{DcSuppression, DcSuppressionMode};
+use num_complex::Complex32;
+
+/// Replaces bins `-k..=k` (`k = cfg.bins`) of a complex FFT output in natural order (DC at index
+/// 0, negative frequencies at the end). Interpolation ramps the magnitude between the nearest
+/// untouched bins on either side and keeps each bin's phase, so the patch blends into the noise.
+pub fn suppress_dc(spectrum: &mut [Complex32], cfg: DcSuppression) {
+    let n = spectrum.len();
+    let k = cfg.bins;
+    if cfg.mode == DcSuppressionMode::Off || n < 2 * k + 3 {
+        return;
+    }
+    let idx = |j: isize| (j + n as isize) as usize % n;
+    let k = k as isize;
+    match cfg.mode {
+        DcSuppressionMode::Off => {}
+        DcSuppressionMode::Blank => {
+            for j in -k..=k {
+                spectrum[idx(j)] = Complex32::new(0.0, 0.0);
+            }
+        }
+        DcSuppressionMode::Interpolate => {
+            let lo = spectrum[idx(-k - 1)];
+            let hi = spectrum[idx(k + 1)];
+            let (lo_mag, hi_mag) = (lo.norm(), hi.norm());
+            let span = (2 * k + 2) as f32;
+            for j in -k..=k {
+                let t = (j + k + 1) as f32 / span;
+                let mag = lo_mag + t * (hi_mag - lo_mag);
+                let bin = &mut spectrum[idx(j)];
+                let phase = if bin.norm_sqr() > 0.0 {
+                    bin.arg()
+                } else {
+                    lo.arg()
+                };
+                *bin = Complex32::from_polar(mag, phase);
+            }
+        }
+    }
+}
+
+/// Applies [`suppress_dc`] to a waterfall that was already quantized (GPU FFT paths quantize on
+/// the device). `base_idx` is the spectrum index of the first level-0 column, as passed to the
+/// quantizer. Columns wholly inside the suppressed range are patched; columns that straddle its
+/// edge keep their value, which matches what the CPU quantizer would produce.
+pub fn suppress_dc_quantized(
+    quantized: &mut [i8],
+    offsets: &[usize],
+    fft_size: usize,
+    base_idx: usize,
+    cfg: DcSuppression,
+) {
+    let k = cfg.bins;
+    if cfg.mode == DcSuppressionMode::Off || fft_size < 2 * k + 3 {
+        return;
+    }
+    let dc_col = (fft_size - base_idx % fft_size) % fft_size;
+    if dc_col < k + 1 || dc_col + k + 1 >= fft_size {
+        return;
+    }
+    // Level-0 columns `first..=last` hold the suppressed bins.
+    let (first, last) = (dc_col - k, dc_col + k);
+    for (level, &offset) in offsets.iter().enumerate() {
+        let len = fft_size >> level;
+        if offset + len > quantized.len() {
+            return;
+        }
+        let row = &mut quantized[offset..offset + len];
+        let start = first.div_ceil(1 << level);
+        let end = (last + 1) >> level;
+        if start >= end || start == 0 || end >= len {
+            continue;
+        }
+        match cfg.mode {
+            DcSuppressionMode::Off => {}
+            DcSuppressionMode::Blank => row[start..end].fill(i8::MIN),
+            DcSuppressionMode::Interpolate => {
+                let (lo, hi) = (row[start - 1] as f32, row[end] as f32);
+                let span = (end - start + 1) as f32;
+                for (i, v) in row[start..end].iter_mut().enumerate() {
+                    let t = (i + 1) as f32 / span;
+                    *v = (lo + t * (hi - lo)).round() as i8;
+                }
+            }
+        }
+    }
+}
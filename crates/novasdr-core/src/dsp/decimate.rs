@@ -0,0 +1,111 @@
+use super::fir::{lowpass_taps, FirDecimator};
+use num_complex::Complex32;
+use std::ops::{Add, Mul};
+
+/// Largest `receiver.input.decimation` accepted.
+pub const MAX_DECIMATION: usize = 64;
+/// Transition width of the last halfband stage, as a fraction of its input rate. It sets how
+/// much of the band's edges rolls off: 5% of the decimated bandwidth on each side.
+const FINAL_TRANSITION: f32 = 0.05;
+
+/// Halfband cascade that divides the input rate by a power of two before the main FFT.
+///
+/// Each stage low-passes at a quarter of its rate and keeps every other sample. Only the last
+/// stage needs a sharp edge; earlier ones only have to keep what would alias into the final band
+/// out, so they get away with a handful of taps. The centre frequency is unchanged: IQ input
+/// keeps `centre ± rate / 2d`, real input keeps `0..rate / 2d`.
+pub struct PreDecimator {
+    factor: usize,
+    stages: Stages,
+}
+
+enum Stages {
+    Real(Vec<Stage<f32>>),
+    Complex {
+        stages: Vec<Stage<Complex32>>,
+        input: Vec<Complex32>,
+    },
+}
+
+struct Stage<T> {
+    fir: FirDecimator<T>,
+    out: Vec<T>,
+}
+
+impl PreDecimator {
+    /// `factor` must be a power of two in `2..=MAX_DECIMATION`.
+    pub fn new(factor: usize, is_real: bool) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            factor.is_power_of_two() && (2..=MAX_DECIMATION).contains(&factor),
+            "decimation must be a power of two in 2..={MAX_DECIMATION}"
+        );
+        let stages = if is_real {
+            Stages::Real(build_stages(factor))
+        } else {
+            Stages::Complex {
+                stages: build_stages(factor),
+                input: Vec::new(),
+            }
+        };
+        Ok(Self { factor, stages })
+    }
+
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Decimates `input` (interleaved for IQ) into `out`, which must be `factor` times shorter.
+    /// Filter state carries over between calls, so consecutive buffers form one stream.
+    pub fn process(&mut self, input: &[f32], out: &mut [f32]) {
+        debug_assert_eq!(input.len(), out.len() * self.factor);
+        match &mut self.stages {
+            Stages::Real(stages) => {
+                let decimated = cascade(stages, input);
+                out.copy_from_slice(decimated);
+            }
+            Stages::Complex { stages, input: buf } => {
+                buf.clear();
+                buf.extend(input.chunks_exact(2).map(|iq| Complex32::new(iq[0], iq[1])));
+                let decimated = cascade(stages, buf);
+                for (dst, c) in out.chunks_exact_mut(2).zip(decimated) {
+                    dst[0] = c.re;
+                    dst[1] = c.im;
+                }
+            }
+        }
+    }
+}
+
+fn build_stages<T>(factor: usize) -> Vec<Stage<T>>
+where
+    T: Copy + Default + Add<Output = T> + Mul<f32, Output = T>,
+{
+    let count = factor.trailing_zeros() as usize;
+    (0..count)
+        .map(|i| {
+            // Stage i runs at `2^(count - i)` times the final rate; everything past
+            // `rate / 2 - final_rate` would fold into the final band.
+            let transition = if i + 1 == count {
+                FINAL_TRANSITION
+            } else {
+                0.5 - 1.0 / (1usize << (count - i)) as f32
+            };
+            Stage {
+                fir: FirDecimator::new(lowpass_taps(0.25, transition), 2),
+                out: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+fn cascade<'a, T>(stages: &'a mut [Stage<T>], input: &'a [T]) -> &'a [T]
+where
+    T: Copy + Default + Add<Output = T> + Mul<f32, Output = T>,
+{
+    let Some((first, rest)) = stages.split_first_mut() else {
+        return input;
+    };
+    first.out.clear();
+    first.fir.process(input, &mut first.out);
+    cascade(rest, &first.out)
+}
@@ -1,12 +1,15 @@
+use super::simd;
 use num_complex::Complex32;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DemodulationMode {
     Usb,
     Lsb,
     Am,
     Sam,
     Fm,
+    /// Broadcast FM: full multiplex demodulation with de-emphasis and optional RDS.
+    Wbfm,
 }
 
 impl DemodulationMode {
@@ -16,10 +19,22 @@ impl DemodulationMode {
             "LSB" => Some(Self::Lsb),
             "AM" => Some(Self::Am),
             "SAM" => Some(Self::Sam),
-            "FM" | "FMC" | "NFM" | "NBFM" | "WBFM" => Some(Self::Fm),
+            "FM" | "FMC" | "NFM" | "NBFM" => Some(Self::Fm),
+            "WBFM" => Some(Self::Wbfm),
             _ => None,
         }
     }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Usb => "USB",
+            Self::Lsb => "LSB",
+            Self::Am => "AM",
+            Self::Sam => "SAM",
+            Self::Fm => "FM",
+            Self::Wbfm => "WBFM",
+        }
+    }
 }
 
 pub fn negate_f32(arr: &mut [f32]) {
@@ -47,9 +62,7 @@ pub fn add_complex(a: &mut [Complex32], b: &[Complex32]) {
 }
 
 pub fn am_envelope(iq: &[Complex32], out: &mut [f32]) {
-    for (dst, v) in out.iter_mut().zip(iq.iter()) {
-        *dst = (v.re * v.re + v.im * v.im).sqrt();
-    }
+    simd::am_envelope(simd::detected(), iq, out);
 }
 
 pub fn sam_demod(iq: &[Complex32], carrier: &[Complex32], out: &mut [f32]) {
@@ -61,20 +74,12 @@ pub fn sam_demod(iq: &[Complex32], carrier: &[Complex32], out: &mut [f32]) {
     }
 }
 
-pub fn polar_discriminator_fm(iq: &[Complex32], mut prev: Complex32, out: &mut [f32]) -> Complex32 {
-    for (dst, v) in out.iter_mut().zip(iq.iter()) {
-        let d = *v * prev.conj();
-        *dst = d.arg();
-        prev = *v;
-    }
-    prev
+pub fn polar_discriminator_fm(iq: &[Complex32], prev: Complex32, out: &mut [f32]) -> Complex32 {
+    simd::polar_discriminator_fm(simd::detected(), iq, prev, out)
 }
 
 pub fn float_to_i16_centered(samples: &[f32], out: &mut [i16], mult: f32) {
-    for (dst, s) in out.iter_mut().zip(samples.iter()) {
-        let v = (s * mult + 32768.5).floor() as i32 - 32768;
-        *dst = v.clamp(-32768, 32767) as i16;
-    }
+    simd::float_to_i16_centered(simd::detected(), samples, out, mult);
 }
 
 pub fn float_to_i8_centered(samples: &[f32], out: &mut [i8], mult: f32) {
@@ -97,10 +102,10 @@ mod tests {
     }
 
     #[test]
-    fn demodulation_mode_accepts_wbfm_alias() {
+    fn demodulation_mode_parses_fm_variants() {
         assert_eq!(
             DemodulationMode::from_str_upper("WBFM"),
-            Some(DemodulationMode::Fm)
+            Some(DemodulationMode::Wbfm)
         );
         assert_eq!(
             DemodulationMode::from_str_upper("NFM"),
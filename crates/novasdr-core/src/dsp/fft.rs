@@ -1,5 +1,6 @@
-use crate::config::Accelerator;
-use crate::dsp::window::hann_window;
+use crate::config::{Accelerator, DcSuppression, FftWindow, VulkanDevice};
+use crate::dsp::dc_suppression::suppress_dc;
+use crate::dsp::window::fft_window;
 use anyhow::Context;
 use num_complex::Complex32;
 use realfft::{RealFftPlanner, RealToComplex};
@@ -16,6 +17,11 @@ pub struct FftSettings {
     pub downsample_levels: usize,
     pub audio_max_fft_size: usize,
     pub accelerator: Accelerator,
+    /// Device for the vkfft backend; `None` uses `NOVASDR_VULKAN_DEVICE` or the best device.
+    pub vulkan_device: Option<VulkanDevice>,
+    /// Applied to complex input only; real input has no LO spike in band.
+    pub dc_suppression: DcSuppression,
+    pub window: FftWindow,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +33,8 @@ pub struct FftResult {
 
 pub struct FftEngine {
     settings: FftSettings,
+    /// Backend that initialised; may differ from `settings.accelerator` after a fallback.
+    backend: Accelerator,
     window: Vec<f32>,
     complex_fft: ComplexFft,
     real_fft: Arc<dyn RealToComplex<f32>>,
@@ -65,6 +73,91 @@ impl ComplexFft {
     }
 }
 
+/// FFT backends tried for `accelerator`, best first; those not built into the binary are
+/// skipped. The CPU always comes last, so a GPU that fails to initialise (no Vulkan loader, no
+/// OpenCL device) degrades instead of stopping the receiver.
+fn backend_candidates(accelerator: Accelerator) -> &'static [Accelerator] {
+    match accelerator {
+        Accelerator::Auto | Accelerator::Vkfft => {
+            &[Accelerator::Vkfft, Accelerator::Clfft, Accelerator::None]
+        }
+        Accelerator::Clfft => &[Accelerator::Clfft, Accelerator::None],
+        Accelerator::None | Accelerator::Unsupported => &[Accelerator::None],
+    }
+}
+
+/// FFT state built for one backend.
+struct Backend {
+    complex: ComplexFft,
+    #[cfg(feature = "clfft")]
+    clfft_real: Option<crate::dsp::clfft::ClfftRealFft>,
+}
+
+impl Backend {
+    fn cpu(fft_size: usize) -> Self {
+        let mut complex_planner = FftPlanner::<f32>::new();
+        Self {
+            complex: ComplexFft::Cpu(complex_planner.plan_fft_forward(fft_size)),
+            #[cfg(feature = "clfft")]
+            clfft_real: None,
+        }
+    }
+
+    #[cfg_attr(
+        not(any(feature = "clfft", feature = "vkfft")),
+        allow(unused_variables)
+    )]
+    fn init(backend: Accelerator, settings: &FftSettings, window: &[f32]) -> anyhow::Result<Self> {
+        let fft_size = settings.fft_size;
+        match backend {
+            Accelerator::Vkfft => {
+                #[cfg(feature = "vkfft")]
+                {
+                    anyhow::ensure!(!settings.is_real, "vkfft does not support real input");
+                    Ok(Self {
+                        complex: ComplexFft::Vkfft(crate::dsp::vkfft::VkfftComplexFft::new(
+                            fft_size,
+                            settings.vulkan_device.as_ref(),
+                            window,
+                        )?),
+                        #[cfg(feature = "clfft")]
+                        clfft_real: None,
+                    })
+                }
+                #[cfg(not(feature = "vkfft"))]
+                {
+                    anyhow::bail!("not built with --features vkfft");
+                }
+            }
+            Accelerator::Clfft => {
+                #[cfg(feature = "clfft")]
+                {
+                    if settings.is_real {
+                        let mut cpu = Self::cpu(fft_size);
+                        cpu.clfft_real =
+                            Some(crate::dsp::clfft::ClfftRealFft::new(fft_size, window)?);
+                        Ok(cpu)
+                    } else {
+                        Ok(Self {
+                            complex: ComplexFft::Clfft(crate::dsp::clfft::ClfftComplexFft::new(
+                                fft_size, window,
+                            )?),
+                            clfft_real: None,
+                        })
+                    }
+                }
+                #[cfg(not(feature = "clfft"))]
+                {
+                    anyhow::bail!("not built with --features clfft");
+                }
+            }
+            Accelerator::None | Accelerator::Auto | Accelerator::Unsupported => {
+                Ok(Self::cpu(fft_size))
+            }
+        }
+    }
+}
+
 impl FftEngine {
     pub fn new(settings: FftSettings) -> anyhow::Result<Self> {
         // FFTW/clFFT pipelines allow mixed-radix FFT sizes. RustFFT/RealFFT planners will reject
@@ -76,54 +169,50 @@ impl FftEngine {
         );
 
         let fft_size = settings.fft_size;
-        let window = hann_window(fft_size);
+        let window = fft_window(settings.window, fft_size);
 
-        let complex_fft = match settings.accelerator {
-            Accelerator::None | Accelerator::Unsupported => {
-                let mut complex_planner = FftPlanner::<f32>::new();
-                ComplexFft::Cpu(complex_planner.plan_fft_forward(fft_size))
+        match settings.accelerator {
+            Accelerator::Clfft if !cfg!(feature = "clfft") => {
+                anyhow::bail!("accelerator = \"clfft\" requires building with --features clfft");
             }
-            Accelerator::Clfft => {
-                if settings.is_real {
-                    let mut complex_planner = FftPlanner::<f32>::new();
-                    ComplexFft::Cpu(complex_planner.plan_fft_forward(fft_size))
-                } else {
-                    #[cfg(feature = "clfft")]
-                    {
-                        ComplexFft::Clfft(crate::dsp::clfft::ClfftComplexFft::new(fft_size)?)
-                    }
-                    #[cfg(not(feature = "clfft"))]
-                    {
-                        anyhow::bail!(
-                            "accelerator = \"clfft\" requires building with --features clfft"
-                        );
-                    }
-                }
+            Accelerator::Vkfft if !cfg!(feature = "vkfft") => {
+                anyhow::bail!("accelerator = \"vkfft\" requires building with --features vkfft");
             }
-            Accelerator::Vkfft => {
-                if settings.is_real {
-                    static WARNED: std::sync::Once = std::sync::Once::new();
-                    WARNED.call_once(|| {
-                        tracing::warn!(
-                            "vkfft accelerator is not used for real input; falling back to CPU"
-                        );
-                    });
-                    let mut complex_planner = FftPlanner::<f32>::new();
-                    ComplexFft::Cpu(complex_planner.plan_fft_forward(fft_size))
-                } else {
-                    #[cfg(feature = "vkfft")]
-                    {
-                        ComplexFft::Vkfft(crate::dsp::vkfft::VkfftComplexFft::new(fft_size)?)
-                    }
-                    #[cfg(not(feature = "vkfft"))]
-                    {
-                        anyhow::bail!(
-                            "accelerator = \"vkfft\" requires building with --features vkfft"
-                        );
-                    }
+            _ => {}
+        }
+
+        let mut selected = None;
+        let built = |b: &&Accelerator| {
+            (**b != Accelerator::Vkfft || cfg!(feature = "vkfft"))
+                && (**b != Accelerator::Clfft || cfg!(feature = "clfft"))
+        };
+        for &candidate in backend_candidates(settings.accelerator)
+            .iter()
+            .filter(built)
+        {
+            match Backend::init(candidate, &settings, &window) {
+                Ok(b) => {
+                    selected = Some((candidate, b));
+                    break;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        accelerator = candidate.as_str(),
+                        fft_size,
+                        error = %e,
+                        "FFT backend unavailable; trying the next one"
+                    );
                 }
             }
+        }
+        let Some((backend, selected)) = selected else {
+            anyhow::bail!("no FFT backend could be initialised");
         };
+        let Backend {
+            complex: complex_fft,
+            #[cfg(feature = "clfft")]
+            clfft_real,
+        } = selected;
 
         let mut real_planner = RealFftPlanner::<f32>::new();
         let real_fft = real_planner.plan_fft_forward(fft_size);
@@ -132,19 +221,9 @@ impl FftEngine {
         let real_spectrum_full = real_fft.make_output_vec();
         let real_frame = vec![0.0f32; fft_size];
 
-        #[cfg(feature = "clfft")]
-        let clfft_real = if settings.accelerator == Accelerator::Clfft && settings.is_real {
-            Some(crate::dsp::clfft::ClfftRealFft::new(fft_size, &window)?)
-        } else {
-            None
-        };
-        #[cfg(not(feature = "clfft"))]
-        if settings.accelerator == Accelerator::Clfft && settings.is_real {
-            anyhow::bail!("accelerator = \"clfft\" requires building with --features clfft");
-        }
-
         Ok(Self {
             settings,
+            backend,
             window,
             complex_fft,
             real_fft,
@@ -161,6 +240,26 @@ impl FftEngine {
         })
     }
 
+    /// Backend actually in use: the configured accelerator, or what it fell back to.
+    pub fn backend(&self) -> Accelerator {
+        self.backend
+    }
+
+    /// Name of the GPU the backend runs on; `None` on the CPU.
+    pub fn device_name(&self) -> Option<&str> {
+        #[cfg(feature = "clfft")]
+        if let Some(clfft) = self.clfft_real.as_ref() {
+            return Some(clfft.device_name());
+        }
+        match &self.complex_fft {
+            ComplexFft::Cpu(_) => None,
+            #[cfg(feature = "clfft")]
+            ComplexFft::Clfft(fft) => Some(fft.device_name()),
+            #[cfg(feature = "vkfft")]
+            ComplexFft::Vkfft(fft) => Some(fft.device_name()),
+        }
+    }
+
     pub fn load_real_half_a(&mut self, half: &[f32]) {
         debug_assert_eq!(half.len(), self.settings.fft_size / 2);
         self.real_half_a.copy_from_slice(half);
@@ -314,7 +413,7 @@ impl FftEngine {
                     fft.window_and_process_inplace(&self.complex_frame)?;
 
                     let (quantized_concat, quantized_level_offsets) = if include_waterfall {
-                        let (q, o) = fft.quantize_and_downsample(
+                        let (mut q, o) = fft.quantize_and_downsample(
                             base_idx,
                             self.settings.downsample_levels,
                             size_log2,
@@ -326,12 +425,20 @@ impl FftEngine {
                             anyhow::bail!("clFFT produced invalid spectrum (max_power={max_p})");
                         }
 
+                        crate::dsp::dc_suppression::suppress_dc_quantized(
+                            &mut q,
+                            &o,
+                            n,
+                            base_idx,
+                            self.settings.dc_suppression,
+                        );
                         (Some(q.into()), Some(o.into()))
                     } else {
                         (None, None)
                     };
 
                     fft.read_fft_output(&mut self.complex_frame)?;
+                    suppress_dc(&mut self.complex_frame, self.settings.dc_suppression);
                     Ok(FftResult {
                         normalize,
                         quantized_concat,
@@ -365,7 +472,7 @@ impl FftEngine {
                     fft.window_and_process_inplace(&self.complex_frame)?;
 
                     let (quantized_concat, quantized_level_offsets) = if include_waterfall {
-                        let (q, o) = fft.quantize_and_downsample(
+                        let (mut q, o) = fft.quantize_and_downsample(
                             base_idx,
                             self.settings.downsample_levels,
                             size_log2,
@@ -377,12 +484,20 @@ impl FftEngine {
                             anyhow::bail!("VkFFT produced invalid spectrum (max_power={max_p})");
                         }
 
+                        crate::dsp::dc_suppression::suppress_dc_quantized(
+                            &mut q,
+                            &o,
+                            n,
+                            base_idx,
+                            self.settings.dc_suppression,
+                        );
                         (Some(q.into()), Some(o.into()))
                     } else {
                         (None, None)
                     };
 
                     fft.read_fft_output(&mut self.complex_frame)?;
+                    suppress_dc(&mut self.complex_frame, self.settings.dc_suppression);
                     Ok(FftResult {
                         normalize,
                         quantized_concat,
@@ -412,6 +527,7 @@ impl FftEngine {
             self.complex_frame[i + half] = self.complex_half_b[i] * self.window[i + half];
         }
         self.complex_fft.process(&mut self.complex_frame)?;
+        suppress_dc(&mut self.complex_frame, self.settings.dc_suppression);
 
         let (quantized_concat, offsets) = if include_waterfall {
             let (q, o) = quantize_and_downsample_cpu(
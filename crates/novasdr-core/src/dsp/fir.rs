@@ -0,0 +1,146 @@
+use std::ops::{Add, Mul};
+
+/// Hamming-window length factor: taps ≈ 3.3 / transition width (as a fraction of the rate).
+const HAMMING_LENGTH_FACTOR: f32 = 3.3;
+
+/// Normalised sinc, `sin(πx) / (πx)`.
+pub fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        let px = std::f32::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// Hamming-windowed sinc low-pass with unity DC gain. `cutoff` and `transition` are fractions of
+/// the sample rate; the tap count is odd so the filter has an integer group delay.
+pub fn lowpass_taps(cutoff: f32, transition: f32) -> Vec<f32> {
+    let n = ((HAMMING_LENGTH_FACTOR / transition.max(1e-4)).ceil() as usize) | 1;
+    let mid = (n - 1) as f32 / 2.0;
+    let mut taps: Vec<f32> = (0..n)
+        .map(|i| {
+            let t = i as f32 - mid;
+            let w = 0.54 - 0.46 * (std::f32::consts::TAU * i as f32 / (n - 1).max(1) as f32).cos();
+            2.0 * cutoff * sinc(2.0 * cutoff * t) * w
+        })
+        .collect();
+    let sum: f32 = taps.iter().sum();
+    if sum.abs() > f32::EPSILON {
+        for t in taps.iter_mut() {
+            *t /= sum;
+        }
+    }
+    taps
+}
+
+/// FIR filter that only computes every `decimation`-th output.
+pub struct FirDecimator<T> {
+    taps: Vec<f32>,
+    /// Input history stored twice so the newest `taps.len()` samples are always contiguous.
+    history: Vec<T>,
+    pos: usize,
+    decimation: usize,
+    phase: usize,
+}
+
+impl<T> FirDecimator<T>
+where
+    T: Copy + Default + Add<Output = T> + Mul<f32, Output = T>,
+{
+    pub fn new(taps: Vec<f32>, decimation: usize) -> Self {
+        let n = taps.len().max(1);
+        Self {
+            taps,
+            history: vec![T::default(); 2 * n],
+            pos: 0,
+            decimation: decimation.max(1),
+            phase: 0,
+        }
+    }
+
+    pub fn process(&mut self, input: &[T], out: &mut Vec<T>) {
+        let n = self.history.len() / 2;
+        for &x in input {
+            self.pos = if self.pos == 0 { n - 1 } else { self.pos - 1 };
+            self.history[self.pos] = x;
+            self.history[self.pos + n] = x;
+            self.phase += 1;
+            if self.phase < self.decimation {
+                continue;
+            }
+            self.phase = 0;
+            let acc = self.history[self.pos..self.pos + n]
+                .iter()
+                .zip(self.taps.iter())
+                .fold(T::default(), |acc, (&h, &t)| acc + h * t);
+            out.push(acc);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.history.fill(T::default());
+        self.pos = 0;
+        self.phase = 0;
+    }
+}
+
+/// Polyphase FIR that raises the rate by `interpolation`: every input sample yields that many
+/// outputs of the low-pass `taps`, which are designed at the output rate. The gain lost to the
+/// zero-stuffing is made up, so a DC input comes out at the same level.
+pub struct FirInterpolator<T> {
+    /// `phases[p][k]` is tap `p + k * interpolation`.
+    phases: Vec<Vec<f32>>,
+    /// Input history stored twice so the newest samples are always contiguous.
+    history: Vec<T>,
+    pos: usize,
+}
+
+impl<T> FirInterpolator<T>
+where
+    T: Copy + Default + Add<Output = T> + Mul<f32, Output = T>,
+{
+    pub fn new(taps: Vec<f32>, interpolation: usize) -> Self {
+        let l = interpolation.max(1);
+        let per_phase = taps.len().div_ceil(l).max(1);
+        let phases = (0..l)
+            .map(|p| {
+                (0..per_phase)
+                    .map(|k| taps.get(p + k * l).copied().unwrap_or(0.0) * l as f32)
+                    .collect()
+            })
+            .collect();
+        Self {
+            phases,
+            history: vec![T::default(); 2 * per_phase],
+            pos: 0,
+        }
+    }
+
+    pub fn interpolation(&self) -> usize {
+        self.phases.len()
+    }
+
+    pub fn process(&mut self, input: &[T], out: &mut Vec<T>) {
+        let n = self.history.len() / 2;
+        out.reserve(input.len() * self.phases.len());
+        for &x in input {
+            self.pos = if self.pos == 0 { n - 1 } else { self.pos - 1 };
+            self.history[self.pos] = x;
+            self.history[self.pos + n] = x;
+            let recent = &self.history[self.pos..self.pos + n];
+            for phase in self.phases.iter() {
+                let acc = recent
+                    .iter()
+                    .zip(phase.iter())
+                    .fold(T::default(), |acc, (&h, &t)| acc + h * t);
+                out.push(acc);
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.history.fill(T::default());
+        self.pos = 0;
+    }
+}
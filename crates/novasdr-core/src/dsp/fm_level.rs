@@ -0,0 +1,55 @@
+/// Deviation that reaches the target level in NFM. 2.5 kHz (12.5 kHz channels) comes out a
+/// little below it and 5 kHz (25 kHz channels) goes into the limiter.
+pub const NFM_DEVIATION_HZ: f32 = 3_000.0;
+/// Deviation that reaches the target level in WBFM: the audio share of 75 kHz after the pilot,
+/// at the levels broadcast processing usually holds.
+pub const WBFM_DEVIATION_HZ: f32 = 60_000.0;
+/// Limiter ceiling relative to the target level.
+const CEILING: f32 = 2.0;
+
+/// Leveling for FM discriminator output.
+///
+/// A discriminator's output tracks the deviation, not the signal strength, so it is already at
+/// a fixed level once the carrier is above the noise. A peak AGC only adds pumping there; this
+/// applies the fixed gain that brings `deviation_hz` to `target` and soft-limits overshoot
+/// above `target` towards twice it.
+#[derive(Debug, Clone)]
+pub struct FmLeveler {
+    gain: f32,
+    target: f32,
+}
+
+impl FmLeveler {
+    /// `discriminator_rate` is the rate the discriminator ran at: the phase step it reports
+    /// for a deviation `f` is `2π f / discriminator_rate`.
+    pub fn new(deviation_hz: f32, discriminator_rate: f32, target: f32) -> Self {
+        let full_deviation = std::f32::consts::TAU * deviation_hz / discriminator_rate;
+        Self {
+            gain: target / full_deviation,
+            target,
+        }
+    }
+
+    fn level(&self, x: f32) -> f32 {
+        let y = x * self.gain;
+        let knee = self.target;
+        let over = y.abs() - knee;
+        if over <= 0.0 {
+            return y;
+        }
+        // Unity slope at the knee, flattening out at the ceiling.
+        let room = knee * (CEILING - 1.0);
+        (knee + room * (over / room).tanh()).copysign(y)
+    }
+
+    pub fn process(&self, samples: &mut [f32]) {
+        for s in samples.iter_mut() {
+            *s = self.level(*s);
+        }
+    }
+
+    pub fn process_stereo(&self, left: &mut [f32], right: &mut [f32]) {
+        self.process(left);
+        self.process(right);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::config::SampleFormat;
+use crate::dsp::sample::SampleReader;
+
+/// Fewer bytes than this give too noisy a histogram to judge.
+pub const MIN_PROBE_BYTES: usize = 4096;
+
+/// Floats that decode into this magnitude range (or to exactly zero) look like real samples;
+/// integer data read as floats mostly lands on denormals, NaNs or huge exponents.
+const FLOAT_PLAUSIBLE: std::ops::Range<f32> = 1e-9..1e3;
+const FLOAT_MIN_PLAUSIBLE_FRACTION: f32 = 0.98;
+/// 16-bit little-endian data has a near-uniform low byte and a high byte clustered around its
+/// centre; 8-bit data has the same spread in even and odd bytes.
+const WIDE_SPREAD_RATIO: f32 = 0.5;
+/// Decoded integer samples at or beyond this magnitude count as clipped.
+const CLIP_LEVEL: f32 = 0.99;
+
+/// Best guess at the sample format of a raw input stream, with the level statistics of the
+/// stream decoded in that format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatProbe {
+    /// Formats with this byte layout. Real and complex streams of the same width cannot be told
+    /// apart by their bytes, so e.g. `s16` and `cs16` are reported together.
+    pub formats: &'static [SampleFormat],
+    pub bytes_per_sample: usize,
+    /// Mean of the decoded samples; far from zero means a DC offset or the wrong signedness.
+    pub dc_offset: f32,
+    pub rms_dbfs: f32,
+    pub clipped_fraction: f32,
+}
+
+/// An all-zero stream decodes to plausible floats in every width, so it counts as not float.
+fn float_plausible_fraction(values: &[f32]) -> f32 {
+    if values.iter().all(|v| *v == 0.0) {
+        return 0.0;
+    }
+    let ok = values
+        .iter()
+        .filter(|v| **v == 0.0 || FLOAT_PLAUSIBLE.contains(&v.abs()))
+        .count();
+    ok as f32 / values.len() as f32
+}
+
+fn decode_f32(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+fn decode_f64(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(8)
+        .map(|c| f64::from_le_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]) as f32)
+        .collect()
+}
+
+/// Mean distance of bytes from mid-scale (128), or from 0 when read as two's complement.
+fn byte_spread(bytes: impl Iterator<Item = u8>, signed: bool) -> f32 {
+    let mut sum = 0f64;
+    let mut n = 0usize;
+    for b in bytes {
+        let v = if signed {
+            (b as i8) as f64
+        } else {
+            b as f64 - 128.0
+        };
+        sum += v.abs();
+        n += 1;
+    }
+    if n == 0 {
+        0.0
+    } else {
+        (sum / n as f64) as f32
+    }
+}
+
+/// Spread of `bytes` around whichever of the unsigned and signed centres fits better, and
+/// whether that was the signed one.
+fn centred_spread(bytes: &[u8], stride: usize, offset: usize) -> (f32, bool) {
+    let pick = || bytes.iter().copied().skip(offset).step_by(stride);
+    let unsigned = byte_spread(pick(), false);
+    let signed = byte_spread(pick(), true);
+    if signed < unsigned {
+        (signed, true)
+    } else {
+        (unsigned, false)
+    }
+}
+
+fn layout(bytes: &[u8]) -> (&'static [SampleFormat], usize) {
+    if float_plausible_fraction(&decode_f32(bytes)) >= FLOAT_MIN_PLAUSIBLE_FRACTION {
+        return (&[SampleFormat::F32, SampleFormat::Cf32], 4);
+    }
+    if float_plausible_fraction(&decode_f64(bytes)) >= FLOAT_MIN_PLAUSIBLE_FRACTION {
+        return (&[SampleFormat::F64], 8);
+    }
+    let (low_spread, _) = centred_spread(bytes, 2, 0);
+    let (high_spread, high_signed) = centred_spread(bytes, 2, 1);
+    if high_spread < WIDE_SPREAD_RATIO * low_spread {
+        if high_signed {
+            (&[SampleFormat::S16, SampleFormat::Cs16], 2)
+        } else {
+            (&[SampleFormat::U16], 2)
+        }
+    } else if centred_spread(bytes, 1, 0).1 {
+        (&[SampleFormat::S8], 1)
+    } else {
+        (&[SampleFormat::U8], 1)
+    }
+}
+
+/// Guesses the sample format of `bytes` from their histogram: floats by how many values decode to
+/// plausible magnitudes, 8 vs 16 bit by comparing the spread of even and odd bytes, and
+/// signedness by whether values cluster around 0 or mid-scale. Assumes little-endian data. `None` for fewer than [`MIN_PROBE_BYTES`].
+pub fn probe_format(bytes: &[u8]) -> Option<FormatProbe> {
+    if bytes.len() < MIN_PROBE_BYTES {
+        return None;
+    }
+    let (formats, bytes_per_sample) = layout(bytes);
+    let usable = bytes.len() - bytes.len() % bytes_per_sample;
+    let mut samples = vec![0f32; usable / bytes_per_sample];
+    SampleReader::new(&bytes[..usable], formats[0])
+        .read_f32(&mut samples)
+        .ok()?;
+
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|&s| s as f64).sum::<f64>() / n;
+    let power = samples.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / n;
+    let is_float = bytes_per_sample >= 4;
+    let clipped = samples
+        .iter()
+        .filter(|s| !is_float && s.abs() >= CLIP_LEVEL)
+        .count();
+    Some(FormatProbe {
+        formats,
+        bytes_per_sample,
+        dc_offset: mean as f32,
+        rms_dbfs: (10.0 * power.max(1e-20).log10()) as f32,
+        clipped_fraction: clipped as f32 / samples.len() as f32,
+    })
+}
@@ -0,0 +1,104 @@
+//! Frequency-shift keying: mark and space tone filters that turn audio into soft symbols, and
+//! bit clock recovery for synchronous streams.
+
+use num_complex::Complex32;
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+
+/// Mixes one tone down to 0 Hz and averages it over one bit, the matched filter of a
+/// rectangular bit.
+struct ToneFilter {
+    phase: f64,
+    step: f64,
+    history: VecDeque<Complex32>,
+    len: usize,
+    sum: Complex32,
+}
+
+impl ToneFilter {
+    fn new(sample_rate: f64, hz: f64, len: usize) -> Self {
+        Self {
+            phase: 0.0,
+            step: TAU * hz / sample_rate,
+            history: VecDeque::with_capacity(len),
+            len,
+            sum: Complex32::new(0.0, 0.0),
+        }
+    }
+
+    /// Magnitude of the tone over the last bit.
+    fn push(&mut self, x: f32) -> f32 {
+        let z = Complex32::from_polar(x, -self.phase as f32);
+        self.phase = (self.phase + self.step) % TAU;
+        if self.history.len() == self.len {
+            self.sum -= self.history.pop_front().unwrap_or_default();
+        }
+        self.history.push_back(z);
+        self.sum += z;
+        self.sum.norm() / self.len as f32
+    }
+}
+
+/// Soft mark/space decisions from FSK audio.
+pub struct FskDemodulator {
+    mark: ToneFilter,
+    space: ToneFilter,
+}
+
+impl FskDemodulator {
+    pub fn new(sample_rate: f64, mark_hz: f64, space_hz: f64, baud: f64) -> Self {
+        let len = ((sample_rate / baud).round() as usize).max(1);
+        Self {
+            mark: ToneFilter::new(sample_rate, mark_hz, len),
+            space: ToneFilter::new(sample_rate, space_hz, len),
+        }
+    }
+
+    /// Soft symbol for one sample, from `1` (mark only) to `-1` (space only). It does not
+    /// depend on the signal level, and stays near 0 with neither tone present.
+    pub fn process(&mut self, x: f32) -> f32 {
+        let mark = self.mark.push(x);
+        let space = self.space.push(x);
+        (mark - space) / (mark + space + f32::EPSILON)
+    }
+}
+
+/// Share of the timing error corrected at every symbol change.
+const CLOCK_GAIN: f64 = 0.15;
+
+/// Recovers the bit clock of a synchronous stream of soft symbols, as put out by
+/// [`FskDemodulator`]: their sign changes lie halfway between two bit centres.
+pub struct BitSync {
+    samples_per_bit: f64,
+    /// Position since the last bit centre, in bits.
+    clock: f64,
+    prev: f32,
+}
+
+impl BitSync {
+    pub fn new(sample_rate: f64, baud: f64) -> Self {
+        Self {
+            samples_per_bit: sample_rate / baud,
+            clock: 0.0,
+            prev: 0.0,
+        }
+    }
+
+    /// Takes one soft symbol; returns the bit (`true` for mark) when it is a bit centre.
+    pub fn push(&mut self, soft: f32) -> Option<bool> {
+        let step = 1.0 / self.samples_per_bit;
+        if (soft > 0.0) != (self.prev > 0.0) && soft != self.prev {
+            // Where between the two samples the sign changed, by linear interpolation.
+            let frac = f64::from(self.prev / (self.prev - soft)).clamp(0.0, 1.0);
+            let at = self.clock + frac * step;
+            self.clock -= CLOCK_GAIN * (at - 0.5);
+        }
+        self.prev = soft;
+        self.clock += step;
+        if self.clock < 1.0 {
+            return None;
+        }
+        self.clock -= 1.0;
+        Some(soft > 0.0)
+    }
+}
@@ -0,0 +1,261 @@
+//! Frequency lowering for listeners with high-frequency hearing loss: speech cues above a few
+//! kHz (sibilants, consonant bursts) are moved down to where they can still be heard.
+//!
+//! A short-time Fourier transform with 75 % overlap; each bin's true frequency is estimated from
+//! its phase advance (phase vocoder), mapped by the profile, and resynthesised at the new
+//! frequency with a running phase. Content the profile leaves in place keeps its own bins, so
+//! the band below the lowering start passes unchanged apart from the delay of one window.
+
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use rustfft::num_complex::Complex32;
+use std::f32::consts::{PI, TAU};
+use std::sync::Arc;
+
+/// Analysis window length in seconds; long enough to resolve speech harmonics.
+const WINDOW_SECONDS: f32 = 0.032;
+/// Hops per window.
+const OVERLAP: usize = 4;
+/// Gain of transposed content mixed back in, so it adds cues rather than masking the original.
+const TRANSPOSE_GAIN: f32 = 0.7;
+
+/// Presets selectable per listener.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HearingProfile {
+    #[default]
+    Off,
+    /// Everything above 1.5 kHz squeezed 2:1, so 1.5–4.5 kHz lands in 1.5–3 kHz.
+    Compress,
+    /// Everything above 1 kHz squeezed 3:1, so 1–4 kHz lands in 1–2 kHz, for more severe loss.
+    CompressStrong,
+    /// 3–6 kHz copied an octave down to 1.5–3 kHz and mixed with the unchanged audio.
+    Transpose,
+}
+
+impl HearingProfile {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "off" => Some(Self::Off),
+            "compress" => Some(Self::Compress),
+            "compress_strong" => Some(Self::CompressStrong),
+            "transpose" => Some(Self::Transpose),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Compress => "compress",
+            Self::CompressStrong => "compress_strong",
+            Self::Transpose => "transpose",
+        }
+    }
+
+    /// Where content at `hz` goes, with its gain: up to two destinations (original and copy).
+    fn map(self, hz: f32) -> [Option<(f32, f32)>; 2] {
+        let compress = |start: f32, ratio: f32| {
+            if hz <= start {
+                hz
+            } else {
+                start + (hz - start) / ratio
+            }
+        };
+        match self {
+            Self::Off => [Some((hz, 1.0)), None],
+            Self::Compress => [Some((compress(1_500.0, 2.0), 1.0)), None],
+            Self::CompressStrong => [Some((compress(1_000.0, 3.0), 1.0)), None],
+            Self::Transpose => [
+                Some((hz, 1.0)),
+                (3_000.0..=6_000.0)
+                    .contains(&hz)
+                    .then_some((hz / 2.0, TRANSPOSE_GAIN)),
+            ],
+        }
+    }
+}
+
+/// The lowering stage of one audio channel. With [`HearingProfile::Off`] samples pass untouched
+/// and without delay.
+pub struct FrequencyLowering {
+    profile: HearingProfile,
+    sample_rate: f32,
+    size: usize,
+    hop: usize,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    window: Vec<f32>,
+    /// Last `size` input samples.
+    input: Vec<f32>,
+    /// Overlap-add accumulator; its first `hop` samples are complete after each frame.
+    output: Vec<f32>,
+    /// Samples taken since the last frame; as many complete output samples are waiting.
+    pending: usize,
+    spectrum: Vec<Complex32>,
+    frame: Vec<f32>,
+    last_phase: Vec<f32>,
+    /// Running phase of moved content per output bin.
+    out_phase: Vec<f32>,
+    /// Content the profile leaves in place, per bin.
+    kept: Vec<Complex32>,
+    /// Moved content per output bin: summed magnitude, and the frequency in bins and magnitude
+    /// of its strongest contribution, which drives the bin's phase.
+    moved: Vec<(f32, f32, f32)>,
+}
+
+impl FrequencyLowering {
+    pub fn new(sample_rate: usize) -> Self {
+        let size = ((sample_rate as f32 * WINDOW_SECONDS) as usize)
+            .next_power_of_two()
+            .max(64);
+        let hop = size / OVERLAP;
+        let mut planner = RealFftPlanner::<f32>::new();
+        let bins = size / 2 + 1;
+        // Periodic Hann for analysis and synthesis; the squared windows overlap-add to 3/2.
+        let window = (0..size)
+            .map(|n| 0.5 - 0.5 * (TAU * n as f32 / size as f32).cos())
+            .collect();
+        Self {
+            profile: HearingProfile::Off,
+            sample_rate: sample_rate as f32,
+            size,
+            hop,
+            forward: planner.plan_fft_forward(size),
+            inverse: planner.plan_fft_inverse(size),
+            window,
+            input: vec![0.0; size],
+            output: vec![0.0; size],
+            pending: 0,
+            spectrum: vec![Complex32::default(); bins],
+            frame: vec![0.0; size],
+            last_phase: vec![0.0; bins],
+            out_phase: vec![0.0; bins],
+            kept: vec![Complex32::default(); bins],
+            moved: vec![(0.0, 0.0, 0.0); bins],
+        }
+    }
+
+    pub fn profile(&self) -> HearingProfile {
+        self.profile
+    }
+
+    /// Switches the profile; a change starts from silence, without the old profile's tail.
+    pub fn set_profile(&mut self, profile: HearingProfile) {
+        if profile != self.profile {
+            self.profile = profile;
+            self.reset();
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        if self.profile == HearingProfile::Off {
+            return;
+        }
+        let mut done = 0;
+        while done < samples.len() {
+            let take = (self.hop - self.pending).min(samples.len() - done);
+            let block = &mut samples[done..done + take];
+            let start = self.size - self.hop + self.pending;
+            self.input[start..start + take].copy_from_slice(block);
+            block.copy_from_slice(&self.output[self.pending..self.pending + take]);
+            self.pending += take;
+            done += take;
+            if self.pending == self.hop {
+                self.pending = 0;
+                self.output.copy_within(self.hop.., 0);
+                let tail = self.size - self.hop;
+                self.output[tail..].fill(0.0);
+                self.run_frame();
+                self.input.copy_within(self.hop.., 0);
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.input.fill(0.0);
+        self.output.fill(0.0);
+        self.pending = 0;
+        self.last_phase.fill(0.0);
+        self.out_phase.fill(0.0);
+    }
+
+    fn run_frame(&mut self) {
+        for ((f, x), w) in self.frame.iter_mut().zip(&self.input).zip(&self.window) {
+            *f = x * w;
+        }
+        if self
+            .forward
+            .process(&mut self.frame, &mut self.spectrum)
+            .is_err()
+        {
+            return;
+        }
+
+        let bins = self.spectrum.len();
+        let bin_hz = self.sample_rate / self.size as f32;
+        // Phase a bin's centre frequency advances by per hop.
+        let advance = TAU * self.hop as f32 / self.size as f32;
+        self.kept.fill(Complex32::default());
+        self.moved.fill((0.0, 0.0, 0.0));
+        for k in 0..bins {
+            let x = self.spectrum[k];
+            let phase = x.arg();
+            let deviation = wrap(phase - self.last_phase[k] - advance * k as f32);
+            self.last_phase[k] = phase;
+            let magnitude = x.norm();
+            if magnitude == 0.0 {
+                continue;
+            }
+            let true_bin = k as f32 + deviation / advance;
+            for (hz, gain) in self.profile.map(true_bin * bin_hz).into_iter().flatten() {
+                let out_bin = hz / bin_hz;
+                let shift = out_bin - true_bin;
+                if shift.abs() < 1e-3 {
+                    // Content left in place keeps bin and phase, so that band is transparent.
+                    self.kept[k] += x * gain;
+                    continue;
+                }
+                // The bin moves with its sinusoid, so the window's main lobe keeps its shape.
+                let j = (k as f32 + shift).round();
+                if j < 0.0 || j as usize >= bins {
+                    continue;
+                }
+                let moved = &mut self.moved[j as usize];
+                let contribution = magnitude * gain;
+                moved.0 += contribution;
+                if contribution > moved.2 {
+                    moved.1 = out_bin;
+                    moved.2 = contribution;
+                }
+            }
+        }
+        for j in 0..bins {
+            let (magnitude, out_bin, _) = self.moved[j];
+            let mut y = self.kept[j];
+            if magnitude > 0.0 {
+                self.out_phase[j] = wrap(self.out_phase[j] + advance * out_bin);
+                y += Complex32::from_polar(magnitude, self.out_phase[j]);
+            }
+            self.spectrum[j] = y;
+        }
+        // A real signal's DC and Nyquist bins carry no phase.
+        self.spectrum[0].im = 0.0;
+        self.spectrum[bins - 1].im = 0.0;
+
+        if self
+            .inverse
+            .process(&mut self.spectrum, &mut self.frame)
+            .is_err()
+        {
+            return;
+        }
+        // Undo the unnormalised transform pair and the overlapping squared windows.
+        let scale = 1.0 / (self.size as f32 * 1.5);
+        for ((out, y), w) in self.output.iter_mut().zip(&self.frame).zip(&self.window) {
+            *out += y * w * scale;
+        }
+    }
+}
+
+fn wrap(phase: f32) -> f32 {
+    (phase + PI).rem_euclid(TAU) - PI
+}
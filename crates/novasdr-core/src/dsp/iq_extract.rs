@@ -0,0 +1,88 @@
+use num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+use std::sync::Arc;
+
+/// Complex IQ of a slice of the receiver's spectrum, at `size` bins' worth of sample rate.
+///
+/// Works like the complex (AM/FM) path of the audio pipeline: the bins around the centre are
+/// moved to DC, inverse-transformed at `size` points, and consecutive frames are overlap-added,
+/// which reconstructs the signal exactly for the Hann window at the engine's 50% overlap. Each
+/// frame yields `size / 2` samples at `size * bin_hz` samples per second.
+pub struct IqExtractor {
+    size: usize,
+    fft_size: usize,
+    ifft: Arc<dyn Fft<f32>>,
+    buf: Vec<Complex32>,
+    scratch: Vec<Complex32>,
+    prev: Vec<Complex32>,
+}
+
+impl IqExtractor {
+    /// `size` must be even and at most the receiver's bin count; `fft_size` is the receiver's
+    /// FFT length, used to bring the output back to the input's amplitude.
+    pub fn new(size: usize, fft_size: usize) -> Self {
+        assert!(size >= 2 && size.is_multiple_of(2), "IQ size must be even");
+        let ifft = FftPlanner::new().plan_fft_inverse(size);
+        let scratch = vec![Complex32::new(0.0, 0.0); ifft.get_inplace_scratch_len()];
+        Self {
+            size,
+            fft_size,
+            ifft,
+            buf: vec![Complex32::new(0.0, 0.0); size],
+            scratch,
+            prev: vec![Complex32::new(0.0, 0.0); size / 2],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Appends one frame of IQ centred on bin `center` of `bins` (the rotated spectrum, lowest
+    /// frequency first). Bins outside the spectrum are silence, so the centre may sit near or
+    /// past an edge.
+    pub fn process(
+        &mut self,
+        bins: &[Complex32],
+        center: i64,
+        is_real_input: bool,
+        frame_num: u64,
+        out: &mut Vec<Complex32>,
+    ) {
+        let n = self.size;
+        let half = (n / 2) as i64;
+        let len = bins.len() as i64;
+        for (k, dst) in self.buf.iter_mut().enumerate() {
+            // Positive offsets first, then the negative ones wrapped to the top half.
+            let offset = if (k as i64) < half {
+                k as i64
+            } else {
+                k as i64 - n as i64
+            };
+            let src = center + offset;
+            *dst = if (0..len).contains(&src) {
+                bins[src as usize]
+            } else {
+                Complex32::new(0.0, 0.0)
+            };
+        }
+        self.ifft
+            .process_with_scratch(&mut self.buf, &mut self.scratch);
+
+        // Shifting a bin to DC leaves a (-1)^bin phase step per half-frame hop; undo it on odd
+        // frames so the halves line up. See `AudioPipeline::demodulate`.
+        let negate = frame_num % 2 == 1 && ((center % 2 == 0) != is_real_input);
+        if negate {
+            self.buf.iter_mut().for_each(|c| *c = -*c);
+        }
+
+        let scale = 1.0 / self.fft_size as f32;
+        let (head, tail) = self.buf.split_at(n / 2);
+        out.extend(
+            head.iter()
+                .zip(self.prev.iter())
+                .map(|(&a, &b)| (a + b) * scale),
+        );
+        self.prev.copy_from_slice(tail);
+    }
+}
@@ -1,10 +1,28 @@
 pub mod agc;
+pub mod channelizer;
 #[cfg(feature = "clfft")]
 pub mod clfft;
 pub mod dc_blocker;
+pub mod dc_suppression;
+pub mod decimate;
 pub mod demod;
 pub mod fft;
+pub mod fir;
+pub mod fm_level;
+pub mod format_probe;
+pub mod fsk;
+pub mod hearing;
+pub mod iq_extract;
+pub mod passband;
+pub mod peaks;
 pub mod sample;
+pub mod simd;
+pub mod subtone;
+pub mod synthetic;
+pub mod tx;
 #[cfg(feature = "vkfft")]
 pub mod vkfft;
+pub mod volume;
+pub mod waterfall_mask;
+pub mod wbfm;
 pub mod window;
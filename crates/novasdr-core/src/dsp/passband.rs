@@ -0,0 +1,131 @@
+use super::fir::lowpass_taps;
+use num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+use std::sync::Arc;
+
+/// Passband as designed: lower edge, upper edge and transition width, all in Hz.
+type Design = (f32, f32, f32);
+
+/// Streaming complex FIR band-pass applied by overlap-save fast convolution. Every call filters
+/// exactly `block` samples in place, so it drops into the per-frame audio path without extra
+/// buffering; the output lags the input by [`Self::delay`] samples.
+pub struct PassbandFilter {
+    block: usize,
+    sample_rate: f32,
+    taps: usize,
+    fft: Option<Arc<dyn Fft<f32>>>,
+    ifft: Option<Arc<dyn Fft<f32>>>,
+    scratch: Vec<Complex32>,
+    /// Frequency response of the taps, scaled for the unnormalised inverse FFT.
+    response: Vec<Complex32>,
+    /// Last `fft_size - block` input samples.
+    history: Vec<Complex32>,
+    work: Vec<Complex32>,
+    design: Option<Design>,
+}
+
+impl PassbandFilter {
+    pub fn new(block: usize, sample_rate: usize) -> Self {
+        Self {
+            block,
+            sample_rate: sample_rate as f32,
+            taps: 0,
+            fft: None,
+            ifft: None,
+            scratch: Vec::new(),
+            response: Vec::new(),
+            history: Vec::new(),
+            work: Vec::new(),
+            design: None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.design.is_some()
+    }
+
+    /// Group delay in samples; zero while the filter is bypassed.
+    pub fn delay(&self) -> usize {
+        if self.is_active() {
+            (self.taps - 1) / 2
+        } else {
+            0
+        }
+    }
+
+    /// Passes `lo_hz..hi_hz` (negative frequencies allowed) with skirts `transition_hz` wide,
+    /// centred on the edges. `None`, or an empty passband, bypasses the filter.
+    pub fn set_passband(&mut self, passband: Option<(f32, f32)>, transition_hz: f32) {
+        let design = passband
+            .filter(|(lo, hi)| hi > lo && transition_hz > 0.0)
+            .map(|(lo, hi)| (lo, hi, transition_hz));
+        if design == self.design {
+            return;
+        }
+        let Some((lo, hi, transition)) = design else {
+            self.design = None;
+            return;
+        };
+
+        let rate = self.sample_rate;
+        let prototype = lowpass_taps(0.5 * (hi - lo) / rate, transition / rate);
+        if prototype.len() != self.taps {
+            // A new length changes the FFT size; history only carries over between designs of
+            // the same length, i.e. while the listener drags the passband.
+            self.taps = prototype.len();
+            let fft_size = (self.block + self.taps - 1).next_power_of_two();
+            let mut planner = FftPlanner::<f32>::new();
+            let fft = planner.plan_fft_forward(fft_size);
+            let ifft = planner.plan_fft_inverse(fft_size);
+            self.scratch = vec![
+                Complex32::new(0.0, 0.0);
+                fft.get_inplace_scratch_len()
+                    .max(ifft.get_inplace_scratch_len())
+            ];
+            self.fft = Some(fft);
+            self.ifft = Some(ifft);
+            self.history = vec![Complex32::new(0.0, 0.0); fft_size - self.block];
+            self.work = vec![Complex32::new(0.0, 0.0); fft_size];
+        }
+        let Some(fft) = self.fft.as_ref() else {
+            return;
+        };
+
+        let fft_size = self.work.len();
+        let centre = std::f32::consts::TAU * 0.5 * (lo + hi) / rate;
+        let mid = (self.taps - 1) as f32 / 2.0;
+        self.response = vec![Complex32::new(0.0, 0.0); fft_size];
+        for (i, (dst, &h)) in self.response.iter_mut().zip(prototype.iter()).enumerate() {
+            *dst = Complex32::from_polar(h / fft_size as f32, centre * (i as f32 - mid));
+        }
+        fft.process_with_scratch(&mut self.response, &mut self.scratch);
+        self.design = design;
+    }
+
+    /// Filters `samples` in place; `samples.len()` must equal the block size.
+    pub fn process(&mut self, samples: &mut [Complex32]) {
+        let (Some(fft), Some(ifft)) = (self.fft.as_ref(), self.ifft.as_ref()) else {
+            return;
+        };
+        if self.design.is_none() || samples.len() != self.block {
+            return;
+        }
+        let keep = self.history.len();
+        self.work[..keep].copy_from_slice(&self.history);
+        self.work[keep..].copy_from_slice(samples);
+        self.history
+            .copy_from_slice(&self.work[self.work.len() - keep..]);
+
+        fft.process_with_scratch(&mut self.work, &mut self.scratch);
+        for (w, h) in self.work.iter_mut().zip(self.response.iter()) {
+            *w *= *h;
+        }
+        ifft.process_with_scratch(&mut self.work, &mut self.scratch);
+        // The first `keep` outputs wrap around the circular convolution; the rest are exact.
+        samples.copy_from_slice(&self.work[keep..]);
+    }
+
+    pub fn reset(&mut self) {
+        self.history.fill(Complex32::new(0.0, 0.0));
+    }
+}
@@ -0,0 +1,71 @@
+/// Level a peak must fall below its threshold before the detector re-arms.
+const REARM_HYSTERESIS_DB: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Peak {
+    /// Index of the strongest bin in the row.
+    pub bin: usize,
+    pub level_db: f32,
+    pub floor_db: f32,
+}
+
+impl Peak {
+    pub fn snr_db(&self) -> f32 {
+        self.level_db - self.floor_db
+    }
+}
+
+/// Median of a power row in dB, used as its noise floor.
+pub fn noise_floor_db(row_db: &[f32]) -> f32 {
+    if row_db.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let mut sorted = row_db.to_vec();
+    let mid = sorted.len() / 2;
+    let (_, median, _) = sorted.select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+    *median
+}
+
+/// Reports a peak once when the strongest bin of a spectrum row rises `threshold_db` above the
+/// row's noise floor, and stays quiet until the row drops back below the threshold.
+pub struct PeakDetector {
+    threshold_db: f32,
+    active: bool,
+}
+
+impl PeakDetector {
+    pub fn new(threshold_db: f32) -> Self {
+        Self {
+            threshold_db,
+            active: false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn update(&mut self, row_db: &[f32]) -> Option<Peak> {
+        let (bin, &level_db) = row_db
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        let floor_db = noise_floor_db(row_db);
+        let snr = level_db - floor_db;
+        if self.active {
+            if snr < self.threshold_db - REARM_HYSTERESIS_DB {
+                self.active = false;
+            }
+            return None;
+        }
+        if snr < self.threshold_db {
+            return None;
+        }
+        self.active = true;
+        Some(Peak {
+            bin,
+            level_db,
+            floor_db,
+        })
+    }
+}
@@ -1,3 +1,5 @@
+use super::decimate::PreDecimator;
+use super::simd;
 use crate::config::SampleFormat;
 use anyhow::Context;
 use std::io::Read;
@@ -9,6 +11,9 @@ pub struct SampleReader<R> {
     scratch_i16: Vec<i16>,
     scratch_u16: Vec<u16>,
     scratch_f64: Vec<f64>,
+    decimator: Option<PreDecimator>,
+    /// Input-rate samples waiting for the decimator.
+    undecimated: Vec<f32>,
 }
 
 impl<R: Read> SampleReader<R> {
@@ -20,10 +25,34 @@ impl<R: Read> SampleReader<R> {
             scratch_i16: Vec::new(),
             scratch_u16: Vec::new(),
             scratch_f64: Vec::new(),
+            decimator: None,
+            undecimated: Vec::new(),
         }
     }
 
+    /// Reads `decimator.factor()` input samples for every sample returned.
+    pub fn with_decimator(mut self, decimator: PreDecimator) -> Self {
+        self.decimator = Some(decimator);
+        self
+    }
+
     pub fn read_f32(&mut self, out: &mut [f32]) -> anyhow::Result<()> {
+        let Some(decimator) = self.decimator.as_mut() else {
+            return self.read_converted(out);
+        };
+        let mut raw = std::mem::take(&mut self.undecimated);
+        raw.resize(out.len() * decimator.factor(), 0.0);
+        let result = self.read_converted(&mut raw);
+        if result.is_ok() {
+            if let Some(decimator) = self.decimator.as_mut() {
+                decimator.process(&raw, out);
+            }
+        }
+        self.undecimated = raw;
+        result
+    }
+
+    fn read_converted(&mut self, out: &mut [f32]) -> anyhow::Result<()> {
         match self.format {
             SampleFormat::U8 => self.read_u8_as_f32(out),
             SampleFormat::S8 => self.read_i8_as_f32(out),
@@ -42,21 +71,7 @@ impl<R: Read> SampleReader<R> {
         let raw = &mut self.scratch_u8[..out.len()];
         self.reader.read_exact(raw).context("input sample read")?;
 
-        // Fast path: precomputed LUT for u8 -> f32 mapping.
-        // This avoids per-sample branches and endian work in the hot loop.
-        static U8_TO_F32: [f32; 256] = {
-            let mut lut = [0.0f32; 256];
-            let mut i = 0usize;
-            while i < 256 {
-                let signed = ((i as u8) ^ 0x80) as i8;
-                lut[i] = (signed as f32) / 128.0;
-                i += 1;
-            }
-            lut
-        };
-        for (dst, src) in out.iter_mut().zip(raw.iter().copied()) {
-            *dst = U8_TO_F32[src as usize];
-        }
+        simd::u8_to_f32(simd::detected(), raw, out);
         Ok(())
     }
 
@@ -100,9 +115,7 @@ impl<R: Read> SampleReader<R> {
             .read_exact(raw_bytes)
             .context("input sample read")?;
 
-        for (dst, src) in out.iter_mut().zip(raw_i16.iter().copied()) {
-            *dst = (src as f32) / 32768.0;
-        }
+        simd::i16_to_f32(simd::detected(), raw_i16, out);
         Ok(())
     }
 
@@ -128,3 +141,19 @@ impl<R: Read> SampleReader<R> {
         Ok(())
     }
 }
+
+/// Converted samples at or beyond this magnitude sat at the ADC's full scale.
+pub const CLIP_LEVEL: f32 = 0.99;
+
+/// Samples of `samples` (as converted by [`SampleReader`]) that hit the ADC's full scale.
+pub fn count_clipped(samples: &[f32]) -> usize {
+    samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count()
+}
+
+/// Mean square of `samples` (as converted by [`SampleReader`]); a full-scale square wave is 1.
+pub fn mean_square(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32
+}
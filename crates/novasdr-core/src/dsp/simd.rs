@@ -0,0 +1,408 @@
+//! Vectorized versions of the per-sample loops that dominate CPU-only receivers: the AM envelope,
+//! the FM discriminator, float to PCM conversion and the 8/16-bit input converters.
+//!
+//! The instruction set is picked once at runtime: AVX2 on x86_64, NEON on aarch64 (64-bit
+//! Raspberry Pi OS), scalar code elsewhere. `NOVASDR_SIMD=off` forces the scalar code, e.g. to
+//! rule the kernels out while debugging. Vector results match the scalar ones except for the FM
+//! discriminator, whose `atan2` is a polynomial accurate to about 1e-5 rad.
+
+use num_complex::Complex32;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+    Scalar,
+    Avx2,
+    Neon,
+}
+
+impl SimdLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SimdLevel::Scalar => "scalar",
+            SimdLevel::Avx2 => "avx2",
+            SimdLevel::Neon => "neon",
+        }
+    }
+}
+
+fn detect() -> SimdLevel {
+    if std::env::var("NOVASDR_SIMD").is_ok_and(|v| v.eq_ignore_ascii_case("off")) {
+        return SimdLevel::Scalar;
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::arch::is_x86_feature_detected!("avx2") {
+            return SimdLevel::Avx2;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return SimdLevel::Neon;
+        }
+    }
+    SimdLevel::Scalar
+}
+
+/// Instruction set the kernels use on this machine.
+pub fn detected() -> SimdLevel {
+    static LEVEL: OnceLock<SimdLevel> = OnceLock::new();
+    *LEVEL.get_or_init(detect)
+}
+
+/// Coefficients of an odd polynomial for `atan(a)` on `[0, 1]` in powers of `a^2`.
+const ATAN_COEFFS: [f32; 6] = [
+    0.999_977_26,
+    -0.332_623_47,
+    0.193_543_46,
+    -0.116_432_87,
+    0.052_653_32,
+    -0.011_721_2,
+];
+
+/// `Complex32` is `#[repr(C)]`, so a slice of it is interleaved `re, im` floats.
+fn as_floats(iq: &[Complex32]) -> *const f32 {
+    iq.as_ptr().cast()
+}
+
+/// `out[i] = |iq[i]|`.
+pub fn am_envelope(level: SimdLevel, iq: &[Complex32], out: &mut [f32]) {
+    let n = iq.len().min(out.len());
+    let done = match level {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: `Avx2` is only reported when the CPU supports it.
+        SimdLevel::Avx2 => unsafe { x86::am_envelope(&iq[..n], &mut out[..n]) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: `Neon` is only reported when the CPU supports it.
+        SimdLevel::Neon => unsafe { arm::am_envelope(&iq[..n], &mut out[..n]) },
+        _ => 0,
+    };
+    for (dst, v) in out[done..n].iter_mut().zip(iq[done..n].iter()) {
+        *dst = (v.re * v.re + v.im * v.im).sqrt();
+    }
+}
+
+/// `out[i] = arg(iq[i] * conj(iq[i - 1]))` with `iq[-1] = prev`; returns the last sample.
+pub fn polar_discriminator_fm(
+    level: SimdLevel,
+    iq: &[Complex32],
+    prev: Complex32,
+    out: &mut [f32],
+) -> Complex32 {
+    let n = iq.len().min(out.len());
+    if n == 0 {
+        return prev;
+    }
+    out[0] = (iq[0] * prev.conj()).arg();
+    let done = 1 + match level {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: `Avx2` is only reported when the CPU supports it.
+        SimdLevel::Avx2 => unsafe { x86::fm_discriminator(&iq[..n], &mut out[1..n]) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: `Neon` is only reported when the CPU supports it.
+        SimdLevel::Neon => unsafe { arm::fm_discriminator(&iq[..n], &mut out[1..n]) },
+        _ => 0,
+    };
+    for k in done..n {
+        out[k] = (iq[k] * iq[k - 1].conj()).arg();
+    }
+    iq[n - 1]
+}
+
+/// `out[i] = clamp(floor(samples[i] * mult + 0.5))` as 16-bit PCM.
+pub fn float_to_i16_centered(level: SimdLevel, samples: &[f32], out: &mut [i16], mult: f32) {
+    let n = samples.len().min(out.len());
+    let done = match level {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: `Avx2` is only reported when the CPU supports it.
+        SimdLevel::Avx2 => unsafe { x86::float_to_i16(&samples[..n], &mut out[..n], mult) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: `Neon` is only reported when the CPU supports it.
+        SimdLevel::Neon => unsafe { arm::float_to_i16(&samples[..n], &mut out[..n], mult) },
+        _ => 0,
+    };
+    for (dst, s) in out[done..n].iter_mut().zip(samples[done..n].iter()) {
+        let v = ((s * mult + 32768.5).floor() as i32).saturating_sub(32768);
+        *dst = v.clamp(-32768, 32767) as i16;
+    }
+}
+
+/// Unsigned 8-bit samples centred at 128 to `[-1, 1)`.
+pub fn u8_to_f32(level: SimdLevel, raw: &[u8], out: &mut [f32]) {
+    let n = raw.len().min(out.len());
+    let done = match level {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: `Avx2` is only reported when the CPU supports it.
+        SimdLevel::Avx2 => unsafe { x86::u8_to_f32(&raw[..n], &mut out[..n]) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: `Neon` is only reported when the CPU supports it.
+        SimdLevel::Neon => unsafe { arm::u8_to_f32(&raw[..n], &mut out[..n]) },
+        _ => 0,
+    };
+    for (dst, src) in out[done..n].iter_mut().zip(raw[done..n].iter()) {
+        *dst = (*src as i32 - 128) as f32 / 128.0;
+    }
+}
+
+/// Signed 16-bit samples to `[-1, 1)`.
+pub fn i16_to_f32(level: SimdLevel, raw: &[i16], out: &mut [f32]) {
+    let n = raw.len().min(out.len());
+    let done = match level {
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: `Avx2` is only reported when the CPU supports it.
+        SimdLevel::Avx2 => unsafe { x86::i16_to_f32(&raw[..n], &mut out[..n]) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: `Neon` is only reported when the CPU supports it.
+        SimdLevel::Neon => unsafe { arm::i16_to_f32(&raw[..n], &mut out[..n]) },
+        _ => 0,
+    };
+    for (dst, src) in out[done..n].iter_mut().zip(raw[done..n].iter()) {
+        *dst = *src as f32 / 32768.0;
+    }
+}
+
+/// Each kernel handles whole vectors and returns how many elements it wrote; the caller finishes
+/// the tail with the scalar loop.
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::{as_floats, ATAN_COEFFS};
+    use num_complex::Complex32;
+    use std::arch::x86_64::*;
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn am_envelope(iq: &[Complex32], out: &mut [f32]) -> usize {
+        let src = as_floats(iq);
+        let chunks = iq.len() / 8;
+        for c in 0..chunks {
+            let a = _mm256_loadu_ps(src.add(c * 16));
+            let b = _mm256_loadu_ps(src.add(c * 16 + 8));
+            // hadd pairs re^2 + im^2 per 128-bit lane; the permute restores sample order.
+            let sums = _mm256_hadd_ps(_mm256_mul_ps(a, a), _mm256_mul_ps(b, b));
+            let ordered = _mm256_castpd_ps(_mm256_permute4x64_pd::<0b11_01_10_00>(
+                _mm256_castps_pd(sums),
+            ));
+            _mm256_storeu_ps(out.as_mut_ptr().add(c * 8), _mm256_sqrt_ps(ordered));
+        }
+        chunks * 8
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn atan2(y: __m256, x: __m256) -> __m256 {
+        let sign_mask = _mm256_set1_ps(-0.0);
+        let ax = _mm256_andnot_ps(sign_mask, x);
+        let ay = _mm256_andnot_ps(sign_mask, y);
+        let num = _mm256_min_ps(ax, ay);
+        let den = _mm256_max_ps(_mm256_max_ps(ax, ay), _mm256_set1_ps(f32::MIN_POSITIVE));
+        let a = _mm256_div_ps(num, den);
+        let s = _mm256_mul_ps(a, a);
+        let mut p = _mm256_set1_ps(ATAN_COEFFS[5]);
+        for &c in ATAN_COEFFS[..5].iter().rev() {
+            p = _mm256_add_ps(_mm256_mul_ps(p, s), _mm256_set1_ps(c));
+        }
+        let mut r = _mm256_mul_ps(p, a);
+        let steep = _mm256_cmp_ps::<_CMP_GT_OQ>(ay, ax);
+        r = _mm256_blendv_ps(
+            r,
+            _mm256_sub_ps(_mm256_set1_ps(std::f32::consts::FRAC_PI_2), r),
+            steep,
+        );
+        // blendv selects on the sign bit, so x = -0.0 counts as the left half plane like atan2.
+        r = _mm256_blendv_ps(r, _mm256_sub_ps(_mm256_set1_ps(std::f32::consts::PI), r), x);
+        _mm256_or_ps(r, _mm256_and_ps(y, sign_mask))
+    }
+
+    /// `out[k - 1]` for `k` in `1..iq.len()`.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn fm_discriminator(iq: &[Complex32], out: &mut [f32]) -> usize {
+        let src = as_floats(iq);
+        let chunks = (iq.len() - 1) / 8;
+        for c in 0..chunks {
+            let mut d = [_mm256_setzero_ps(); 2];
+            for (h, dh) in d.iter_mut().enumerate() {
+                let k = 1 + c * 8 + h * 4;
+                let cur = _mm256_loadu_ps(src.add(k * 2));
+                let prev = _mm256_loadu_ps(src.add((k - 1) * 2));
+                // cur * conj(prev): re = cr*pr + ci*pi, im = ci*pr - cr*pi.
+                let t1 = _mm256_mul_ps(cur, _mm256_moveldup_ps(prev));
+                let t2 = _mm256_mul_ps(
+                    _mm256_permute_ps::<0b10_11_00_01>(cur),
+                    _mm256_movehdup_ps(prev),
+                );
+                *dh = _mm256_addsub_ps(t1, _mm256_xor_ps(t2, _mm256_set1_ps(-0.0)));
+            }
+            let re = _mm256_shuffle_ps::<0b10_00_10_00>(d[0], d[1]);
+            let im = _mm256_shuffle_ps::<0b11_01_11_01>(d[0], d[1]);
+            let arg = atan2(im, re);
+            let ordered = _mm256_castpd_ps(_mm256_permute4x64_pd::<0b11_01_10_00>(
+                _mm256_castps_pd(arg),
+            ));
+            _mm256_storeu_ps(out.as_mut_ptr().add(c * 8), ordered);
+        }
+        chunks * 8
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn float_to_i16(samples: &[f32], out: &mut [i16], mult: f32) -> usize {
+        let m = _mm256_set1_ps(mult);
+        let offset = _mm256_set1_ps(32768.5);
+        let lo = _mm256_setzero_ps();
+        let hi = _mm256_set1_ps(65535.0);
+        let bias = _mm256_set1_epi32(32768);
+        let chunks = samples.len() / 16;
+        for c in 0..chunks {
+            let mut ints = [_mm256_setzero_si256(); 2];
+            for (h, dst) in ints.iter_mut().enumerate() {
+                let x = _mm256_loadu_ps(samples.as_ptr().add(c * 16 + h * 8));
+                let v = _mm256_floor_ps(_mm256_add_ps(_mm256_mul_ps(x, m), offset));
+                // max(v, 0) maps NaN to 0, like `as i32` does.
+                let v = _mm256_min_ps(_mm256_max_ps(v, lo), hi);
+                *dst = _mm256_sub_epi32(_mm256_cvtps_epi32(v), bias);
+            }
+            let packed =
+                _mm256_permute4x64_epi64::<0b11_01_10_00>(_mm256_packs_epi32(ints[0], ints[1]));
+            _mm256_storeu_si256(out.as_mut_ptr().add(c * 16) as *mut __m256i, packed);
+        }
+        chunks * 16
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn u8_to_f32(raw: &[u8], out: &mut [f32]) -> usize {
+        let bias = _mm256_set1_epi32(128);
+        let scale = _mm256_set1_ps(1.0 / 128.0);
+        let chunks = raw.len() / 8;
+        for c in 0..chunks {
+            let bytes = _mm_loadl_epi64(raw.as_ptr().add(c * 8) as *const __m128i);
+            let ints = _mm256_sub_epi32(_mm256_cvtepu8_epi32(bytes), bias);
+            let v = _mm256_mul_ps(_mm256_cvtepi32_ps(ints), scale);
+            _mm256_storeu_ps(out.as_mut_ptr().add(c * 8), v);
+        }
+        chunks * 8
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn i16_to_f32(raw: &[i16], out: &mut [f32]) -> usize {
+        let scale = _mm256_set1_ps(1.0 / 32768.0);
+        let chunks = raw.len() / 8;
+        for c in 0..chunks {
+            let words = _mm_loadu_si128(raw.as_ptr().add(c * 8) as *const __m128i);
+            let v = _mm256_mul_ps(_mm256_cvtepi32_ps(_mm256_cvtepi16_epi32(words)), scale);
+            _mm256_storeu_ps(out.as_mut_ptr().add(c * 8), v);
+        }
+        chunks * 8
+    }
+}
+
+/// Same contract as the x86 kernels, with 4-wide NEON vectors.
+#[cfg(target_arch = "aarch64")]
+mod arm {
+    use super::{as_floats, ATAN_COEFFS};
+    use num_complex::Complex32;
+    use std::arch::aarch64::*;
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn am_envelope(iq: &[Complex32], out: &mut [f32]) -> usize {
+        let src = as_floats(iq);
+        let chunks = iq.len() / 4;
+        for c in 0..chunks {
+            let v = vld2q_f32(src.add(c * 8));
+            let power = vaddq_f32(vmulq_f32(v.0, v.0), vmulq_f32(v.1, v.1));
+            vst1q_f32(out.as_mut_ptr().add(c * 4), vsqrtq_f32(power));
+        }
+        chunks * 4
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn atan2(y: float32x4_t, x: float32x4_t) -> float32x4_t {
+        let ax = vabsq_f32(x);
+        let ay = vabsq_f32(y);
+        let num = vminq_f32(ax, ay);
+        let den = vmaxq_f32(vmaxq_f32(ax, ay), vdupq_n_f32(f32::MIN_POSITIVE));
+        let a = vdivq_f32(num, den);
+        let s = vmulq_f32(a, a);
+        let mut p = vdupq_n_f32(ATAN_COEFFS[5]);
+        for &c in ATAN_COEFFS[..5].iter().rev() {
+            p = vaddq_f32(vmulq_f32(p, s), vdupq_n_f32(c));
+        }
+        let mut r = vmulq_f32(p, a);
+        r = vbslq_f32(
+            vcgtq_f32(ay, ax),
+            vsubq_f32(vdupq_n_f32(std::f32::consts::FRAC_PI_2), r),
+            r,
+        );
+        // Select on the sign bit, so x = -0.0 counts as the left half plane like atan2.
+        let left = vreinterpretq_u32_s32(vshrq_n_s32::<31>(vreinterpretq_s32_f32(x)));
+        r = vbslq_f32(left, vsubq_f32(vdupq_n_f32(std::f32::consts::PI), r), r);
+        let sign = vandq_u32(vreinterpretq_u32_f32(y), vdupq_n_u32(0x8000_0000));
+        vreinterpretq_f32_u32(vorrq_u32(vreinterpretq_u32_f32(r), sign))
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn fm_discriminator(iq: &[Complex32], out: &mut [f32]) -> usize {
+        let src = as_floats(iq);
+        let chunks = (iq.len() - 1) / 4;
+        for c in 0..chunks {
+            let k = 1 + c * 4;
+            let cur = vld2q_f32(src.add(k * 2));
+            let prev = vld2q_f32(src.add((k - 1) * 2));
+            let re = vaddq_f32(vmulq_f32(cur.0, prev.0), vmulq_f32(cur.1, prev.1));
+            let im = vsubq_f32(vmulq_f32(cur.1, prev.0), vmulq_f32(cur.0, prev.1));
+            vst1q_f32(out.as_mut_ptr().add(c * 4), atan2(im, re));
+        }
+        chunks * 4
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn float_to_i16(samples: &[f32], out: &mut [i16], mult: f32) -> usize {
+        let m = vdupq_n_f32(mult);
+        let offset = vdupq_n_f32(32768.5);
+        let lo = vdupq_n_f32(0.0);
+        let hi = vdupq_n_f32(65535.0);
+        let bias = vdupq_n_s32(32768);
+        let chunks = samples.len() / 4;
+        for c in 0..chunks {
+            let v = vrndmq_f32(vaddq_f32(
+                vmulq_f32(vld1q_f32(samples.as_ptr().add(c * 4)), m),
+                offset,
+            ));
+            // maxnm maps NaN to 0, like `as i32` does.
+            let v = vminnmq_f32(vmaxnmq_f32(v, lo), hi);
+            let ints = vsubq_s32(vcvtq_s32_f32(v), bias);
+            vst1_s16(out.as_mut_ptr().add(c * 4), vqmovn_s32(ints));
+        }
+        chunks * 4
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn u8_to_f32(raw: &[u8], out: &mut [f32]) -> usize {
+        let bias = vdupq_n_s32(128);
+        let scale = vdupq_n_f32(1.0 / 128.0);
+        let chunks = raw.len() / 8;
+        for c in 0..chunks {
+            let wide = vmovl_u8(vld1_u8(raw.as_ptr().add(c * 8)));
+            for (h, half) in [vget_low_u16(wide), vget_high_u16(wide)]
+                .into_iter()
+                .enumerate()
+            {
+                let ints = vsubq_s32(vreinterpretq_s32_u32(vmovl_u16(half)), bias);
+                let v = vmulq_f32(vcvtq_f32_s32(ints), scale);
+                vst1q_f32(out.as_mut_ptr().add(c * 8 + h * 4), v);
+            }
+        }
+        chunks * 8
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn i16_to_f32(raw: &[i16], out: &mut [f32]) -> usize {
+        let scale = vdupq_n_f32(1.0 / 32768.0);
+        let chunks = raw.len() / 4;
+        for c in 0..chunks {
+            let ints = vmovl_s16(vld1_s16(raw.as_ptr().add(c * 4)));
+            vst1q_f32(
+                out.as_mut_ptr().add(c * 4),
+                vmulq_f32(vcvtq_f32_s32(ints), scale),
+            );
+        }
+        chunks * 4
+    }
+}
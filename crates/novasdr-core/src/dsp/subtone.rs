@@ -0,0 +1,394 @@
+//! Sub-audible signalling on NBFM channels: CTCSS tones and DCS codes, detected from the
+//! discriminator output below 300 Hz.
+
+use crate::dsp::fir::{lowpass_taps, FirDecimator};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// CTCSS tones in tenths of a hertz: the EIA set plus the common extras.
+pub const CTCSS_TONES: [u16; 51] = [
+    670, 693, 719, 744, 770, 797, 825, 854, 885, 915, 948, 974, 1000, 1035, 1072, 1109, 1148, 1188,
+    1230, 1273, 1318, 1365, 1413, 1462, 1500, 1514, 1567, 1598, 1622, 1655, 1679, 1713, 1738, 1773,
+    1799, 1835, 1862, 1899, 1928, 1966, 1995, 2035, 2065, 2107, 2181, 2257, 2291, 2336, 2418, 2503,
+    2541,
+];
+
+/// The standard DCS codes, as octal numbers.
+pub const DCS_CODES: [u16; 104] = [
+    0o023, 0o025, 0o026, 0o031, 0o032, 0o036, 0o043, 0o047, 0o051, 0o053, 0o054, 0o065, 0o071,
+    0o072, 0o073, 0o074, 0o114, 0o115, 0o116, 0o122, 0o125, 0o131, 0o132, 0o134, 0o143, 0o145,
+    0o152, 0o155, 0o156, 0o162, 0o165, 0o172, 0o174, 0o205, 0o212, 0o223, 0o225, 0o226, 0o243,
+    0o244, 0o245, 0o246, 0o251, 0o252, 0o255, 0o261, 0o263, 0o265, 0o266, 0o271, 0o274, 0o306,
+    0o311, 0o315, 0o325, 0o331, 0o332, 0o343, 0o346, 0o351, 0o356, 0o364, 0o365, 0o371, 0o411,
+    0o412, 0o413, 0o423, 0o431, 0o432, 0o445, 0o446, 0o452, 0o454, 0o455, 0o462, 0o464, 0o465,
+    0o466, 0o503, 0o506, 0o516, 0o523, 0o526, 0o532, 0o546, 0o565, 0o606, 0o612, 0o624, 0o627,
+    0o631, 0o632, 0o654, 0o662, 0o664, 0o703, 0o712, 0o723, 0o731, 0o732, 0o734, 0o743, 0o754,
+];
+
+/// DCS signals at 134.4 bit/s, one 23-bit Golay word repeated back to back.
+pub const DCS_BIT_RATE: f32 = 134.4;
+const DCS_WORD_BITS: u64 = 23;
+const GOLAY_POLY: u32 = 0xC75;
+
+/// Sub-audio band, and the rate it is analysed at (at least this, at most twice it).
+const LOWPASS_HZ: f32 = 300.0;
+const LOWPASS_TRANSITION_HZ: f32 = 200.0;
+const ANALYSIS_RATE: usize = 1_000;
+/// CTCSS window: one second resolves the closest tones (150.0 and 151.4 Hz).
+const WINDOW_SECONDS: f32 = 1.0;
+const HOPS_PER_WINDOW: usize = 4;
+/// Share of the sub-audio energy a tone needs to be detected, and to stay detected under speech.
+const LOCK_RATIO: f32 = 0.4;
+const HOLD_RATIO: f32 = 0.15;
+/// DCS clock loop gain, and words without the code before it is lost.
+const DCS_TIMING_GAIN: f32 = 0.2;
+const DCS_MISSED_WORDS: u32 = 3;
+/// Time constant of the DC (tuning offset) removal before the DCS slicer.
+const DC_SECONDS: f32 = 1.0;
+
+/// A CTCSS tone or a DCS code. Written as `"88.5"` for a tone and `"D023N"` / `"D023I"` for a
+/// code with normal or inverted polarity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Subtone {
+    /// Tone frequency in tenths of a hertz.
+    Ctcss(u16),
+    /// Code as an octal number, e.g. `0o023`.
+    Dcs { code: u16, inverted: bool },
+}
+
+impl fmt::Display for Subtone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Subtone::Ctcss(tenths) => write!(f, "{}.{}", tenths / 10, tenths % 10),
+            Subtone::Dcs { code, inverted } => {
+                write!(f, "D{code:03o}{}", if inverted { 'I' } else { 'N' })
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Subtone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(rest) = s.strip_prefix(['D', 'd']) {
+            let (digits, inverted) = match rest.char_indices().last() {
+                Some((i, 'N' | 'n')) => (&rest[..i], false),
+                Some((i, 'I' | 'i')) => (&rest[..i], true),
+                _ => (rest, false),
+            };
+            let code = u16::from_str_radix(digits, 8)
+                .map_err(|_| anyhow::anyhow!("bad DCS code {s:?}"))?;
+            anyhow::ensure!(
+                DCS_CODES.contains(&code),
+                "{s:?} is not a standard DCS code"
+            );
+            return Ok(Subtone::Dcs { code, inverted });
+        }
+        let hz: f32 = s
+            .parse()
+            .map_err(|_| anyhow::anyhow!("bad CTCSS tone {s:?}"))?;
+        let tenths = (hz * 10.0).round() as u16;
+        anyhow::ensure!(
+            CTCSS_TONES.contains(&tenths),
+            "{s:?} is not a standard CTCSS tone"
+        );
+        Ok(Subtone::Ctcss(tenths))
+    }
+}
+
+impl From<Subtone> for String {
+    fn from(tone: Subtone) -> Self {
+        tone.to_string()
+    }
+}
+
+impl TryFrom<String> for Subtone {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// The 23-bit Golay word for 12 data bits, parity in the top 11 bits.
+fn golay(data: u32) -> u32 {
+    let data = data & 0xFFF;
+    let mut cw = data;
+    for _ in 0..12 {
+        if cw & 1 != 0 {
+            cw ^= GOLAY_POLY;
+        }
+        cw >>= 1;
+    }
+    (cw << 12) | data
+}
+
+/// The word a DCS code is sent as, first bit in bit 0: the 9 code bits, `100`, then parity.
+pub fn dcs_word(code: u16) -> u32 {
+    golay(0x800 | u32::from(code & 0x1FF))
+}
+
+/// The standard code carried by `word`, if it is one. Words are cyclic, so a stream of one code
+/// holds other codes at other bit offsets; those are rarely standard ones.
+fn dcs_code(word: u32) -> Option<u16> {
+    let data = word & 0xFFF;
+    if data >> 9 != 0b100 || golay(data) != word {
+        return None;
+    }
+    let code = (data & 0x1FF) as u16;
+    DCS_CODES.contains(&code).then_some(code)
+}
+
+/// Bit slicer and word matcher for DCS.
+struct DcsSlicer {
+    step: f32,
+    phase: f32,
+    dc: f32,
+    dc_alpha: f32,
+    sign: bool,
+    bits: u32,
+    index: u64,
+    /// Recent matches: the code, its polarity and the bit it ended on.
+    hits: Vec<(u16, bool, u64)>,
+    code: Option<(u16, bool)>,
+    missed: u32,
+}
+
+impl DcsSlicer {
+    fn new(rate: f32) -> Self {
+        Self {
+            step: DCS_BIT_RATE / rate,
+            phase: 0.0,
+            dc: 0.0,
+            dc_alpha: 1.0 / (DC_SECONDS * rate),
+            sign: false,
+            bits: 0,
+            index: 0,
+            hits: Vec::new(),
+            code: None,
+            missed: 0,
+        }
+    }
+
+    fn push(&mut self, x: f32) {
+        self.dc += (x - self.dc) * self.dc_alpha;
+        let sign = x > self.dc;
+        let before = self.phase;
+        self.phase += self.step;
+        if sign != self.sign {
+            // Bit edges belong at phase 0.
+            let err = if self.phase < 0.5 {
+                self.phase
+            } else {
+                self.phase - 1.0
+            };
+            self.phase -= DCS_TIMING_GAIN * err;
+            self.sign = sign;
+        }
+        if before < 0.5 && self.phase >= 0.5 {
+            self.bit(sign);
+        }
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        } else if self.phase < 0.0 {
+            self.phase += 1.0;
+        }
+    }
+
+    fn bit(&mut self, bit: bool) {
+        self.bits = (self.bits >> 1) | (u32::from(bit) << 22);
+        self.index += 1;
+        let i = self.index;
+        if i >= DCS_WORD_BITS {
+            for inverted in [false, true] {
+                let word = if inverted {
+                    !self.bits & 0x7F_FFFF
+                } else {
+                    self.bits
+                };
+                if let Some(code) = dcs_code(word) {
+                    self.hits.push((code, inverted, i));
+                }
+            }
+        }
+        self.hits.retain(|&(_, _, at)| at + 2 * DCS_WORD_BITS > i);
+        if !i.is_multiple_of(DCS_WORD_BITS) {
+            return;
+        }
+        // A code counts once it was seen a word apart. The same signal reads as a code and, at
+        // another offset with the other polarity, as its twin; normal polarity wins.
+        let confirmed = self
+            .hits
+            .iter()
+            .filter(|&&(code, inverted, at)| {
+                at + DCS_WORD_BITS > i
+                    && self
+                        .hits
+                        .iter()
+                        .any(|&h| h == (code, inverted, at - DCS_WORD_BITS))
+            })
+            .map(|&(code, inverted, _)| (code, inverted));
+        let best = confirmed
+            .clone()
+            .find(|&c| Some(c) == self.code)
+            .or_else(|| confirmed.min_by_key(|&(code, inverted)| (inverted, code)));
+        match best {
+            Some(c) => {
+                self.code = Some(c);
+                self.missed = 0;
+            }
+            None => {
+                self.missed += 1;
+                if self.missed >= DCS_MISSED_WORDS {
+                    self.code = None;
+                }
+            }
+        }
+    }
+}
+
+/// Detects the CTCSS tone or DCS code in FM discriminator output.
+pub struct SubtoneDetector {
+    filter: FirDecimator<f32>,
+    filtered: Vec<f32>,
+    /// The last `window.len()` sub-audio samples, oldest at `pos`.
+    window: Vec<f32>,
+    pos: usize,
+    filled: usize,
+    hop: usize,
+    since_hop: usize,
+    hann: Vec<f32>,
+    scratch: Vec<f32>,
+    /// Goertzel coefficient `2 cos ω` per tone.
+    coeffs: Vec<f32>,
+    ctcss: Option<usize>,
+    candidate: Option<usize>,
+    dcs: DcsSlicer,
+    tone: Option<Subtone>,
+}
+
+impl SubtoneDetector {
+    pub fn new(sample_rate: usize) -> Self {
+        let decimation = (sample_rate / ANALYSIS_RATE).max(1);
+        let input_rate = sample_rate as f32;
+        let rate = sample_rate as f32 / decimation as f32;
+        let taps = lowpass_taps(
+            (LOWPASS_HZ / input_rate).min(0.45),
+            LOWPASS_TRANSITION_HZ / input_rate,
+        );
+        let n = (rate * WINDOW_SECONDS) as usize;
+        let hann = (0..n)
+            .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / n as f32).cos())
+            .collect();
+        let coeffs = CTCSS_TONES
+            .iter()
+            .map(|&t| 2.0 * (std::f32::consts::TAU * f32::from(t) / 10.0 / rate).cos())
+            .collect();
+        Self {
+            filter: FirDecimator::new(taps, decimation),
+            filtered: Vec::new(),
+            window: vec![0.0; n],
+            pos: 0,
+            filled: 0,
+            hop: n / HOPS_PER_WINDOW,
+            since_hop: 0,
+            hann,
+            scratch: Vec::with_capacity(n),
+            coeffs,
+            ctcss: None,
+            candidate: None,
+            dcs: DcsSlicer::new(rate),
+            tone: None,
+        }
+    }
+
+    /// Runs over one frame of discriminator output; true when the detected tone changed.
+    pub fn process(&mut self, input: &[f32]) -> bool {
+        self.filtered.clear();
+        self.filter.process(input, &mut self.filtered);
+        for i in 0..self.filtered.len() {
+            let x = self.filtered[i];
+            self.dcs.push(x);
+            self.window[self.pos] = x;
+            self.pos = (self.pos + 1) % self.window.len();
+            self.filled = (self.filled + 1).min(self.window.len());
+            self.since_hop += 1;
+            if self.since_hop >= self.hop && self.filled == self.window.len() {
+                self.since_hop = 0;
+                self.update_ctcss();
+            }
+        }
+        let tone = match (self.dcs.code, self.ctcss) {
+            (Some((code, inverted)), _) => Some(Subtone::Dcs { code, inverted }),
+            (None, Some(i)) => Some(Subtone::Ctcss(CTCSS_TONES[i])),
+            (None, None) => None,
+        };
+        let changed = tone != self.tone;
+        self.tone = tone;
+        changed
+    }
+
+    fn update_ctcss(&mut self) {
+        let n = self.window.len();
+        self.scratch.clear();
+        self.scratch.extend(
+            self.window[self.pos..]
+                .iter()
+                .chain(&self.window[..self.pos]),
+        );
+        let mean = self.scratch.iter().sum::<f32>() / n as f32;
+        let mut energy = 0.0;
+        for (x, w) in self.scratch.iter_mut().zip(&self.hann) {
+            *x = (*x - mean) * w;
+            energy += *x * *x;
+        }
+        if energy <= f32::MIN_POSITIVE {
+            self.ctcss = None;
+            self.candidate = None;
+            return;
+        }
+        // A Hann-windowed sinusoid puts N/3 of its windowed energy in its Goertzel power.
+        let norm = 3.0 / (n as f32 * energy);
+        let ratios: Vec<f32> = self
+            .coeffs
+            .iter()
+            .map(|&c| {
+                let (mut s1, mut s2) = (0.0f32, 0.0f32);
+                for &x in &self.scratch {
+                    let s = x + c * s1 - s2;
+                    s2 = s1;
+                    s1 = s;
+                }
+                (s1 * s1 + s2 * s2 - c * s1 * s2) * norm
+            })
+            .collect();
+        let best = ratios
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .filter(|&(_, &r)| r >= LOCK_RATIO)
+            .map(|(i, _)| i);
+
+        if let Some(held) = self.ctcss {
+            if ratios[held] >= HOLD_RATIO && best.is_none_or(|b| b == held) {
+                self.candidate = None;
+                return;
+            }
+        }
+        // A new tone has to win two windows in a row.
+        if best.is_some() && best == self.candidate {
+            self.ctcss = best;
+        } else if self.ctcss.is_some_and(|held| ratios[held] < HOLD_RATIO) {
+            self.ctcss = None;
+        }
+        self.candidate = best;
+    }
+
+    /// The tone or code currently present.
+    pub fn tone(&self) -> Option<Subtone> {
+        self.tone
+    }
+}
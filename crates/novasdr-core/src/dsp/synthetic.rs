@@ -0,0 +1,399 @@
+//! Samples for the `synthetic` input: a [`SyntheticScenario`] rendered as the raw `f32` stream a
+//! receiver reads, the same on every run for one seed.
+//!
+//! SSB and FM are produced by the transmit [`Modulator`] at [`ENVELOPE_RATE`], and noise bands
+//! as complex noise at their own width; both are brought up to the input rate by linear
+//! interpolation and mixed onto their carrier. That keeps the cost per input sample at a few
+//! operations per signal, with the interpolation images more than 40 dB down.
+
+use crate::config::{Sideband, SyntheticScenario, SyntheticSignal};
+use crate::dsp::tx::{Modulator, TxMode, TX_AUDIO_RATE};
+use anyhow::Context;
+use rustfft::num_complex::Complex32;
+use std::f32::consts::{PI, TAU};
+
+/// Rate the modulated envelopes are generated at before interpolation.
+const ENVELOPE_RATE: usize = 48_000;
+/// Audio rendered per modulator call, in samples at [`TX_AUDIO_RATE`].
+const AUDIO_BLOCK: usize = TX_AUDIO_RATE / 50;
+const NOISE_BLOCK: usize = 1024;
+/// Samples between renormalizations of the carrier oscillators.
+const RENORMALIZE_EVERY: usize = 1024;
+/// Length and peak of the built-in voice clip.
+const VOICE_SECONDS: usize = 6;
+const VOICE_PEAK: f32 = 0.8;
+/// Formants (F1, F2, F3) of the vowels the built-in voice strings into syllables.
+const VOWELS: [[f32; 3]; 5] = [
+    [730.0, 1_090.0, 2_440.0],
+    [530.0, 1_840.0, 2_480.0],
+    [270.0, 2_290.0, 3_010.0],
+    [570.0, 840.0, 2_410.0],
+    [300.0, 870.0, 2_240.0],
+];
+const FORMANT_BANDWIDTHS: [f32; 3] = [90.0, 110.0, 170.0];
+
+pub struct Generator {
+    is_real: bool,
+    noise: Noise,
+    /// Standard deviation of each component of the noise floor.
+    noise_sigma: f32,
+    sources: Vec<Source>,
+    mix: Vec<Complex32>,
+}
+
+impl Generator {
+    /// `center` and `sample_rate` are the input's `frequency` and `sps`. Scenario frequencies
+    /// are offset from `center`, which is the middle of an IQ input and the bottom of a real one.
+    pub fn new(
+        scenario: &SyntheticScenario,
+        center: i64,
+        sample_rate: usize,
+        is_real: bool,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(sample_rate > 0, "synthetic input needs sps > 0");
+        let fs = sample_rate as f64;
+        // A real output keeps half the power of a complex one.
+        let real_gain = if is_real { 2f32.sqrt() } else { 1.0 };
+        let mut sources = Vec::with_capacity(scenario.signals.len() + scenario.noise_bands.len());
+        for (n, signal) in scenario.signals.iter().enumerate() {
+            let seed = scenario.seed.wrapping_add(n as u64 + 1);
+            let envelope = match signal {
+                SyntheticSignal::Carrier { .. } => None,
+                SyntheticSignal::Ssb {
+                    sideband, audio, ..
+                } => {
+                    let mode = match sideband {
+                        Sideband::Usb => TxMode::Usb,
+                        Sideband::Lsb => TxMode::Lsb,
+                    };
+                    let clip = match audio {
+                        Some(path) => read_audio(path)?,
+                        None => synthesize_voice(&mut Noise::new(seed)),
+                    };
+                    Some(Envelope::modulated(
+                        mode,
+                        Audio::Clip { clip, cursor: 0 },
+                        fs,
+                    ))
+                }
+                SyntheticSignal::Fm { tone_hz, .. } => Some(Envelope::modulated(
+                    TxMode::Fm,
+                    Audio::Tone {
+                        phase: 0.0,
+                        step: tone_hz / TX_AUDIO_RATE as f32,
+                    },
+                    fs,
+                )),
+            };
+            sources.push(Source::new(
+                signal.frequency() - center,
+                db_to_amplitude(signal.level_db()),
+                envelope,
+                fs,
+            ));
+        }
+        for (n, band) in scenario.noise_bands.iter().enumerate() {
+            let width = (band.end - band.start) as f64;
+            // Half the power per component, scaled up for the third of it that interpolating
+            // between noise samples loses on average.
+            let sigma = db_to_amplitude(band.level_db) * real_gain * (0.75f32).sqrt();
+            let seed = scenario
+                .seed
+                .wrapping_add((scenario.signals.len() + n) as u64 + 1);
+            sources.push(Source::new(
+                (band.start + band.end) / 2 - center,
+                1.0,
+                Some(Envelope::new(
+                    EnvelopeKind::Noise {
+                        noise: Noise::new(seed),
+                        sigma,
+                    },
+                    width.min(fs) / fs,
+                )),
+                fs,
+            ));
+        }
+        let floor = db_to_amplitude(scenario.noise_floor_db);
+        Ok(Self {
+            is_real,
+            noise: Noise::new(scenario.seed),
+            noise_sigma: if is_real { floor } else { floor / 2f32.sqrt() },
+            sources,
+            mix: Vec::new(),
+        })
+    }
+
+    /// Fills `out` with the next samples: real values, or interleaved I/Q pairs for an IQ input
+    /// (`out.len()` even).
+    pub fn fill(&mut self, out: &mut [f32]) {
+        let n = if self.is_real {
+            out.len()
+        } else {
+            out.len() / 2
+        };
+        self.mix.clear();
+        self.mix.resize(n, Complex32::new(0.0, 0.0));
+        for source in self.sources.iter_mut() {
+            source.add_to(&mut self.mix);
+        }
+        let sigma = self.noise_sigma;
+        if self.is_real {
+            for (o, z) in out.iter_mut().zip(self.mix.iter()) {
+                *o = z.re + sigma * self.noise.gaussian();
+            }
+        } else {
+            for (o, z) in out.chunks_exact_mut(2).zip(self.mix.iter()) {
+                o[0] = z.re + sigma * self.noise.gaussian();
+                o[1] = z.im + sigma * self.noise.gaussian();
+            }
+        }
+    }
+}
+
+fn db_to_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// One signal or noise band on its carrier.
+struct Source {
+    oscillator: Complex32,
+    rotation: Complex32,
+    amplitude: f32,
+    envelope: Option<Envelope>,
+}
+
+impl Source {
+    fn new(offset_hz: i64, amplitude: f32, envelope: Option<Envelope>, fs: f64) -> Self {
+        let step = (offset_hz as f64 / fs).rem_euclid(1.0);
+        Self {
+            oscillator: Complex32::new(1.0, 0.0),
+            rotation: Complex32::from_polar(1.0, TAU * step as f32),
+            amplitude,
+            envelope,
+        }
+    }
+
+    fn add_to(&mut self, mix: &mut [Complex32]) {
+        for block in mix.chunks_mut(RENORMALIZE_EVERY) {
+            for z in block.iter_mut() {
+                let envelope = match self.envelope.as_mut() {
+                    Some(e) => e.sample(),
+                    None => Complex32::new(1.0, 0.0),
+                };
+                *z += envelope * self.oscillator * self.amplitude;
+                self.oscillator *= self.rotation;
+            }
+            self.oscillator /= self.oscillator.norm();
+        }
+    }
+}
+
+/// A complex envelope generated at a low rate and linearly interpolated to the input rate.
+struct Envelope {
+    kind: EnvelopeKind,
+    /// Envelope samples per input sample.
+    step: f64,
+    /// Position between `previous` and `next`, in envelope samples.
+    position: f64,
+    previous: Complex32,
+    next: Complex32,
+    buffer: Vec<Complex32>,
+    read: usize,
+}
+
+enum EnvelopeKind {
+    Modulated {
+        modulator: Box<Modulator>,
+        audio: Audio,
+        block: Vec<f32>,
+    },
+    Noise {
+        noise: Noise,
+        sigma: f32,
+    },
+}
+
+enum Audio {
+    /// Looped.
+    Clip { clip: Vec<f32>, cursor: usize },
+    /// Phase and step in cycles.
+    Tone { phase: f32, step: f32 },
+}
+
+impl Envelope {
+    fn new(kind: EnvelopeKind, step: f64) -> Self {
+        Self {
+            kind,
+            step,
+            position: 0.0,
+            previous: Complex32::new(0.0, 0.0),
+            next: Complex32::new(0.0, 0.0),
+            buffer: Vec::new(),
+            read: 0,
+        }
+    }
+
+    fn modulated(mode: TxMode, audio: Audio, fs: f64) -> Self {
+        Self::new(
+            EnvelopeKind::Modulated {
+                modulator: Box::new(Modulator::new(mode, ENVELOPE_RATE)),
+                audio,
+                block: Vec::with_capacity(AUDIO_BLOCK),
+            },
+            ENVELOPE_RATE as f64 / fs,
+        )
+    }
+
+    fn sample(&mut self) -> Complex32 {
+        let z = self.previous + (self.next - self.previous) * self.position as f32;
+        self.position += self.step;
+        while self.position >= 1.0 {
+            self.position -= 1.0;
+            self.previous = self.next;
+            self.next = self.pull();
+        }
+        z
+    }
+
+    fn pull(&mut self) -> Complex32 {
+        if self.read == self.buffer.len() {
+            self.buffer.clear();
+            self.read = 0;
+            self.kind.render(&mut self.buffer);
+        }
+        self.read += 1;
+        self.buffer[self.read - 1]
+    }
+}
+
+impl EnvelopeKind {
+    /// Appends at least one envelope sample to `out`.
+    fn render(&mut self, out: &mut Vec<Complex32>) {
+        match self {
+            EnvelopeKind::Modulated {
+                modulator,
+                audio,
+                block,
+            } => {
+                block.clear();
+                match audio {
+                    Audio::Clip { clip, cursor } => {
+                        for _ in 0..AUDIO_BLOCK {
+                            block.push(clip[*cursor]);
+                            *cursor = (*cursor + 1) % clip.len();
+                        }
+                    }
+                    Audio::Tone { phase, step } => {
+                        for _ in 0..AUDIO_BLOCK {
+                            block.push((TAU * *phase).sin());
+                            *phase = (*phase + *step).fract();
+                        }
+                    }
+                }
+                modulator.process(block, out);
+            }
+            EnvelopeKind::Noise { noise, sigma } => {
+                for _ in 0..NOISE_BLOCK {
+                    out.push(Complex32::new(
+                        *sigma * noise.gaussian(),
+                        *sigma * noise.gaussian(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Mono s16le at [`TX_AUDIO_RATE`].
+fn read_audio(path: &str) -> anyhow::Result<Vec<f32>> {
+    let bytes = std::fs::read(path).with_context(|| format!("read synthetic audio {path:?}"))?;
+    let clip: Vec<f32> = bytes
+        .chunks_exact(2)
+        .map(|b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0)
+        .collect();
+    anyhow::ensure!(!clip.is_empty(), "synthetic audio {path:?} is empty");
+    Ok(clip)
+}
+
+/// A voice-like clip at [`TX_AUDIO_RATE`]: words of vowel syllables from a pulse train through
+/// formant resonators, with falling pitch and pauses between words.
+fn synthesize_voice(noise: &mut Noise) -> Vec<f32> {
+    let rate = TX_AUDIO_RATE as f32;
+    let total = TX_AUDIO_RATE * VOICE_SECONDS;
+    let syllable = TX_AUDIO_RATE * 22 / 100;
+    let mut out = Vec::with_capacity(total + TX_AUDIO_RATE);
+    let mut glottis = 0.0f32;
+    let mut resonators = [(0.0f32, 0.0f32); 3];
+    while out.len() < total {
+        let syllables = 2 + (noise.next_u64() % 4) as usize;
+        let pitch = 100.0 + (noise.next_u64() % 40) as f32;
+        for s in 0..syllables {
+            let formants = VOWELS[(noise.next_u64() % VOWELS.len() as u64) as usize];
+            let coefficients: Vec<(f32, f32, f32)> = formants
+                .iter()
+                .zip(FORMANT_BANDWIDTHS)
+                .map(|(&f, bw)| {
+                    let r = (-PI * bw / rate).exp();
+                    (1.0 - r, 2.0 * r * (TAU * f / rate).cos(), -r * r)
+                })
+                .collect();
+            let f0 = pitch * (1.0 - 0.2 * s as f32 / syllables as f32);
+            for k in 0..syllable {
+                glottis += f0 / rate;
+                let excitation = if glottis >= 1.0 {
+                    glottis -= 1.0;
+                    1.0
+                } else {
+                    0.0
+                };
+                let mut y = 0.0;
+                for ((gain, a1, a2), (y1, y2)) in coefficients.iter().zip(resonators.iter_mut()) {
+                    let v = gain * excitation + a1 * *y1 + a2 * *y2;
+                    *y2 = *y1;
+                    *y1 = v;
+                    y += v;
+                }
+                out.push((PI * k as f32 / syllable as f32).sin() * y);
+            }
+        }
+        let pause = TX_AUDIO_RATE * (3 + (noise.next_u64() % 4) as usize) / 10;
+        out.resize(out.len() + pause, 0.0);
+    }
+    out.truncate(total);
+    let peak = out.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+    if peak > 0.0 {
+        for x in out.iter_mut() {
+            *x *= VOICE_PEAK / peak;
+        }
+    }
+    out
+}
+
+/// xorshift64*: fast and the same on every platform, which is all the scenario needs.
+struct Noise(u64);
+
+impl Noise {
+    fn new(seed: u64) -> Self {
+        // SplitMix64 spreads small seeds over the state; xorshift must not start at zero.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Self((z ^ (z >> 31)).max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Zero mean and unit variance, close to Gaussian: the sum of four 16-bit uniforms.
+    fn gaussian(&mut self) -> f32 {
+        let r = self.next_u64();
+        let sum = (r & 0xffff) + ((r >> 16) & 0xffff) + ((r >> 32) & 0xffff) + (r >> 48);
+        (sum as f32 / 65_536.0 - 2.0) * 3f32.sqrt()
+    }
+}
@@ -0,0 +1,181 @@
+//! Transmit modulation: operator audio at [`TX_AUDIO_RATE`] to complex baseband at the
+//! transmitter's sample rate, centred on the carrier.
+//!
+//! SSB uses the Weaver method: the audio is shifted down by the centre of the voice band, the
+//! negative-frequency image is cut off with a real low-pass and the result is shifted back up,
+//! which leaves one sideband without a Hilbert transformer. FM is generated after
+//! interpolation, at the output rate, so its sidebands are not folded by the audio rate.
+
+use crate::dsp::fir::{lowpass_taps, FirDecimator, FirInterpolator};
+use rustfft::num_complex::Complex32;
+use std::f32::consts::TAU;
+
+/// Sample rate of the audio the modulator takes.
+pub const TX_AUDIO_RATE: usize = 12_000;
+/// Peak deviation of full-scale audio in FM (narrow FM, 12.5 kHz channels).
+pub const FM_DEVIATION_HZ: f32 = 2_500.0;
+
+/// SSB voice band; the Weaver shift is its centre.
+const SSB_LOW_HZ: f32 = 300.0;
+const SSB_HIGH_HZ: f32 = 2_700.0;
+/// Transition of the sideband filter; narrower means deeper opposite-sideband suppression
+/// near the carrier at the cost of taps.
+const SSB_TRANSITION_HZ: f32 = 250.0;
+/// Highest audio frequency sent in FM, and the width of the filter's edge above it.
+const FM_AUDIO_HZ: f32 = 3_000.0;
+const FM_AUDIO_TRANSITION_HZ: f32 = 500.0;
+/// Pass band and transition of the interpolation filter, at the output rate; the stop band
+/// starts below half the audio rate so no image of the audio reaches the antenna.
+const INTERPOLATION_PASS_HZ: f32 = 3_500.0;
+const INTERPOLATION_TRANSITION_HZ: f32 = 2_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxMode {
+    Usb,
+    Lsb,
+    Fm,
+}
+
+impl TxMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_uppercase().as_str() {
+            "USB" => Some(Self::Usb),
+            "LSB" => Some(Self::Lsb),
+            "FM" => Some(Self::Fm),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Usb => "USB",
+            Self::Lsb => "LSB",
+            Self::Fm => "FM",
+        }
+    }
+
+    /// Lowest and highest offset from the carrier the emission occupies, in Hz; band limits
+    /// are checked against the carrier plus these.
+    pub fn occupied_hz(self) -> (i64, i64) {
+        match self {
+            Self::Usb => (0, 3_000),
+            Self::Lsb => (-3_000, 0),
+            // Carson's rule for the deviation and audio band above.
+            Self::Fm => (-5_500, 5_500),
+        }
+    }
+}
+
+/// One transmission's modulator. Output samples stay within the unit circle.
+pub struct Modulator {
+    mode: TxMode,
+    output_rate: f32,
+    sideband: FirDecimator<Complex32>,
+    fm_audio: FirDecimator<f32>,
+    interpolate: FirInterpolator<Complex32>,
+    interpolate_fm: FirInterpolator<f32>,
+    /// Phase of the Weaver oscillators, in cycles.
+    weaver_phase: f32,
+    fm_phase: f32,
+    shifted: Vec<Complex32>,
+    filtered: Vec<Complex32>,
+    audio: Vec<f32>,
+    upsampled: Vec<f32>,
+}
+
+impl Modulator {
+    /// `output_rate` must be a multiple of [`TX_AUDIO_RATE`].
+    pub fn new(mode: TxMode, output_rate: usize) -> Self {
+        let audio_rate = TX_AUDIO_RATE as f32;
+        let factor = (output_rate / TX_AUDIO_RATE).max(1);
+        let out = (TX_AUDIO_RATE * factor) as f32;
+        let half_band = (SSB_HIGH_HZ - SSB_LOW_HZ) / 2.0;
+        let interpolation_taps = lowpass_taps(
+            INTERPOLATION_PASS_HZ / out,
+            INTERPOLATION_TRANSITION_HZ / out,
+        );
+        Self {
+            mode,
+            output_rate: out,
+            sideband: FirDecimator::new(
+                lowpass_taps(half_band / audio_rate, SSB_TRANSITION_HZ / audio_rate),
+                1,
+            ),
+            fm_audio: FirDecimator::new(
+                lowpass_taps(
+                    FM_AUDIO_HZ / audio_rate,
+                    FM_AUDIO_TRANSITION_HZ / audio_rate,
+                ),
+                1,
+            ),
+            interpolate: FirInterpolator::new(interpolation_taps.clone(), factor),
+            interpolate_fm: FirInterpolator::new(interpolation_taps, factor),
+            weaver_phase: 0.0,
+            fm_phase: 0.0,
+            shifted: Vec::new(),
+            filtered: Vec::new(),
+            audio: Vec::new(),
+            upsampled: Vec::new(),
+        }
+    }
+
+    pub fn mode(&self) -> TxMode {
+        self.mode
+    }
+
+    /// Modulates `audio` (at [`TX_AUDIO_RATE`], full scale ±1) and appends the baseband to `out`.
+    pub fn process(&mut self, audio: &[f32], out: &mut Vec<Complex32>) {
+        let start = out.len();
+        match self.mode {
+            TxMode::Usb | TxMode::Lsb => {
+                let centre = (SSB_LOW_HZ + SSB_HIGH_HZ) / 2.0 / TX_AUDIO_RATE as f32;
+                self.shifted.clear();
+                let mut phase = self.weaver_phase;
+                for &x in audio {
+                    self.shifted.push(Complex32::from_polar(x, -TAU * phase));
+                    phase = (phase + centre).fract();
+                }
+                self.filtered.clear();
+                self.sideband.process(&self.shifted, &mut self.filtered);
+                let mut phase = self.weaver_phase;
+                for z in self.filtered.iter_mut() {
+                    // The filter halves the amplitude along with the image; restore it.
+                    *z *= Complex32::from_polar(2.0, TAU * phase);
+                    if self.mode == TxMode::Lsb {
+                        *z = z.conj();
+                    }
+                    phase = (phase + centre).fract();
+                }
+                self.weaver_phase = phase;
+                self.interpolate.process(&self.filtered, out);
+            }
+            TxMode::Fm => {
+                self.audio.clear();
+                self.fm_audio.process(audio, &mut self.audio);
+                self.upsampled.clear();
+                self.interpolate_fm
+                    .process(&self.audio, &mut self.upsampled);
+                let step = FM_DEVIATION_HZ / self.output_rate;
+                for &x in self.upsampled.iter() {
+                    self.fm_phase = (self.fm_phase + step * x.clamp(-1.0, 1.0)).rem_euclid(1.0);
+                    out.push(Complex32::from_polar(1.0, TAU * self.fm_phase));
+                }
+            }
+        }
+        for z in out[start..].iter_mut() {
+            let magnitude = z.norm();
+            if magnitude > 1.0 {
+                *z /= magnitude;
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.sideband.reset();
+        self.fm_audio.reset();
+        self.interpolate.reset();
+        self.interpolate_fm.reset();
+        self.weaver_phase = 0.0;
+        self.fm_phase = 0.0;
+    }
+}
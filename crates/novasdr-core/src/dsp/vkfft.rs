@@ -1,3 +1,4 @@
+use crate::config::VulkanDevice;
 use anyhow::Context;
 use ash::vk;
 use ash::vk::Handle;
@@ -66,6 +67,7 @@ pub struct VkfftComplexFft {
 
     plan: NonNull<ffi::NovaVkfftPlan>,
     fft_size: usize,
+    device_name: String,
 }
 
 pub struct VkfftWaterfallQuantizer {
@@ -113,8 +115,15 @@ struct PushParams {
 }
 
 impl VkfftComplexFft {
-    pub fn new(fft_size: usize) -> anyhow::Result<Self> {
+    /// `selector` picks the Vulkan device; `None` falls back to `NOVASDR_VULKAN_DEVICE`, then to
+    /// the best device found. `window_values` is applied to each frame on the device.
+    pub fn new(
+        fft_size: usize,
+        selector: Option<&VulkanDevice>,
+        window_values: &[f32],
+    ) -> anyhow::Result<Self> {
         anyhow::ensure!(fft_size >= 8, "fft_size too small");
+        anyhow::ensure!(window_values.len() == fft_size, "window length mismatch");
         anyhow::ensure!(
             fft_size.is_power_of_two(),
             "vkfft requires power-of-two fft_size"
@@ -123,8 +132,8 @@ impl VkfftComplexFft {
         let entry = unsafe { ash::Entry::load().context("load Vulkan loader (libvulkan)")? };
         let instance = create_instance(&entry).context("create Vulkan instance")?;
 
-        let (physical, queue_family_index) =
-            select_physical_device(&instance).context("select Vulkan device")?;
+        let (physical, queue_family_index, device_name) =
+            select_physical_device(&instance, selector).context("select Vulkan device")?;
         let (device, queue) = create_device(&instance, physical, queue_family_index)
             .context("create Vulkan device")?;
 
@@ -201,7 +210,7 @@ impl VkfftComplexFft {
         )
         .context("create Vulkan quant buffer")?;
 
-        upload_window(&device, &window, fft_size).context("upload Hann window")?;
+        upload_window(&device, &window, window_values).context("upload FFT window")?;
 
         let (desc_set_layout, desc_pool, desc_set) =
             create_descriptor_set(&device, buffer, bytes, &window, &power, &quant)
@@ -269,9 +278,14 @@ impl VkfftComplexFft {
             pipeline_half,
             plan,
             fft_size,
+            device_name,
         })
     }
 
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
     pub fn window_and_process_inplace(
         &mut self,
         data: &[num_complex::Complex32],
@@ -670,7 +684,7 @@ impl VkfftComplexFft {
 }
 
 impl VkfftWaterfallQuantizer {
-    pub fn new(fft_size: usize) -> anyhow::Result<Self> {
+    pub fn new(fft_size: usize, selector: Option<&VulkanDevice>) -> anyhow::Result<Self> {
         anyhow::ensure!(fft_size >= 8, "fft_size too small");
         anyhow::ensure!(
             fft_size.is_power_of_two(),
@@ -680,8 +694,8 @@ impl VkfftWaterfallQuantizer {
         let entry = unsafe { ash::Entry::load().context("load Vulkan loader (libvulkan)")? };
         let instance = create_instance(&entry).context("create Vulkan instance")?;
 
-        let (physical, queue_family_index) =
-            select_physical_device(&instance).context("select Vulkan device")?;
+        let (physical, queue_family_index, _) =
+            select_physical_device(&instance, selector).context("select Vulkan device")?;
         let (device, queue) = create_device(&instance, physical, queue_family_index)
             .context("create Vulkan device")?;
 
@@ -1076,7 +1090,12 @@ fn create_instance(entry: &ash::Entry) -> anyhow::Result<ash::Instance> {
     }
 }
 
-fn select_physical_device(instance: &ash::Instance) -> anyhow::Result<(vk::PhysicalDevice, u32)> {
+/// Physical device, compute queue family and device name for `selector`, else
+/// `NOVASDR_VULKAN_DEVICE`, else the best-scoring device.
+fn select_physical_device(
+    instance: &ash::Instance,
+    selector: Option<&VulkanDevice>,
+) -> anyhow::Result<(vk::PhysicalDevice, u32, String)> {
     let devices = unsafe {
         instance
             .enumerate_physical_devices()
@@ -1084,23 +1103,43 @@ fn select_physical_device(instance: &ash::Instance) -> anyhow::Result<(vk::Physi
     };
     anyhow::ensure!(!devices.is_empty(), "no Vulkan physical devices found");
 
-    let preferred = std::env::var("NOVASDR_VULKAN_DEVICE")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok());
-
     let mut candidates: Vec<(vk::PhysicalDevice, vk::PhysicalDeviceProperties)> = Vec::new();
     for d in devices {
         let props = unsafe { instance.get_physical_device_properties(d) };
         candidates.push((d, props));
     }
+    let name = |p: &vk::PhysicalDeviceProperties| {
+        p.device_name_as_c_str()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| "<unknown>".to_string())
+    };
 
-    if let Some(idx) = preferred {
-        let (d, _) = candidates
-            .get(idx)
-            .copied()
-            .with_context(|| format!("NOVASDR_VULKAN_DEVICE={idx} out of range"))?;
+    let selector = selector.cloned().or_else(|| {
+        std::env::var("NOVASDR_VULKAN_DEVICE")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .map(VulkanDevice::Index)
+    });
+
+    if let Some(selector) = selector {
+        let (d, props) = match &selector {
+            VulkanDevice::Index(idx) => candidates.get(*idx).copied().with_context(|| {
+                format!(
+                    "Vulkan device {idx} out of range ({} devices)",
+                    candidates.len()
+                )
+            })?,
+            VulkanDevice::PciId(id) => {
+                let (vendor, device) = VulkanDevice::parse_pci_id(id)?;
+                candidates
+                    .iter()
+                    .find(|(_, p)| p.vendor_id == vendor && p.device_id == device)
+                    .copied()
+                    .with_context(|| format!("no Vulkan device with PCI id {id}"))?
+            }
+        };
         let q = find_compute_queue_family(instance, d).context("find compute queue family")?;
-        return Ok((d, q));
+        return Ok((d, q, name(&props)));
     }
 
     // Prefer discrete, then integrated, then anything.
@@ -1114,9 +1153,9 @@ fn select_physical_device(instance: &ash::Instance) -> anyhow::Result<(vk::Physi
     }
 
     candidates.sort_by_key(|(_, p)| -score(p));
-    for (d, _) in candidates {
+    for (d, props) in candidates {
         if let Ok(q) = find_compute_queue_family(instance, d) {
-            return Ok((d, q));
+            return Ok((d, q, name(&props)));
         }
     }
 
@@ -1358,8 +1397,11 @@ fn invalidate_mapped(
     }
 }
 
-fn upload_window(device: &ash::Device, window: &MappedBuffer, n: usize) -> anyhow::Result<()> {
-    let window_values = crate::dsp::window::hann_window(n);
+fn upload_window(
+    device: &ash::Device,
+    window: &MappedBuffer,
+    window_values: &[f32],
+) -> anyhow::Result<()> {
     anyhow::ensure!(
         (window_values.len() as u64) * 4 <= window.len_bytes,
         "window buffer too small"
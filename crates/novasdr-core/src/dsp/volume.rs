@@ -0,0 +1,82 @@
+/// Accepted volume range in dB; commands outside it are clamped.
+pub const MIN_VOLUME_DB: f32 = -60.0;
+pub const MAX_VOLUME_DB: f32 = 12.0;
+
+/// Corner of the bass shelf used for loudness compensation.
+const SHELF_HZ: f32 = 250.0;
+/// Bass boost per dB of attenuation, and its ceiling. Hearing loses low end faster than mid
+/// range as the level drops, so turning a quiet receiver down otherwise leaves it thin.
+const BOOST_PER_DB: f32 = 0.25;
+const MAX_BOOST_DB: f32 = 12.0;
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Listener volume with loudness compensation, applied to demodulated audio before the 16-bit
+/// conversion.
+///
+/// Below 0 dB a low shelf lifts content under [`SHELF_HZ`] by a quarter of the attenuation (at
+/// most 12 dB), so bass stays audible at low volumes. At or above 0 dB it is a plain gain.
+pub struct LoudnessVolume {
+    /// Coefficient of the two cascaded one-pole lowpasses that split off the shelf band.
+    alpha: f32,
+    volume_db: f32,
+    gain: f32,
+    /// Extra linear gain for content below the shelf corner, minus one.
+    bass_extra: f32,
+    lowpass: [f32; 2],
+}
+
+impl LoudnessVolume {
+    pub fn new(sample_rate: usize) -> Self {
+        let alpha = 1.0 - (-std::f32::consts::TAU * SHELF_HZ / sample_rate as f32).exp();
+        Self {
+            alpha,
+            volume_db: 0.0,
+            gain: 1.0,
+            bass_extra: 0.0,
+            lowpass: [0.0; 2],
+        }
+    }
+
+    pub fn volume_db(&self) -> f32 {
+        self.volume_db
+    }
+
+    pub fn set_volume_db(&mut self, volume_db: f32) {
+        let volume_db = if volume_db.is_finite() {
+            volume_db.clamp(MIN_VOLUME_DB, MAX_VOLUME_DB)
+        } else {
+            0.0
+        };
+        if volume_db == self.volume_db {
+            return;
+        }
+        self.volume_db = volume_db;
+        self.gain = db_to_gain(volume_db);
+        let boost_db = (-volume_db * BOOST_PER_DB).clamp(0.0, MAX_BOOST_DB);
+        self.bass_extra = db_to_gain(boost_db) - 1.0;
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        if self.bass_extra == 0.0 {
+            if self.gain != 1.0 {
+                for s in samples.iter_mut() {
+                    *s *= self.gain;
+                }
+            }
+            self.lowpass = [0.0; 2];
+            return;
+        }
+        for s in samples.iter_mut() {
+            self.lowpass[0] += self.alpha * (*s - self.lowpass[0]);
+            self.lowpass[1] += self.alpha * (self.lowpass[0] - self.lowpass[1]);
+            *s = self.gain * (*s + self.bass_extra * self.lowpass[1]);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.lowpass = [0.0; 2];
+    }
+}
@@ -0,0 +1,32 @@
+/// Level-0 waterfall columns `start..end` shown `attenuation_db` lower than measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaterfallMask {
+    pub start: usize,
+    pub end: usize,
+    pub attenuation_db: u8,
+}
+
+/// Dims the masked columns of a quantized waterfall, one quantizer step per dB. `len0` is the
+/// length of level 0; a downsampled column is dimmed when any of its level-0 columns is masked,
+/// so a masked carrier does not show through the coarser levels.
+pub fn apply_waterfall_masks(
+    quantized: &mut [i8],
+    offsets: &[usize],
+    len0: usize,
+    masks: &[WaterfallMask],
+) {
+    for (level, &offset) in offsets.iter().enumerate() {
+        let len = len0 >> level;
+        if offset + len > quantized.len() {
+            return;
+        }
+        let row = &mut quantized[offset..offset + len];
+        for mask in masks {
+            let start = (mask.start >> level).min(len);
+            let end = mask.end.div_ceil(1 << level).min(len);
+            for v in &mut row[start.min(end)..end] {
+                *v = (i16::from(*v) - i16::from(mask.attenuation_db)).max(i16::from(i8::MIN)) as i8;
+            }
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use super::demod::{add_complex, negate_complex, polar_discriminator_fm};
+use super::fir::{lowpass_taps, FirDecimator};
+use crate::rds;
+use num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+use std::sync::Arc;
+
+/// Multiplex sample rate the WBFM path aims for: enough for the 57 kHz RDS subcarrier.
+pub const TARGET_MPX_RATE: usize = 240_000;
+/// Upper edge of broadcast FM audio.
+const MONO_CUTOFF_HZ: f32 = 15_000.0;
+/// Share of the audio Nyquist band the mono filter may use when the audio rate is low.
+const MONO_CUTOFF_FRACTION: f32 = 0.8;
+/// De-emphasis time constant used outside the Americas.
+const DEEMPHASIS_TAU_S: f32 = 50e-6;
+/// Stereo pilot tone.
+const PILOT_HZ: f64 = 19_000.0;
+/// Lowest multiplex rate that carries the whole L-R band around 38 kHz.
+const STEREO_MIN_MPX_RATE: f32 = 2.0 * (2.0 * PILOT_HZ as f32 + MONO_CUTOFF_HZ);
+/// Cutoff of the one-pole filters that pull the pilot out of the multiplex after mixing.
+const PILOT_FILTER_HZ: f64 = 150.0;
+/// Pilot PLL noise bandwidth; well below [`PILOT_FILTER_HZ`] to keep the loop stable.
+const PILOT_LOOP_BANDWIDTH_HZ: f64 = 10.0;
+const PILOT_LOOP_DAMPING: f64 = std::f64::consts::FRAC_1_SQRT_2;
+/// How far the PLL may pull from 19 kHz; transmitters hold the pilot to ±2 Hz.
+const PILOT_PULL_RANGE_HZ: f64 = 50.0;
+/// In-phase pilot deviation needed to decode stereo. Broadcasters send 6.75–7.5 kHz, and the
+/// in-phase part only gets this large once the PLL is close to lock.
+const PILOT_LOCK_DEVIATION_HZ: f64 = 2_500.0;
+/// Time constant of the mono/stereo blend, so pilot fades do not click.
+const STEREO_BLEND_TAU_S: f32 = 0.05;
+
+/// Phase-locked loop on the 19 kHz pilot. The loop phase `phase` tracks the pilot as
+/// `sin(phase)`, so `sin(2 * phase)` is the 38 kHz subcarrier of the L-R signal.
+struct PilotPll {
+    phase: f64,
+    freq: f64,
+    nominal: f64,
+    pull_range: f64,
+    alpha: f64,
+    beta: f64,
+    filter_coeff: f64,
+    in_phase: f64,
+    quadrature: f64,
+    /// In-phase average above which the pilot counts as locked.
+    lock_level: f64,
+}
+
+impl PilotPll {
+    fn new(mpx_rate: f64) -> Self {
+        let omega = std::f64::consts::TAU * PILOT_LOOP_BANDWIDTH_HZ / mpx_rate;
+        let denom = 1.0 + 2.0 * PILOT_LOOP_DAMPING * omega + omega * omega;
+        let nominal = std::f64::consts::TAU * PILOT_HZ / mpx_rate;
+        Self {
+            phase: 0.0,
+            freq: nominal,
+            nominal,
+            pull_range: std::f64::consts::TAU * PILOT_PULL_RANGE_HZ / mpx_rate,
+            alpha: 4.0 * PILOT_LOOP_DAMPING * omega / denom,
+            beta: 4.0 * omega * omega / denom,
+            filter_coeff: 1.0 - (-std::f64::consts::TAU * PILOT_FILTER_HZ / mpx_rate).exp(),
+            in_phase: 0.0,
+            quadrature: 0.0,
+            // The discriminator outputs radians per sample; the mixer halves the amplitude.
+            lock_level: 0.5 * std::f64::consts::TAU * PILOT_LOCK_DEVIATION_HZ / mpx_rate,
+        }
+    }
+
+    /// Advances the loop by one multiplex sample and returns the 38 kHz subcarrier.
+    fn step(&mut self, x: f32) -> f32 {
+        let (s, c) = self.phase.sin_cos();
+        let x = x as f64;
+        self.in_phase += (x * s - self.in_phase) * self.filter_coeff;
+        self.quadrature += (x * c - self.quadrature) * self.filter_coeff;
+        let err = self.quadrature.atan2(self.in_phase);
+        self.freq = (self.freq + self.beta * err).clamp(
+            self.nominal - self.pull_range,
+            self.nominal + self.pull_range,
+        );
+        let subcarrier = 2.0 * s * c;
+        self.phase = (self.phase + self.freq + self.alpha * err) % std::f64::consts::TAU;
+        subcarrier as f32
+    }
+
+    fn locked(&self) -> bool {
+        self.in_phase > self.lock_level
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+        self.freq = self.nominal;
+        self.in_phase = 0.0;
+        self.quadrature = 0.0;
+    }
+}
+
+/// L-R decoder: demodulates the 38 kHz DSB subcarrier against the pilot PLL.
+struct StereoDecoder {
+    pll: PilotPll,
+    side_mpx: Vec<f32>,
+    side: FirDecimator<f32>,
+    side_out: Vec<f32>,
+    /// 0 for mono, 1 for full separation.
+    blend: f32,
+    blend_coeff: f32,
+    deemphasis_left: f32,
+    deemphasis_right: f32,
+}
+
+/// Wideband FM demodulator: recovers the full multiplex at `audio_rate * decimation`, filters
+/// the mono (L+R) programme down to the audio rate and optionally decodes stereo and RDS.
+pub struct WbfmDemodulator {
+    size: usize,
+    ifft: Arc<dyn Fft<f32>>,
+    scratch: Vec<Complex32>,
+    baseband: Vec<Complex32>,
+    baseband_prev: Vec<Complex32>,
+    disc_prev: Complex32,
+    mpx: Vec<f32>,
+    mono: FirDecimator<f32>,
+    mono_out: Vec<f32>,
+    deemphasis_alpha: f32,
+    deemphasis_state: f32,
+    stereo: Option<StereoDecoder>,
+    rds: Option<rds::Receiver>,
+    rds_changed: bool,
+}
+
+impl WbfmDemodulator {
+    /// `audio_fft_size` is the per-frame audio IFFT size; the multiplex IFFT is `decimation`
+    /// times larger and spans the same bins as the slice passed to [`Self::process`].
+    pub fn new(audio_rate: usize, audio_fft_size: usize, decimation: usize) -> Self {
+        let decimation = decimation.max(1);
+        let size = audio_fft_size * decimation;
+        let mpx_rate = (audio_rate * decimation) as f32;
+        let nyquist = audio_rate as f32 / 2.0;
+        let cutoff = MONO_CUTOFF_HZ.min(MONO_CUTOFF_FRACTION * nyquist);
+        let taps = lowpass_taps(cutoff / mpx_rate, (nyquist - cutoff).max(1.0) / mpx_rate);
+        let mut planner = FftPlanner::<f32>::new();
+        let ifft = planner.plan_fft_inverse(size);
+        let scratch = vec![Complex32::new(0.0, 0.0); ifft.get_inplace_scratch_len()];
+        let stereo = (mpx_rate >= STEREO_MIN_MPX_RATE).then(|| StereoDecoder {
+            pll: PilotPll::new(mpx_rate as f64),
+            side_mpx: vec![0.0; size / 2],
+            side: FirDecimator::new(taps.clone(), decimation),
+            side_out: Vec::with_capacity(audio_fft_size / 2),
+            blend: 0.0,
+            blend_coeff: 1.0 - (-1.0 / (audio_rate as f32 * STEREO_BLEND_TAU_S)).exp(),
+            deemphasis_left: 0.0,
+            deemphasis_right: 0.0,
+        });
+        let rds =
+            (mpx_rate as f64 >= rds::MIN_MPX_RATE).then(|| rds::Receiver::new(mpx_rate as f64));
+        Self {
+            size,
+            ifft,
+            scratch,
+            baseband: vec![Complex32::new(0.0, 0.0); size],
+            baseband_prev: vec![Complex32::new(0.0, 0.0); size / 2],
+            disc_prev: Complex32::new(0.0, 0.0),
+            mpx: vec![0.0; size / 2],
+            mono: FirDecimator::new(taps, decimation),
+            mono_out: Vec::with_capacity(audio_fft_size / 2),
+            deemphasis_alpha: 1.0 - (-1.0 / (audio_rate as f32 * DEEMPHASIS_TAU_S)).exp(),
+            deemphasis_state: 0.0,
+            stereo,
+            rds,
+            rds_changed: false,
+        }
+    }
+
+    /// Number of spectrum bins the demodulator consumes per frame.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the multiplex rate is high enough to carry the L-R subcarrier.
+    pub fn stereo_available(&self) -> bool {
+        self.stereo.is_some()
+    }
+
+    /// Whether the last frame decoded with a locked pilot.
+    pub fn stereo_locked(&self) -> bool {
+        self.stereo.as_ref().is_some_and(|s| s.pll.locked())
+    }
+
+    /// Whether the multiplex rate is high enough to carry the RDS subcarrier.
+    pub fn rds_available(&self) -> bool {
+        self.rds.is_some()
+    }
+
+    /// Demodulates one frame of `bins` tuned to bin `centre`, writing `out.len()` audio samples
+    /// (half the audio IFFT size). `negate` applies the odd-frame sign flip of the overlapped
+    /// spectrum frames. With `right`, `out` receives the left channel and `right` the right one;
+    /// both carry the mono programme while no pilot is locked.
+    pub fn process(
+        &mut self,
+        bins: &[Complex32],
+        centre: i32,
+        negate: bool,
+        decode_rds: bool,
+        right: Option<&mut [f32]>,
+        out: &mut [f32],
+    ) {
+        let len = bins.len() as i32;
+        let half = (self.size / 2) as i32;
+        self.baseband.fill(Complex32::new(0.0, 0.0));
+        for i in 0.max(centre)..len.min(centre + half) {
+            self.baseband[(i - centre) as usize] = bins[i as usize];
+        }
+        for i in 0.max(centre - half + 1)..len.min(centre) {
+            self.baseband[self.size - (centre - i) as usize] = bins[i as usize];
+        }
+        self.ifft
+            .process_with_scratch(&mut self.baseband, &mut self.scratch);
+        if negate {
+            negate_complex(&mut self.baseband);
+        }
+        let half = self.size / 2;
+        add_complex(&mut self.baseband[..half], &self.baseband_prev);
+        self.baseband_prev.copy_from_slice(&self.baseband[half..]);
+        self.disc_prev =
+            polar_discriminator_fm(&self.baseband[..half], self.disc_prev, &mut self.mpx);
+        self.decode_mpx(decode_rds, right, out);
+    }
+
+    /// Turns the multiplex in `self.mpx` into audio and feeds RDS.
+    fn decode_mpx(&mut self, decode_rds: bool, right: Option<&mut [f32]>, out: &mut [f32]) {
+        self.mono_out.clear();
+        self.mono.process(&self.mpx, &mut self.mono_out);
+        match (right, self.stereo.as_mut()) {
+            (Some(right), Some(stereo)) => {
+                stereo.process(&self.mpx, &self.mono_out, self.deemphasis_alpha, out, right);
+            }
+            (right, _) => {
+                for (dst, &s) in out.iter_mut().zip(self.mono_out.iter()) {
+                    self.deemphasis_state += self.deemphasis_alpha * (s - self.deemphasis_state);
+                    *dst = self.deemphasis_state;
+                }
+                if let Some(right) = right {
+                    right.copy_from_slice(out);
+                }
+            }
+        }
+
+        if decode_rds {
+            if let Some(rds) = self.rds.as_mut() {
+                self.rds_changed |= rds.process(&self.mpx);
+            }
+        }
+    }
+
+    /// Station information, if it changed since the last call.
+    pub fn take_rds_update(&mut self) -> Option<&rds::StationInfo> {
+        if !std::mem::take(&mut self.rds_changed) {
+            return None;
+        }
+        self.rds.as_ref().map(|r| r.info())
+    }
+
+    pub fn reset(&mut self) {
+        self.baseband_prev.fill(Complex32::new(0.0, 0.0));
+        self.disc_prev = Complex32::new(0.0, 0.0);
+        self.mono.reset();
+        self.deemphasis_state = 0.0;
+        if let Some(stereo) = self.stereo.as_mut() {
+            stereo.reset();
+        }
+        if let Some(rds) = self.rds.as_mut() {
+            rds.reset();
+        }
+        self.rds_changed = false;
+    }
+}
+
+impl StereoDecoder {
+    /// Recovers L-R from `mpx` and mixes it with the already decimated `mono` (L+R) samples.
+    fn process(
+        &mut self,
+        mpx: &[f32],
+        mono: &[f32],
+        deemphasis_alpha: f32,
+        left: &mut [f32],
+        right: &mut [f32],
+    ) {
+        for (dst, &x) in self.side_mpx.iter_mut().zip(mpx.iter()) {
+            // The subcarrier is suppressed, so 2·sin(2φ) recovers L-R at the mono level.
+            *dst = 2.0 * x * self.pll.step(x);
+        }
+        self.side_out.clear();
+        self.side.process(&self.side_mpx, &mut self.side_out);
+
+        let target = if self.pll.locked() { 1.0 } else { 0.0 };
+        for (((l, r), &m), &d) in left
+            .iter_mut()
+            .zip(right.iter_mut())
+            .zip(mono.iter())
+            .zip(self.side_out.iter())
+        {
+            self.blend += (target - self.blend) * self.blend_coeff;
+            let d = d * self.blend;
+            self.deemphasis_left += deemphasis_alpha * (m + d - self.deemphasis_left);
+            self.deemphasis_right += deemphasis_alpha * (m - d - self.deemphasis_right);
+            *l = self.deemphasis_left;
+            *r = self.deemphasis_right;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.pll.reset();
+        self.side.reset();
+        self.blend = 0.0;
+        self.deemphasis_left = 0.0;
+        self.deemphasis_right = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUDIO_RATE: usize = 48_000;
+    const DECIMATION: usize = 5;
+    const FRAMES: usize = 200;
+
+    fn rms(x: &[f32]) -> f32 {
+        (x.iter().map(|v| v * v).sum::<f32>() / x.len() as f32).sqrt()
+    }
+
+    /// Decodes a multiplex whose programme sits in the left channel only; returns the RMS of
+    /// each channel over the second half of the run.
+    fn decode_left_only(pilot: f64) -> (f32, f32) {
+        let mut demod = WbfmDemodulator::new(AUDIO_RATE, 512, DECIMATION);
+        let mpx_rate = (AUDIO_RATE * DECIMATION) as f64;
+        let frame = demod.mpx.len();
+        let tau = std::f64::consts::TAU;
+        let (mut left, mut right) = (vec![0.0; 256], vec![0.0; 256]);
+        let (mut left_tail, mut right_tail) = (Vec::new(), Vec::new());
+        for f in 0..FRAMES {
+            for (i, x) in demod.mpx.iter_mut().enumerate() {
+                let t = (f * frame + i) as f64 / mpx_rate;
+                let l = 0.8 * (tau * 1_000.0 * t).sin();
+                let pilot_phase = tau * PILOT_HZ * t + 0.3;
+                let sum = l / 2.0;
+                let diff = l / 2.0;
+                *x = (sum + diff * (2.0 * pilot_phase).sin() + pilot * pilot_phase.sin()) as f32;
+            }
+            demod.decode_mpx(false, Some(&mut right), &mut left);
+            if f >= FRAMES / 2 {
+                left_tail.extend_from_slice(&left);
+                right_tail.extend_from_slice(&right);
+            }
+        }
+        (rms(&left_tail), rms(&right_tail))
+    }
+
+    #[test]
+    fn pilot_enables_channel_separation() {
+        // 7.5 kHz pilot deviation, in discriminator units at 240 kHz.
+        let (left, right) = decode_left_only(0.196);
+        assert!(left > 0.1, "left level {left}");
+        assert!(right < left * 0.1, "separation too low: {left} vs {right}");
+    }
+
+    #[test]
+    fn missing_pilot_falls_back_to_mono() {
+        let (left, right) = decode_left_only(0.0);
+        assert!(left > 0.1, "left level {left}");
+        assert!((left - right).abs() < left * 0.01, "{left} vs {right}");
+    }
+}
@@ -1,3 +1,5 @@
+use crate::config::FftWindow;
+
 pub fn hann_window(size: usize) -> Vec<f32> {
     let mut out = vec![0.0f32; size];
     let denom = size as f32;
@@ -6,3 +8,73 @@ pub fn hann_window(size: usize) -> Vec<f32> {
     }
     out
 }
+
+/// `window` of `size` points (periodic, like [`hann_window`]), scaled to the coherent gain of
+/// Hann (mean 0.5) so waterfall brightness and S-meter readings do not move with the window.
+pub fn fft_window(window: FftWindow, size: usize) -> Vec<f32> {
+    let mut out = match window {
+        FftWindow::Hann => return hann_window(size),
+        FftWindow::BlackmanHarris => cosine_sum(&[0.35875, 0.48829, 0.14128, 0.01168], size),
+        FftWindow::FlatTop => cosine_sum(
+            &[
+                0.215_578_95,
+                0.416_631_58,
+                0.277_263_16,
+                0.083_578_95,
+                0.006_947_37,
+            ],
+            size,
+        ),
+        FftWindow::Kaiser { beta } => kaiser(beta as f64, size),
+    };
+    let mean = out.iter().map(|&v| v as f64).sum::<f64>() / size.max(1) as f64;
+    if mean > 0.0 {
+        let scale = (0.5 / mean) as f32;
+        out.iter_mut().for_each(|v| *v *= scale);
+    }
+    out
+}
+
+/// `a0 - a1 cos(x) + a2 cos(2x) - ...` over one period.
+fn cosine_sum(coeffs: &[f64], size: usize) -> Vec<f32> {
+    let denom = size as f64;
+    (0..size)
+        .map(|i| {
+            let x = 2.0 * std::f64::consts::PI * i as f64 / denom;
+            coeffs
+                .iter()
+                .enumerate()
+                .map(|(k, &a)| {
+                    let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                    sign * a * (k as f64 * x).cos()
+                })
+                .sum::<f64>() as f32
+        })
+        .collect()
+}
+
+fn kaiser(beta: f64, size: usize) -> Vec<f32> {
+    let half = size as f64 / 2.0;
+    let norm = bessel_i0(beta);
+    (0..size)
+        .map(|i| {
+            let r = (i as f64 - half) / half;
+            (bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / norm) as f32
+        })
+        .collect()
+}
+
+/// Zeroth-order modified Bessel function of the first kind (power series).
+fn bessel_i0(x: f64) -> f64 {
+    let q = x * x / 4.0;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for k in 1..200 {
+        term *= q / (k * k) as f64;
+        sum += term;
+        if term < sum * 1e-12 {
+            break;
+        }
+    }
+    sum
+}
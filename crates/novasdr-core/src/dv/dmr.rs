@@ -0,0 +1,485 @@
+//! DMR (ETSI TS 102 361) on one timeslot: sync, slot type, the full link control carried by
+//! voice headers and terminators, and the three AMBE frames of each voice burst.
+
+use super::{AmbeFrame, Event, Mode, VoiceFrame, HOLD_SYMBOLS};
+
+/// One burst: 54 payload, 24 sync or embedded signalling, 54 payload symbols.
+pub const BURST_SYMBOLS: usize = 132;
+/// A timeslot repeats every 60 ms: two bursts, each behind 12 symbols of CACH.
+pub const SLOT_SYMBOLS: u64 = 288;
+const HALF_SLOT_SYMBOLS: u64 = SLOT_SYMBOLS / 2;
+const SYNC_SYMBOLS: usize = 24;
+/// Symbols from the end of a sync word to the end of its burst.
+const SYNC_TO_END: u64 = 54;
+const MAX_SYNC_ERRORS: u32 = 2;
+/// Bursts B to F of a voice superframe carry embedded signalling instead of sync.
+const SUPERFRAME_BURSTS: u8 = 6;
+
+/// Base-station and mobile-station sourced sync words.
+pub const BS_VOICE_SYNC: u64 = 0x755F_D7DF_75F7;
+pub const BS_DATA_SYNC: u64 = 0xDFF5_7D75_DF5D;
+pub const MS_VOICE_SYNC: u64 = 0x7F7D_5DD5_7DFD;
+pub const MS_DATA_SYNC: u64 = 0xD5D7_F77F_D757;
+
+/// Slot type data types this decoder acts on.
+pub const DATA_TYPE_VOICE_HEADER: u8 = 1;
+pub const DATA_TYPE_TERMINATOR: u8 = 2;
+/// CRC masks applied to the Reed-Solomon parity of the full link control.
+pub const VOICE_HEADER_MASK: u8 = 0x96;
+pub const TERMINATOR_MASK: u8 = 0x99;
+
+/// Full link control opcodes for group and individual voice calls.
+const FLCO_GROUP: u8 = 0x00;
+const FLCO_PRIVATE: u8 = 0x03;
+
+/// Dibit to symbol level, indexed by dibit.
+const LEVELS: [f32; 4] = [1.0, 3.0, -1.0, -3.0];
+
+/// Where each voice dibit lands in the `ambe_fr` layout mbelib expects: the high bit goes to
+/// `[W][X]`, the low bit to `[Y][Z]`.
+const AMBE_W: [usize; 36] = [
+    0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 2, 0, 2, 0, 2, 0, 2, 0, 2,
+    0, 2, 0, 2,
+];
+const AMBE_X: [usize; 36] = [
+    23, 10, 22, 9, 21, 8, 20, 7, 19, 6, 18, 5, 17, 4, 16, 3, 15, 2, 14, 1, 13, 0, 12, 10, 11, 9,
+    10, 8, 9, 7, 8, 6, 7, 5, 6, 4,
+];
+const AMBE_Y: [usize; 36] = [
+    0, 2, 0, 2, 0, 2, 0, 2, 0, 3, 0, 3, 1, 3, 1, 3, 1, 3, 1, 3, 1, 3, 1, 3, 1, 3, 1, 3, 1, 3, 1, 3,
+    1, 3, 1, 3,
+];
+const AMBE_Z: [usize; 36] = [
+    5, 3, 4, 2, 3, 1, 2, 0, 1, 13, 0, 12, 22, 11, 21, 10, 20, 9, 19, 8, 18, 7, 17, 6, 16, 5, 15, 4,
+    14, 3, 13, 2, 12, 1, 11, 0,
+];
+const AMBE_FRAME_DIBITS: usize = 36;
+
+/// Burst position of dibit `i` of AMBE frame `frame`; the second frame straddles the sync.
+fn voice_dibit(frame: usize, i: usize) -> usize {
+    let pos = frame * AMBE_FRAME_DIBITS + i;
+    if pos < 54 {
+        pos
+    } else {
+        pos + SYNC_SYMBOLS
+    }
+}
+
+/// Positions of the 96 data bits in a deinterleaved BPTC(196,96) block.
+const BPTC_DATA: [std::ops::RangeInclusive<usize>; 9] = [
+    4..=11,
+    16..=26,
+    31..=41,
+    46..=56,
+    61..=71,
+    76..=86,
+    91..=101,
+    106..=116,
+    121..=131,
+];
+
+/// Reed-Solomon (12,9) generator over GF(2^8) with polynomial 0x11D.
+const RS_GENERATOR: [u8; 3] = [64, 56, 14];
+
+/// Full link control of a voice call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FullLc {
+    pub flco: u8,
+    pub feature_set: u8,
+    pub service_options: u8,
+    pub destination: u32,
+    pub source: u32,
+}
+
+impl FullLc {
+    pub fn group_call(destination: u32, source: u32) -> Self {
+        Self {
+            flco: FLCO_GROUP,
+            feature_set: 0,
+            service_options: 0,
+            destination,
+            source,
+        }
+    }
+
+    /// Whether the destination is a talkgroup, for the two voice call opcodes.
+    pub fn group(&self) -> Option<bool> {
+        match self.flco {
+            FLCO_GROUP => Some(true),
+            FLCO_PRIVATE => Some(false),
+            _ => None,
+        }
+    }
+
+    fn to_bytes(self, mask: u8) -> [u8; 12] {
+        let mut b = [0u8; 12];
+        b[0] = self.flco & 0x3F;
+        b[1] = self.feature_set;
+        b[2] = self.service_options;
+        b[3..6].copy_from_slice(&self.destination.to_be_bytes()[1..]);
+        b[6..9].copy_from_slice(&self.source.to_be_bytes()[1..]);
+        let parity = rs_parity(&b[..9]);
+        b[9] = parity[2] ^ mask;
+        b[10] = parity[1] ^ mask;
+        b[11] = parity[0] ^ mask;
+        b
+    }
+
+    fn from_bytes(mut b: [u8; 12], mask: u8) -> Option<Self> {
+        for p in &mut b[9..] {
+            *p ^= mask;
+        }
+        let parity = rs_parity(&b[..9]);
+        if b[9] != parity[2] || b[10] != parity[1] || b[11] != parity[0] {
+            return None;
+        }
+        let id = |s: &[u8]| u32::from_be_bytes([0, s[0], s[1], s[2]]);
+        Some(Self {
+            flco: b[0] & 0x3F,
+            feature_set: b[1],
+            service_options: b[2],
+            destination: id(&b[3..6]),
+            source: id(&b[6..9]),
+        })
+    }
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut out = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            out ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1D;
+        }
+        b >>= 1;
+    }
+    out
+}
+
+/// Parity of the systematic RS(12,9) code, highest-order byte last.
+fn rs_parity(data: &[u8]) -> [u8; 3] {
+    let mut parity = [0u8; 3];
+    for &byte in data {
+        let feedback = byte ^ parity[2];
+        parity[2] = parity[1] ^ gf_mul(RS_GENERATOR[2], feedback);
+        parity[1] = parity[0] ^ gf_mul(RS_GENERATOR[1], feedback);
+        parity[0] = gf_mul(RS_GENERATOR[0], feedback);
+    }
+    parity
+}
+
+fn bptc_index(a: usize) -> usize {
+    (a * 181) % 196
+}
+
+/// The 196 BPTC bits of a burst: both info halves around the slot type and sync.
+fn burst_info_bits(dibits: &[u8; BURST_SYMBOLS]) -> [u8; 196] {
+    let mut bits = [0u8; 196];
+    for (i, bit) in bits.iter_mut().enumerate() {
+        let pos = if i < 98 { i } else { i + 68 };
+        *bit = (dibits[pos / 2] >> (1 - pos % 2)) & 1;
+    }
+    bits
+}
+
+fn decode_bptc(raw: &[u8; 196]) -> [u8; 12] {
+    let mut bytes = [0u8; 12];
+    let data = BPTC_DATA.iter().cloned().flatten();
+    for (n, a) in data.enumerate() {
+        bytes[n / 8] |= raw[bptc_index(a)] << (7 - n % 8);
+    }
+    bytes
+}
+
+/// Color code and data type from the two slot type halves around the sync.
+fn slot_type(dibits: &[u8; BURST_SYMBOLS]) -> (u8, u8) {
+    let first = dibits[49..53].iter().fold(0u8, |acc, &d| (acc << 2) | d);
+    (first >> 4, first & 0x0F)
+}
+
+fn ambe_frames(dibits: &[u8; BURST_SYMBOLS]) -> [AmbeFrame; 3] {
+    let mut frames = [[[0u8; 24]; 4]; 3];
+    for (f, frame) in frames.iter_mut().enumerate() {
+        for i in 0..AMBE_FRAME_DIBITS {
+            let d = dibits[voice_dibit(f, i)];
+            frame[AMBE_W[i]][AMBE_X[i]] = d >> 1;
+            frame[AMBE_Y[i]][AMBE_Z[i]] = d & 1;
+        }
+    }
+    frames
+}
+
+fn sync_dibits(sync: u64) -> impl Iterator<Item = u8> {
+    (0..SYNC_SYMBOLS).map(move |k| ((sync >> (46 - 2 * k)) & 3) as u8)
+}
+
+/// One bit per sync symbol, set where the symbol is negative; the first symbol is the top bit.
+const fn sign_pattern(sync: u64) -> u32 {
+    let mut pattern = 0u32;
+    let mut k = 0;
+    while k < SYNC_SYMBOLS {
+        pattern = (pattern << 1) | ((sync >> (47 - 2 * k)) & 1) as u32;
+        k += 1;
+    }
+    pattern
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SyncKind {
+    Voice,
+    Data,
+}
+
+const SYNCS: [(u32, SyncKind); 4] = [
+    (sign_pattern(BS_VOICE_SYNC), SyncKind::Voice),
+    (sign_pattern(BS_DATA_SYNC), SyncKind::Data),
+    (sign_pattern(MS_VOICE_SYNC), SyncKind::Voice),
+    (sign_pattern(MS_DATA_SYNC), SyncKind::Data),
+];
+
+/// A voice header or terminator burst, as dibits.
+///
+/// The BPTC Hamming parity is left zero; [`super::Decoder`] relies on the Reed-Solomon check alone.
+pub fn encode_lc_burst(
+    sync: u64,
+    color_code: u8,
+    data_type: u8,
+    lc: &FullLc,
+    mask: u8,
+) -> [u8; BURST_SYMBOLS] {
+    let bytes = lc.to_bytes(mask);
+    let mut raw = [0u8; 196];
+    let data = BPTC_DATA.iter().cloned().flatten();
+    for (n, a) in data.enumerate() {
+        raw[bptc_index(a)] = (bytes[n / 8] >> (7 - n % 8)) & 1;
+    }
+    let mut dibits = [0u8; BURST_SYMBOLS];
+    for (i, &bit) in raw.iter().enumerate() {
+        let pos = if i < 98 { i } else { i + 68 };
+        dibits[pos / 2] |= bit << (1 - pos % 2);
+    }
+    // Slot type: color code and data type, then Golay parity (left zero, as above).
+    let slot = ((color_code & 0x0F) << 4) | (data_type & 0x0F);
+    for k in 0..4 {
+        dibits[49 + k] = (slot >> (6 - 2 * k)) & 3;
+    }
+    for (d, s) in dibits[54..78].iter_mut().zip(sync_dibits(sync)) {
+        *d = s;
+    }
+    dibits
+}
+
+/// A voice burst carrying three AMBE frames, with `sync` for burst A of a superframe and the
+/// embedded signalling (here zero) otherwise.
+pub fn encode_voice_burst(frames: &[AmbeFrame; 3], sync: Option<u64>) -> [u8; BURST_SYMBOLS] {
+    let mut dibits = [0u8; BURST_SYMBOLS];
+    for (f, frame) in frames.iter().enumerate() {
+        for i in 0..AMBE_FRAME_DIBITS {
+            dibits[voice_dibit(f, i)] =
+                (frame[AMBE_W[i]][AMBE_X[i]] << 1) | frame[AMBE_Y[i]][AMBE_Z[i]];
+        }
+    }
+    if let Some(sync) = sync {
+        for (d, s) in dibits[54..78].iter_mut().zip(sync_dibits(sync)) {
+            *d = s;
+        }
+    }
+    dibits
+}
+
+/// Symbol level of a dibit.
+pub fn level(dibit: u8) -> f32 {
+    LEVELS[usize::from(dibit & 3)]
+}
+
+/// Dibit nearest to a symbol level, with ±3 as the outer levels.
+pub(super) fn dibit(level: f32) -> u8 {
+    match level {
+        x if x >= 2.0 => 0b01,
+        x if x >= 0.0 => 0b00,
+        x if x >= -2.0 => 0b10,
+        _ => 0b11,
+    }
+}
+
+const HISTORY: usize = 256;
+
+/// Follows DMR bursts on one timeslot: the first one a call is heard on.
+pub(super) struct Tracker {
+    history: [f32; HISTORY],
+    signs: u32,
+    /// Symbols seen so far; the newest is `index - 1`.
+    index: u64,
+    /// Bursts whose sync was found, by burst end.
+    pending: Vec<(u64, SyncKind)>,
+    last_sync: Option<u64>,
+    confirmed: bool,
+    offset: f32,
+    unit: f32,
+    /// Burst end phase of the followed timeslot, and when a burst on it was last heard.
+    slot: Option<(u64, u64)>,
+    /// End and superframe position of the next voice burst without sync.
+    next_voice: Option<(u64, u8)>,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self {
+            history: [0.0; HISTORY],
+            signs: 0,
+            index: 0,
+            pending: Vec::new(),
+            last_sync: None,
+            confirmed: false,
+            offset: 0.0,
+            unit: 1.0,
+            slot: None,
+            next_voice: None,
+        }
+    }
+
+    /// Whether a DMR signal has been confirmed and heard recently.
+    pub fn locked(&self) -> bool {
+        self.confirmed
+    }
+
+    pub fn push(&mut self, value: f32, events: &mut Vec<Event>) {
+        let i = self.index;
+        self.index += 1;
+        self.history[i as usize % HISTORY] = value;
+        self.signs = ((self.signs << 1) | u32::from(value < 0.0)) & 0x00FF_FFFF;
+
+        let whole_burst = i + SYNC_TO_END >= BURST_SYMBOLS as u64;
+        if let Some(kind) = self.match_sync().filter(|_| whole_burst) {
+            if self.last_sync.is_none_or(|p| i - p > 2) {
+                self.on_sync(i);
+                self.pending.push((i + SYNC_TO_END, kind));
+            }
+        }
+        if let Some(pos) = self.pending.iter().position(|&(end, _)| end == i) {
+            let (_, kind) = self.pending.swap_remove(pos);
+            self.sync_burst(i, kind, events);
+        }
+        if let Some((end, n)) = self.next_voice {
+            if end == i {
+                self.voice_burst(i, events);
+                self.next_voice = (n + 1 < SUPERFRAME_BURSTS).then_some((i + SLOT_SYMBOLS, n + 1));
+            }
+        }
+
+        if self.last_sync.is_some_and(|p| i - p > HOLD_SYMBOLS) {
+            self.confirmed = false;
+        }
+        if self.slot.is_some_and(|(_, seen)| i - seen > HOLD_SYMBOLS) {
+            self.slot = None;
+            self.next_voice = None;
+        }
+    }
+
+    fn match_sync(&self) -> Option<SyncKind> {
+        SYNCS
+            .iter()
+            .find(|(pattern, _)| (self.signs ^ pattern).count_ones() <= MAX_SYNC_ERRORS)
+            .map(|&(_, kind)| kind)
+    }
+
+    fn on_sync(&mut self, i: u64) {
+        // Sync symbols are all ±3: they set the slicer's zero and unit.
+        let values = (0..SYNC_SYMBOLS as u64).map(|k| self.value(i + 1 + k - SYNC_SYMBOLS as u64));
+        let (mut sum, mut mag) = (0.0f32, 0.0f32);
+        for v in values {
+            sum += v;
+            mag += v.abs();
+        }
+        self.offset = sum / SYNC_SYMBOLS as f32;
+        self.unit = (mag / SYNC_SYMBOLS as f32 / 3.0).max(1e-6);
+        if let Some(p) = self.last_sync {
+            let d = i - p;
+            let phase = d % HALF_SLOT_SYMBOLS;
+            if d <= 12 * HALF_SLOT_SYMBOLS && (phase <= 1 || phase >= HALF_SLOT_SYMBOLS - 1) {
+                self.confirmed = true;
+            }
+        }
+        self.last_sync = Some(i);
+    }
+
+    fn value(&self, i: u64) -> f32 {
+        self.history[i as usize % HISTORY]
+    }
+
+    fn dibits(&self, end: u64) -> [u8; BURST_SYMBOLS] {
+        let mut out = [0u8; BURST_SYMBOLS];
+        let start = end + 1 - BURST_SYMBOLS as u64;
+        for (k, d) in out.iter_mut().enumerate() {
+            *d = dibit((self.value(start + k as u64) - self.offset) / self.unit);
+        }
+        out
+    }
+
+    fn on_followed_slot(&self, end: u64) -> bool {
+        self.slot.is_none_or(|(phase, _)| {
+            let d = (end + SLOT_SYMBOLS - phase) % SLOT_SYMBOLS;
+            d <= 1 || d >= SLOT_SYMBOLS - 1
+        })
+    }
+
+    fn follow(&mut self, end: u64) {
+        self.slot = Some((end % SLOT_SYMBOLS, end));
+    }
+
+    fn sync_burst(&mut self, end: u64, kind: SyncKind, events: &mut Vec<Event>) {
+        if !self.on_followed_slot(end) {
+            return;
+        }
+        let dibits = self.dibits(end);
+        match kind {
+            SyncKind::Voice => {
+                if !self.confirmed {
+                    return;
+                }
+                self.follow(end);
+                self.emit_voice(&dibits, events);
+                self.next_voice = Some((end + SLOT_SYMBOLS, 1));
+            }
+            SyncKind::Data => {
+                let (color_code, data_type) = slot_type(&dibits);
+                let mask = match data_type {
+                    DATA_TYPE_VOICE_HEADER => VOICE_HEADER_MASK,
+                    DATA_TYPE_TERMINATOR => TERMINATOR_MASK,
+                    _ => return,
+                };
+                let Some(lc) = FullLc::from_bytes(decode_bptc(&burst_info_bits(&dibits)), mask)
+                else {
+                    return;
+                };
+                self.confirmed = true;
+                self.next_voice = None;
+                if data_type == DATA_TYPE_TERMINATOR {
+                    events.push(Event::Dmr { color_code, lc });
+                    events.push(Event::End(Mode::Dmr));
+                    self.slot = None;
+                } else {
+                    events.push(Event::Activity(Mode::Dmr));
+                    events.push(Event::Dmr { color_code, lc });
+                    self.follow(end);
+                }
+            }
+        }
+    }
+
+    fn voice_burst(&mut self, end: u64, events: &mut Vec<Event>) {
+        let dibits = self.dibits(end);
+        self.follow(end);
+        self.emit_voice(&dibits, events);
+    }
+
+    fn emit_voice(&self, dibits: &[u8; BURST_SYMBOLS], events: &mut Vec<Event>) {
+        events.push(Event::Activity(Mode::Dmr));
+        let frames = ambe_frames(dibits).map(VoiceFrame::Dmr);
+        events.extend(frames.into_iter().map(Event::Voice));
+    }
+}
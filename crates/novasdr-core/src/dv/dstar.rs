@@ -0,0 +1,353 @@
+//! D-STAR digital voice: data sync, the slow data channel's header and text message, and the
+//! AMBE 3600x2400 voice frames.
+
+use super::{AmbeFrame, Event, Mode, VoiceFrame, HOLD_SYMBOLS};
+
+/// 72 AMBE bits then 24 slow data bits, every 20 ms.
+pub const FRAME_BITS: u64 = 96;
+/// Every 21st frame carries the data sync in place of slow data.
+pub const SUPERFRAME_FRAMES: u64 = 21;
+const SLOW_BITS: usize = 24;
+const VOICE_BITS: usize = 72;
+/// Slow data sync bytes, sent like all D-STAR bytes least significant bit first.
+const DATA_SYNC: [u8; 3] = [0x55, 0x2D, 0x16];
+const MAX_SYNC_ERRORS: u32 = 1;
+/// Slow data is XORed with this before transmission.
+const SCRAMBLER: [u8; 3] = [0x70, 0x4F, 0x93];
+/// The AMBE silence frame, sent in idle voice slots.
+pub const SILENCE: [u8; 9] = [0x9E, 0x8D, 0x32, 0x88, 0x26, 0x1A, 0x3F, 0x61, 0xE8];
+
+/// Where each voice bit, in the order sent, lands in the `ambe_fr` layout mbelib expects.
+const AMBE_W: [usize; VOICE_BITS] = [
+    0, 0, 3, 2, 1, 1, 0, 0, 1, 1, 0, 0, 3, 2, 1, 1, 3, 2, 1, 1, 0, 0, 3, 2, 0, 0, 3, 2, 1, 1, 0, 0,
+    1, 1, 0, 0, 3, 2, 1, 1, 3, 2, 1, 1, 0, 0, 3, 2, 0, 0, 3, 2, 1, 1, 0, 0, 1, 1, 0, 0, 3, 2, 1, 1,
+    3, 3, 2, 1, 0, 0, 3, 3,
+];
+const AMBE_X: [usize; VOICE_BITS] = [
+    10, 22, 11, 9, 10, 22, 11, 23, 8, 20, 9, 21, 10, 8, 9, 21, 8, 6, 7, 19, 8, 20, 9, 7, 6, 18, 7,
+    5, 6, 18, 7, 19, 4, 16, 5, 17, 6, 4, 5, 17, 4, 2, 3, 15, 4, 16, 5, 3, 2, 14, 3, 1, 2, 14, 3,
+    15, 0, 12, 1, 13, 2, 0, 1, 13, 0, 12, 10, 11, 0, 12, 1, 13,
+];
+
+const HEADER_LEN: usize = 41;
+const MESSAGE_LEN: usize = 20;
+const MESSAGE_BLOCKS: u8 = 4;
+const BLOCK_MESSAGE: u8 = 0x40;
+const BLOCK_HEADER: u8 = 0x50;
+const BLOCK_FILL: u8 = 0x66;
+
+/// Radio header: the route and callsigns of a transmission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub flags: [u8; 3],
+    pub rpt2: String,
+    pub rpt1: String,
+    pub your: String,
+    pub my: String,
+    pub suffix: String,
+}
+
+fn field(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| {
+            if (0x20..=0x7E).contains(&b) {
+                b as char
+            } else {
+                '?'
+            }
+        })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+fn padded(s: &str, len: usize) -> impl Iterator<Item = u8> + '_ {
+    s.bytes().chain(std::iter::repeat(b' ')).take(len)
+}
+
+/// CRC-16/X.25 as used by the radio header.
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for &b in bytes {
+        crc ^= u16::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+impl Header {
+    pub fn from_bytes(bytes: &[u8; HEADER_LEN]) -> Option<Self> {
+        let crc = crc16(&bytes[..39]);
+        if bytes[39..] != crc.to_le_bytes() {
+            return None;
+        }
+        Some(Self {
+            flags: [bytes[0], bytes[1], bytes[2]],
+            rpt2: field(&bytes[3..11]),
+            rpt1: field(&bytes[11..19]),
+            your: field(&bytes[19..27]),
+            my: field(&bytes[27..35]),
+            suffix: field(&bytes[35..39]),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut out = [0u8; HEADER_LEN];
+        out[..3].copy_from_slice(&self.flags);
+        let fields = [
+            (&self.rpt2, 3, 8),
+            (&self.rpt1, 11, 8),
+            (&self.your, 19, 8),
+            (&self.my, 27, 8),
+            (&self.suffix, 35, 4),
+        ];
+        for (s, at, len) in fields {
+            for (o, b) in out[at..at + len].iter_mut().zip(padded(s, len)) {
+                *o = b;
+            }
+        }
+        let crc = crc16(&out[..39]);
+        out[39..].copy_from_slice(&crc.to_le_bytes());
+        out
+    }
+}
+
+/// A voice frame as sent, 9 bytes least significant bit first, laid out for the vocoder.
+pub fn ambe_frame(voice: &[u8; 9]) -> AmbeFrame {
+    let mut frame = [[0u8; 24]; 4];
+    for i in 0..VOICE_BITS {
+        frame[AMBE_W[i]][AMBE_X[i]] = (voice[i / 8] >> (i % 8)) & 1;
+    }
+    frame
+}
+
+fn push_bytes(bits: &mut Vec<u8>, bytes: &[u8]) {
+    for &b in bytes {
+        bits.extend((0..8).map(|k| (b >> k) & 1));
+    }
+}
+
+/// Bits of a stream carrying one voice frame per entry of `voice`, from the start of a
+/// superframe, whose slow data repeats the message and the header, in that order.
+pub fn encode_stream(header: &Header, message: &str, voice: &[[u8; 9]]) -> Vec<u8> {
+    let mut blocks: Vec<[u8; 6]> = Vec::new();
+    let text: Vec<u8> = padded(message, MESSAGE_LEN).collect();
+    for (n, chunk) in text.chunks(5).enumerate() {
+        let mut block = [BLOCK_MESSAGE | n as u8, 0, 0, 0, 0, 0];
+        block[1..].copy_from_slice(chunk);
+        blocks.push(block);
+    }
+    for chunk in header.to_bytes().chunks(5) {
+        let mut block = [BLOCK_FILL; 6];
+        block[0] = BLOCK_HEADER | chunk.len() as u8;
+        block[1..=chunk.len()].copy_from_slice(chunk);
+        blocks.push(block);
+    }
+    let mut bits = Vec::with_capacity(voice.len() * FRAME_BITS as usize);
+    let mut next = blocks
+        .iter()
+        .cycle()
+        .flat_map(|b| [[b[0], b[1], b[2]], [b[3], b[4], b[5]]]);
+    for superframe in voice.chunks(SUPERFRAME_FRAMES as usize) {
+        for (n, frame) in superframe.iter().enumerate() {
+            push_bytes(&mut bits, frame);
+            if n == 0 {
+                push_bytes(&mut bits, &DATA_SYNC);
+            } else {
+                let half = next.next().unwrap_or_default();
+                let scrambled: Vec<u8> = half.iter().zip(SCRAMBLER).map(|(b, s)| b ^ s).collect();
+                push_bytes(&mut bits, &scrambled);
+            }
+        }
+    }
+    bits
+}
+
+const fn sync_pattern() -> u32 {
+    let mut pattern = 0u32;
+    let mut i = 0;
+    while i < SLOW_BITS {
+        pattern = (pattern << 1) | ((DATA_SYNC[i / 8] >> (i % 8)) & 1) as u32;
+        i += 1;
+    }
+    pattern
+}
+
+const SYNC_PATTERN: u32 = sync_pattern();
+const HISTORY: usize = 128;
+
+/// Slow data blocks, two frames each, and what they assemble into.
+struct SlowData {
+    block: [u8; 6],
+    len: usize,
+    header: Vec<u8>,
+    message: [u8; MESSAGE_LEN],
+    message_blocks: u8,
+}
+
+impl SlowData {
+    fn new() -> Self {
+        Self {
+            block: [0; 6],
+            len: 0,
+            header: Vec::new(),
+            message: [b' '; MESSAGE_LEN],
+            message_blocks: 0,
+        }
+    }
+
+    /// Starts a new block; blocks restart at every data sync.
+    fn align(&mut self) {
+        self.len = 0;
+    }
+
+    fn push(&mut self, bytes: [u8; 3], events: &mut Vec<Event>) {
+        for (b, s) in bytes.iter().zip(SCRAMBLER) {
+            self.block[self.len] = b ^ s;
+            self.len += 1;
+        }
+        if self.len < self.block.len() {
+            return;
+        }
+        self.len = 0;
+        let kind = self.block[0] & 0xF0;
+        let n = usize::from(self.block[0] & 0x0F);
+        match kind {
+            BLOCK_MESSAGE if n < usize::from(MESSAGE_BLOCKS) => {
+                self.message[n * 5..n * 5 + 5].copy_from_slice(&self.block[1..]);
+                self.message_blocks |= 1 << n;
+                if self.message_blocks == (1 << MESSAGE_BLOCKS) - 1 {
+                    self.message_blocks = 0;
+                    events.push(Event::DstarMessage(field(&self.message)));
+                }
+            }
+            BLOCK_HEADER => {
+                self.header.extend_from_slice(&self.block[1..1 + n.min(5)]);
+                if self.header.len() > 2 * HEADER_LEN {
+                    let excess = self.header.len() - 2 * HEADER_LEN;
+                    self.header.drain(..excess);
+                }
+                let found = self
+                    .header
+                    .windows(HEADER_LEN)
+                    .find_map(|w| Header::from_bytes(w.try_into().ok()?));
+                if let Some(header) = found {
+                    self.header.clear();
+                    events.push(Event::DstarHeader(header));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(super) struct Tracker {
+    bits: [u8; HISTORY],
+    reg: u32,
+    index: u64,
+    inverted: bool,
+    last_sync: Option<u64>,
+    confirmed: bool,
+    /// End of the next frame to read, and its number in the superframe.
+    next_frame: Option<(u64, u64)>,
+    slow: SlowData,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self {
+            bits: [0; HISTORY],
+            reg: 0,
+            index: 0,
+            inverted: false,
+            last_sync: None,
+            confirmed: false,
+            next_frame: None,
+            slow: SlowData::new(),
+        }
+    }
+
+    pub fn locked(&self) -> bool {
+        self.confirmed
+    }
+
+    pub fn push(&mut self, value: f32, events: &mut Vec<Event>) {
+        let i = self.index;
+        self.index += 1;
+        let bit = u8::from(value > 0.0);
+        self.bits[i as usize % HISTORY] = bit;
+        self.reg = ((self.reg << 1) | u32::from(bit)) & 0x00FF_FFFF;
+
+        let full = i + 1 >= SLOW_BITS as u64;
+        let errors = (self.reg ^ SYNC_PATTERN).count_ones();
+        if full && (errors <= MAX_SYNC_ERRORS || errors >= SLOW_BITS as u32 - MAX_SYNC_ERRORS) {
+            self.inverted = errors > MAX_SYNC_ERRORS;
+            self.on_sync(i, events);
+        } else if let Some((end, n)) = self.next_frame.filter(|&(end, _)| end == i) {
+            if self.confirmed {
+                events.push(Event::Activity(Mode::Dstar));
+                events.push(Event::Voice(VoiceFrame::Dstar(self.voice_frame(end))));
+            }
+            self.slow.push(self.slow_bytes(end), events);
+            self.next_frame = (n + 1 < SUPERFRAME_FRAMES).then_some((end + FRAME_BITS, n + 1));
+        }
+
+        if self.last_sync.is_some_and(|p| i - p > HOLD_SYMBOLS) {
+            self.confirmed = false;
+            self.next_frame = None;
+        }
+    }
+
+    fn on_sync(&mut self, i: u64, events: &mut Vec<Event>) {
+        if let Some(p) = self.last_sync {
+            let period = SUPERFRAME_FRAMES * FRAME_BITS;
+            let d = i - p;
+            let phase = d % period;
+            let in_step = phase <= 1 || phase >= period - 1;
+            if self.confirmed && !in_step {
+                // A sync look-alike in the voice bits of a followed stream.
+                return;
+            }
+            if d <= 4 * period && in_step {
+                self.confirmed = true;
+            }
+        }
+        self.last_sync = Some(i);
+        if self.confirmed {
+            // The sync takes the slow data of the superframe's first frame, not its voice.
+            events.push(Event::Activity(Mode::Dstar));
+            events.push(Event::Voice(VoiceFrame::Dstar(self.voice_frame(i))));
+        }
+        self.slow.align();
+        self.next_frame = Some((i + FRAME_BITS, 1));
+    }
+
+    fn bit(&self, i: u64) -> u8 {
+        self.bits[i as usize % HISTORY] ^ u8::from(self.inverted)
+    }
+
+    fn voice_frame(&self, end: u64) -> AmbeFrame {
+        let start = end + 1 - FRAME_BITS;
+        let mut frame = [[0u8; 24]; 4];
+        for k in 0..VOICE_BITS {
+            frame[AMBE_W[k]][AMBE_X[k]] = self.bit(start + k as u64);
+        }
+        frame
+    }
+
+    fn slow_bytes(&self, end: u64) -> [u8; 3] {
+        let start = end + 1 - SLOW_BITS as u64;
+        let mut out = [0u8; 3];
+        for k in 0..SLOW_BITS {
+            out[k / 8] |= self.bit(start + k as u64) << (k % 8);
+        }
+        out
+    }
+}
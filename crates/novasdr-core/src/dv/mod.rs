@@ -0,0 +1,228 @@
+//! Digital voice on narrowband FM channels: DMR, D-STAR and System Fusion are recognised from
+//! the discriminator output. DMR calls yield their IDs, D-STAR calls their header and text
+//! message, and all three their voice frames for a vocoder. System Fusion voice is taken from
+//! VD mode 2 frames only; VD mode 1 and Voice FR calls are reported but yield no voice.
+
+pub mod dmr;
+pub mod dstar;
+mod symbols;
+pub mod ysf;
+
+pub use crate::protocol::{DigitalVoiceCall as CallInfo, DigitalVoiceMode as Mode};
+pub use symbols::SymbolClock;
+
+use std::collections::VecDeque;
+
+/// All three modes signal at 4800 symbols a second.
+pub const SYMBOL_RATE: f64 = 4_800.0;
+/// A call ends after a second without its sync or voice.
+const HOLD_SYMBOLS: u64 = 4_800;
+/// Voice frames kept for a caller that falls behind: one second in every mode.
+const MAX_VOICE_FRAMES: usize = 50;
+const MAX_UPDATES: usize = 16;
+
+/// One AMBE frame as 4 rows of bits (24, 23, 11 and 14 used), before error correction.
+pub type AmbeFrame = [[u8; 24]; 4];
+
+/// One voice frame for the vocoder, in the layout mbelib takes for its mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceFrame {
+    /// AMBE+2 at 3600x2450, before error correction.
+    Dmr(AmbeFrame),
+    /// AMBE at 3600x2400, before error correction.
+    Dstar(AmbeFrame),
+    /// The 49 AMBE+2 parameter bits of a System Fusion VD mode 2 frame, after the repetition vote.
+    Ysf([u8; ysf::VOICE_BITS]),
+}
+
+fn new_call(mode: Mode) -> CallInfo {
+    CallInfo {
+        mode,
+        active: true,
+        color_code: None,
+        source: None,
+        destination: None,
+        group: None,
+        callsign: None,
+        suffix: None,
+        your: None,
+        rpt1: None,
+        rpt2: None,
+        message: None,
+    }
+}
+
+/// What the per-mode trackers report, in stream order.
+enum Event {
+    /// Sync or voice of a confirmed signal.
+    Activity(Mode),
+    Dmr {
+        color_code: u8,
+        lc: dmr::FullLc,
+    },
+    DstarHeader(dstar::Header),
+    DstarMessage(String),
+    Voice(VoiceFrame),
+    End(Mode),
+}
+
+/// Full chain from FM discriminator output (radians per sample) to call information and voice.
+pub struct Decoder {
+    clock: SymbolClock,
+    symbols: Vec<f32>,
+    dmr: dmr::Tracker,
+    dstar: dstar::Tracker,
+    ysf: ysf::Tracker,
+    events: Vec<Event>,
+    /// Symbols seen so far, and when the current call was last heard.
+    index: u64,
+    last_activity: u64,
+    call: Option<CallInfo>,
+    updates: VecDeque<CallInfo>,
+    voice: VecDeque<VoiceFrame>,
+}
+
+impl Decoder {
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            clock: SymbolClock::new(sample_rate),
+            symbols: Vec::new(),
+            dmr: dmr::Tracker::new(),
+            dstar: dstar::Tracker::new(),
+            ysf: ysf::Tracker::new(),
+            events: Vec::new(),
+            index: 0,
+            last_activity: 0,
+            call: None,
+            updates: VecDeque::new(),
+            voice: VecDeque::new(),
+        }
+    }
+
+    pub fn process(&mut self, samples: &[f32]) {
+        self.symbols.clear();
+        self.clock.process(samples, &mut self.symbols);
+        let before = self.call.clone();
+        for i in 0..self.symbols.len() {
+            let v = self.symbols[i];
+            self.dmr.push(v, &mut self.events);
+            self.dstar.push(v, &mut self.events);
+            self.ysf.push(v, &mut self.events);
+            self.index += 1;
+            let events = std::mem::take(&mut self.events);
+            for event in events {
+                self.apply(event);
+            }
+        }
+        if self.call.is_some() && self.index - self.last_activity > HOLD_SYMBOLS {
+            self.end_call();
+        }
+        if self.call.is_some() && self.call != before {
+            self.publish();
+        }
+    }
+
+    fn apply(&mut self, event: Event) {
+        match event {
+            Event::Activity(mode) => {
+                self.start(mode);
+                self.last_activity = self.index;
+            }
+            Event::Dmr { color_code, lc } => {
+                if let Some(call) = self.call.as_mut().filter(|c| c.mode == Mode::Dmr) {
+                    call.color_code = Some(color_code);
+                    call.source = Some(lc.source);
+                    call.destination = Some(lc.destination);
+                    call.group = lc.group();
+                }
+            }
+            Event::DstarHeader(header) => {
+                // The header CRC is as good as a second sync.
+                let call = self.start(Mode::Dstar);
+                call.callsign = Some(header.my);
+                call.suffix = Some(header.suffix).filter(|s| !s.is_empty());
+                call.your = Some(header.your);
+                call.rpt1 = Some(header.rpt1);
+                call.rpt2 = Some(header.rpt2);
+                self.last_activity = self.index;
+            }
+            Event::DstarMessage(message) => {
+                if let Some(call) = self.call.as_mut().filter(|c| c.mode == Mode::Dstar) {
+                    call.message = Some(message);
+                }
+            }
+            Event::Voice(frame) => {
+                if self.voice.len() == MAX_VOICE_FRAMES {
+                    self.voice.pop_front();
+                }
+                self.voice.push_back(frame);
+            }
+            Event::End(mode) => {
+                if self.call.as_ref().is_some_and(|c| c.mode == mode) {
+                    self.end_call();
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, mode: Mode) -> &mut CallInfo {
+        if self.call.as_ref().is_some_and(|c| c.mode != mode) {
+            self.end_call();
+        }
+        self.call.get_or_insert_with(|| new_call(mode))
+    }
+
+    fn end_call(&mut self) {
+        if let Some(mut call) = self.call.take() {
+            call.active = false;
+            self.push_update(call);
+        }
+    }
+
+    fn publish(&mut self) {
+        if let Some(call) = self.call.clone() {
+            self.push_update(call);
+        }
+    }
+
+    fn push_update(&mut self, call: CallInfo) {
+        if self.updates.len() == MAX_UPDATES {
+            self.updates.pop_front();
+        }
+        self.updates.push_back(call);
+    }
+
+    /// The next change to the call: a new call, new details, or its end (`active` false).
+    pub fn take_update(&mut self) -> Option<CallInfo> {
+        self.updates.pop_front()
+    }
+
+    /// Mode of the call in progress.
+    pub fn active(&self) -> Option<Mode> {
+        self.call.as_ref().map(|c| c.mode)
+    }
+
+    /// Whether a digital signal is present, in a call or not (e.g. a repeater's idle bursts).
+    pub fn digital(&self) -> bool {
+        self.call.is_some() || self.dmr.locked() || self.dstar.locked() || self.ysf.locked()
+    }
+
+    /// The oldest voice frame not yet taken.
+    pub fn pop_voice(&mut self) -> Option<VoiceFrame> {
+        self.voice.pop_front()
+    }
+
+    /// Forgets the signal, e.g. after a squelch closed or a retune; a call in progress ends with
+    /// a final update.
+    pub fn reset(&mut self) {
+        self.end_call();
+        self.clock.reset();
+        self.dmr = dmr::Tracker::new();
+        self.dstar = dstar::Tracker::new();
+        self.ysf = ysf::Tracker::new();
+        self.events.clear();
+        self.index = 0;
+        self.last_activity = 0;
+        self.voice.clear();
+    }
+}
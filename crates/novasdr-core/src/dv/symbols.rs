@@ -0,0 +1,99 @@
+use super::SYMBOL_RATE;
+use crate::dsp::fir::{lowpass_taps, FirDecimator};
+
+/// Pre-filter on the discriminator output: the 4800-baud shaped symbol stream ends near 2.9 kHz.
+const LOWPASS_HZ: f32 = 2_900.0;
+const LOWPASS_TRANSITION_HZ: f32 = 1_200.0;
+/// Gardner loop gain, as a fraction of a symbol per unit of normalised timing error.
+const TIMING_GAIN: f32 = 0.04;
+/// Time constants, in symbols, of the DC (tuning offset) and level averages.
+const DC_SYMBOLS: f32 = 2_000.0;
+const LEVEL_SYMBOLS: f32 = 500.0;
+const LEVEL_FLOOR: f32 = 1e-6;
+
+/// Recovers symbol-centre values from FM discriminator output: a low-pass, a Gardner timing loop
+/// on a cubic interpolator and a running DC removal. Works from two samples per symbol up.
+pub struct SymbolClock {
+    sps: f64,
+    filter: FirDecimator<f32>,
+    filtered: Vec<f32>,
+    /// Filtered samples not yet behind the next mid-symbol point.
+    buf: Vec<f32>,
+    /// Position of the next symbol centre in `buf`.
+    next: f64,
+    prev: f32,
+    dc: f32,
+    level: f32,
+}
+
+impl SymbolClock {
+    pub fn new(sample_rate: f64) -> Self {
+        let rate = sample_rate as f32;
+        let cutoff = (LOWPASS_HZ / rate).min(0.45);
+        let taps = lowpass_taps(cutoff, LOWPASS_TRANSITION_HZ / rate);
+        let sps = sample_rate / SYMBOL_RATE;
+        Self {
+            sps,
+            filter: FirDecimator::new(taps, 1),
+            filtered: Vec::new(),
+            buf: Vec::new(),
+            next: sps + 2.0,
+            prev: 0.0,
+            dc: 0.0,
+            level: LEVEL_FLOOR,
+        }
+    }
+
+    /// Appends the centre value of every symbol completed by `input`, DC removed, to `out`.
+    pub fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        self.filtered.clear();
+        self.filter.process(input, &mut self.filtered);
+        self.buf.extend_from_slice(&self.filtered);
+        while self.next + 2.0 < self.buf.len() as f64 {
+            let centre = self.interp(self.next) - self.dc;
+            let mid = self.interp(self.next - self.sps / 2.0) - self.dc;
+            // Gardner: a late clock puts the mid-point past the zero of the transition, on the
+            // side of the newer symbol.
+            let scale = self.level * self.level;
+            let err = ((centre - self.prev) * mid / scale).clamp(-1.0, 1.0);
+            self.prev = centre;
+            self.dc += centre / DC_SYMBOLS;
+            self.level =
+                (self.level + (centre.abs() - self.level) / LEVEL_SYMBOLS).max(LEVEL_FLOOR);
+            out.push(centre);
+            self.next += self.sps * f64::from(1.0 - TIMING_GAIN * err);
+        }
+        // Keep what the next mid-point and its interpolation need.
+        let keep_from = (self.next - self.sps / 2.0).floor() as isize - 2;
+        if keep_from > 0 {
+            let drop = (keep_from as usize).min(self.buf.len());
+            self.buf.drain(..drop);
+            self.next -= drop as f64;
+        }
+    }
+
+    /// Catmull-Rom interpolation of `buf` at `t`.
+    fn interp(&self, t: f64) -> f32 {
+        let i = (t.floor() as usize).clamp(1, self.buf.len().saturating_sub(3));
+        let f = (t - i as f64).clamp(0.0, 1.0) as f32;
+        let (p0, p1, p2, p3) = (
+            self.buf[i - 1],
+            self.buf[i],
+            self.buf[i + 1],
+            self.buf[i + 2],
+        );
+        p1 + 0.5
+            * f
+            * (p2 - p0
+                + f * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3 + f * (3.0 * (p1 - p2) + p3 - p0)))
+    }
+
+    pub fn reset(&mut self) {
+        self.filter.reset();
+        self.buf.clear();
+        self.next = self.sps + 2.0;
+        self.prev = 0.0;
+        self.dc = 0.0;
+        self.level = LEVEL_FLOOR;
+    }
+}
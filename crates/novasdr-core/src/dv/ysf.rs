@@ -0,0 +1,222 @@
+//! System Fusion (YSF): frame sync and the voice channel of VD mode 2 frames. Headers and the
+//! FICH are not decoded; VD mode 2 is told from the other frame types by its repeated voice bits.
+
+use super::dmr::{dibit, level};
+use super::{Event, Mode, VoiceFrame, HOLD_SYMBOLS};
+
+/// Frame sync, 20 symbols at the start of every 100 ms frame.
+pub const SYNC: u64 = 0xD4_71C9_634D;
+pub const SYNC_SYMBOLS: usize = 20;
+pub const FRAME_SYMBOLS: u64 = 480;
+const MAX_SYNC_ERRORS: u32 = 1;
+/// Frames a second sync may come after the first and still confirm it.
+const CONFIRM_FRAMES: u64 = 4;
+
+/// AMBE+2 parameter bits of one VD mode 2 voice frame.
+pub const VOICE_BITS: usize = 49;
+/// Each frame ends in five blocks of 20 data and 52 voice symbols, after sync and FICH.
+pub const VOICE_FRAMES: usize = 5;
+const FICH_SYMBOLS: usize = 100;
+const PAYLOAD_SYMBOLS: usize = FRAME_SYMBOLS as usize - SYNC_SYMBOLS - FICH_SYMBOLS;
+const BLOCK_BITS: usize = 144;
+const DATA_BITS: usize = 40;
+const VCH_BITS: usize = 104;
+/// The first 27 voice bits are sent three times each.
+const REPEATED_BITS: usize = 27;
+/// Repeated bits, over a frame's five voice channels, whose copies must all agree for the frame
+/// to count as VD mode 2. Random bits agree a quarter of the time.
+const MIN_AGREEING: usize = VOICE_FRAMES * REPEATED_BITS * 2 / 3;
+/// XORed over the voice channel before transmission.
+const WHITENING: [u8; 13] = [
+    0x93, 0xD7, 0x51, 0x21, 0x9C, 0x2F, 0x6C, 0xD0, 0xEF, 0x0F, 0xF8, 0x3D, 0xF1,
+];
+
+const fn sign_pattern(sync: u64) -> u32 {
+    let mut pattern = 0u32;
+    let mut k = 0;
+    while k < SYNC_SYMBOLS {
+        pattern = (pattern << 1) | ((sync >> (39 - 2 * k)) & 1) as u32;
+        k += 1;
+    }
+    pattern
+}
+
+const PATTERN: u32 = sign_pattern(SYNC);
+
+fn sync_dibit(k: usize) -> u8 {
+    ((SYNC >> (38 - 2 * k)) & 3) as u8
+}
+
+/// Bit `i` of the voice channel sits at this bit of its block's 104, which are sent 26 by 4.
+fn vch_position(i: usize) -> usize {
+    (i % 26) * 4 + i / 26
+}
+
+fn whitening(i: usize) -> u8 {
+    (WHITENING[i / 8] >> (7 - i % 8)) & 1
+}
+
+/// The voice channel bits of one VD mode 2 frame.
+fn encode_vch(voice: &[u8; VOICE_BITS]) -> [u8; VCH_BITS] {
+    let mut vch = [0u8; VCH_BITS];
+    for (i, &bit) in voice[..REPEATED_BITS].iter().enumerate() {
+        vch[3 * i..3 * i + 3].fill(bit);
+    }
+    vch[3 * REPEATED_BITS..VCH_BITS - 1].copy_from_slice(&voice[REPEATED_BITS..]);
+    for (i, bit) in vch.iter_mut().enumerate() {
+        *bit ^= whitening(i);
+    }
+    vch
+}
+
+/// Voice bits of one voice channel, and how many of the repeated ones agreed in all copies.
+fn decode_vch(vch: &[u8; VCH_BITS]) -> ([u8; VOICE_BITS], usize) {
+    let bits: Vec<u8> = (0..VCH_BITS).map(|i| vch[i] ^ whitening(i)).collect();
+    let mut voice = [0u8; VOICE_BITS];
+    let mut agreeing = 0;
+    for (i, copies) in bits[..3 * REPEATED_BITS].chunks(3).enumerate() {
+        let ones: u8 = copies.iter().sum();
+        voice[i] = u8::from(ones >= 2);
+        agreeing += usize::from(ones == 0 || ones == 3);
+    }
+    voice[REPEATED_BITS..].copy_from_slice(&bits[3 * REPEATED_BITS..VCH_BITS - 1]);
+    (voice, agreeing)
+}
+
+/// Payload bit `n` is the high (even `n`) or low bit of a dibit.
+fn payload_bit(dibits: &[u8], n: usize) -> u8 {
+    (dibits[n / 2] >> (1 - n % 2)) & 1
+}
+
+/// A VD mode 2 frame as dibits, with FICH and data channel left zero.
+pub fn encode_vd2_frame(voice: &[[u8; VOICE_BITS]; VOICE_FRAMES]) -> [u8; FRAME_SYMBOLS as usize] {
+    let mut dibits = [0u8; FRAME_SYMBOLS as usize];
+    for (k, d) in dibits[..SYNC_SYMBOLS].iter_mut().enumerate() {
+        *d = sync_dibit(k);
+    }
+    let payload = &mut dibits[SYNC_SYMBOLS + FICH_SYMBOLS..];
+    for (block, frame) in voice.iter().enumerate() {
+        let vch = encode_vch(frame);
+        for (i, &bit) in vch.iter().enumerate() {
+            let n = block * BLOCK_BITS + DATA_BITS + vch_position(i);
+            payload[n / 2] |= bit << (1 - n % 2);
+        }
+    }
+    dibits
+}
+
+/// The voice of the five voice channels of a frame's payload, if it is VD mode 2.
+fn vd2_voice(payload: &[u8; PAYLOAD_SYMBOLS]) -> Option<[[u8; VOICE_BITS]; VOICE_FRAMES]> {
+    let mut voice = [[0u8; VOICE_BITS]; VOICE_FRAMES];
+    let mut agreeing = 0;
+    for (block, frame) in voice.iter_mut().enumerate() {
+        let mut vch = [0u8; VCH_BITS];
+        for (i, bit) in vch.iter_mut().enumerate() {
+            *bit = payload_bit(payload, block * BLOCK_BITS + DATA_BITS + vch_position(i));
+        }
+        let (bits, agreed) = decode_vch(&vch);
+        *frame = bits;
+        agreeing += agreed;
+    }
+    (agreeing >= MIN_AGREEING).then_some(voice)
+}
+
+const HISTORY: usize = 512;
+
+pub(super) struct Tracker {
+    history: [f32; HISTORY],
+    signs: u32,
+    index: u64,
+    last_sync: Option<u64>,
+    confirmed: bool,
+    offset: f32,
+    unit: f32,
+    /// End of the frame behind the last sync.
+    frame_end: Option<u64>,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self {
+            history: [0.0; HISTORY],
+            signs: 0,
+            index: 0,
+            last_sync: None,
+            confirmed: false,
+            offset: 0.0,
+            unit: 1.0,
+            frame_end: None,
+        }
+    }
+
+    pub fn locked(&self) -> bool {
+        self.confirmed
+    }
+
+    pub fn push(&mut self, value: f32, events: &mut Vec<Event>) {
+        let i = self.index;
+        self.index += 1;
+        self.history[i as usize % HISTORY] = value;
+        self.signs = ((self.signs << 1) | u32::from(value < 0.0)) & ((1 << SYNC_SYMBOLS) - 1);
+        let full = i + 1 >= SYNC_SYMBOLS as u64;
+        if full && (self.signs ^ PATTERN).count_ones() <= MAX_SYNC_ERRORS {
+            if let Some(p) = self.last_sync {
+                let d = i - p;
+                let phase = d % FRAME_SYMBOLS;
+                if d <= CONFIRM_FRAMES * FRAME_SYMBOLS && (phase <= 1 || phase >= FRAME_SYMBOLS - 1)
+                {
+                    self.confirmed = true;
+                }
+            }
+            self.last_sync = Some(i);
+            self.fit_levels(i);
+            self.frame_end = Some(i + FRAME_SYMBOLS - SYNC_SYMBOLS as u64);
+            if self.confirmed {
+                events.push(Event::Activity(Mode::Ysf));
+            }
+        }
+        if self.frame_end == Some(i) {
+            self.frame_end = None;
+            if self.confirmed {
+                self.voice(i, events);
+            }
+        }
+        if self.last_sync.is_some_and(|p| i - p > HOLD_SYMBOLS) {
+            self.confirmed = false;
+        }
+    }
+
+    fn value(&self, i: u64) -> f32 {
+        self.history[i as usize % HISTORY]
+    }
+
+    /// Sets the slicer's zero and unit from the sync ending at `i`, a least-squares fit of its
+    /// values to their levels (the sync uses all four).
+    fn fit_levels(&mut self, i: u64) {
+        let start = i + 1 - SYNC_SYMBOLS as u64;
+        let pairs: Vec<(f32, f32)> = (0..SYNC_SYMBOLS)
+            .map(|k| (level(sync_dibit(k)), self.value(start + k as u64)))
+            .collect();
+        let n = SYNC_SYMBOLS as f32;
+        let mean_level = pairs.iter().map(|(l, _)| l).sum::<f32>() / n;
+        let mean_value = pairs.iter().map(|(_, v)| v).sum::<f32>() / n;
+        let (mut cov, mut var) = (0.0f32, 0.0f32);
+        for (l, v) in &pairs {
+            cov += (l - mean_level) * (v - mean_value);
+            var += (l - mean_level) * (l - mean_level);
+        }
+        self.unit = (cov / var).max(1e-6);
+        self.offset = mean_value - self.unit * mean_level;
+    }
+
+    fn voice(&self, end: u64, events: &mut Vec<Event>) {
+        let start = end + 1 - PAYLOAD_SYMBOLS as u64;
+        let mut payload = [0u8; PAYLOAD_SYMBOLS];
+        for (k, d) in payload.iter_mut().enumerate() {
+            *d = dibit((self.value(start + k as u64) - self.offset) / self.unit);
+        }
+        if let Some(frames) = vd2_voice(&payload) {
+            events.extend(frames.map(|f| Event::Voice(VoiceFrame::Ysf(f))));
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::Context;
+use serde::Serialize;
+
+/// A known user or allocation: a single frequency (`start_hz == end_hz`) or a range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FreqEntry {
+    pub start_hz: i64,
+    pub end_hz: i64,
+    pub name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl FreqEntry {
+    /// Distance from `hz` to the entry; 0 inside a range.
+    pub fn distance(&self, hz: i64) -> i64 {
+        if hz < self.start_hz {
+            self.start_hz - hz
+        } else if hz > self.end_hz {
+            hz - self.end_hz
+        } else {
+            0
+        }
+    }
+}
+
+/// Splits one CSV line, honouring double-quoted fields (`""` is a literal quote).
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut cur = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cur.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut cur)),
+            _ => cur.push(c),
+        }
+    }
+    fields.push(cur);
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// Parses `start_hz,end_hz,name,notes` lines. `end_hz` may be empty for a single frequency and
+/// `notes` may be left out. Blank lines, `#` comments and a header line (first field not a
+/// number) are skipped.
+pub fn parse_csv(raw: &str) -> anyhow::Result<Vec<FreqEntry>> {
+    let mut out = Vec::new();
+    for (idx, line) in raw.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = split_csv_line(line);
+        let Ok(start_hz) = fields[0].parse::<i64>() else {
+            if idx == 0 {
+                continue;
+            }
+            anyhow::bail!("line {}: start_hz {:?} is not a number", idx + 1, fields[0]);
+        };
+        let end_hz = match fields.get(1).map(String::as_str) {
+            None | Some("") => start_hz,
+            Some(v) => v
+                .parse::<i64>()
+                .with_context(|| format!("line {}: end_hz {v:?} is not a number", idx + 1))?,
+        };
+        if end_hz < start_hz {
+            anyhow::bail!("line {}: end_hz is below start_hz", idx + 1);
+        }
+        let name = fields.get(2).cloned().unwrap_or_default();
+        if name.is_empty() {
+            anyhow::bail!("line {}: name is empty", idx + 1);
+        }
+        out.push(FreqEntry {
+            start_hz,
+            end_hz,
+            name,
+            notes: fields.get(3).cloned().unwrap_or_default(),
+        });
+    }
+    Ok(out)
+}
+
+/// Entries within `tolerance_hz` of `hz`, nearest first and narrowest first among equals (a
+/// station before the band it is in), at most `max`.
+pub fn lookup(entries: &[FreqEntry], hz: i64, tolerance_hz: i64, max: usize) -> Vec<FreqEntry> {
+    let mut hits: Vec<&FreqEntry> = entries
+        .iter()
+        .filter(|e| e.distance(hz) <= tolerance_hz)
+        .collect();
+    hits.sort_by_key(|e| (e.distance(hz), e.end_hz - e.start_hz));
+    hits.into_iter().take(max).cloned().collect()
+}
+
+/// Fills `{hz}`, `{khz}` and `{mhz}` in a lookup URL template.
+pub fn expand_url(template: &str, hz: i64) -> String {
+    template
+        .replace("{hz}", &hz.to_string())
+        .replace("{khz}", &format!("{}", hz as f64 / 1e3))
+        .replace("{mhz}", &format!("{}", hz as f64 / 1e6))
+}
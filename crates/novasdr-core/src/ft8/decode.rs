@@ -0,0 +1,340 @@
+use super::constants::{FT4_XOR, LDPC_N};
+use super::{encode_tones, ldpc, message, Message, Protocol, SAMPLE_RATE};
+use crate::dsp::window::hann_window;
+use realfft::RealFftPlanner;
+use std::collections::HashSet;
+
+/// Spectrogram steps per symbol.
+const TIME_OSR: usize = 2;
+/// Spectrogram bins per tone spacing.
+const FREQ_OSR: usize = 2;
+const POWER_FLOOR: f32 = 1e-12;
+/// Variance the bit log-likelihoods are normalised to before belief propagation.
+const LLR_VARIANCE: f32 = 24.0;
+/// Reference noise bandwidth of the WSJT-X SNR convention.
+const SNR_REFERENCE_BW_HZ: f32 = 2500.0;
+/// Equivalent noise bandwidth of a Hann window, in bins.
+const HANN_ENBW_BINS: f32 = 1.5;
+/// Earliest frame start searched, relative to the nominal start.
+const MIN_DT_S: f32 = -1.0;
+
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    pub min_freq_hz: f32,
+    pub max_freq_hz: f32,
+    /// Latest frame start searched, relative to the nominal start.
+    pub max_dt_s: f32,
+    /// Minimum average Costas contrast in dB for a sync candidate.
+    pub min_sync_score: f32,
+    pub max_candidates: usize,
+    pub ldpc_iterations: usize,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            min_freq_hz: 100.0,
+            max_freq_hz: 3000.0,
+            max_dt_s: 2.5,
+            min_sync_score: 2.0,
+            max_candidates: 200,
+            ldpc_iterations: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    pub message: Message,
+    /// Audio frequency of the lowest tone in Hz.
+    pub freq_hz: f32,
+    /// Frame start relative to the nominal start in seconds.
+    pub dt_s: f32,
+    /// Signal-to-noise ratio in the 2500 Hz WSJT-X reference bandwidth.
+    pub snr_db: i32,
+}
+
+struct Spectrogram {
+    steps: usize,
+    bins: usize,
+    power: Vec<f32>,
+    db: Vec<f32>,
+    /// Median power of each bin over time, used as the noise reference.
+    noise: Vec<f32>,
+}
+
+impl Spectrogram {
+    fn new(protocol: Protocol, samples: &[f32]) -> Self {
+        let nfft = protocol.symbol_samples() * FREQ_OSR;
+        let hop = protocol.symbol_samples() / TIME_OSR;
+        let bins = nfft / 2;
+        let steps = if samples.len() >= nfft {
+            (samples.len() - nfft) / hop + 1
+        } else {
+            0
+        };
+
+        let window = hann_window(nfft);
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(nfft);
+        let mut input = fft.make_input_vec();
+        let mut output = fft.make_output_vec();
+        let mut scratch = fft.make_scratch_vec();
+
+        let mut power = vec![0f32; steps * bins];
+        for t in 0..steps {
+            let frame = &samples[t * hop..t * hop + nfft];
+            for ((dst, &x), &w) in input.iter_mut().zip(frame.iter()).zip(window.iter()) {
+                *dst = x * w;
+            }
+            if fft
+                .process_with_scratch(&mut input, &mut output, &mut scratch)
+                .is_err()
+            {
+                continue;
+            }
+            for (dst, c) in power[t * bins..(t + 1) * bins]
+                .iter_mut()
+                .zip(output.iter())
+            {
+                *dst = c.norm_sqr();
+            }
+        }
+        let db = power
+            .iter()
+            .map(|p| 10.0 * (p + POWER_FLOOR).log10())
+            .collect();
+
+        let mut noise = vec![POWER_FLOOR; bins];
+        if steps > 0 {
+            let mut column = vec![0f32; steps];
+            for (k, n) in noise.iter_mut().enumerate() {
+                for (t, c) in column.iter_mut().enumerate() {
+                    *c = power[t * bins + k];
+                }
+                column.sort_unstable_by(|a, b| a.total_cmp(b));
+                *n = column[steps / 2].max(POWER_FLOOR);
+            }
+        }
+
+        Self {
+            steps,
+            bins,
+            power,
+            db,
+            noise,
+        }
+    }
+
+    fn step(&self, t: isize) -> Option<usize> {
+        (t >= 0 && (t as usize) < self.steps).then_some(t as usize)
+    }
+
+    fn db_at(&self, t: usize, k: usize) -> f32 {
+        self.db[t * self.bins + k]
+    }
+
+    fn sync_score(&self, protocol: Protocol, t0: isize, k0: usize) -> f32 {
+        let num_tones = protocol.num_tones();
+        let sync_len = protocol.sync_len();
+        let mut score = 0f32;
+        let mut count = 0usize;
+        for (block, &offset) in protocol.sync_offsets().iter().enumerate() {
+            for k in 0..sync_len {
+                let Some(t) = self.step(t0 + (TIME_OSR * (offset + k)) as isize) else {
+                    continue;
+                };
+                let tone = protocol.sync_tone(block, k) as usize;
+                let bin = k0 + tone * FREQ_OSR;
+                let here = self.db_at(t, bin);
+                if tone > 0 {
+                    score += here - self.db_at(t, bin - FREQ_OSR);
+                    count += 1;
+                }
+                if tone + 1 < num_tones {
+                    score += here - self.db_at(t, bin + FREQ_OSR);
+                    count += 1;
+                }
+                if k > 0 && t >= TIME_OSR {
+                    score += here - self.db_at(t - TIME_OSR, bin);
+                    count += 1;
+                }
+                if k + 1 < sync_len && t + TIME_OSR < self.steps {
+                    score += here - self.db_at(t + TIME_OSR, bin);
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            f32::NEG_INFINITY
+        } else {
+            score / count as f32
+        }
+    }
+
+    fn llr(&self, protocol: Protocol, t0: isize, k0: usize) -> [f32; LDPC_N] {
+        let bits = protocol.bits_per_symbol();
+        let gray = protocol.gray();
+        let mut llr = [0f32; LDPC_N];
+        let mut tone_db = [0f32; 8];
+        for (i, sym) in protocol.data_symbols().enumerate() {
+            let Some(t) = self.step(t0 + (TIME_OSR * sym) as isize) else {
+                continue;
+            };
+            for (value, db) in tone_db.iter_mut().take(gray.len()).enumerate() {
+                *db = self.db_at(t, k0 + gray[value] as usize * FREQ_OSR);
+            }
+            for b in 0..bits {
+                let mask = 1usize << (bits - 1 - b);
+                let mut max1 = f32::NEG_INFINITY;
+                let mut max0 = f32::NEG_INFINITY;
+                for (value, &db) in tone_db.iter().take(gray.len()).enumerate() {
+                    if value & mask != 0 {
+                        max1 = max1.max(db);
+                    } else {
+                        max0 = max0.max(db);
+                    }
+                }
+                llr[i * bits + b] = max1 - max0;
+            }
+        }
+
+        let n = llr.len() as f32;
+        let mean = llr.iter().sum::<f32>() / n;
+        let variance = llr.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / n;
+        if variance > 0.0 {
+            let scale = (LLR_VARIANCE / variance).sqrt();
+            for v in llr.iter_mut() {
+                *v *= scale;
+            }
+        }
+        llr
+    }
+
+    fn snr_db(&self, protocol: Protocol, t0: isize, k0: usize, tones: &[u8]) -> i32 {
+        let mut signal = 0f32;
+        let mut count = 0usize;
+        for (sym, &tone) in tones.iter().enumerate() {
+            let Some(t) = self.step(t0 + (TIME_OSR * sym) as isize) else {
+                continue;
+            };
+            signal += self.power[t * self.bins + k0 + tone as usize * FREQ_OSR];
+            count += 1;
+        }
+        if count == 0 {
+            return 0;
+        }
+        let signal = signal / count as f32;
+        let noise = (0..protocol.num_tones())
+            .map(|j| self.noise[k0 + j * FREQ_OSR])
+            .sum::<f32>()
+            / protocol.num_tones() as f32;
+        let ratio = ((signal - noise) / noise).max(1e-3);
+        let bin_hz = protocol.tone_spacing_hz() / FREQ_OSR as f32;
+        let snr =
+            10.0 * ratio.log10() - 10.0 * (SNR_REFERENCE_BW_HZ / (HANN_ENBW_BINS * bin_hz)).log10();
+        snr.round().clamp(-30.0, 40.0) as i32
+    }
+}
+
+struct Candidate {
+    t0: isize,
+    k0: usize,
+    score: f32,
+}
+
+fn find_candidates(protocol: Protocol, spec: &Spectrogram, opts: &DecodeOptions) -> Vec<Candidate> {
+    let nsps = protocol.symbol_samples() as f32;
+    let hop = nsps / TIME_OSR as f32;
+    let bin_hz = protocol.tone_spacing_hz() / FREQ_OSR as f32;
+    let start_to_step = |start_s: f32| ((start_s * SAMPLE_RATE as f32 - nsps / 2.0) / hop).floor();
+
+    let t_min = start_to_step(protocol.nominal_start_s() + MIN_DT_S) as isize;
+    let t_max = start_to_step(protocol.nominal_start_s() + opts.max_dt_s) as isize;
+    let span = (protocol.num_tones() - 1) * FREQ_OSR;
+    let k_min = ((opts.min_freq_hz / bin_hz).ceil() as usize).max(1);
+    let k_max =
+        ((opts.max_freq_hz / bin_hz).floor() as usize).min(spec.bins.saturating_sub(span + 2));
+    if t_max < t_min || k_max < k_min {
+        return Vec::new();
+    }
+
+    let width = k_max - k_min + 1;
+    let height = (t_max - t_min + 1) as usize;
+    let mut scores = vec![f32::NEG_INFINITY; width * height];
+    for (row, t0) in (t_min..=t_max).enumerate() {
+        for (col, k0) in (k_min..=k_max).enumerate() {
+            scores[row * width + col] = spec.sync_score(protocol, t0, k0);
+        }
+    }
+
+    let mut out = Vec::new();
+    for row in 0..height {
+        for col in 0..width {
+            let score = scores[row * width + col];
+            if score < opts.min_sync_score {
+                continue;
+            }
+            let is_peak = (row.saturating_sub(1)..=(row + 1).min(height - 1)).all(|r| {
+                (col.saturating_sub(1)..=(col + 1).min(width - 1))
+                    .all(|c| (r == row && c == col) || scores[r * width + c] <= score)
+            });
+            if is_peak {
+                out.push(Candidate {
+                    t0: t_min + row as isize,
+                    k0: k_min + col,
+                    score,
+                });
+            }
+        }
+    }
+    out.sort_by(|a, b| b.score.total_cmp(&a.score));
+    out.truncate(opts.max_candidates);
+    out
+}
+
+/// Decodes all FT8 or FT4 frames in one receive slot.
+///
+/// `samples` is real audio at [`SAMPLE_RATE`] starting at the slot boundary; frequencies are
+/// reported relative to the start of that passband.
+pub fn decode(protocol: Protocol, samples: &[f32], opts: &DecodeOptions) -> Vec<Decoded> {
+    let spec = Spectrogram::new(protocol, samples);
+    if spec.steps == 0 {
+        return Vec::new();
+    }
+    let hop = (protocol.symbol_samples() / TIME_OSR) as f32;
+    let bin_hz = protocol.tone_spacing_hz() / FREQ_OSR as f32;
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for cand in find_candidates(protocol, &spec, opts) {
+        let llr = spec.llr(protocol, cand.t0, cand.k0);
+        let Some(bits) = ldpc::decode(&llr, opts.ldpc_iterations) else {
+            continue;
+        };
+        let Some(mut payload) = ldpc::check_crc(&bits) else {
+            continue;
+        };
+        if protocol == Protocol::Ft4 {
+            for (b, x) in payload.iter_mut().zip(FT4_XOR.iter()) {
+                *b ^= x;
+            }
+        }
+        let Some(message) = message::unpack(&payload) else {
+            continue;
+        };
+        if !seen.insert(message.text.clone()) {
+            continue;
+        }
+        let tones = encode_tones(protocol, &payload);
+        let start_s =
+            (cand.t0 as f32 * hop + protocol.symbol_samples() as f32 / 2.0) / SAMPLE_RATE as f32;
+        out.push(Decoded {
+            snr_db: spec.snr_db(protocol, cand.t0, cand.k0, &tones),
+            message,
+            freq_hz: cand.k0 as f32 * bin_hz,
+            dt_s: start_s - protocol.nominal_start_s(),
+        });
+    }
+    out
+}
@@ -0,0 +1,46 @@
+use super::constants::FT4_XOR;
+use super::{ldpc, Protocol, SAMPLE_RATE};
+
+/// Maps a 77-bit payload to the channel tone sequence of `protocol`.
+pub fn encode_tones(protocol: Protocol, payload: &[u8; 10]) -> Vec<u8> {
+    let mut payload = *payload;
+    if protocol == Protocol::Ft4 {
+        for (b, x) in payload.iter_mut().zip(FT4_XOR.iter()) {
+            *b ^= x;
+        }
+    }
+    let codeword = ldpc::encode(&ldpc::add_crc(&payload));
+
+    let bits = protocol.bits_per_symbol();
+    let gray = protocol.gray();
+    let mut tones = vec![0u8; protocol.num_symbols()];
+    for (block, &offset) in protocol.sync_offsets().iter().enumerate() {
+        for k in 0..protocol.sync_len() {
+            tones[offset + k] = protocol.sync_tone(block, k);
+        }
+    }
+    for (i, sym) in protocol.data_symbols().enumerate() {
+        let value = codeword[i * bits..(i + 1) * bits]
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 1) | b as usize);
+        tones[sym] = gray[value];
+    }
+    tones
+}
+
+/// Renders a tone sequence as continuous-phase FSK at [`SAMPLE_RATE`], starting at `base_hz`.
+pub fn synthesize(protocol: Protocol, tones: &[u8], base_hz: f32, amplitude: f32) -> Vec<f32> {
+    let nsps = protocol.symbol_samples();
+    let spacing = protocol.tone_spacing_hz();
+    let mut out = Vec::with_capacity(tones.len() * nsps);
+    let mut phase = 0f64;
+    for &tone in tones {
+        let freq = (base_hz + tone as f32 * spacing) as f64;
+        let step = std::f64::consts::TAU * freq / SAMPLE_RATE as f64;
+        for _ in 0..nsps {
+            out.push(amplitude * phase.sin() as f32);
+            phase = (phase + step) % std::f64::consts::TAU;
+        }
+    }
+    out
+}
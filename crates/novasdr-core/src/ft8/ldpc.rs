@@ -0,0 +1,153 @@
+use super::constants::{LDPC_GENERATOR, LDPC_K, LDPC_N, LDPC_NM};
+use std::sync::OnceLock;
+
+const CRC14_POLY: u16 = 0x2757;
+const CRC14_BITS: usize = 14;
+const ATANH_CLAMP: f32 = 0.999_99;
+
+pub fn get_bit(bytes: &[u8], idx: usize) -> u8 {
+    (bytes[idx / 8] >> (7 - (idx % 8))) & 1
+}
+
+pub fn set_bit(bytes: &mut [u8], idx: usize, bit: u8) {
+    let mask = 0x80u8 >> (idx % 8);
+    if bit != 0 {
+        bytes[idx / 8] |= mask;
+    } else {
+        bytes[idx / 8] &= !mask;
+    }
+}
+
+/// CRC-14 over the 77-bit payload, zero-extended to 82 bits as in the WSJT-X reference.
+pub fn crc14(payload: &[u8; 10]) -> u16 {
+    let mut padded = [0u8; 12];
+    padded[..10].copy_from_slice(payload);
+    padded[9] &= 0xf8;
+
+    let top = 1u16 << (CRC14_BITS - 1);
+    let mut remainder: u16 = 0;
+    for idx in 0..(96 - CRC14_BITS) {
+        if idx % 8 == 0 {
+            remainder ^= (padded[idx / 8] as u16) << (CRC14_BITS - 8);
+        }
+        remainder = if remainder & top != 0 {
+            (remainder << 1) ^ CRC14_POLY
+        } else {
+            remainder << 1
+        };
+    }
+    remainder & ((top << 1) - 1)
+}
+
+/// Appends the CRC to a 77-bit payload, producing the 91 LDPC message bits (12 bytes).
+pub fn add_crc(payload: &[u8; 10]) -> [u8; 12] {
+    let mut out = [0u8; 12];
+    out[..10].copy_from_slice(payload);
+    out[9] &= 0xf8;
+    let crc = crc14(payload);
+    out[9] |= (crc >> 11) as u8;
+    out[10] = (crc >> 3) as u8;
+    out[11] = (crc << 5) as u8;
+    out
+}
+
+/// Extracts the payload from 91 message bits if the embedded CRC matches.
+pub fn check_crc(message: &[u8; 12]) -> Option<[u8; 10]> {
+    let mut payload = [0u8; 10];
+    payload.copy_from_slice(&message[..10]);
+    payload[9] &= 0xf8;
+    let received = (((message[9] & 0x07) as u16) << 11)
+        | ((message[10] as u16) << 3)
+        | ((message[11] as u16) >> 5);
+    if payload.iter().all(|b| *b == 0) || crc14(&payload) != received {
+        return None;
+    }
+    Some(payload)
+}
+
+/// Encodes 91 message bits into a 174-bit systematic codeword (one bit per byte).
+pub fn encode(message: &[u8; 12]) -> [u8; LDPC_N] {
+    let mut codeword = [0u8; LDPC_N];
+    for (i, bit) in codeword.iter_mut().take(LDPC_K).enumerate() {
+        *bit = get_bit(message, i);
+    }
+    for (row, gen) in LDPC_GENERATOR.iter().enumerate() {
+        let ones: u32 = gen
+            .iter()
+            .zip(message.iter())
+            .map(|(g, m)| (g & m).count_ones())
+            .sum();
+        codeword[LDPC_K + row] = (ones & 1) as u8;
+    }
+    codeword
+}
+
+/// Codeword bits (0-based) participating in each parity check.
+fn checks() -> &'static [Vec<usize>] {
+    static CHECKS: OnceLock<Vec<Vec<usize>>> = OnceLock::new();
+    CHECKS.get_or_init(|| {
+        LDPC_NM
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .filter(|&&b| b != 0)
+                    .map(|&b| b as usize - 1)
+                    .collect()
+            })
+            .collect()
+    })
+}
+
+fn parity_errors(checks: &[Vec<usize>], plain: &[u8; LDPC_N]) -> usize {
+    checks
+        .iter()
+        .filter(|row| row.iter().fold(0u8, |acc, &n| acc ^ plain[n]) != 0)
+        .count()
+}
+
+/// Sum-product belief propagation decoder.
+///
+/// `llr` holds one log-likelihood ratio per codeword bit, positive values favouring `1`.
+/// Returns the 91 message bits once all parity checks are satisfied.
+pub fn decode(llr: &[f32; LDPC_N], max_iterations: usize) -> Option<[u8; 12]> {
+    let checks = checks();
+    // Check-to-bit messages, indexed like `checks`.
+    let mut to_bit: Vec<Vec<f32>> = checks.iter().map(|row| vec![0.0; row.len()]).collect();
+    let mut plain = [0u8; LDPC_N];
+    let mut tanh_buf = [0f32; 7];
+
+    for _ in 0..=max_iterations {
+        let mut total = *llr;
+        for (m, row) in checks.iter().enumerate() {
+            for (j, &n) in row.iter().enumerate() {
+                total[n] += to_bit[m][j];
+            }
+        }
+        for (p, t) in plain.iter_mut().zip(total.iter()) {
+            *p = u8::from(*t > 0.0);
+        }
+        if parity_errors(checks, &plain) == 0 {
+            let mut out = [0u8; 12];
+            for (i, &bit) in plain.iter().take(LDPC_K).enumerate() {
+                set_bit(&mut out, i, bit);
+            }
+            return Some(out);
+        }
+
+        for (m, row) in checks.iter().enumerate() {
+            for (j, &n) in row.iter().enumerate() {
+                let to_check = total[n] - to_bit[m][j];
+                tanh_buf[j] = (-to_check / 2.0).tanh();
+            }
+            let degree = row.len();
+            for (j, msg) in to_bit[m].iter_mut().take(degree).enumerate() {
+                let prod: f32 = (0..degree)
+                    .filter(|&k| k != j)
+                    .map(|k| tanh_buf[k])
+                    .product();
+                *msg = -2.0 * prod.clamp(-ATANH_CLAMP, ATANH_CLAMP).atanh();
+            }
+        }
+    }
+    None
+}
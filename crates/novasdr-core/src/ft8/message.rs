@@ -0,0 +1,366 @@
+use super::ldpc::{get_bit, set_bit};
+
+const NTOKENS: u32 = 2_063_592;
+const MAX22: u32 = 4_194_304;
+const MAXGRID4: u32 = 32_400;
+
+const CHARSET_A1: &[u8] = b" 0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const CHARSET_A2: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const CHARSET_A3: &[u8] = b"0123456789";
+const CHARSET_A4: &[u8] = b" ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const CHARSET_FREE_TEXT: &[u8] = b" 0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ+-./?";
+const CHARSET_NONSTANDARD: &[u8] = b" 0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ/";
+
+const FREE_TEXT_LEN: usize = 13;
+const NONSTANDARD_CALL_LEN: usize = 11;
+const HASHED_CALL: &str = "<...>";
+
+/// A decoded 77-bit FT8/FT4 message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Human-readable text in WSJT-X notation.
+    pub text: String,
+    /// Callsign of the transmitting station when the message type identifies it.
+    pub sender: Option<String>,
+    /// Maidenhead locator sent by the transmitting station, if any.
+    pub locator: Option<String>,
+}
+
+fn read_bits(payload: &[u8; 10], start: usize, len: usize) -> u128 {
+    (start..start + len).fold(0u128, |acc, i| (acc << 1) | get_bit(payload, i) as u128)
+}
+
+fn write_bits(payload: &mut [u8; 10], start: usize, len: usize, value: u128) {
+    for i in 0..len {
+        let bit = ((value >> (len - 1 - i)) & 1) as u8;
+        set_bit(payload, start + i, bit);
+    }
+}
+
+fn char_index(charset: &[u8], c: u8) -> Option<u32> {
+    charset.iter().position(|&x| x == c).map(|i| i as u32)
+}
+
+fn unpack_call28(n28: u32) -> Option<String> {
+    match n28 {
+        0 => return Some("DE".to_string()),
+        1 => return Some("QRZ".to_string()),
+        2 => return Some("CQ".to_string()),
+        3..=1002 => return Some(format!("CQ {:03}", n28 - 3)),
+        1003..=532_443 => {
+            let mut n = n28 - 1003;
+            let mut buf = [b' '; 4];
+            for slot in buf.iter_mut().rev() {
+                *slot = CHARSET_A4[(n % 27) as usize];
+                n /= 27;
+            }
+            let suffix = String::from_utf8_lossy(&buf).trim().to_string();
+            return Some(format!("CQ {suffix}"));
+        }
+        _ => {}
+    }
+    if n28 < NTOKENS {
+        return None;
+    }
+    let n = n28 - NTOKENS;
+    if n < MAX22 {
+        return Some(HASHED_CALL.to_string());
+    }
+    let mut n = n - MAX22;
+    let i6 = n % 27;
+    n /= 27;
+    let i5 = n % 27;
+    n /= 27;
+    let i4 = n % 27;
+    n /= 27;
+    let i3 = n % 10;
+    n /= 10;
+    let i2 = n % 36;
+    n /= 36;
+    let i1 = n;
+    if i1 as usize >= CHARSET_A1.len() {
+        return None;
+    }
+    let raw = [
+        CHARSET_A1[i1 as usize],
+        CHARSET_A2[i2 as usize],
+        CHARSET_A3[i3 as usize],
+        CHARSET_A4[i4 as usize],
+        CHARSET_A4[i5 as usize],
+        CHARSET_A4[i6 as usize],
+    ];
+    let call = String::from_utf8_lossy(&raw).trim().to_string();
+    if call.is_empty() || call.contains(' ') {
+        return None;
+    }
+    Some(call)
+}
+
+fn pack_call28(call: &str) -> Option<u32> {
+    let call = call.trim().to_ascii_uppercase();
+    match call.as_str() {
+        "DE" => return Some(0),
+        "QRZ" => return Some(1),
+        "CQ" => return Some(2),
+        _ => {}
+    }
+    if let Some(rest) = call.strip_prefix("CQ ") {
+        if rest.len() == 3 && rest.bytes().all(|b| b.is_ascii_digit()) {
+            return rest.parse::<u32>().ok().map(|v| 3 + v);
+        }
+        if (1..=4).contains(&rest.len()) && rest.bytes().all(|b| b.is_ascii_uppercase()) {
+            let padded = format!("{rest:>4}");
+            let mut n = 0u32;
+            for b in padded.bytes() {
+                n = n * 27 + char_index(CHARSET_A4, b)?;
+            }
+            return Some(1003 + n);
+        }
+        return None;
+    }
+
+    // Standard callsigns have their call-area digit in the third position.
+    let bytes = call.as_bytes();
+    let aligned = if bytes.len() >= 3 && bytes[2].is_ascii_digit() {
+        call.clone()
+    } else if bytes.len() >= 2 && bytes[1].is_ascii_digit() {
+        format!(" {call}")
+    } else {
+        return None;
+    };
+    if aligned.len() > 6 {
+        return None;
+    }
+    let padded = format!("{aligned:<6}");
+    let p = padded.as_bytes();
+    let i1 = char_index(CHARSET_A1, p[0])?;
+    let i2 = char_index(CHARSET_A2, p[1])?;
+    let i3 = char_index(CHARSET_A3, p[2])?;
+    let i4 = char_index(CHARSET_A4, p[3])?;
+    let i5 = char_index(CHARSET_A4, p[4])?;
+    let i6 = char_index(CHARSET_A4, p[5])?;
+    let n = ((((i1 * 36 + i2) * 10 + i3) * 27 + i4) * 27 + i5) * 27 + i6;
+    Some(NTOKENS + MAX22 + n)
+}
+
+fn is_grid4(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() == 4
+        && (b'A'..=b'R').contains(&b[0])
+        && (b'A'..=b'R').contains(&b[1])
+        && b[2].is_ascii_digit()
+        && b[3].is_ascii_digit()
+}
+
+fn unpack_extra(ir: bool, igrid4: u32) -> (String, Option<String>) {
+    if igrid4 < MAXGRID4 {
+        let mut n = igrid4;
+        let j1 = n / 1800;
+        n -= j1 * 1800;
+        let j2 = n / 100;
+        n -= j2 * 100;
+        let grid = format!(
+            "{}{}{}{}",
+            (b'A' + j1 as u8) as char,
+            (b'A' + j2 as u8) as char,
+            n / 10,
+            n % 10
+        );
+        if ir {
+            return (format!("R {grid}"), None);
+        }
+        return (grid.clone(), Some(grid));
+    }
+    let text = match igrid4 - MAXGRID4 {
+        1 => String::new(),
+        2 => "RRR".to_string(),
+        3 => "RR73".to_string(),
+        4 => "73".to_string(),
+        irpt => {
+            let report = format!("{:+03}", irpt as i32 - 35);
+            if ir {
+                format!("R{report}")
+            } else {
+                report
+            }
+        }
+    };
+    (text, None)
+}
+
+fn pack_extra(extra: &str) -> Option<(bool, u32)> {
+    let extra = extra.trim().to_ascii_uppercase();
+    match extra.as_str() {
+        "" => return Some((false, MAXGRID4 + 1)),
+        "RRR" => return Some((false, MAXGRID4 + 2)),
+        "RR73" => return Some((false, MAXGRID4 + 3)),
+        "73" => return Some((false, MAXGRID4 + 4)),
+        _ => {}
+    }
+    if let Some(grid) = extra.strip_prefix("R ") {
+        if is_grid4(grid) {
+            return pack_extra(grid).map(|(_, v)| (true, v));
+        }
+    }
+    if is_grid4(&extra) {
+        let b = extra.as_bytes();
+        let v = ((b[0] - b'A') as u32) * 1800
+            + ((b[1] - b'A') as u32) * 100
+            + ((b[2] - b'0') as u32) * 10
+            + (b[3] - b'0') as u32;
+        return Some((false, v));
+    }
+    let (ir, report) = match extra.strip_prefix('R') {
+        Some(rest) => (true, rest),
+        None => (false, extra.as_str()),
+    };
+    let report: i32 = report.parse().ok()?;
+    if !(-30..=32).contains(&report) {
+        return None;
+    }
+    Some((ir, MAXGRID4 + (report + 35) as u32))
+}
+
+/// Packs a standard message such as `CQ K1ABC FN42` or `K1ABC W9XYZ -12`.
+pub fn pack_standard(to: &str, de: &str, extra: &str) -> Option<[u8; 10]> {
+    let n28a = pack_call28(to)?;
+    let n28b = pack_call28(de)?;
+    let (ir, igrid4) = pack_extra(extra)?;
+    let mut payload = [0u8; 10];
+    write_bits(&mut payload, 0, 28, n28a as u128);
+    write_bits(&mut payload, 28, 1, 0);
+    write_bits(&mut payload, 29, 28, n28b as u128);
+    write_bits(&mut payload, 57, 1, 0);
+    write_bits(&mut payload, 58, 1, u128::from(ir));
+    write_bits(&mut payload, 59, 15, igrid4 as u128);
+    write_bits(&mut payload, 74, 3, 1);
+    Some(payload)
+}
+
+/// Packs up to 13 characters of free text.
+pub fn pack_free_text(text: &str) -> Option<[u8; 10]> {
+    let text = text.to_ascii_uppercase();
+    if text.len() > FREE_TEXT_LEN {
+        return None;
+    }
+    let padded = format!("{text:>width$}", width = FREE_TEXT_LEN);
+    let mut value = 0u128;
+    for b in padded.bytes() {
+        value = value * CHARSET_FREE_TEXT.len() as u128 + char_index(CHARSET_FREE_TEXT, b)? as u128;
+    }
+    let mut payload = [0u8; 10];
+    write_bits(&mut payload, 0, 71, value);
+    Some(payload)
+}
+
+fn unpack_standard(payload: &[u8; 10], i3: u8) -> Option<Message> {
+    let n28a = read_bits(payload, 0, 28) as u32;
+    let ipa = read_bits(payload, 28, 1) == 1;
+    let n28b = read_bits(payload, 29, 28) as u32;
+    let ipb = read_bits(payload, 57, 1) == 1;
+    let ir = read_bits(payload, 58, 1) == 1;
+    let igrid4 = read_bits(payload, 59, 15) as u32;
+
+    let suffix = if i3 == 2 { "/P" } else { "/R" };
+    let mut to = unpack_call28(n28a)?;
+    let mut de = unpack_call28(n28b)?;
+    if ipa {
+        to.push_str(suffix);
+    }
+    if ipb {
+        de.push_str(suffix);
+    }
+    let (extra, locator) = unpack_extra(ir, igrid4);
+    let text = [to.as_str(), de.as_str(), extra.as_str()]
+        .iter()
+        .filter(|s| !s.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let is_token = n28b < NTOKENS || de == HASHED_CALL;
+    let sender = (!is_token).then_some(de);
+    Some(Message {
+        text,
+        sender,
+        locator,
+    })
+}
+
+fn unpack_free_text(payload: &[u8; 10]) -> Message {
+    let mut value = read_bits(payload, 0, 71);
+    let mut buf = [b' '; FREE_TEXT_LEN];
+    let base = CHARSET_FREE_TEXT.len() as u128;
+    for slot in buf.iter_mut().rev() {
+        *slot = CHARSET_FREE_TEXT[(value % base) as usize];
+        value /= base;
+    }
+    Message {
+        text: String::from_utf8_lossy(&buf).trim().to_string(),
+        sender: None,
+        locator: None,
+    }
+}
+
+fn unpack_telemetry(payload: &[u8; 10]) -> Message {
+    let value = read_bits(payload, 0, 71);
+    let hex = format!("{value:018X}");
+    let trimmed = hex.trim_start_matches('0');
+    Message {
+        text: if trimmed.is_empty() { "0" } else { trimmed }.to_string(),
+        sender: None,
+        locator: None,
+    }
+}
+
+fn unpack_nonstandard(payload: &[u8; 10]) -> Message {
+    let mut n58 = read_bits(payload, 12, 58);
+    let h1 = read_bits(payload, 70, 1) == 1;
+    let r2 = read_bits(payload, 71, 2);
+    let cq = read_bits(payload, 73, 1) == 1;
+
+    let mut buf = [b' '; NONSTANDARD_CALL_LEN];
+    let base = CHARSET_NONSTANDARD.len() as u128;
+    for slot in buf.iter_mut().rev() {
+        *slot = CHARSET_NONSTANDARD[(n58 % base) as usize];
+        n58 /= base;
+    }
+    let call = String::from_utf8_lossy(&buf).trim().to_string();
+
+    if cq {
+        return Message {
+            text: format!("CQ {call}"),
+            sender: Some(call),
+            locator: None,
+        };
+    }
+    let (to, de, sender) = if h1 {
+        (call, HASHED_CALL.to_string(), None)
+    } else {
+        (HASHED_CALL.to_string(), call.clone(), Some(call))
+    };
+    let extra = match r2 {
+        1 => "RRR",
+        2 => "RR73",
+        3 => "73",
+        _ => "",
+    };
+    let text = format!("{to} {de} {extra}").trim().to_string();
+    Message {
+        text,
+        sender,
+        locator: None,
+    }
+}
+
+/// Decodes a 77-bit payload. Returns `None` for message types that are not supported.
+pub fn unpack(payload: &[u8; 10]) -> Option<Message> {
+    let i3 = read_bits(payload, 74, 3) as u8;
+    let n3 = read_bits(payload, 71, 3) as u8;
+    match (i3, n3) {
+        (0, 0) => Some(unpack_free_text(payload)),
+        (0, 5) => Some(unpack_telemetry(payload)),
+        (1, _) | (2, _) => unpack_standard(payload, i3),
+        (4, _) => Some(unpack_nonstandard(payload)),
+        _ => None,
+    }
+}
@@ -0,0 +1,111 @@
+pub mod constants;
+mod decode;
+mod encode;
+pub mod ldpc;
+pub mod message;
+
+pub use decode::{decode, DecodeOptions, Decoded};
+pub use encode::{encode_tones, synthesize};
+pub use message::{pack_free_text, pack_standard, unpack, Message};
+
+/// Sample rate the decoder and encoder operate at.
+pub const SAMPLE_RATE: u32 = 12_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Ft8,
+    Ft4,
+}
+
+impl Protocol {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ft8 => "FT8",
+            Self::Ft4 => "FT4",
+        }
+    }
+
+    /// Length of a transmit/receive slot in milliseconds.
+    pub fn slot_ms(self) -> u64 {
+        match self {
+            Self::Ft8 => 15_000,
+            Self::Ft4 => 7_500,
+        }
+    }
+
+    /// Samples per symbol at [`SAMPLE_RATE`].
+    pub fn symbol_samples(self) -> usize {
+        match self {
+            Self::Ft8 => 1920,
+            Self::Ft4 => 576,
+        }
+    }
+
+    pub fn tone_spacing_hz(self) -> f32 {
+        SAMPLE_RATE as f32 / self.symbol_samples() as f32
+    }
+
+    pub fn num_tones(self) -> usize {
+        match self {
+            Self::Ft8 => 8,
+            Self::Ft4 => 4,
+        }
+    }
+
+    fn bits_per_symbol(self) -> usize {
+        match self {
+            Self::Ft8 => 3,
+            Self::Ft4 => 2,
+        }
+    }
+
+    /// Number of channel symbols in a frame (FT4 excludes the ramp symbols).
+    pub fn num_symbols(self) -> usize {
+        match self {
+            Self::Ft8 => 79,
+            Self::Ft4 => 103,
+        }
+    }
+
+    fn sync_len(self) -> usize {
+        match self {
+            Self::Ft8 => 7,
+            Self::Ft4 => 4,
+        }
+    }
+
+    /// Start symbol of each Costas block.
+    fn sync_offsets(self) -> &'static [usize] {
+        match self {
+            Self::Ft8 => &[0, 36, 72],
+            Self::Ft4 => &[0, 33, 66, 99],
+        }
+    }
+
+    fn sync_tone(self, block: usize, k: usize) -> u8 {
+        match self {
+            Self::Ft8 => constants::FT8_COSTAS[k],
+            Self::Ft4 => constants::FT4_COSTAS[block][k],
+        }
+    }
+
+    fn gray(self) -> &'static [u8] {
+        match self {
+            Self::Ft8 => &constants::FT8_GRAY,
+            Self::Ft4 => &constants::FT4_GRAY,
+        }
+    }
+
+    /// Channel symbol index of each data symbol, in transmission order.
+    fn data_symbols(self) -> impl Iterator<Item = usize> {
+        let sync_len = self.sync_len();
+        let offsets = self.sync_offsets();
+        (0..self.num_symbols())
+            .filter(move |&s| !offsets.iter().any(|&o| s >= o && s < o + sync_len))
+    }
+
+    /// Nominal start of the first symbol after the slot boundary.
+    fn nominal_start_s(self) -> f32 {
+        0.5
+    }
+}
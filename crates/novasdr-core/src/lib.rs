@@ -1,5 +1,17 @@
+pub mod acars;
+pub mod clock;
 pub mod codec;
 pub mod config;
+pub mod cw;
 pub mod dsp;
+#[cfg(feature = "dv")]
+pub mod dv;
+pub mod freqdb;
+pub mod ft8;
 pub mod protocol;
+pub mod rds;
+pub mod rtty;
+pub mod smeter;
+pub mod sstv;
 pub mod util;
+pub mod wspr;
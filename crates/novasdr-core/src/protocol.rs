@@ -18,7 +18,10 @@ pub struct BasicInfoDefaults {
 
 #[derive(Debug, Clone, Serialize)]
 pub struct BasicInfo {
+    /// Rate the spectrum is computed at, after `receiver.input.decimation`.
     pub sps: i64,
+    /// Rate of the input before decimation; equal to `sps` without it.
+    pub input_sps: i64,
     pub audio_max_sps: i64,
     pub audio_max_fft: usize,
     pub fft_size: usize,
@@ -32,6 +35,9 @@ pub struct BasicInfo {
     pub grid_locator: String,
     pub smeter_offset: i32,
     pub markers: String,
+    /// Base64 Ed25519 public key of the server identity, when one is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_public_key: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -47,6 +53,22 @@ pub enum ClientCommand {
         m: Option<f64>,
         #[serde(default)]
         level: Option<i32>,
+        /// Waterfall row aggregation: `none`, `average` or `peak` (`/waterfall` only). Omitted
+        /// keeps the current setting.
+        #[serde(default)]
+        aggregate: Option<String>,
+        /// Frames per aggregated row, `2..=16` (default 4).
+        #[serde(default)]
+        frames: Option<u32>,
+        /// Waterfall window this applies to (`/waterfall` only); omitted is window 0. Setting a
+        /// window that is not open yet opens it.
+        #[serde(default)]
+        window: Option<u8>,
+    },
+    /// Stops the frames of an extra waterfall window opened with `window` (`/waterfall` only).
+    #[serde(rename = "close_window")]
+    CloseWindow {
+        window: u8,
     },
     Demodulation {
         demodulation: String,
@@ -59,6 +81,64 @@ pub enum ClientCommand {
     },
     Squelch {
         enabled: bool,
+        /// `variance` (default) or `level`. Omitted keeps the current mode.
+        #[serde(default)]
+        mode: Option<String>,
+        /// `level` mode: dB over the noise floor that opens the squelch.
+        #[serde(default)]
+        open_db: Option<f32>,
+        /// `level` mode: how far below `open_db` the level must drop to close it.
+        #[serde(default)]
+        hysteresis_db: Option<f32>,
+    },
+    /// Opt in to RDS station information while demodulating WBFM.
+    Rds {
+        enabled: bool,
+    },
+    /// Ask for stereo audio while demodulating WBFM; frames stay mono until a pilot is found.
+    Stereo {
+        enabled: bool,
+    },
+    /// Digital voice while demodulating FM: DMR, D-STAR and System Fusion calls are reported and,
+    /// with the vocoder built in, decoded to speech. Servers built without digital voice ignore it.
+    #[serde(rename = "digital_voice")]
+    DigitalVoice {
+        enabled: bool,
+    },
+    /// Report the CTCSS tone or DCS code heard while demodulating FM.
+    Subtone {
+        enabled: bool,
+    },
+    /// Decode SSTV pictures from the demodulated audio; finished ones are published on `/sstv`.
+    Sstv {
+        enabled: bool,
+    },
+    /// Decode RTTY or NAVTEX from the demodulated audio: `mode` is `rtty45`, `rtty50`, `navtex`
+    /// or `null` to turn it off; the tones lie `shift` Hz apart around `center` Hz of audio.
+    Fsk {
+        #[serde(default)]
+        mode: Option<String>,
+        #[serde(default)]
+        center: Option<f64>,
+        #[serde(default)]
+        shift: Option<f64>,
+        #[serde(default)]
+        reverse: bool,
+    },
+    /// Only let FM audio through while this tone or code is heard; `null` turns it off.
+    #[serde(rename = "tone_squelch")]
+    ToneSquelch {
+        #[serde(default)]
+        tone: Option<crate::dsp::subtone::Subtone>,
+    },
+    /// Send a clean sine of `freq` Hz at `level` dBFS in place of the demodulated audio, to tell
+    /// network or client decoding trouble from the DSP chain; `freq: null` turns it off.
+    #[serde(rename = "test_tone")]
+    TestTone {
+        #[serde(default)]
+        freq: Option<f64>,
+        #[serde(default)]
+        level: Option<f64>,
     },
     Chat {
         message: String,
@@ -70,6 +150,10 @@ pub enum ClientCommand {
         #[serde(default)]
         reply_to_username: Option<String>,
     },
+    /// Passband skirt steepness: `off`, `soft`, `normal` or `sharp`.
+    Filter {
+        sharpness: String,
+    },
     Agc {
         speed: String,
         #[serde(default)]
@@ -77,19 +161,285 @@ pub enum ClientCommand {
         #[serde(default)]
         release: Option<f32>,
     },
+    /// Opts the listener into the `/events` presence list under `nickname`; no or an empty
+    /// nickname opts out again (`/audio` only).
+    Presence {
+        #[serde(default)]
+        nickname: Option<String>,
+    },
+    /// Listener-proposed frequency label, queued for operator approval (`/chat` only).
+    #[serde(rename = "suggest_marker")]
+    SuggestMarker {
+        frequency: i64,
+        name: String,
+        #[serde(default)]
+        mode: Option<String>,
+        #[serde(default)]
+        user_id: Option<String>,
+        #[serde(default)]
+        username: Option<String>,
+    },
+    /// Audio buffering preference; `dx` selects low-latency packets, anything else the default.
     Buffer {
         size: String,
     },
+    /// Listener volume in dB (`-60..=12`), with loudness compensation below 0 dB.
+    Volume {
+        db: f32,
+    },
+    /// Frequency lowering for high-frequency hearing loss (`/audio` only): `off`, `compress`,
+    /// `compress_strong` or `transpose`.
+    Hearing {
+        profile: String,
+    },
+    /// Waterfall frames per second for this client (`/waterfall` only); `0` restores the full rate.
+    #[serde(rename = "waterfall_rate")]
+    WaterfallRate {
+        fps: f64,
+    },
+    /// Waterfall rows for frames strictly between `after` and `before` (the last frame received
+    /// before a gap and the first after it), averaged to at most `lines` (`/waterfall` only).
+    Catchup {
+        after: u64,
+        before: u64,
+        #[serde(default)]
+        lines: Option<u32>,
+        #[serde(default)]
+        window: Option<u8>,
+    },
+    /// "What is this?": known users and allocations at `frequency` (Hz), from `freqdb`.
+    Lookup {
+        frequency: i64,
+    },
+    /// Scanner control (`/audio` only): `start` with `channels` or a `markers` search, `skip`
+    /// the current channel, or `stop`.
+    Scan {
+        action: String,
+        #[serde(default)]
+        channels: Vec<ScanChannel>,
+        /// Scan the markers in the receiver's band whose text contains this, as `/api/markers?q=`.
+        #[serde(default)]
+        markers: Option<String>,
+        /// Time on each channel while the squelch stays closed.
+        #[serde(default)]
+        dwell_ms: Option<u32>,
+        /// Time a channel is held after its squelch closes again.
+        #[serde(default)]
+        hang_ms: Option<u32>,
+    },
+    /// Time-shift control (`/audio` only): `pause`, `resume`, `seek` to `seconds` behind live,
+    /// or back to `live`.
+    Timeshift {
+        action: String,
+        #[serde(default)]
+        seconds: Option<f64>,
+    },
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Commands of an `/iq` client.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+pub enum IqCommand {
+    /// Centre of the passband in Hz, and its width as a sample rate (the current one when
+    /// absent).
+    Tune {
+        frequency: i64,
+        #[serde(default)]
+        sample_rate: Option<u32>,
+    },
+}
+
+/// Commands of a `/diversity` client.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+pub enum DiversityCommand {
+    /// Tunes both receivers to `frequency` (Hz), in `mode` or the current one.
+    Tune {
+        frequency: i64,
+        #[serde(default)]
+        mode: Option<String>,
+    },
+    /// Share of receiver `b` in the mix: 0 plays only `a`, 1 only `b`.
+    Balance { value: f32 },
+    /// `mix` or `stereo` (`a` left, `b` right).
+    Output { value: String },
+}
+
+/// Commands of the operator's `/tx` session.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+pub enum TxCommand {
+    /// Carrier frequency in Hz and `USB`, `LSB` or `FM`.
+    Tune { frequency: i64, mode: String },
+    /// Output as a share of the band's gain limit, 0–100 %.
+    Power { percent: f64 },
+    /// Keys or unkeys the transmitter.
+    Ptt { on: bool },
+}
+
+/// One entry of a `scan` list; `mode` defaults to the client's current demodulation.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScanChannel {
+    pub frequency: i64,
+    #[serde(default)]
+    pub mode: Option<String>,
+}
+
+/// Parts of the `/events` stream a client subscribes to with `/events?topics=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventTopics(u8);
+
+impl EventTopics {
+    /// Client counts, DX-mode listeners and `countries`.
+    pub const USERS: Self = Self(1);
+    /// `waterfall_kbits` and `audio_kbits`.
+    pub const BANDWIDTH: Self = Self(1 << 1);
+    /// Other listeners' tuning (`signal_changes`).
+    pub const DSP: Self = Self(1 << 2);
+    /// `chat_clients` and the `presence` list.
+    pub const CHAT: Self = Self(1 << 3);
+    /// `maintenance` banners and `receiver_status` messages.
+    pub const MAINTENANCE: Self = Self(1 << 4);
+    /// `overlays_updated` messages.
+    pub const OVERLAYS: Self = Self(1 << 5);
+    /// `digital_voice` call reports.
+    pub const DIGITAL_VOICE: Self = Self(1 << 6);
+    /// Skimmer `spot` messages.
+    pub const SPOTS: Self = Self(1 << 7);
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u8::MAX);
+    /// Topics carried by the periodic payload rather than by typed messages.
+    pub const PERIODIC: Self =
+        Self(Self::USERS.0 | Self::BANDWIDTH.0 | Self::DSP.0 | Self::CHAT.0 | Self::MAINTENANCE.0);
+
+    const NAMES: [(&'static str, Self); 8] = [
+        ("users", Self::USERS),
+        ("bandwidth", Self::BANDWIDTH),
+        ("dsp", Self::DSP),
+        ("chat", Self::CHAT),
+        ("maintenance", Self::MAINTENANCE),
+        ("overlays", Self::OVERLAYS),
+        ("digital_voice", Self::DIGITAL_VOICE),
+        ("spots", Self::SPOTS),
+    ];
+
+    /// Parses a comma-separated topic list; `None` or an empty list selects every topic.
+    pub fn parse(list: Option<&str>) -> Result<Self, String> {
+        let Some(list) = list.map(str::trim).filter(|l| !l.is_empty()) else {
+            return Ok(Self::ALL);
+        };
+        let mut topics = Self::NONE;
+        for name in list.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            match Self::NAMES
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            {
+                Some((_, topic)) => topics = topics.union(*topic),
+                None => return Err(format!("unknown events topic `{name}`")),
+            }
+        }
+        Ok(topics)
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// The periodic `/events` payload. Fields of topics a client did not subscribe to are left out.
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct EventsInfo {
-    pub waterfall_clients: usize,
-    pub signal_clients: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waterfall_clients: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signal_clients: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signal_changes: Option<std::collections::HashMap<String, (i32, f64, i32)>>,
-    pub waterfall_kbits: f64,
-    pub audio_kbits: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waterfall_kbits: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_kbits: Option<f64>,
+    /// Audio clients in DX (low-latency) mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low_latency_clients: Option<usize>,
+    /// Connected `/chat` clients.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_clients: Option<usize>,
+    /// Listeners who opted into the presence list, with where they are tuned. Left out when
+    /// `server.otherusers` hides other listeners.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence: Option<Vec<PresenceEntry>>,
+    /// Connected client IPs per country code (`unknown` when not in the database). Only sent
+    /// when the server has a GeoIP database.
+    #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub countries: std::collections::BTreeMap<String, usize>,
+    /// Banner messages keyed by receiver id for receivers in maintenance mode.
+    #[serde(skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub maintenance: std::collections::HashMap<String, String>,
+}
+
+/// One listener of the presence list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PresenceEntry {
+    pub nickname: String,
+    pub receiver_id: String,
+    /// Tuned frequency in Hz.
+    pub frequency: i64,
+    pub mode: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigitalVoiceMode {
+    Dmr,
+    Dstar,
+    Ysf,
+}
+
+/// What is known about a digital voice call, sent as the body of a `digital_voice` message.
+/// Fields a mode does not carry stay `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DigitalVoiceCall {
+    pub mode: DigitalVoiceMode,
+    /// False in the last update of a call.
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_code: Option<u8>,
+    /// DMR radio ID of the caller.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<u32>,
+    /// DMR talkgroup or radio ID called.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<bool>,
+    /// D-STAR callsign of the caller, and its suffix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callsign: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    /// D-STAR route: who is called, and through which repeaters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub your: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpt1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpt2: Option<String>,
+    /// D-STAR 20-character text message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -108,6 +458,9 @@ pub struct WaterfallPacket<'a> {
     pub frame_num: u64,
     pub l: i32,
     pub r: i32,
+    /// Window the row belongs to; left out for window 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<u8>,
     #[serde(with = "serde_bytes")]
     pub data: &'a [u8],
 }
@@ -0,0 +1,182 @@
+/// Generator polynomial x^10 + x^8 + x^7 + x^5 + x^4 + x^3 + 1.
+const POLY: u32 = 0x5B9;
+const CHECK_BITS: u32 = 10;
+pub const BLOCK_BITS: usize = 26;
+pub const GROUP_BITS: usize = 4 * BLOCK_BITS;
+/// Consecutive failed blocks after which block sync is considered lost.
+const MAX_BAD_BLOCKS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Offset {
+    A,
+    B,
+    C,
+    CPrime,
+    D,
+}
+
+const OFFSETS: [Offset; 5] = [Offset::A, Offset::B, Offset::C, Offset::CPrime, Offset::D];
+
+impl Offset {
+    fn word(self) -> u16 {
+        match self {
+            Self::A => 0x0FC,
+            Self::B => 0x198,
+            Self::C => 0x168,
+            Self::CPrime => 0x350,
+            Self::D => 0x1B4,
+        }
+    }
+
+    /// Position of the block within its group.
+    fn slot(self) -> usize {
+        match self {
+            Self::A => 0,
+            Self::B => 1,
+            Self::C | Self::CPrime => 2,
+            Self::D => 3,
+        }
+    }
+}
+
+fn checkword(info: u16) -> u16 {
+    let mut reg = (info as u32) << CHECK_BITS;
+    for bit in (CHECK_BITS..CHECK_BITS + 16).rev() {
+        if reg & (1 << bit) != 0 {
+            reg ^= POLY << (bit - CHECK_BITS);
+        }
+    }
+    (reg & 0x3FF) as u16
+}
+
+/// Offset word of a received 26-bit block, if its checkword is valid.
+fn block_offset(block: u32) -> Option<Offset> {
+    let info = (block >> CHECK_BITS) as u16;
+    let syndrome = checkword(info) ^ (block & 0x3FF) as u16;
+    OFFSETS.iter().copied().find(|o| o.word() == syndrome)
+}
+
+/// The four 16-bit information words of a group; blocks that failed their check are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Group {
+    pub blocks: [Option<u16>; 4],
+}
+
+/// Encodes a group as 104 transmitted bits (one per byte, MSB first), as a transmitter would
+/// before differential coding.
+pub fn encode_group(blocks: [u16; 4]) -> Vec<u8> {
+    let version_b = blocks[1] & 0x0800 != 0;
+    let offsets = [
+        Offset::A,
+        Offset::B,
+        if version_b { Offset::CPrime } else { Offset::C },
+        Offset::D,
+    ];
+    let mut bits = Vec::with_capacity(GROUP_BITS);
+    for (&info, offset) in blocks.iter().zip(offsets) {
+        let block = ((info as u32) << CHECK_BITS) | (checkword(info) ^ offset.word()) as u32;
+        bits.extend((0..BLOCK_BITS).rev().map(|i| ((block >> i) & 1) as u8));
+    }
+    bits
+}
+
+/// Finds block boundaries in the RDS bit stream and collects groups.
+pub struct BlockSync {
+    reg: u32,
+    bit_count: usize,
+    /// Offset that matched at each of the last 26 bit positions while searching for sync.
+    recent: [Option<Offset>; BLOCK_BITS],
+    synced: bool,
+    bits_in_block: usize,
+    next_slot: usize,
+    bad_blocks: u32,
+    group: Group,
+}
+
+impl Default for BlockSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockSync {
+    pub fn new() -> Self {
+        Self {
+            reg: 0,
+            bit_count: 0,
+            recent: [None; BLOCK_BITS],
+            synced: false,
+            bits_in_block: 0,
+            next_slot: 0,
+            bad_blocks: 0,
+            group: Group::default(),
+        }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Feeds one bit; returns a group once its last block slot has been received.
+    pub fn push_bit(&mut self, bit: u8) -> Option<Group> {
+        self.reg = ((self.reg << 1) | (bit & 1) as u32) & ((1 << BLOCK_BITS) - 1);
+        self.bit_count = self.bit_count.wrapping_add(1);
+        if !self.synced {
+            self.acquire();
+            return None;
+        }
+
+        self.bits_in_block += 1;
+        if self.bits_in_block < BLOCK_BITS {
+            return None;
+        }
+        self.bits_in_block = 0;
+        let slot = self.next_slot;
+        self.next_slot = (slot + 1) % 4;
+        match block_offset(self.reg).filter(|o| o.slot() == slot) {
+            Some(_) => {
+                self.bad_blocks = 0;
+                self.group.blocks[slot] = Some((self.reg >> CHECK_BITS) as u16);
+            }
+            None => {
+                self.bad_blocks += 1;
+                if self.bad_blocks >= MAX_BAD_BLOCKS {
+                    self.reset();
+                    return None;
+                }
+            }
+        }
+        if slot == 3 {
+            let group = std::mem::take(&mut self.group);
+            return group.blocks.iter().any(Option::is_some).then_some(group);
+        }
+        None
+    }
+
+    /// Declares sync once two consecutive blocks validate exactly one block length apart.
+    fn acquire(&mut self) {
+        let idx = self.bit_count % BLOCK_BITS;
+        let current = block_offset(self.reg);
+        let previous = std::mem::replace(&mut self.recent[idx], current);
+        let (Some(current), Some(previous)) = (current, previous) else {
+            return;
+        };
+        if (previous.slot() + 1) % 4 != current.slot() {
+            return;
+        }
+        let slot = current.slot();
+        self.synced = true;
+        self.bits_in_block = 0;
+        self.next_slot = (slot + 1) % 4;
+        self.bad_blocks = 0;
+        self.group = Group::default();
+        // A group that is already complete carries too little to be worth emitting.
+        if slot != 3 {
+            self.group.blocks[slot] = Some((self.reg >> CHECK_BITS) as u16);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use super::{BIT_RATE, SUBCARRIER_HZ};
+use crate::dsp::fir::{lowpass_taps, sinc, FirDecimator};
+use num_complex::Complex32;
+
+/// Baseband samples per RDS bit; each biphase half-symbol spans half of them.
+const SAMPLES_PER_BIT: usize = 16;
+const BASEBAND_RATE: f64 = BIT_RATE * SAMPLES_PER_BIT as f64;
+/// Passband edge of the resampler; the biphase spectrum ends at 2375 Hz.
+const RESAMPLER_PASS_HZ: f64 = 2_600.0;
+/// Stopband edge of the resampler: anything above folds into the RDS band at [`BASEBAND_RATE`].
+const RESAMPLER_STOP_HZ: f64 = BASEBAND_RATE - RESAMPLER_PASS_HZ;
+/// Blackman-window length factor: taps ≈ 5.5 / transition width (as a fraction of the rate).
+const BLACKMAN_LENGTH_FACTOR: f64 = 5.5;
+/// Fractional delays tabulated by the resampler.
+const RESAMPLER_PHASES: usize = 32;
+/// Channel filter applied at [`BASEBAND_RATE`].
+const CHANNEL_CUTOFF_HZ: f32 = 2_400.0;
+const CHANNEL_TRANSITION_HZ: f32 = 1_600.0;
+/// Costas loop noise bandwidth; RDS subcarriers are locked to the pilot, so this only tracks the
+/// tuning offset.
+const COSTAS_BANDWIDTH_HZ: f32 = 20.0;
+const COSTAS_DAMPING: f32 = std::f32::consts::FRAC_1_SQRT_2;
+/// Time constant, in bits, of the per-phase energy average used for symbol timing.
+const TIMING_AVERAGE_BITS: f32 = 128.0;
+const POWER_FLOOR: f32 = 1e-12;
+
+/// Polyphase windowed-sinc resampler from the MPX rate to [`BASEBAND_RATE`].
+struct Resampler {
+    /// Input samples per output sample.
+    step: f64,
+    /// Position of the next output's first tap in `buf`.
+    next: f64,
+    /// `RESAMPLER_PHASES + 1` rows of taps, one per fractional delay.
+    table: Vec<Vec<f32>>,
+    buf: Vec<Complex32>,
+}
+
+impl Resampler {
+    fn new(in_rate: f64) -> Self {
+        let step = in_rate / BASEBAND_RATE;
+        let transition = (RESAMPLER_STOP_HZ - RESAMPLER_PASS_HZ) / in_rate;
+        let taps = ((BLACKMAN_LENGTH_FACTOR / transition).ceil() as usize).max(2);
+        let cutoff = (0.5 * (RESAMPLER_PASS_HZ + RESAMPLER_STOP_HZ) / in_rate) as f32;
+        let span = taps as f32;
+        let table = (0..=RESAMPLER_PHASES)
+            .map(|p| {
+                let frac = p as f32 / RESAMPLER_PHASES as f32;
+                let mut row: Vec<f32> = (0..taps)
+                    .map(|k| {
+                        // Distance from the output instant, which sits `frac` past the middle tap.
+                        let t = k as f32 - (taps - 1) as f32 / 2.0 - frac;
+                        let x = (t / span + 0.5).clamp(0.0, 1.0);
+                        let w = 0.42 - 0.5 * (std::f32::consts::TAU * x).cos()
+                            + 0.08 * (2.0 * std::f32::consts::TAU * x).cos();
+                        sinc(2.0 * cutoff * t) * w
+                    })
+                    .collect();
+                let sum: f32 = row.iter().sum();
+                if sum.abs() > f32::EPSILON {
+                    for v in row.iter_mut() {
+                        *v /= sum;
+                    }
+                }
+                row
+            })
+            .collect();
+        Self {
+            step,
+            next: 0.0,
+            table,
+            buf: Vec::new(),
+        }
+    }
+
+    fn process(&mut self, input: &[Complex32], out: &mut Vec<Complex32>) {
+        self.buf.extend_from_slice(input);
+        let taps = self.table[0].len();
+        while self.next as usize + taps <= self.buf.len() {
+            let start = self.next as usize;
+            let frac = self.next - start as f64;
+            let phase = (frac * RESAMPLER_PHASES as f64).round() as usize;
+            let acc = self.buf[start..start + taps]
+                .iter()
+                .zip(self.table[phase].iter())
+                .fold(Complex32::new(0.0, 0.0), |acc, (&x, &h)| acc + x * h);
+            out.push(acc);
+            self.next += self.step;
+        }
+        let consumed = (self.next as usize).min(self.buf.len());
+        self.buf.drain(..consumed);
+        self.next -= consumed as f64;
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+        self.next = 0.0;
+    }
+}
+
+/// Recovers the RDS bit stream from FM multiplex samples.
+pub struct Demodulator {
+    nco_phase: f64,
+    nco_step: f64,
+    mixed: Vec<Complex32>,
+    resampler: Resampler,
+    resampled: Vec<Complex32>,
+    channel: FirDecimator<Complex32>,
+    filtered: Vec<Complex32>,
+    costas_phase: f32,
+    costas_freq: f32,
+    costas_alpha: f32,
+    costas_beta: f32,
+    /// Last bit period of in-phase samples, oldest first from `chip_pos`.
+    chips: [f32; SAMPLES_PER_BIT],
+    chip_pos: usize,
+    energy: [f32; SAMPLES_PER_BIT],
+    timing_phase: usize,
+    prev_symbol: bool,
+}
+
+impl Demodulator {
+    pub fn new(mpx_rate: f64) -> Self {
+        let omega = 2.0 * std::f32::consts::PI * COSTAS_BANDWIDTH_HZ / BASEBAND_RATE as f32;
+        let denom = 1.0 + 2.0 * COSTAS_DAMPING * omega + omega * omega;
+        Self {
+            nco_phase: 0.0,
+            nco_step: std::f64::consts::TAU * SUBCARRIER_HZ / mpx_rate,
+            mixed: Vec::new(),
+            resampler: Resampler::new(mpx_rate),
+            resampled: Vec::new(),
+            channel: FirDecimator::new(
+                lowpass_taps(
+                    CHANNEL_CUTOFF_HZ / BASEBAND_RATE as f32,
+                    CHANNEL_TRANSITION_HZ / BASEBAND_RATE as f32,
+                ),
+                1,
+            ),
+            filtered: Vec::new(),
+            costas_phase: 0.0,
+            costas_freq: 0.0,
+            costas_alpha: 4.0 * COSTAS_DAMPING * omega / denom,
+            costas_beta: 4.0 * omega * omega / denom,
+            chips: [0.0; SAMPLES_PER_BIT],
+            chip_pos: 0,
+            energy: [0.0; SAMPLES_PER_BIT],
+            timing_phase: 0,
+            prev_symbol: false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.nco_phase = 0.0;
+        self.resampler.reset();
+        self.channel.reset();
+        self.costas_phase = 0.0;
+        self.costas_freq = 0.0;
+        self.chips = [0.0; SAMPLES_PER_BIT];
+        self.chip_pos = 0;
+        self.energy = [0.0; SAMPLES_PER_BIT];
+        self.timing_phase = 0;
+        self.prev_symbol = false;
+    }
+
+    /// Demodulates `mpx` and appends the differentially decoded bits to `bits`.
+    pub fn process(&mut self, mpx: &[f32], bits: &mut Vec<u8>) {
+        self.mixed.clear();
+        for &x in mpx {
+            let (s, c) = self.nco_phase.sin_cos();
+            self.mixed.push(Complex32::new(x * c as f32, -x * s as f32));
+            self.nco_phase = (self.nco_phase + self.nco_step) % std::f64::consts::TAU;
+        }
+        self.resampled.clear();
+        self.resampler.process(&self.mixed, &mut self.resampled);
+        self.filtered.clear();
+        self.channel.process(&self.resampled, &mut self.filtered);
+
+        let decay = 1.0 / TIMING_AVERAGE_BITS;
+        for i in 0..self.filtered.len() {
+            let v = self.filtered[i] * Complex32::from_polar(1.0, -self.costas_phase);
+            let err = v.re * v.im / v.norm_sqr().max(POWER_FLOOR);
+            self.costas_freq += self.costas_beta * err;
+            self.costas_phase = (self.costas_phase + self.costas_freq + self.costas_alpha * err)
+                % std::f32::consts::TAU;
+
+            self.chips[self.chip_pos] = v.re;
+            self.chip_pos = (self.chip_pos + 1) % SAMPLES_PER_BIT;
+            // Biphase matched filter: first half of the bit minus the second half.
+            let half = SAMPLES_PER_BIT / 2;
+            let mut m = 0f32;
+            for k in 0..SAMPLES_PER_BIT {
+                let c = self.chips[(self.chip_pos + k) % SAMPLES_PER_BIT];
+                m += if k < half { c } else { -c };
+            }
+
+            // The phase of the bit clock with the most matched-filter energy marks bit ends.
+            let phase = self.chip_pos;
+            self.energy[phase] += (m * m - self.energy[phase]) * decay;
+            if phase == 0 {
+                self.timing_phase = self
+                    .energy
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.total_cmp(b.1))
+                    .map(|(i, _)| i)
+                    .unwrap_or(0);
+            }
+            if phase == self.timing_phase {
+                let symbol = m > 0.0;
+                bits.push((symbol != self.prev_symbol) as u8);
+                self.prev_symbol = symbol;
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use super::Group;
+
+const PS_LEN: usize = 8;
+const RT_LEN: usize = 64;
+/// Version B RadioText carries two characters per group, so it is at most 32 long.
+const RT_B_LEN: usize = 32;
+/// Carriage return marks the end of a RadioText shorter than the full buffer.
+const RT_END: u8 = 0x0D;
+
+/// Station information decoded from RDS groups.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StationInfo {
+    /// Programme identification code.
+    pub pi: Option<u16>,
+    /// Programme type (0..=31).
+    pub pty: Option<u8>,
+    /// Traffic programme flag.
+    pub tp: bool,
+    /// Programme service name, once all four segments have been received.
+    pub ps: Option<String>,
+    /// RadioText, once every segment up to the end marker has been received.
+    pub radiotext: Option<String>,
+}
+
+/// Replaces anything outside printable ASCII; the RDS character table differs above 0x7E.
+fn rds_char(b: u8) -> char {
+    if (0x20..=0x7E).contains(&b) {
+        b as char
+    } else {
+        '?'
+    }
+}
+
+/// Assembles programme service name and RadioText from decoded groups.
+pub struct GroupDecoder {
+    info: StationInfo,
+    ps: [u8; PS_LEN],
+    ps_received: u8,
+    rt: [u8; RT_LEN],
+    rt_received: u64,
+    rt_ab: Option<bool>,
+}
+
+impl Default for GroupDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GroupDecoder {
+    pub fn new() -> Self {
+        Self {
+            info: StationInfo::default(),
+            ps: [b' '; PS_LEN],
+            ps_received: 0,
+            rt: [b' '; RT_LEN],
+            rt_received: 0,
+            rt_ab: None,
+        }
+    }
+
+    pub fn info(&self) -> &StationInfo {
+        &self.info
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Applies one group; returns true when the station information changed.
+    pub fn apply(&mut self, group: &Group) -> bool {
+        let before = self.info.clone();
+        let [a, b, c, d] = group.blocks;
+
+        // Version B groups repeat the PI code in block C.
+        if let Some(pi) = a.or(b.filter(|b| b & 0x0800 != 0).and(c)) {
+            if self.info.pi.is_some_and(|old| old != pi) {
+                // Another station: drop everything assembled so far.
+                self.reset();
+            }
+            self.info.pi = Some(pi);
+        }
+
+        let Some(b) = b else {
+            return self.info != before;
+        };
+        let group_type = (b >> 12) as u8;
+        let version_b = b & 0x0800 != 0;
+        self.info.tp = b & 0x0400 != 0;
+        self.info.pty = Some(((b >> 5) & 0x1F) as u8);
+
+        match (group_type, version_b) {
+            (0, _) => {
+                if let Some(d) = d {
+                    self.apply_ps((b & 0x3) as usize, d);
+                }
+            }
+            (2, false) => self.apply_rt(b, &[c, d], RT_LEN),
+            (2, true) => self.apply_rt(b, &[d], RT_B_LEN),
+            _ => {}
+        }
+        self.info != before
+    }
+
+    fn apply_ps(&mut self, segment: usize, chars: u16) {
+        self.ps[segment * 2] = (chars >> 8) as u8;
+        self.ps[segment * 2 + 1] = chars as u8;
+        self.ps_received |= 1 << segment;
+        if self.ps_received == 0xF {
+            self.info.ps = Some(self.ps.iter().map(|&b| rds_char(b)).collect());
+        }
+    }
+
+    fn apply_rt(&mut self, b: u16, words: &[Option<u16>], max_len: usize) {
+        let ab = b & 0x0010 != 0;
+        if self.rt_ab.is_some_and(|old| old != ab) {
+            // The A/B flag toggles when the station starts a new text.
+            self.rt = [b' '; RT_LEN];
+            self.rt_received = 0;
+        }
+        self.rt_ab = Some(ab);
+
+        let base = (b & 0xF) as usize * words.len() * 2;
+        for (i, word) in words.iter().enumerate() {
+            let Some(word) = word else {
+                continue;
+            };
+            for (j, byte) in [(word >> 8) as u8, *word as u8].into_iter().enumerate() {
+                let pos = base + i * 2 + j;
+                if pos < max_len {
+                    self.rt[pos] = byte;
+                    self.rt_received |= 1 << pos;
+                }
+            }
+        }
+
+        let end = self.rt[..max_len]
+            .iter()
+            .position(|&b| b == RT_END)
+            .unwrap_or(max_len);
+        let needed = if end >= RT_LEN {
+            u64::MAX
+        } else {
+            (1u64 << end) - 1
+        };
+        if self.rt_received & needed == needed {
+            let text: String = self.rt[..end].iter().map(|&b| rds_char(b)).collect();
+            self.info.radiotext = Some(text.trim_end().to_string());
+        }
+    }
+}
@@ -0,0 +1,56 @@
+mod blocks;
+mod demod;
+mod groups;
+
+pub use blocks::{encode_group, BlockSync, Group, BLOCK_BITS, GROUP_BITS};
+pub use demod::Demodulator;
+pub use groups::{GroupDecoder, StationInfo};
+
+/// RDS subcarrier, the third harmonic of the 19 kHz stereo pilot.
+pub const SUBCARRIER_HZ: f64 = 57_000.0;
+/// Bit rate: the subcarrier divided by 48.
+pub const BIT_RATE: f64 = SUBCARRIER_HZ / 48.0;
+/// Lowest multiplex sample rate that still holds the subcarrier and both sidebands.
+pub const MIN_MPX_RATE: f64 = 2.0 * (SUBCARRIER_HZ + 2.0 * BIT_RATE);
+
+/// Full chain from FM multiplex samples to decoded station information.
+pub struct Receiver {
+    demod: Demodulator,
+    sync: BlockSync,
+    groups: GroupDecoder,
+    bits: Vec<u8>,
+}
+
+impl Receiver {
+    pub fn new(mpx_rate: f64) -> Self {
+        Self {
+            demod: Demodulator::new(mpx_rate),
+            sync: BlockSync::new(),
+            groups: GroupDecoder::new(),
+            bits: Vec::new(),
+        }
+    }
+
+    /// Feeds multiplex samples; returns true when the station information changed.
+    pub fn process(&mut self, mpx: &[f32]) -> bool {
+        self.bits.clear();
+        self.demod.process(mpx, &mut self.bits);
+        let mut changed = false;
+        for &bit in &self.bits {
+            if let Some(group) = self.sync.push_bit(bit) {
+                changed |= self.groups.apply(&group);
+            }
+        }
+        changed
+    }
+
+    pub fn info(&self) -> &StationInfo {
+        self.groups.info()
+    }
+
+    pub fn reset(&mut self) {
+        self.demod.reset();
+        self.sync.reset();
+        self.groups.reset();
+    }
+}
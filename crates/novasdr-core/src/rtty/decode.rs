@@ -0,0 +1,281 @@
+use super::{ita2_of_ccir476, Mode, Shift, ALPHA, BETA, REP};
+use crate::dsp::fsk::{BitSync, FskDemodulator};
+use std::collections::VecDeque;
+
+/// Least mean soft symbol magnitude of an accepted RTTY character.
+const MIN_CONFIDENCE: f32 = 0.45;
+/// Valid CCIR 476 words in a row at one bit phase before NAVTEX locks on to it.
+const LOCK_WORDS: u32 = 10;
+/// Characters in a row with both copies broken before NAVTEX lets go of the signal.
+const UNLOCK_ERRORS: u32 = 8;
+/// Votes one slot parity needs over the other to be taken as the DX copy.
+const DX_VOTE_MARGIN: i32 = 3;
+/// The RX copy of a NAVTEX character follows its DX copy by this many slots.
+const RX_DELAY: usize = 5;
+
+/// Decodes the text of one RTTY or NAVTEX signal from demodulated audio.
+pub struct Decoder {
+    demod: FskDemodulator,
+    shift: Shift,
+    text: String,
+    framing: Framing,
+}
+
+enum Framing {
+    Async(Async),
+    Sitor(Box<Sitor>),
+}
+
+impl Decoder {
+    /// A decoder of `mode` with the mark tone at `mark_hz` and the space tone at `space_hz` in
+    /// audio at `sample_rate`.
+    pub fn new(mode: Mode, sample_rate: f64, mark_hz: f64, space_hz: f64) -> Self {
+        let baud = mode.baud();
+        let framing = match mode {
+            Mode::Rtty45 | Mode::Rtty50 => Framing::Async(Async::new(sample_rate / baud)),
+            Mode::Navtex => Framing::Sitor(Box::new(Sitor::new(sample_rate, baud))),
+        };
+        Self {
+            demod: FskDemodulator::new(sample_rate, mark_hz, space_hz, baud),
+            shift: Shift::default(),
+            text: String::new(),
+            framing,
+        }
+    }
+
+    pub fn process(&mut self, samples: &[f32]) {
+        for &x in samples {
+            let soft = self.demod.process(x);
+            match &mut self.framing {
+                Framing::Async(a) => {
+                    if let Some(code) = a.push(soft) {
+                        if let Some(c) = self.shift.decode(code, true) {
+                            self.text.push(c);
+                        }
+                    }
+                }
+                Framing::Sitor(s) => s.push(soft, &mut self.shift, &mut self.text),
+            }
+        }
+    }
+
+    /// Text decoded since the last call.
+    pub fn take_text(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+}
+
+/// Start/stop framing of RTTY: every character opens with a space start bit, whose leading
+/// edge sets the clock for its five data bits and the mark stop bit.
+struct Async {
+    samples_per_bit: f64,
+    /// Samples seen.
+    n: u64,
+    prev: f32,
+    /// Where the current start bit began, in the soft symbol stream.
+    start: Option<f64>,
+    bits: Vec<f32>,
+}
+
+impl Async {
+    fn new(samples_per_bit: f64) -> Self {
+        Self {
+            samples_per_bit,
+            n: 0,
+            prev: 0.0,
+            start: None,
+            bits: Vec::with_capacity(7),
+        }
+    }
+
+    fn push(&mut self, soft: f32) -> Option<u8> {
+        let n = self.n as f64;
+        self.n += 1;
+        let prev = std::mem::replace(&mut self.prev, soft);
+        let Some(start) = self.start else {
+            if prev > 0.0 && soft <= 0.0 {
+                let frac = f64::from(prev / (prev - soft));
+                self.start = Some(n - 1.0 + frac);
+                self.bits.clear();
+            }
+            return None;
+        };
+        // The filters average over one bit, so a bit is cleanest half a bit after the
+        // crossing that ends it.
+        let centre = start + (self.bits.len() as f64 + 0.5) * self.samples_per_bit;
+        if n < centre.round() {
+            return None;
+        }
+        self.bits.push(soft);
+        if self.bits.len() == 1 && soft > 0.0 {
+            // A glitch rather than a start bit.
+            self.start = None;
+            return None;
+        }
+        if self.bits.len() < 7 {
+            return None;
+        }
+        self.start = None;
+        let confidence = self.bits.iter().map(|b| b.abs()).sum::<f32>() / 7.0;
+        if self.bits[6] <= 0.0 || confidence < MIN_CONFIDENCE {
+            return None;
+        }
+        Some(
+            self.bits[1..6]
+                .iter()
+                .enumerate()
+                .fold(0, |code, (i, &b)| code | (u8::from(b > 0.0) << i)),
+        )
+    }
+}
+
+/// Synchronous SITOR-B framing of NAVTEX: a continuous stream of 7-bit words, every character
+/// sent once as DX and again five words later as RX.
+struct Sitor {
+    sync: BitSync,
+    /// The last seven bits.
+    word: u8,
+    bits: u64,
+    /// Valid words in a row at every bit phase, for normal and inverted polarity.
+    runs: [[u32; 2]; 7],
+    lock: Option<Lock>,
+}
+
+struct Lock {
+    phase: u64,
+    inverted: bool,
+    /// Received words not yet printed, oldest first.
+    slots: VecDeque<u8>,
+    /// Index of the first word in `slots`.
+    base: u64,
+    /// Evidence that even slots carry DX, minus the evidence for odd slots.
+    dx_votes: i32,
+    dx_parity: Option<u64>,
+    errors: u32,
+}
+
+fn is_valid(word: u8) -> bool {
+    word.count_ones() == 4
+}
+
+impl Sitor {
+    fn new(sample_rate: f64, baud: f64) -> Self {
+        Self {
+            sync: BitSync::new(sample_rate, baud),
+            word: 0,
+            bits: 0,
+            runs: [[0; 2]; 7],
+            lock: None,
+        }
+    }
+
+    fn push(&mut self, soft: f32, shift: &mut Shift, text: &mut String) {
+        let Some(bit) = self.sync.push(soft) else {
+            return;
+        };
+        self.word = ((self.word << 1) | u8::from(bit)) & 0x7f;
+        self.bits += 1;
+        let phase = self.bits % 7;
+
+        let Some(lock) = &mut self.lock else {
+            let runs = &mut self.runs[phase as usize];
+            for (inverted, run) in runs.iter_mut().enumerate() {
+                let word = if inverted == 1 {
+                    !self.word & 0x7f
+                } else {
+                    self.word
+                };
+                *run = if is_valid(word) { *run + 1 } else { 0 };
+                if *run >= LOCK_WORDS {
+                    self.lock = Some(Lock {
+                        phase,
+                        inverted: inverted == 1,
+                        slots: VecDeque::new(),
+                        base: 0,
+                        dx_votes: 0,
+                        dx_parity: None,
+                        errors: 0,
+                    });
+                    self.runs = [[0; 2]; 7];
+                    *shift = Shift::default();
+                    break;
+                }
+            }
+            return;
+        };
+        if phase != lock.phase {
+            return;
+        }
+        let word = if lock.inverted {
+            !self.word & 0x7f
+        } else {
+            self.word
+        };
+        if !lock.push(word, shift, text) {
+            self.lock = None;
+        }
+    }
+}
+
+impl Lock {
+    /// Takes the next word; returns whether the signal still holds.
+    fn push(&mut self, word: u8, shift: &mut Shift, text: &mut String) -> bool {
+        self.slots.push_back(word);
+        let slot = self.base + self.slots.len() as u64 - 1;
+        if self.dx_parity.is_none() {
+            let vote = |parity: u64| if parity == 0 { 1 } else { -1 };
+            match word {
+                ALPHA => self.dx_votes += vote(slot % 2),
+                REP => self.dx_votes -= vote(slot % 2),
+                _ => {}
+            }
+            if let Some(&earlier) = self.slots.iter().rev().nth(RX_DELAY) {
+                if earlier == word && is_valid(word) && word != ALPHA && word != REP {
+                    self.dx_votes += vote((slot - RX_DELAY as u64) % 2);
+                }
+            }
+            if self.dx_votes.abs() >= DX_VOTE_MARGIN {
+                self.dx_parity = Some(if self.dx_votes > 0 { 0 } else { 1 });
+            } else if self.slots.len() > 4 * RX_DELAY {
+                self.pop();
+            }
+            return true;
+        }
+        let dx_parity = self.dx_parity.unwrap_or_default();
+        if self.base % 2 != dx_parity {
+            self.pop();
+        }
+        while self.slots.len() > RX_DELAY {
+            let dx = self.slots[0];
+            let rx = self.slots[RX_DELAY];
+            self.pop();
+            self.pop();
+            let best = if is_valid(dx) {
+                dx
+            } else if is_valid(rx) {
+                rx
+            } else {
+                self.errors += 1;
+                if self.errors >= UNLOCK_ERRORS {
+                    return false;
+                }
+                text.push('_');
+                continue;
+            };
+            self.errors = 0;
+            if matches!(best, ALPHA | BETA | REP) {
+                continue;
+            }
+            if let Some(c) = ita2_of_ccir476(best).and_then(|code| shift.decode(code, false)) {
+                text.push(c);
+            }
+        }
+        true
+    }
+
+    fn pop(&mut self) {
+        if self.slots.pop_front().is_some() {
+            self.base += 1;
+        }
+    }
+}
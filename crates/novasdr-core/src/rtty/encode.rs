@@ -0,0 +1,59 @@
+use super::{ita2, Mode, ALPHA, CCIR476, REP};
+use std::f64::consts::TAU;
+
+/// Mark tone before and after RTTY text, in bits.
+const RTTY_IDLE_BITS: usize = 20;
+/// Phasing signals before NAVTEX text, in characters.
+const NAVTEX_PHASING: usize = 20;
+/// The RX copy of a NAVTEX character follows its DX copy by this many characters.
+const NAVTEX_RX_DELAY: usize = 5;
+
+/// `text` keyed as `mode`, as the audio of an SSB receiver with the mark tone at `mark_hz` and
+/// the space tone at `space_hz`, at `sample_rate`. Characters without an ITA2 code are left
+/// out; a line feed is sent as CR LF.
+pub fn encode(text: &str, mode: Mode, sample_rate: f64, mark_hz: f64, space_hz: f64) -> Vec<f32> {
+    let codes = ita2(text);
+    // Every bit as a number of half bits and whether it is mark.
+    let mut bits: Vec<(usize, bool)> = Vec::new();
+    match mode {
+        Mode::Rtty45 | Mode::Rtty50 => {
+            bits.push((2 * RTTY_IDLE_BITS, true));
+            for code in codes {
+                bits.push((2, false));
+                bits.extend((0..5).map(|i| (2, code >> i & 1 == 1)));
+                bits.push((3, true));
+            }
+            bits.push((2 * RTTY_IDLE_BITS, true));
+        }
+        Mode::Navtex => {
+            let mut dx: Vec<u8> = vec![ALPHA; NAVTEX_PHASING];
+            dx.extend(codes.iter().map(|&c| CCIR476[usize::from(c)]));
+            dx.extend([ALPHA; NAVTEX_RX_DELAY]);
+            let rx_offset = NAVTEX_RX_DELAY / 2;
+            for (k, &word) in dx.iter().enumerate() {
+                let rx = match k.checked_sub(rx_offset) {
+                    Some(j) if j >= NAVTEX_PHASING => dx[j],
+                    _ => REP,
+                };
+                for w in [word, rx] {
+                    bits.extend((0..7).rev().map(|i| (2, w >> i & 1 == 1)));
+                }
+            }
+        }
+    }
+
+    let half_bit = sample_rate / mode.baud() / 2.0;
+    let mut out = Vec::new();
+    let mut phase = 0.0f64;
+    let mut elapsed = 0usize;
+    for (halves, mark) in bits {
+        elapsed += halves;
+        let end = (elapsed as f64 * half_bit).round() as usize;
+        let step = TAU * if mark { mark_hz } else { space_hz } / sample_rate;
+        while out.len() < end {
+            out.push(phase.sin() as f32);
+            phase = (phase + step) % TAU;
+        }
+    }
+    out
+}
@@ -0,0 +1,127 @@
+//! Radioteletype from the FSK audio of an SSB receiver: ITA2 ("Baudot") RTTY at 45.45 and
+//! 50 baud, and NAVTEX, which sends ITA2 text as SITOR-B: every character mapped to a 7-bit
+//! CCIR 476 code with four marks, sent twice at 100 baud.
+
+mod decode;
+mod encode;
+
+pub use decode::Decoder;
+pub use encode::encode;
+
+/// Customary shift between mark and space.
+pub const SHIFT_HZ: f64 = 170.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Amateur RTTY: 45.45 baud, one start and 1.5 stop bits.
+    Rtty45,
+    /// Weather and press RTTY at 50 baud.
+    Rtty50,
+    /// NAVTEX maritime safety broadcasts (SITOR-B).
+    Navtex,
+}
+
+impl Mode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "rtty45" => Some(Self::Rtty45),
+            "rtty50" => Some(Self::Rtty50),
+            "navtex" => Some(Self::Navtex),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Rtty45 => "rtty45",
+            Self::Rtty50 => "rtty50",
+            Self::Navtex => "navtex",
+        }
+    }
+
+    pub fn baud(self) -> f64 {
+        match self {
+            Self::Rtty45 => 45.45,
+            Self::Rtty50 => 50.0,
+            Self::Navtex => 100.0,
+        }
+    }
+}
+
+const LTRS: u8 = 0x1f;
+const FIGS: u8 = 0x1b;
+/// ITA2 characters by 5-bit code (bit 1 least significant); NUL for shifts and unused codes.
+const LETTERS: &[u8; 32] = b"\0E\nA SIU\rDRJNFCKTZLWHYPQOBG\0MXV\0";
+const FIGURES: &[u8; 32] = b"\x003\n- '87\r$4\x07,!:(5+)2#6019?&\0./=\0";
+
+/// CCIR 476 code of every ITA2 code, first bit sent most significant.
+const CCIR476: [u8; 32] = [
+    0x6a, 0x56, 0x6c, 0x47, 0x5c, 0x4b, 0x4d, 0x4e, 0x78, 0x53, 0x55, 0x17, 0x59, 0x1b, 0x1d, 0x1e,
+    0x74, 0x63, 0x65, 0x27, 0x69, 0x2b, 0x2d, 0x2e, 0x71, 0x72, 0x35, 0x36, 0x39, 0x3a, 0x3c, 0x5a,
+];
+/// Phasing signal 1, idle in the first (DX) copy.
+const ALPHA: u8 = 0x0f;
+/// Phasing signal 2, idle in the repeated (RX) copy.
+const REP: u8 = 0x66;
+/// Idle signal beta.
+const BETA: u8 = 0x33;
+
+fn ita2_of_ccir476(code: u8) -> Option<u8> {
+    CCIR476.iter().position(|&c| c == code).map(|i| i as u8)
+}
+
+/// Letters/figures state of a teletype printer.
+#[derive(Default)]
+struct Shift {
+    figures: bool,
+}
+
+impl Shift {
+    /// The printed character of an ITA2 code; `None` for shifts and for what a terminal would
+    /// not show. `unshift_on_space` returns to letters after a space, as RTTY terminals do.
+    fn decode(&mut self, code: u8, unshift_on_space: bool) -> Option<char> {
+        match code {
+            LTRS => self.figures = false,
+            FIGS => self.figures = true,
+            _ => {}
+        }
+        let table = if self.figures { FIGURES } else { LETTERS };
+        let c = table[usize::from(code & 0x1f)];
+        if c == b' ' && unshift_on_space {
+            self.figures = false;
+        }
+        match c {
+            0 | b'\r' | 0x07 => None,
+            c => Some(char::from(c)),
+        }
+    }
+}
+
+/// `text` as ITA2 codes. Whether a space returns the printer to letters
+/// differs between RTTY and NAVTEX terminals, so a shift is sent again after every space.
+fn ita2(text: &str) -> Vec<u8> {
+    let mut codes = Vec::new();
+    // Known shift state: `Some(true)` for figures.
+    let mut figures = None;
+    let find = |table: &[u8; 32], c: u8| table.iter().position(|&t| t == c && c != 0);
+    for c in text.to_ascii_uppercase().bytes() {
+        let chars: &[u8] = if c == b'\n' { b"\r\n" } else { &[c] };
+        for &c in chars {
+            let (code, needs) = match (find(LETTERS, c), find(FIGURES, c)) {
+                (Some(l), Some(f)) if l == f => (l, None),
+                (Some(l), _) => (l, Some(false)),
+                (None, Some(f)) => (f, Some(true)),
+                (None, None) => continue,
+            };
+            if needs.is_some() && needs != figures {
+                codes.push(if needs == Some(true) { FIGS } else { LTRS });
+                figures = needs;
+            }
+            codes.push(code as u8);
+            if c == b' ' {
+                figures = None;
+            }
+        }
+    }
+    codes
+}
@@ -0,0 +1,84 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Points closer than this are treated as the same calibration frequency; setting one replaces
+/// the other instead of adding a near-duplicate.
+pub const POINT_MERGE_HZ: i64 = 1_000;
+
+/// One measured correction: the S-meter reads `offset_db` too low at `frequency`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationPoint {
+    pub frequency: i64,
+    pub offset_db: f32,
+}
+
+/// Per-receiver S-meter calibration: a frequency -> dB offset table, linearly interpolated
+/// between points and held flat beyond the first and last point. An empty table applies no
+/// correction.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SmeterCalibration {
+    #[serde(default)]
+    points: Vec<CalibrationPoint>,
+}
+
+impl SmeterCalibration {
+    pub fn new(mut points: Vec<CalibrationPoint>) -> Self {
+        points.retain(|p| p.offset_db.is_finite());
+        points.sort_by_key(|p| p.frequency);
+        points.dedup_by_key(|p| p.frequency);
+        Self { points }
+    }
+
+    /// Reads a calibration file; a missing file is an empty table.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = match std::fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        };
+        let parsed: Self =
+            serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display()))?;
+        Ok(Self::new(parsed.points))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let value = serde_json::to_value(self).context("serialize S-meter calibration")?;
+        crate::config::write_json_atomic(path, &value)
+    }
+
+    pub fn points(&self) -> &[CalibrationPoint] {
+        &self.points
+    }
+
+    /// Adds a point, replacing any existing point within [`POINT_MERGE_HZ`].
+    pub fn set_point(&mut self, point: CalibrationPoint) {
+        self.points
+            .retain(|p| (p.frequency - point.frequency).abs() >= POINT_MERGE_HZ);
+        self.points.push(point);
+        self.points = Self::new(std::mem::take(&mut self.points)).points;
+    }
+
+    /// Correction in dB to add to the level measured at `frequency`.
+    pub fn offset_db(&self, frequency: i64) -> f32 {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 0.0,
+        };
+        if frequency <= first.frequency {
+            return first.offset_db;
+        }
+        if frequency >= last.frequency {
+            return last.offset_db;
+        }
+        let upper = self.points.partition_point(|p| p.frequency <= frequency);
+        let (a, b) = (self.points[upper - 1], self.points[upper]);
+        let t = (frequency - a.frequency) as f32 / (b.frequency - a.frequency) as f32;
+        a.offset_db + t * (b.offset_db - a.offset_db)
+    }
+
+    /// Linear power factor for [`Self::offset_db`] at `frequency`.
+    pub fn power_gain(&self, frequency: i64) -> f32 {
+        10f32.powf(self.offset_db(frequency) / 10.0)
+    }
+}
@@ -0,0 +1,450 @@
+use super::{level, ycc_to_rgb, Channel, Mode, LEADER_HZ, SYNC_HZ, VIS_ONE_HZ, VIS_ZERO_HZ};
+use crate::dsp::fir::{lowpass_taps, FirDecimator};
+use num_complex::Complex32;
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+
+/// Middle of the band from sync to white; the audio is mixed down from here.
+const CENTER_HZ: f32 = 1700.0;
+const FILTER_CUTOFF_HZ: f32 = 1100.0;
+const FILTER_TRANSITION_HZ: f32 = 400.0;
+/// Faster audio is decimated to about this rate before the tone is tracked.
+const TRACK_RATE: f64 = 12_000.0;
+/// How far a VIS tone may be off, averaged over a millisecond.
+const TONE_TOLERANCE_HZ: f32 = 100.0;
+/// Leader heard before a start bit, of the 300 ms sent.
+const MIN_LEADER_MS: usize = 150;
+/// Start bit, seven data bits, parity and stop bit.
+const VIS_MS: usize = 300;
+/// Start of each VIS bit skipped while averaging it, and as much of its end.
+const VIS_BIT_GUARD_MS: usize = 5;
+/// Tones below this count towards a sync pulse.
+const SYNC_THRESHOLD_HZ: f32 = 1350.0;
+/// Share of a pulse that must be below the threshold for it to count as found.
+const SYNC_MIN_SHARE: f32 = 0.6;
+/// Search window around the expected first sync pulse; the VIS code only places it to about a
+/// millisecond, and later pulses are searched one pulse length either side.
+const FIRST_SYNC_SEARCH_MS: f64 = 10.0;
+/// Lines without two sync pulses in a row after which the transmission has ended. Noise now and
+/// then passes for a single pulse, but seldom for two a line apart.
+const MAX_MISSED_SYNCS: usize = 8;
+/// Images that ended before this many lines are dropped.
+const MIN_LINES: usize = 16;
+/// A [`Event::Progress`] is sent every this many lines.
+const PROGRESS_LINES: usize = 16;
+
+/// A received picture. `height` is below the mode's when the transmission ended early.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub mode: Mode,
+    pub width: usize,
+    pub height: usize,
+    /// Row-major 8-bit RGB.
+    pub rgb: Vec<u8>,
+}
+
+impl Image {
+    pub fn complete(&self) -> bool {
+        self.height == self.mode.height()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A VIS code announced a picture in this mode.
+    Started(Mode),
+    /// Lines of the picture in this mode received so far.
+    Progress(Mode, usize),
+    Finished(Image),
+}
+
+struct Receiving {
+    mode: Mode,
+    /// Where the next line's sync pulse should start, in tracked samples.
+    next_sync: f64,
+    line: usize,
+    /// Whether the last line's sync pulse was found.
+    synced: bool,
+    /// Lines up to the last one whose sync pulse followed another.
+    kept: usize,
+    rgb: Vec<u8>,
+    /// Robot 36: luma and red difference of the even line whose blue difference comes next.
+    even: Option<(Vec<f32>, Vec<f32>)>,
+}
+
+/// Finds VIS codes in demodulated audio and decodes the pictures that follow.
+pub struct Decoder {
+    sample_rate: f64,
+    /// Rate of the tracked tone.
+    rate: f64,
+    osc_phase: f64,
+    osc_step: f64,
+    filter: FirDecimator<Complex32>,
+    mixed: Vec<Complex32>,
+    filtered: Vec<Complex32>,
+    prev: Complex32,
+    /// Tracked tone in Hz; `tones[0]` is sample `base`.
+    tones: Vec<f32>,
+    base: u64,
+    /// Millisecond means of the tone for the VIS search; `ms[0]` is millisecond `ms_base`.
+    ms: VecDeque<f32>,
+    ms_base: u64,
+    ms_sum: f32,
+    ms_count: usize,
+    leader_run: usize,
+    last_leader: Option<u64>,
+    /// Milliseconds where a start bit may begin, waiting for the rest of the code.
+    candidates: VecDeque<u64>,
+    receiving: Option<Receiving>,
+    events: VecDeque<Event>,
+}
+
+impl Decoder {
+    pub fn new(sample_rate: f64) -> Self {
+        let decimation = ((sample_rate / TRACK_RATE).floor() as usize).max(1);
+        let taps = lowpass_taps(
+            FILTER_CUTOFF_HZ / sample_rate as f32,
+            FILTER_TRANSITION_HZ / sample_rate as f32,
+        );
+        Self {
+            sample_rate,
+            rate: sample_rate / decimation as f64,
+            osc_phase: 0.0,
+            osc_step: TAU * f64::from(CENTER_HZ) / sample_rate,
+            filter: FirDecimator::new(taps, decimation),
+            mixed: Vec::new(),
+            filtered: Vec::new(),
+            prev: Complex32::new(0.0, 0.0),
+            tones: Vec::new(),
+            base: 0,
+            ms: VecDeque::new(),
+            ms_base: 0,
+            ms_sum: 0.0,
+            ms_count: 0,
+            leader_run: 0,
+            last_leader: None,
+            candidates: VecDeque::new(),
+            receiving: None,
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn process(&mut self, audio: &[f32]) {
+        self.mixed.clear();
+        for &x in audio {
+            let (sin, cos) = self.osc_phase.sin_cos();
+            self.mixed
+                .push(Complex32::new(x * cos as f32, -x * sin as f32));
+            self.osc_phase = (self.osc_phase + self.osc_step) % TAU;
+        }
+        self.filtered.clear();
+        self.filter.process(&self.mixed, &mut self.filtered);
+        let hz_per_radian = (self.rate / TAU) as f32;
+        for i in 0..self.filtered.len() {
+            let z = self.filtered[i];
+            let tone = CENTER_HZ + (z * self.prev.conj()).arg() * hz_per_radian;
+            self.prev = z;
+            self.push_tone(tone);
+        }
+        self.decode_lines();
+        self.trim();
+    }
+
+    pub fn pop_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Mode of the picture being received and the lines received so far.
+    pub fn receiving(&self) -> Option<(Mode, usize)> {
+        self.receiving.as_ref().map(|rx| (rx.mode, rx.line))
+    }
+
+    /// Ends a picture in progress as if its transmission had stopped.
+    pub fn finish(&mut self) {
+        let Some(mut rx) = self.receiving.take() else {
+            return;
+        };
+        if let Some((luma, red_diff)) = rx.even.take() {
+            // The blue difference never came; leave it neutral.
+            let neutral = vec![127.5; luma.len()];
+            write_ycc(&mut rx.rgb, rx.line - 1, &luma, &red_diff, &neutral);
+        }
+        if rx.line < MIN_LINES.min(rx.mode.height()) {
+            return;
+        }
+        let width = rx.mode.width();
+        rx.rgb.truncate(rx.line * width * 3);
+        self.events.push_back(Event::Finished(Image {
+            mode: rx.mode,
+            width,
+            height: rx.line,
+            rgb: rx.rgb,
+        }));
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.sample_rate);
+    }
+
+    fn samples(&self, ms: f64) -> f64 {
+        ms * self.rate / 1000.0
+    }
+
+    fn push_tone(&mut self, tone: f32) {
+        let i = self.base + self.tones.len() as u64;
+        self.tones.push(tone);
+        let ms = (i as f64 * 1000.0 / self.rate) as u64;
+        let next_ms = ((i + 1) as f64 * 1000.0 / self.rate) as u64;
+        self.ms_sum += tone;
+        self.ms_count += 1;
+        if next_ms != ms {
+            let mean = self.ms_sum / self.ms_count as f32;
+            self.ms_sum = 0.0;
+            self.ms_count = 0;
+            self.on_millisecond(ms, mean);
+        }
+    }
+
+    fn on_millisecond(&mut self, k: u64, mean: f32) {
+        if self.ms.is_empty() {
+            self.ms_base = k;
+        }
+        self.ms.push_back(mean);
+        while self.ms.len() > VIS_MS + 1 {
+            self.ms.pop_front();
+            self.ms_base += 1;
+        }
+
+        if near(mean, LEADER_HZ) {
+            if self.last_leader.is_none_or(|last| last + 1 != k) {
+                self.leader_run = 0;
+            }
+            self.leader_run += 1;
+            self.last_leader = Some(k);
+        } else if near(mean, SYNC_HZ)
+            && self.leader_run >= MIN_LEADER_MS
+            && self.last_leader.is_some_and(|last| k - last <= 2)
+        {
+            self.candidates.push_back(k);
+            self.leader_run = 0;
+        }
+
+        while let Some(&start) = self.candidates.front() {
+            if k < start + VIS_MS as u64 - 1 {
+                break;
+            }
+            self.candidates.pop_front();
+            if let Some(mode) = self.read_vis(start) {
+                self.candidates.clear();
+                self.start(mode, start + VIS_MS as u64);
+            }
+        }
+    }
+
+    /// The mode named by the VIS code whose start bit begins at millisecond `start`.
+    fn read_vis(&self, start: u64) -> Option<Mode> {
+        let bit = |n: usize| {
+            let from = (start - self.ms_base) as usize + n * 30 + VIS_BIT_GUARD_MS;
+            let len = 30 - 2 * VIS_BIT_GUARD_MS;
+            self.ms.range(from..from + len).sum::<f32>() / len as f32
+        };
+        if !near(bit(0), SYNC_HZ) || !near(bit(9), SYNC_HZ) {
+            return None;
+        }
+        let mut code = 0u8;
+        for n in 0..8 {
+            let tone = bit(n + 1);
+            let one = if near(tone, VIS_ONE_HZ) && tone < SYNC_HZ {
+                true
+            } else if near(tone, VIS_ZERO_HZ) && tone >= SYNC_HZ {
+                false
+            } else {
+                return None;
+            };
+            code |= u8::from(one) << n;
+        }
+        // Even parity over the data bits and the parity bit.
+        if !code.count_ones().is_multiple_of(2) {
+            return None;
+        }
+        Mode::from_vis(code & 0x7F)
+    }
+
+    fn start(&mut self, mode: Mode, image_ms: u64) {
+        self.finish();
+        let image_start = self.samples(image_ms as f64);
+        let (width, height) = (mode.width(), mode.height());
+        self.receiving = Some(Receiving {
+            mode,
+            next_sync: image_start + self.samples(mode.timing().first_sync_ms),
+            line: 0,
+            synced: true,
+            kept: 0,
+            rgb: vec![0; width * height * 3],
+            even: None,
+        });
+        self.events.push_back(Event::Started(mode));
+    }
+
+    fn decode_lines(&mut self) {
+        let end = (self.base + self.tones.len() as u64) as f64;
+        while let Some(rx) = self.receiving.as_ref() {
+            let t = rx.mode.timing();
+            let window = self.samples(if rx.line == 0 {
+                FIRST_SYNC_SEARCH_MS
+            } else {
+                t.sync_ms
+            });
+            let last = t
+                .scans
+                .iter()
+                .map(|s| s.offset_ms + s.len_ms)
+                .fold(t.sync_ms, f64::max);
+            if rx.next_sync + window + self.samples(last) >= end {
+                return;
+            }
+            let expected = rx.next_sync;
+            let found = self.find_sync(expected, window, self.samples(t.sync_ms));
+            self.decode_line(found.unwrap_or(expected), found.is_some());
+        }
+    }
+
+    /// Start of the sync pulse closest to `expected` within `window` samples, if there is one.
+    fn find_sync(&self, expected: f64, window: f64, len: f64) -> Option<f64> {
+        let len = len.round() as usize;
+        let lo = (expected - window).round().max(self.base as f64) as u64;
+        let hi = (expected + window).round() as u64;
+        let below = |i: u64| {
+            let tone = self.tones[(i - self.base) as usize];
+            usize::from(tone < SYNC_THRESHOLD_HZ)
+        };
+        let mut count: usize = (lo..lo + len as u64).map(below).sum();
+        let mut best: Option<(usize, f64, u64)> = None;
+        for start in lo..=hi {
+            if start > lo {
+                count = count + below(start + len as u64 - 1) - below(start - 1);
+            }
+            let distance = (start as f64 - expected).abs();
+            if best.is_none_or(|(c, d, _)| count > c || (count == c && distance < d)) {
+                best = Some((count, distance, start));
+            }
+        }
+        let (count, _, start) = best?;
+        (count as f32 >= SYNC_MIN_SHARE * len as f32).then_some(start as f64)
+    }
+
+    fn decode_line(&mut self, sync: f64, found: bool) {
+        let Some(mut rx) = self.receiving.take() else {
+            return;
+        };
+        let t = rx.mode.timing();
+        let width = rx.mode.width();
+        let mut luma = Vec::new();
+        let (mut red_diff, mut blue_diff) = (Vec::new(), Vec::new());
+        for s in t.scans {
+            let levels = self.scan(
+                sync + self.samples(s.offset_ms),
+                self.samples(s.len_ms),
+                width,
+            );
+            match s.channel {
+                Channel::Red | Channel::Green | Channel::Blue => {
+                    let c = match s.channel {
+                        Channel::Red => 0,
+                        Channel::Green => 1,
+                        _ => 2,
+                    };
+                    let row = &mut rx.rgb[rx.line * width * 3..][..width * 3];
+                    for (px, v) in row.chunks_exact_mut(3).zip(&levels) {
+                        px[c] = v.round() as u8;
+                    }
+                }
+                Channel::Luma => luma = levels,
+                Channel::RedDiff => red_diff = levels,
+                Channel::BlueDiff => blue_diff = levels,
+                Channel::AlternateDiff if rx.line % 2 == 0 => red_diff = levels,
+                Channel::AlternateDiff => blue_diff = levels,
+            }
+        }
+        match rx.mode {
+            Mode::Robot72 => write_ycc(&mut rx.rgb, rx.line, &luma, &red_diff, &blue_diff),
+            Mode::Robot36 if rx.line % 2 == 0 => rx.even = Some((luma, red_diff)),
+            Mode::Robot36 => {
+                if let Some((even_luma, red_diff)) = rx.even.take() {
+                    write_ycc(&mut rx.rgb, rx.line - 1, &even_luma, &red_diff, &blue_diff);
+                    write_ycc(&mut rx.rgb, rx.line, &luma, &red_diff, &blue_diff);
+                }
+            }
+            _ => {}
+        }
+
+        rx.line += 1;
+        if found && rx.synced {
+            rx.kept = rx.line;
+        }
+        rx.synced = found;
+        let ended = rx.line - rx.kept >= MAX_MISSED_SYNCS;
+        rx.next_sync = sync + self.samples(t.line_ms);
+        if rx.line % PROGRESS_LINES == 0 && rx.line < rx.mode.height() {
+            self.events.push_back(Event::Progress(rx.mode, rx.line));
+        }
+        let done = rx.line == rx.mode.height() || ended;
+        self.receiving = Some(rx);
+        if done {
+            if let Some(rx) = self.receiving.as_mut() {
+                if ended {
+                    // Lines after the last sync pulses were noise.
+                    rx.line = rx.kept;
+                    rx.even = None;
+                }
+            }
+            self.finish();
+        }
+    }
+
+    /// Pixel levels of a scan `len` samples long starting at sample `from`.
+    fn scan(&self, from: f64, len: f64, width: usize) -> Vec<f32> {
+        (0..width)
+            .map(|x| {
+                let a = from + len * x as f64 / width as f64;
+                let b = from + len * (x + 1) as f64 / width as f64;
+                let a = (a.floor() as u64).max(self.base);
+                let b = (b.floor() as u64).max(a + 1);
+                let tones = &self.tones[(a - self.base) as usize..(b - self.base) as usize];
+                level(tones.iter().sum::<f32>() / tones.len() as f32)
+            })
+            .collect()
+    }
+
+    /// Drops tracked tones no longer needed: those before the earliest scan of the next line,
+    /// or older than a second while no picture is coming in.
+    fn trim(&mut self) {
+        let end = self.base + self.tones.len() as u64;
+        let keep_from = match self.receiving.as_ref() {
+            Some(rx) => {
+                let t = rx.mode.timing();
+                let earliest = t.scans.iter().map(|s| s.offset_ms).fold(0.0, f64::min);
+                let back = self.samples(FIRST_SYNC_SEARCH_MS - earliest);
+                (rx.next_sync - back).max(0.0) as u64
+            }
+            None => end.saturating_sub(self.rate as u64),
+        };
+        // Drained in chunks so the buffer is not shifted on every call.
+        if keep_from > self.base + self.rate as u64 {
+            self.tones.drain(..(keep_from - self.base) as usize);
+            self.base = keep_from;
+        }
+    }
+}
+
+fn near(tone: f32, target: f32) -> bool {
+    (tone - target).abs() < TONE_TOLERANCE_HZ
+}
+
+fn write_ycc(rgb: &mut [u8], line: usize, luma: &[f32], red_diff: &[f32], blue_diff: &[f32]) {
+    let width = luma.len();
+    let row = &mut rgb[line * width * 3..][..width * 3];
+    for (x, px) in row.chunks_exact_mut(3).enumerate() {
+        px.copy_from_slice(&ycc_to_rgb(luma[x], red_diff[x], blue_diff[x]));
+    }
+}
@@ -0,0 +1,91 @@
+use super::{
+    rgb_to_ycc, tone, Channel, Mode, BLACK_HZ, LEADER_HZ, SYNC_HZ, VIS_BIT_MS, VIS_ONE_HZ,
+    VIS_ZERO_HZ,
+};
+use std::f64::consts::TAU;
+
+const LEADER_MS: f64 = 300.0;
+const BREAK_MS: f64 = 10.0;
+
+/// A whole transmission of `rgb` (row-major, `width * height * 3` bytes), VIS header included,
+/// as audio at `sample_rate`. Porches and separators are sent at black level.
+pub fn encode(mode: Mode, rgb: &[u8], sample_rate: f64) -> Vec<f32> {
+    let t = mode.timing();
+    let (width, height) = (mode.width(), mode.height());
+    assert_eq!(
+        rgb.len(),
+        width * height * 3,
+        "image size does not match the mode"
+    );
+    let samples = |ms: f64| (ms * sample_rate / 1000.0).round() as usize;
+
+    let mut header = vec![
+        (LEADER_HZ, LEADER_MS),
+        (SYNC_HZ, BREAK_MS),
+        (LEADER_HZ, LEADER_MS),
+        (SYNC_HZ, VIS_BIT_MS),
+    ];
+    let code = mode.vis();
+    let bits = (0..7)
+        .map(|i| code >> i & 1)
+        .chain([code.count_ones() as u8 & 1]);
+    header.extend(bits.map(|b| (if b == 1 { VIS_ONE_HZ } else { VIS_ZERO_HZ }, VIS_BIT_MS)));
+    header.push((SYNC_HZ, VIS_BIT_MS));
+    let header_ms: f64 = header.iter().map(|&(_, ms)| ms).sum();
+
+    // Tone per sample over the whole transmission; scans are written where they lie, which for
+    // Scottie is not the order of their lines.
+    let last_end = t
+        .scans
+        .iter()
+        .map(|s| s.offset_ms + s.len_ms)
+        .fold(t.sync_ms, f64::max);
+    let image_ms = t.first_sync_ms + (height - 1) as f64 * t.line_ms + last_end;
+    let total = samples(header_ms + image_ms);
+    let mut hz = vec![BLACK_HZ; total];
+    let mut fill = |from_ms: f64, len_ms: f64, f: f32| {
+        let (a, b) = (samples(from_ms), samples(from_ms + len_ms));
+        hz[a..b.min(total)].fill(f);
+    };
+    let mut at = 0.0;
+    for &(f, ms) in &header {
+        fill(at, ms, f);
+        at += ms;
+    }
+    fill(header_ms, t.start_sync_ms, SYNC_HZ);
+
+    let pixel = |x: usize, y: usize| {
+        let i = (y * width + x) * 3;
+        [rgb[i], rgb[i + 1], rgb[i + 2]]
+    };
+    for line in 0..height {
+        let sync = header_ms + t.first_sync_ms + line as f64 * t.line_ms;
+        fill(sync, t.sync_ms, SYNC_HZ);
+        for s in t.scans {
+            let px_ms = s.len_ms / width as f64;
+            for x in 0..width {
+                let p = pixel(x, line);
+                let [y, red_diff, blue_diff] = rgb_to_ycc(p);
+                let level = match s.channel {
+                    Channel::Red => f32::from(p[0]),
+                    Channel::Green => f32::from(p[1]),
+                    Channel::Blue => f32::from(p[2]),
+                    Channel::Luma => y,
+                    Channel::RedDiff => red_diff,
+                    Channel::BlueDiff => blue_diff,
+                    Channel::AlternateDiff if line % 2 == 0 => red_diff,
+                    Channel::AlternateDiff => blue_diff,
+                };
+                fill(sync + s.offset_ms + x as f64 * px_ms, px_ms, tone(level));
+            }
+        }
+    }
+
+    let mut phase = 0.0f64;
+    hz.iter()
+        .map(|&f| {
+            phase = (phase + TAU * f64::from(f) / sample_rate) % TAU;
+            phase.sin() as f32
+        })
+        .collect()
+}
@@ -0,0 +1,234 @@
+//! Slow-scan television from demodulated audio: the VIS header that names the mode, and the
+//! Martin, Scottie and Robot colour modes. Brightness is a tone between [`BLACK_HZ`] and
+//! [`WHITE_HZ`]; every line has a [`SYNC_HZ`] pulse.
+
+mod decode;
+mod encode;
+
+pub use decode::{Decoder, Event, Image};
+pub use encode::encode;
+
+pub const SYNC_HZ: f32 = 1200.0;
+pub const BLACK_HZ: f32 = 1500.0;
+pub const WHITE_HZ: f32 = 2300.0;
+/// Tone of the two VIS leaders.
+pub const LEADER_HZ: f32 = 1900.0;
+pub const VIS_ONE_HZ: f32 = 1100.0;
+pub const VIS_ZERO_HZ: f32 = 1300.0;
+/// Every VIS bit, start and stop bits included.
+pub const VIS_BIT_MS: f64 = 30.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Martin1,
+    Martin2,
+    Scottie1,
+    Scottie2,
+    Robot36,
+    Robot72,
+}
+
+pub const MODES: [Mode; 6] = [
+    Mode::Martin1,
+    Mode::Martin2,
+    Mode::Scottie1,
+    Mode::Scottie2,
+    Mode::Robot36,
+    Mode::Robot72,
+];
+
+/// What a scan carries. Robot 36 sends one colour difference per line, red on even lines and
+/// blue on odd ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Channel {
+    Red,
+    Green,
+    Blue,
+    Luma,
+    RedDiff,
+    BlueDiff,
+    AlternateDiff,
+}
+
+/// One scan of a line and where it lies from the start of the line's sync pulse, in ms.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Scan {
+    pub channel: Channel,
+    pub offset_ms: f64,
+    pub len_ms: f64,
+}
+
+pub(crate) struct Timing {
+    pub sync_ms: f64,
+    pub line_ms: f64,
+    /// From the end of the VIS stop bit to the first line's sync pulse.
+    pub first_sync_ms: f64,
+    /// Scottie sends one extra sync pulse right after the VIS code.
+    pub start_sync_ms: f64,
+    pub scans: &'static [Scan],
+}
+
+const fn scan(channel: Channel, offset_ms: f64, len_ms: f64) -> Scan {
+    Scan {
+        channel,
+        offset_ms,
+        len_ms,
+    }
+}
+
+const MARTIN1: Timing = Timing {
+    sync_ms: 4.862,
+    line_ms: 446.446,
+    first_sync_ms: 0.0,
+    start_sync_ms: 0.0,
+    scans: &[
+        scan(Channel::Green, 5.434, 146.432),
+        scan(Channel::Blue, 152.438, 146.432),
+        scan(Channel::Red, 299.442, 146.432),
+    ],
+};
+
+const MARTIN2: Timing = Timing {
+    sync_ms: 4.862,
+    line_ms: 226.798,
+    first_sync_ms: 0.0,
+    start_sync_ms: 0.0,
+    scans: &[
+        scan(Channel::Green, 5.434, 73.216),
+        scan(Channel::Blue, 79.222, 73.216),
+        scan(Channel::Red, 153.01, 73.216),
+    ],
+};
+
+// Scottie lines are green, blue, sync, red; the sync pulse sits before red, so green and blue
+// come before it.
+const SCOTTIE1: Timing = Timing {
+    sync_ms: 9.0,
+    line_ms: 428.22,
+    first_sync_ms: 288.48,
+    start_sync_ms: 9.0,
+    scans: &[
+        scan(Channel::Green, -277.98, 138.24),
+        scan(Channel::Blue, -138.24, 138.24),
+        scan(Channel::Red, 10.5, 138.24),
+    ],
+};
+
+const SCOTTIE2: Timing = Timing {
+    sync_ms: 9.0,
+    line_ms: 277.692,
+    first_sync_ms: 188.128,
+    start_sync_ms: 9.0,
+    scans: &[
+        scan(Channel::Green, -177.628, 88.064),
+        scan(Channel::Blue, -88.064, 88.064),
+        scan(Channel::Red, 10.5, 88.064),
+    ],
+};
+
+const ROBOT36: Timing = Timing {
+    sync_ms: 9.0,
+    line_ms: 150.0,
+    first_sync_ms: 0.0,
+    start_sync_ms: 0.0,
+    scans: &[
+        scan(Channel::Luma, 12.0, 88.0),
+        scan(Channel::AlternateDiff, 106.0, 44.0),
+    ],
+};
+
+const ROBOT72: Timing = Timing {
+    sync_ms: 9.0,
+    line_ms: 300.0,
+    first_sync_ms: 0.0,
+    start_sync_ms: 0.0,
+    scans: &[
+        scan(Channel::Luma, 12.0, 138.0),
+        scan(Channel::RedDiff, 156.0, 69.0),
+        scan(Channel::BlueDiff, 231.0, 69.0),
+    ],
+};
+
+impl Mode {
+    pub fn from_vis(code: u8) -> Option<Self> {
+        MODES.into_iter().find(|m| m.vis() == code)
+    }
+
+    /// The 7-bit VIS code.
+    pub fn vis(self) -> u8 {
+        match self {
+            Self::Martin1 => 44,
+            Self::Martin2 => 40,
+            Self::Scottie1 => 60,
+            Self::Scottie2 => 56,
+            Self::Robot36 => 8,
+            Self::Robot72 => 12,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Martin1 => "Martin 1",
+            Self::Martin2 => "Martin 2",
+            Self::Scottie1 => "Scottie 1",
+            Self::Scottie2 => "Scottie 2",
+            Self::Robot36 => "Robot 36",
+            Self::Robot72 => "Robot 72",
+        }
+    }
+
+    pub fn width(self) -> usize {
+        320
+    }
+
+    pub fn height(self) -> usize {
+        match self {
+            Self::Robot36 | Self::Robot72 => 240,
+            _ => 256,
+        }
+    }
+
+    pub(crate) fn timing(self) -> &'static Timing {
+        match self {
+            Self::Martin1 => &MARTIN1,
+            Self::Martin2 => &MARTIN2,
+            Self::Scottie1 => &SCOTTIE1,
+            Self::Scottie2 => &SCOTTIE2,
+            Self::Robot36 => &ROBOT36,
+            Self::Robot72 => &ROBOT72,
+        }
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Pixel level (0-255) of a tone.
+pub(crate) fn level(hz: f32) -> f32 {
+    ((hz - BLACK_HZ) / (WHITE_HZ - BLACK_HZ) * 255.0).clamp(0.0, 255.0)
+}
+
+pub(crate) fn tone(level: f32) -> f32 {
+    BLACK_HZ + level / 255.0 * (WHITE_HZ - BLACK_HZ)
+}
+
+// Robot colour as other SSTV software decodes it: full-range luma and colour differences
+// centred on mid-scale.
+pub(crate) fn ycc_to_rgb(y: f32, red_diff: f32, blue_diff: f32) -> [u8; 3] {
+    let (cr, cb) = (red_diff - 127.5, blue_diff - 127.5);
+    [y + 1.40 * cr, y - 0.71 * cr - 0.33 * cb, y + 1.78 * cb]
+        .map(|v| v.round().clamp(0.0, 255.0) as u8)
+}
+
+pub(crate) fn rgb_to_ycc([r, g, b]: [u8; 3]) -> [f32; 3] {
+    let (r, g, b) = (f32::from(r), f32::from(g), f32::from(b));
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    [
+        y,
+        (127.5 + (r - y) / 1.40).clamp(0.0, 255.0),
+        (127.5 + (b - y) / 1.78).clamp(0.0, 255.0),
+    ]
+}
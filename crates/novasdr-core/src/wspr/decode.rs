@@ -0,0 +1,305 @@
+use super::{
+    encode_tones, fano, interleave_order, unpack, Message, NOMINAL_START_S, NUM_SYMBOLS, NUM_TONES,
+    SAMPLE_RATE, SYMBOL_SAMPLES, SYNC, TONE_SPACING_HZ,
+};
+use realfft::RealFftPlanner;
+use std::collections::HashSet;
+
+/// Spectrogram steps per symbol.
+const TIME_OSR: usize = 4;
+/// Spectrogram bins per tone spacing.
+const FREQ_OSR: usize = 2;
+const POWER_FLOOR: f32 = 1e-12;
+/// RMS the soft symbols are normalised to before sequential decoding.
+const LLR_RMS: f32 = 3.0;
+/// Reference noise bandwidth of the WSJT-X SNR convention.
+const SNR_REFERENCE_BW_HZ: f32 = 2500.0;
+/// Earliest frame start searched, relative to the nominal start.
+const MIN_DT_S: f32 = -1.0;
+/// Centre of the linear drift ramp, in symbols.
+const MID_SYMBOL: f32 = NUM_SYMBOLS as f32 / 2.0;
+/// Offset of the signal centre from the lowest tone, in tone spacings.
+const CENTRE_TONE: f32 = (NUM_TONES - 1) as f32 / 2.0;
+
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    /// Lowest signal centre frequency searched.
+    pub min_freq_hz: f32,
+    /// Highest signal centre frequency searched.
+    pub max_freq_hz: f32,
+    /// Latest frame start searched, relative to the nominal start.
+    pub max_dt_s: f32,
+    /// Largest frequency change over the transmission searched, in either direction.
+    pub max_drift_hz: f32,
+    /// Minimum normalised sync correlation (0..1) for a candidate.
+    pub min_sync_score: f32,
+    pub max_candidates: usize,
+    pub max_cycles_per_bit: usize,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            min_freq_hz: 1400.0,
+            max_freq_hz: 1600.0,
+            max_dt_s: 3.0,
+            max_drift_hz: 2.0,
+            min_sync_score: 0.2,
+            max_candidates: 40,
+            max_cycles_per_bit: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    pub message: Message,
+    /// Audio frequency of the signal centre (between tones 1 and 2) in Hz.
+    pub freq_hz: f32,
+    /// Frame start relative to the nominal start in seconds.
+    pub dt_s: f32,
+    /// Frequency change over the transmission in Hz.
+    pub drift_hz: f32,
+    /// Signal-to-noise ratio in the 2500 Hz WSJT-X reference bandwidth.
+    pub snr_db: i32,
+}
+
+struct Spectrogram {
+    steps: usize,
+    /// FFT bin of local bin 0.
+    first_bin: usize,
+    bins: usize,
+    power: Vec<f32>,
+    /// Median power over the kept region, used as the noise reference.
+    noise: f32,
+}
+
+impl Spectrogram {
+    fn new(samples: &[f32], first_bin: usize, bins: usize) -> Self {
+        let nfft = SYMBOL_SAMPLES * FREQ_OSR;
+        let hop = SYMBOL_SAMPLES / TIME_OSR;
+        let steps = if samples.len() >= SYMBOL_SAMPLES {
+            (samples.len() - SYMBOL_SAMPLES) / hop + 1
+        } else {
+            0
+        };
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(nfft);
+        let mut input = fft.make_input_vec();
+        let mut output = fft.make_output_vec();
+        let mut scratch = fft.make_scratch_vec();
+
+        let mut power = vec![0f32; steps * bins];
+        for t in 0..steps {
+            let frame = &samples[t * hop..t * hop + SYMBOL_SAMPLES];
+            // A rectangular window one symbol long is the matched filter for each tone and keeps
+            // adjacent tones orthogonal. The transform uses its input as scratch, so the zero
+            // padding is restored each time.
+            input[..SYMBOL_SAMPLES].copy_from_slice(frame);
+            input[SYMBOL_SAMPLES..].fill(0.0);
+            if fft
+                .process_with_scratch(&mut input, &mut output, &mut scratch)
+                .is_err()
+            {
+                continue;
+            }
+            for (dst, c) in power[t * bins..(t + 1) * bins]
+                .iter_mut()
+                .zip(output.iter().skip(first_bin))
+            {
+                *dst = c.norm_sqr();
+            }
+        }
+
+        let mut sorted = power.clone();
+        sorted.sort_unstable_by(|a, b| a.total_cmp(b));
+        let noise = sorted
+            .get(sorted.len() / 2)
+            .copied()
+            .unwrap_or(POWER_FLOOR)
+            .max(POWER_FLOOR);
+
+        Self {
+            steps,
+            first_bin,
+            bins,
+            power,
+            noise,
+        }
+    }
+
+    fn at(&self, t: usize, k: usize) -> f32 {
+        self.power[t * self.bins + k]
+    }
+
+    /// Local bin of the lowest tone of symbol `s` for a drift of `drift` bins.
+    fn tone0_bin(k0: usize, drift: isize, s: usize) -> usize {
+        let offset = (drift as f32 * (s as f32 - MID_SYMBOL) / NUM_SYMBOLS as f32).round();
+        (k0 as isize + offset as isize) as usize
+    }
+
+    fn tones(&self, t0: usize, k0: usize, drift: isize, s: usize) -> [f32; NUM_TONES] {
+        let t = t0 + s * TIME_OSR;
+        let k = Self::tone0_bin(k0, drift, s);
+        std::array::from_fn(|tone| self.at(t, k + tone * FREQ_OSR))
+    }
+
+    /// Correlation of the tone powers with the sync vector, normalised to the total power.
+    fn sync_score(&self, t0: usize, k0: usize, drift: isize) -> f32 {
+        let mut num = 0f32;
+        let mut den = 0f32;
+        for (s, &sync) in SYNC.iter().enumerate() {
+            let p = self.tones(t0, k0, drift, s);
+            let odd = (p[1] + p[3]) - (p[0] + p[2]);
+            num += if sync == 1 { odd } else { -odd };
+            den += p.iter().sum::<f32>();
+        }
+        num / den.max(POWER_FLOOR)
+    }
+
+    /// Soft data bits in code-bit order; positive favours 1.
+    fn llr(&self, t0: usize, k0: usize, drift: isize) -> [f32; fano::CODED_BITS] {
+        let mut diff = [0f32; NUM_SYMBOLS];
+        for (s, d) in diff.iter_mut().enumerate() {
+            let p = self.tones(t0, k0, drift, s);
+            let sync = SYNC[s] as usize;
+            *d = p[2 + sync].sqrt() - p[sync].sqrt();
+        }
+        let rms = (diff.iter().map(|d| d * d).sum::<f32>() / NUM_SYMBOLS as f32)
+            .sqrt()
+            .max(POWER_FLOOR);
+        let mut llr = [0f32; fano::CODED_BITS];
+        for (l, pos) in llr.iter_mut().zip(interleave_order()) {
+            *l = diff[pos] / rms * LLR_RMS;
+        }
+        llr
+    }
+
+    fn snr_db(&self, t0: usize, k0: usize, drift: isize, tones: &[u8]) -> i32 {
+        let signal = tones
+            .iter()
+            .enumerate()
+            .map(|(s, &tone)| self.tones(t0, k0, drift, s)[tone as usize] - self.noise)
+            .sum::<f32>()
+            / tones.len() as f32;
+        let noise_ref = self.noise * SNR_REFERENCE_BW_HZ / TONE_SPACING_HZ;
+        (10.0 * (signal.max(POWER_FLOOR) / noise_ref).log10()).round() as i32
+    }
+}
+
+struct Candidate {
+    score: f32,
+    t0: usize,
+    k0: usize,
+    drift: isize,
+}
+
+/// Decodes all type 1 WSPR messages in a 2-minute slot sampled at [`SAMPLE_RATE`] whose first
+/// sample is the even-minute boundary.
+pub fn decode(samples: &[f32], opts: &DecodeOptions) -> Vec<Decoded> {
+    let bin_hz = SAMPLE_RATE as f32 / (SYMBOL_SAMPLES * FREQ_OSR) as f32;
+    let hop = (SYMBOL_SAMPLES / TIME_OSR) as f32;
+    let max_drift = (opts.max_drift_hz / bin_hz).round().max(0.0) as usize;
+    let margin = max_drift / 2 + 1;
+    let centre_offset = CENTRE_TONE * TONE_SPACING_HZ;
+    let lo_bin = ((opts.min_freq_hz - centre_offset) / bin_hz)
+        .floor()
+        .max(0.0) as usize;
+    let hi_bin = ((opts.max_freq_hz - centre_offset) / bin_hz)
+        .ceil()
+        .max(0.0) as usize;
+    if hi_bin < lo_bin {
+        return Vec::new();
+    }
+    let first_bin = lo_bin.saturating_sub(margin);
+    let last_bin =
+        (hi_bin + (NUM_TONES - 1) * FREQ_OSR + margin).min(SYMBOL_SAMPLES * FREQ_OSR / 2);
+    if last_bin <= first_bin {
+        return Vec::new();
+    }
+    let spec = Spectrogram::new(samples, first_bin, last_bin - first_bin + 1);
+
+    let frame_steps = (NUM_SYMBOLS - 1) * TIME_OSR;
+    if spec.steps <= frame_steps {
+        return Vec::new();
+    }
+    let t_min = ((NOMINAL_START_S + MIN_DT_S) * SAMPLE_RATE as f32 / hop).max(0.0) as usize;
+    let t_max = (((NOMINAL_START_S + opts.max_dt_s) * SAMPLE_RATE as f32 / hop).max(0.0) as usize)
+        .min(spec.steps - 1 - frame_steps);
+    if t_max < t_min {
+        return Vec::new();
+    }
+    // Keep every drifted tone inside the spectrogram.
+    let k_min = (lo_bin - first_bin).max(margin);
+    let k_max = (hi_bin - first_bin).min(
+        spec.bins
+            .saturating_sub(1 + (NUM_TONES - 1) * FREQ_OSR + margin),
+    );
+    if k_max < k_min {
+        return Vec::new();
+    }
+
+    // Best alignment for every frequency, then local maxima across frequency.
+    let best: Vec<Candidate> = (k_min..=k_max)
+        .map(|k0| {
+            let mut best = Candidate {
+                score: f32::NEG_INFINITY,
+                t0: t_min,
+                k0,
+                drift: 0,
+            };
+            for drift in -(max_drift as isize)..=max_drift as isize {
+                for t0 in t_min..=t_max {
+                    let score = spec.sync_score(t0, k0, drift);
+                    if score > best.score {
+                        best = Candidate {
+                            score,
+                            t0,
+                            k0,
+                            drift,
+                        };
+                    }
+                }
+            }
+            best
+        })
+        .collect();
+    let mut candidates: Vec<&Candidate> = best
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| {
+            c.score >= opts.min_sync_score
+                && best[i.saturating_sub(FREQ_OSR)..(i + FREQ_OSR + 1).min(best.len())]
+                    .iter()
+                    .all(|o| o.score <= c.score)
+        })
+        .map(|(_, c)| c)
+        .collect();
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(opts.max_candidates);
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for c in candidates {
+        let llr = spec.llr(c.t0, c.k0, c.drift);
+        let Some(payload) = fano::decode(&llr, opts.max_cycles_per_bit) else {
+            continue;
+        };
+        let Some(message) = unpack(&payload) else {
+            continue;
+        };
+        if !seen.insert(message.text()) {
+            continue;
+        }
+        let tones = encode_tones(&payload);
+        out.push(Decoded {
+            snr_db: spec.snr_db(c.t0, c.k0, c.drift, &tones),
+            freq_hz: (spec.first_bin + c.k0) as f32 * bin_hz + centre_offset,
+            dt_s: c.t0 as f32 * hop / SAMPLE_RATE as f32 - NOMINAL_START_S,
+            drift_hz: c.drift as f32 * bin_hz,
+            message,
+        });
+    }
+    out
+}
@@ -0,0 +1,28 @@
+use super::{
+    fano, interleave_order, NUM_SYMBOLS, SAMPLE_RATE, SYMBOL_SAMPLES, SYNC, TONE_SPACING_HZ,
+};
+
+/// Maps a 50-bit payload to the 162 channel tones (0..=3).
+pub fn encode_tones(payload: &[u8; 7]) -> [u8; NUM_SYMBOLS] {
+    let coded = fano::encode(payload);
+    let mut tones = SYNC;
+    for (&bit, pos) in coded.iter().zip(interleave_order()) {
+        tones[pos] += 2 * bit;
+    }
+    tones
+}
+
+/// Renders a tone sequence as continuous-phase FSK at [`SAMPLE_RATE`], starting at `base_hz`.
+pub fn synthesize(tones: &[u8], base_hz: f32, amplitude: f32) -> Vec<f32> {
+    let mut out = Vec::with_capacity(tones.len() * SYMBOL_SAMPLES);
+    let mut phase = 0f64;
+    for &tone in tones {
+        let freq = (base_hz + tone as f32 * TONE_SPACING_HZ) as f64;
+        let step = std::f64::consts::TAU * freq / SAMPLE_RATE as f64;
+        for _ in 0..SYMBOL_SAMPLES {
+            out.push(amplitude * phase.sin() as f32);
+            phase = (phase + step) % std::f64::consts::TAU;
+        }
+    }
+    out
+}
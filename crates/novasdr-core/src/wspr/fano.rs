@@ -0,0 +1,154 @@
+/// Generator polynomials of the K=32, rate 1/2 convolutional code.
+const POLY1: u32 = 0xf2d0_5351;
+const POLY2: u32 = 0xe461_3c47;
+/// Zero bits appended to flush the encoder.
+const TAIL_BITS: usize = 31;
+
+pub const DATA_BITS: usize = 50;
+pub const CODED_BITS: usize = 2 * (DATA_BITS + TAIL_BITS);
+
+/// Bit metrics are kept as integers in tenths of a bit.
+const METRIC_SCALE: f32 = 10.0;
+/// Code rate subtracted from every bit metric.
+const METRIC_BIAS: f32 = 0.5;
+const MAX_LLR: f32 = 20.0;
+/// Threshold step of the Fano search, in metric units.
+const DELTA: i64 = 60;
+
+fn parity(x: u32) -> usize {
+    (x.count_ones() & 1) as usize
+}
+
+/// Both code bits produced for an encoder state, first bit in the high position.
+fn branch_symbol(state: u32) -> usize {
+    (parity(state & POLY1) << 1) | parity(state & POLY2)
+}
+
+fn data_bit(data: &[u8; 7], i: usize) -> u32 {
+    if i < DATA_BITS {
+        ((data[i / 8] >> (7 - i % 8)) & 1) as u32
+    } else {
+        0
+    }
+}
+
+/// Encodes the 50 payload bits (MSB first) into code bits in generation order.
+pub fn encode(data: &[u8; 7]) -> [u8; CODED_BITS] {
+    let mut out = [0u8; CODED_BITS];
+    let mut state = 0u32;
+    for (i, pair) in out.chunks_exact_mut(2).enumerate() {
+        state = (state << 1) | data_bit(data, i);
+        let sym = branch_symbol(state);
+        pair[0] = (sym >> 1) as u8;
+        pair[1] = (sym & 1) as u8;
+    }
+    out
+}
+
+/// Fano metric of receiving a bit with log-likelihood ratio `llr` (positive favours 1).
+fn bit_metric(llr: f32, bit: usize) -> i32 {
+    let l = llr.clamp(-MAX_LLR, MAX_LLR);
+    let l = if bit == 1 { l } else { -l };
+    let m = 1.0 - (1.0 + (-l).exp()).log2() - METRIC_BIAS;
+    (m * METRIC_SCALE).round() as i32
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Node {
+    state: u32,
+    gamma: i64,
+    metrics: [i32; 4],
+    /// Branch metrics sorted best first.
+    tm: [i32; 2],
+    /// Branch currently being explored (0 = best).
+    i: usize,
+}
+
+impl Node {
+    fn sort_branches(&mut self, in_tail: bool) {
+        let lsym = branch_symbol(self.state);
+        let m0 = self.metrics[lsym];
+        if in_tail {
+            // The tail is all zeroes; the 1-branch is never explored.
+            self.tm[0] = m0;
+        } else {
+            // Both polynomials have their low bit set, so the 1-branch emits the complement.
+            let m1 = self.metrics[3 ^ lsym];
+            if m0 > m1 {
+                self.tm = [m0, m1];
+            } else {
+                self.tm = [m1, m0];
+                self.state |= 1;
+            }
+        }
+        self.i = 0;
+    }
+}
+
+/// Sequential (Fano) decoding of code bits in generation order. Returns the 50 payload bits
+/// packed MSB first, or `None` if the search exceeds `max_cycles_per_bit` per decoded bit.
+pub fn decode(llr: &[f32; CODED_BITS], max_cycles_per_bit: usize) -> Option<[u8; 7]> {
+    const NBITS: usize = DATA_BITS + TAIL_BITS;
+    let mut nodes = [Node::default(); NBITS];
+    for (node, pair) in nodes.iter_mut().zip(llr.chunks_exact(2)) {
+        for (sym, m) in node.metrics.iter_mut().enumerate() {
+            *m = bit_metric(pair[0], sym >> 1) + bit_metric(pair[1], sym & 1);
+        }
+    }
+
+    nodes[0].sort_branches(false);
+    let mut np = 0usize;
+    let mut threshold = 0i64;
+    let max_cycles = max_cycles_per_bit.saturating_mul(NBITS);
+    let mut cycles = 0usize;
+    loop {
+        cycles += 1;
+        if cycles > max_cycles {
+            return None;
+        }
+        let node = nodes[np];
+        let ngamma = node.gamma + node.tm[node.i] as i64;
+        if ngamma >= threshold {
+            if node.gamma < threshold + DELTA {
+                // First visit: tighten the threshold as far as the new metric allows.
+                while ngamma >= threshold + DELTA {
+                    threshold += DELTA;
+                }
+            }
+            if np + 1 == NBITS {
+                break;
+            }
+            np += 1;
+            nodes[np].gamma = ngamma;
+            nodes[np].state = node.state << 1;
+            nodes[np].sort_branches(np >= DATA_BITS);
+            continue;
+        }
+
+        loop {
+            if np == 0 || nodes[np - 1].gamma < threshold {
+                // Cannot move back either: relax the threshold and retry the best branch.
+                threshold -= DELTA;
+                if nodes[np].i != 0 {
+                    nodes[np].i = 0;
+                    nodes[np].state ^= 1;
+                }
+                break;
+            }
+            np -= 1;
+            if np < DATA_BITS && nodes[np].i != 1 {
+                nodes[np].i += 1;
+                nodes[np].state ^= 1;
+                break;
+            }
+        }
+    }
+
+    let mut data = [0u8; 7];
+    for (j, byte) in data.iter_mut().enumerate() {
+        *byte = nodes[8 * j + 7].state as u8;
+    }
+    // Only the top two bits of the last byte are payload.
+    data[6] &= 0xc0;
+    Some(data)
+}
@@ -0,0 +1,152 @@
+/// Callsign alphabet; positions 10.. double as the letters-or-space suffix alphabet.
+const ALPHABET: &[u8; 37] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ ";
+const CALLSIGN_LEN: usize = 6;
+const SUFFIX_VALUES: u32 = 27;
+/// Number of distinct packed callsigns; larger values mark compound-call messages.
+const CALLSIGN_VALUES: u32 = 37 * 36 * 10 * SUFFIX_VALUES * SUFFIX_VALUES * SUFFIX_VALUES;
+const LOCATOR_FIELDS: u32 = 18;
+const MAX_POWER_DBM: i32 = 60;
+/// Offset added to the power field; type 1 messages carry 0..=60 dBm.
+const POWER_OFFSET: i32 = 64;
+
+/// A decoded type 1 WSPR message: callsign, 4-character locator and transmit power.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub callsign: String,
+    pub locator: String,
+    pub power_dbm: i32,
+}
+
+impl Message {
+    /// Human-readable text in WSJT-X notation.
+    pub fn text(&self) -> String {
+        format!("{} {} {}", self.callsign, self.locator, self.power_dbm)
+    }
+}
+
+fn alphabet_index(c: u8) -> Option<u32> {
+    ALPHABET.iter().position(|&a| a == c).map(|i| i as u32)
+}
+
+/// Type 1 messages only carry power levels ending in 0, 3 or 7.
+fn valid_power(dbm: i32) -> bool {
+    (0..=MAX_POWER_DBM).contains(&dbm) && matches!(dbm % 10, 0 | 3 | 7)
+}
+
+fn pack_callsign(callsign: &str) -> Option<u32> {
+    let call = callsign.trim().to_ascii_uppercase();
+    let bytes = call.as_bytes();
+    // The third character must be the call area digit; short prefixes are padded with a space.
+    let mut padded: Vec<u8> = match bytes {
+        [_, _, d, ..] if d.is_ascii_digit() => bytes.to_vec(),
+        [_, d, ..] if d.is_ascii_digit() => {
+            std::iter::once(b' ').chain(bytes.iter().copied()).collect()
+        }
+        _ => return None,
+    };
+    if padded.len() > CALLSIGN_LEN {
+        return None;
+    }
+    padded.resize(CALLSIGN_LEN, b' ');
+
+    let c0 = alphabet_index(padded[0])?;
+    let c1 = alphabet_index(padded[1]).filter(|&v| v < 36)?;
+    let c2 = alphabet_index(padded[2]).filter(|&v| v < 10)?;
+    let mut n = (c0 * 36 + c1) * 10 + c2;
+    for &c in &padded[3..] {
+        let v = alphabet_index(c).filter(|&v| v >= 10)?;
+        n = n * SUFFIX_VALUES + (v - 10);
+    }
+    Some(n)
+}
+
+fn unpack_callsign(mut n: u32) -> Option<String> {
+    if n >= CALLSIGN_VALUES {
+        return None;
+    }
+    let mut out = [b' '; CALLSIGN_LEN];
+    for c in out[3..].iter_mut().rev() {
+        *c = ALPHABET[(10 + n % SUFFIX_VALUES) as usize];
+        n /= SUFFIX_VALUES;
+    }
+    out[2] = ALPHABET[(n % 10) as usize];
+    n /= 10;
+    out[1] = ALPHABET[(n % 36) as usize];
+    out[0] = ALPHABET[(n / 36) as usize];
+    let call = std::str::from_utf8(&out).ok()?.trim().to_string();
+    // A space inside the call means the value did not come from a real callsign.
+    (!call.contains(' ')).then_some(call)
+}
+
+fn pack_locator(locator: &str) -> Option<u32> {
+    let loc = locator.trim().to_ascii_uppercase();
+    let b = loc.as_bytes();
+    if b.len() != 4 {
+        return None;
+    }
+    let field = |c: u8| {
+        (b'A'..b'A' + LOCATOR_FIELDS as u8)
+            .contains(&c)
+            .then(|| (c - b'A') as u32)
+    };
+    let digit = |c: u8| c.is_ascii_digit().then(|| (c - b'0') as u32);
+    let (a, bb, c, d) = (field(b[0])?, field(b[1])?, digit(b[2])?, digit(b[3])?);
+    Some((179 - 10 * a - c) * 180 + 10 * bb + d)
+}
+
+fn unpack_locator(m: u32) -> Option<String> {
+    let row = 179u32.checked_sub(m / 180)?;
+    let col = m % 180;
+    let (a, c) = (row / 10, row % 10);
+    let (b, d) = (col / 10, col % 10);
+    if a >= LOCATOR_FIELDS || b >= LOCATOR_FIELDS {
+        return None;
+    }
+    Some(format!(
+        "{}{}{}{}",
+        (b'A' + a as u8) as char,
+        (b'A' + b as u8) as char,
+        c,
+        d
+    ))
+}
+
+/// Packs a type 1 message into 50 bits, MSB first.
+pub fn pack(callsign: &str, locator: &str, power_dbm: i32) -> Option<[u8; 7]> {
+    if !valid_power(power_dbm) {
+        return None;
+    }
+    let n = pack_callsign(callsign)?;
+    let m = pack_locator(locator)? * 128 + (power_dbm + POWER_OFFSET) as u32;
+    Some([
+        (n >> 20) as u8,
+        (n >> 12) as u8,
+        (n >> 4) as u8,
+        (((n & 0x0f) << 4) | ((m >> 18) & 0x0f)) as u8,
+        (m >> 10) as u8,
+        (m >> 2) as u8,
+        ((m & 0x03) << 6) as u8,
+    ])
+}
+
+/// Unpacks a type 1 message. Compound-callsign and 6-character locator messages (types 2 and 3)
+/// return `None`.
+pub fn unpack(data: &[u8; 7]) -> Option<Message> {
+    let n = ((data[0] as u32) << 20)
+        | ((data[1] as u32) << 12)
+        | ((data[2] as u32) << 4)
+        | ((data[3] as u32) >> 4);
+    let m = (((data[3] & 0x0f) as u32) << 18)
+        | ((data[4] as u32) << 10)
+        | ((data[5] as u32) << 2)
+        | ((data[6] as u32) >> 6);
+    let power_dbm = (m % 128) as i32 - POWER_OFFSET;
+    if !valid_power(power_dbm) {
+        return None;
+    }
+    Some(Message {
+        callsign: unpack_callsign(n)?,
+        locator: unpack_locator(m / 128)?,
+        power_dbm,
+    })
+}
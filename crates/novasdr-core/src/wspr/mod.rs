@@ -0,0 +1,40 @@
+mod decode;
+mod encode;
+pub mod fano;
+pub mod message;
+
+pub use decode::{decode, DecodeOptions, Decoded};
+pub use encode::{encode_tones, synthesize};
+pub use message::{pack, unpack, Message};
+
+/// Sample rate the decoder and encoder operate at.
+pub const SAMPLE_RATE: u32 = 12_000;
+/// Length of a transmit/receive slot in milliseconds; slots start on even UTC minutes.
+pub const SLOT_MS: u64 = 120_000;
+/// Samples per symbol at [`SAMPLE_RATE`] (about 683 ms).
+pub const SYMBOL_SAMPLES: usize = 8192;
+pub const NUM_SYMBOLS: usize = 162;
+pub const NUM_TONES: usize = 4;
+pub const TONE_SPACING_HZ: f32 = SAMPLE_RATE as f32 / SYMBOL_SAMPLES as f32;
+/// Nominal start of the first symbol after the slot boundary.
+pub const NOMINAL_START_S: f32 = 1.0;
+
+/// Pseudo-random sync vector carried in the low bit of every channel symbol.
+const SYNC: [u8; NUM_SYMBOLS] = [
+    1, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1, 0, //
+    0, 1, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 0, 1, //
+    0, 0, 0, 0, 0, 0, 1, 0, 1, 1, 0, 0, 1, 1, 0, 1, 0, 0, 0, 1, //
+    1, 0, 1, 0, 0, 0, 0, 1, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 0, 1, //
+    0, 0, 1, 0, 1, 1, 0, 0, 0, 1, 1, 0, 1, 0, 1, 0, 0, 0, 1, 0, //
+    0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 1, 1, 1, 0, 1, 1, 0, 0, 1, 1, //
+    0, 1, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 1, 1, //
+    0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 0, 1, 1, 0, 0, 0, 1, 1, 0, //
+    0, 0,
+];
+
+/// Channel symbol that carries each code bit: the bit-reversal interleaver over 8-bit indices.
+fn interleave_order() -> impl Iterator<Item = usize> {
+    (0u8..=255)
+        .map(|i| i.reverse_bits() as usize)
+        .filter(|&j| j < NUM_SYMBOLS)
+}
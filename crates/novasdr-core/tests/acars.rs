@@ -0,0 +1,98 @@
+use novasdr_core::acars::{encode, Decoder, Message};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Uniform noise against the unit tones, about 11 dB below them across a 6 kHz audio band.
+const NOISE: f32 = 0.35;
+
+fn downlink() -> Message {
+    Message {
+        mode: '2',
+        registration: "N123AB".to_string(),
+        ack: None,
+        label: "H1".to_string(),
+        block_id: '4',
+        message_number: Some("M01A".to_string()),
+        flight: Some("UA0123".to_string()),
+        text: "#DFB POS N40123W073456,FL350".to_string(),
+        more: false,
+    }
+}
+
+fn uplink() -> Message {
+    Message {
+        mode: '2',
+        registration: "G-ABCD".to_string(),
+        ack: Some('4'),
+        label: "_\u{7f}".to_string(),
+        block_id: 'B',
+        message_number: None,
+        flight: None,
+        text: String::new(),
+        more: false,
+    }
+}
+
+/// `audio` between stretches of noise, decoded 20 ms at a time.
+fn decode(audio: &[f32], rate: f64, rng: &mut StdRng) -> Vec<Message> {
+    let pad = vec![0.0; (rate * 0.3) as usize];
+    let audio: Vec<f32> = pad
+        .iter()
+        .chain(audio)
+        .chain(&pad)
+        .map(|&x| x + rng.gen_range(-NOISE..NOISE))
+        .collect();
+    let mut decoder = Decoder::new(rate);
+    let mut messages = Vec::new();
+    for chunk in audio.chunks((rate / 50.0) as usize) {
+        decoder.process(chunk);
+        messages.extend(std::iter::from_fn(|| decoder.pop_message()));
+    }
+    messages
+}
+
+#[test]
+fn downlink_is_decoded() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let message = downlink();
+    let messages = decode(&encode(&message, 12_000.0), 12_000.0, &mut rng);
+    assert_eq!(messages, vec![message]);
+    assert!(messages[0].is_downlink());
+}
+
+#[test]
+fn blocks_are_decoded_at_any_sample_rate() {
+    for (seed, rate) in [(2, 11_025.0), (3, 16_000.0), (4, 48_000.0)] {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut audio = encode(&uplink(), rate);
+        audio.extend(vec![0.0; (rate * 0.05) as usize]);
+        audio.extend(encode(&downlink(), rate));
+        let messages = decode(&audio, rate, &mut rng);
+        assert_eq!(messages, vec![uplink(), downlink()], "{rate} Hz");
+    }
+}
+
+#[test]
+fn corrupted_blocks_are_dropped() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let rate = 12_000.0;
+    let sent = encode(&downlink(), rate);
+    let other = encode(
+        &Message {
+            text: "#DFB POS N40123W073457,FL350".to_string(),
+            ..downlink()
+        },
+        rate,
+    );
+    // The two differ in one character; take that part of the second.
+    let at = sent.iter().zip(&other).position(|(a, b)| a != b).unwrap();
+    let mut audio = sent.clone();
+    audio[at..at + 40].copy_from_slice(&other[at..at + 40]);
+    assert!(decode(&audio, rate, &mut rng).is_empty());
+    assert_eq!(decode(&sent, rate, &mut rng).len(), 1);
+}
+
+#[test]
+fn noise_decodes_nothing() {
+    let mut rng = StdRng::seed_from_u64(6);
+    assert!(decode(&vec![0.0; 12_000 * 30], 12_000.0, &mut rng).is_empty());
+}
@@ -0,0 +1,68 @@
+use flate2::read::ZlibDecoder;
+use novasdr_core::{codec::png, dsp::peaks::PeakDetector};
+use std::io::Read;
+
+fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    let mut out = Vec::new();
+    let mut pos = 8usize;
+    while pos < png.len() {
+        let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind: [u8; 4] = png[pos + 4..pos + 8].try_into().unwrap();
+        let data = png[pos + 8..pos + 8 + len].to_vec();
+        let crc = u32::from_be_bytes(png[pos + 8 + len..pos + 12 + len].try_into().unwrap());
+        assert_eq!(crc, crc32fast::hash(&png[pos + 4..pos + 8 + len]));
+        out.push((kind, data));
+        pos += 12 + len;
+    }
+    out
+}
+
+#[test]
+fn png_has_valid_chunks_and_pixels() {
+    let rgb: Vec<u8> = (0..2 * 3 * 3).map(|i| i as u8).collect();
+    let file = png::encode_rgb(3, 2, &rgb).unwrap();
+    assert!(file.starts_with(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']));
+
+    let chunks = chunks(&file);
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(k, _)| k).collect();
+    assert_eq!(kinds, vec![b"IHDR", b"IDAT", b"IEND"]);
+    assert_eq!(&chunks[0].1[..8], &[0, 0, 0, 3, 0, 0, 0, 2]);
+
+    let mut raw = Vec::new();
+    ZlibDecoder::new(&chunks[1].1[..])
+        .read_to_end(&mut raw)
+        .unwrap();
+    assert_eq!(raw.len(), 2 * (1 + 9));
+    assert_eq!(&raw[1..10], &rgb[..9]);
+    assert_eq!(&raw[11..20], &rgb[9..]);
+}
+
+#[test]
+fn png_rejects_mismatched_buffer() {
+    assert!(png::encode_rgb(4, 4, &[0u8; 10]).is_err());
+}
+
+#[test]
+fn peak_detector_fires_once_per_rising_edge() {
+    let quiet = vec![-100.0f32; 64];
+    let mut loud = quiet.clone();
+    loud[17] = -70.0;
+
+    let mut det = PeakDetector::new(20.0);
+    assert!(det.update(&quiet).is_none());
+    let peak = det.update(&loud).expect("peak");
+    assert_eq!(peak.bin, 17);
+    assert_eq!(peak.snr_db(), 30.0);
+    assert!(det.update(&loud).is_none());
+    assert!(det.is_active());
+
+    // Still inside the hysteresis band: stays latched.
+    let mut fading = quiet.clone();
+    fading[17] = -81.0;
+    assert!(det.update(&fading).is_none());
+    assert!(det.is_active());
+
+    assert!(det.update(&quiet).is_none());
+    assert!(!det.is_active());
+    assert!(det.update(&loud).is_some());
+}
@@ -0,0 +1,82 @@
+use novasdr_core::dsp::channelizer::{ChannelDdc, ChannelGrid, ChannelTuner};
+use novasdr_core::dsp::iq_extract::IqExtractor;
+use num_complex::Complex32;
+
+const BINS: usize = 4096;
+const SIZE: usize = 256;
+
+/// Noise in bins 950..1070, which every channel and listener bin below covers.
+fn noise_frame(seed: &mut u32) -> Vec<Complex32> {
+    let mut next = || {
+        *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (*seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+    };
+    (0..BINS)
+        .map(|i| {
+            let v = Complex32::new(next(), next());
+            if (950..1070).contains(&i) {
+                v
+            } else {
+                Complex32::default()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn tuned_channel_matches_a_ddc_on_the_listener_bin() {
+    let grid = ChannelGrid::new(SIZE);
+    let tuner = ChannelTuner::new(SIZE);
+    for is_real in [false, true] {
+        for mid in [1000i64, 1001, 1037, 962] {
+            let mut channel = ChannelDdc::new(grid, 1024);
+            let mut own = IqExtractor::new(SIZE, 1);
+            let mut seed = mid as u32;
+            let mut expected = Vec::new();
+            let mut tuned = vec![Complex32::default(); SIZE / 2];
+            for frame_num in 0..12u64 {
+                let bins = noise_frame(&mut seed);
+                expected.clear();
+                own.process(&bins, mid, is_real, frame_num, &mut expected);
+                let baseband = channel.baseband(&bins, is_real, frame_num).unwrap();
+                tuner.tune(baseband, mid - 1024, frame_num, &mut tuned);
+                for (i, (a, b)) in tuned.iter().zip(&expected).enumerate() {
+                    assert!(
+                        (a - b).norm() < 1e-3 * (1.0 + b.norm()),
+                        "real {is_real} mid {mid} frame {frame_num} sample {i}: {a} vs {b}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn channel_keeps_recent_frames_for_late_listeners() {
+    let mut channel = ChannelDdc::new(ChannelGrid::new(SIZE), 512);
+    let mut seed = 7;
+    let mut first = Vec::new();
+    for frame_num in 0..6u64 {
+        let bins = noise_frame(&mut seed);
+        let baseband = channel.baseband(&bins, false, frame_num).unwrap().to_vec();
+        if frame_num == 3 {
+            first = baseband;
+        }
+    }
+    // A listener behind gets what was computed for its frame, whatever bins it brings.
+    let late = channel.baseband(&[], false, 3).unwrap();
+    assert_eq!(late, first.as_slice());
+    assert!(channel.baseband(&[], false, 0).is_none());
+}
+
+#[test]
+fn grid_picks_a_channel_that_holds_the_window() {
+    let grid = ChannelGrid::new(SIZE);
+    // SSB-sized window just above the tuned bin: the nearest channel on the 64-bin grid.
+    assert_eq!(grid.centre_for(1003, 1030, 1000, 8), Some(1024));
+    assert_eq!(grid.centre_for(1060, 1100, 1080, 8), Some(1088));
+    // Too wide for any channel, or for the audio rate around the tuned bin.
+    assert_eq!(grid.centre_for(900, 1150, 1020, 8), None);
+    assert_eq!(grid.centre_for(1003, 1030, 880, 8), None);
+    assert_eq!(grid.centre_for(1030, 1030, 1030, 8), None);
+}
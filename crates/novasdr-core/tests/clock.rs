@@ -0,0 +1,112 @@
+use chrono::{DateTime, Duration, NaiveDateTime, NaiveTime, Utc, Weekday};
+use novasdr_core::clock::{DailyTime, Zone};
+
+fn at(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+fn daily(time: &str, zone: &str) -> DailyTime {
+    DailyTime {
+        time: NaiveTime::parse_from_str(time, "%H:%M").unwrap(),
+        zone: Zone::parse(zone).unwrap(),
+        days: None,
+    }
+}
+
+fn local(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+#[test]
+fn local_times_follow_daylight_saving() {
+    let berlin = Zone::parse("Europe/Berlin").unwrap();
+    // Clocks go forward on 2026-03-29 and back on 2026-10-25.
+    assert_eq!(
+        berlin.to_utc(local("2026-03-28 06:00")),
+        at("2026-03-28T05:00:00Z")
+    );
+    assert_eq!(
+        berlin.to_utc(local("2026-03-30 06:00")),
+        at("2026-03-30T04:00:00Z")
+    );
+    // 02:30 does not exist on the spring day and is read as 03:30 CEST.
+    assert_eq!(
+        berlin.to_utc(local("2026-03-29 02:30")),
+        at("2026-03-29T01:30:00Z")
+    );
+    // 02:30 happens twice on the autumn day; the first (CEST) one counts.
+    assert_eq!(
+        berlin.to_utc(local("2026-10-25 02:30")),
+        at("2026-10-25T00:30:00Z")
+    );
+    assert_eq!(
+        Zone::Utc.to_utc(local("2026-03-29 02:30")),
+        at("2026-03-29T02:30:00Z")
+    );
+    assert!(Zone::parse("Mars/Olympus_Mons").is_err());
+    assert_eq!(Zone::parse(" utc "), Ok(Zone::Utc));
+}
+
+#[test]
+fn repeated_hour_runs_once() {
+    let rule = daily("02:30", "Europe/Berlin");
+    let ten = Duration::minutes(10);
+    assert_eq!(
+        rule.running(ten, at("2026-10-25T00:35:00Z")),
+        Some(at("2026-10-25T00:30:00Z"))
+    );
+    // 02:35 CET, the second pass through the same wall-clock time.
+    assert_eq!(rule.running(ten, at("2026-10-25T01:35:00Z")), None);
+}
+
+#[test]
+fn day_long_occurrences_span_the_short_day() {
+    // Saturday 2026-03-28 23:59 CET; Sunday has 23 hours, so it ends at 00:59 on Monday.
+    let mut rule = daily("23:59", "Europe/Berlin");
+    rule.days = Some(vec![Weekday::Sat]);
+    let day = Duration::hours(24);
+    assert_eq!(
+        rule.running(day, at("2026-03-29T22:30:00Z")),
+        Some(at("2026-03-28T22:59:00Z"))
+    );
+    assert_eq!(rule.running(day, at("2026-03-29T23:00:00Z")), None);
+}
+
+#[test]
+fn weekdays_are_those_of_the_local_calendar() {
+    // Monday 08:00 in Auckland is Sunday evening UTC.
+    let mut rule = daily("08:00", "Pacific/Auckland");
+    rule.days = Some(vec![Weekday::Mon]);
+    let hour = Duration::hours(1);
+    assert_eq!(
+        rule.running(hour, at("2026-06-14T20:30:00Z")),
+        Some(at("2026-06-14T20:00:00Z"))
+    );
+    assert_eq!(rule.running(hour, at("2026-06-15T20:30:00Z")), None);
+}
+
+#[test]
+fn last_start_is_the_latest_occurrence_at_or_before_now() {
+    let rule = daily("06:30", "Europe/Berlin");
+    assert_eq!(
+        rule.last_start(at("2026-07-01T04:30:00Z")),
+        Some(at("2026-07-01T04:30:00Z"))
+    );
+    assert_eq!(
+        rule.last_start(at("2026-07-01T04:29:00Z")),
+        Some(at("2026-06-30T04:30:00Z"))
+    );
+    // Just after the autumn change, yesterday's start was still in summer time.
+    assert_eq!(
+        rule.last_start(at("2026-10-26T05:00:00Z")),
+        Some(at("2026-10-25T05:30:00Z"))
+    );
+
+    // A weekly time reaches back to its last weekday.
+    let mut weekly = daily("20:00", "UTC");
+    weekly.days = Some(vec![Weekday::Fri]);
+    assert_eq!(
+        weekly.last_start(at("2026-06-18T12:00:00Z")),
+        Some(at("2026-06-12T20:00:00Z"))
+    );
+}
@@ -1,4 +1,10 @@
-use novasdr_core::codec::{flac_stream::FlacStreamEncoder, zstd_stream::ZstdStreamEncoder};
+use novasdr_core::codec::{
+    flac_stream::FlacStreamEncoder,
+    ima_adpcm, lz4_block,
+    ogg_opus::{self, OggOpusWriter},
+    wav,
+    zstd_stream::{self, ZstdStreamEncoder},
+};
 use zstd_safe::{DCtx, InBuffer, OutBuffer};
 
 #[test]
@@ -8,6 +14,45 @@ fn flac_header_starts_with_magic() {
     assert!(header.starts_with(b"fLaC"));
 }
 
+#[test]
+fn ima_adpcm_roundtrips_a_tone_within_quantisation_error() {
+    let pcm: Vec<i16> = (0..961)
+        .map(|i| ((i as f32 * 0.05).sin() * 12_000.0) as i16)
+        .collect();
+    let block = ima_adpcm::encode_block_i16_mono(&pcm);
+    // Header plus one nibble per sample after the first.
+    assert_eq!(block.len(), 6 + 480);
+
+    let decoded = ima_adpcm::decode_block_i16_mono(&block).unwrap();
+    assert_eq!(decoded.len(), pcm.len());
+    assert_eq!(decoded[0], pcm[0]);
+    let worst = pcm
+        .iter()
+        .zip(&decoded)
+        .skip(16)
+        .map(|(&a, &b)| (i32::from(a) - i32::from(b)).abs())
+        .max()
+        .unwrap();
+    assert!(worst < 600, "worst error {worst}");
+
+    assert!(ima_adpcm::encode_block_i16_mono(&[]).is_empty());
+    assert!(ima_adpcm::decode_block_i16_mono(&block[..block.len() - 10]).is_err());
+}
+
+fn zstd_decompress(dctx: &mut DCtx, out: &[u8]) -> Vec<u8> {
+    let mut dst = vec![0u8; 1024];
+    let pos = {
+        let mut out_buf = OutBuffer::around(&mut dst[..]);
+        let mut in_buf = InBuffer::around(out);
+        while in_buf.pos < in_buf.src.len() && out_buf.pos() < out_buf.capacity() {
+            let _ = dctx.decompress_stream(&mut out_buf, &mut in_buf).unwrap();
+        }
+        out_buf.pos()
+    };
+    dst.truncate(pos);
+    dst
+}
+
 #[test]
 fn zstd_stream_flush_roundtrip() {
     let mut enc = ZstdStreamEncoder::new(3).unwrap();
@@ -27,3 +72,102 @@ fn zstd_stream_flush_roundtrip() {
     dst.truncate(pos);
     assert_eq!(&dst, input);
 }
+
+#[test]
+fn zstd_frames_are_reproducible_and_decode_as_one_stream() {
+    let (a, b) = (
+        b"first waterfall packet".to_vec(),
+        b"second packet".to_vec(),
+    );
+    let mut enc = ZstdStreamEncoder::new(3).unwrap();
+    let fa = enc.compress_frame(&a).unwrap();
+    let fb = enc.compress_frame(&b).unwrap();
+    // Earlier frames leave no trace: another encoder produces the same bytes.
+    assert_eq!(
+        ZstdStreamEncoder::new(3)
+            .unwrap()
+            .compress_frame(&b)
+            .unwrap(),
+        fb
+    );
+
+    let mut dctx = DCtx::create();
+    assert_eq!(zstd_decompress(&mut dctx, &fa), a);
+    assert_eq!(zstd_decompress(&mut dctx, &fb), b);
+}
+
+#[test]
+fn zstd_dictionary_trains_and_roundtrips() {
+    // Waterfall-like messages: a shared header and a noisy floor around the same level.
+    let mut seed = 1u32;
+    let samples: Vec<Vec<u8>> = (0..400)
+        .map(|i| {
+            let mut msg = b"frame_num l r data".to_vec();
+            msg.extend_from_slice(&(i as u32).to_le_bytes());
+            msg.extend((0..256).map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (200 + (seed >> 28)) as u8
+            }));
+            msg
+        })
+        .collect();
+    let dictionary = zstd_stream::train_dictionary(&samples, 4096).unwrap();
+    assert!(!dictionary.is_empty() && dictionary.len() <= 4096);
+    assert_ne!(zstd_stream::dictionary_id(&dictionary), 0);
+
+    let mut enc = ZstdStreamEncoder::with_dictionary(3, &dictionary).unwrap();
+    let out = enc.compress_flush(&samples[7]).unwrap();
+    let mut dctx = DCtx::create();
+    dctx.load_dictionary(&dictionary).unwrap();
+    assert_eq!(zstd_decompress(&mut dctx, &out), samples[7]);
+}
+
+#[test]
+fn lz4_packets_roundtrip_independently() {
+    let a = vec![7u8; 1000];
+    let b: Vec<u8> = (0..1000).map(|i| (i % 13) as u8).collect();
+    let (pa, pb) = (
+        lz4_block::compress_packet(&a),
+        lz4_block::compress_packet(&b),
+    );
+    assert!(pa.len() < a.len());
+    assert_eq!(&pa[..4], &1000u32.to_le_bytes());
+    assert_eq!(lz4_block::decompress_packet(&pb).unwrap(), b);
+    assert_eq!(lz4_block::decompress_packet(&pa).unwrap(), a);
+}
+
+#[test]
+fn ogg_pages_carry_checksums_sequence_and_48k_granules() {
+    assert_eq!(ogg_opus::crc32(b"123456789"), 0x89A1_897F);
+
+    let mut ogg = OggOpusWriter::new(0x1234, 12_000, 1);
+    let headers = ogg.headers("NovaSDR");
+    assert_eq!(&headers[..4], b"OggS");
+    assert_eq!(headers[5], 0x02, "first page starts the stream");
+    assert_eq!(&headers[28..36], b"OpusHead");
+
+    // 20 ms at 12 kHz is 960 samples at 48 kHz; a 300-byte packet needs two lacing values.
+    let page = ogg.packet(&[0xAA; 300], 240);
+    assert_eq!(&page[6..14], &960u64.to_le_bytes());
+    assert_eq!(&page[18..22], &2u32.to_le_bytes());
+    assert_eq!(&page[26..29], &[2, 255, 45]);
+    assert_eq!(page.len(), 29 + 300);
+    let mut unchecked = page.clone();
+    unchecked[22..26].fill(0);
+    assert_eq!(&page[22..26], &ogg_opus::crc32(&unchecked).to_le_bytes());
+}
+
+#[test]
+fn wav_header_carries_lengths_and_format() {
+    let h = wav::header(48_000, 2, 1_000);
+    assert_eq!(h.len(), wav::HEADER_LEN);
+    let u32_at = |i: usize| u32::from_le_bytes([h[i], h[i + 1], h[i + 2], h[i + 3]]);
+    assert_eq!(u32_at(4), 1_036);
+    assert_eq!(u32_at(24), 48_000);
+    assert_eq!(u32_at(28), 192_000);
+    assert_eq!(u32_at(40), 1_000);
+    assert_eq!(
+        &wav::streaming_header(12_000, 1)[40..],
+        &u32::MAX.to_le_bytes()
+    );
+}
@@ -10,22 +10,60 @@ fn websdr_register_url_default_is_present() {
         websdr,
         limits: novasdr_core::config::Limits::default(),
         updates: novasdr_core::config::Updates::default(),
+        admin: novasdr_core::config::Admin::default(),
+        pskreporter: novasdr_core::config::PskReporter::default(),
+        wsprnet: novasdr_core::config::Wsprnet::default(),
+        rbn: novasdr_core::config::Rbn::default(),
+        capture: novasdr_core::config::Capture::default(),
+        geoip: novasdr_core::config::GeoIp::default(),
+        export: novasdr_core::config::Export::default(),
+        freqdb: novasdr_core::config::FreqDb::default(),
+        accounts: novasdr_core::config::Accounts::default(),
+        rtl_tcp: novasdr_core::config::RtlTcp::default(),
+        crash_reports: novasdr_core::config::CrashReports::default(),
+        streams: novasdr_core::config::Streams::default(),
+        iq: novasdr_core::config::IqOutput::default(),
+        diversity: novasdr_core::config::Diversity::default(),
+        recordings: novasdr_core::config::Recordings::default(),
+        time_shift: novasdr_core::config::TimeShift::default(),
+        disk_guard: novasdr_core::config::DiskGuard::default(),
         receivers: vec![novasdr_core::config::ReceiverConfig {
             id: "rx0".to_string(),
             enabled: true,
             name: "rx0".to_string(),
+            maintenance: novasdr_core::config::ReceiverMaintenance::default(),
+            skimmer: novasdr_core::config::SkimmerConfig::default(),
+            capture_triggers: Vec::new(),
+            limits: Default::default(),
+            fft_tap: Default::default(),
+            acars: Default::default(),
+            replay: Default::default(),
+            channel_dump: Default::default(),
+            black_box: Default::default(),
+            tx: Default::default(),
+            udp_output: Default::default(),
+            band_schedule: Vec::new(),
+            accuracy: Default::default(),
             input: novasdr_core::config::ReceiverInput {
                 sps: 2_048_000,
                 frequency: 100_900_000,
                 signal: novasdr_core::config::SignalType::Iq,
+                decimation: 1,
                 fft_size: 131_072,
                 brightness_offset: 0,
                 audio_sps: 12_000,
                 waterfall_size: 1024,
+                waterfall_max_fps: 10.0,
                 waterfall_compression: novasdr_core::config::WaterfallCompression::Zstd,
                 audio_compression: novasdr_core::config::AudioCompression::Adpcm,
+                shared_channels: true,
                 smeter_offset: 0,
+                smeter_calibration: None,
+                dc_suppression: Default::default(),
+                window: Default::default(),
                 accelerator: novasdr_core::config::Accelerator::None,
+                accelerator_fallback: None,
+                vulkan_device: None,
                 driver: novasdr_core::config::InputDriver::Stdin {
                     format: novasdr_core::config::SampleFormat::U8,
                 },
@@ -41,3 +79,15 @@ fn websdr_register_url_default_is_present() {
         "https://sdr-list.xyz/api/update_websdr"
     );
 }
+
+#[test]
+fn waterfall_compression_names_roundtrip() {
+    use novasdr_core::config::WaterfallCompression;
+    for c in WaterfallCompression::ALL {
+        assert_eq!(WaterfallCompression::parse(c.as_str()), Some(c));
+        let from_json: WaterfallCompression =
+            serde_json::from_str(&format!("\"{}\"", c.as_str())).unwrap();
+        assert_eq!(from_json, c);
+    }
+    assert_eq!(WaterfallCompression::parse("brotli"), None);
+}
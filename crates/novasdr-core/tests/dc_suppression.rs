@@ -0,0 +1,76 @@
+use novasdr_core::config::{DcSuppression, DcSuppressionMode};
+use novasdr_core::dsp::dc_suppression::{suppress_dc, suppress_dc_quantized};
+use novasdr_core::dsp::fft::quantize_and_downsample_cpu;
+use num_complex::Complex32;
+
+const N: usize = 64;
+
+/// Flat noise floor of magnitude 1 with a strong spike at DC.
+fn spectrum_with_spike() -> Vec<Complex32> {
+    let mut s: Vec<Complex32> = (0..N)
+        .map(|i| Complex32::from_polar(1.0, i as f32 * 0.7))
+        .collect();
+    s[0] = Complex32::new(1000.0, 0.0);
+    s[1] = Complex32::new(30.0, 0.0);
+    s[N - 1] = Complex32::new(30.0, 0.0);
+    s
+}
+
+fn cfg(mode: DcSuppressionMode) -> DcSuppression {
+    DcSuppression { mode, bins: 2 }
+}
+
+#[test]
+fn off_leaves_spectrum_untouched() {
+    let mut s = spectrum_with_spike();
+    suppress_dc(&mut s, cfg(DcSuppressionMode::Off));
+    assert_eq!(s, spectrum_with_spike());
+}
+
+#[test]
+fn blank_zeroes_only_the_bins_around_dc() {
+    let mut s = spectrum_with_spike();
+    suppress_dc(&mut s, cfg(DcSuppressionMode::Blank));
+    for idx in [N - 2, N - 1, 0, 1, 2] {
+        assert_eq!(s[idx], Complex32::new(0.0, 0.0), "bin {idx}");
+    }
+    let orig = spectrum_with_spike();
+    assert_eq!(s[3], orig[3]);
+    assert_eq!(s[N - 3], orig[N - 3]);
+}
+
+#[test]
+fn interpolate_replaces_spike_with_neighbour_level() {
+    let mut s = spectrum_with_spike();
+    suppress_dc(&mut s, cfg(DcSuppressionMode::Interpolate));
+    for idx in [N - 2, N - 1, 0, 1, 2] {
+        assert!((s[idx].norm() - 1.0).abs() < 1e-4, "bin {idx}: {}", s[idx]);
+    }
+}
+
+#[test]
+fn quantized_patch_matches_cpu_quantizer_for_covered_columns() {
+    let base_idx = N / 2 + 1;
+    let levels = 3;
+    let size_log2 = N.ilog2() as i32;
+    let mode = cfg(DcSuppressionMode::Blank);
+
+    let mut suppressed = spectrum_with_spike();
+    suppress_dc(&mut suppressed, mode);
+    let (expected, offsets) =
+        quantize_and_downsample_cpu(&suppressed, N as f32, base_idx, levels, size_log2);
+
+    let (mut patched, _) = quantize_and_downsample_cpu(
+        &spectrum_with_spike(),
+        N as f32,
+        base_idx,
+        levels,
+        size_log2,
+    );
+    suppress_dc_quantized(&mut patched, &offsets, N, base_idx, mode);
+
+    // Level 0 is patched exactly; coarser columns straddling the edge may differ.
+    assert_eq!(patched[..N], expected[..N]);
+    let dc_col = N - base_idx;
+    assert_eq!(patched[offsets[1] + dc_col / 2], i8::MIN);
+}
@@ -0,0 +1,313 @@
+#![cfg(feature = "dv")]
+
+use novasdr_core::dv::{self, dmr, dstar, ysf, AmbeFrame, CallInfo, Decoder, Mode, VoiceFrame};
+use novasdr_core::protocol::ClientCommand;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::f32::consts::{PI, TAU};
+
+const RATE: f64 = 12_000.0;
+/// 4FSK deviation per unit of symbol level: ±3 is 1944 Hz.
+const DMR_DEVIATION_HZ: f32 = 648.0;
+const DSTAR_DEVIATION_HZ: f32 = 1_200.0;
+/// Transmitter clock error the symbol timing has to track.
+const BAUD_ERROR: f64 = 80e-6;
+
+/// Discriminator output, in radians per sample, for symbol levels with raised-cosine transitions.
+fn modulate(levels: &[f32], deviation_hz: f32, rng: &mut StdRng) -> Vec<f32> {
+    let baud = dv::SYMBOL_RATE * (1.0 + BAUD_ERROR);
+    let n = ((levels.len() - 1) as f64 * RATE / baud) as usize;
+    let scale = TAU * deviation_hz / RATE as f32;
+    (0..n)
+        .map(|i| {
+            let t = i as f64 * baud / RATE + 0.37;
+            let k = (t.floor() as usize).min(levels.len() - 2);
+            let f = (t - k as f64) as f32;
+            let w = (1.0 - (PI * f).cos()) / 2.0;
+            let level = levels[k] + (levels[k + 1] - levels[k]) * w;
+            level * scale + rng.gen_range(-0.05..0.05) * scale
+        })
+        .collect()
+}
+
+fn run(decoder: &mut Decoder, samples: &[f32]) -> (Vec<CallInfo>, Vec<VoiceFrame>) {
+    let mut updates = Vec::new();
+    let mut voice = Vec::new();
+    for chunk in samples.chunks(480) {
+        decoder.process(chunk);
+        updates.extend(std::iter::from_fn(|| decoder.take_update()));
+        voice.extend(std::iter::from_fn(|| decoder.pop_voice()));
+    }
+    (updates, voice)
+}
+
+fn random_frame(rng: &mut StdRng) -> AmbeFrame {
+    let mut frame = [[0u8; 24]; 4];
+    let used = [24, 23, 11, 14];
+    for (row, &len) in frame.iter_mut().zip(&used) {
+        for bit in &mut row[..len] {
+            *bit = rng.gen_range(0..2);
+        }
+    }
+    frame
+}
+
+/// One slot of a base station: CACH, our burst, then CACH and an idle burst on the other slot.
+fn slot(burst: &[u8; dmr::BURST_SYMBOLS], rng: &mut StdRng) -> Vec<f32> {
+    let mut dibits: Vec<u8> = (0..12).map(|_| rng.gen_range(0..4)).collect();
+    dibits.extend_from_slice(burst);
+    dibits.extend((0..12 + dmr::BURST_SYMBOLS).map(|_| rng.gen_range(0..4u8)));
+    dibits.into_iter().map(dmr::level).collect()
+}
+
+fn dmr_call(lc: &dmr::FullLc, superframes: usize, rng: &mut StdRng) -> (Vec<f32>, Vec<AmbeFrame>) {
+    let header = dmr::encode_lc_burst(
+        dmr::BS_DATA_SYNC,
+        7,
+        dmr::DATA_TYPE_VOICE_HEADER,
+        lc,
+        dmr::VOICE_HEADER_MASK,
+    );
+    let terminator = dmr::encode_lc_burst(
+        dmr::BS_DATA_SYNC,
+        7,
+        dmr::DATA_TYPE_TERMINATOR,
+        lc,
+        dmr::TERMINATOR_MASK,
+    );
+    let mut levels: Vec<f32> = (0..2_000)
+        .map(|_| dmr::level(rng.gen_range(0..4)))
+        .collect();
+    let mut sent = Vec::new();
+    for _ in 0..3 {
+        levels.extend(slot(&header, rng));
+    }
+    for _ in 0..superframes {
+        for burst in 0..6 {
+            let frames = [random_frame(rng), random_frame(rng), random_frame(rng)];
+            let sync = (burst == 0).then_some(dmr::BS_VOICE_SYNC);
+            levels.extend(slot(&dmr::encode_voice_burst(&frames, sync), rng));
+            sent.extend(frames);
+        }
+    }
+    for _ in 0..3 {
+        levels.extend(slot(&terminator, rng));
+    }
+    levels.extend((0..2_000).map(|_| dmr::level(rng.gen_range(0..4))));
+    (levels, sent)
+}
+
+#[test]
+fn dmr_call_yields_ids_and_every_voice_frame() {
+    let mut rng = StdRng::seed_from_u64(62);
+    let lc = dmr::FullLc::group_call(91, 2_345_678);
+    let (levels, sent) = dmr_call(&lc, 4, &mut rng);
+    let samples = modulate(&levels, DMR_DEVIATION_HZ, &mut rng);
+
+    let mut decoder = Decoder::new(RATE);
+    let (updates, voice) = run(&mut decoder, &samples);
+    let first = updates
+        .iter()
+        .find(|u| u.source.is_some())
+        .expect("call with ids");
+    assert_eq!(first.mode, Mode::Dmr);
+    assert!(first.active);
+    assert_eq!(first.color_code, Some(7));
+    assert_eq!(first.source, Some(2_345_678));
+    assert_eq!(first.destination, Some(91));
+    assert_eq!(first.group, Some(true));
+    let last = updates.last().unwrap();
+    assert!(!last.active);
+    let sent: Vec<VoiceFrame> = sent.into_iter().map(VoiceFrame::Dmr).collect();
+    assert_eq!(voice, sent);
+    assert_eq!(decoder.active(), None);
+}
+
+#[test]
+fn corrupted_link_control_is_ignored() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let lc = dmr::FullLc::group_call(91, 2_345_678);
+    let mut burst = dmr::encode_lc_burst(
+        dmr::BS_DATA_SYNC,
+        1,
+        dmr::DATA_TYPE_VOICE_HEADER,
+        &lc,
+        dmr::VOICE_HEADER_MASK,
+    );
+    for dibit in &mut burst[..10] {
+        *dibit ^= 3;
+    }
+    let mut levels: Vec<f32> = (0..500).map(|_| dmr::level(rng.gen_range(0..4))).collect();
+    levels.extend(slot(&burst, &mut rng));
+    levels.extend((0..500).map(|_| dmr::level(rng.gen_range(0..4))));
+    let samples = modulate(&levels, DMR_DEVIATION_HZ, &mut rng);
+
+    let mut decoder = Decoder::new(RATE);
+    let (updates, voice) = run(&mut decoder, &samples);
+    assert!(updates.is_empty());
+    assert!(voice.is_empty());
+}
+
+#[test]
+fn dstar_header_and_message_come_from_slow_data() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let header = dstar::Header {
+        flags: [0, 0, 0],
+        rpt2: "DB0XYZ G".into(),
+        rpt1: "DB0XYZ B".into(),
+        your: "CQCQCQ".into(),
+        my: "DL1ABC".into(),
+        suffix: "ID51".into(),
+    };
+    assert_eq!(
+        dstar::Header::from_bytes(&header.to_bytes()),
+        Some(header.clone())
+    );
+
+    let mut levels: Vec<f32> = (0..1_000)
+        .map(|_| rng.gen_range(0..2) as f32 * 2.0 - 1.0)
+        .collect();
+    let silence = [dstar::SILENCE; 4 * dstar::SUPERFRAME_FRAMES as usize];
+    levels.extend(
+        dstar::encode_stream(&header, "Hello from NovaSDR", &silence)
+            .into_iter()
+            .map(|b| if b == 1 { 1.0 } else { -1.0 }),
+    );
+    let samples = modulate(&levels, DSTAR_DEVIATION_HZ, &mut rng);
+
+    let mut decoder = Decoder::new(RATE);
+    let (updates, voice) = run(&mut decoder, &samples);
+    let call = updates
+        .iter()
+        .rev()
+        .find(|u| u.active && u.message.is_some() && u.callsign.is_some())
+        .expect("header and message");
+    assert_eq!(call.mode, Mode::Dstar);
+    assert_eq!(call.callsign.as_deref(), Some("DL1ABC"));
+    assert_eq!(call.suffix.as_deref(), Some("ID51"));
+    assert_eq!(call.your.as_deref(), Some("CQCQCQ"));
+    assert_eq!(call.rpt1.as_deref(), Some("DB0XYZ B"));
+    assert_eq!(call.message.as_deref(), Some("Hello from NovaSDR"));
+    let silence = VoiceFrame::Dstar(dstar::ambe_frame(&dstar::SILENCE));
+    assert!(!voice.is_empty());
+    assert!(voice.iter().all(|&v| v == silence));
+}
+
+#[test]
+fn dstar_voice_frames_follow_the_stream_once_confirmed() {
+    let mut rng = StdRng::seed_from_u64(23);
+    let header = dstar::Header {
+        flags: [0, 0, 0],
+        rpt2: String::new(),
+        rpt1: String::new(),
+        your: "CQCQCQ".into(),
+        my: "DL1ABC".into(),
+        suffix: String::new(),
+    };
+    let frames = 4 * dstar::SUPERFRAME_FRAMES as usize;
+    let sent: Vec<[u8; 9]> = (0..frames).map(|_| rng.gen()).collect();
+    let bit = |b: u8| if b == 1 { 1.0 } else { -1.0 };
+    let mut levels: Vec<f32> = (0..1_000).map(|_| bit(rng.gen_range(0..2))).collect();
+    levels.extend(
+        dstar::encode_stream(&header, "", &sent)
+            .into_iter()
+            .map(bit),
+    );
+    levels.extend((0..1_000).map(|_| bit(rng.gen_range(0..2))));
+    let samples = modulate(&levels, DSTAR_DEVIATION_HZ, &mut rng);
+
+    let mut decoder = Decoder::new(RATE);
+    let (_, voice) = run(&mut decoder, &samples);
+    // The second data sync confirms the stream, from the voice of the frame it ends onwards.
+    let from = dstar::SUPERFRAME_FRAMES as usize;
+    let expected: Vec<VoiceFrame> = sent[from..]
+        .iter()
+        .map(|v| VoiceFrame::Dstar(dstar::ambe_frame(v)))
+        .collect();
+    assert_eq!(voice, expected);
+}
+
+#[test]
+fn dstar_voice_bits_fill_each_ambe_row_once() {
+    let mut seen = [[0u8; 24]; 4];
+    for bit in 0..72 {
+        let mut voice = [0u8; 9];
+        voice[bit / 8] = 1 << (bit % 8);
+        let frame = dstar::ambe_frame(&voice);
+        for (row, seen) in frame.iter().zip(&mut seen) {
+            for (b, s) in row.iter().zip(seen.iter_mut()) {
+                *s += b;
+            }
+        }
+    }
+    let used = [24, 23, 11, 14];
+    for (row, &len) in seen.iter().zip(&used) {
+        assert!(row[..len].iter().all(|&n| n == 1));
+        assert!(row[len..].iter().all(|&n| n == 0));
+    }
+}
+
+#[test]
+fn ysf_is_detected_from_repeated_frame_sync() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut levels = Vec::new();
+    for _ in 0..6 {
+        levels.extend((0..ysf::SYNC_SYMBOLS).map(|k| {
+            let dibit = (ysf::SYNC >> (38 - 2 * k)) & 3;
+            dmr::level(dibit as u8)
+        }));
+        let payload = ysf::FRAME_SYMBOLS as usize - ysf::SYNC_SYMBOLS;
+        levels.extend((0..payload).map(|_| dmr::level(rng.gen_range(0..4))));
+    }
+    let samples = modulate(&levels, DMR_DEVIATION_HZ, &mut rng);
+    let mut decoder = Decoder::new(RATE);
+    let (updates, voice) = run(&mut decoder, &samples);
+    assert_eq!(updates.first().map(|u| u.mode), Some(Mode::Ysf));
+    assert_eq!(decoder.active(), Some(Mode::Ysf));
+    // Random payloads are not VD mode 2.
+    assert!(voice.is_empty());
+}
+
+#[test]
+fn ysf_vd2_voice_is_taken_from_every_voice_channel() {
+    let mut rng = StdRng::seed_from_u64(29);
+    let mut levels: Vec<f32> = (0..500).map(|_| dmr::level(rng.gen_range(0..4))).collect();
+    let mut sent = Vec::new();
+    for _ in 0..6 {
+        let mut voice = [[0u8; ysf::VOICE_BITS]; ysf::VOICE_FRAMES];
+        for bit in voice.iter_mut().flatten() {
+            *bit = rng.gen_range(0..2);
+        }
+        levels.extend(ysf::encode_vd2_frame(&voice).map(dmr::level));
+        sent.extend(voice.map(VoiceFrame::Ysf));
+    }
+    levels.extend((0..500).map(|_| dmr::level(rng.gen_range(0..4))));
+    let samples = modulate(&levels, DMR_DEVIATION_HZ, &mut rng);
+
+    let mut decoder = Decoder::new(RATE);
+    let (_, voice) = run(&mut decoder, &samples);
+    // The second frame's sync confirms the signal.
+    assert_eq!(voice, sent[ysf::VOICE_FRAMES..]);
+}
+
+#[test]
+fn noise_and_analog_fm_stay_quiet() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let noise: Vec<f32> = (0..RATE as usize * 10)
+        .map(|_| rng.gen_range(-3.0..3.0))
+        .collect();
+    let tone: Vec<f32> = (0..RATE as usize * 5)
+        .map(|i| 0.5 * (TAU * 1_000.0 * i as f32 / RATE as f32).sin())
+        .collect();
+    let mut decoder = Decoder::new(RATE);
+    let (updates, voice) = run(&mut decoder, &[noise, tone].concat());
+    assert!(updates.is_empty());
+    assert!(voice.is_empty());
+    assert!(!decoder.digital());
+}
+
+#[test]
+fn digital_voice_command_parses_under_its_documented_name() {
+    let cmd: ClientCommand =
+        serde_json::from_str(r#"{"cmd":"digital_voice","enabled":true}"#).unwrap();
+    assert!(matches!(cmd, ClientCommand::DigitalVoice { enabled: true }));
+}
@@ -0,0 +1,62 @@
+use novasdr_core::protocol::{EventTopics, EventsInfo, PresenceEntry};
+
+#[test]
+fn topic_lists_parse_case_insensitively_and_default_to_everything() {
+    assert_eq!(EventTopics::parse(None), Ok(EventTopics::ALL));
+    assert_eq!(EventTopics::parse(Some(" ")), Ok(EventTopics::ALL));
+    let topics = EventTopics::parse(Some("users, Maintenance,")).unwrap();
+    assert!(topics.contains(EventTopics::USERS));
+    assert!(topics.contains(EventTopics::MAINTENANCE));
+    assert!(!topics.contains(EventTopics::BANDWIDTH));
+    assert!(!topics.contains(EventTopics::OVERLAYS));
+    assert!(EventTopics::parse(Some("users,firehose")).is_err());
+
+    let only_overlays = EventTopics::parse(Some("overlays")).unwrap();
+    assert!(only_overlays.intersection(EventTopics::PERIODIC).is_empty());
+    let calls = EventTopics::parse(Some("digital_voice")).unwrap();
+    assert!(calls.intersection(EventTopics::PERIODIC).is_empty());
+    assert!(EventTopics::ALL.contains(calls));
+    let spots = EventTopics::parse(Some("spots")).unwrap();
+    assert!(spots.intersection(EventTopics::PERIODIC).is_empty());
+    assert!(EventTopics::ALL.contains(spots.union(calls)));
+}
+
+#[test]
+fn unsubscribed_fields_are_left_out_of_the_payload() {
+    assert_eq!(serde_json::to_string(&EventsInfo::default()).unwrap(), "{}");
+    let info = EventsInfo {
+        waterfall_clients: Some(2),
+        signal_clients: Some(1),
+        ..EventsInfo::default()
+    };
+    assert_eq!(
+        serde_json::to_value(&info).unwrap(),
+        serde_json::json!({ "waterfall_clients": 2, "signal_clients": 1 })
+    );
+}
+
+#[test]
+fn an_empty_presence_list_is_still_sent() {
+    let info = EventsInfo {
+        chat_clients: Some(0),
+        presence: Some(Vec::new()),
+        ..EventsInfo::default()
+    };
+    assert_eq!(
+        serde_json::to_value(&info).unwrap(),
+        serde_json::json!({ "chat_clients": 0, "presence": [] })
+    );
+    let info = EventsInfo {
+        presence: Some(vec![PresenceEntry {
+            nickname: "DL1ABC".to_string(),
+            receiver_id: "hf".to_string(),
+            frequency: 7_074_000,
+            mode: "USB",
+        }]),
+        ..EventsInfo::default()
+    };
+    assert_eq!(
+        serde_json::to_value(&info).unwrap()["presence"][0],
+        serde_json::json!({ "nickname": "DL1ABC", "receiver_id": "hf", "frequency": 7074000, "mode": "USB" })
+    );
+}
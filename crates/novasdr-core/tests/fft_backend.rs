@@ -0,0 +1,59 @@
+use novasdr_core::config::Accelerator;
+use novasdr_core::dsp::fft::{FftEngine, FftSettings};
+
+fn settings(accelerator: Accelerator, is_real: bool) -> FftSettings {
+    FftSettings {
+        fft_size: 1024,
+        is_real,
+        brightness_offset: 0,
+        downsample_levels: 2,
+        audio_max_fft_size: 64,
+        accelerator,
+        vulkan_device: None,
+        dc_suppression: Default::default(),
+        window: Default::default(),
+    }
+}
+
+#[test]
+fn cpu_backend_is_used_without_accelerator() {
+    let engine = FftEngine::new(settings(Accelerator::None, false)).unwrap();
+    assert_eq!(engine.backend(), Accelerator::None);
+}
+
+#[test]
+fn auto_falls_back_to_a_working_backend() {
+    for is_real in [false, true] {
+        let mut engine = FftEngine::new(settings(Accelerator::Auto, is_real)).unwrap();
+        assert_ne!(engine.backend(), Accelerator::Auto);
+        if is_real {
+            assert_ne!(engine.backend(), Accelerator::Vkfft);
+        }
+        let res = engine.execute(true).unwrap();
+        assert!(res.quantized_concat.is_some());
+    }
+}
+
+#[test]
+fn unavailable_accelerators_fall_back_to_the_cpu_off_linux() {
+    assert_eq!(
+        Accelerator::Unsupported.for_platform(false),
+        Accelerator::None
+    );
+    assert_eq!(Accelerator::Auto.for_platform(false), Accelerator::Auto);
+    assert_eq!(
+        Accelerator::Vkfft.for_platform(false),
+        if cfg!(feature = "vkfft") {
+            Accelerator::Vkfft
+        } else {
+            Accelerator::None
+        }
+    );
+    // Linux keeps the configured value so startup reports the missing feature.
+    assert_eq!(
+        Accelerator::Unsupported.for_platform(true),
+        Accelerator::Unsupported
+    );
+    assert!(Accelerator::built_in().contains(&Accelerator::None));
+    assert!(!Accelerator::built_in().contains(&Accelerator::Unsupported));
+}
@@ -0,0 +1,70 @@
+use novasdr_core::config::FftWindow;
+use novasdr_core::dsp::window::{fft_window, hann_window};
+
+const N: usize = 4096;
+
+/// Peak of a full-scale tone `offset` bins above bin `N/8`, windowed with `w`.
+fn tone_peak(w: &[f32], offset: f32) -> f32 {
+    let freq = N as f32 / 8.0 + offset;
+    let k = (N / 8) as f32 + offset.round();
+    let (mut re, mut im) = (0.0f32, 0.0f32);
+    for (i, &wv) in w.iter().enumerate() {
+        let x = (2.0 * std::f32::consts::PI * freq * i as f32 / N as f32).cos() * wv;
+        let phase = -2.0 * std::f32::consts::PI * k * i as f32 / N as f32;
+        re += x * phase.cos();
+        im += x * phase.sin();
+    }
+    (re * re + im * im).sqrt()
+}
+
+#[test]
+fn windows_share_hann_coherent_gain() {
+    assert_eq!(fft_window(FftWindow::Hann, N), hann_window(N));
+    for window in [
+        FftWindow::BlackmanHarris,
+        FftWindow::FlatTop,
+        FftWindow::Kaiser { beta: 8.6 },
+    ] {
+        let w = fft_window(window, N);
+        assert_eq!(w.len(), N);
+        let mean = w.iter().sum::<f32>() / N as f32;
+        assert!(
+            (mean - 0.5).abs() < 1e-4,
+            "{}: mean {mean}",
+            window.as_str()
+        );
+        // Periodic and symmetric around N/2.
+        for i in 1..N / 2 {
+            assert!(
+                (w[i] - w[N - i]).abs() < 1e-4,
+                "{} not symmetric",
+                window.as_str()
+            );
+        }
+    }
+}
+
+#[test]
+fn flat_top_keeps_amplitude_between_bins() {
+    let scallop_db = |window| {
+        let w = fft_window(window, N);
+        20.0 * (tone_peak(&w, 0.5) / tone_peak(&w, 0.0)).log10()
+    };
+    assert!(scallop_db(FftWindow::Hann) < -1.0);
+    assert!(scallop_db(FftWindow::FlatTop).abs() < 0.05);
+}
+
+#[test]
+fn window_parses_from_string_or_kaiser_object() {
+    let parse = |raw: &str| serde_json::from_str::<FftWindow>(raw).unwrap();
+    assert_eq!(parse(r#""blackman-harris""#), FftWindow::BlackmanHarris);
+    assert_eq!(parse(r#""flat-top""#), FftWindow::FlatTop);
+    assert_eq!(
+        parse(r#"{ "kaiser": { "beta": 6.0 } }"#),
+        FftWindow::Kaiser { beta: 6.0 }
+    );
+    assert_eq!(
+        parse(r#"{ "kaiser": {} }"#),
+        FftWindow::Kaiser { beta: 8.6 }
+    );
+}
@@ -0,0 +1,44 @@
+use novasdr_core::dsp::fm_level::FmLeveler;
+use std::f32::consts::TAU;
+
+const RATE: f32 = 12_000.0;
+const TARGET: f32 = 0.1;
+
+/// Discriminator output for a 1 kHz tone at `deviation_hz`.
+fn discriminator_tone(deviation_hz: f32) -> Vec<f32> {
+    (0..RATE as usize)
+        .map(|n| TAU * deviation_hz / RATE * (TAU * 1_000.0 * n as f32 / RATE).sin())
+        .collect()
+}
+
+fn peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0f32, |m, s| m.max(s.abs()))
+}
+
+#[test]
+fn nominal_deviation_reaches_the_target_without_limiting() {
+    let leveler = FmLeveler::new(3_000.0, RATE, TARGET);
+    let mut tone = discriminator_tone(3_000.0);
+    leveler.process(&mut tone);
+    assert!((peak(&tone) - TARGET).abs() < 1e-3);
+
+    // Level follows deviation linearly below the knee, with no gain riding.
+    let mut quiet = discriminator_tone(1_500.0);
+    leveler.process(&mut quiet);
+    assert!((peak(&quiet) - TARGET / 2.0).abs() < 1e-3);
+}
+
+#[test]
+fn overdeviation_is_soft_limited_below_twice_the_target() {
+    let leveler = FmLeveler::new(3_000.0, RATE, TARGET);
+    let mut loud = discriminator_tone(5_000.0);
+    leveler.process(&mut loud);
+    let p = peak(&loud);
+    assert!(p > TARGET && p < 2.0 * TARGET, "peak {p}");
+
+    // Noise without a carrier spans the whole ±π range.
+    let mut noise = vec![std::f32::consts::PI, -std::f32::consts::PI, 0.5];
+    leveler.process(&mut noise);
+    assert!(peak(&noise) <= 2.0 * TARGET);
+    assert!(noise[0] > 0.0 && noise[1] < 0.0);
+}
@@ -0,0 +1,77 @@
+use novasdr_core::config::SampleFormat;
+use novasdr_core::dsp::format_probe::{probe_format, MIN_PROBE_BYTES};
+
+/// Deterministic, roughly Gaussian noise with unit variance (Irwin-Hall over an LCG).
+fn noise(n: usize) -> Vec<f64> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut uniform = move || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..n)
+        .map(|_| (0..12).map(|_| uniform()).sum::<f64>() - 6.0)
+        .collect()
+}
+
+const N: usize = 32_768;
+
+#[test]
+fn probe_detects_integer_layouts() {
+    let u8_bytes: Vec<u8> = noise(N)
+        .iter()
+        .map(|x| (127.5 + 20.0 * x).round().clamp(0.0, 255.0) as u8)
+        .collect();
+    let s8_bytes: Vec<u8> = noise(N)
+        .iter()
+        .map(|x| (20.0 * x).round().clamp(-128.0, 127.0) as i8 as u8)
+        .collect();
+    let s16_bytes: Vec<u8> = noise(N)
+        .iter()
+        .flat_map(|x| ((2000.0 * x).round() as i16).to_le_bytes())
+        .collect();
+    let u16_bytes: Vec<u8> = noise(N)
+        .iter()
+        .flat_map(|x| ((32768.0 + 2000.0 * x).round() as u16).to_le_bytes())
+        .collect();
+
+    let probe = probe_format(&u8_bytes).unwrap();
+    assert_eq!(probe.formats, &[SampleFormat::U8]);
+    assert!(probe.dc_offset.abs() < 0.01, "{probe:?}");
+    assert_eq!(
+        probe_format(&s8_bytes).unwrap().formats,
+        &[SampleFormat::S8]
+    );
+    let probe = probe_format(&s16_bytes).unwrap();
+    assert_eq!(probe.formats, &[SampleFormat::S16, SampleFormat::Cs16]);
+    // 2000 / 32768 full scale is about -24 dBFS.
+    assert!((probe.rms_dbfs + 24.3).abs() < 0.5, "{probe:?}");
+    assert_eq!(probe.clipped_fraction, 0.0);
+    assert_eq!(
+        probe_format(&u16_bytes).unwrap().formats,
+        &[SampleFormat::U16]
+    );
+}
+
+#[test]
+fn probe_detects_float_layouts_and_needs_enough_bytes() {
+    let f32_bytes: Vec<u8> = noise(N)
+        .iter()
+        .flat_map(|x| ((0.1 * x) as f32).to_le_bytes())
+        .collect();
+    let f64_bytes: Vec<u8> = noise(N)
+        .iter()
+        .flat_map(|x| (0.1 * x).to_le_bytes())
+        .collect();
+
+    assert_eq!(
+        probe_format(&f32_bytes).unwrap().formats,
+        &[SampleFormat::F32, SampleFormat::Cf32]
+    );
+    assert_eq!(
+        probe_format(&f64_bytes).unwrap().formats,
+        &[SampleFormat::F64]
+    );
+    assert!(probe_format(&f32_bytes[..MIN_PROBE_BYTES - 1]).is_none());
+}
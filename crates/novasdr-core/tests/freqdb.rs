@@ -0,0 +1,46 @@
+use novasdr_core::freqdb::{expand_url, lookup, parse_csv};
+
+const CSV: &str = "\
+start_hz,end_hz,name,notes
+# 40 m
+7000000,7200000,40m amateur band,Region 1: 7.0-7.2 MHz
+7074000,,FT8
+9420000,,\"Greece, ERT\",\"Voice of \"\"Greece\"\"\"
+";
+
+#[test]
+fn parse_csv_handles_headers_ranges_and_quotes() {
+    let entries = parse_csv(CSV).expect("parse");
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[1].start_hz, 7_074_000);
+    assert_eq!(entries[1].end_hz, 7_074_000);
+    assert_eq!(entries[1].notes, "");
+    assert_eq!(entries[2].name, "Greece, ERT");
+    assert_eq!(entries[2].notes, "Voice of \"Greece\"");
+
+    assert!(parse_csv("7000000,6999000,backwards").is_err());
+    assert!(parse_csv("7000000,,").is_err());
+}
+
+#[test]
+fn lookup_orders_nearest_and_narrowest_first() {
+    let entries = parse_csv(CSV).expect("parse");
+    let names: Vec<String> = lookup(&entries, 7_074_500, 1_000, 10)
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+    assert_eq!(names, ["40m amateur band", "FT8"]);
+
+    let names: Vec<String> = lookup(&entries, 7_074_000, 1_000, 10)
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+    assert_eq!(names, ["FT8", "40m amateur band"]);
+    assert!(lookup(&entries, 9_500_000, 1_000, 10).is_empty());
+    assert_eq!(lookup(&entries, 7_074_000, 1_000, 1).len(), 1);
+
+    assert_eq!(
+        expand_url("https://db.example/q?f={khz}&hz={hz}", 7_074_000),
+        "https://db.example/q?f=7074&hz=7074000"
+    );
+}
@@ -0,0 +1,118 @@
+use novasdr_core::ft8::{self, DecodeOptions, Protocol, SAMPLE_RATE};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+fn slot_with_signals(
+    protocol: Protocol,
+    signals: &[([u8; 10], f32, f32, f32)],
+    noise: f32,
+) -> Vec<f32> {
+    let slot_len = (protocol.slot_ms() as usize) * SAMPLE_RATE as usize / 1000;
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut out: Vec<f32> = (0..slot_len)
+        .map(|_| {
+            // Box-Muller
+            let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+            let u2: f32 = rng.gen();
+            noise * (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+        })
+        .collect();
+    for (payload, freq_hz, start_s, amplitude) in signals {
+        let tones = ft8::encode_tones(protocol, payload);
+        let wave = ft8::synthesize(protocol, &tones, *freq_hz, *amplitude);
+        let start = (start_s * SAMPLE_RATE as f32) as usize;
+        for (dst, s) in out[start..].iter_mut().zip(wave.iter()) {
+            *dst += s;
+        }
+    }
+    out
+}
+
+#[test]
+fn standard_message_roundtrips_through_pack_and_unpack() {
+    let cases = [
+        ("CQ", "K1ABC", "FN42", "CQ K1ABC FN42"),
+        ("K1ABC", "W9XYZ", "-12", "K1ABC W9XYZ -12"),
+        ("W9XYZ", "K1ABC", "R+05", "W9XYZ K1ABC R+05"),
+        ("K1ABC", "G4ABC", "RR73", "K1ABC G4ABC RR73"),
+        ("CQ DX", "PA3XYZ", "JO22", "CQ DX PA3XYZ JO22"),
+    ];
+    for (to, de, extra, text) in cases {
+        let payload = ft8::pack_standard(to, de, extra).expect("pack");
+        let msg = ft8::unpack(&payload).expect("unpack");
+        assert_eq!(msg.text, text);
+        assert_eq!(msg.sender.as_deref(), Some(de));
+    }
+
+    let grid = ft8::unpack(&ft8::pack_standard("CQ", "K1ABC", "FN42").unwrap()).unwrap();
+    assert_eq!(grid.locator.as_deref(), Some("FN42"));
+    let report = ft8::unpack(&ft8::pack_standard("K1ABC", "W9XYZ", "-12").unwrap()).unwrap();
+    assert_eq!(report.locator, None);
+}
+
+#[test]
+fn free_text_roundtrips() {
+    let payload = ft8::pack_free_text("TNX 73 GL").expect("pack");
+    let msg = ft8::unpack(&payload).expect("unpack");
+    assert_eq!(msg.text, "TNX 73 GL");
+    assert_eq!(msg.sender, None);
+}
+
+#[test]
+fn ldpc_corrects_flipped_bits() {
+    let payload = ft8::pack_standard("CQ", "K1ABC", "FN42").unwrap();
+    let codeword = ft8::ldpc::encode(&ft8::ldpc::add_crc(&payload));
+    let mut llr = [0f32; 174];
+    for (l, &bit) in llr.iter_mut().zip(codeword.iter()) {
+        *l = if bit == 1 { 4.0 } else { -4.0 };
+    }
+    for idx in [3usize, 40, 77, 120, 160] {
+        llr[idx] = -llr[idx] * 0.5;
+    }
+    let bits = ft8::ldpc::decode(&llr, 30).expect("ldpc converges");
+    assert_eq!(ft8::ldpc::check_crc(&bits), Some(payload));
+}
+
+#[test]
+fn ft8_decodes_multiple_signals_in_noise() {
+    let a = ft8::pack_standard("CQ", "K1ABC", "FN42").unwrap();
+    let b = ft8::pack_standard("K1ABC", "W9XYZ", "-12").unwrap();
+    let c = ft8::pack_free_text("HELLO WORLD").unwrap();
+    let samples = slot_with_signals(
+        Protocol::Ft8,
+        &[
+            (a, 1000.0, 0.5, 0.3),
+            (b, 1503.0, 0.8, 0.2),
+            (c, 2210.0, 0.3, 0.25),
+        ],
+        1.0,
+    );
+    let decoded = ft8::decode(Protocol::Ft8, &samples, &DecodeOptions::default());
+    let texts: Vec<_> = decoded.iter().map(|d| d.message.text.as_str()).collect();
+    assert!(texts.contains(&"CQ K1ABC FN42"), "{texts:?}");
+    assert!(texts.contains(&"K1ABC W9XYZ -12"), "{texts:?}");
+    assert!(texts.contains(&"HELLO WORLD"), "{texts:?}");
+
+    let cq = decoded
+        .iter()
+        .find(|d| d.message.text == "CQ K1ABC FN42")
+        .unwrap();
+    assert!((cq.freq_hz - 1000.0).abs() < 4.0, "freq {}", cq.freq_hz);
+    assert!(cq.dt_s.abs() < 0.1, "dt {}", cq.dt_s);
+    assert!((-20..=0).contains(&cq.snr_db), "snr {}", cq.snr_db);
+}
+
+#[test]
+fn ft4_decodes_signal_in_noise() {
+    let a = ft8::pack_standard("CQ", "PA3XYZ", "JO22").unwrap();
+    let samples = slot_with_signals(Protocol::Ft4, &[(a, 1200.0, 0.5, 0.4)], 1.0);
+    let decoded = ft8::decode(Protocol::Ft4, &samples, &DecodeOptions::default());
+    assert_eq!(decoded.len(), 1, "{decoded:?}");
+    assert_eq!(decoded[0].message.text, "CQ PA3XYZ JO22");
+    assert_eq!(decoded[0].message.sender.as_deref(), Some("PA3XYZ"));
+}
+
+#[test]
+fn noise_only_slot_decodes_nothing() {
+    let samples = slot_with_signals(Protocol::Ft8, &[], 1.0);
+    assert!(ft8::decode(Protocol::Ft8, &samples, &DecodeOptions::default()).is_empty());
+}
@@ -0,0 +1,71 @@
+use novasdr_core::dsp::hearing::{FrequencyLowering, HearingProfile};
+
+const RATE: usize = 12_000;
+
+fn tone(freq: f32) -> Vec<f32> {
+    (0..RATE)
+        .map(|n| 0.5 * (std::f32::consts::TAU * freq * n as f32 / RATE as f32).sin())
+        .collect()
+}
+
+/// Amplitude of the `freq` component in the second half of `samples` (Goertzel).
+fn level_at(samples: &[f32], freq: f32) -> f32 {
+    let tail = &samples[RATE / 2..];
+    let coeff = 2.0 * (std::f32::consts::TAU * freq / RATE as f32).cos();
+    let (mut s1, mut s2) = (0.0f32, 0.0f32);
+    for &x in tail {
+        let s = x + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s;
+    }
+    let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+    2.0 * power.sqrt() / tail.len() as f32
+}
+
+fn run(profile: HearingProfile, input: &[f32]) -> Vec<f32> {
+    let mut stage = FrequencyLowering::new(RATE);
+    stage.set_profile(profile);
+    let mut samples = input.to_vec();
+    // Uneven block sizes, as the audio pipeline delivers them.
+    for block in samples.chunks_mut(173) {
+        stage.process(block);
+    }
+    samples
+}
+
+#[test]
+fn profiles_parse_by_name() {
+    for name in ["off", "compress", "compress_strong", "transpose"] {
+        assert_eq!(HearingProfile::parse(name).unwrap().as_str(), name);
+    }
+    assert_eq!(HearingProfile::parse("loud"), None);
+}
+
+#[test]
+fn off_passes_samples_untouched() {
+    let input = tone(4_000.0);
+    assert_eq!(run(HearingProfile::Off, &input), input);
+}
+
+#[test]
+fn compress_moves_high_tones_down_and_keeps_low_ones() {
+    let low = run(HearingProfile::Compress, &tone(700.0));
+    let kept = level_at(&low, 700.0);
+    assert!((kept / 0.5 - 1.0).abs() < 0.1, "700 Hz level {kept}");
+
+    let high = run(HearingProfile::Compress, &tone(4_000.0));
+    // 1.5 kHz + (4 - 1.5) kHz / 2.
+    let moved = level_at(&high, 2_750.0);
+    let left = level_at(&high, 4_000.0);
+    assert!(moved > 0.3, "2750 Hz level {moved}");
+    assert!(left < 0.05, "4000 Hz level {left}");
+}
+
+#[test]
+fn transpose_adds_an_octave_down_copy() {
+    let out = run(HearingProfile::Transpose, &tone(4_000.0));
+    let original = level_at(&out, 4_000.0);
+    let copy = level_at(&out, 2_000.0);
+    assert!(original > 0.4, "4000 Hz level {original}");
+    assert!(copy > 0.2, "2000 Hz level {copy}");
+}
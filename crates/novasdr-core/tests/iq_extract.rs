@@ -0,0 +1,65 @@
+use novasdr_core::dsp::{iq_extract::IqExtractor, window::hann_window};
+use num_complex::Complex32;
+use rustfft::FftPlanner;
+use std::f32::consts::TAU;
+
+const FFT_SIZE: usize = 1024;
+const SIZE: usize = 64;
+
+/// Runs a complex tone at `tone_bin` (raw FFT bin, may be fractional) through a Hann FFT at 50%
+/// overlap, rotated like the DSP runner does for IQ input, and extracts around `center`.
+fn extract(tone_bin: f32, amplitude: f32, center: i64, frames: u64) -> Vec<Complex32> {
+    let fft = FftPlanner::new().plan_fft_forward(FFT_SIZE);
+    let window = hann_window(FFT_SIZE);
+    let base_idx = FFT_SIZE / 2 + 1;
+    let mut extractor = IqExtractor::new(SIZE, FFT_SIZE);
+    let mut out = Vec::new();
+    for frame in 0..frames {
+        let start = frame as usize * FFT_SIZE / 2;
+        let mut buf: Vec<Complex32> = (0..FFT_SIZE)
+            .map(|n| {
+                let phase = TAU * tone_bin * (start + n) as f32 / FFT_SIZE as f32;
+                Complex32::from_polar(amplitude * window[n], phase)
+            })
+            .collect();
+        fft.process(&mut buf);
+        let rotated: Vec<Complex32> = (0..FFT_SIZE)
+            .map(|i| buf[(i + base_idx) % FFT_SIZE])
+            .collect();
+        extractor.process(&rotated, center, false, frame, &mut out);
+    }
+    out
+}
+
+#[test]
+fn tone_keeps_its_amplitude_and_offset_frequency() {
+    // Raw bin 30 sits at rotated index 30 + N/2 - 1; try an even and an odd centre.
+    for center in [540i64, 541] {
+        let tone = 37.25f32;
+        let out = extract(tone, 0.5, center, 40);
+        let settled = &out[2 * SIZE..];
+        for s in settled {
+            assert!(
+                (s.norm() - 0.5).abs() < 0.01,
+                "center {center}: |s| {}",
+                s.norm()
+            );
+        }
+        // Offset from the centre in bins, at SIZE samples per FFT_SIZE input samples.
+        let offset_bins = tone - (center - (FFT_SIZE as i64 / 2 - 1)) as f32;
+        let expected = TAU * offset_bins / SIZE as f32;
+        for w in settled.windows(2) {
+            let step = (w[1] * w[0].conj()).arg();
+            assert!(
+                (step - expected).abs() < 1e-3,
+                "center {center}: step {step}"
+            );
+        }
+    }
+}
+
+#[test]
+fn bins_past_the_edge_are_silence() {
+    let out = extract(10.0, 0.5, -(SIZE as i64), 6);
+    assert!(out.iter().all(|s| s.norm() < 1e-6));
+}
@@ -187,3 +187,594 @@ fn json_load_fifo_input() {
     let cfg = load_from_files(&config, &receivers).unwrap();
     assert_eq!(cfg.active_receiver_id, "rx0");
 }
+
+#[test]
+fn json_load_ka9q_input() {
+    let config = write_temp(
+        "config.json",
+        r#"{
+  "server": { "port": 9002, "host": "0.0.0.0", "html_root": "frontend/dist/", "otherusers": 1, "threads": 1 },
+  "websdr": { "name": "NovaSDR" },
+  "limits": { "audio": 1, "waterfall": 1, "events": 1 },
+  "active_receiver_id": "rx0"
+}"#,
+    );
+    let receivers = write_temp(
+        "receivers.json",
+        r#"{
+  "receivers": [
+    { "id": "rx0", "input": { "sps": 192000, "frequency": 7100000, "signal": "iq", "driver": { "kind": "ka9q", "group": "239.1.2.3:5004", "ssrc": 7100, "format": "cs16", "byte_order": "big" } } }
+  ]
+}"#,
+    );
+
+    let cfg = load_from_files(&config, &receivers).unwrap();
+    let rx = cfg.active_receiver().unwrap();
+    let novasdr_core::config::InputDriver::Ka9q(driver) = &rx.input.driver else {
+        panic!("expected ka9q driver, got {:?}", rx.input.driver);
+    };
+    assert_eq!(driver.group, "239.1.2.3:5004");
+    assert_eq!(driver.ssrc, Some(7100));
+    assert_eq!(driver.payload_type, None);
+    assert_eq!(rx.input.driver.as_str(), "ka9q");
+}
+
+#[test]
+fn soapysdr_receivers_share_a_device_on_distinct_channels() {
+    let config = write_temp(
+        "config.json",
+        r#"{
+  "server": { "port": 9002, "host": "0.0.0.0", "html_root": "frontend/dist/", "otherusers": 1, "threads": 1 },
+  "websdr": { "name": "NovaSDR" },
+  "limits": { "audio": 1, "waterfall": 1, "events": 1 },
+  "active_receiver_id": "rx0"
+}"#,
+    );
+    let receivers = |ch1: usize, sps1: i64| {
+        write_temp(
+            "receivers.json",
+            &format!(
+                r#"{{
+  "receivers": [
+    {{ "id": "rx0", "input": {{ "sps": 2000000, "frequency": 7100000, "signal": "iq", "driver": {{ "kind": "soapysdr", "device": "driver=lime", "format": "cs16", "channel": 0 }} }} }},
+    {{ "id": "rx1", "input": {{ "sps": {sps1}, "frequency": 14100000, "signal": "iq", "driver": {{ "kind": "soapysdr", "device": "driver=lime", "format": "cs16", "channel": {ch1} }} }} }}
+  ]
+}}"#
+            ),
+        )
+    };
+
+    let cfg = load_from_files(&config, &receivers(1, 2_000_000)).unwrap();
+    assert_eq!(cfg.receivers.len(), 2);
+
+    let msg = load_from_files(&config, &receivers(0, 2_000_000))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        msg.contains("which another receiver already uses"),
+        "unexpected error: {msg}"
+    );
+
+    let msg = load_from_files(&config, &receivers(1, 1_000_000))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        msg.contains("input.sps must match"),
+        "unexpected error: {msg}"
+    );
+}
+
+#[test]
+fn soapysdr_ppm_correction_is_bounded() {
+    let config = write_temp(
+        "config.json",
+        r#"{
+  "server": { "port": 9002, "host": "0.0.0.0", "html_root": "frontend/dist/", "otherusers": 1, "threads": 1 },
+  "websdr": { "name": "NovaSDR" },
+  "limits": { "audio": 1, "waterfall": 1, "events": 1 },
+  "active_receiver_id": "rx0"
+}"#,
+    );
+    let receivers = |ppm: f64| {
+        write_temp(
+            "receivers.json",
+            &format!(
+                r#"{{
+  "receivers": [
+    {{ "id": "rx0", "input": {{ "sps": 2048000, "frequency": 100000000, "signal": "iq", "driver": {{ "kind": "soapysdr", "device": "driver=rtlsdr", "format": "cs16", "ppm_correction": {ppm}, "reference_sensors": ["ref_locked"] }} }} }}
+  ]
+}}"#
+            ),
+        )
+    };
+
+    let cfg = load_from_files(&config, &receivers(-42.5)).unwrap();
+    let novasdr_core::config::InputDriver::SoapySdr(driver) =
+        &cfg.active_receiver().unwrap().input.driver
+    else {
+        panic!("expected a soapysdr driver");
+    };
+    assert_eq!(driver.ppm_correction, Some(-42.5));
+    assert_eq!(driver.reference_sensors, ["ref_locked"]);
+
+    let msg = load_from_files(&config, &receivers(900.0))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        msg.contains("ppm_correction must be within"),
+        "unexpected error: {msg}"
+    );
+}
+
+#[test]
+fn disk_guard_defaults_on_and_needs_ordered_water_marks() {
+    use novasdr_core::config::DiskCleanup;
+
+    let config = |guard: &str| {
+        write_temp(
+            "config.json",
+            &format!(
+                r#"{{
+  "server": {{ "port": 9002, "host": "0.0.0.0", "html_root": "frontend/dist/", "otherusers": 1, "threads": 1 }},
+  "websdr": {{ "name": "NovaSDR" }},
+  "limits": {{ "audio": 1, "waterfall": 1, "events": 1 }},
+  {guard}
+  "active_receiver_id": "rx0"
+}}"#
+            ),
+        )
+    };
+    let receivers = write_temp(
+        "receivers.json",
+        r#"{
+  "receivers": [
+    { "id": "rx0", "input": { "sps": 2048000, "frequency": 100000000, "signal": "iq", "driver": { "kind": "stdin", "format": "u8" } } }
+  ]
+}"#,
+    );
+
+    let cfg = load_from_files(&config(""), &receivers).unwrap();
+    assert!(cfg.disk_guard.enabled);
+    assert_eq!(cfg.disk_guard.high_water_percent, 95.0);
+    assert_eq!(cfg.disk_guard.low_water_percent, 90.0);
+    assert_eq!(cfg.disk_guard.cleanup, DiskCleanup::None);
+
+    let cfg = load_from_files(
+        &config(r#""disk_guard": { "high_water_percent": 85, "low_water_percent": 70, "cleanup": "recordings_and_logs" },"#),
+        &receivers,
+    )
+    .unwrap();
+    assert_eq!(cfg.disk_guard.cleanup, DiskCleanup::RecordingsAndLogs);
+
+    let msg = load_from_files(
+        &config(r#""disk_guard": { "high_water_percent": 80, "low_water_percent": 90 },"#),
+        &receivers,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+        msg.contains("low_water_percent < high_water_percent"),
+        "unexpected error: {msg}"
+    );
+    // Off, the marks are not looked at.
+    load_from_files(
+        &config(r#""disk_guard": { "enabled": false, "low_water_percent": 99 },"#),
+        &receivers,
+    )
+    .unwrap();
+}
+
+#[test]
+fn band_schedule_picks_the_latest_entry_and_wraps_at_midnight() {
+    let config = write_temp(
+        "config.json",
+        r#"{
+  "server": { "port": 9002, "host": "0.0.0.0", "html_root": "frontend/dist/", "otherusers": 1, "threads": 1 },
+  "websdr": { "name": "NovaSDR" },
+  "limits": { "audio": 1, "waterfall": 1, "events": 1 },
+  "active_receiver_id": "rx0"
+}"#,
+    );
+    let receivers = |driver: &str, at: &str, timezone: &str| {
+        write_temp(
+            "receivers.json",
+            &format!(
+                r#"{{
+  "receivers": [
+    {{ "id": "rx0", "band_schedule": [ {{ "at": "{at}", "timezone": "{timezone}", "frequency": 14100000 }}, {{ "at": "18:00", "frequency": 7100000 }} ],
+      "input": {{ "sps": 2000000, "frequency": 7100000, "signal": "iq", "driver": {driver} }} }}
+  ]
+}}"#
+            ),
+        )
+    };
+    let soapy = r#"{ "kind": "soapysdr", "device": "driver=airspy", "format": "cs16" }"#;
+
+    let at = |s: &str| {
+        chrono::DateTime::parse_from_rfc3339(s)
+            .unwrap()
+            .with_timezone(&chrono::Utc)
+    };
+
+    let cfg = load_from_files(&config, &receivers(soapy, "06:30", "")).unwrap();
+    let rx = cfg.active_receiver().unwrap();
+    assert_eq!(
+        rx.scheduled_frequency(at("2026-07-01T00:00:00Z")),
+        Some(7_100_000)
+    );
+    assert_eq!(
+        rx.scheduled_frequency(at("2026-07-01T06:29:00Z")),
+        Some(7_100_000)
+    );
+    assert_eq!(
+        rx.scheduled_frequency(at("2026-07-01T06:30:00Z")),
+        Some(14_100_000)
+    );
+    assert_eq!(
+        rx.scheduled_frequency(at("2026-07-01T18:00:00Z")),
+        Some(7_100_000)
+    );
+
+    // 06:30 in Berlin is 04:30 UTC in summer and 05:30 UTC in winter.
+    let cfg = load_from_files(&config, &receivers(soapy, "06:30", "Europe/Berlin")).unwrap();
+    let rx = cfg.active_receiver().unwrap();
+    assert_eq!(
+        rx.scheduled_frequency(at("2026-07-01T04:29:00Z")),
+        Some(7_100_000)
+    );
+    assert_eq!(
+        rx.scheduled_frequency(at("2026-07-01T04:30:00Z")),
+        Some(14_100_000)
+    );
+    assert_eq!(
+        rx.scheduled_frequency(at("2026-01-15T05:29:00Z")),
+        Some(7_100_000)
+    );
+    assert_eq!(
+        rx.scheduled_frequency(at("2026-01-15T05:30:00Z")),
+        Some(14_100_000)
+    );
+
+    let msg = load_from_files(&config, &receivers(soapy, "6h30", ""))
+        .unwrap_err()
+        .to_string();
+    assert!(msg.contains("is not HH:MM"), "unexpected error: {msg}");
+    let msg = load_from_files(&config, &receivers(soapy, "06:30", "Europe/Atlantis"))
+        .unwrap_err()
+        .to_string();
+    assert!(msg.contains("unknown time zone"), "unexpected error: {msg}");
+    let stdin = r#"{ "kind": "stdin", "format": "u8" }"#;
+    let msg = load_from_files(&config, &receivers(stdin, "06:30", ""))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        msg.contains("band_schedule needs"),
+        "unexpected error: {msg}"
+    );
+}
+
+#[test]
+fn accuracy_parses_and_only_the_stated_part_reloads_in_place() {
+    use novasdr_core::config::{ClockReference, ReceiverReload};
+    let config = write_temp(
+        "config.json",
+        r#"{
+  "server": { "port": 9002, "host": "0.0.0.0", "html_root": "frontend/dist/", "otherusers": 1, "threads": 1 },
+  "websdr": { "name": "NovaSDR" },
+  "limits": { "audio": 1, "waterfall": 1, "events": 1 },
+  "active_receiver_id": "rx0"
+}"#,
+    );
+    let receivers = |accuracy: &str| {
+        write_temp(
+            "receivers.json",
+            &format!(
+                r#"{{
+  "receivers": [
+    {{ "id": "rx0", "accuracy": {accuracy},
+      "input": {{ "sps": 2000000, "frequency": 10000000, "signal": "iq", "driver": {{ "kind": "stdin", "format": "u8" }} }} }}
+  ]
+}}"#
+            ),
+        )
+    };
+
+    let cfg = load_from_files(
+        &config,
+        &receivers(r#"{ "reference": "tcxo", "ppm": 0.5 }"#),
+    )
+    .unwrap();
+    let rx = cfg.active_receiver().unwrap().clone();
+    assert_eq!(rx.accuracy.reference, ClockReference::Tcxo);
+    assert_eq!(rx.accuracy.ppm, Some(0.5));
+    assert_eq!(rx.accuracy.drift_carrier, None);
+
+    let gpsdo = load_from_files(&config, &receivers(r#"{ "reference": "gpsdo" }"#)).unwrap();
+    assert_eq!(
+        rx.reload_to(gpsdo.active_receiver().unwrap()),
+        ReceiverReload::Settings
+    );
+    let drift = load_from_files(
+        &config,
+        &receivers(r#"{ "reference": "tcxo", "ppm": 0.5, "drift_carrier": 10000000 }"#),
+    )
+    .unwrap();
+    assert_eq!(
+        rx.reload_to(drift.active_receiver().unwrap()),
+        ReceiverReload::Restart
+    );
+
+    let msg = load_from_files(&config, &receivers(r#"{ "ppm": -1 }"#))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        msg.contains("accuracy.ppm must be >= 0"),
+        "unexpected error: {msg}"
+    );
+}
+
+#[test]
+fn json_load_rx888_input() {
+    let config = write_temp(
+        "config.json",
+        r#"{
+  "server": { "port": 9002, "host": "0.0.0.0", "html_root": "frontend/dist/", "otherusers": 1, "threads": 1 },
+  "websdr": { "name": "NovaSDR" },
+  "limits": { "audio": 1, "waterfall": 1, "events": 1 },
+  "active_receiver_id": "rx0"
+}"#,
+    );
+    let receivers = write_temp(
+        "receivers.json",
+        r#"{
+  "receivers": [
+    { "id": "rx0", "input": { "sps": 64000000, "frequency": 0, "signal": "real", "fft_size": 1048576, "driver": { "kind": "rx888", "command": "rx888_stream", "args": ["-s", "64000000"], "randomizer": true } } }
+  ]
+}"#,
+    );
+
+    let cfg = load_from_files(&config, &receivers).unwrap();
+    let rx = cfg.active_receiver().unwrap();
+    let novasdr_core::config::InputDriver::Rx888(driver) = &rx.input.driver else {
+        panic!("expected rx888 driver, got {:?}", rx.input.driver);
+    };
+    assert_eq!(driver.command, "rx888_stream");
+    assert_eq!(driver.args, vec!["-s", "64000000"]);
+    assert!(driver.randomizer);
+    assert_eq!(
+        rx.input.driver.get_sample_format(),
+        novasdr_core::config::SampleFormat::S16
+    );
+    let rt = cfg.runtime_for("rx0").unwrap();
+    assert_eq!(rt.total_bandwidth, 32_000_000);
+    assert_eq!(rt.fft_result_size, 524_288);
+}
+
+#[test]
+fn receiver_limits_are_optional() {
+    let input = r#""input": { "sps": 2048000, "frequency": 100000000, "signal": "iq", "driver": { "kind": "stdin", "format": "u8" } }"#;
+    let plain: novasdr_core::config::ReceiverConfig =
+        serde_json::from_str(&format!(r#"{{ "id": "a", {input} }}"#)).unwrap();
+    assert_eq!(plain.limits.audio, None);
+    assert_eq!(plain.limits.waterfall, None);
+
+    let capped: novasdr_core::config::ReceiverConfig = serde_json::from_str(&format!(
+        r#"{{ "id": "b", "limits": {{ "audio": 20 }}, {input} }}"#
+    ))
+    .unwrap();
+    assert_eq!(capped.limits.audio, Some(20));
+    assert_eq!(capped.limits.waterfall, None);
+}
+
+#[test]
+fn channel_dump_modes_are_checked() {
+    let config = write_temp(
+        "config.json",
+        r#"{ "server": { "port": 9002 }, "websdr": { "name": "NovaSDR" } }"#,
+    );
+    let receivers = |mode: &str| {
+        write_temp(
+            "receivers.json",
+            &format!(
+                r#"{{ "receivers": [ {{
+  "id": "rx0",
+  "channel_dump": {{ "enabled": true, "channels": [
+    {{ "frequency": 156800000, "mode": "{mode}", "name": "16" }},
+    {{ "frequency": 156300000 }}
+  ] }},
+  "input": {{ "sps": 2048000, "frequency": 156000000, "signal": "iq", "driver": {{ "kind": "stdin", "format": "u8" }} }}
+}} ] }}"#
+            ),
+        )
+    };
+
+    let cfg = load_from_files(&config, &receivers("fm")).unwrap();
+    let dump = &cfg.active_receiver().unwrap().channel_dump;
+    assert_eq!(dump.segment_minutes, 60);
+    assert_eq!(
+        dump.channels[0].demodulation(),
+        Some(novasdr_core::dsp::demod::DemodulationMode::Fm)
+    );
+    assert_eq!(dump.channels[1].demodulation(), None);
+
+    let err = load_from_files(&config, &receivers("FMX")).unwrap_err();
+    assert!(err.to_string().contains("channel_dump"), "{err}");
+}
+
+#[test]
+fn udp_output_needs_an_address_and_a_mode() {
+    let config = write_temp(
+        "config.json",
+        r#"{ "server": { "port": 9002 }, "websdr": { "name": "NovaSDR" } }"#,
+    );
+    let receivers = |output: &str| {
+        write_temp(
+            "receivers.json",
+            &format!(
+                r#"{{ "receivers": [ {{
+  "id": "rx0",
+  "udp_output": {{ "enabled": true, {output} }},
+  "input": {{ "sps": 2048000, "frequency": 144000000, "signal": "iq", "driver": {{ "kind": "stdin", "format": "u8" }} }}
+}} ] }}"#
+            ),
+        )
+    };
+
+    let cfg = load_from_files(
+        &config,
+        &receivers(r#""frequency": 144800000, "mode": "fm""#),
+    )
+    .unwrap();
+    let output = &cfg.active_receiver().unwrap().udp_output;
+    assert_eq!(output.target, "127.0.0.1:7355");
+    assert_eq!(output.packet_ms, 20);
+    assert_eq!(
+        output.demodulation(),
+        Some(novasdr_core::dsp::demod::DemodulationMode::Fm)
+    );
+
+    for bad in [
+        r#""target": "localhost""#,
+        r#""mode": "FMX""#,
+        r#""packet_ms": 0"#,
+    ] {
+        let err = load_from_files(&config, &receivers(bad)).unwrap_err();
+        assert!(err.to_string().contains("udp_output"), "{bad}: {err}");
+    }
+}
+
+#[test]
+fn receiver_reloads_are_classified() {
+    use novasdr_core::config::{ReceiverConfig, ReceiverReload};
+    let receiver = |extra: &str, driver: &str, sps: u32| -> ReceiverConfig {
+        serde_json::from_str(&format!(
+            r#"{{ "id": "a", {extra} "input": {{ "sps": {sps}, "frequency": 100000000, "signal": "iq", "driver": {driver} }} }}"#
+        ))
+        .unwrap()
+    };
+    let stdin = r#"{ "kind": "stdin", "format": "u8" }"#;
+    let fifo = r#"{ "kind": "fifo", "path": "/tmp/iq", "format": "u8" }"#;
+    let base = receiver("", stdin, 2_048_000);
+
+    assert_eq!(base.reload_to(&base.clone()), ReceiverReload::Unchanged);
+    let renamed = receiver(
+        r#""name": "HF", "limits": { "audio": 5 }, "udp_output": { "enabled": true },"#,
+        stdin,
+        2_048_000,
+    );
+    assert_eq!(base.reload_to(&renamed), ReceiverReload::Settings);
+    let moved = receiver(r#""name": "HF","#, fifo, 2_048_000);
+    assert_eq!(base.reload_to(&moved), ReceiverReload::Input);
+    let faster = receiver("", fifo, 2_400_000);
+    assert_eq!(base.reload_to(&faster), ReceiverReload::Restart);
+}
+
+#[test]
+fn tx_needs_soapysdr_and_band_limits() {
+    let config = write_temp(
+        "config.json",
+        r#"{
+  "server": { "port": 9002, "host": "0.0.0.0", "html_root": "frontend/dist/", "otherusers": 1, "threads": 1 },
+  "websdr": { "name": "NovaSDR" },
+  "limits": { "audio": 1, "waterfall": 1, "events": 1 },
+  "active_receiver_id": "rx0"
+}"#,
+    );
+    let receivers = |driver: &str, tx: &str| {
+        write_temp(
+            "receivers.json",
+            &format!(
+                r#"{{ "receivers": [ {{
+  "id": "rx0", "tx": {tx},
+  "input": {{ "sps": 2000000, "frequency": 14100000, "signal": "iq", "driver": {driver} }}
+}} ] }}"#
+            ),
+        )
+    };
+    let soapy = r#"{ "kind": "soapysdr", "device": "driver=hackrf", "format": "cs16" }"#;
+    let tx = r#"{ "enabled": true, "max_gain_db": 20, "bands": [
+      { "name": "20m", "start": 14000000, "end": 14350000 },
+      { "name": "40m", "start": 7000000, "end": 7200000, "max_gain_db": 10 } ] }"#;
+
+    let cfg = load_from_files(&config, &receivers(soapy, tx)).unwrap();
+    let tx = &cfg.active_receiver().unwrap().tx;
+    assert_eq!(tx.max_seconds, 180);
+    let band = tx.band_for(7_100_000, 7_103_000).unwrap();
+    assert_eq!(tx.gain_limit(band), 10.0);
+    assert_eq!(
+        tx.gain_limit(tx.band_for(14_200_000, 14_203_000).unwrap()),
+        20.0
+    );
+    assert!(tx.band_for(14_349_000, 14_352_000).is_none());
+
+    let stdin = r#"{ "kind": "stdin", "format": "u8" }"#;
+    let bands = r#"{ "enabled": true, "bands": [ { "start": 14000000, "end": 14350000 } ] }"#;
+    let err = load_from_files(&config, &receivers(stdin, bands)).unwrap_err();
+    assert!(err.to_string().contains("tx needs"), "{err}");
+    let err = load_from_files(&config, &receivers(soapy, r#"{ "enabled": true }"#)).unwrap_err();
+    assert!(err.to_string().contains("tx.bands"), "{err}");
+    let odd =
+        r#"{ "enabled": true, "sample_rate": 1000000, "bands": [ { "start": 1, "end": 2 } ] }"#;
+    let err = load_from_files(&config, &receivers(soapy, odd)).unwrap_err();
+    assert!(err.to_string().contains("tx.sample_rate"), "{err}");
+}
+
+#[test]
+fn synthetic_scenario_loads_and_stays_in_the_input() {
+    let config = write_temp(
+        "config.json",
+        r#"{
+  "server": { "port": 9002, "host": "0.0.0.0", "html_root": "frontend/dist/", "otherusers": 1, "threads": 1 },
+  "websdr": { "name": "NovaSDR" },
+  "limits": { "audio": 1, "waterfall": 1, "events": 1 },
+  "active_receiver_id": "rx0"
+}"#,
+    );
+    let receivers = |signals: &str| {
+        write_temp(
+            "receivers.json",
+            &format!(
+                r#"{{ "receivers": [ {{
+  "id": "rx0",
+  "input": {{ "sps": 192000, "frequency": 7100000, "signal": "iq",
+    "driver": {{ "kind": "synthetic", "scenario": {{ "seed": 7, "signals": {signals},
+      "noise_bands": [ {{ "start": 7020000, "end": 7040000, "level_db": -60 }} ] }} }} }}
+}} ] }}"#
+            ),
+        )
+    };
+    let signals = r#"[
+      { "type": "carrier", "frequency": 7030000, "level_db": -30 },
+      { "type": "ssb", "frequency": 7100000, "level_db": -20, "sideband": "lsb" },
+      { "type": "fm", "frequency": 7150000, "level_db": -40 } ]"#;
+
+    let cfg = load_from_files(&config, &receivers(signals)).unwrap();
+    let rx = cfg.active_receiver().unwrap();
+    let novasdr_core::config::InputDriver::Synthetic { scenario } = &rx.input.driver else {
+        panic!("expected synthetic driver, got {:?}", rx.input.driver);
+    };
+    assert_eq!(scenario.seed, 7);
+    assert_eq!(scenario.noise_floor_db, -80.0);
+    assert_eq!(scenario.signals.len(), 3);
+    assert_eq!(
+        scenario.signals[1],
+        novasdr_core::config::SyntheticSignal::Ssb {
+            frequency: 7_100_000,
+            level_db: -20.0,
+            sideband: novasdr_core::config::Sideband::Lsb,
+            audio: None,
+        }
+    );
+    assert_eq!(
+        scenario.signals[2],
+        novasdr_core::config::SyntheticSignal::Fm {
+            frequency: 7_150_000,
+            level_db: -40.0,
+            tone_hz: 1_000.0,
+        }
+    );
+
+    let outside = r#"[ { "type": "carrier", "frequency": 7200000, "level_db": -30 } ]"#;
+    let err = load_from_files(&config, &receivers(outside)).unwrap_err();
+    assert!(err.to_string().contains("outside the input"), "{err}");
+}
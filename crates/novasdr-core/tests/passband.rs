@@ -0,0 +1,75 @@
+use novasdr_core::dsp::passband::PassbandFilter;
+use num_complex::Complex32;
+
+const RATE: usize = 12_000;
+const BLOCK: usize = 256;
+
+/// Steady-state RMS of a complex tone at `freq` after filtering.
+fn tone_gain(filter: &mut PassbandFilter, freq: f32) -> f32 {
+    filter.reset();
+    let mut energy = 0.0f32;
+    let mut count = 0usize;
+    for b in 0..40 {
+        let mut block: Vec<Complex32> = (0..BLOCK)
+            .map(|i| {
+                let n = (b * BLOCK + i) as f32;
+                Complex32::from_polar(1.0, std::f32::consts::TAU * freq * n / RATE as f32)
+            })
+            .collect();
+        filter.process(&mut block);
+        if b >= 20 {
+            energy += block.iter().map(|c| c.norm_sqr()).sum::<f32>();
+            count += block.len();
+        }
+    }
+    (energy / count as f32).sqrt()
+}
+
+#[test]
+fn passband_filter_attenuates_outside_the_edges() {
+    let mut filter = PassbandFilter::new(BLOCK, RATE);
+    filter.set_passband(Some((300.0, 2_700.0)), 200.0);
+    assert!(filter.is_active());
+
+    let pass = tone_gain(&mut filter, 1_500.0);
+    assert!((pass - 1.0).abs() < 0.01, "passband gain {pass}");
+    // Half of the transition band past each edge is stopband.
+    for freq in [-1_500.0, 0.0, 3_000.0, 4_500.0] {
+        let stop = tone_gain(&mut filter, freq);
+        assert!(stop < 0.01, "{freq} Hz leaks through at {stop}");
+    }
+}
+
+#[test]
+fn passband_filter_delays_impulses_by_half_its_length() {
+    let mut filter = PassbandFilter::new(BLOCK, RATE);
+    filter.set_passband(Some((-3_000.0, 3_000.0)), 400.0);
+    let delay = filter.delay();
+    assert!(delay > 0 && delay < 2 * BLOCK, "delay {delay}");
+
+    let mut out = Vec::new();
+    for b in 0..4 {
+        let mut block = vec![Complex32::new(0.0, 0.0); BLOCK];
+        if b == 0 {
+            block[0] = Complex32::new(1.0, 0.0);
+        }
+        filter.process(&mut block);
+        out.extend(block);
+    }
+    let peak = out
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.norm().total_cmp(&b.1.norm()))
+        .map(|(i, _)| i);
+    assert_eq!(peak, Some(delay));
+}
+
+#[test]
+fn empty_passband_bypasses_the_filter() {
+    let mut filter = PassbandFilter::new(BLOCK, RATE);
+    filter.set_passband(Some((1_000.0, 1_000.0)), 200.0);
+    assert!(!filter.is_active());
+    let mut block = vec![Complex32::new(0.5, -0.5); BLOCK];
+    filter.process(&mut block);
+    assert!(block.iter().all(|c| *c == Complex32::new(0.5, -0.5)));
+}
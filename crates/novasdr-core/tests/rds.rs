@@ -0,0 +1,125 @@
+use novasdr_core::rds::{self, BlockSync, GroupDecoder};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const PI_CODE: u16 = 0xD3C2;
+const PTY: u16 = 10;
+
+/// Type 0A groups carrying an 8-character programme service name.
+fn ps_groups(ps: &str) -> Vec<[u16; 4]> {
+    let b = ps.as_bytes();
+    (0..4)
+        .map(|seg| {
+            let block_b = (PTY << 5) | 0x0400 | seg as u16;
+            let chars = ((b[seg * 2] as u16) << 8) | b[seg * 2 + 1] as u16;
+            [PI_CODE, block_b, 0xE0CD, chars]
+        })
+        .collect()
+}
+
+/// Type 2A groups carrying RadioText terminated by a carriage return.
+fn rt_groups(text: &str) -> Vec<[u16; 4]> {
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.push(0x0D);
+    bytes.resize(bytes.len().div_ceil(4) * 4, b' ');
+    bytes
+        .chunks(4)
+        .enumerate()
+        .map(|(addr, c)| {
+            let block_b = (2 << 12) | 0x0400 | (PTY << 5) | addr as u16;
+            [
+                PI_CODE,
+                block_b,
+                ((c[0] as u16) << 8) | c[1] as u16,
+                ((c[2] as u16) << 8) | c[3] as u16,
+            ]
+        })
+        .collect()
+}
+
+fn broadcast_bits(repeats: usize) -> Vec<u8> {
+    let mut groups = ps_groups("NOVA FM ");
+    groups.extend(rt_groups("Hello from NovaSDR"));
+    (0..repeats)
+        .flat_map(|_| groups.iter().flat_map(|g| rds::encode_group(*g)))
+        .collect()
+}
+
+fn decode_bits(bits: &[u8]) -> GroupDecoder {
+    let mut sync = BlockSync::new();
+    let mut decoder = GroupDecoder::new();
+    for &bit in bits {
+        if let Some(group) = sync.push_bit(bit) {
+            decoder.apply(&group);
+        }
+    }
+    decoder
+}
+
+#[test]
+fn groups_decode_from_an_unaligned_bit_stream() {
+    // Start mid-block so sync has to be found.
+    let bits = broadcast_bits(3);
+    let decoder = decode_bits(&bits[37..]);
+    let info = decoder.info();
+    assert_eq!(info.pi, Some(PI_CODE));
+    assert_eq!(info.pty, Some(PTY as u8));
+    assert!(info.tp);
+    assert_eq!(info.ps.as_deref(), Some("NOVA FM "));
+    assert_eq!(info.radiotext.as_deref(), Some("Hello from NovaSDR"));
+}
+
+#[test]
+fn corrupted_blocks_are_dropped() {
+    let mut bits = broadcast_bits(3);
+    // Flip one bit in every block of the first pass; the later passes fill the gaps.
+    for block in 0..bits.len() / rds::BLOCK_BITS / 3 {
+        bits[block * rds::BLOCK_BITS + 5] ^= 1;
+    }
+    let decoder = decode_bits(&bits);
+    assert_eq!(decoder.info().ps.as_deref(), Some("NOVA FM "));
+    assert_eq!(
+        decoder.info().radiotext.as_deref(),
+        Some("Hello from NovaSDR")
+    );
+}
+
+#[test]
+fn rds_decodes_from_fm_multiplex() {
+    let mpx_rate = 240_000.0f64;
+    let bits = broadcast_bits(4);
+    let samples_per_bit = mpx_rate / rds::BIT_RATE;
+    let total = (bits.len() as f64 * samples_per_bit) as usize;
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut symbol = false;
+    let mut current_bit = usize::MAX;
+    let mut mpx = Vec::with_capacity(total);
+    for n in 0..total {
+        let t = n as f64 / mpx_rate;
+        let pos = n as f64 / samples_per_bit;
+        let bit_idx = pos as usize;
+        if bit_idx != current_bit {
+            current_bit = bit_idx;
+            // Differential coding, as done by the transmitter.
+            symbol ^= bits[bit_idx] == 1;
+        }
+        let first_half = pos.fract() < 0.5;
+        let chip = if symbol == first_half { 1.0 } else { -1.0 };
+        let tau = std::f64::consts::TAU;
+        let audio = 0.45 * (tau * 1_000.0 * t).sin();
+        let pilot = 0.09 * (tau * 19_000.0 * t).sin();
+        let stereo = 0.3 * (tau * 400.0 * t).sin() * (tau * 38_000.0 * t).sin();
+        let rds = 0.04 * chip * (tau * rds::SUBCARRIER_HZ * t + 0.7).sin();
+        let noise = 0.01 * (rng.gen::<f64>() - 0.5);
+        mpx.push((audio + pilot + stereo + rds + noise) as f32);
+    }
+
+    let mut receiver = rds::Receiver::new(mpx_rate);
+    for chunk in mpx.chunks(4096) {
+        receiver.process(chunk);
+    }
+    let info = receiver.info();
+    assert_eq!(info.pi, Some(PI_CODE));
+    assert_eq!(info.ps.as_deref(), Some("NOVA FM "));
+    assert_eq!(info.radiotext.as_deref(), Some("Hello from NovaSDR"));
+}
@@ -1,6 +1,6 @@
 use novasdr_core::config::{
     AudioCompression, Config, InputDriver, Limits, ReceiverConfig, ReceiverDefaults, ReceiverInput,
-    SampleFormat, Server, SignalType, Updates, WaterfallCompression, WebSdr,
+    SampleFormat, Server, SignalType, Updates, VulkanDevice, WaterfallCompression, WebSdr,
 };
 
 fn base_config(signal: SignalType) -> Config {
@@ -8,18 +8,39 @@ fn base_config(signal: SignalType) -> Config {
         id: "rx0".to_string(),
         enabled: true,
         name: "rx0".to_string(),
+        maintenance: novasdr_core::config::ReceiverMaintenance::default(),
+        skimmer: novasdr_core::config::SkimmerConfig::default(),
+        capture_triggers: Vec::new(),
+        limits: Default::default(),
+        fft_tap: Default::default(),
+        acars: Default::default(),
+        replay: Default::default(),
+        channel_dump: Default::default(),
+        black_box: Default::default(),
+        tx: Default::default(),
+        udp_output: Default::default(),
+        band_schedule: Vec::new(),
+        accuracy: Default::default(),
         input: ReceiverInput {
             sps: 2_000_000,
             frequency: 7_100_000,
             signal,
+            decimation: 1,
             fft_size: 131_072,
             brightness_offset: 0,
             audio_sps: 12_000,
             waterfall_size: 1024,
+            waterfall_max_fps: 10.0,
             waterfall_compression: WaterfallCompression::Zstd,
             audio_compression: AudioCompression::Adpcm,
+            shared_channels: true,
             smeter_offset: 0,
+            smeter_calibration: None,
+            dc_suppression: Default::default(),
+            window: Default::default(),
             accelerator: novasdr_core::config::Accelerator::None,
+            accelerator_fallback: None,
+            vulkan_device: None,
             driver: InputDriver::Stdin {
                 format: SampleFormat::S16,
             },
@@ -38,6 +59,23 @@ fn base_config(signal: SignalType) -> Config {
         websdr: WebSdr::default(),
         limits: Limits::default(),
         updates: Updates::default(),
+        admin: novasdr_core::config::Admin::default(),
+        pskreporter: novasdr_core::config::PskReporter::default(),
+        wsprnet: novasdr_core::config::Wsprnet::default(),
+        rbn: novasdr_core::config::Rbn::default(),
+        capture: novasdr_core::config::Capture::default(),
+        geoip: novasdr_core::config::GeoIp::default(),
+        export: novasdr_core::config::Export::default(),
+        freqdb: novasdr_core::config::FreqDb::default(),
+        accounts: novasdr_core::config::Accounts::default(),
+        rtl_tcp: novasdr_core::config::RtlTcp::default(),
+        crash_reports: novasdr_core::config::CrashReports::default(),
+        streams: novasdr_core::config::Streams::default(),
+        iq: novasdr_core::config::IqOutput::default(),
+        diversity: novasdr_core::config::Diversity::default(),
+        recordings: novasdr_core::config::Recordings::default(),
+        time_shift: novasdr_core::config::TimeShift::default(),
+        disk_guard: novasdr_core::config::DiskGuard::default(),
         receivers: vec![receiver],
         active_receiver_id: "rx0".to_string(),
     }
@@ -64,3 +102,103 @@ fn runtime_real_basefreq_and_defaults() {
     assert_eq!(rt.basefreq, 7_100_000);
     assert_eq!(rt.total_bandwidth, 1_000_000);
 }
+
+#[test]
+fn runtime_waterfall_frame_skip_follows_max_fps() {
+    // 2 MS/s with fft_size 131072 gives ~30.5 FFT frames per second.
+    let mut cfg = base_config(SignalType::Iq);
+    assert_eq!(cfg.runtime().unwrap().waterfall_frame_skip, 4);
+
+    cfg.receivers[0].input.waterfall_max_fps = 5.0;
+    assert_eq!(cfg.runtime().unwrap().waterfall_frame_skip, 7);
+
+    cfg.receivers[0].input.waterfall_max_fps = 100.0;
+    assert_eq!(cfg.runtime().unwrap().waterfall_frame_skip, 1);
+
+    cfg.receivers[0].input.waterfall_max_fps = 0.0;
+    assert!(cfg.runtime().is_err());
+}
+
+#[test]
+fn runtime_decimation_divides_the_fft_rate() {
+    let mut cfg = base_config(SignalType::Iq);
+    cfg.receivers[0].input.decimation = 4;
+    let rt = cfg.runtime().unwrap();
+    assert_eq!(rt.input_sps, 2_000_000);
+    assert_eq!(rt.sps, 500_000);
+    assert_eq!(rt.total_bandwidth, 500_000);
+    assert_eq!(rt.basefreq, 6_850_000);
+    // Four times fewer FFT frames per second: ~7.6, under the 10 fps cap.
+    assert_eq!(rt.waterfall_frame_skip, 1);
+
+    for bad in [0, 3, 128] {
+        cfg.receivers[0].input.decimation = bad;
+        assert!(cfg.runtime().is_err(), "{bad}");
+    }
+    cfg.receivers[0].input.decimation = 16;
+    assert!(cfg.runtime().is_ok());
+    cfg.receivers[0].input.sps = 2_000_001;
+    cfg.receivers[0].input.decimation = 2;
+    assert!(cfg.runtime().is_err());
+}
+
+#[test]
+fn runtime_validates_vulkan_device_pci_ids() {
+    let d: VulkanDevice = serde_json::from_str("1").unwrap();
+    assert_eq!(d, VulkanDevice::Index(1));
+    let d: VulkanDevice = serde_json::from_str("\"10de:2684\"").unwrap();
+    assert_eq!(d, VulkanDevice::PciId("10de:2684".to_string()));
+    assert_eq!(
+        VulkanDevice::parse_pci_id("10DE:2684").unwrap(),
+        (0x10de, 0x2684)
+    );
+
+    let mut cfg = base_config(SignalType::Iq);
+    cfg.receivers[0].input.vulkan_device = Some(VulkanDevice::PciId("10de:2684".to_string()));
+    assert!(cfg.runtime().is_ok());
+    for bad in ["10de", "10de:xyz", "10de:12345"] {
+        cfg.receivers[0].input.vulkan_device = Some(VulkanDevice::PciId(bad.to_string()));
+        assert!(cfg.runtime().is_err(), "{bad:?}");
+    }
+}
+
+#[test]
+fn runtime_fit_window_to_mode_replaces_incompatible_windows() {
+    use novasdr_core::dsp::demod::DemodulationMode;
+
+    let rt = base_config(SignalType::Iq).runtime().unwrap();
+    let m = rt.default_m;
+    let usb = rt.mode_window("USB", m);
+    assert_eq!((rt.default_l, rt.default_r), usb);
+    assert_eq!(
+        rt.fit_window_to_mode(DemodulationMode::Usb, usb.0, usb.1, m),
+        None
+    );
+
+    // A WBFM-wide window is too wide for SSB and gets the default USB passband.
+    let (wl, wr) = rt.mode_window("WBFM", m);
+    assert!(wr - wl > rt.hz_to_bins(6_000) as i32);
+    assert_eq!(
+        rt.fit_window_to_mode(DemodulationMode::Usb, wl, wr, m),
+        Some(usb)
+    );
+    assert_eq!(
+        rt.fit_window_to_mode(DemodulationMode::Wbfm, usb.0, usb.1, m),
+        None
+    );
+
+    // An upper-sideband window holds nothing for LSB, and AM needs the carrier inside.
+    assert_eq!(
+        rt.fit_window_to_mode(DemodulationMode::Lsb, usb.0, usb.1, m),
+        Some(rt.mode_window("LSB", m))
+    );
+    assert_eq!(
+        rt.fit_window_to_mode(DemodulationMode::Am, usb.0, usb.1, m),
+        Some(rt.mode_window("AM", m))
+    );
+    let (al, ar) = rt.mode_window("AM", m);
+    assert_eq!(
+        rt.fit_window_to_mode(DemodulationMode::Sam, al, ar, m),
+        None
+    );
+}
@@ -9,18 +9,39 @@ fn runtime_defaults_use_configured_modulation() {
         id: "rx0".to_string(),
         enabled: true,
         name: "rx0".to_string(),
+        maintenance: novasdr_core::config::ReceiverMaintenance::default(),
+        skimmer: novasdr_core::config::SkimmerConfig::default(),
+        capture_triggers: Vec::new(),
+        limits: Default::default(),
+        fft_tap: Default::default(),
+        acars: Default::default(),
+        replay: Default::default(),
+        channel_dump: Default::default(),
+        black_box: Default::default(),
+        tx: Default::default(),
+        udp_output: Default::default(),
+        band_schedule: Vec::new(),
+        accuracy: Default::default(),
         input: ReceiverInput {
             sps: 60_000_000,
             frequency: 60_000_000,
             signal: SignalType::Real,
+            decimation: 1,
             fft_size: 1_048_576,
             brightness_offset: 0,
             audio_sps: 12_000,
             waterfall_size: 1024,
+            waterfall_max_fps: 10.0,
             waterfall_compression: WaterfallCompression::Zstd,
             audio_compression: AudioCompression::Adpcm,
+            shared_channels: true,
             smeter_offset: 0,
+            smeter_calibration: None,
+            dc_suppression: Default::default(),
+            window: Default::default(),
             accelerator: Accelerator::Clfft,
+            accelerator_fallback: None,
+            vulkan_device: None,
             driver: InputDriver::Stdin {
                 format: SampleFormat::S16,
             },
@@ -39,6 +60,23 @@ fn runtime_defaults_use_configured_modulation() {
         websdr: WebSdr::default(),
         limits: Limits::default(),
         updates: Updates::default(),
+        admin: novasdr_core::config::Admin::default(),
+        pskreporter: novasdr_core::config::PskReporter::default(),
+        wsprnet: novasdr_core::config::Wsprnet::default(),
+        rbn: novasdr_core::config::Rbn::default(),
+        capture: novasdr_core::config::Capture::default(),
+        geoip: novasdr_core::config::GeoIp::default(),
+        export: novasdr_core::config::Export::default(),
+        freqdb: novasdr_core::config::FreqDb::default(),
+        accounts: novasdr_core::config::Accounts::default(),
+        rtl_tcp: novasdr_core::config::RtlTcp::default(),
+        crash_reports: novasdr_core::config::CrashReports::default(),
+        streams: novasdr_core::config::Streams::default(),
+        iq: novasdr_core::config::IqOutput::default(),
+        diversity: novasdr_core::config::Diversity::default(),
+        recordings: novasdr_core::config::Recordings::default(),
+        time_shift: novasdr_core::config::TimeShift::default(),
+        disk_guard: novasdr_core::config::DiskGuard::default(),
         receivers: vec![receiver],
         active_receiver_id: "rx0".to_string(),
     };
@@ -60,18 +98,39 @@ fn runtime_defaults_respect_configured_ssb_passband() {
         id: "rx0".to_string(),
         enabled: true,
         name: "rx0".to_string(),
+        maintenance: novasdr_core::config::ReceiverMaintenance::default(),
+        skimmer: novasdr_core::config::SkimmerConfig::default(),
+        capture_triggers: Vec::new(),
+        limits: Default::default(),
+        fft_tap: Default::default(),
+        acars: Default::default(),
+        replay: Default::default(),
+        channel_dump: Default::default(),
+        black_box: Default::default(),
+        tx: Default::default(),
+        udp_output: Default::default(),
+        band_schedule: Vec::new(),
+        accuracy: Default::default(),
         input: ReceiverInput {
             sps: 2_000_000,
             frequency: 7_100_000,
             signal: SignalType::Iq,
+            decimation: 1,
             fft_size: 131_072,
             brightness_offset: 0,
             audio_sps: 48_000,
             waterfall_size: 1024,
+            waterfall_max_fps: 10.0,
             waterfall_compression: WaterfallCompression::Zstd,
             audio_compression: AudioCompression::Adpcm,
+            shared_channels: true,
             smeter_offset: 0,
+            smeter_calibration: None,
+            dc_suppression: Default::default(),
+            window: Default::default(),
             accelerator: Accelerator::None,
+            accelerator_fallback: None,
+            vulkan_device: None,
             driver: InputDriver::Stdin {
                 format: SampleFormat::S16,
             },
@@ -90,6 +149,23 @@ fn runtime_defaults_respect_configured_ssb_passband() {
         websdr: WebSdr::default(),
         limits: Limits::default(),
         updates: Updates::default(),
+        admin: novasdr_core::config::Admin::default(),
+        pskreporter: novasdr_core::config::PskReporter::default(),
+        wsprnet: novasdr_core::config::Wsprnet::default(),
+        rbn: novasdr_core::config::Rbn::default(),
+        capture: novasdr_core::config::Capture::default(),
+        geoip: novasdr_core::config::GeoIp::default(),
+        export: novasdr_core::config::Export::default(),
+        freqdb: novasdr_core::config::FreqDb::default(),
+        accounts: novasdr_core::config::Accounts::default(),
+        rtl_tcp: novasdr_core::config::RtlTcp::default(),
+        crash_reports: novasdr_core::config::CrashReports::default(),
+        streams: novasdr_core::config::Streams::default(),
+        iq: novasdr_core::config::IqOutput::default(),
+        diversity: novasdr_core::config::Diversity::default(),
+        recordings: novasdr_core::config::Recordings::default(),
+        time_shift: novasdr_core::config::TimeShift::default(),
+        disk_guard: novasdr_core::config::DiskGuard::default(),
         receivers: vec![receiver],
         active_receiver_id: "rx0".to_string(),
     };
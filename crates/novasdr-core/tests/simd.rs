@@ -0,0 +1,82 @@
+use novasdr_core::dsp::simd::{self, SimdLevel};
+use num_complex::Complex32;
+
+/// Odd lengths exercise both the vector body and the scalar tail.
+const LEN: usize = 1031;
+
+fn iq(len: usize) -> Vec<Complex32> {
+    (0..len)
+        .map(|i| {
+            let t = i as f32;
+            Complex32::from_polar(
+                0.2 + (t * 0.37).sin().abs(),
+                t * 0.9 + (t * 0.013).sin() * 3.0,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn simd_kernels_match_scalar() {
+    let level = simd::detected();
+    let iq = iq(LEN);
+
+    let mut scalar = vec![0f32; LEN];
+    let mut vector = vec![0f32; LEN];
+    simd::am_envelope(SimdLevel::Scalar, &iq, &mut scalar);
+    simd::am_envelope(level, &iq, &mut vector);
+    assert_eq!(scalar, vector);
+
+    let prev = Complex32::new(0.0, -1.0);
+    let last_s = simd::polar_discriminator_fm(SimdLevel::Scalar, &iq, prev, &mut scalar);
+    let last_v = simd::polar_discriminator_fm(level, &iq, prev, &mut vector);
+    assert_eq!(last_s, last_v);
+    for (k, (s, v)) in scalar.iter().zip(vector.iter()).enumerate() {
+        assert!((s - v).abs() < 1e-4, "sample {k}: {s} vs {v}");
+    }
+
+    let mut pcm_s = vec![0i16; LEN];
+    let mut pcm_v = vec![0i16; LEN];
+    let mut audio: Vec<f32> = (0..LEN).map(|i| (i as f32 * 0.05).sin() * 1.3).collect();
+    audio[3] = f32::NAN;
+    audio[5] = f32::INFINITY;
+    audio[7] = -1e12;
+    simd::float_to_i16_centered(SimdLevel::Scalar, &audio, &mut pcm_s, 32768.0);
+    simd::float_to_i16_centered(level, &audio, &mut pcm_v, 32768.0);
+    assert_eq!(pcm_s, pcm_v);
+
+    let raw_u8: Vec<u8> = (0..LEN).map(|i| (i * 7) as u8).collect();
+    simd::u8_to_f32(SimdLevel::Scalar, &raw_u8, &mut scalar);
+    simd::u8_to_f32(level, &raw_u8, &mut vector);
+    assert_eq!(scalar, vector);
+
+    let raw_i16: Vec<i16> = (0..LEN)
+        .map(|i| (i as i32 * 977 - 500_000) as i16)
+        .collect();
+    simd::i16_to_f32(SimdLevel::Scalar, &raw_i16, &mut scalar);
+    simd::i16_to_f32(level, &raw_i16, &mut vector);
+    assert_eq!(scalar, vector);
+}
+
+#[test]
+fn fm_discriminator_handles_quadrants_and_silence() {
+    // Phase steps of every size and sign, including exactly +-pi and zero-magnitude samples.
+    let mut iq: Vec<Complex32> = (0..64)
+        .map(|i| Complex32::from_polar(1.0, i as f32 * i as f32 * 0.21))
+        .collect();
+    iq[10] = Complex32::new(0.0, 0.0);
+    iq[20] = Complex32::new(-1.0, 0.0);
+    iq[21] = Complex32::new(1.0, 0.0);
+    let mut scalar = vec![0f32; iq.len()];
+    let mut vector = vec![0f32; iq.len()];
+    simd::polar_discriminator_fm(
+        SimdLevel::Scalar,
+        &iq,
+        Complex32::new(1.0, 0.0),
+        &mut scalar,
+    );
+    simd::polar_discriminator_fm(simd::detected(), &iq, Complex32::new(1.0, 0.0), &mut vector);
+    for (k, (s, v)) in scalar.iter().zip(vector.iter()).enumerate() {
+        assert!((s - v).abs() < 1e-4, "sample {k}: {s} vs {v}");
+    }
+}
@@ -0,0 +1,53 @@
+use novasdr_core::smeter::{CalibrationPoint, SmeterCalibration};
+
+fn point(frequency: i64, offset_db: f32) -> CalibrationPoint {
+    CalibrationPoint {
+        frequency,
+        offset_db,
+    }
+}
+
+#[test]
+fn empty_calibration_applies_no_offset() {
+    let cal = SmeterCalibration::default();
+    assert_eq!(cal.offset_db(7_000_000), 0.0);
+    assert_eq!(cal.power_gain(7_000_000), 1.0);
+}
+
+#[test]
+fn offsets_interpolate_between_points_and_hold_at_the_ends() {
+    let cal = SmeterCalibration::new(vec![point(14_000_000, 6.0), point(4_000_000, 2.0)]);
+    assert_eq!(cal.offset_db(1_000_000), 2.0);
+    assert_eq!(cal.offset_db(30_000_000), 6.0);
+    assert!((cal.offset_db(9_000_000) - 4.0).abs() < 1e-4);
+    assert!((cal.power_gain(14_000_000) - 10f32.powf(0.6)).abs() < 1e-4);
+}
+
+#[test]
+fn set_point_replaces_nearby_point() {
+    let mut cal = SmeterCalibration::new(vec![point(7_000_000, 1.0), point(10_000_000, 2.0)]);
+    cal.set_point(point(7_000_500, 4.0));
+    assert_eq!(
+        cal.points(),
+        &[point(7_000_500, 4.0), point(10_000_000, 2.0)]
+    );
+}
+
+#[test]
+fn calibration_round_trips_through_file_and_missing_file_is_empty() {
+    let mut path = std::env::temp_dir();
+    path.push(format!(
+        "novasdr_smeter_{}_{}.json",
+        std::process::id(),
+        rand::random::<u32>()
+    ));
+    assert_eq!(
+        SmeterCalibration::load(&path).unwrap(),
+        SmeterCalibration::default()
+    );
+
+    let cal = SmeterCalibration::new(vec![point(3_500_000, -2.5), point(28_000_000, 5.0)]);
+    cal.save(&path).unwrap();
+    assert_eq!(SmeterCalibration::load(&path).unwrap(), cal);
+    let _ = std::fs::remove_file(&path);
+}
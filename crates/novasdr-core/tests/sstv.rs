@@ -0,0 +1,166 @@
+use novasdr_core::sstv::{encode, Decoder, Event, Image, Mode, MODES};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const RATE: f64 = 12_000.0;
+/// Noise added to the audio, about 18 dB below the signal; colour differences amplify it in the
+/// Robot modes.
+const NOISE: f32 = 0.15;
+/// Mean per-channel difference from the sent picture, in 8-bit levels.
+const MAX_MEAN_ERROR: f64 = 15.0;
+/// Leaders, break and VIS code.
+const VIS_HEADER_MS: f64 = 910.0;
+
+/// Bands of colour bars and of a horizontal ramp, so every channel carries detail.
+fn test_card(mode: Mode) -> Vec<u8> {
+    const BARS: [[u8; 3]; 6] = [
+        [255, 255, 255],
+        [255, 255, 0],
+        [0, 255, 255],
+        [0, 255, 0],
+        [255, 0, 255],
+        [255, 0, 0],
+    ];
+    let (width, height) = (mode.width(), mode.height());
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            if y / 16 % 2 == 0 {
+                rgb.extend_from_slice(&BARS[x * BARS.len() / width]);
+            } else {
+                let v = (x * 255 / (width - 1)) as u8;
+                rgb.extend_from_slice(&[v, 255 - v, v / 2]);
+            }
+        }
+    }
+    rgb
+}
+
+/// Audio with noise, in 20 ms frames as the audio pipeline hands it over, and the events it
+/// produced.
+fn run(decoder: &mut Decoder, audio: &[f32], rate: f64, rng: &mut StdRng) -> Vec<Event> {
+    let noisy: Vec<f32> = audio
+        .iter()
+        .map(|&x| x + rng.gen_range(-NOISE..NOISE))
+        .collect();
+    let mut events = Vec::new();
+    for chunk in noisy.chunks((rate / 50.0) as usize) {
+        decoder.process(chunk);
+        events.extend(std::iter::from_fn(|| decoder.pop_event()));
+    }
+    events
+}
+
+fn finished(events: &[Event]) -> Vec<&Image> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Finished(image) => Some(image),
+            _ => None,
+        })
+        .collect()
+}
+
+fn mean_error(image: &Image, expected: &[u8]) -> f64 {
+    let n = image.rgb.len();
+    image
+        .rgb
+        .iter()
+        .zip(&expected[..n])
+        .map(|(&a, &b)| (f64::from(a) - f64::from(b)).abs())
+        .sum::<f64>()
+        / n as f64
+}
+
+#[test]
+fn every_mode_is_decoded() {
+    // The start of each picture is enough to check the mode's timing; the carrier then goes.
+    const LINES: usize = 48;
+    for mode in MODES {
+        let card = test_card(mode);
+        let mut audio = encode(mode, &card, RATE);
+        let header = (VIS_HEADER_MS * RATE / 1000.0) as usize;
+        audio.truncate(header + (audio.len() - header) * LINES / mode.height());
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut decoder = Decoder::new(RATE);
+        let mut events = run(&mut decoder, &audio, RATE, &mut rng);
+        // Without sync pulses the picture ends after a few lines.
+        events.extend(run(
+            &mut decoder,
+            &vec![0.0; RATE as usize * 5],
+            RATE,
+            &mut rng,
+        ));
+
+        assert!(
+            matches!(events.first(), Some(Event::Started(m)) if *m == mode),
+            "{mode}: {:?}",
+            events.first()
+        );
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::Progress(m, 16) if *m == mode)));
+        let images = finished(&events);
+        assert_eq!(images.len(), 1, "{mode}");
+        let image = images[0];
+        assert!(!image.complete());
+        assert!(
+            (LINES - 2..=LINES).contains(&image.height),
+            "{mode}: {} lines",
+            image.height
+        );
+        assert_eq!(image.rgb.len(), image.width * image.height * 3);
+        let error = mean_error(image, &card);
+        assert!(error < MAX_MEAN_ERROR, "{mode}: mean error {error:.1}");
+        assert!(decoder.receiving().is_none());
+    }
+}
+
+#[test]
+fn whole_picture_is_decoded() {
+    for rate in [RATE, 48_000.0] {
+        let mode = Mode::Robot36;
+        let mut audio = encode(mode, &test_card(mode), rate);
+        audio.extend(std::iter::repeat_n(0.0, (rate / 2.0) as usize));
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut decoder = Decoder::new(rate);
+        let events = run(&mut decoder, &audio, rate, &mut rng);
+        let images = finished(&events);
+        assert_eq!(images.len(), 1, "{rate}");
+        assert!(images[0].complete(), "{rate}: {} lines", images[0].height);
+        let error = mean_error(images[0], &test_card(mode));
+        assert!(error < MAX_MEAN_ERROR, "{rate}: mean error {error:.1}");
+    }
+}
+
+#[test]
+fn noise_and_tones_start_no_picture() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut decoder = Decoder::new(RATE);
+    let noise: Vec<f32> = (0..RATE as usize * 10)
+        .map(|_| rng.gen_range(-1.0..1.0))
+        .collect();
+    // A steady 1900 Hz carrier and a keyed 1200 Hz one look like leader and sync, but carry
+    // no VIS code.
+    let carrier: Vec<f32> = (0..RATE as usize * 3)
+        .map(|i| {
+            let t = i as f64 / RATE;
+            let hz = if (t * 2.0).fract() < 0.8 {
+                1900.0
+            } else {
+                1200.0
+            };
+            (std::f64::consts::TAU * hz * t).sin() as f32
+        })
+        .collect();
+    let events = run(&mut decoder, &[noise, carrier].concat(), RATE, &mut rng);
+    assert!(events.is_empty(), "{events:?}");
+}
+
+#[test]
+fn vis_codes_name_their_modes() {
+    for mode in MODES {
+        assert_eq!(Mode::from_vis(mode.vis()), Some(mode));
+    }
+    assert_eq!(Mode::from_vis(0), None);
+    assert_eq!(Mode::Scottie1.to_string(), "Scottie 1");
+}
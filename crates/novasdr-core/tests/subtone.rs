@@ -0,0 +1,141 @@
+use novasdr_core::dsp::subtone::{dcs_word, Subtone, SubtoneDetector, DCS_BIT_RATE};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::f32::consts::TAU;
+
+const RATE: usize = 12_000;
+/// Sub-audio deviation and a louder voice band on top of it, in discriminator units.
+const SUBTONE_LEVEL: f32 = 0.05;
+const VOICE_LEVEL: f32 = 0.2;
+
+/// Seconds of discriminator output: the sub-audio signal, a 1 kHz "voice", noise and a tuning
+/// offset.
+fn channel(seconds: f32, subaudio: impl Fn(f32) -> f32, rng: &mut StdRng) -> Vec<f32> {
+    (0..(seconds * RATE as f32) as usize)
+        .map(|i| {
+            let t = i as f32 / RATE as f32;
+            subaudio(t)
+                + VOICE_LEVEL * (TAU * 1_000.0 * t).sin()
+                + rng.gen_range(-0.05..0.05)
+                + 0.01
+        })
+        .collect()
+}
+
+fn ctcss(hz: f32) -> impl Fn(f32) -> f32 {
+    move |t| SUBTONE_LEVEL * (TAU * hz * t).sin()
+}
+
+/// NRZ DCS, bits in send order, ones as positive deviation.
+fn dcs(word: u32) -> impl Fn(f32) -> f32 {
+    move |t| {
+        let bit = (t * DCS_BIT_RATE) as u64 % 23;
+        if word >> bit & 1 == 1 {
+            SUBTONE_LEVEL
+        } else {
+            -SUBTONE_LEVEL
+        }
+    }
+}
+
+/// Runs the detector in 20 ms frames and returns every tone it reported, in order.
+fn run(detector: &mut SubtoneDetector, samples: &[f32]) -> Vec<Option<Subtone>> {
+    let mut reports = Vec::new();
+    for chunk in samples.chunks(RATE / 50) {
+        if detector.process(chunk) {
+            reports.push(detector.tone());
+        }
+    }
+    reports
+}
+
+#[test]
+fn ctcss_tone_is_found_under_voice() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut detector = SubtoneDetector::new(RATE);
+    let reports = run(&mut detector, &channel(3.0, ctcss(88.5), &mut rng));
+    assert_eq!(reports, vec![Some(Subtone::Ctcss(885))]);
+
+    // The transmitter drops the tone when the carrier ends.
+    let reports = run(&mut detector, &channel(2.0, |_| 0.0, &mut rng));
+    assert_eq!(reports, vec![None]);
+}
+
+#[test]
+fn closest_ctcss_tones_are_told_apart() {
+    for (hz, tenths) in [(150.0, 1500), (151.4, 1514)] {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut detector = SubtoneDetector::new(RATE);
+        run(&mut detector, &channel(3.0, ctcss(hz), &mut rng));
+        assert_eq!(detector.tone(), Some(Subtone::Ctcss(tenths)));
+    }
+}
+
+#[test]
+fn dcs_code_is_decoded() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut detector = SubtoneDetector::new(RATE);
+    let word = dcs_word(0o023);
+    let reports = run(&mut detector, &channel(3.0, dcs(word), &mut rng));
+    let code = Subtone::Dcs {
+        code: 0o023,
+        inverted: false,
+    };
+    assert_eq!(reports, vec![Some(code)]);
+
+    // Inverted 023 is the same bit stream as normal 047, which is how radios read it too.
+    let mut detector = SubtoneDetector::new(RATE);
+    run(
+        &mut detector,
+        &channel(3.0, dcs(!word & 0x7F_FFFF), &mut rng),
+    );
+    assert_eq!(
+        detector.tone(),
+        Some(Subtone::Dcs {
+            code: 0o047,
+            inverted: false,
+        })
+    );
+}
+
+#[test]
+fn voice_and_noise_carry_no_tone() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut detector = SubtoneDetector::new(RATE);
+    let noise: Vec<f32> = (0..RATE * 5).map(|_| rng.gen_range(-1.0..1.0)).collect();
+    let voice = channel(5.0, |_| 0.0, &mut rng);
+    let reports = run(&mut detector, &[noise, voice].concat());
+    assert!(reports.is_empty(), "{reports:?}");
+}
+
+#[test]
+fn tones_and_codes_round_trip_as_text() {
+    for text in ["88.5", "67.0", "254.1", "D023N", "D754I"] {
+        let tone: Subtone = text.parse().unwrap();
+        assert_eq!(tone.to_string(), text);
+        assert_eq!(serde_json::to_string(&tone).unwrap(), format!("\"{text}\""));
+    }
+    assert_eq!("d023".parse::<Subtone>().ok(), "D023N".parse().ok());
+    assert!("88.6".parse::<Subtone>().is_err());
+    assert!("D024N".parse::<Subtone>().is_err());
+    assert!(serde_json::from_str::<Subtone>("\"D999\"").is_err());
+}
+
+#[test]
+fn tone_squelch_command_takes_a_tone_or_null() {
+    use novasdr_core::protocol::ClientCommand;
+    let cmd: ClientCommand =
+        serde_json::from_str(r#"{"cmd":"tone_squelch","tone":"D023N"}"#).unwrap();
+    assert!(matches!(
+        cmd,
+        ClientCommand::ToneSquelch {
+            tone: Some(Subtone::Dcs { code: 0o023, .. })
+        }
+    ));
+    let cmd: ClientCommand = serde_json::from_str(r#"{"cmd":"tone_squelch","tone":null}"#).unwrap();
+    assert!(matches!(cmd, ClientCommand::ToneSquelch { tone: None }));
+    let cmd: ClientCommand = serde_json::from_str(r#"{"cmd":"subtone","enabled":true}"#).unwrap();
+    assert!(matches!(cmd, ClientCommand::Subtone { enabled: true }));
+    assert!(
+        serde_json::from_str::<ClientCommand>(r#"{"cmd":"tone_squelch","tone":"88.6"}"#).is_err()
+    );
+}
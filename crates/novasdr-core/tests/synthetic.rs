@@ -0,0 +1,163 @@
+use novasdr_core::config::{Sideband, SyntheticNoiseBand, SyntheticScenario, SyntheticSignal};
+use novasdr_core::dsp::synthetic::Generator;
+use rustfft::num_complex::Complex32;
+
+const SAMPLE_RATE: usize = 192_000;
+const CENTER: i64 = 7_100_000;
+
+fn scenario(signals: Vec<SyntheticSignal>) -> SyntheticScenario {
+    SyntheticScenario {
+        noise_floor_db: -100.0,
+        signals,
+        ..Default::default()
+    }
+}
+
+/// One second of IQ, after the modulators' filters have settled.
+fn render(scenario: &SyntheticScenario) -> Vec<Complex32> {
+    let mut generator = Generator::new(scenario, CENTER, SAMPLE_RATE, false).unwrap();
+    let mut raw = vec![0.0f32; 2 * SAMPLE_RATE];
+    generator.fill(&mut raw[..SAMPLE_RATE / 5]);
+    // Odd block sizes, as a reader asks for them.
+    for block in raw.chunks_mut(2 * 4_001) {
+        generator.fill(block);
+    }
+    raw.chunks_exact(2)
+        .map(|p| Complex32::new(p[0], p[1]))
+        .collect()
+}
+
+/// Amplitude of the component at `offset_hz` from the centre.
+fn level_at(samples: &[Complex32], offset_hz: f32) -> f32 {
+    let sum: Complex32 = samples
+        .iter()
+        .enumerate()
+        .map(|(n, &z)| {
+            z * Complex32::from_polar(
+                1.0,
+                -std::f32::consts::TAU * offset_hz * n as f32 / SAMPLE_RATE as f32,
+            )
+        })
+        .sum();
+    sum.norm() / samples.len() as f32
+}
+
+/// Power between `low` and `high` Hz from the centre.
+fn band_power(samples: &[Complex32], low: i32, high: i32) -> f32 {
+    let mut spectrum = samples.to_vec();
+    rustfft::FftPlanner::new()
+        .plan_fft_forward(spectrum.len())
+        .process(&mut spectrum);
+    let bin_hz = SAMPLE_RATE as f32 / spectrum.len() as f32;
+    let scale = (spectrum.len() as f32).powi(2);
+    (low..high)
+        .map(|f| {
+            let bin = (f as f32 / bin_hz).round() as isize;
+            spectrum[bin.rem_euclid(spectrum.len() as isize) as usize].norm_sqr() / scale
+        })
+        .sum()
+}
+
+fn rms_db(samples: &[Complex32]) -> f32 {
+    let power = samples.iter().map(|z| z.norm_sqr()).sum::<f32>() / samples.len() as f32;
+    10.0 * power.log10()
+}
+
+#[test]
+fn carriers_sit_at_their_frequency_and_level() {
+    let iq = render(&scenario(vec![
+        SyntheticSignal::Carrier {
+            frequency: CENTER + 25_000,
+            level_db: -20.0,
+        },
+        SyntheticSignal::Carrier {
+            frequency: CENTER - 40_000,
+            level_db: -40.0,
+        },
+    ]));
+    assert!((level_at(&iq, 25_000.0) - 0.1).abs() < 0.002);
+    assert!((level_at(&iq, -40_000.0) - 0.01).abs() < 0.0005);
+    assert!(level_at(&iq, 40_000.0) < 1e-4);
+}
+
+#[test]
+fn ssb_voice_stays_on_its_sideband() {
+    let ssb = |sideband| {
+        render(&scenario(vec![SyntheticSignal::Ssb {
+            frequency: CENTER,
+            level_db: -10.0,
+            sideband,
+            audio: None,
+        }]))
+    };
+    let usb = ssb(Sideband::Usb);
+    let upper = band_power(&usb, 200, 3_000);
+    let lower = band_power(&usb, -3_000, -200);
+    assert!(upper > 1e-4, "no voice: {upper}");
+    assert!(upper / lower > 1e3, "{upper} {lower}");
+    let lsb = ssb(Sideband::Lsb);
+    assert!(band_power(&lsb, -3_000, -200) / band_power(&lsb, 200, 3_000) > 1e3);
+}
+
+#[test]
+fn fm_tone_has_a_constant_envelope_and_sidebands() {
+    let iq = render(&scenario(vec![SyntheticSignal::Fm {
+        frequency: CENTER + 10_000,
+        level_db: -6.0,
+        tone_hz: 1_000.0,
+    }]));
+    assert!((rms_db(&iq) + 6.0).abs() < 0.2);
+    // 2.5 kHz deviation at 1 kHz: the first sidebands are stronger than the carrier.
+    assert!(level_at(&iq, 11_000.0) > level_at(&iq, 10_000.0));
+    assert!(level_at(&iq, 9_000.0) > level_at(&iq, 10_000.0));
+}
+
+#[test]
+fn noise_floor_and_bands_have_their_level() {
+    let iq = render(&SyntheticScenario {
+        noise_floor_db: -40.0,
+        ..Default::default()
+    });
+    assert!((rms_db(&iq) + 40.0).abs() < 0.3, "{}", rms_db(&iq));
+
+    let iq = render(&SyntheticScenario {
+        noise_floor_db: -100.0,
+        noise_bands: vec![SyntheticNoiseBand {
+            start: CENTER + 10_000,
+            end: CENTER + 30_000,
+            level_db: -30.0,
+        }],
+        ..Default::default()
+    });
+    assert!((rms_db(&iq) + 30.0).abs() < 0.5, "{}", rms_db(&iq));
+    assert!(band_power(&iq, 15_000, 25_000) > 100.0 * band_power(&iq, -50_000, -30_000));
+}
+
+#[test]
+fn scenarios_repeat_for_a_seed() {
+    let mut s = scenario(vec![SyntheticSignal::Ssb {
+        frequency: CENTER,
+        level_db: -10.0,
+        sideband: Sideband::Usb,
+        audio: None,
+    }]);
+    s.noise_floor_db = -50.0;
+    let first = render(&s);
+    assert_eq!(first, render(&s));
+    s.seed = 1;
+    assert_ne!(first, render(&s));
+}
+
+#[test]
+fn real_inputs_put_signals_above_the_bottom() {
+    let s = scenario(vec![SyntheticSignal::Carrier {
+        frequency: CENTER + 30_000,
+        level_db: -20.0,
+    }]);
+    let mut generator = Generator::new(&s, CENTER, SAMPLE_RATE, true).unwrap();
+    let mut raw = vec![0.0f32; SAMPLE_RATE];
+    generator.fill(&mut raw);
+    let real: Vec<Complex32> = raw.iter().map(|&x| Complex32::new(x, 0.0)).collect();
+    // A real cosine splits its amplitude between both sides.
+    assert!((level_at(&real, 30_000.0) - 0.05).abs() < 0.002);
+}
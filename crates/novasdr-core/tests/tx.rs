@@ -0,0 +1,87 @@
+use novasdr_core::dsp::tx::{Modulator, TxMode, FM_DEVIATION_HZ, TX_AUDIO_RATE};
+use rustfft::num_complex::Complex32;
+
+const OUTPUT_RATE: usize = 48_000;
+
+fn tone(freq: f32, amplitude: f32) -> Vec<f32> {
+    (0..TX_AUDIO_RATE)
+        .map(|n| amplitude * (std::f32::consts::TAU * freq * n as f32 / TX_AUDIO_RATE as f32).sin())
+        .collect()
+}
+
+fn modulate(mode: TxMode, audio: &[f32]) -> Vec<Complex32> {
+    let mut modulator = Modulator::new(mode, OUTPUT_RATE);
+    let mut out = Vec::new();
+    // Uneven blocks, as audio arrives over the websocket.
+    for block in audio.chunks(250) {
+        modulator.process(block, &mut out);
+    }
+    out
+}
+
+/// Amplitude of the component at `freq` (negative below the carrier) in the second half.
+fn level_at(samples: &[Complex32], freq: f32) -> f32 {
+    let tail = &samples[samples.len() / 2..];
+    let sum: Complex32 = tail
+        .iter()
+        .enumerate()
+        .map(|(n, &z)| {
+            z * Complex32::from_polar(
+                1.0,
+                -std::f32::consts::TAU * freq * n as f32 / OUTPUT_RATE as f32,
+            )
+        })
+        .sum();
+    sum.norm() / tail.len() as f32
+}
+
+fn db(ratio: f32) -> f32 {
+    20.0 * ratio.log10()
+}
+
+#[test]
+fn ssb_puts_the_tone_on_one_side_of_the_carrier() {
+    let audio = tone(1_000.0, 0.5);
+    let usb = modulate(TxMode::Usb, &audio);
+    assert_eq!(usb.len(), audio.len() * OUTPUT_RATE / TX_AUDIO_RATE);
+    let wanted = level_at(&usb, 1_000.0);
+    assert!((wanted - 0.5).abs() < 0.05, "USB level {wanted}");
+    let image = level_at(&usb, -1_000.0);
+    assert!(db(wanted / image) > 40.0, "USB image {image}");
+
+    let lsb = modulate(TxMode::Lsb, &audio);
+    assert!((level_at(&lsb, -1_000.0) - 0.5).abs() < 0.05);
+    assert!(level_at(&lsb, 1_000.0) < 0.005);
+}
+
+#[test]
+fn ssb_leaves_out_audio_outside_the_voice_band_and_its_images() {
+    let usb = modulate(TxMode::Usb, &tone(5_000.0, 0.5));
+    for freq in [5_000.0, 7_000.0, 17_000.0] {
+        let level = level_at(&usb, freq);
+        assert!(db(level / 0.5) < -40.0, "{freq} Hz at {level}");
+    }
+}
+
+#[test]
+fn fm_has_a_constant_envelope_and_the_nominal_deviation() {
+    let out = modulate(TxMode::Fm, &tone(1_000.0, 1.0));
+    let tail = &out[out.len() / 2..];
+    assert!(tail.iter().all(|z| (z.norm() - 1.0).abs() < 1e-3));
+    let peak = tail
+        .windows(2)
+        .map(|w| (w[1] * w[0].conj()).arg().abs() * OUTPUT_RATE as f32 / std::f32::consts::TAU)
+        .fold(0.0f32, f32::max);
+    assert!(
+        (peak - FM_DEVIATION_HZ).abs() < 100.0,
+        "peak deviation {peak} Hz"
+    );
+}
+
+#[test]
+fn modes_parse_and_report_their_occupied_band() {
+    assert_eq!(TxMode::parse("usb"), Some(TxMode::Usb));
+    assert_eq!(TxMode::parse("FM"), Some(TxMode::Fm));
+    assert_eq!(TxMode::parse("AM"), None);
+    assert_eq!(TxMode::Lsb.occupied_hz(), (-3_000, 0));
+}
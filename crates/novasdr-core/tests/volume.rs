@@ -0,0 +1,48 @@
+use novasdr_core::dsp::volume::LoudnessVolume;
+
+const RATE: usize = 12_000;
+
+/// Steady-state RMS gain for a sine at `freq` through `volume`.
+fn tone_gain(volume: &mut LoudnessVolume, freq: f32) -> f32 {
+    volume.reset();
+    let mut samples: Vec<f32> = (0..RATE)
+        .map(|n| (std::f32::consts::TAU * freq * n as f32 / RATE as f32).sin())
+        .collect();
+    volume.process(&mut samples);
+    let tail = &samples[RATE / 2..];
+    (tail.iter().map(|s| s * s).sum::<f32>() / tail.len() as f32).sqrt() * std::f32::consts::SQRT_2
+}
+
+fn db(gain: f32) -> f32 {
+    20.0 * gain.log10()
+}
+
+#[test]
+fn volume_is_a_plain_gain_at_and_above_zero_db() {
+    let mut volume = LoudnessVolume::new(RATE);
+    assert!((tone_gain(&mut volume, 100.0) - 1.0).abs() < 1e-3);
+
+    volume.set_volume_db(6.0);
+    for freq in [100.0, 1_000.0, 3_000.0] {
+        assert!(
+            (db(tone_gain(&mut volume, freq)) - 6.0).abs() < 0.05,
+            "{freq} Hz"
+        );
+    }
+
+    volume.set_volume_db(100.0);
+    assert_eq!(volume.volume_db(), 12.0);
+    volume.set_volume_db(f32::NAN);
+    assert_eq!(volume.volume_db(), 0.0);
+}
+
+#[test]
+fn low_volumes_lift_the_bass() {
+    let mut volume = LoudnessVolume::new(RATE);
+    volume.set_volume_db(-40.0);
+    let bass = db(tone_gain(&mut volume, 50.0));
+    let treble = db(tone_gain(&mut volume, 3_000.0));
+    assert!((treble + 40.0).abs() < 1.0, "treble {treble} dB");
+    // A quarter of the attenuation comes back below the shelf corner.
+    assert!((bass + 30.0).abs() < 1.5, "bass {bass} dB");
+}
@@ -0,0 +1,36 @@
+use novasdr_core::dsp::waterfall_mask::{apply_waterfall_masks, WaterfallMask};
+
+#[test]
+fn masks_dim_overlapping_columns_on_every_level() {
+    // Levels of 8, 4 and 2 columns, all at 0.
+    let mut q = vec![0i8; 14];
+    let offsets = [0, 8, 12];
+    let masks = [
+        WaterfallMask {
+            start: 2,
+            end: 3,
+            attenuation_db: 30,
+        },
+        WaterfallMask {
+            start: 7,
+            end: 8,
+            attenuation_db: 200,
+        },
+    ];
+    apply_waterfall_masks(&mut q, &offsets, 8, &masks);
+    assert_eq!(&q[0..8], &[0, 0, -30, 0, 0, 0, 0, -128]);
+    assert_eq!(&q[8..12], &[0, -30, 0, -128]);
+    assert_eq!(&q[12..14], &[-30, -128]);
+}
+
+#[test]
+fn masks_outside_the_row_are_ignored() {
+    let mut q = vec![5i8; 4];
+    let mask = WaterfallMask {
+        start: 10,
+        end: 20,
+        attenuation_db: 10,
+    };
+    apply_waterfall_masks(&mut q, &[0], 4, &[mask]);
+    assert_eq!(q, vec![5; 4]);
+}
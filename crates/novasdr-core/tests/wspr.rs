@@ -0,0 +1,86 @@
+use novasdr_core::wspr::{self, DecodeOptions, SAMPLE_RATE};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+fn slot_with_signals(signals: &[([u8; 7], f32, f32, f32)], noise: f32) -> Vec<f32> {
+    let slot_len = (wspr::SLOT_MS as usize) * SAMPLE_RATE as usize / 1000;
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut out: Vec<f32> = (0..slot_len)
+        .map(|_| {
+            // Box-Muller
+            let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+            let u2: f32 = rng.gen();
+            noise * (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+        })
+        .collect();
+    for (payload, centre_hz, start_s, amplitude) in signals {
+        let tones = wspr::encode_tones(payload);
+        let base_hz = centre_hz - 1.5 * wspr::TONE_SPACING_HZ;
+        let wave = wspr::synthesize(&tones, base_hz, *amplitude);
+        let start = (start_s * SAMPLE_RATE as f32) as usize;
+        for (dst, s) in out[start..].iter_mut().zip(wave.iter()) {
+            *dst += s;
+        }
+    }
+    out
+}
+
+#[test]
+fn type1_message_roundtrips_through_pack_and_unpack() {
+    let cases = [
+        ("K1ABC", "FN42", 37, "K1ABC FN42 37"),
+        ("G4ABC", "IO91", 23, "G4ABC IO91 23"),
+        ("PA3XYZ", "JO22", 0, "PA3XYZ JO22 0"),
+        ("W9XY", "EN52", 60, "W9XY EN52 60"),
+    ];
+    for (call, loc, dbm, text) in cases {
+        let payload = wspr::pack(call, loc, dbm).expect("pack");
+        let msg = wspr::unpack(&payload).expect("unpack");
+        assert_eq!(msg.text(), text);
+    }
+    assert!(wspr::pack("K1ABC", "FN42", 35).is_none());
+    assert!(wspr::pack("K1ABC", "ZZ42", 30).is_none());
+    assert!(wspr::pack("KABCDE", "FN42", 30).is_none());
+}
+
+#[test]
+fn fano_corrects_flipped_bits() {
+    let payload = wspr::pack("K1ABC", "FN42", 37).unwrap();
+    let coded = wspr::fano::encode(&payload);
+    let mut llr = [0f32; wspr::fano::CODED_BITS];
+    for (l, &bit) in llr.iter_mut().zip(coded.iter()) {
+        *l = if bit == 1 { 3.0 } else { -3.0 };
+    }
+    for idx in [5usize, 30, 31, 77, 101, 140] {
+        llr[idx] = -llr[idx] * 0.5;
+    }
+    assert_eq!(wspr::fano::decode(&llr, 10_000), Some(payload));
+}
+
+#[test]
+fn wspr_decodes_signals_in_noise() {
+    let a = wspr::pack("K1ABC", "FN42", 37).unwrap();
+    let b = wspr::pack("G4ABC", "IO91", 23).unwrap();
+    let samples = slot_with_signals(&[(a, 1450.0, 1.0, 0.05), (b, 1530.0, 1.8, 0.035)], 1.0);
+    let decoded = wspr::decode(&samples, &DecodeOptions::default());
+
+    let find = |text: &str| {
+        decoded
+            .iter()
+            .find(|d| d.message.text() == text)
+            .unwrap_or_else(|| panic!("{text} not decoded: {decoded:?}"))
+    };
+    let da = find("K1ABC FN42 37");
+    assert!((da.freq_hz - 1450.0).abs() < 1.0, "{}", da.freq_hz);
+    assert!(da.dt_s.abs() < 0.3, "{}", da.dt_s);
+    let db = find("G4ABC IO91 23");
+    assert!((db.dt_s - 0.8).abs() < 0.3, "{}", db.dt_s);
+    assert!(db.snr_db < da.snr_db);
+    // Noise of unit variance over 6 kHz; amplitude 0.05 is about -24 dB in 2500 Hz.
+    assert!((da.snr_db + 24).abs() <= 3, "{}", da.snr_db);
+}
+
+#[test]
+fn wspr_finds_nothing_in_pure_noise() {
+    let samples = slot_with_signals(&[], 1.0);
+    assert!(wspr::decode(&samples, &DecodeOptions::default()).is_empty());
+}
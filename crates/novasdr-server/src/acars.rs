@@ -0,0 +1,205 @@
+//! ACARS messages decoded from the airband channels of receivers with `acars.enabled`: pushed to
+//! every `/digital` client, with the last few kept for clients that connect later.
+
+use crate::rtl_tcp::Tuning;
+use crate::shutdown;
+use crate::state::{AppState, ReceiverState};
+use novasdr_core::acars::{Decoder, Message};
+use num_complex::Complex32;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast;
+
+/// Messages kept for `/digital` clients that connect later.
+const RECENT_MESSAGES: usize = 50;
+/// Messages a slow `/digital` client may fall behind before it skips ahead.
+const CHANNEL_DEPTH: usize = 64;
+/// IQ rate of each channel: wide enough for the 2400 Hz tone on both sides of the carrier.
+const SAMPLE_RATE: u32 = 12_000;
+/// Rate the carrier level is followed at, per sample; removing it leaves the tones.
+const CARRIER_RATE: f32 = 0.01;
+
+/// A decoded message and its `acars` description.
+pub struct Decoded {
+    pub receiver_id: String,
+    pub json: String,
+}
+
+pub struct Messages {
+    recent: Mutex<VecDeque<Arc<Decoded>>>,
+    tx: broadcast::Sender<Arc<Decoded>>,
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self {
+            recent: Mutex::default(),
+            tx: broadcast::channel(CHANNEL_DEPTH).0,
+        }
+    }
+}
+
+impl Messages {
+    fn recent(&self) -> MutexGuard<'_, VecDeque<Arc<Decoded>>> {
+        match self.recent.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("acars messages mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    /// The recent messages, oldest first, and the ones that follow.
+    pub fn subscribe(&self) -> (Vec<Arc<Decoded>>, broadcast::Receiver<Arc<Decoded>>) {
+        // Under the lock `publish` holds, so no message is in both or neither.
+        let recent = self.recent();
+        (recent.iter().cloned().collect(), self.tx.subscribe())
+    }
+
+    fn publish(&self, receiver_id: &str, frequency: i64, message: &Message) {
+        tracing::info!(
+            receiver_id,
+            frequency,
+            registration = %message.registration,
+            label = %message.label,
+            "acars message received"
+        );
+        let decoded = Arc::new(Decoded {
+            receiver_id: receiver_id.to_string(),
+            json: message_json(receiver_id, frequency, message).to_string(),
+        });
+        let mut recent = self.recent();
+        if recent.len() >= RECENT_MESSAGES {
+            recent.pop_front();
+        }
+        recent.push_back(decoded.clone());
+        // No `/digital` clients is not an error.
+        let _ = self.tx.send(decoded);
+    }
+}
+
+fn message_json(receiver_id: &str, frequency: i64, message: &Message) -> serde_json::Value {
+    json!({
+        "type": "acars",
+        "receiver_id": receiver_id,
+        "frequency": frequency,
+        "time": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "mode": message.mode.to_string(),
+        "registration": message.registration,
+        "flight": message.flight,
+        "message_number": message.message_number,
+        "label": message.label,
+        "block_id": message.block_id.to_string(),
+        "ack": message.ack.map(String::from),
+        "downlink": message.is_downlink(),
+        "more": message.more,
+        "text": message.text,
+    })
+}
+
+/// One airband channel: its slice of the band, AM-demodulated and decoded.
+struct Channel {
+    frequency: i64,
+    tuning: Tuning,
+    carrier: f32,
+    decoder: Decoder,
+}
+
+/// Starts decoding on every receiver with `acars.enabled`.
+pub fn spawn(state: Arc<AppState>) {
+    for receiver in state.receivers.values() {
+        if receiver.receiver.acars.enabled {
+            tokio::spawn(run(state.clone(), receiver.clone()));
+        }
+    }
+}
+
+async fn run(state: Arc<AppState>, receiver: Arc<ReceiverState>) {
+    let receiver_id = receiver.receiver.id.as_str();
+    let rt = &receiver.rt;
+    let top = rt.basefreq + rt.total_bandwidth;
+    let mut channels: Vec<Channel> = Vec::new();
+    for &frequency in receiver.receiver.acars.frequencies.iter() {
+        if !(rt.basefreq..top).contains(&frequency) {
+            tracing::warn!(
+                receiver_id,
+                frequency,
+                "acars channel outside the receiver range"
+            );
+            continue;
+        }
+        let tuning = Tuning::new(&receiver, frequency, SAMPLE_RATE, SAMPLE_RATE);
+        let decoder = Decoder::new(tuning.sample_rate(&receiver));
+        tracing::info!(receiver_id, frequency, "acars channel");
+        channels.push(Channel {
+            frequency,
+            tuning,
+            carrier: 0.0,
+            decoder,
+        });
+    }
+    if channels.is_empty() {
+        return;
+    }
+
+    let (tx, mut frames) = crate::state::iq_frame_channel();
+    let client_id = state.alloc_client_id();
+    receiver.iq_clients.insert(client_id, tx);
+    let mut iq: Vec<Complex32> = Vec::new();
+    let mut audio: Vec<f32> = Vec::new();
+    while let Some(frame) = frames.recv().await {
+        if shutdown::is_shutdown_requested() {
+            break;
+        }
+        for ch in channels.iter_mut() {
+            ch.tuning.process(&frame, rt.is_real, &mut iq);
+            audio.clear();
+            for s in iq.iter() {
+                let envelope = s.norm();
+                ch.carrier += (envelope - ch.carrier) * CARRIER_RATE;
+                audio.push(envelope - ch.carrier);
+            }
+            ch.decoder.process(&audio);
+            while let Some(message) = ch.decoder.pop_message() {
+                state
+                    .acars_messages
+                    .publish(receiver_id, ch.frequency, &message);
+            }
+        }
+    }
+    receiver.iq_clients.remove(&client_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_reach_late_and_live_subscribers() {
+        let messages = Messages::default();
+        let message = Message {
+            mode: '2',
+            registration: "N123AB".to_string(),
+            ack: None,
+            label: "H1".to_string(),
+            block_id: '4',
+            message_number: Some("M01A".to_string()),
+            flight: Some("UA0123".to_string()),
+            text: "#DFB".to_string(),
+            more: false,
+        };
+        let (_, mut rx) = messages.subscribe();
+        messages.publish("vhf", 131_550_000, &message);
+        let (recent, _) = messages.subscribe();
+        assert_eq!(recent.len(), 1);
+        let live = rx.try_recv().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&live.json).unwrap();
+        assert_eq!(json["type"], "acars");
+        assert_eq!(json["frequency"], 131_550_000);
+        assert_eq!(json["flight"], "UA0123");
+        assert_eq!(json["ack"], serde_json::Value::Null);
+        assert_eq!(json["downlink"], true);
+    }
+}
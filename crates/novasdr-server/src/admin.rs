@@ -0,0 +1,920 @@
+use crate::state::{AppState, FftSwitchRequest, ReceiverState};
+use crate::{auth, marker_suggestions};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use novasdr_core::{
+    config::{self, ReceiverMaintenance},
+    smeter::{CalibrationPoint, SmeterCalibration},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+const MAX_MAINTENANCE_MESSAGE_LEN: usize = 280;
+/// Larger corrections point at a wrong reference level rather than a receiver that needs them.
+const MAX_CALIBRATION_OFFSET_DB: f32 = 60.0;
+/// The DSP thread picks up a switch at its next frame; GPU initialisation can take a few seconds.
+const FFT_SWITCH_TIMEOUT: Duration = Duration::from_secs(30);
+const NOT_SOAPYSDR: &str = "frequency correction needs a soapysdr input";
+
+/// Operator-only routes, behind [`auth::require_admin`].
+pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/admin/receivers/:receiver_id/maintenance",
+            put(set_maintenance),
+        )
+        .route(
+            "/admin/receivers/:receiver_id/smeter_calibration",
+            put(set_calibration_point)
+                .get(get_calibration)
+                .delete(clear_calibration),
+        )
+        .route(
+            "/admin/receivers/:receiver_id/fft_backend",
+            put(set_fft_backend).get(get_fft_backend),
+        )
+        .route(
+            "/admin/receivers/:receiver_id/ppm_correction",
+            put(set_ppm_correction).get(get_ppm_correction),
+        )
+        .route("/admin/receivers/:receiver_id/auto_gain", post(auto_gain))
+        .route(
+            "/admin/receivers/:receiver_id/antenna",
+            put(set_antenna).get(get_antenna),
+        )
+        .route(
+            "/admin/receivers/:receiver_id/udp_output",
+            put(set_udp_output).get(get_udp_output),
+        )
+        .route(
+            "/admin/receivers/:receiver_id/black_box/extract",
+            post(extract_black_box),
+        )
+        .route(
+            "/admin/receivers/:receiver_id/codec_compare",
+            get(crate::codec_compare::upgrade),
+        )
+        .route("/admin/reload", post(reload_config))
+        .route("/admin/snapshot", get(crate::snapshot::export))
+        .route("/admin/marker_suggestions", get(marker_suggestions::list))
+        .route(
+            "/admin/marker_suggestions/:id",
+            delete(marker_suggestions::reject),
+        )
+        .route(
+            "/admin/marker_suggestions/:id/approve",
+            post(marker_suggestions::approve),
+        )
+        .route_layer(middleware::from_fn_with_state(state, auth::require_admin))
+}
+
+/// Reloads `config.json` and `receivers.json` like SIGHUP, and reports what took effect.
+async fn reload_config(State(state): State<Arc<AppState>>) -> Response {
+    match crate::reload::reload(&state).await {
+        Ok(summary) => Json(summary).into_response(),
+        Err(e) => {
+            tracing::warn!(error = ?e, "admin: configuration reload failed");
+            (StatusCode::BAD_REQUEST, format!("{e:#}")).into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BlackBoxExtractRequest {
+    /// End of the window as RFC 3339; now when unset.
+    #[serde(default)]
+    end: Option<String>,
+    seconds: u32,
+}
+
+/// Cuts the last `seconds` before `end` out of a receiver's black box into a recording.
+async fn extract_black_box(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+    Json(body): Json<BlackBoxExtractRequest>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let black_box = &receiver.receiver.black_box;
+    if !black_box.enabled {
+        return (StatusCode::NOT_FOUND, "black box not enabled").into_response();
+    }
+    if body.seconds == 0 || u64::from(body.seconds) > u64::from(black_box.minutes) * 60 {
+        return (
+            StatusCode::BAD_REQUEST,
+            "seconds must be between 1 and black_box.minutes * 60",
+        )
+            .into_response();
+    }
+    let end = match body
+        .end
+        .as_deref()
+        .map(chrono::DateTime::parse_from_rfc3339)
+    {
+        None => chrono::Utc::now(),
+        Some(Ok(end)) => end.with_timezone(&chrono::Utc),
+        Some(Err(_)) => {
+            return (StatusCode::BAD_REQUEST, "end is not an RFC 3339 time").into_response()
+        }
+    };
+    let start = end - chrono::Duration::seconds(i64::from(body.seconds));
+    if crate::disk_guard::recordings_paused() {
+        return (
+            StatusCode::INSUFFICIENT_STORAGE,
+            "capture.dir is above the disk guard's high water mark",
+        )
+            .into_response();
+    }
+    match crate::black_box::extract(&state, &receiver, start, end).await {
+        Ok(Some(x)) => Json(json!({
+            "receiver_id": receiver_id,
+            "file": x.file,
+            "frequency": x.frequency,
+            "sample_rate": x.sample_rate,
+            "start": x.start.to_rfc3339(),
+            "end": x.end.to_rfc3339(),
+        }))
+        .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "nothing recorded in that window").into_response(),
+        Err(e) => {
+            tracing::error!(receiver_id = %receiver_id, error = ?e, "black box extract failed");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn set_maintenance(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+    Json(body): Json<ReceiverMaintenance>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+
+    let mut next = body;
+    next.message = next.message.trim().to_string();
+    if next.message.len() > MAX_MAINTENANCE_MESSAGE_LEN {
+        return (StatusCode::BAD_REQUEST, "maintenance message too long").into_response();
+    }
+
+    let receivers_path = state.config_paths.receivers.clone();
+    let persisted = next.clone();
+    let id_for_file = receiver_id.clone();
+    let write = tokio::task::spawn_blocking(move || {
+        config::update_receiver_in_file(&receivers_path, id_for_file.as_str(), |entry| {
+            entry.insert("maintenance".to_string(), json!(persisted));
+        })
+    })
+    .await;
+    match write {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            tracing::error!(receiver_id = %receiver_id, error = ?e, "failed to persist maintenance state");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to persist receivers.json",
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!(receiver_id = %receiver_id, error = ?e, "maintenance persist task failed");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    receiver.set_maintenance(next.clone());
+    tracing::info!(
+        receiver_id = %receiver_id,
+        enabled = next.enabled,
+        message = %next.message,
+        "admin: receiver maintenance updated"
+    );
+    state.broadcast_event_info(false);
+
+    Json(json!({ "receiver_id": receiver_id, "maintenance": next })).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct FftBackendRequest {
+    accelerator: config::Accelerator,
+    /// Also write the accelerator to receivers.json so it survives a restart.
+    #[serde(default)]
+    persist: bool,
+}
+
+/// Rejects accelerators this binary cannot run, before the DSP thread tries them.
+fn check_accelerator(accelerator: config::Accelerator) -> Result<(), &'static str> {
+    if accelerator.is_built_in() {
+        return Ok(());
+    }
+    match accelerator {
+        config::Accelerator::Clfft => Err("this build has no clfft support"),
+        config::Accelerator::Vkfft => Err("this build has no vkfft support"),
+        _ => Err("unsupported accelerator"),
+    }
+}
+
+async fn get_fft_backend(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()) else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    Json(json!({ "receiver_id": receiver_id, "fft_backend": receiver.fft_backend() }))
+        .into_response()
+}
+
+/// Rebuilds the receiver's FFT pipeline on another backend without a restart. The usual fallback
+/// applies (vkfft, then clfft, then the CPU), so the reply reports the backend actually in use.
+async fn set_fft_backend(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+    Json(body): Json<FftBackendRequest>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    if let Err(msg) = check_accelerator(body.accelerator) {
+        return (StatusCode::BAD_REQUEST, msg).into_response();
+    }
+
+    let (reply, done) = tokio::sync::oneshot::channel();
+    receiver.request_fft_switch(FftSwitchRequest {
+        accelerator: body.accelerator,
+        reply,
+    });
+    let backend = match tokio::time::timeout(FFT_SWITCH_TIMEOUT, done).await {
+        Ok(Ok(Ok(backend))) => backend,
+        Ok(Ok(Err(e))) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("FFT backend switch failed: {e:#}"),
+            )
+                .into_response();
+        }
+        Ok(Err(_)) => {
+            return (StatusCode::CONFLICT, "superseded by another switch request").into_response();
+        }
+        Err(_) => {
+            return (
+                StatusCode::GATEWAY_TIMEOUT,
+                "receiver DSP thread did not pick up the switch; is its input running?",
+            )
+                .into_response();
+        }
+    };
+
+    if body.persist {
+        let receivers_path = state.config_paths.receivers.clone();
+        let accelerator = body.accelerator.as_str();
+        let id_for_file = receiver_id.clone();
+        let write = tokio::task::spawn_blocking(move || {
+            config::update_receiver_in_file(&receivers_path, id_for_file.as_str(), |entry| {
+                let input = entry
+                    .entry("input".to_string())
+                    .or_insert_with(|| json!({}));
+                if let Some(input) = input.as_object_mut() {
+                    input.insert("accelerator".to_string(), json!(accelerator));
+                }
+            })
+        })
+        .await;
+        match write {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                tracing::error!(receiver_id = %receiver_id, error = ?e, "failed to persist accelerator");
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "failed to persist receivers.json",
+                )
+                    .into_response();
+            }
+            Err(e) => {
+                tracing::error!(receiver_id = %receiver_id, error = ?e, "accelerator persist task failed");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    tracing::info!(
+        receiver_id = %receiver_id,
+        requested = body.accelerator.as_str(),
+        fft_backend = backend.backend.as_str(),
+        persisted = body.persist,
+        "admin: FFT backend switched"
+    );
+    Json(json!({
+        "receiver_id": receiver_id,
+        "requested": body.accelerator.as_str(),
+        "fft_backend": backend,
+    }))
+    .into_response()
+}
+
+/// A calibration point, either as a direct offset or from a reference signal: with a generator of
+/// known level `reference_dbm` tuned at `frequency`, `measured_dbm` is what the S-meter reads now
+/// (with the current calibration applied).
+#[derive(Debug, Deserialize)]
+struct CalibrationPointRequest {
+    frequency: i64,
+    #[serde(default)]
+    offset_db: Option<f32>,
+    #[serde(default)]
+    reference_dbm: Option<f32>,
+    #[serde(default)]
+    measured_dbm: Option<f32>,
+}
+
+fn calibration_offset(
+    req: &CalibrationPointRequest,
+    current: &SmeterCalibration,
+) -> Result<f32, &'static str> {
+    let offset = match (req.offset_db, req.reference_dbm, req.measured_dbm) {
+        (Some(offset), None, None) => offset,
+        (None, Some(reference), Some(measured)) => {
+            current.offset_db(req.frequency) + reference - measured
+        }
+        _ => return Err("send either offset_db or reference_dbm with measured_dbm"),
+    };
+    if !offset.is_finite() || offset.abs() > MAX_CALIBRATION_OFFSET_DB {
+        return Err("calibration offset out of range");
+    }
+    Ok(offset)
+}
+
+fn calibration_json(receiver_id: &str, calibration: &SmeterCalibration) -> Response {
+    Json(json!({ "receiver_id": receiver_id, "points": calibration.points() })).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct PpmCorrectionRequest {
+    ppm: f64,
+}
+
+/// SoapySDR driver of `receiver` as currently configured (a reload may have changed it);
+/// `None` for other inputs.
+fn soapysdr_driver(state: &AppState, receiver: &ReceiverState) -> Option<config::SoapySdrDriver> {
+    let cfg = state.cfg();
+    let entry = cfg
+        .receivers
+        .iter()
+        .find(|r| r.id == receiver.receiver.id)
+        .unwrap_or(&receiver.receiver);
+    match &entry.input.driver {
+        config::InputDriver::SoapySdr(driver) => Some(driver.clone()),
+        _ => None,
+    }
+}
+
+/// Correction in effect, the configured one, the reference sensors and, with
+/// `accuracy.drift_carrier`, the error still measured on the known carrier.
+fn ppm_correction_json(receiver: &ReceiverState, driver: &config::SoapySdrDriver) -> Response {
+    let receiver_id = receiver.receiver.id.as_str();
+    let ppm = match crate::input::ppm_correction(receiver_id, driver) {
+        Ok(ppm) => ppm,
+        Err(e) => return (StatusCode::CONFLICT, format!("{e:#}")).into_response(),
+    };
+    let sensors = match crate::input::reference_sensors(receiver_id, driver) {
+        Ok(readings) => json!(readings),
+        Err(e) => {
+            tracing::debug!(receiver_id, error = ?e, "reference sensors unavailable");
+            serde_json::Value::Null
+        }
+    };
+    Json(json!({
+        "receiver_id": receiver_id,
+        "ppm_correction": ppm,
+        "configured": driver.ppm_correction,
+        "sensors": sensors,
+        "measured_ppm": receiver.drift().map(|d| d.ppm),
+    }))
+    .into_response()
+}
+
+async fn get_ppm_correction(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    match soapysdr_driver(&state, &receiver) {
+        Some(driver) => ppm_correction_json(&receiver, &driver),
+        None => (StatusCode::BAD_REQUEST, NOT_SOAPYSDR).into_response(),
+    }
+}
+
+async fn set_ppm_correction(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+    Json(body): Json<PpmCorrectionRequest>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let Some(driver) = soapysdr_driver(&state, &receiver) else {
+        return (StatusCode::BAD_REQUEST, NOT_SOAPYSDR).into_response();
+    };
+    if !body.ppm.is_finite() || body.ppm.abs() > config::MAX_PPM_CORRECTION {
+        return (StatusCode::BAD_REQUEST, "ppm out of range").into_response();
+    }
+    if let Err(e) = crate::input::set_ppm_correction(&receiver_id, &driver, body.ppm) {
+        tracing::warn!(receiver_id = %receiver_id, error = ?e, "admin: frequency correction failed");
+        return (StatusCode::CONFLICT, format!("{e:#}")).into_response();
+    }
+    // The drift estimate described the old correction; start it over.
+    receiver.set_drift(None);
+    tracing::info!(
+        receiver_id = %receiver_id,
+        ppm = body.ppm,
+        "admin: frequency correction set"
+    );
+    ppm_correction_json(&receiver, &driver)
+}
+
+/// Sweeps the receiver's gain and keeps the best one; see [`crate::auto_gain`].
+async fn auto_gain(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+    Json(body): Json<crate::auto_gain::SweepRequest>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let Some(driver) = soapysdr_driver(&state, &receiver) else {
+        return (StatusCode::BAD_REQUEST, "auto gain needs a soapysdr input").into_response();
+    };
+    let outcome = match crate::auto_gain::sweep(&state, &receiver, &driver, &body).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            tracing::warn!(receiver_id = %receiver_id, error = ?e, "admin: auto gain failed");
+            return (StatusCode::CONFLICT, format!("{e:#}")).into_response();
+        }
+    };
+
+    if body.persist {
+        let receivers_path = state.config_paths.receivers.clone();
+        let gain = outcome.gain_db;
+        let id_for_file = receiver_id.clone();
+        let write = tokio::task::spawn_blocking(move || {
+            config::update_receiver_in_file(&receivers_path, id_for_file.as_str(), |entry| {
+                let driver = entry
+                    .get_mut("input")
+                    .and_then(|input| input.get_mut("driver"))
+                    .and_then(|driver| driver.as_object_mut());
+                if let Some(driver) = driver {
+                    driver.insert("gain".to_string(), json!(gain));
+                }
+            })
+        })
+        .await;
+        match write {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                tracing::error!(receiver_id = %receiver_id, error = ?e, "failed to persist gain");
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "failed to persist receivers.json",
+                )
+                    .into_response();
+            }
+            Err(e) => {
+                tracing::error!(receiver_id = %receiver_id, error = ?e, "gain persist task failed");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    tracing::info!(
+        receiver_id = %receiver_id,
+        gain_db = outcome.gain_db,
+        persisted = body.persist,
+        "admin: auto gain applied"
+    );
+    Json(json!({
+        "receiver_id": receiver_id,
+        "gain_db": outcome.gain_db,
+        "steps": outcome.steps,
+        "persisted": body.persist,
+    }))
+    .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct AntennaRequest {
+    #[serde(default)]
+    antenna: Option<String>,
+    #[serde(default)]
+    bias_tee: Option<bool>,
+    /// Also write the change to receivers.json.
+    #[serde(default)]
+    persist: bool,
+}
+
+fn antenna_json(receiver_id: &str, antenna: &crate::input::AntennaState) -> Response {
+    Json(json!({
+        "receiver_id": receiver_id,
+        "antenna": antenna.antenna,
+        "antennas": antenna.antennas,
+        "bias_tee": antenna.bias_tee,
+        "bias_tee_setting": antenna.bias_tee_setting,
+    }))
+    .into_response()
+}
+
+async fn get_antenna(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let Some(driver) = soapysdr_driver(&state, &receiver) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "antenna control needs a soapysdr input",
+        )
+            .into_response();
+    };
+    match crate::input::antenna_state(&receiver_id, &driver) {
+        Ok(antenna) => antenna_json(&receiver_id, &antenna),
+        Err(e) => (StatusCode::CONFLICT, format!("{e:#}")).into_response(),
+    }
+}
+
+/// Switches the antenna port and/or the bias tee, after checking both against what the device
+/// offers.
+async fn set_antenna(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+    Json(body): Json<AntennaRequest>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let Some(driver) = soapysdr_driver(&state, &receiver) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "antenna control needs a soapysdr input",
+        )
+            .into_response();
+    };
+    if body.antenna.is_none() && body.bias_tee.is_none() {
+        return (StatusCode::BAD_REQUEST, "set antenna or bias_tee").into_response();
+    }
+    let current = match crate::input::antenna_state(&receiver_id, &driver) {
+        Ok(current) => current,
+        Err(e) => return (StatusCode::CONFLICT, format!("{e:#}")).into_response(),
+    };
+    if let Some(antenna) = body.antenna.as_deref() {
+        if !current.antennas.iter().any(|a| a == antenna) {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "unknown antenna {antenna:?} (available: {:?})",
+                    current.antennas
+                ),
+            )
+                .into_response();
+        }
+    }
+    if body.bias_tee.is_some() && current.bias_tee_setting.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            "the device has no bias tee; set input.driver.bias_tee_setting if it names it otherwise",
+        )
+            .into_response();
+    }
+
+    let mut changed = Ok(());
+    if let Some(antenna) = body.antenna.as_deref() {
+        changed = crate::input::set_antenna(&receiver_id, &driver, antenna);
+    }
+    if let (Ok(()), Some(on)) = (&changed, body.bias_tee) {
+        changed = crate::input::set_bias_tee(&receiver_id, &driver, on);
+    }
+    if let Err(e) = changed {
+        tracing::warn!(receiver_id = %receiver_id, error = ?e, "admin: antenna change failed");
+        return (StatusCode::CONFLICT, format!("{e:#}")).into_response();
+    }
+    tracing::info!(
+        receiver_id = %receiver_id,
+        antenna = ?body.antenna,
+        bias_tee = ?body.bias_tee,
+        "admin: antenna changed"
+    );
+
+    if body.persist {
+        let receivers_path = state.config_paths.receivers.clone();
+        let id_for_file = receiver_id.clone();
+        let antenna = body.antenna.clone();
+        let bias_tee = body
+            .bias_tee
+            .zip(current.bias_tee_setting.clone())
+            .map(|(on, key)| (key, on));
+        let write = tokio::task::spawn_blocking(move || {
+            config::update_receiver_in_file(&receivers_path, id_for_file.as_str(), |entry| {
+                let Some(driver) = entry
+                    .get_mut("input")
+                    .and_then(|input| input.get_mut("driver"))
+                    .and_then(|driver| driver.as_object_mut())
+                else {
+                    return;
+                };
+                if let Some(antenna) = antenna {
+                    driver.insert("antenna".to_string(), json!(antenna));
+                }
+                if let Some((key, on)) = bias_tee {
+                    let settings = driver
+                        .entry("settings")
+                        .or_insert_with(|| json!({}))
+                        .as_object_mut();
+                    if let Some(settings) = settings {
+                        settings.insert(key, json!(if on { "true" } else { "false" }));
+                    }
+                }
+            })
+        })
+        .await;
+        match write {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                tracing::error!(receiver_id = %receiver_id, error = ?e, "failed to persist antenna");
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "failed to persist receivers.json",
+                )
+                    .into_response();
+            }
+            Err(e) => {
+                tracing::error!(receiver_id = %receiver_id, error = ?e, "antenna persist task failed");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    match crate::input::antenna_state(&receiver_id, &driver) {
+        Ok(antenna) => antenna_json(&receiver_id, &antenna),
+        Err(e) => (StatusCode::CONFLICT, format!("{e:#}")).into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UdpOutputRequest {
+    #[serde(default)]
+    enabled: Option<bool>,
+    #[serde(default)]
+    target: Option<String>,
+    #[serde(default)]
+    frequency: Option<i64>,
+    #[serde(default)]
+    mode: Option<String>,
+    #[serde(default)]
+    packet_ms: Option<u32>,
+    /// Also write the change to receivers.json.
+    #[serde(default)]
+    persist: bool,
+}
+
+fn udp_output_settings(output: &config::UdpOutput) -> serde_json::Value {
+    json!({
+        "enabled": output.enabled,
+        "target": output.target,
+        "frequency": output.frequency,
+        "mode": output.mode,
+        "packet_ms": output.packet_ms,
+    })
+}
+
+fn udp_output_json(receiver_id: &str, receiver: &ReceiverState) -> Response {
+    let output = &receiver.udp_output;
+    let mut body = udp_output_settings(&output.settings());
+    if let Some(body) = body.as_object_mut() {
+        body.insert("receiver_id".to_string(), json!(receiver_id));
+        body.insert("sample_rate".to_string(), json!(receiver.rt.audio_max_sps));
+        body.insert("packets".to_string(), json!(output.packets()));
+        body.insert("error".to_string(), json!(output.error()));
+    }
+    Json(body).into_response()
+}
+
+async fn get_udp_output(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    udp_output_json(&receiver_id, &receiver)
+}
+
+/// Switches, retunes or redirects the UDP audio output; fields left out keep their value.
+async fn set_udp_output(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+    Json(body): Json<UdpOutputRequest>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let mut next = receiver.udp_output.settings();
+    if let Some(enabled) = body.enabled {
+        next.enabled = enabled;
+    }
+    if let Some(target) = body.target {
+        next.target = target.trim().to_string();
+    }
+    if let Some(frequency) = body.frequency {
+        next.frequency = Some(frequency);
+    }
+    if let Some(mode) = body.mode {
+        next.mode = Some(mode.trim().to_ascii_uppercase());
+    }
+    if let Some(packet_ms) = body.packet_ms {
+        next.packet_ms = packet_ms;
+    }
+    if let Err(reason) = next.check() {
+        return (StatusCode::BAD_REQUEST, reason).into_response();
+    }
+    if let Some(frequency) = next.frequency.filter(|f| !receiver.covers(*f)) {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("frequency {frequency} is outside the receiver range"),
+        )
+            .into_response();
+    }
+
+    if body.persist {
+        let receivers_path = state.config_paths.receivers.clone();
+        let id_for_file = receiver_id.clone();
+        let persisted = udp_output_settings(&next);
+        let write = tokio::task::spawn_blocking(move || {
+            config::update_receiver_in_file(&receivers_path, id_for_file.as_str(), |entry| {
+                entry.insert("udp_output".to_string(), persisted);
+            })
+        })
+        .await;
+        match write {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                tracing::error!(receiver_id = %receiver_id, error = ?e, "failed to persist udp output");
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "failed to persist receivers.json",
+                )
+                    .into_response();
+            }
+            Err(e) => {
+                tracing::error!(receiver_id = %receiver_id, error = ?e, "udp output persist task failed");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    tracing::info!(
+        receiver_id = %receiver_id,
+        enabled = next.enabled,
+        target = %next.target,
+        frequency = ?next.frequency,
+        mode = ?next.mode,
+        "admin: udp output updated"
+    );
+    receiver.udp_output.set(next);
+    udp_output_json(&receiver_id, &receiver)
+}
+
+async fn get_calibration(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()) else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    calibration_json(&receiver_id, &receiver.smeter_calibration())
+}
+
+async fn set_calibration_point(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+    Json(body): Json<CalibrationPointRequest>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    if !receiver.covers(body.frequency) {
+        return (StatusCode::BAD_REQUEST, "frequency outside receiver range").into_response();
+    }
+
+    let mut next = (*receiver.smeter_calibration()).clone();
+    let offset_db = match calibration_offset(&body, &next) {
+        Ok(v) => v,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+    next.set_point(CalibrationPoint {
+        frequency: body.frequency,
+        offset_db,
+    });
+    if let Err(resp) = persist_calibration(&state, &receiver, &next).await {
+        return resp;
+    }
+    receiver.set_smeter_calibration(next.clone());
+    tracing::info!(
+        receiver_id = %receiver_id,
+        frequency = body.frequency,
+        offset_db,
+        "admin: S-meter calibration point set"
+    );
+    calibration_json(&receiver_id, &next)
+}
+
+async fn clear_calibration(
+    State(state): State<Arc<AppState>>,
+    Path(receiver_id): Path<String>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let next = SmeterCalibration::default();
+    if let Err(resp) = persist_calibration(&state, &receiver, &next).await {
+        return resp;
+    }
+    receiver.set_smeter_calibration(next.clone());
+    tracing::info!(receiver_id = %receiver_id, "admin: S-meter calibration cleared");
+    calibration_json(&receiver_id, &next)
+}
+
+async fn persist_calibration(
+    state: &AppState,
+    receiver: &ReceiverState,
+    calibration: &SmeterCalibration,
+) -> Result<(), Response> {
+    let path = state.config_paths.smeter_calibration(&receiver.receiver);
+    let calibration = calibration.clone();
+    let write = tokio::task::spawn_blocking(move || calibration.save(&path)).await;
+    match write {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => {
+            tracing::error!(receiver_id = %receiver.receiver.id, error = ?e, "failed to persist S-meter calibration");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to persist S-meter calibration",
+            )
+                .into_response())
+        }
+        Err(e) => {
+            tracing::error!(receiver_id = %receiver.receiver.id, error = ?e, "S-meter calibration persist task failed");
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_accelerator_rejects_backends_not_built_in() {
+        assert!(check_accelerator(config::Accelerator::None).is_ok());
+        assert!(check_accelerator(config::Accelerator::Auto).is_ok());
+        assert!(check_accelerator(config::Accelerator::Unsupported).is_err());
+        assert_eq!(
+            check_accelerator(config::Accelerator::Vkfft).is_ok(),
+            cfg!(feature = "vkfft")
+        );
+        let body: FftBackendRequest = serde_json::from_str(r#"{"accelerator":"clfft"}"#).unwrap();
+        assert_eq!(body.accelerator, config::Accelerator::Clfft);
+        assert!(!body.persist);
+    }
+
+    #[test]
+    fn calibration_offset_from_reference_adds_to_current_offset() {
+        let current = SmeterCalibration::new(vec![CalibrationPoint {
+            frequency: 7_000_000,
+            offset_db: 3.0,
+        }]);
+        let req = CalibrationPointRequest {
+            frequency: 7_000_000,
+            offset_db: None,
+            reference_dbm: Some(-73.0),
+            measured_dbm: Some(-78.0),
+        };
+        assert_eq!(calibration_offset(&req, &current), Ok(8.0));
+
+        let both = CalibrationPointRequest {
+            offset_db: Some(1.0),
+            ..req
+        };
+        assert!(calibration_offset(&both, &current).is_err());
+    }
+}
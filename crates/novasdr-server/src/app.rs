@@ -1,8 +1,21 @@
-use crate::{shutdown, state, ws};
+use crate::{
+    admin, auth, identity, markers, metrics, recordings, shutdown, skimmer, state, stream, users,
+    waterfall_masks, waterfall_png, ws,
+};
 use anyhow::Context;
-use axum::{routing::get, Router};
+use axum::{
+    middleware,
+    routing::{get, post},
+    Router,
+};
 use std::{net::SocketAddr, sync::Arc};
-use tower_http::{compression::CompressionLayer, services::ServeDir};
+use tower_http::{
+    compression::{
+        predicate::{DefaultPredicate, NotForContentType, Predicate},
+        CompressionLayer,
+    },
+    services::ServeDir,
+};
 
 pub fn router(state: Arc<state::AppState>) -> Router {
     let html_root = state.html_root.clone();
@@ -10,21 +23,66 @@ pub fn router(state: Arc<state::AppState>) -> Router {
     Router::new()
         .route("/server-info.json", get(state::server_info))
         .route("/receivers.json", get(state::receivers_info))
+        .route("/waterfall.png", get(waterfall_png::handler))
+        .route(
+            "/api/markers",
+            get(markers::search).merge(post(markers::upsert).delete(markers::remove).route_layer(
+                middleware::from_fn_with_state(state.clone(), auth::require_admin),
+            )),
+        )
+        .route(
+            "/api/waterfall_masks",
+            get(waterfall_masks::list)
+                .post(waterfall_masks::upsert)
+                .delete(waterfall_masks::remove)
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::require_admin,
+                )),
+        )
+        .route("/api/login", post(auth::login))
+        .route(
+            "/api/user",
+            get(users::get)
+                .post(users::create)
+                .put(users::update)
+                .delete(users::remove),
+        )
+        .route("/api/user/token", post(users::rotate_token))
         .route("/audio", get(ws::audio::upgrade))
         .route("/waterfall", get(ws::waterfall::upgrade))
         .route("/events", get(ws::events::upgrade))
         .route("/chat", get(ws::chat::upgrade))
+        .route("/digital", get(ws::digital::upgrade))
+        .route("/sstv", get(ws::sstv::upgrade))
+        .route("/iq", get(ws::iq::upgrade))
+        .route("/diversity", get(ws::diversity::upgrade))
+        .route("/tx", get(ws::tx::upgrade))
+        .route(
+            "/stream/:receiver_id/:frequency/:file",
+            get(stream::handler),
+        )
+        .route("/api/recordings", get(recordings::list))
+        .route("/recordings/:receiver_id/:name", get(recordings::download))
+        .route("/skimmer/spots.json", get(skimmer::spots))
+        .route("/metrics", get(metrics::handler))
+        .route(identity::WELL_KNOWN_PATH, get(identity::well_known))
+        .merge(admin::router(state.clone()))
         .nest_service(
             "/",
             ServeDir::new(html_root).append_index_html_on_directories(true),
         )
-        .layer(CompressionLayer::new())
+        // Audio is already compressed or streamed to players that expect the raw bytes.
+        .layer(
+            CompressionLayer::new()
+                .compress_when(DefaultPredicate::new().and(NotForContentType::const_new("audio/"))),
+        )
         .with_state(state)
 }
 
 pub async fn serve(state: Arc<state::AppState>) -> anyhow::Result<()> {
-    let host = state.cfg.server.host.clone();
-    let port = state.cfg.server.port;
+    let host = state.cfg().server.host.clone();
+    let port = state.cfg().server.port;
     let host = if host.contains(':') && !host.starts_with('[') {
         format!("[{host}]")
     } else {
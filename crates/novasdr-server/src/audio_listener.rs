@@ -0,0 +1,295 @@
+use crate::markers::{marker_frequency, MarkerIndex};
+use crate::state::{AgcSpeed, AppState, AudioClient, AudioParams, ClientId, FilterSharpness};
+use crate::state::{ReceiverState, SquelchMode};
+use crate::ws::audio_pool::{self, PipelineShape};
+use novasdr_core::dsp::demod::DemodulationMode;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Parameters a new listener on `receiver` starts with: the receiver's default tune.
+pub fn default_params(receiver: &ReceiverState) -> AudioParams {
+    let settings = receiver.settings();
+    AudioParams {
+        l: settings.default_l,
+        m: settings.default_m,
+        r: settings.default_r,
+        mute: false,
+        squelch_enabled: settings.defaults.squelch_enabled,
+        squelch_mode: SquelchMode::default(),
+        rds: false,
+        stereo: false,
+        digital_voice: false,
+        subtone: false,
+        tone_squelch: None,
+        sstv: false,
+        fsk: None,
+        test_tone: None,
+        demodulation: DemodulationMode::from_str_upper(settings.default_mode_str.as_str())
+            .unwrap_or(DemodulationMode::Usb),
+        agc_speed: AgcSpeed::Default,
+        agc_attack_ms: None,
+        agc_release_ms: None,
+        filter_sharpness: FilterSharpness::Normal,
+        low_latency: false,
+        volume_db: 0.0,
+        hearing: Default::default(),
+    }
+}
+
+/// Channels of a new audio client: what it receives from the DSP thread and what it produces.
+pub struct ClientQueues {
+    pub audio: mpsc::Receiver<Vec<u8>>,
+    pub text: mpsc::Receiver<String>,
+    /// For [`spawn_frame_task`].
+    pub frames: mpsc::Receiver<crate::state::AudioFrame>,
+}
+
+pub fn new_client(
+    unique_id: String,
+    params: AudioParams,
+    pipeline: crate::ws::audio::AudioPipeline,
+    text_capacity: usize,
+) -> (Arc<AudioClient>, ClientQueues) {
+    let (tx, audio) = crate::state::audio_channel();
+    let (frames, frames_rx) = crate::state::audio_frame_channel();
+    let (text_tx, text) = mpsc::channel::<String>(text_capacity);
+    let client = Arc::new(AudioClient {
+        unique_id,
+        frames,
+        tx,
+        text_tx,
+        params: std::sync::Mutex::new(params),
+        pipeline: std::sync::Mutex::new(pipeline),
+        squelch_open: std::sync::atomic::AtomicBool::new(false),
+        now_playing: std::sync::Mutex::new(None),
+        rejected_windows: std::sync::atomic::AtomicU64::new(0),
+        presence: std::sync::Mutex::new(None),
+    });
+    let queues = ClientQueues {
+        audio,
+        text,
+        frames: frames_rx,
+    };
+    (client, queues)
+}
+
+/// Demodulates the client's frames. It runs here rather than on the DSP thread, so listeners
+/// spread over the runtime's worker threads. Frames left over from a receiver this client
+/// switched away from are skipped.
+pub fn spawn_frame_task(
+    state: Arc<AppState>,
+    client_id: ClientId,
+    client: Arc<AudioClient>,
+    mut frames_rx: mpsc::Receiver<crate::state::AudioFrame>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut sent = crate::channels::ChannelTextSent::default();
+        let mut next_now_playing = Instant::now();
+        while let Some(frame) = frames_rx.recv().await {
+            if !frame.receiver.audio_clients.contains_key(&client_id) {
+                continue;
+            }
+            crate::dsp_runner::process_audio_frame(&state, &client, &frame, &mut sent);
+            if Instant::now() >= next_now_playing {
+                next_now_playing = Instant::now() + NOW_PLAYING_INTERVAL;
+                update_now_playing(&state, &frame.receiver, &client).await;
+            }
+        }
+    })
+}
+
+/// How often a listener's tune is matched against the markers.
+const NOW_PLAYING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The marker a listener is tuned to, for now-playing displays and stream titles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NowPlaying {
+    pub name: String,
+    pub frequency: i64,
+}
+
+/// The named marker nearest to the carrier at bin `m`, no further from it than the far edge of
+/// the passband `l..r`.
+pub fn now_playing_at(
+    markers: &MarkerIndex,
+    receiver: &ReceiverState,
+    l: i32,
+    m: f64,
+    r: i32,
+) -> Option<NowPlaying> {
+    let center = receiver.bin_frequency(m);
+    let reach = (center - receiver.bin_frequency(f64::from(l)))
+        .max(receiver.bin_frequency(f64::from(r)) - center)
+        .max(0);
+    let marker = markers.nearest(center, reach)?;
+    let name = marker.get("name")?.as_str()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some(NowPlaying {
+        name: name.to_string(),
+        frequency: marker_frequency(marker)?,
+    })
+}
+
+/// Refreshes `client.now_playing` and tells the client when it changed.
+async fn update_now_playing(state: &AppState, receiver: &ReceiverState, client: &AudioClient) {
+    let (l, m, r) = match client.params.lock() {
+        Ok(p) => (p.l, p.m, p.r),
+        Err(poisoned) => {
+            let p = poisoned.into_inner();
+            (p.l, p.m, p.r)
+        }
+    };
+    let markers = state.markers.read().await.clone();
+    let found = now_playing_at(&markers, receiver, l, m, r);
+    let mut current = match client.now_playing.lock() {
+        Ok(g) => g,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if *current == found {
+        return;
+    }
+    let msg = match &found {
+        Some(np) => json!({ "type": "now_playing", "name": np.name, "frequency": np.frequency }),
+        None => json!({ "type": "now_playing", "name": null }),
+    };
+    // With the text queue full, the change is left for the next check to send.
+    if let Err(mpsc::error::TrySendError::Full(_)) = client.text_tx.try_send(msg.to_string()) {
+        return;
+    }
+    *current = found;
+}
+
+/// Ends a client's frame task and returns its pipeline to the pool. The caller has already
+/// removed the client from its receiver.
+pub async fn release(
+    state: &AppState,
+    client: Arc<AudioClient>,
+    mut frame_task: JoinHandle<()>,
+    shape: PipelineShape,
+) {
+    // The frame task holds the client (and the client its frame sender), so it only ends here.
+    frame_task.abort();
+    let _ = (&mut frame_task).await;
+    // The DSP thread may still hold the client for the frame in flight; the pipeline is then
+    // dropped instead of pooled.
+    if let Ok(client) = Arc::try_unwrap(client) {
+        let pipeline = match client.pipeline.into_inner() {
+            Ok(p) => p,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        state.audio_pipelines.release(shape, pipeline);
+    }
+}
+
+/// An audio listener without a WebSocket, fixed to one tune. It counts as an audio client of its
+/// receiver like a browser does and hands out mono PCM at the receiver's audio rate.
+pub struct HeadlessListener {
+    state: Arc<AppState>,
+    receiver: Arc<ReceiverState>,
+    client_id: ClientId,
+    client: Arc<AudioClient>,
+    shape: PipelineShape,
+    frame_task: JoinHandle<()>,
+}
+
+impl HeadlessListener {
+    /// Tunes to `frequency` (Hz) in `mode` with the mode's default passband. The returned queue
+    /// yields little-endian `i16` PCM, one buffer per demodulated frame.
+    pub async fn start(
+        state: Arc<AppState>,
+        receiver: Arc<ReceiverState>,
+        frequency: i64,
+        mode: DemodulationMode,
+    ) -> anyhow::Result<(Self, mpsc::Receiver<Vec<u8>>)> {
+        let shape = PipelineShape::of(&receiver);
+        let mut pipeline = audio_pool::acquire(&state, shape).await?;
+        pipeline.set_raw_pcm(true);
+
+        let m = receiver.frequency_bin(frequency);
+        let (l, r) = receiver.rt.mode_window(mode.as_str(), m);
+        let params = AudioParams {
+            l,
+            m,
+            r,
+            squelch_enabled: false,
+            demodulation: mode,
+            ..default_params(&receiver)
+        };
+        let unique_id = novasdr_core::util::generate_unique_id();
+        let (client, queues) = new_client(unique_id.clone(), params, pipeline, 1);
+        let client_id = state.alloc_client_id();
+        let frame_task = spawn_frame_task(state.clone(), client_id, client.clone(), queues.frames);
+        receiver.audio_clients.insert(client_id, client.clone());
+        state.broadcast_signal_changes(receiver.receiver.id.as_str(), &unique_id, l, m, r);
+        let listener = Self {
+            state,
+            receiver,
+            client_id,
+            client,
+            shape,
+            frame_task,
+        };
+        Ok((listener, queues.audio))
+    }
+
+    /// Tunes to `frequency` (Hz) in `mode` with the mode's default passband.
+    pub fn retune(&self, frequency: i64, mode: DemodulationMode) {
+        let m = self.receiver.frequency_bin(frequency);
+        let (l, r) = self.receiver.rt.mode_window(mode.as_str(), m);
+        {
+            let mut params = match self.client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %self.client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            params.l = l;
+            params.m = m;
+            params.r = r;
+            params.demodulation = mode;
+        }
+        self.state.broadcast_signal_changes(
+            self.receiver.receiver.id.as_str(),
+            &self.client.unique_id,
+            l,
+            m,
+            r,
+        );
+    }
+
+    pub fn now_playing(&self) -> Option<NowPlaying> {
+        match self.client.now_playing.lock() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    pub async fn stop(self) {
+        let Self {
+            state,
+            receiver,
+            client_id,
+            client,
+            shape,
+            frame_task,
+        } = self;
+        receiver.audio_clients.remove(&client_id);
+        state.broadcast_signal_changes(
+            receiver.receiver.id.as_str(),
+            &client.unique_id,
+            -1,
+            -1.0,
+            -1,
+        );
+        release(&state, client, frame_task, shape).await;
+    }
+}
@@ -0,0 +1,252 @@
+use crate::state::AppState;
+use axum::{
+    extract::{connect_info::ConnectInfo, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine as _};
+use dashmap::DashMap;
+use ring::{digest, hmac, rand::SecureRandom};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// First line of every signed session; bumping it invalidates tokens of an older layout.
+const SESSION_CONTEXT: &str = "novasdr-session-v1";
+/// Failed logins allowed per IP within [`LOGIN_FAILURE_WINDOW`] before further attempts get 429.
+const MAX_LOGIN_FAILURES: u32 = 5;
+const LOGIN_FAILURE_WINDOW: Duration = Duration::from_secs(300);
+
+/// Issues and checks operator session tokens.
+///
+/// Tokens are `<expires_at>.<nonce>.<signature>`, HMAC-SHA256 signed with a key generated at
+/// startup: nothing is stored server-side, and restarting the server signs everyone out.
+pub struct SessionSigner {
+    key: hmac::Key,
+    rng: ring::rand::SystemRandom,
+}
+
+impl SessionSigner {
+    pub fn new() -> anyhow::Result<Self> {
+        let rng = ring::rand::SystemRandom::new();
+        let key = hmac::Key::generate(hmac::HMAC_SHA256, &rng)
+            .map_err(|_| anyhow::anyhow!("generate session key"))?;
+        Ok(Self { key, rng })
+    }
+
+    fn message(expires_at: i64, nonce: &str) -> String {
+        format!("{SESSION_CONTEXT}\n{expires_at}\n{nonce}")
+    }
+
+    /// Returns a token valid until `expires_at` (Unix seconds).
+    pub fn issue(&self, expires_at: i64) -> anyhow::Result<String> {
+        let mut nonce = [0u8; 16];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| anyhow::anyhow!("generate session nonce"))?;
+        let nonce = BASE64URL.encode(nonce);
+        let tag = hmac::sign(&self.key, Self::message(expires_at, &nonce).as_bytes());
+        Ok(format!(
+            "{expires_at}.{nonce}.{}",
+            BASE64URL.encode(tag.as_ref())
+        ))
+    }
+
+    pub fn verify(&self, token: &str, now: i64) -> bool {
+        let mut parts = token.splitn(3, '.');
+        let (Some(expires_at), Some(nonce), Some(signature)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return false;
+        };
+        let Ok(expires_at) = expires_at.parse::<i64>() else {
+            return false;
+        };
+        let Ok(signature) = BASE64URL.decode(signature) else {
+            return false;
+        };
+        expires_at > now
+            && hmac::verify(
+                &self.key,
+                Self::message(expires_at, nonce).as_bytes(),
+                &signature,
+            )
+            .is_ok()
+    }
+}
+
+/// Failed login attempts per client IP.
+#[derive(Default)]
+pub struct LoginThrottle {
+    failures: DashMap<IpAddr, (u32, Instant)>,
+}
+
+impl LoginThrottle {
+    fn is_blocked(&self, ip: IpAddr, now: Instant) -> bool {
+        self.failures.get(&ip).is_some_and(|entry| {
+            let (count, since) = *entry;
+            count >= MAX_LOGIN_FAILURES && now.duration_since(since) < LOGIN_FAILURE_WINDOW
+        })
+    }
+
+    fn record_failure(&self, ip: IpAddr, now: Instant) {
+        let mut entry = self.failures.entry(ip).or_insert((0, now));
+        if now.duration_since(entry.1) >= LOGIN_FAILURE_WINDOW {
+            *entry = (0, now);
+        }
+        entry.0 += 1;
+    }
+
+    fn clear(&self, ip: IpAddr) {
+        self.failures.remove(&ip);
+    }
+
+    /// Forgets windows that have expired; called from the periodic sweep.
+    pub fn prune(&self, now: Instant) {
+        self.failures
+            .retain(|_, (_, since)| now.duration_since(*since) < LOGIN_FAILURE_WINDOW);
+    }
+}
+
+/// Checks `Authorization: Bearer <token>` against `admin.token` or a session from `/api/login`.
+///
+/// The admin API is reported as missing (404) when neither a token nor a password is configured
+/// so that public deployments do not advertise it.
+pub fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), (StatusCode, &'static str)> {
+    let admin = &state.cfg().admin;
+    if !admin.enabled() {
+        return Err((StatusCode::NOT_FOUND, "admin API disabled"));
+    }
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+    let token_ok =
+        !admin.token.is_empty() && constant_time_eq(provided.as_bytes(), admin.token.as_bytes());
+    let session_ok = !admin.password.is_empty()
+        && state
+            .sessions
+            .verify(provided, chrono::Utc::now().timestamp());
+    if !token_ok && !session_ok {
+        tracing::warn!("admin API request rejected: invalid token");
+        return Err((StatusCode::UNAUTHORIZED, "invalid admin token"));
+    }
+    Ok(())
+}
+
+/// Middleware for operator-only routes.
+pub async fn require_admin(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    req: Request,
+    next: Next,
+) -> Response {
+    if let Err(rejection) = authorize(&state, &headers) {
+        return rejection.into_response();
+    }
+    next.run(req).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub password: String,
+}
+
+/// `POST /api/login`: exchanges the operator password for a session token.
+pub async fn login(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(body): Json<LoginRequest>,
+) -> Response {
+    let admin = &state.cfg().admin;
+    if admin.password.is_empty() {
+        return (StatusCode::NOT_FOUND, "login disabled").into_response();
+    }
+    let ip = addr.ip();
+    let now = Instant::now();
+    if state.login_throttle.is_blocked(ip, now) {
+        return (StatusCode::TOO_MANY_REQUESTS, "too many failed logins").into_response();
+    }
+    // Compare digests so the comparison time does not depend on the password length either.
+    let provided = digest::digest(&digest::SHA256, body.password.as_bytes());
+    let expected = digest::digest(&digest::SHA256, admin.password.as_bytes());
+    if !constant_time_eq(provided.as_ref(), expected.as_ref()) {
+        state.login_throttle.record_failure(ip, now);
+        tracing::warn!(%ip, "operator login failed");
+        return (StatusCode::UNAUTHORIZED, "wrong password").into_response();
+    }
+    state.login_throttle.clear(ip);
+
+    let expires_at = chrono::Utc::now().timestamp() + admin.session_ttl_secs as i64;
+    match state.sessions.issue(expires_at) {
+        Ok(token) => {
+            tracing::info!(%ip, "operator logged in");
+            Json(json!({ "token": token, "expires_at": expires_at })).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = ?e, "failed to issue session token");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_time_eq_matches_only_identical_input() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"x"));
+    }
+
+    #[test]
+    fn session_tokens_verify_until_expiry_and_reject_tampering() {
+        let signer = SessionSigner::new().unwrap();
+        let token = signer.issue(1_000).unwrap();
+        assert!(signer.verify(&token, 999));
+        assert!(!signer.verify(&token, 1_000));
+
+        let extended = token.replacen("1000.", "9000.", 1);
+        assert!(!signer.verify(&extended, 999));
+        assert!(!signer.verify("", 0));
+        assert!(!signer.verify("1000.x.y", 0));
+
+        let other = SessionSigner::new().unwrap();
+        assert!(!other.verify(&token, 999));
+    }
+
+    #[test]
+    fn login_throttle_blocks_after_repeated_failures() {
+        let throttle = LoginThrottle::default();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let now = Instant::now();
+        for _ in 0..MAX_LOGIN_FAILURES {
+            assert!(!throttle.is_blocked(ip, now));
+            throttle.record_failure(ip, now);
+        }
+        assert!(throttle.is_blocked(ip, now));
+        assert!(!throttle.is_blocked(ip, now + LOGIN_FAILURE_WINDOW));
+        throttle.clear(ip);
+        assert!(!throttle.is_blocked(ip, now));
+    }
+}
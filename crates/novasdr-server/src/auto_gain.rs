@@ -0,0 +1,275 @@
+//! Gain sweep of a SoapySDR receiver (`POST /admin/receivers/{id}/auto_gain`): the overall gain
+//! is stepped up through the device's range while the input's clipping and the spectrum's noise
+//! floor are measured, and the gain that lifts the antenna noise clear of the ADC's own without
+//! overloading the front end is kept.
+
+use crate::state::{AppState, IqFrame, ReceiverState};
+use anyhow::Context;
+use novasdr_core::config::SoapySdrDriver;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Time a new gain gets before it is measured: tuner settling and samples already in flight.
+const SETTLE: Duration = Duration::from_millis(300);
+/// Measurement time per gain step.
+const MEASURE: Duration = Duration::from_secs(1);
+/// Fraction of samples at full scale from which a step counts as clipping.
+const MAX_CLIPPED: f64 = 1e-5;
+/// Gain kept above the point where the noise floor starts to follow the gain, i.e. where the
+/// antenna's noise starts to dominate the ADC's.
+const KNEE_MARGIN_DB: f64 = 6.0;
+/// Every this many bins enter the noise floor; the median hardly changes, the cost does.
+const FLOOR_BIN_STRIDE: usize = 16;
+const MAX_STEPS: usize = 64;
+
+/// Receivers with a sweep in progress; a second one would fight the first over the gain.
+static SWEEPING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Deserialize)]
+pub struct SweepRequest {
+    /// Part of the device's gain range to sweep, in dB; all of it by default.
+    #[serde(default)]
+    pub min_db: Option<f64>,
+    #[serde(default)]
+    pub max_db: Option<f64>,
+    #[serde(default = "default_step_db")]
+    pub step_db: f64,
+    /// Kept below the highest gain that did not clip, for signals stronger than those seen
+    /// during the sweep.
+    #[serde(default = "default_headroom_db")]
+    pub headroom_db: f64,
+    /// Also write the chosen gain to receivers.json as `input.driver.gain`.
+    #[serde(default)]
+    pub persist: bool,
+}
+
+fn default_step_db() -> f64 {
+    3.0
+}
+
+fn default_headroom_db() -> f64 {
+    6.0
+}
+
+/// Measurement at one gain.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Step {
+    pub gain_db: f64,
+    /// Median bin power, in dB of the receiver's unscaled FFT output.
+    pub floor_db: f64,
+    /// Fraction of input samples at the ADC's full scale.
+    pub clipped: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Outcome {
+    pub gain_db: f64,
+    pub steps: Vec<Step>,
+}
+
+struct SweepGuard(String);
+
+impl SweepGuard {
+    fn claim(receiver_id: &str) -> Option<Self> {
+        let mut sweeping = sweeping();
+        if sweeping.iter().any(|id| id == receiver_id) {
+            return None;
+        }
+        sweeping.push(receiver_id.to_string());
+        Some(Self(receiver_id.to_string()))
+    }
+}
+
+impl Drop for SweepGuard {
+    fn drop(&mut self) {
+        sweeping().retain(|id| *id != self.0);
+    }
+}
+
+fn sweeping() -> std::sync::MutexGuard<'static, Vec<String>> {
+    match SWEEPING.lock() {
+        Ok(g) => g,
+        Err(poisoned) => {
+            tracing::error!("auto gain mutex poisoned; recovering");
+            poisoned.into_inner()
+        }
+    }
+}
+
+/// Sweeps `receiver`'s gain and leaves it at the best one. On an error the gain stays at the
+/// last step tried.
+pub async fn sweep(
+    state: &AppState,
+    receiver: &ReceiverState,
+    driver: &SoapySdrDriver,
+    req: &SweepRequest,
+) -> anyhow::Result<Outcome> {
+    let receiver_id = receiver.receiver.id.as_str();
+    anyhow::ensure!(
+        driver.agc != Some(true),
+        "the receiver runs with agc = true; turn it off to sweep the gain"
+    );
+    anyhow::ensure!(
+        (0.5..=20.0).contains(&req.step_db),
+        "step_db must be between 0.5 and 20"
+    );
+    anyhow::ensure!(
+        (0.0..=30.0).contains(&req.headroom_db),
+        "headroom_db must be between 0 and 30"
+    );
+    let _guard = SweepGuard::claim(receiver_id).context("a gain sweep is already running")?;
+
+    let (lo, hi) = crate::input::gain_range(receiver_id)?;
+    let lo = req.min_db.map_or(lo, |min| min.max(lo));
+    let hi = req.max_db.map_or(hi, |max| max.min(hi));
+    anyhow::ensure!(lo < hi, "empty gain range {lo}..{hi} dB");
+
+    let (tx, mut frames) = crate::state::iq_frame_channel();
+    let client_id = state.alloc_client_id();
+    receiver.iq_clients.insert(client_id, tx);
+    let swept = async {
+        let mut steps = Vec::new();
+        let mut gain = lo;
+        while gain <= hi + 1e-9 && steps.len() < MAX_STEPS {
+            crate::input::set_gain(receiver_id, driver, gain)?;
+            let step = measure(receiver, &mut frames, gain).await?;
+            tracing::debug!(receiver_id, ?step, "auto gain step");
+            steps.push(step);
+            if step.clipped > MAX_CLIPPED {
+                // More gain only clips harder.
+                break;
+            }
+            gain += req.step_db;
+        }
+        anyhow::Ok(steps)
+    }
+    .await;
+    receiver.iq_clients.remove(&client_id);
+    let steps = swept?;
+
+    let gain_db = choose(&steps, req.headroom_db).unwrap_or(lo);
+    crate::input::set_gain(receiver_id, driver, gain_db)?;
+    tracing::info!(receiver_id, gain_db, steps = steps.len(), "auto gain done");
+    Ok(Outcome { gain_db, steps })
+}
+
+async fn measure(
+    receiver: &ReceiverState,
+    frames: &mut mpsc::Receiver<IqFrame>,
+    gain_db: f64,
+) -> anyhow::Result<Step> {
+    tokio::time::sleep(SETTLE).await;
+    // Frames queued meanwhile may predate the change.
+    while frames.try_recv().is_ok() {}
+    let samples_before = receiver.input_samples.load(Ordering::Relaxed);
+    let clipped_before = receiver.clipped_samples.load(Ordering::Relaxed);
+
+    let deadline = tokio::time::Instant::now() + MEASURE;
+    let mut power = Vec::new();
+    let mut floor_sum = 0.0f64;
+    let mut measured = 0usize;
+    loop {
+        match tokio::time::timeout_at(deadline, frames.recv()).await {
+            Ok(Some(frame)) => {
+                power.clear();
+                power.extend(
+                    frame
+                        .bins
+                        .iter()
+                        .step_by(FLOOR_BIN_STRIDE)
+                        .map(|b| b.norm_sqr()),
+                );
+                if power.is_empty() {
+                    continue;
+                }
+                let mid = power.len() / 2;
+                let (_, median, _) = power.select_nth_unstable_by(mid, f32::total_cmp);
+                floor_sum += f64::from(*median);
+                measured += 1;
+            }
+            Ok(None) => anyhow::bail!("receiver stopped during the sweep"),
+            Err(_) => break,
+        }
+    }
+    anyhow::ensure!(measured > 0, "no spectrum frames; is the input running?");
+
+    let samples = receiver
+        .input_samples
+        .load(Ordering::Relaxed)
+        .saturating_sub(samples_before);
+    let clipped = receiver
+        .clipped_samples
+        .load(Ordering::Relaxed)
+        .saturating_sub(clipped_before);
+    Ok(Step {
+        gain_db,
+        floor_db: 10.0 * (floor_sum / measured as f64).max(1e-30).log10(),
+        clipped: clipped as f64 / samples.max(1) as f64,
+    })
+}
+
+/// Gain to keep from ascending `steps`: [`KNEE_MARGIN_DB`] above the step where the noise floor
+/// started to follow the gain, at most `headroom_db` below the highest step that did not clip,
+/// and one of the steps tried. `None` when even the first step clipped.
+fn choose(steps: &[Step], headroom_db: f64) -> Option<f64> {
+    let clean = steps
+        .iter()
+        .take_while(|s| s.clipped <= MAX_CLIPPED)
+        .count();
+    let top = steps[..clean].last()?.gain_db;
+    // The floor rises with the gain once the antenna's noise dominates; before that the ADC's
+    // own noise holds it flat.
+    let knee = steps[..clean]
+        .windows(2)
+        .find(|w| w[1].floor_db - w[0].floor_db >= 0.5 * (w[1].gain_db - w[0].gain_db))
+        .map(|w| w[0].gain_db);
+    let wanted = knee.map_or(top, |k| k + KNEE_MARGIN_DB);
+    let limit = wanted.min(top - headroom_db);
+    Some(
+        steps[..clean]
+            .iter()
+            .map(|s| s.gain_db)
+            .rfind(|&g| g <= limit + 1e-9)
+            .unwrap_or(steps[0].gain_db),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(floors: &[f64], clip_from: usize) -> Vec<Step> {
+        floors
+            .iter()
+            .enumerate()
+            .map(|(i, &floor_db)| Step {
+                gain_db: i as f64 * 3.0,
+                floor_db,
+                clipped: if i >= clip_from { 1e-3 } else { 0.0 },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gain_sits_above_the_noise_knee_and_below_clipping() {
+        // Flat (ADC noise) up to 9 dB, then following the gain; clipping from 30 dB.
+        let s = steps(
+            &[
+                -90.0, -90.0, -90.0, -90.0, -87.0, -84.0, -81.0, -78.0, -75.0, -72.0, -69.0,
+            ],
+            10,
+        );
+        assert_eq!(choose(&s, 6.0), Some(15.0));
+        // Little headroom left: the clipping limit wins over the knee.
+        let s = steps(&[-90.0, -90.0, -90.0, -90.0, -87.0, -84.0, -81.0], 6);
+        assert_eq!(choose(&s, 6.0), Some(9.0));
+        // The floor never rises: the ADC's noise dominates, so take what the headroom allows.
+        let s = steps(&[-90.0; 8], 8);
+        assert_eq!(choose(&s, 6.0), Some(15.0));
+        // Clipping at the lowest gain already.
+        assert_eq!(choose(&steps(&[-60.0, -57.0], 0), 6.0), None);
+    }
+}
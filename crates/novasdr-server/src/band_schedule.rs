@@ -0,0 +1,87 @@
+//! Band switching by the clock (`receivers[].band_schedule`): at each configured time of day the
+//! receiver's input is reopened on another centre frequency. The spectrum layout stays, so
+//! listeners stay connected and keep their place in the band; they are told the band moved.
+
+use crate::state::{AppState, ReceiverState};
+use chrono::Timelike;
+use novasdr_core::protocol::EventTopics;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Centre frequency `receiver` should be on now: its schedule's, else `input.frequency`. Read
+/// from the current configuration, so a reload that changes the schedule applies at once.
+fn wanted_frequency(state: &AppState, receiver: &ReceiverState) -> i64 {
+    let cfg = state.cfg();
+    let entry = cfg
+        .receivers
+        .iter()
+        .find(|r| r.id == receiver.receiver.id)
+        .unwrap_or(&receiver.receiver);
+    entry
+        .scheduled_frequency(chrono::Utc::now())
+        .unwrap_or(entry.input.frequency)
+}
+
+/// Tunes every receiver with a schedule to its current band before the DSP threads open the
+/// inputs, so a start in the middle of a scheduled slot does not open the wrong band first.
+pub fn apply_initial(state: &AppState) {
+    for receiver in state.receivers.values() {
+        if !receiver.receiver.band_schedule.is_empty() {
+            receiver.set_center_frequency(wanted_frequency(state, receiver));
+        }
+    }
+}
+
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            // Shortly after each full minute, the resolution of `at`.
+            let into_minute = chrono::Utc::now().second() as u64;
+            tokio::time::sleep(Duration::from_secs(61 - into_minute)).await;
+            for receiver in state.receivers.values() {
+                if !receiver.receiver.enabled {
+                    continue;
+                }
+                let frequency = wanted_frequency(&state, receiver);
+                if frequency != receiver.center_frequency() {
+                    retune(&state, receiver, frequency);
+                }
+            }
+        }
+    });
+}
+
+/// Reopens `receiver`'s input on `frequency` and tells its listeners and `/events`.
+fn retune(state: &AppState, receiver: &ReceiverState, frequency: i64) {
+    let id = receiver.receiver.id.as_str();
+    let cfg = state.cfg();
+    let mut next = cfg
+        .receivers
+        .iter()
+        .find(|r| r.id == id)
+        .unwrap_or(&receiver.receiver)
+        .clone();
+    next.input.frequency = frequency;
+    tracing::info!(
+        receiver_id = %id,
+        from = receiver.center_frequency(),
+        to = frequency,
+        "band schedule: retuning"
+    );
+    receiver.set_center_frequency(frequency);
+    receiver.request_input_restart(next);
+
+    let msg = json!({
+        "type": "band_change",
+        "receiver_id": id,
+        "frequency": frequency,
+        "basefreq": receiver.basefreq(),
+        "total_bandwidth": receiver.rt.total_bandwidth,
+    })
+    .to_string();
+    for client in receiver.audio_clients.iter() {
+        let _ = client.text_tx.try_send(msg.clone());
+    }
+    state.broadcast_event_json(EventTopics::MAINTENANCE, msg);
+}
@@ -4,9 +4,10 @@ use rand::Rng;
 use novasdr_core::config::{Accelerator, AudioCompression};
 use novasdr_core::dsp::demod::DemodulationMode;
 use novasdr_core::dsp::fft::{FftEngine, FftSettings};
+use novasdr_core::dsp::simd::{self, SimdLevel};
 
 use crate::cli::BenchmarkKind;
-use crate::state::{AgcSpeed, AudioParams};
+use crate::state::{AgcSpeed, AudioParams, FilterSharpness};
 use crate::ws::audio::AudioPipeline;
 
 fn generate_random_vector_complex<T: Rng>(rng: &mut T, size: usize) -> Vec<Complex32> {
@@ -33,7 +34,7 @@ fn ssb_benchmark(iterations: usize) -> anyhow::Result<()> {
     let audio_fft_size = 8192;
     let is_real_input = false;
     let compression = AudioCompression::Adpcm;
-    let mut pipeline = AudioPipeline::new(sample_rate, audio_fft_size, compression)?;
+    let mut pipeline = AudioPipeline::new(sample_rate, audio_fft_size, 1, compression)?;
 
     let mut rng = rand::thread_rng();
     let spectrum = generate_random_vector_complex(&mut rng, audio_fft_size);
@@ -43,21 +44,105 @@ fn ssb_benchmark(iterations: usize) -> anyhow::Result<()> {
         r: 2000,
         mute: false,
         squelch_enabled: false,
+        squelch_mode: Default::default(),
+        rds: false,
+        stereo: false,
+        digital_voice: false,
+        subtone: false,
+        tone_squelch: None,
+        sstv: false,
+        fsk: None,
+        test_tone: None,
         demodulation: DemodulationMode::Usb,
         agc_speed: AgcSpeed::Off,
         agc_attack_ms: None,
         agc_release_ms: None,
+        filter_sharpness: FilterSharpness::Normal,
+        low_latency: false,
+        volume_db: 0.0,
+        hearing: Default::default(),
     };
 
     for idx in 0..iterations {
         let frame_num = idx as u64;
         let audio_mid_idx = params.m.floor() as i32;
 
-        let _ = pipeline.process(&spectrum, frame_num, &params, is_real_input, audio_mid_idx)?;
+        let _ = pipeline.process(
+            &spectrum,
+            frame_num,
+            &params,
+            is_real_input,
+            audio_mid_idx,
+            1.0,
+        )?;
     }
     Ok(())
 }
 
+/// Runs `f` `iterations` times and returns the throughput in million samples per second.
+fn msamples_per_sec(iterations: usize, samples: usize, mut f: impl FnMut()) -> f64 {
+    let start = std::time::Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    (iterations * samples) as f64 / start.elapsed().as_secs_f64() / 1e6
+}
+
+fn kernel_benchmark(iterations: usize, len: usize) {
+    let simd_level = simd::detected();
+    println!(
+        "Run kernel_benchmark for: iterations={} len={} simd={} ...",
+        iterations,
+        len,
+        simd_level.as_str()
+    );
+    let mut rng = rand::thread_rng();
+    let iq = generate_random_vector_complex(&mut rng, len);
+    let real = generate_random_vector_real(&mut rng, len);
+    let raw_u8: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+    let raw_i16: Vec<i16> = (0..len).map(|_| rng.gen()).collect();
+    let mut out_f32 = vec![0f32; len];
+    let mut out_i16 = vec![0i16; len];
+
+    for level in [SimdLevel::Scalar, simd_level] {
+        let name = level.as_str();
+        let report = |kernel: &str, rate: f64| println!("{kernel:>24} {name:>6}: {rate:9.1} MS/s");
+        report(
+            "am_envelope",
+            msamples_per_sec(iterations, len, || {
+                simd::am_envelope(level, &iq, &mut out_f32)
+            }),
+        );
+        report(
+            "polar_discriminator_fm",
+            msamples_per_sec(iterations, len, || {
+                simd::polar_discriminator_fm(level, &iq, Complex32::new(1.0, 0.0), &mut out_f32);
+            }),
+        );
+        report(
+            "float_to_i16_centered",
+            msamples_per_sec(iterations, len, || {
+                simd::float_to_i16_centered(level, &real, &mut out_i16, 32768.0)
+            }),
+        );
+        report(
+            "u8_to_f32",
+            msamples_per_sec(iterations, len, || {
+                simd::u8_to_f32(level, &raw_u8, &mut out_f32)
+            }),
+        );
+        report(
+            "i16_to_f32",
+            msamples_per_sec(iterations, len, || {
+                simd::i16_to_f32(level, &raw_i16, &mut out_f32)
+            }),
+        );
+        if level == simd_level {
+            break;
+        }
+    }
+}
+
 fn fft_benchmark(
     accelerator: Accelerator,
     is_real: bool,
@@ -80,6 +165,9 @@ fn fft_benchmark(
         downsample_levels,
         audio_max_fft_size,
         accelerator,
+        vulkan_device: None,
+        dc_suppression: Default::default(),
+        window: Default::default(),
     };
     let mut fft = FftEngine::new(settings)?;
 
@@ -110,6 +198,10 @@ pub fn run_benchmark(
         BenchmarkKind::VkFftComplex => (Accelerator::Vkfft, false),
         BenchmarkKind::VkFftReal => (Accelerator::Vkfft, true),
         BenchmarkKind::Ssb => return ssb_benchmark(iterations.unwrap_or(500)),
+        BenchmarkKind::Kernels => {
+            kernel_benchmark(iterations.unwrap_or(200), fftsize.unwrap_or(65536));
+            return Ok(());
+        }
     };
 
     fft_benchmark(
@@ -0,0 +1,454 @@
+//! Black box: receivers with `black_box.enabled` write wideband IQ around the clock into
+//! fixed-length segments under `capture.dir/<receiver id>/black_box/`, like a dashcam, deleting
+//! the oldest once they fall out of the look-back window. [`extract`] copies a stretch of them
+//! into an ordinary recording that `/api/recordings` lists.
+
+use crate::replay::IqSubscription;
+use crate::rtl_tcp::Tuning;
+use crate::schedule::{to_i16, WavWriter, HEADER_UPDATE};
+use crate::shutdown;
+use crate::state::{AppState, ReceiverState};
+use anyhow::Context;
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDateTime, Utc};
+use novasdr_core::codec::wav;
+use num_complex::Complex32;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Segments live here, under the receiver's directory, so recording listing and retention
+/// leave them alone.
+const SUBDIR: &str = "black_box";
+const SEGMENT_TIME: &str = "%Y%m%dT%H%M%S%.3fZ";
+/// Bytes of one IQ sample: 16-bit I, then 16-bit Q.
+const SAMPLE_BYTES: u64 = 4;
+const COPY_CHUNK: usize = 1 << 20;
+
+/// One segment file, named `<UTC start>_<frequency>_<sample rate>.wav`.
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    path: PathBuf,
+    start: DateTime<Utc>,
+    frequency: i64,
+    sample_rate: u32,
+    /// Complete IQ samples written so far.
+    samples: u64,
+}
+
+impl Segment {
+    fn name(start: DateTime<Utc>, frequency: i64, sample_rate: u32) -> String {
+        format!(
+            "{}_{frequency}_{sample_rate}.wav",
+            start.format(SEGMENT_TIME)
+        )
+    }
+
+    /// Start, frequency and sample rate from a segment file name.
+    fn parse_name(name: &str) -> Option<(DateTime<Utc>, i64, u32)> {
+        let mut parts = name.strip_suffix(".wav")?.split('_');
+        let start = NaiveDateTime::parse_from_str(parts.next()?, SEGMENT_TIME).ok()?;
+        let frequency = parts.next()?.parse().ok()?;
+        let sample_rate = parts.next()?.parse().ok()?;
+        if parts.next().is_some() || sample_rate == 0 {
+            return None;
+        }
+        Some((start.and_utc(), frequency, sample_rate))
+    }
+
+    fn end(&self) -> DateTime<Utc> {
+        self.start + micros(self.samples as f64 / f64::from(self.sample_rate))
+    }
+}
+
+fn micros(secs: f64) -> ChronoDuration {
+    ChronoDuration::microseconds((secs * 1e6).round() as i64)
+}
+
+/// Starts the black box of every receiver with `black_box.enabled`.
+pub fn spawn(state: Arc<AppState>) {
+    for receiver in state.receivers.values() {
+        if receiver.receiver.black_box.enabled {
+            tokio::spawn(run(state.clone(), receiver.clone()));
+        }
+    }
+}
+
+fn segment_dir(state: &AppState, receiver_id: &str) -> PathBuf {
+    PathBuf::from(&state.cfg().capture.dir)
+        .join(receiver_id)
+        .join(SUBDIR)
+}
+
+/// The frequency a receiver's black box is centred on.
+fn frequency(receiver: &ReceiverState) -> i64 {
+    let rt = &receiver.rt;
+    receiver
+        .receiver
+        .black_box
+        .frequency
+        .unwrap_or(rt.basefreq + rt.total_bandwidth / 2)
+}
+
+async fn run(state: Arc<AppState>, receiver: Arc<ReceiverState>) {
+    let receiver_id = receiver.receiver.id.as_str();
+    let cfg = &receiver.receiver.black_box;
+    let rt = &receiver.rt;
+    let frequency = frequency(&receiver);
+    if !(rt.basefreq..rt.basefreq + rt.total_bandwidth).contains(&frequency) {
+        tracing::warn!(
+            receiver_id,
+            frequency,
+            "black box frequency outside the receiver range"
+        );
+        return;
+    }
+    let max_rate = u32::try_from(rt.total_bandwidth).unwrap_or(u32::MAX);
+    let mut tuning = Tuning::new(&receiver, frequency, cfg.sample_rate, max_rate);
+    let sample_rate = tuning.sample_rate(&receiver).round() as u32;
+    let dir = segment_dir(&state, receiver_id);
+    let window = ChronoDuration::minutes(i64::from(cfg.minutes));
+    let segment = std::time::Duration::from_secs(u64::from(cfg.segment_seconds));
+
+    // Segments of other settings, from before a restart, cannot be joined with new ones.
+    for old in segments(&dir).await {
+        if old.frequency != frequency || old.sample_rate != sample_rate {
+            remove(&old.path).await;
+        }
+    }
+
+    let mut frames = IqSubscription::new(&state, &receiver, false);
+    tracing::info!(
+        receiver_id,
+        frequency,
+        sample_rate,
+        minutes = cfg.minutes,
+        dir = %dir.display(),
+        "black box started"
+    );
+    let mut iq: Vec<Complex32> = Vec::new();
+    let mut bytes: Vec<u8> = Vec::new();
+    'segments: while !shutdown::is_shutdown_requested() {
+        let start = Utc::now();
+        let res = async {
+            tokio::fs::create_dir_all(&dir).await?;
+            WavWriter::create(
+                &dir.join(Segment::name(start, frequency, sample_rate)),
+                sample_rate,
+                2,
+            )
+            .await
+        }
+        .await;
+        let mut out = match res {
+            Ok(out) => out,
+            Err(e) => {
+                tracing::warn!(receiver_id, error = ?e, "black box cannot create segment");
+                break;
+            }
+        };
+        for old in expired(&segments(&dir).await, start - window) {
+            remove(&old.path).await;
+        }
+
+        let end = tokio::time::Instant::now() + segment;
+        let mut header = tokio::time::interval(HEADER_UPDATE);
+        let res = async {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(end) => return anyhow::Ok(true),
+                    _ = header.tick() => out.update_header().await?,
+                    frame = frames.recv() => {
+                        let Some(frame) = frame else {
+                            return Ok(false);
+                        };
+                        tuning.process(&frame, rt.is_real, &mut iq);
+                        bytes.clear();
+                        for s in &iq {
+                            bytes.extend_from_slice(&to_i16(s.re).to_le_bytes());
+                            bytes.extend_from_slice(&to_i16(s.im).to_le_bytes());
+                        }
+                        out.write(&bytes).await?;
+                    }
+                }
+                if shutdown::is_shutdown_requested() {
+                    return Ok(false);
+                }
+            }
+        }
+        .await;
+        let flushed = out.update_header().await;
+        match res.and_then(|more| flushed.map(|()| more)) {
+            Ok(true) => {}
+            Ok(false) => break 'segments,
+            Err(e) => {
+                tracing::warn!(receiver_id, error = ?e, "black box write failed");
+                break 'segments;
+            }
+        }
+    }
+    tracing::info!(receiver_id, "black box stopped");
+}
+
+/// Segments in `dir`, oldest first.
+async fn segments(dir: &Path) -> Vec<Segment> {
+    let mut out = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return out;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Some((start, frequency, sample_rate)) =
+            entry.file_name().to_str().and_then(Segment::parse_name)
+        else {
+            continue;
+        };
+        let Ok(meta) = entry.metadata().await else {
+            continue;
+        };
+        out.push(Segment {
+            path: entry.path(),
+            start,
+            frequency,
+            sample_rate,
+            samples: meta.len().saturating_sub(wav::HEADER_LEN as u64) / SAMPLE_BYTES,
+        });
+    }
+    out.sort_by_key(|s| s.start);
+    out
+}
+
+/// Segments of `segments` (oldest first) that ended before `cutoff`. A segment ends where the
+/// next one starts, so the newest is never expired.
+fn expired(segments: &[Segment], cutoff: DateTime<Utc>) -> impl Iterator<Item = &Segment> {
+    segments
+        .windows(2)
+        .take_while(move |pair| pair[1].start <= cutoff)
+        .map(|pair| &pair[0])
+}
+
+async fn remove(path: &Path) {
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => tracing::debug!(path = %path.display(), "black box segment expired"),
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = ?e, "cannot remove black box segment")
+        }
+    }
+}
+
+/// The samples of one segment that fall into an extracted window.
+#[derive(Debug, PartialEq)]
+struct Piece {
+    segment: usize,
+    first: u64,
+    count: u64,
+}
+
+/// What of `segments` lies between `from` and `to`.
+fn pieces(segments: &[Segment], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Piece> {
+    let mut out = Vec::new();
+    for (index, seg) in segments.iter().enumerate() {
+        let a = from.max(seg.start);
+        let b = to.min(seg.end());
+        if b <= a {
+            continue;
+        }
+        let rate = f64::from(seg.sample_rate);
+        let offset = |t: DateTime<Utc>| {
+            let secs = (t - seg.start).num_microseconds().unwrap_or(0) as f64 / 1e6;
+            ((secs * rate).round() as u64).min(seg.samples)
+        };
+        let (first, last) = (offset(a), offset(b));
+        if last > first {
+            out.push(Piece {
+                segment: index,
+                first,
+                count: last - first,
+            });
+        }
+    }
+    out
+}
+
+/// A recording cut out of the black box.
+pub struct Extracted {
+    pub file: String,
+    pub frequency: i64,
+    pub sample_rate: u32,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Copies the black box IQ between `from` and `to` into
+/// `capture.dir/<receiver id>/<UTC start>_black_box.wav`, with a `.json` sidecar. `None` when
+/// nothing of that window is on disk.
+pub async fn extract(
+    state: &AppState,
+    receiver: &ReceiverState,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Option<Extracted>> {
+    let receiver_id = receiver.receiver.id.as_str();
+    let segments = segments(&segment_dir(state, receiver_id)).await;
+    let pieces = pieces(&segments, from, to);
+    let (Some(first), Some(last)) = (pieces.first(), pieces.last()) else {
+        return Ok(None);
+    };
+    let head = &segments[first.segment];
+    let tail = &segments[last.segment];
+    let rate = f64::from(head.sample_rate);
+    let start = head.start + micros(first.first as f64 / rate);
+    let end = tail.start + micros((last.first + last.count) as f64 / rate);
+
+    let dir = PathBuf::from(&state.cfg().capture.dir).join(receiver_id);
+    let stem = format!("{}_black_box", start.format("%Y%m%dT%H%M%SZ"));
+    tokio::fs::create_dir_all(&dir).await?;
+    let mut out = WavWriter::create(&dir.join(format!("{stem}.wav")), head.sample_rate, 2).await?;
+    let mut buf = vec![0u8; COPY_CHUNK];
+    for piece in &pieces {
+        let path = &segments[piece.segment].path;
+        let mut file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("open {}", path.display()))?;
+        file.seek(std::io::SeekFrom::Start(
+            wav::HEADER_LEN as u64 + piece.first * SAMPLE_BYTES,
+        ))
+        .await?;
+        let mut left = piece.count * SAMPLE_BYTES;
+        while left > 0 {
+            let want = buf.len().min(usize::try_from(left).unwrap_or(usize::MAX));
+            let n = file.read(&mut buf[..want]).await?;
+            if n == 0 {
+                break;
+            }
+            out.write(&buf[..n]).await?;
+            left -= n as u64;
+        }
+    }
+    out.update_header().await?;
+
+    let meta = json!({
+        "name": "black box",
+        "receiver_id": receiver_id,
+        "frequency": head.frequency,
+        "format": "iq",
+        "sample_rate": head.sample_rate,
+        "channels": 2,
+        "start": start.to_rfc3339(),
+        "end": end.to_rfc3339(),
+        "file": format!("{stem}.wav"),
+    });
+    let mut text = serde_json::to_string_pretty(&meta)?;
+    text.push('\n');
+    tokio::fs::write(dir.join(format!("{stem}.json")), text).await?;
+    tracing::info!(
+        receiver_id,
+        file = %format!("{stem}.wav"),
+        start = %start.to_rfc3339(),
+        end = %end.to_rfc3339(),
+        "black box extracted"
+    );
+    Ok(Some(Extracted {
+        file: format!("{stem}.wav"),
+        frequency: head.frequency,
+        sample_rate: head.sample_rate,
+        start,
+        end,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn segment(start: &str, samples: u64) -> Segment {
+        Segment {
+            path: PathBuf::new(),
+            start: at(start),
+            frequency: 7_100_000,
+            sample_rate: 1000,
+            samples,
+        }
+    }
+
+    #[test]
+    fn segment_names_round_trip() {
+        let start = at("2026-03-01T06:00:01.250Z");
+        let name = Segment::name(start, 7_100_000, 192_000);
+        assert_eq!(name, "20260301T060001.250Z_7100000_192000.wav");
+        assert_eq!(
+            Segment::parse_name(&name),
+            Some((start, 7_100_000, 192_000))
+        );
+        assert_eq!(Segment::parse_name("20260301T060001Z_beacon.wav"), None);
+    }
+
+    #[test]
+    fn segments_expire_once_the_next_one_is_out_of_the_window() {
+        let segs = [
+            segment("2026-03-01T06:00:00Z", 60_000),
+            segment("2026-03-01T06:01:00Z", 60_000),
+            segment("2026-03-01T06:02:00Z", 60_000),
+        ];
+        let starts = |cutoff: &str| {
+            expired(&segs, at(cutoff))
+                .map(|s| s.start)
+                .collect::<Vec<_>>()
+        };
+        assert!(starts("2026-03-01T06:00:59Z").is_empty());
+        assert_eq!(starts("2026-03-01T06:01:30Z"), [segs[0].start]);
+        assert_eq!(
+            starts("2026-03-01T07:00:00Z"),
+            [segs[0].start, segs[1].start]
+        );
+    }
+
+    #[test]
+    fn a_window_is_cut_across_segments() {
+        // The second segment is still being written: 20 s so far.
+        let segs = [
+            segment("2026-03-01T06:00:00Z", 60_000),
+            segment("2026-03-01T06:01:00Z", 20_000),
+        ];
+        assert_eq!(
+            pieces(
+                &segs,
+                at("2026-03-01T06:00:50Z"),
+                at("2026-03-01T06:01:05Z")
+            ),
+            [
+                Piece {
+                    segment: 0,
+                    first: 50_000,
+                    count: 10_000
+                },
+                Piece {
+                    segment: 1,
+                    first: 0,
+                    count: 5_000
+                },
+            ]
+        );
+        assert_eq!(
+            pieces(
+                &segs,
+                at("2026-03-01T06:01:10Z"),
+                at("2026-03-01T06:05:00Z")
+            ),
+            [Piece {
+                segment: 1,
+                first: 10_000,
+                count: 10_000
+            }]
+        );
+        assert!(pieces(
+            &segs,
+            at("2026-03-01T05:00:00Z"),
+            at("2026-03-01T05:59:00Z")
+        )
+        .is_empty());
+    }
+}
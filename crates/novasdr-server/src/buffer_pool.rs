@@ -0,0 +1,178 @@
+//! Size-keyed pools of DSP scratch buffers. An [`AudioPipeline`](crate::ws::audio::AudioPipeline)
+//! needs its FFT workspaces only while it demodulates a frame, so instead of every client keeping
+//! its own, they are borrowed from here for the frame: the number alive follows the frames being
+//! demodulated at once rather than the client count, and long-running servers stop collecting
+//! one set of large allocations per client that ever connected.
+
+use num_complex::Complex32;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Idle buffers kept per size. Buffers are returned right after each frame, so this only has to
+/// cover the frames demodulated in parallel; returns beyond it (after a burst) are freed.
+const MAX_IDLE_PER_SIZE: usize = 64;
+
+/// Complex workspaces of the audio demodulators.
+pub static COMPLEX: BufferPool<Complex32> = BufferPool::new("complex");
+
+pub struct BufferPool<T: 'static> {
+    name: &'static str,
+    /// Idle buffers by length; few distinct lengths exist, so a list beats a map.
+    shelves: Mutex<Vec<(usize, Vec<Vec<T>>)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    in_use_bytes: AtomicUsize,
+    peak_in_use_bytes: AtomicUsize,
+    idle_bytes: AtomicUsize,
+}
+
+/// Counters of one pool, for `/metrics`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    pub name: &'static str,
+    pub hits: u64,
+    pub misses: u64,
+    pub in_use_bytes: usize,
+    pub peak_in_use_bytes: usize,
+    pub idle_bytes: usize,
+}
+
+impl<T: Copy + Default + 'static> BufferPool<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            shelves: Mutex::new(Vec::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            in_use_bytes: AtomicUsize::new(0),
+            peak_in_use_bytes: AtomicUsize::new(0),
+            idle_bytes: AtomicUsize::new(0),
+        }
+    }
+
+    fn shelves(&self) -> MutexGuard<'_, Vec<(usize, Vec<Vec<T>>)>> {
+        match self.shelves.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(pool = self.name, "buffer pool mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    /// A zeroed buffer of `len` elements, returned to the pool when dropped.
+    pub fn take(&'static self, len: usize) -> Pooled<T> {
+        let reused = self
+            .shelves()
+            .iter_mut()
+            .find(|(size, _)| *size == len)
+            .and_then(|(_, idle)| idle.pop());
+        let bytes = len * std::mem::size_of::<T>();
+        let buf = match reused {
+            Some(mut buf) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                self.idle_bytes.fetch_sub(bytes, Ordering::Relaxed);
+                buf.fill(T::default());
+                buf
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                vec![T::default(); len]
+            }
+        };
+        let in_use = self.in_use_bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.peak_in_use_bytes.fetch_max(in_use, Ordering::Relaxed);
+        Pooled { buf, pool: self }
+    }
+
+    fn put(&self, buf: Vec<T>) {
+        let len = buf.len();
+        let bytes = len * std::mem::size_of::<T>();
+        self.in_use_bytes.fetch_sub(bytes, Ordering::Relaxed);
+        let mut shelves = self.shelves();
+        let idle = match shelves.iter().position(|(size, _)| *size == len) {
+            Some(i) => &mut shelves[i].1,
+            None => {
+                shelves.push((len, Vec::new()));
+                &mut shelves.last_mut().expect("just pushed").1
+            }
+        };
+        if idle.len() < MAX_IDLE_PER_SIZE {
+            idle.push(buf);
+            self.idle_bytes.fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            name: self.name,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            in_use_bytes: self.in_use_bytes.load(Ordering::Relaxed),
+            peak_in_use_bytes: self.peak_in_use_bytes.load(Ordering::Relaxed),
+            idle_bytes: self.idle_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Stats of every pool.
+pub fn stats() -> [PoolStats; 1] {
+    [COMPLEX.stats()]
+}
+
+/// A buffer borrowed from a [`BufferPool`].
+pub struct Pooled<T: Copy + Default + 'static> {
+    buf: Vec<T>,
+    pool: &'static BufferPool<T>,
+}
+
+impl<T: Copy + Default + 'static> Deref for Pooled<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.buf
+    }
+}
+
+impl<T: Copy + Default + 'static> DerefMut for Pooled<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.buf
+    }
+}
+
+impl<T: Copy + Default + 'static> Drop for Pooled<T> {
+    fn drop(&mut self) {
+        self.pool.put(std::mem::take(&mut self.buf));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_are_reused_by_size_and_come_back_zeroed() {
+        static POOL: BufferPool<f32> = BufferPool::new("test");
+        {
+            let mut a = POOL.take(16);
+            a.fill(1.0);
+            let _b = POOL.take(16);
+            let _c = POOL.take(32);
+            let s = POOL.stats();
+            assert_eq!((s.hits, s.misses), (0, 3));
+            assert_eq!(s.in_use_bytes, 64 * 4);
+            assert_eq!(s.idle_bytes, 0);
+        }
+        let s = POOL.stats();
+        assert_eq!(s.in_use_bytes, 0);
+        assert_eq!(s.peak_in_use_bytes, 64 * 4);
+        assert_eq!(s.idle_bytes, 64 * 4);
+
+        let a = POOL.take(16);
+        assert!(a.iter().all(|&x| x == 0.0));
+        let s = POOL.stats();
+        assert_eq!((s.hits, s.misses), (1, 3));
+        assert_eq!(s.idle_bytes, 48 * 4);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::colormap::Colormap;
+use crate::state::{AppState, ReceiverState, WatchLevel};
+use anyhow::Context;
+use novasdr_core::{
+    codec::png,
+    config::CaptureTrigger,
+    dsp::peaks::{noise_floor_db, PeakDetector},
+};
+use num_complex::Complex32;
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+/// Target spacing of screenshot rows.
+const ROW_INTERVAL_MS: f64 = 100.0;
+/// Rows kept before a trigger fires (about 30 s at the target row rate).
+const HISTORY_ROWS: usize = 300;
+/// Rows recorded after the trigger so the event itself is visible.
+const POST_TRIGGER_ROWS: usize = 50;
+const MAX_WIDTH_PX: usize = 1024;
+const WRITE_QUEUE_DEPTH: usize = 4;
+/// Colour scale: starts this far below the image noise floor and spans `DISPLAY_RANGE_DB`.
+pub const FLOOR_MARGIN_DB: f32 = 5.0;
+pub const DISPLAY_RANGE_DB: f32 = 50.0;
+const POWER_FLOOR: f32 = 1e-20;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Pending {
+    fired_ms: u64,
+    peak_hz: i64,
+    snr_db: f32,
+    rows_left: usize,
+}
+
+struct Watch {
+    trigger: CaptureTrigger,
+    start_bin: usize,
+    bins: usize,
+    width: usize,
+    detector: PeakDetector,
+    history: VecDeque<Vec<f32>>,
+    last_fired_ms: Option<u64>,
+    pending: Option<Pending>,
+}
+
+impl Watch {
+    /// Max-pools the watched bins to at most `MAX_WIDTH_PX` columns so narrow carriers survive.
+    fn row(&self, spectrum: &[Complex32], base_idx: usize) -> Vec<f32> {
+        let len = spectrum.len();
+        (0..self.width)
+            .map(|px| {
+                let lo = px * self.bins / self.width;
+                let hi = ((px + 1) * self.bins / self.width).max(lo + 1);
+                let power = (lo..hi)
+                    .map(|k| spectrum[(self.start_bin + k + base_idx) % len].norm_sqr())
+                    .fold(0f32, f32::max);
+                10.0 * (power + POWER_FLOOR).log10()
+            })
+            .collect()
+    }
+}
+
+struct CaptureJob {
+    receiver_id: String,
+    trigger: String,
+    start_hz: i64,
+    end_hz: i64,
+    fired_ms: u64,
+    peak_hz: i64,
+    snr_db: f32,
+    row_interval_ms: f64,
+    rows: Vec<Vec<f32>>,
+}
+
+/// Watches configured frequency ranges of one receiver and archives a waterfall screenshot when
+/// a new strong signal appears.
+pub struct EventCapture {
+    receiver_id: String,
+    receiver: Arc<ReceiverState>,
+    watches: Vec<Watch>,
+    base_idx: usize,
+    basefreq: i64,
+    bin_hz: f64,
+    frames_per_row: u64,
+    row_interval_ms: f64,
+    jobs: mpsc::Sender<CaptureJob>,
+}
+
+impl EventCapture {
+    /// Must be called from within the Tokio runtime; the writer runs as a task.
+    pub fn new(state: Arc<AppState>, receiver: &Arc<ReceiverState>) -> Option<Self> {
+        let triggers = &receiver.receiver.capture_triggers;
+        if triggers.is_empty() {
+            return None;
+        }
+        let rt = &receiver.rt;
+        let receiver_id = receiver.receiver.id.clone();
+        let bin_hz = rt.total_bandwidth as f64 / rt.fft_result_size as f64;
+        let top = rt.basefreq + rt.total_bandwidth;
+
+        let mut watches = Vec::new();
+        for t in triggers.iter() {
+            let start_hz = t.start_hz.max(rt.basefreq);
+            let end_hz = t.end_hz.min(top);
+            if end_hz <= start_hz {
+                tracing::warn!(
+                    receiver_id = %receiver_id,
+                    trigger = %t.name,
+                    "capture trigger outside receiver range; ignored"
+                );
+                continue;
+            }
+            let start_bin = ((start_hz - rt.basefreq) as f64 / bin_hz).floor() as usize;
+            let end_bin =
+                (((end_hz - rt.basefreq) as f64 / bin_hz).ceil() as usize).min(rt.fft_result_size);
+            let bins = end_bin.saturating_sub(start_bin).max(1);
+            watches.push(Watch {
+                trigger: t.clone(),
+                start_bin,
+                bins,
+                width: bins.min(MAX_WIDTH_PX),
+                detector: PeakDetector::new(t.threshold_db),
+                history: VecDeque::with_capacity(HISTORY_ROWS + POST_TRIGGER_ROWS),
+                last_fired_ms: None,
+                pending: None,
+            });
+        }
+        if watches.is_empty() {
+            return None;
+        }
+
+        let frame_ms = (rt.fft_size / 2) as f64 * 1000.0 / rt.sps as f64;
+        let frames_per_row = ((ROW_INTERVAL_MS / frame_ms).round() as u64).max(1);
+        let (jobs, jobs_rx) = mpsc::channel(WRITE_QUEUE_DEPTH);
+        tokio::spawn(run_writer(state, jobs_rx));
+        tracing::info!(
+            receiver_id = %receiver_id,
+            triggers = watches.len(),
+            "event capture enabled"
+        );
+
+        Some(Self {
+            receiver_id,
+            receiver: receiver.clone(),
+            watches,
+            base_idx: if rt.is_real { 0 } else { rt.fft_size / 2 + 1 },
+            basefreq: rt.basefreq,
+            bin_hz,
+            frames_per_row,
+            row_interval_ms: frames_per_row as f64 * frame_ms,
+            jobs,
+        })
+    }
+
+    pub fn process(&mut self, spectrum: &[Complex32], frame_num: u64, now_ms: u64) {
+        if !frame_num.is_multiple_of(self.frames_per_row) {
+            return;
+        }
+        let calibration = self.receiver.smeter_calibration();
+        let smeter_offset = self.receiver.receiver.input.smeter_offset as f32;
+        for w in self.watches.iter_mut() {
+            let row = w.row(spectrum, self.base_idx);
+            let offset_db =
+                smeter_offset + calibration.offset_db((w.trigger.start_hz + w.trigger.end_hz) / 2);
+            self.receiver.watch_levels.insert(
+                w.trigger.name.clone(),
+                WatchLevel {
+                    peak_db: row.iter().copied().fold(f32::NEG_INFINITY, f32::max) + offset_db,
+                    floor_db: noise_floor_db(&row) + offset_db,
+                },
+            );
+            let peak = w.detector.update(&row);
+            if w.history.len() >= HISTORY_ROWS + POST_TRIGGER_ROWS {
+                w.history.pop_front();
+            }
+            w.history.push_back(row);
+
+            if let Some(p) = w.pending.as_mut() {
+                p.rows_left = p.rows_left.saturating_sub(1);
+                if p.rows_left == 0 {
+                    if let Some(p) = w.pending.take() {
+                        let job = CaptureJob {
+                            receiver_id: self.receiver_id.clone(),
+                            trigger: w.trigger.name.clone(),
+                            start_hz: w.trigger.start_hz,
+                            end_hz: w.trigger.end_hz,
+                            fired_ms: p.fired_ms,
+                            peak_hz: p.peak_hz,
+                            snr_db: p.snr_db,
+                            row_interval_ms: self.row_interval_ms,
+                            rows: w.history.iter().cloned().collect(),
+                        };
+                        if self.jobs.try_send(job).is_err() {
+                            tracing::warn!(
+                                receiver_id = %self.receiver_id,
+                                trigger = %w.trigger.name,
+                                "capture writer busy; screenshot dropped"
+                            );
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let Some(peak) = peak else {
+                continue;
+            };
+            let holdoff_ms = w.trigger.holdoff_secs.saturating_mul(1000);
+            if w.last_fired_ms
+                .is_some_and(|last| now_ms.saturating_sub(last) < holdoff_ms)
+            {
+                continue;
+            }
+            let bin = w.start_bin as f64 + (peak.bin as f64 + 0.5) * w.bins as f64 / w.width as f64;
+            let peak_hz = self.basefreq + (bin * self.bin_hz).round() as i64;
+            tracing::info!(
+                receiver_id = %self.receiver_id,
+                trigger = %w.trigger.name,
+                peak_hz,
+                snr_db = peak.snr_db(),
+                "capture trigger fired"
+            );
+            w.last_fired_ms = Some(now_ms);
+            w.pending = Some(Pending {
+                fired_ms: now_ms,
+                peak_hz,
+                snr_db: peak.snr_db(),
+                rows_left: POST_TRIGGER_ROWS,
+            });
+        }
+    }
+}
+
+/// Renders rows newest-first, matching the live waterfall's scroll direction.
+fn render(rows: &[Vec<f32>]) -> anyhow::Result<Vec<u8>> {
+    let width = rows.first().map(Vec::len).unwrap_or(0);
+    let all: Vec<f32> = rows.iter().flatten().copied().collect();
+    let low = noise_floor_db(&all) - FLOOR_MARGIN_DB;
+    let mut rgb = Vec::with_capacity(width * rows.len() * 3);
+    for row in rows.iter().rev() {
+        for &db in row.iter() {
+            rgb.extend_from_slice(&Colormap::Default.rgb((db - low) / DISPLAY_RANGE_DB));
+        }
+    }
+    png::encode_rgb(width as u32, rows.len() as u32, &rgb)
+}
+
+pub fn file_safe(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Writes `<stem>.png` and `<stem>.json` and returns the metadata document.
+fn write_capture(dir: &Path, job: &CaptureJob) -> anyhow::Result<serde_json::Value> {
+    let fired = chrono::DateTime::<chrono::Utc>::from_timestamp_millis(job.fired_ms as i64)
+        .context("capture timestamp out of range")?;
+    let receiver_dir = dir.join(file_safe(&job.receiver_id));
+    std::fs::create_dir_all(&receiver_dir)
+        .with_context(|| format!("create {}", receiver_dir.display()))?;
+    let stem = format!(
+        "{}_{}",
+        fired.format("%Y%m%dT%H%M%SZ"),
+        file_safe(&job.trigger)
+    );
+    let image_path: PathBuf = receiver_dir.join(format!("{stem}.png"));
+    let meta_path = receiver_dir.join(format!("{stem}.json"));
+
+    let png = render(&job.rows)?;
+    std::fs::write(&image_path, png).with_context(|| format!("write {}", image_path.display()))?;
+
+    let meta = serde_json::json!({
+        "receiver_id": job.receiver_id,
+        "trigger": job.trigger,
+        "time": fired.to_rfc3339(),
+        "start_hz": job.start_hz,
+        "end_hz": job.end_hz,
+        "peak_hz": job.peak_hz,
+        "snr_db": (job.snr_db * 10.0).round() / 10.0,
+        "rows": job.rows.len(),
+        "post_trigger_rows": POST_TRIGGER_ROWS,
+        "row_interval_ms": job.row_interval_ms,
+        "image": format!("{stem}.png"),
+        "image_path": image_path.display().to_string(),
+    });
+    let mut text = serde_json::to_string_pretty(&meta).context("serialize capture metadata")?;
+    text.push('\n');
+    std::fs::write(&meta_path, text).with_context(|| format!("write {}", meta_path.display()))?;
+    Ok(meta)
+}
+
+async fn run_writer(state: Arc<AppState>, mut jobs: mpsc::Receiver<CaptureJob>) {
+    let dir = PathBuf::from(&state.cfg().capture.dir);
+    let webhook = state.cfg().capture.webhook_url.trim().to_string();
+    let client = reqwest::Client::new();
+    while let Some(job) = jobs.recv().await {
+        if crate::disk_guard::recordings_paused() {
+            tracing::debug!(trigger = %job.trigger, "capture dropped: disk above its high water mark");
+            continue;
+        }
+        let dir = dir.clone();
+        let meta = match tokio::task::spawn_blocking(move || write_capture(&dir, &job)).await {
+            Ok(Ok(meta)) => meta,
+            Ok(Err(e)) => {
+                tracing::warn!(error = ?e, "capture write failed");
+                continue;
+            }
+            Err(e) => {
+                tracing::error!(error = ?e, "capture writer task panicked");
+                continue;
+            }
+        };
+        tracing::info!(image = %meta["image_path"], "capture saved");
+        if webhook.is_empty() {
+            continue;
+        }
+        let res = client
+            .post(&webhook)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&meta)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        if let Err(e) = res {
+            tracing::warn!(error = %e, "capture webhook failed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_are_sanitised() {
+        assert_eq!(file_safe("40m beacon/CW"), "40m_beacon_CW");
+    }
+}
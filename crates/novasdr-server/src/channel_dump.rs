@@ -0,0 +1,310 @@
+//! Channel dumps: fixed channels of receivers with `channel_dump.enabled`, each demodulated by a
+//! headless listener around the clock and written side by side into one multichannel WAV file
+//! per segment under `capture.dir`, where `/api/recordings` lists them.
+
+use crate::audio_listener::HeadlessListener;
+use crate::schedule::{WavWriter, HEADER_UPDATE};
+use crate::shutdown;
+use crate::state::{AppState, ReceiverState};
+use chrono::{DateTime, Utc};
+use novasdr_core::dsp::demod::DemodulationMode;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How far one channel may run ahead of another before the one behind is padded with silence,
+/// in seconds. Listeners hand out one buffer per FFT frame, so they only drift apart when one
+/// of them lost audio.
+const MAX_SKEW_SECS: f64 = 1.0;
+/// How often the loop checks for shutdown while no audio arrives.
+const POLL: Duration = Duration::from_secs(1);
+
+/// One channel of the file, in the order of the WAV channels.
+struct Channel {
+    frequency: i64,
+    mode: DemodulationMode,
+    name: String,
+}
+
+/// Starts the dump of every receiver with `channel_dump.enabled`.
+pub fn spawn(state: Arc<AppState>) {
+    for receiver in state.receivers.values() {
+        if receiver.receiver.channel_dump.enabled {
+            tokio::spawn(run(state.clone(), receiver.clone()));
+        }
+    }
+}
+
+async fn run(state: Arc<AppState>, receiver: Arc<ReceiverState>) {
+    let receiver_id = receiver.receiver.id.as_str();
+    let rt = &receiver.rt;
+    let default_mode =
+        DemodulationMode::from_str_upper(receiver.settings().default_mode_str.as_str())
+            .unwrap_or(DemodulationMode::Usb);
+    let top = rt.basefreq + rt.total_bandwidth;
+    let mut channels = Vec::new();
+    for ch in receiver.receiver.channel_dump.channels.iter() {
+        if !(rt.basefreq..top).contains(&ch.frequency) {
+            tracing::warn!(
+                receiver_id,
+                frequency = ch.frequency,
+                "channel dump channel outside the receiver range"
+            );
+            continue;
+        }
+        channels.push(Channel {
+            frequency: ch.frequency,
+            mode: ch.demodulation().unwrap_or(default_mode),
+            name: ch.name.trim().to_string(),
+        });
+    }
+    if channels.is_empty() {
+        return;
+    }
+
+    let (tx, mut pcm) = mpsc::channel::<(usize, Vec<u8>)>(channels.len() * 4);
+    let mut listeners = Vec::new();
+    for (index, ch) in channels.iter().enumerate() {
+        let (listener, mut rx) =
+            match HeadlessListener::start(state.clone(), receiver.clone(), ch.frequency, ch.mode)
+                .await
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    tracing::warn!(
+                        receiver_id,
+                        frequency = ch.frequency,
+                        error = ?e,
+                        "channel dump channel failed to start; it stays silent"
+                    );
+                    continue;
+                }
+            };
+        listeners.push(listener);
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            while let Some(buf) = rx.recv().await {
+                if tx.send((index, buf)).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
+    if listeners.is_empty() {
+        return;
+    }
+
+    let sample_rate = rt.audio_max_sps as u32;
+    let segment =
+        chrono::Duration::minutes(i64::from(receiver.receiver.channel_dump.segment_minutes));
+    let dir = PathBuf::from(&state.cfg().capture.dir).join(receiver_id);
+    let max_skew = (f64::from(sample_rate) * MAX_SKEW_SECS) as usize;
+    let mut interleaver = Interleaver::new(channels.len(), max_skew);
+    let mut bytes = Vec::new();
+    tracing::info!(
+        receiver_id,
+        channels = channels.len(),
+        sample_rate,
+        dir = %dir.display(),
+        "channel dump started"
+    );
+    'segments: while !shutdown::is_shutdown_requested() {
+        if crate::disk_guard::recordings_paused() {
+            // No new file on a full disk; the channels are drained meanwhile.
+            tokio::select! {
+                _ = tokio::time::sleep(POLL) => {}
+                buf = pcm.recv() => if buf.is_none() {
+                    break 'segments;
+                },
+            }
+            interleaver = Interleaver::new(channels.len(), max_skew);
+            continue;
+        }
+        let start = Utc::now();
+        let stem = format!("{}_channels", start.format("%Y%m%dT%H%M%SZ"));
+        let res = async {
+            tokio::fs::create_dir_all(&dir).await?;
+            WavWriter::create(
+                &dir.join(format!("{stem}.wav")),
+                sample_rate,
+                channels.len() as u16,
+            )
+            .await
+        }
+        .await;
+        let mut out = match res {
+            Ok(out) => out,
+            Err(e) => {
+                tracing::warn!(receiver_id, error = ?e, "channel dump cannot create file");
+                break;
+            }
+        };
+        let end = tokio::time::Instant::now() + segment.to_std().unwrap_or_default();
+        let mut header = tokio::time::interval(HEADER_UPDATE);
+        let mut poll = tokio::time::interval(POLL);
+        let res = async {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(end) => return anyhow::Ok(true),
+                    _ = header.tick() => out.update_header().await?,
+                    _ = poll.tick() => {}
+                    buf = pcm.recv() => {
+                        let Some((index, buf)) = buf else {
+                            return Ok(false);
+                        };
+                        interleaver.push(index, &buf);
+                        bytes.clear();
+                        interleaver.drain(&mut bytes);
+                        out.write(&bytes).await?;
+                    }
+                }
+                if shutdown::is_shutdown_requested() {
+                    return Ok(false);
+                }
+            }
+        }
+        .await;
+        let flushed = out.update_header().await;
+        write_sidecar(&dir, &stem, receiver_id, sample_rate, &channels, start).await;
+        match res.and_then(|more| flushed.map(|()| more)) {
+            Ok(true) => {}
+            Ok(false) => break 'segments,
+            Err(e) => {
+                tracing::warn!(receiver_id, error = ?e, "channel dump write failed");
+                break 'segments;
+            }
+        }
+    }
+    for listener in listeners {
+        listener.stop().await;
+    }
+    tracing::info!(receiver_id, "channel dump stopped");
+}
+
+/// The `.json` sidecar of a finished file: which WAV channel holds which frequency.
+async fn write_sidecar(
+    dir: &Path,
+    stem: &str,
+    receiver_id: &str,
+    sample_rate: u32,
+    channels: &[Channel],
+    start: DateTime<Utc>,
+) {
+    let meta = json!({
+        "name": "channel dump",
+        "receiver_id": receiver_id,
+        "format": "channels",
+        "sample_rate": sample_rate,
+        "channels": channels.len(),
+        "channel_list": channels
+            .iter()
+            .map(|ch| json!({ "frequency": ch.frequency, "mode": ch.mode.as_str(), "name": ch.name }))
+            .collect::<Vec<_>>(),
+        "start": start.to_rfc3339(),
+        "end": Utc::now().to_rfc3339(),
+        "file": format!("{stem}.wav"),
+    });
+    let path = dir.join(format!("{stem}.json"));
+    let res = async {
+        let mut text = serde_json::to_string_pretty(&meta)?;
+        text.push('\n');
+        tokio::fs::write(&path, text).await?;
+        anyhow::Ok(())
+    }
+    .await;
+    if let Err(e) = res {
+        tracing::warn!(path = %path.display(), error = ?e, "cannot write recording metadata");
+    }
+}
+
+/// Lines mono 16-bit PCM of several channels up into interleaved frames.
+struct Interleaver {
+    queues: Vec<VecDeque<i16>>,
+    max_skew: usize,
+}
+
+impl Interleaver {
+    fn new(channels: usize, max_skew: usize) -> Self {
+        Self {
+            queues: vec![VecDeque::new(); channels],
+            max_skew,
+        }
+    }
+
+    /// Queues little-endian `i16` samples of channel `index`.
+    fn push(&mut self, index: usize, pcm: &[u8]) {
+        let Some(queue) = self.queues.get_mut(index) else {
+            return;
+        };
+        queue.extend(
+            pcm.chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        );
+    }
+
+    /// Appends every frame all channels have a sample for, as little-endian `i16`. A channel
+    /// more than `max_skew` samples behind the furthest one is padded with silence first.
+    fn drain(&mut self, out: &mut Vec<u8>) {
+        let longest = self.queues.iter().map(VecDeque::len).max().unwrap_or(0);
+        for queue in self.queues.iter_mut() {
+            if longest - queue.len() > self.max_skew {
+                let missing = longest - queue.len();
+                queue.extend(std::iter::repeat_n(0, missing));
+            }
+        }
+        let frames = self.queues.iter().map(VecDeque::len).min().unwrap_or(0);
+        out.reserve(frames * self.queues.len() * 2);
+        for _ in 0..frames {
+            for queue in self.queues.iter_mut() {
+                let s = queue.pop_front().unwrap_or_default();
+                out.extend_from_slice(&s.to_le_bytes());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pcm(samples: &[i16]) -> Vec<u8> {
+        samples.iter().flat_map(|s| s.to_le_bytes()).collect()
+    }
+
+    fn samples(bytes: &[u8]) -> Vec<i16> {
+        bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn channels_are_interleaved_once_all_have_audio() {
+        let mut il = Interleaver::new(2, 100);
+        let mut out = Vec::new();
+        il.push(0, &pcm(&[1, 2, 3]));
+        il.drain(&mut out);
+        assert!(out.is_empty());
+        il.push(1, &pcm(&[-1, -2]));
+        il.drain(&mut out);
+        assert_eq!(samples(&out), [1, -1, 2, -2]);
+        out.clear();
+        il.push(1, &pcm(&[-3, -4]));
+        il.drain(&mut out);
+        assert_eq!(samples(&out), [3, -3]);
+    }
+
+    #[test]
+    fn a_channel_that_lost_audio_is_padded() {
+        let mut il = Interleaver::new(2, 2);
+        let mut out = Vec::new();
+        il.push(0, &pcm(&[1, 2, 3, 4]));
+        il.push(1, &pcm(&[-1]));
+        il.drain(&mut out);
+        assert_eq!(samples(&out), [1, -1, 2, 0, 3, 0, 4, 0]);
+    }
+}
@@ -0,0 +1,612 @@
+//! Shared demodulated channels and channelizer channels.
+//!
+//! The receiver's wide FFT already splits the band into bins, so what each listener still pays
+//! for is the audio pipeline's inverse FFT back to the audio rate and the demodulator. Listeners
+//! tuned to the same bins with the same demodulator settings would compute identical audio up to
+//! that point, so they share one [`SharedChannel`]; AGC, volume and the codec stay per listener.
+//!
+//! Listeners tuned apart but within one channel of the channelizer grid
+//! ([`novasdr_core::dsp::channelizer`]) share that channel's decimated baseband instead: its
+//! inverse FFT runs once per frame, and each listener shifts it to its own bin and band-limits it
+//! with its passband filter before demodulating.
+
+use crate::state::{AudioParams, FilterSharpness, SquelchMode};
+use crate::ws::audio::AudioPipeline;
+use novasdr_core::config::{AudioCompression, Runtime};
+use novasdr_core::dsp::channelizer::{ChannelDdc, ChannelGrid};
+use novasdr_core::dsp::demod::DemodulationMode;
+use novasdr_core::dsp::subtone::Subtone;
+use novasdr_core::protocol::DigitalVoiceCall;
+use num_complex::Complex32;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Below this many listeners on the same settings, sharing only adds a copy.
+const MIN_SHARED_LISTENERS: usize = 2;
+
+/// Everything [`AudioPipeline::demodulate`] depends on, besides the receiver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChannelKey {
+    l: i32,
+    r: i32,
+    mid_bin: i32,
+    demodulation: DemodulationMode,
+    filter_sharpness: FilterSharpness,
+    squelch_enabled: bool,
+    squelch_mode: SquelchMode,
+    rds: bool,
+    stereo: bool,
+    digital_voice: bool,
+    subtone: bool,
+    tone_squelch: Option<Subtone>,
+}
+
+impl ChannelKey {
+    /// `None` for muted listeners, which demodulate nothing, and for those on a test tone.
+    pub fn of(params: &AudioParams) -> Option<Self> {
+        if params.mute || params.test_tone.is_some() {
+            return None;
+        }
+        let wbfm = params.demodulation == DemodulationMode::Wbfm;
+        let fm = params.demodulation == DemodulationMode::Fm;
+        // WBFM always demodulates the whole broadcast channel around the tuned bin and has no
+        // passband filter; the RDS and stereo switches only matter there.
+        let (l, r) = if wbfm { (0, 0) } else { (params.l, params.r) };
+        Some(Self {
+            l,
+            r,
+            mid_bin: params.m.floor() as i32,
+            demodulation: params.demodulation,
+            filter_sharpness: if wbfm {
+                FilterSharpness::Normal
+            } else {
+                params.filter_sharpness
+            },
+            squelch_enabled: params.squelch_enabled,
+            // Only an enabled squelch's mode changes what the channel produces.
+            squelch_mode: if params.squelch_enabled {
+                params.squelch_mode
+            } else {
+                SquelchMode::Variance
+            },
+            rds: wbfm && params.rds,
+            stereo: wbfm && params.stereo,
+            digital_voice: fm && params.digital_voice,
+            subtone: fm && (params.subtone || params.tone_squelch.is_some()),
+            tone_squelch: params.tone_squelch.filter(|_| fm),
+        })
+    }
+}
+
+/// Centre bin of the channelizer channel a listener can be tuned from, or `None` when it needs
+/// its own inverse FFT: muted listeners and those on a test tone, WBFM and SAM (which work on the
+/// bins), no filter sharpness (the bin window is then the only filter), or a window no channel
+/// holds.
+pub fn ddc_centre(params: &AudioParams, shape: ChannelShape) -> Option<i64> {
+    if params.mute || params.test_tone.is_some() {
+        return None;
+    }
+    if !matches!(
+        params.demodulation,
+        DemodulationMode::Am | DemodulationMode::Fm | DemodulationMode::Usb | DemodulationMode::Lsb
+    ) {
+        return None;
+    }
+    let transition_hz = params.filter_sharpness.transition_hz()?;
+    let bin_hz = shape.sample_rate as f32 / shape.audio_fft_size as f32;
+    // The passband filter's skirts reach half the transition past each edge.
+    let guard = (0.5 * transition_hz / bin_hz).ceil() as i64 + 1;
+    ChannelGrid::new(shape.audio_fft_size).centre_for(
+        i64::from(params.l),
+        i64::from(params.r),
+        params.m.floor() as i64,
+        guard,
+    )
+}
+
+/// Pipeline shape of a channel: the receiver's audio rate and sizes. The codec is never used,
+/// so the cheapest one is built.
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelShape {
+    pub sample_rate: usize,
+    pub audio_fft_size: usize,
+    pub wbfm_decimation: usize,
+}
+
+impl ChannelShape {
+    pub fn of(rt: &Runtime) -> Self {
+        Self {
+            sample_rate: rt.audio_max_sps as usize,
+            audio_fft_size: rt.audio_max_fft_size,
+            wbfm_decimation: rt.wbfm_decimation,
+        }
+    }
+}
+
+pub struct SharedChannel {
+    key: ChannelKey,
+    shape: ChannelShape,
+    /// Built by the first listener that needs the channel, on its own task.
+    pipeline: Option<AudioPipeline>,
+    frame_num: Option<u64>,
+    squelch_open: bool,
+    rds: Option<String>,
+    /// Latest digital voice call update and how many were seen, so listeners notice each one.
+    digital_voice: Option<(u64, DigitalVoiceCall)>,
+    subtone: Option<String>,
+}
+
+/// Text a listener was last sent from its shared channel.
+#[derive(Debug, Default)]
+pub struct ChannelTextSent {
+    rds: Option<String>,
+    digital_voice: u64,
+    subtone: Option<String>,
+}
+
+impl SharedChannel {
+    fn new(key: ChannelKey, shape: ChannelShape) -> Self {
+        Self {
+            key,
+            shape,
+            pipeline: None,
+            frame_num: None,
+            squelch_open: false,
+            rds: None,
+            digital_voice: None,
+            subtone: None,
+        }
+    }
+
+    pub fn key(&self) -> ChannelKey {
+        self.key
+    }
+
+    /// Demodulates `frame_num` unless another listener already did, and loads the audio into
+    /// `into`. Returns whether the squelch is open, or `None` when the channel is already past
+    /// this frame (the listener fell behind) or could not be built; the caller then demodulates
+    /// on its own pipeline.
+    pub fn demodulate_into(
+        &mut self,
+        into: &mut AudioPipeline,
+        spectrum_slice: &[Complex32],
+        frame_num: u64,
+        params: &AudioParams,
+        is_real_input: bool,
+    ) -> Option<bool> {
+        if self.frame_num.is_some_and(|done| frame_num < done) {
+            return None;
+        }
+        if self.frame_num != Some(frame_num) {
+            if self.pipeline.is_none() {
+                match AudioPipeline::new(
+                    self.shape.sample_rate,
+                    self.shape.audio_fft_size,
+                    self.shape.wbfm_decimation,
+                    AudioCompression::Adpcm,
+                ) {
+                    Ok(p) => self.pipeline = Some(p),
+                    Err(e) => {
+                        tracing::warn!(error = ?e, "shared audio channel init failed");
+                        return None;
+                    }
+                }
+            }
+            let pipeline = self.pipeline.as_mut()?;
+            pipeline.set_squelch_floor(into.squelch_floor());
+            self.squelch_open = pipeline.demodulate(
+                spectrum_slice,
+                frame_num,
+                params,
+                is_real_input,
+                params.m.floor() as i32,
+            );
+            if let Some(txt) = pipeline.take_rds_update() {
+                self.rds = Some(txt);
+            }
+            if let Some(txt) = pipeline.take_subtone_update() {
+                self.subtone = Some(txt);
+            }
+            while let Some(call) = pipeline.take_digital_voice_update() {
+                let seq = self.digital_voice.as_ref().map_or(1, |(seq, _)| seq + 1);
+                self.digital_voice = Some((seq, call));
+            }
+            self.frame_num = Some(frame_num);
+        }
+
+        if self.squelch_open {
+            let (left, right) = self.pipeline.as_ref()?.demodulated();
+            into.load_demodulated(left, right);
+        } else {
+            into.close_squelch();
+        }
+        Some(self.squelch_open)
+    }
+
+    /// Latest RDS station information, if it differs from what this listener was last sent.
+    pub fn rds_update(&self, sent: &mut ChannelTextSent) -> Option<String> {
+        let rds = self.rds.as_ref()?;
+        if sent.rds.as_ref() == Some(rds) {
+            return None;
+        }
+        sent.rds = Some(rds.clone());
+        Some(rds.clone())
+    }
+
+    /// Latest detected CTCSS tone or DCS code, if it differs from what this listener was last
+    /// sent.
+    pub fn subtone_update(&self, sent: &mut ChannelTextSent) -> Option<String> {
+        let subtone = self.subtone.as_ref()?;
+        if sent.subtone.as_ref() == Some(subtone) {
+            return None;
+        }
+        sent.subtone = Some(subtone.clone());
+        Some(subtone.clone())
+    }
+
+    /// Latest digital voice call update, if this listener has not been sent it yet.
+    pub fn digital_voice_update(&self, sent: &mut ChannelTextSent) -> Option<DigitalVoiceCall> {
+        let (seq, call) = self.digital_voice.as_ref()?;
+        if sent.digital_voice == *seq {
+            return None;
+        }
+        sent.digital_voice = *seq;
+        Some(call.clone())
+    }
+}
+
+/// The channels of one receiver, regrouped by the DSP thread on every frame.
+#[derive(Default)]
+pub struct ChannelBank {
+    channels: HashMap<ChannelKey, Arc<Mutex<SharedChannel>>>,
+    listeners: HashMap<ChannelKey, usize>,
+    used: HashSet<ChannelKey>,
+    /// Channelizer channels by centre bin, for listeners that share one but not a key.
+    ddcs: HashMap<i64, Arc<Mutex<ChannelDdc>>>,
+    ddc_listeners: HashMap<i64, usize>,
+    ddc_used: HashSet<i64>,
+}
+
+impl ChannelBank {
+    /// Counts the listeners per key, and per channelizer channel those left without a shared
+    /// key, for this frame. Call before [`Self::channel_for`] and [`Self::ddc_for`].
+    pub fn group(&mut self, listeners: &[(Option<ChannelKey>, Option<i64>)]) {
+        self.listeners.clear();
+        self.ddc_listeners.clear();
+        for key in listeners.iter().filter_map(|(key, _)| key.as_ref()) {
+            *self.listeners.entry(*key).or_default() += 1;
+        }
+        for (key, centre) in listeners {
+            let shares_key = key.is_some_and(|key| self.listeners[&key] >= MIN_SHARED_LISTENERS);
+            if let Some(centre) = centre.filter(|_| !shares_key) {
+                *self.ddc_listeners.entry(centre).or_default() += 1;
+            }
+        }
+    }
+
+    /// The channel a listener with `key` shares, or `None` to demodulate privately.
+    pub fn channel_for(
+        &mut self,
+        key: ChannelKey,
+        shape: ChannelShape,
+    ) -> Option<Arc<Mutex<SharedChannel>>> {
+        if self.listeners.get(&key).copied().unwrap_or(0) < MIN_SHARED_LISTENERS {
+            return None;
+        }
+        self.used.insert(key);
+        Some(
+            self.channels
+                .entry(key)
+                .or_insert_with(|| Arc::new(Mutex::new(SharedChannel::new(key, shape))))
+                .clone(),
+        )
+    }
+
+    /// The channelizer channel centred on `centre`, for a listener without a shared key, or
+    /// `None` to run the inverse FFT privately.
+    pub fn ddc_for(&mut self, centre: i64, shape: ChannelShape) -> Option<Arc<Mutex<ChannelDdc>>> {
+        if self.ddc_listeners.get(&centre).copied().unwrap_or(0) < MIN_SHARED_LISTENERS {
+            return None;
+        }
+        self.ddc_used.insert(centre);
+        Some(
+            self.ddcs
+                .entry(centre)
+                .or_insert_with(|| {
+                    let grid = ChannelGrid::new(shape.audio_fft_size);
+                    Arc::new(Mutex::new(ChannelDdc::new(grid, centre)))
+                })
+                .clone(),
+        )
+    }
+
+    /// Drops channels nobody was grouped into this frame.
+    pub fn finish_frame(&mut self) {
+        let used = &self.used;
+        self.channels.retain(|key, _| used.contains(key));
+        self.used.clear();
+        let ddc_used = &self.ddc_used;
+        self.ddcs.retain(|centre, _| ddc_used.contains(centre));
+        self.ddc_used.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Channelizer channels in use.
+    pub fn ddc_len(&self) -> usize {
+        self.ddcs.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AgcSpeed;
+
+    fn params(m: f64, mode: DemodulationMode) -> AudioParams {
+        AudioParams {
+            l: 100,
+            m,
+            r: 130,
+            mute: false,
+            squelch_enabled: false,
+            squelch_mode: Default::default(),
+            rds: true,
+            stereo: false,
+            digital_voice: false,
+            subtone: false,
+            tone_squelch: None,
+            sstv: false,
+            fsk: None,
+            test_tone: None,
+            demodulation: mode,
+            agc_speed: AgcSpeed::Default,
+            agc_attack_ms: None,
+            agc_release_ms: None,
+            filter_sharpness: FilterSharpness::Normal,
+            low_latency: false,
+            volume_db: 0.0,
+            hearing: Default::default(),
+        }
+    }
+
+    #[test]
+    fn key_ignores_per_listener_settings() {
+        let a = params(100.2, DemodulationMode::Usb);
+        let mut b = params(100.7, DemodulationMode::Usb);
+        b.volume_db = -6.0;
+        b.agc_speed = AgcSpeed::Fast;
+        b.low_latency = true;
+        assert_eq!(ChannelKey::of(&a), ChannelKey::of(&b));
+
+        let mut c = a.clone();
+        c.r = 131;
+        assert_ne!(ChannelKey::of(&a), ChannelKey::of(&c));
+
+        let mut muted = a.clone();
+        muted.mute = true;
+        assert_eq!(ChannelKey::of(&muted), None);
+
+        // Digital voice only changes FM channels.
+        let mut dv = a.clone();
+        dv.digital_voice = true;
+        assert_eq!(ChannelKey::of(&a), ChannelKey::of(&dv));
+        let fm = params(100.0, DemodulationMode::Fm);
+        let mut fm_dv = fm.clone();
+        fm_dv.digital_voice = true;
+        assert_ne!(ChannelKey::of(&fm), ChannelKey::of(&fm_dv));
+
+        // So do tone reports and the tone squelch.
+        let mut tones = a.clone();
+        tones.subtone = true;
+        tones.tone_squelch = "88.5".parse().ok();
+        assert_eq!(ChannelKey::of(&a), ChannelKey::of(&tones));
+        let mut fm_tone = fm.clone();
+        fm_tone.tone_squelch = "88.5".parse().ok();
+        assert_ne!(ChannelKey::of(&fm), ChannelKey::of(&fm_tone));
+    }
+
+    #[test]
+    fn shared_channel_audio_matches_private_demodulation() {
+        let shape = ChannelShape {
+            sample_rate: 12_000,
+            audio_fft_size: 512,
+            wbfm_decimation: 1,
+        };
+        let build = || AudioPipeline::new(12_000, 512, 1, AudioCompression::Adpcm).unwrap();
+        let mut p = params(100.0, DemodulationMode::Am);
+        p.l = 90;
+        p.r = 110;
+        let key = ChannelKey::of(&p).unwrap();
+        let mut channel = SharedChannel::new(key, shape);
+        let (mut private, mut shared) = (build(), build());
+
+        let mut seed = 1u32;
+        for frame_num in 0..40u64 {
+            let slice: Vec<Complex32> = (0..20)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    let v = (seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5;
+                    Complex32::new(v * 100.0, -v * 40.0)
+                })
+                .collect();
+            let expected = private
+                .process(&slice, frame_num, &p, false, 100, 1.0)
+                .unwrap();
+            let open = channel.demodulate_into(&mut shared, &slice, frame_num, &p, false);
+            assert_eq!(open, Some(true));
+            let got = shared.encode(&slice, frame_num, &p, 1.0).unwrap();
+            assert_eq!(got, expected, "frame {frame_num}");
+        }
+        // A listener that fell behind demodulates on its own.
+        let mut late = build();
+        assert_eq!(
+            channel.demodulate_into(&mut late, &[Complex32::default(); 20], 3, &p, false),
+            None
+        );
+    }
+
+    /// Rotated bins of a Hann FFT at 50% overlap, as the DSP runner computes them, over complex
+    /// tones given as (rotated bin, amplitude).
+    fn tone_frames(tones: &[(f32, f32)], frames: u64) -> Vec<Vec<Complex32>> {
+        const FFT_SIZE: usize = 4096;
+        let fft = rustfft::FftPlanner::new().plan_fft_forward(FFT_SIZE);
+        let window = novasdr_core::dsp::window::hann_window(FFT_SIZE);
+        let base_idx = FFT_SIZE / 2 + 1;
+        (0..frames as usize)
+            .map(|frame| {
+                let start = frame * FFT_SIZE / 2;
+                let mut buf: Vec<Complex32> = (0..FFT_SIZE)
+                    .map(|n| {
+                        let t = (start + n) as f32 / FFT_SIZE as f32;
+                        let x: Complex32 = tones
+                            .iter()
+                            .map(|&(bin, amp)| {
+                                let raw = bin + base_idx as f32;
+                                Complex32::from_polar(amp, std::f32::consts::TAU * raw * t)
+                            })
+                            .sum();
+                        x * window[n]
+                    })
+                    .collect();
+                fft.process(&mut buf);
+                (0..FFT_SIZE)
+                    .map(|i| buf[(i + base_idx) % FFT_SIZE])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn channel_tuned_demodulation_matches_private_demodulation() {
+        let shape = ChannelShape {
+            sample_rate: 12_000,
+            audio_fft_size: 512,
+            wbfm_decimation: 1,
+        };
+        let build = || AudioPipeline::new(12_000, 512, 1, AudioCompression::Adpcm).unwrap();
+        let mut usb = params(1000.0, DemodulationMode::Usb);
+        (usb.l, usb.r) = (1004, 1110);
+        let mut lsb = params(1051.0, DemodulationMode::Lsb);
+        (lsb.l, lsb.r) = (960, 1047);
+        let mut am = params(1017.0, DemodulationMode::Am);
+        (am.l, am.r) = (990, 1044);
+        let cases = [
+            (usb, vec![(1030.3, 1.0), (1061.7, 0.5), (1090.2, 0.7)]),
+            (lsb, vec![(975.4, 0.6), (1010.8, 1.0), (1033.1, 0.4)]),
+            (am, vec![(1017.0, 1.0), (1005.5, 0.3), (1028.5, 0.3)]),
+        ];
+        for (p, tones) in cases {
+            let centre = ddc_centre(&p, shape).expect("fits a channel");
+            let mut ddc = ChannelDdc::new(ChannelGrid::new(512), centre);
+            let (mut private, mut tuned) = (build(), build());
+            let (l, r) = (p.l as usize, p.r as usize);
+            let m0 = p.m.floor() as i32;
+            // The private LSB path time-reverses each frame, which puts its audio a sample
+            // ahead.
+            let lag = usize::from(p.demodulation == DemodulationMode::Lsb);
+            let (mut error, mut power) = (0.0f32, 0.0f32);
+            for (frame_num, bins) in tone_frames(&tones, 40).iter().enumerate() {
+                let frame_num = frame_num as u64;
+                private.demodulate(&bins[l..r], frame_num, &p, false, m0);
+                let baseband = ddc.baseband(bins, false, frame_num).unwrap();
+                tuned.load_channel(baseband, i64::from(m0) - centre, frame_num);
+                tuned.demodulate(&bins[l..r], frame_num, &p, false, m0);
+                // Past the filters' start-up.
+                if frame_num >= 4 {
+                    let (expected, _) = private.demodulated();
+                    let (got, _) = tuned.demodulated();
+                    for (a, b) in got[lag..].iter().zip(expected) {
+                        error += (a - b) * (a - b);
+                        power += b * b;
+                    }
+                }
+            }
+            // What is left is bin leakage the private bin window cuts and, for SSB, the image the
+            // private path's real signal leaks through the filter's stopband.
+            let error_db = 10.0 * (error / power).log10();
+            assert!(error_db < -60.0, "{:?}: {error_db:.1} dB", p.demodulation);
+        }
+    }
+
+    #[test]
+    fn ddc_centre_needs_a_filter_and_a_bin_mode() {
+        let shape = ChannelShape {
+            sample_rate: 12_000,
+            audio_fft_size: 512,
+            wbfm_decimation: 1,
+        };
+        let mut p = params(100.0, DemodulationMode::Usb);
+        assert_eq!(ddc_centre(&p, shape), Some(128));
+        p.filter_sharpness = FilterSharpness::Off;
+        assert_eq!(ddc_centre(&p, shape), None);
+        let sam = params(100.0, DemodulationMode::Sam);
+        assert_eq!(ddc_centre(&sam, shape), None);
+        let mut wide = params(100.0, DemodulationMode::Am);
+        (wide.l, wide.r) = (0, 400);
+        assert_eq!(ddc_centre(&wide, shape), None);
+    }
+
+    #[test]
+    fn bank_shares_only_keys_with_several_listeners() {
+        let shape = ChannelShape {
+            sample_rate: 12_000,
+            audio_fft_size: 512,
+            wbfm_decimation: 1,
+        };
+        let usb = ChannelKey::of(&params(100.0, DemodulationMode::Usb)).unwrap();
+        let am = ChannelKey::of(&params(100.0, DemodulationMode::Am)).unwrap();
+        let mut bank = ChannelBank::default();
+
+        bank.group(&[(Some(usb), None), (Some(usb), None), (Some(am), None)]);
+        let first = bank.channel_for(usb, shape).expect("shared");
+        let second = bank.channel_for(usb, shape).expect("shared");
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(bank.channel_for(am, shape).is_none());
+        bank.finish_frame();
+        assert_eq!(bank.len(), 1);
+
+        bank.group(&[(Some(usb), None)]);
+        assert!(bank.channel_for(usb, shape).is_none());
+        bank.finish_frame();
+        assert_eq!(bank.len(), 0);
+    }
+
+    #[test]
+    fn bank_tunes_listeners_without_a_shared_key_from_one_channel() {
+        let shape = ChannelShape {
+            sample_rate: 12_000,
+            audio_fft_size: 512,
+            wbfm_decimation: 1,
+        };
+        let usb = ChannelKey::of(&params(100.0, DemodulationMode::Usb)).unwrap();
+        let am = ChannelKey::of(&params(100.0, DemodulationMode::Am)).unwrap();
+        let fm = ChannelKey::of(&params(104.0, DemodulationMode::Fm)).unwrap();
+        let mut bank = ChannelBank::default();
+
+        // The two USB listeners share a key, so only AM and FM count towards the channel.
+        bank.group(&[
+            (Some(usb), Some(128)),
+            (Some(usb), Some(128)),
+            (Some(am), Some(128)),
+            (Some(fm), Some(128)),
+        ]);
+        assert!(bank.channel_for(usb, shape).is_some());
+        let first = bank.ddc_for(128, shape).expect("channelizer channel");
+        let second = bank.ddc_for(128, shape).expect("channelizer channel");
+        assert!(Arc::ptr_eq(&first, &second));
+        bank.finish_frame();
+        assert_eq!((bank.len(), bank.ddc_len()), (1, 1));
+
+        bank.group(&[
+            (Some(usb), Some(128)),
+            (Some(usb), Some(128)),
+            (Some(am), Some(128)),
+        ]);
+        assert!(bank.ddc_for(128, shape).is_none());
+        bank.finish_frame();
+        assert_eq!(bank.ddc_len(), 0);
+    }
+}
@@ -13,6 +13,18 @@ pub enum Command {
         iterations: Option<usize>,
         fftsize: Option<usize>,
     },
+    /// Sample raw input and guess its sample format and rate.
+    ProbeInput {
+        /// Read from this file or FIFO instead of stdin.
+        #[arg(long, conflicts_with = "tcp")]
+        path: Option<PathBuf>,
+        /// Read from a TCP source such as rtl_tcp (`host:port`).
+        #[arg(long)]
+        tcp: Option<String>,
+        /// How long to sample the input.
+        #[arg(long, default_value_t = 3)]
+        seconds: u64,
+    },
 }
 
 #[derive(Debug, Clone, clap::ValueEnum)]
@@ -24,6 +36,8 @@ pub enum BenchmarkKind {
     VkFftComplex,
     VkFftReal,
     Ssb,
+    /// Scalar vs SIMD timings of the per-sample DSP kernels.
+    Kernels,
 }
 
 #[derive(Debug, Parser)]
@@ -52,4 +66,10 @@ pub struct Args {
     pub log_dir: Option<PathBuf>,
     #[arg(long = "no-file-log")]
     pub no_file_log: bool,
+    /// Write the files of a snapshot from `GET /admin/snapshot` into place before starting.
+    #[arg(long = "import-snapshot", value_name = "FILE")]
+    pub import_snapshot: Option<PathBuf>,
+    /// Run under the Windows service control manager: no console, logs only to files.
+    #[arg(long = "service")]
+    pub service: bool,
 }
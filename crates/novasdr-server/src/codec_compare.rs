@@ -0,0 +1,372 @@
+use crate::audio_listener::HeadlessListener;
+use crate::state::AppState;
+use axum::{
+    extract::{ws, Path, Query, State, WebSocketUpgrade},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use futures::{SinkExt, StreamExt};
+use interop::opus;
+use novasdr_core::codec::ima_adpcm;
+use novasdr_core::dsp::demod::DemodulationMode;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Length of one compared frame; both codecs get the same frames, so their packets line up.
+const FRAME_MS: usize = 20;
+/// Large enough for one 20 ms Opus packet at any bitrate the encoder is set to.
+const OPUS_PACKET_MAX: usize = 4000;
+const OPUS_MIN_BITRATE: i32 = 6_000;
+const OPUS_MAX_BITRATE: i32 = 256_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    /// `bitrate` in bits per second; `None` keeps the `/audio` default.
+    Opus {
+        bitrate: Option<i32>,
+    },
+    Adpcm,
+}
+
+impl Codec {
+    /// `opus`, `opus:<bits per second>` or `adpcm`.
+    fn parse(spec: &str) -> Result<Self, String> {
+        let (name, arg) = match spec.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (spec, None),
+        };
+        match (name.to_ascii_lowercase().as_str(), arg) {
+            ("adpcm", None) => Ok(Self::Adpcm),
+            ("opus", None) => Ok(Self::Opus { bitrate: None }),
+            ("opus", Some(bitrate)) => match bitrate.parse::<i32>() {
+                Ok(b) if (OPUS_MIN_BITRATE..=OPUS_MAX_BITRATE).contains(&b) => {
+                    Ok(Self::Opus { bitrate: Some(b) })
+                }
+                _ => Err(format!(
+                    "Opus bitrate must be {OPUS_MIN_BITRATE}..={OPUS_MAX_BITRATE} bits per second"
+                )),
+            },
+            _ => Err(format!(
+                "unknown codec {spec:?}; use adpcm, opus or opus:<bits per second>"
+            )),
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            Self::Opus { bitrate: None } => "opus".to_string(),
+            Self::Opus { bitrate: Some(b) } => format!("opus:{b}"),
+            Self::Adpcm => "adpcm".to_string(),
+        }
+    }
+}
+
+enum Encoder {
+    Opus {
+        encoder: opus::Encoder,
+        packet: Vec<u8>,
+    },
+    Adpcm,
+}
+
+impl Encoder {
+    fn new(codec: Codec, sample_rate: usize) -> anyhow::Result<Self> {
+        Ok(match codec {
+            Codec::Opus { bitrate } => {
+                let mut encoder =
+                    crate::ws::audio::new_opus_encoder(sample_rate, opus::Channels::Mono)?;
+                if let Some(bitrate) = bitrate {
+                    encoder
+                        .set_bitrate(opus::Bitrate::BitsPerSecond(bitrate))
+                        .map_err(|e| anyhow::anyhow!("Opus set_bitrate error: {e}"))?;
+                }
+                Self::Opus {
+                    encoder,
+                    packet: vec![0; OPUS_PACKET_MAX],
+                }
+            }
+            Codec::Adpcm => Self::Adpcm,
+        })
+    }
+
+    fn encode(&mut self, frame: &[i16]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Opus { encoder, packet } => {
+                let size = encoder
+                    .encode(frame, packet)
+                    .map_err(|e| anyhow::anyhow!("Opus encode error: {e}"))?;
+                Ok(packet[..size].to_vec())
+            }
+            Self::Adpcm => Ok(ima_adpcm::encode_block_i16_mono(frame)),
+        }
+    }
+}
+
+/// Cuts the listener's PCM into frames and encodes every frame with both codecs. The codecs
+/// see the very same samples, after AGC and volume, so neither plays louder than the other.
+struct Comparison {
+    sides: [(Codec, Encoder); 2],
+    /// Samples per frame.
+    frame: usize,
+    pending: Vec<i16>,
+    /// Samples framed so far; the offset of the next frame.
+    offset: u64,
+    reference: bool,
+}
+
+impl Comparison {
+    /// Binary messages for the complete frames in `pcm` (little-endian `i16`): `A`, `B` and,
+    /// with `reference`, `R` for the frame's PCM.
+    fn push(&mut self, pcm: &[u8]) -> anyhow::Result<Vec<Vec<u8>>> {
+        self.pending.extend(
+            pcm.chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        );
+        let mut out = Vec::new();
+        let mut start = 0;
+        while self.pending.len() - start >= self.frame {
+            let frame = &self.pending[start..start + self.frame];
+            for (tag, (_, encoder)) in [b'A', b'B'].into_iter().zip(self.sides.iter_mut()) {
+                out.push(message(tag, self.offset, &encoder.encode(frame)?));
+            }
+            if self.reference {
+                let pcm: Vec<u8> = frame.iter().flat_map(|s| s.to_le_bytes()).collect();
+                out.push(message(b'R', self.offset, &pcm));
+            }
+            self.offset += self.frame as u64;
+            start += self.frame;
+        }
+        self.pending.drain(..start);
+        Ok(out)
+    }
+
+    fn labels(&self) -> (String, String) {
+        (self.sides[0].0.label(), self.sides[1].0.label())
+    }
+}
+
+/// A tag byte, the offset of the frame's first sample as little-endian `u64`, and the payload.
+fn message(tag: u8, offset: u64, payload: &[u8]) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(9 + payload.len());
+    msg.push(tag);
+    msg.extend_from_slice(&offset.to_le_bytes());
+    msg.extend_from_slice(payload);
+    msg
+}
+
+fn default_mode() -> String {
+    "usb".to_string()
+}
+
+fn default_a() -> String {
+    "opus".to_string()
+}
+
+fn default_b() -> String {
+    "adpcm".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareQuery {
+    /// Tuned frequency in Hz.
+    frequency: i64,
+    #[serde(default = "default_mode")]
+    mode: String,
+    #[serde(default = "default_a")]
+    a: String,
+    #[serde(default = "default_b")]
+    b: String,
+    /// Also send the PCM both codecs were fed, for offline scoring against a reference.
+    #[serde(default)]
+    reference: bool,
+    /// Assign the codecs to `A` and `B` at random and name them only on `{"cmd":"reveal"}`.
+    #[serde(default)]
+    blind: bool,
+}
+
+/// `GET /admin/receivers/:receiver_id/codec_compare`: a WebSocket carrying one fixed tune
+/// encoded with two codecs at once, for listening tests and offline codec scoring.
+pub async fn upgrade(
+    ws: WebSocketUpgrade,
+    Path(receiver_id): Path<String>,
+    Query(query): Query<CompareQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let Some(mode) = DemodulationMode::from_str_upper(&query.mode.to_ascii_uppercase()) else {
+        return (StatusCode::BAD_REQUEST, "unknown mode").into_response();
+    };
+    let rt = receiver.rt.as_ref();
+    if !receiver.covers(query.frequency) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "frequency outside this receiver's band",
+        )
+            .into_response();
+    }
+    let sample_rate = rt.audio_max_sps as usize;
+    let mut sides = Vec::with_capacity(2);
+    for spec in [&query.a, &query.b] {
+        let codec = match Codec::parse(spec) {
+            Ok(codec) => codec,
+            Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+        };
+        match Encoder::new(codec, sample_rate) {
+            Ok(encoder) => sides.push((codec, encoder)),
+            Err(e) => return (StatusCode::BAD_REQUEST, format!("{e:#}")).into_response(),
+        }
+    }
+    if query.blind && rand::random::<bool>() {
+        sides.swap(0, 1);
+    }
+    let Ok(sides) = <[(Codec, Encoder); 2]>::try_from(sides) else {
+        unreachable!("two codecs");
+    };
+    if receiver.audio_full() {
+        return (StatusCode::TOO_MANY_REQUESTS, "receiver is full").into_response();
+    }
+
+    let comparison = Comparison {
+        sides,
+        frame: sample_rate * FRAME_MS / 1000,
+        pending: Vec::new(),
+        offset: 0,
+        reference: query.reference,
+    };
+    let (listener, pcm) =
+        match HeadlessListener::start(state.clone(), receiver, query.frequency, mode).await {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!(error = ?e, "codec comparison: cannot start listener");
+                return (StatusCode::SERVICE_UNAVAILABLE, "audio unavailable").into_response();
+            }
+        };
+    let hello = json!({
+        "type": "codec_compare",
+        "receiver_id": receiver_id,
+        "frequency": query.frequency,
+        "mode": mode.as_str(),
+        "sample_rate": sample_rate,
+        "frame_samples": comparison.frame,
+        "a": (!query.blind).then(|| comparison.sides[0].0.label()),
+        "b": (!query.blind).then(|| comparison.sides[1].0.label()),
+        "reference": query.reference,
+    });
+    ws.on_upgrade(move |socket| handle(socket, listener, pcm, comparison, hello.to_string()))
+}
+
+async fn handle(
+    socket: ws::WebSocket,
+    listener: HeadlessListener,
+    mut pcm: mpsc::Receiver<Vec<u8>>,
+    mut comparison: Comparison,
+    hello: String,
+) {
+    let (a, b) = comparison.labels();
+    tracing::info!(a = %a, b = %b, "codec comparison started");
+    let (mut sender, mut receiver) = socket.split();
+    if sender.send(ws::Message::Text(hello)).await.is_ok() {
+        loop {
+            tokio::select! {
+                chunk = pcm.recv() => {
+                    let Some(chunk) = chunk else { break };
+                    let messages = match comparison.push(&chunk) {
+                        Ok(messages) => messages,
+                        Err(e) => {
+                            tracing::warn!(error = ?e, "codec comparison ended");
+                            break;
+                        }
+                    };
+                    let mut sent = true;
+                    for msg in messages {
+                        if sender.send(ws::Message::Binary(msg)).await.is_err() {
+                            sent = false;
+                            break;
+                        }
+                    }
+                    if !sent {
+                        break;
+                    }
+                }
+                msg = receiver.next() => {
+                    let txt = match msg {
+                        Some(Ok(ws::Message::Text(txt))) => txt,
+                        Some(Ok(ws::Message::Close(_))) | Some(Err(_)) | None => break,
+                        Some(Ok(_)) => continue,
+                    };
+                    let is_reveal = serde_json::from_str::<serde_json::Value>(&txt)
+                        .is_ok_and(|v| v["cmd"] == "reveal");
+                    if is_reveal {
+                        let reveal = json!({ "type": "reveal", "a": a, "b": b });
+                        if sender.send(ws::Message::Text(reveal.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    tracing::info!(a = %a, b = %b, "codec comparison ended");
+    listener.stop().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codec_specs_parse() {
+        assert_eq!(Codec::parse("adpcm"), Ok(Codec::Adpcm));
+        assert_eq!(Codec::parse("Opus"), Ok(Codec::Opus { bitrate: None }));
+        assert_eq!(
+            Codec::parse("opus:24000"),
+            Ok(Codec::Opus {
+                bitrate: Some(24_000)
+            })
+        );
+        assert!(Codec::parse("opus:100").is_err());
+        assert!(Codec::parse("adpcm:1").is_err());
+        assert!(Codec::parse("flac").is_err());
+        assert_eq!(Codec::parse("opus:24000").unwrap().label(), "opus:24000");
+    }
+
+    #[test]
+    fn both_sides_get_the_same_frames() {
+        let mut comparison = Comparison {
+            sides: [
+                (Codec::Adpcm, Encoder::Adpcm),
+                (Codec::Adpcm, Encoder::Adpcm),
+            ],
+            frame: 160,
+            pending: Vec::new(),
+            offset: 0,
+            reference: true,
+        };
+        let samples: Vec<i16> = (0..400).map(|i| ((i % 50) * 300) as i16).collect();
+        let pcm: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+        let messages = comparison.push(&pcm[..300]).unwrap();
+        assert!(messages.is_empty());
+        let messages = comparison.push(&pcm[300..]).unwrap();
+        // Two complete frames, 80 samples left over.
+        let tags: Vec<u8> = messages.iter().map(|m| m[0]).collect();
+        assert_eq!(tags, b"ABRABR");
+        assert_eq!(comparison.pending.len(), 80);
+
+        for (i, frame) in messages.chunks(3).enumerate() {
+            let offset = (i * 160) as u64;
+            for msg in frame {
+                assert_eq!(u64::from_le_bytes(msg[1..9].try_into().unwrap()), offset);
+            }
+            assert_eq!(frame[0][9..], frame[1][9..]);
+            let reference = &pcm[i * 320..(i + 1) * 320];
+            assert_eq!(&frame[2][9..], reference);
+            let decoded = ima_adpcm::decode_block_i16_mono(&frame[0][9..]).unwrap();
+            assert_eq!(decoded.len(), 160);
+        }
+    }
+}
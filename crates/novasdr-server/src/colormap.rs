@@ -0,0 +1,109 @@
+//! Waterfall colour maps for images rendered on the server (capture screenshots,
+//! `/waterfall.png`).
+
+/// A colour map by name, as in `input.defaults.colormap` and the `colormap` query parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Colormap {
+    /// Black through blue, cyan, yellow and red to white.
+    #[default]
+    Default,
+    Gray,
+    Viridis,
+    Inferno,
+    Turbo,
+}
+
+const DEFAULT: &[[u8; 3]] = &[
+    [0, 0, 0],
+    [0, 0, 140],
+    [0, 160, 220],
+    [240, 230, 0],
+    [230, 40, 0],
+    [255, 255, 255],
+];
+const GRAY: &[[u8; 3]] = &[[0, 0, 0], [255, 255, 255]];
+const VIRIDIS: &[[u8; 3]] = &[
+    [68, 1, 84],
+    [72, 40, 120],
+    [62, 74, 137],
+    [49, 104, 142],
+    [38, 130, 142],
+    [31, 158, 137],
+    [53, 183, 121],
+    [109, 205, 89],
+    [180, 222, 44],
+    [253, 231, 37],
+];
+const INFERNO: &[[u8; 3]] = &[
+    [0, 0, 4],
+    [27, 12, 65],
+    [74, 12, 107],
+    [120, 28, 109],
+    [165, 44, 96],
+    [207, 68, 70],
+    [237, 105, 37],
+    [251, 155, 6],
+    [247, 209, 61],
+    [252, 255, 164],
+];
+const TURBO: &[[u8; 3]] = &[
+    [48, 18, 59],
+    [70, 107, 227],
+    [40, 187, 236],
+    [49, 242, 153],
+    [162, 252, 60],
+    [237, 208, 58],
+    [251, 128, 34],
+    [208, 47, 5],
+    [122, 4, 3],
+];
+
+impl Colormap {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "default" => Some(Self::Default),
+            "gray" | "grey" | "grayscale" => Some(Self::Gray),
+            "viridis" => Some(Self::Viridis),
+            "inferno" => Some(Self::Inferno),
+            "turbo" => Some(Self::Turbo),
+            _ => None,
+        }
+    }
+
+    fn stops(self) -> &'static [[u8; 3]] {
+        match self {
+            Self::Default => DEFAULT,
+            Self::Gray => GRAY,
+            Self::Viridis => VIRIDIS,
+            Self::Inferno => INFERNO,
+            Self::Turbo => TURBO,
+        }
+    }
+
+    /// Colour of `t`, from `0` (weakest) to `1` (strongest); values outside are clamped.
+    pub fn rgb(self, t: f32) -> [u8; 3] {
+        let stops = self.stops();
+        let scaled = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let i = (scaled.floor() as usize).min(stops.len() - 2);
+        let frac = scaled - i as f32;
+        let (a, b) = (stops[i], stops[i + 1]);
+        std::array::from_fn(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * frac).round() as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colormaps_span_their_ends() {
+        assert_eq!(Colormap::Default.rgb(-1.0), [0, 0, 0]);
+        assert_eq!(Colormap::Default.rgb(0.0), [0, 0, 0]);
+        assert_eq!(Colormap::Default.rgb(1.0), [255, 255, 255]);
+        assert_eq!(Colormap::Default.rgb(2.0), [255, 255, 255]);
+        assert_eq!(Colormap::Gray.rgb(0.5), [128, 128, 128]);
+        assert_eq!(Colormap::parse(" Viridis "), Some(Colormap::Viridis));
+        assert_eq!(Colormap::Viridis.rgb(1.0), [253, 231, 37]);
+        assert_eq!(Colormap::parse("rainbow"), None);
+    }
+}
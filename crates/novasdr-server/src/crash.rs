@@ -0,0 +1,380 @@
+use crate::build_info;
+use crate::state::AppState;
+use anyhow::Context;
+use novasdr_core::config::Config;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(15);
+/// Reports sent per start, newest first; older ones stay on disk.
+const MAX_UPLOADS_PER_START: usize = 5;
+const REPORT_PREFIX: &str = "crash-";
+const REPORT_SUFFIX: &str = ".json";
+/// Appended to a report once the upload endpoint accepted it.
+const SENT_SUFFIX: &str = ".sent";
+/// Reports kept on disk, sent or not; older ones are deleted when a new one is written.
+const MAX_KEPT_REPORTS: usize = 50;
+/// A panic that repeats (a task restarted in a loop) writes one report per interval.
+const MIN_REPORT_INTERVAL_MS: i64 = 10_000;
+
+struct Settings {
+    enabled: bool,
+    dir: PathBuf,
+    /// From the log directory; used while `crash_reports.dir` is empty.
+    default_dir: PathBuf,
+    config: Option<Value>,
+}
+
+static SETTINGS: RwLock<Option<Settings>> = RwLock::new(None);
+/// Unix milliseconds of the last report written.
+static LAST_REPORT_MS: AtomicI64 = AtomicI64::new(i64::MIN);
+
+#[derive(Serialize)]
+struct CrashReport<'a> {
+    time: String,
+    version: &'static str,
+    build: Value,
+    os: &'static str,
+    arch: &'static str,
+    thread: &'a str,
+    message: &'a str,
+    location: Option<String>,
+    backtrace: String,
+    config: Option<&'a Value>,
+}
+
+/// Writes reports to `<log_dir>/crashes` until [`configure`] runs; `crashes/` without a log dir.
+pub fn set_default_dir(log_dir: Option<&Path>) {
+    let dir = log_dir.map_or_else(|| PathBuf::from("crashes"), |d| d.join("crashes"));
+    let mut settings = write_settings();
+    *settings = Some(Settings {
+        enabled: true,
+        dir: dir.clone(),
+        default_dir: dir,
+        config: None,
+    });
+}
+
+/// Applies `crash_reports` and records the config summary included in reports. Runs at
+/// startup and after every reload.
+pub fn configure(cfg: &Config) {
+    let mut settings = write_settings();
+    let default_dir = settings
+        .as_ref()
+        .map_or_else(|| PathBuf::from("crashes"), |s| s.default_dir.clone());
+    let dir = match cfg.crash_reports.dir.trim() {
+        "" => default_dir.clone(),
+        dir => PathBuf::from(dir),
+    };
+    *settings = Some(Settings {
+        enabled: cfg.crash_reports.enabled,
+        dir,
+        default_dir,
+        config: Some(config_summary(cfg)),
+    });
+}
+
+fn write_settings() -> std::sync::RwLockWriteGuard<'static, Option<Settings>> {
+    match SETTINGS.write() {
+        Ok(g) => g,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Called from the panic hook. Never panics itself; failures only go to the log.
+pub fn write_report(info: &std::panic::PanicHookInfo<'_>) {
+    let settings = match SETTINGS.read() {
+        Ok(g) => g,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let Some(settings) = settings.as_ref().filter(|s| s.enabled) else {
+        return;
+    };
+    let now = chrono::Utc::now();
+    let last = LAST_REPORT_MS.swap(now.timestamp_millis(), Ordering::Relaxed);
+    if now.timestamp_millis().saturating_sub(last) < MIN_REPORT_INTERVAL_MS {
+        tracing::error!(
+            "panic within {MIN_REPORT_INTERVAL_MS} ms of the last crash report; not written"
+        );
+        return;
+    }
+    let thread = std::thread::current();
+    let message = panic_message(info.payload());
+    let report = CrashReport {
+        time: now.to_rfc3339(),
+        version: build_info::version(),
+        build: build_summary(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        thread: thread.name().unwrap_or("unnamed"),
+        message: &message,
+        location: info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        config: settings.config.as_ref(),
+    };
+    let name = format!(
+        "{REPORT_PREFIX}{}-{}{REPORT_SUFFIX}",
+        now.format("%Y%m%dT%H%M%S%.3fZ"),
+        std::process::id()
+    );
+    let path = settings.dir.join(name);
+    let written = std::fs::create_dir_all(&settings.dir)
+        .map_err(anyhow::Error::from)
+        .and_then(|()| Ok(serde_json::to_vec_pretty(&report)?))
+        .and_then(|bytes| Ok(std::fs::write(&path, bytes)?));
+    match written {
+        Ok(()) => tracing::error!(path = %path.display(), "crash report written"),
+        Err(e) => tracing::error!(path = %path.display(), error = ?e, "crash report not written"),
+    }
+    if let Err(e) = prune_reports(&settings.dir, MAX_KEPT_REPORTS) {
+        tracing::warn!(dir = %settings.dir.display(), error = ?e, "old crash reports not removed");
+    }
+}
+
+/// Deletes all but the newest `keep` reports in `dir`, sent or not.
+fn prune_reports(dir: &Path, keep: usize) -> anyhow::Result<()> {
+    let mut reports: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name().and_then(|n| n.to_str()).is_some_and(|n| {
+                n.starts_with(REPORT_PREFIX)
+                    && n.strip_suffix(SENT_SUFFIX)
+                        .unwrap_or(n)
+                        .ends_with(REPORT_SUFFIX)
+            })
+        })
+        .collect();
+    reports.sort();
+    let excess = reports.len().saturating_sub(keep);
+    for path in reports.into_iter().take(excess) {
+        std::fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+fn build_summary() -> Value {
+    json!({
+        "source": build_info::build_source(),
+        "profile": build_info::profile(),
+        "target": build_info::target(),
+        "features": build_info::features(),
+        "release_tag": build_info::release_tag(),
+        "git_commit": build_info::git_commit(),
+        "git_tag": build_info::git_tag(),
+        "git_dirty": build_info::git_dirty(),
+    })
+}
+
+/// The shape of the setup, without anything secret or identifying: no tokens, passwords,
+/// callsigns, URLs, paths or device strings.
+fn config_summary(cfg: &Config) -> Value {
+    let receivers: Vec<Value> = cfg
+        .receivers
+        .iter()
+        .map(|r| {
+            let input = &r.input;
+            json!({
+                "id": r.id,
+                "enabled": r.enabled,
+                "driver": input.driver.as_str(),
+                "format": format!("{:?}", input.driver.get_sample_format()),
+                "signal": format!("{:?}", input.signal),
+                "sps": input.sps,
+                "decimation": input.decimation,
+                "fft_size": input.fft_size,
+                "audio_sps": input.audio_sps,
+                "waterfall_size": input.waterfall_size,
+                "waterfall_compression": input.waterfall_compression.as_str(),
+                "audio_compression": format!("{:?}", input.audio_compression),
+                "accelerator": input.accelerator.as_str(),
+                "window": format!("{:?}", input.window),
+                "skimmer": r.skimmer.enabled,
+                "skimmer_bands": r.skimmer.bands.len(),
+                "capture_triggers": r.capture_triggers.len(),
+            })
+        })
+        .collect();
+    json!({
+        "threads": cfg.server.threads,
+        "otherusers": cfg.server.otherusers,
+        "register_online": cfg.websdr.register_online,
+        "admin": !cfg.admin.token.is_empty() || !cfg.admin.password.is_empty(),
+        "pskreporter": cfg.pskreporter.enabled,
+        "wsprnet": cfg.wsprnet.enabled,
+        "geoip": !cfg.geoip.database.is_empty(),
+        "export": format!("{:?}", cfg.export.format).to_lowercase(),
+        "freqdb": !cfg.freqdb.csv.is_empty() || !cfg.freqdb.url_template.is_empty(),
+        "accounts": cfg.accounts.enabled,
+        "rtl_tcp": cfg.rtl_tcp.enabled,
+        "streams": cfg.streams.enabled,
+        "recordings": cfg.recordings.enabled,
+        "time_shift_minutes": cfg.time_shift.minutes,
+        "active_receiver_id": cfg.active_receiver_id,
+        "receivers": receivers,
+    })
+}
+
+/// Sends reports left by earlier runs to `crash_reports.upload_url`, when set.
+pub fn spawn_upload(state: Arc<AppState>) {
+    let url = state.cfg().crash_reports.upload_url.trim().to_string();
+    if url.is_empty() {
+        return;
+    }
+    let dir = match SETTINGS.read() {
+        Ok(g) => g.as_ref().map(|s| s.dir.clone()),
+        Err(poisoned) => poisoned.into_inner().as_ref().map(|s| s.dir.clone()),
+    };
+    let Some(dir) = dir else {
+        return;
+    };
+    tokio::spawn(async move {
+        let pending = match pending_reports(&dir) {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::debug!(dir = %dir.display(), error = ?e, "no crash reports to upload");
+                return;
+            }
+        };
+        if pending.is_empty() {
+            return;
+        }
+        let client = match reqwest::Client::builder().timeout(UPLOAD_TIMEOUT).build() {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::warn!(error = ?e, "crash report upload disabled: cannot build HTTP client");
+                return;
+            }
+        };
+        for path in pending.into_iter().take(MAX_UPLOADS_PER_START) {
+            match upload(&client, &url, &path).await {
+                Ok(()) => tracing::info!(path = %path.display(), "crash report uploaded"),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = ?e, "crash report upload failed");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+async fn upload(client: &reqwest::Client, url: &str, path: &Path) -> anyhow::Result<()> {
+    let body = tokio::fs::read(path).await.context("read crash report")?;
+    let resp = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(reqwest::header::USER_AGENT, "NovaSDR crash report")
+        .body(body)
+        .send()
+        .await
+        .context("POST crash report")?;
+    anyhow::ensure!(resp.status().is_success(), "HTTP {}", resp.status());
+    let mut sent = path.as_os_str().to_owned();
+    sent.push(SENT_SUFFIX);
+    tokio::fs::rename(path, sent)
+        .await
+        .context("mark crash report sent")?;
+    Ok(())
+}
+
+/// Unsent reports in `dir`, newest first.
+fn pending_reports(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut out: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(REPORT_PREFIX) && n.ends_with(REPORT_SUFFIX))
+        })
+        .collect();
+    // Names start with the UTC time, so they sort chronologically.
+    out.sort();
+    out.reverse();
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_reports_skip_sent_and_foreign_files_newest_first() {
+        let dir = std::env::temp_dir().join(format!(
+            "novasdr_crash_test_{}_{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "crash-20260101T000000.000Z-1.json",
+            "crash-20260301T000000.000Z-1.json",
+            "crash-20260201T000000.000Z-1.json.sent",
+            "novasdr.2026-01-01",
+        ] {
+            std::fs::write(dir.join(name), b"{}").unwrap();
+        }
+        let names: Vec<String> = pending_reports(&dir)
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "crash-20260301T000000.000Z-1.json",
+                "crash-20260101T000000.000Z-1.json"
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_reports_sent_or_not() {
+        let dir = std::env::temp_dir().join(format!(
+            "novasdr_crash_prune_{}",
+            novasdr_core::util::generate_unique_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "crash-20260101T000000.000Z-1.json",
+            "crash-20260201T000000.000Z-1.json.sent",
+            "crash-20260301T000000.000Z-1.json",
+            "crash-20260401T000000.000Z-1.json.sent",
+            "novasdr.2026-01-01",
+        ] {
+            std::fs::write(dir.join(name), b"{}").unwrap();
+        }
+        prune_reports(&dir, 2).unwrap();
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "crash-20260301T000000.000Z-1.json",
+                "crash-20260401T000000.000Z-1.json.sent",
+                "novasdr.2026-01-01"
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,417 @@
+//! Digital voice on NFM channels, with the `digital-voice` feature: the core decoder for call
+//! information, and with the `mbelib` feature the AMBE vocoders that turn DMR, D-STAR and System
+//! Fusion voice frames back into speech.
+
+use novasdr_core::protocol::DigitalVoiceCall;
+#[cfg(feature = "digital-voice")]
+use novasdr_core::{
+    dsp::fm_level::NFM_DEVIATION_HZ,
+    dv::{Decoder, VoiceFrame},
+};
+#[cfg(feature = "digital-voice")]
+use std::collections::VecDeque;
+
+/// Detects digital voice in discriminator output and replaces it with decoded speech, or with
+/// silence while there is none (no vocoder, or a System Fusion call in VD mode 1 or Voice FR).
+#[cfg(feature = "digital-voice")]
+pub struct DigitalVoice {
+    decoder: Decoder,
+    vocoder: Option<Vocoder>,
+    upsampler: Upsampler,
+    speech: VecDeque<f32>,
+    max_buffered: usize,
+    /// Discriminator units of full-scale speech at the audio rate.
+    scale: f32,
+}
+
+#[cfg(feature = "digital-voice")]
+impl DigitalVoice {
+    /// The vocoder's output rate.
+    const SPEECH_RATE: f64 = 8_000.0;
+    /// Speech at full scale is sent as this fraction of the NFM deviation, so the FM leveler
+    /// treats it like a loud analog voice.
+    const SPEECH_DEVIATION: f32 = 0.5;
+    /// Decoded speech kept ahead of playback; more than this means playback fell behind.
+    const MAX_BUFFERED_SECONDS: f64 = 0.5;
+
+    pub fn new(audio_rate: usize) -> Self {
+        let rate = audio_rate as f64;
+        Self {
+            decoder: Decoder::new(rate),
+            vocoder: Vocoder::new(),
+            upsampler: Upsampler::new(Self::SPEECH_RATE / rate),
+            speech: VecDeque::new(),
+            max_buffered: (rate * Self::MAX_BUFFERED_SECONDS) as usize,
+            scale: Self::SPEECH_DEVIATION * std::f32::consts::TAU * NFM_DEVIATION_HZ
+                / audio_rate as f32,
+        }
+    }
+
+    /// Runs the decoder over one frame of discriminator output and, while a digital signal is
+    /// present, overwrites it with speech.
+    pub fn process(&mut self, audio: &mut [f32]) {
+        self.decoder.process(audio);
+        while let Some(frame) = self.decoder.pop_voice() {
+            if let Some(vocoder) = self.vocoder.as_mut() {
+                let pcm = vocoder.decode(&frame);
+                self.upsampler.push(&pcm, &mut self.speech);
+            }
+        }
+        let excess = self.speech.len().saturating_sub(self.max_buffered);
+        self.speech.drain(..excess);
+
+        if !self.decoder.digital() {
+            self.speech.clear();
+            return;
+        }
+        let speaking = self.decoder.active().is_some();
+        for sample in audio.iter_mut() {
+            let speech = if speaking {
+                self.speech.pop_front()
+            } else {
+                None
+            };
+            *sample = speech.unwrap_or(0.0) * self.scale;
+        }
+    }
+
+    pub fn take_update(&mut self) -> Option<DigitalVoiceCall> {
+        self.decoder.take_update()
+    }
+
+    pub fn reset(&mut self) {
+        self.decoder.reset();
+        self.speech.clear();
+        self.upsampler.reset();
+        if let Some(vocoder) = self.vocoder.as_mut() {
+            vocoder.reset();
+        }
+    }
+}
+
+/// Without the `digital-voice` feature the audio passes through and no call is reported.
+#[cfg(not(feature = "digital-voice"))]
+pub struct DigitalVoice;
+
+#[cfg(not(feature = "digital-voice"))]
+impl DigitalVoice {
+    pub fn new(_audio_rate: usize) -> Self {
+        Self
+    }
+
+    pub fn process(&mut self, _audio: &mut [f32]) {}
+
+    pub fn take_update(&mut self) -> Option<DigitalVoiceCall> {
+        None
+    }
+
+    pub fn reset(&mut self) {}
+}
+
+/// Linear interpolation from the vocoder rate up to the audio rate.
+#[cfg(feature = "digital-voice")]
+struct Upsampler {
+    /// Input samples per output sample.
+    step: f64,
+    pos: f64,
+    prev: f32,
+}
+
+#[cfg(feature = "digital-voice")]
+impl Upsampler {
+    fn new(step: f64) -> Self {
+        Self {
+            step,
+            pos: 0.0,
+            prev: 0.0,
+        }
+    }
+
+    fn push(&mut self, input: &[f32], out: &mut VecDeque<f32>) {
+        for &x in input {
+            while self.pos < 1.0 {
+                out.push_back(self.prev + (x - self.prev) * self.pos as f32);
+                self.pos += self.step;
+            }
+            self.pos -= 1.0;
+            self.prev = x;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.pos = 0.0;
+        self.prev = 0.0;
+    }
+}
+
+#[cfg(feature = "mbelib")]
+mod mbe {
+    use std::os::raw::{c_char, c_int, c_short};
+
+    /// `mbe_parms` from mbelib's `mbelib.h`.
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    pub struct MbeParms {
+        w0: f32,
+        L: c_int,
+        K: c_int,
+        Vl: [c_int; 57],
+        Ml: [f32; 57],
+        log2Ml: [f32; 57],
+        PHIl: [f32; 57],
+        PSIl: [f32; 57],
+        gamma: f32,
+        un: c_int,
+        repeat: c_int,
+    }
+
+    impl MbeParms {
+        pub fn zeroed() -> Box<Self> {
+            Box::new(Self {
+                w0: 0.0,
+                L: 0,
+                K: 0,
+                Vl: [0; 57],
+                Ml: [0.0; 57],
+                log2Ml: [0.0; 57],
+                PHIl: [0.0; 57],
+                PSIl: [0.0; 57],
+                gamma: 0.0,
+                un: 0,
+                repeat: 0,
+            })
+        }
+    }
+
+    #[link(name = "mbe")]
+    extern "C" {
+        pub fn mbe_initMbeParms(cur: *mut MbeParms, prev: *mut MbeParms, prev_enh: *mut MbeParms);
+        #[allow(clippy::too_many_arguments)]
+        pub fn mbe_processAmbe3600x2450Frame(
+            aout_buf: *mut c_short,
+            errs: *mut c_int,
+            errs2: *mut c_int,
+            err_str: *mut c_char,
+            ambe_fr: *mut [c_char; 24],
+            ambe_d: *mut c_char,
+            cur: *mut MbeParms,
+            prev: *mut MbeParms,
+            prev_enh: *mut MbeParms,
+            uvquality: c_int,
+        );
+        #[allow(clippy::too_many_arguments)]
+        pub fn mbe_processAmbe3600x2400Frame(
+            aout_buf: *mut c_short,
+            errs: *mut c_int,
+            errs2: *mut c_int,
+            err_str: *mut c_char,
+            ambe_fr: *mut [c_char; 24],
+            ambe_d: *mut c_char,
+            cur: *mut MbeParms,
+            prev: *mut MbeParms,
+            prev_enh: *mut MbeParms,
+            uvquality: c_int,
+        );
+        #[allow(clippy::too_many_arguments)]
+        pub fn mbe_processAmbe2450Data(
+            aout_buf: *mut c_short,
+            errs: *mut c_int,
+            errs2: *mut c_int,
+            err_str: *mut c_char,
+            ambe_d: *mut c_char,
+            cur: *mut MbeParms,
+            prev: *mut MbeParms,
+            prev_enh: *mut MbeParms,
+            uvquality: c_int,
+        );
+    }
+}
+
+/// AMBE (3600x2400, D-STAR) and AMBE+2 (3600x2450, DMR and System Fusion VD mode 2) decoders
+/// from mbelib, sharing one set of speech parameters since a channel carries one call at a time.
+#[cfg(feature = "mbelib")]
+pub struct Vocoder {
+    cur: Box<mbe::MbeParms>,
+    prev: Box<mbe::MbeParms>,
+    prev_enh: Box<mbe::MbeParms>,
+}
+
+#[cfg(feature = "mbelib")]
+impl Vocoder {
+    /// mbelib's default unvoiced synthesis quality.
+    const UV_QUALITY: i32 = 3;
+    const FRAME_SAMPLES: usize = 160;
+
+    pub fn new() -> Option<Self> {
+        let mut vocoder = Self {
+            cur: mbe::MbeParms::zeroed(),
+            prev: mbe::MbeParms::zeroed(),
+            prev_enh: mbe::MbeParms::zeroed(),
+        };
+        vocoder.reset();
+        Some(vocoder)
+    }
+
+    fn reset(&mut self) {
+        // SAFETY: the three structs are valid, distinct and live for the call.
+        unsafe {
+            mbe::mbe_initMbeParms(&mut *self.cur, &mut *self.prev, &mut *self.prev_enh);
+        }
+    }
+
+    /// 20 ms of speech at 8 kHz, full scale 1.0.
+    fn decode(&mut self, frame: &VoiceFrame) -> [f32; Self::FRAME_SAMPLES] {
+        use std::os::raw::c_char;
+
+        let mut ambe_fr = [[0 as c_char; 24]; 4];
+        let mut ambe_d = [0 as c_char; 49];
+        match frame {
+            VoiceFrame::Dmr(bits) | VoiceFrame::Dstar(bits) => {
+                for (row, bits) in ambe_fr.iter_mut().zip(bits) {
+                    for (b, &bit) in row.iter_mut().zip(bits) {
+                        *b = bit as c_char;
+                    }
+                }
+            }
+            VoiceFrame::Ysf(bits) => {
+                for (b, &bit) in ambe_d.iter_mut().zip(bits) {
+                    *b = bit as c_char;
+                }
+            }
+        }
+        let mut pcm = [0i16; Self::FRAME_SAMPLES];
+        let mut err_str = [0 as c_char; 64];
+        let (mut errs, mut errs2) = (0, 0);
+        let (cur, prev, prev_enh) = (&mut *self.cur, &mut *self.prev, &mut *self.prev_enh);
+        // SAFETY: every buffer has the size mbelib writes: 160 samples, 64 error characters,
+        // 4x24 frame bits and 49 data bits.
+        unsafe {
+            match frame {
+                VoiceFrame::Dmr(_) => mbe::mbe_processAmbe3600x2450Frame(
+                    pcm.as_mut_ptr(),
+                    &mut errs,
+                    &mut errs2,
+                    err_str.as_mut_ptr(),
+                    ambe_fr.as_mut_ptr(),
+                    ambe_d.as_mut_ptr(),
+                    cur,
+                    prev,
+                    prev_enh,
+                    Self::UV_QUALITY,
+                ),
+                VoiceFrame::Dstar(_) => mbe::mbe_processAmbe3600x2400Frame(
+                    pcm.as_mut_ptr(),
+                    &mut errs,
+                    &mut errs2,
+                    err_str.as_mut_ptr(),
+                    ambe_fr.as_mut_ptr(),
+                    ambe_d.as_mut_ptr(),
+                    cur,
+                    prev,
+                    prev_enh,
+                    Self::UV_QUALITY,
+                ),
+                VoiceFrame::Ysf(_) => mbe::mbe_processAmbe2450Data(
+                    pcm.as_mut_ptr(),
+                    &mut errs,
+                    &mut errs2,
+                    err_str.as_mut_ptr(),
+                    ambe_d.as_mut_ptr(),
+                    cur,
+                    prev,
+                    prev_enh,
+                    Self::UV_QUALITY,
+                ),
+            }
+        }
+        pcm.map(|s| f32::from(s) / 32_768.0)
+    }
+}
+
+/// Without the `mbelib` feature digital voice is muted.
+#[cfg(all(feature = "digital-voice", not(feature = "mbelib")))]
+pub struct Vocoder;
+
+#[cfg(all(feature = "digital-voice", not(feature = "mbelib")))]
+impl Vocoder {
+    pub fn new() -> Option<Self> {
+        None
+    }
+
+    fn reset(&mut self) {}
+
+    fn decode(&mut self, _frame: &VoiceFrame) -> [f32; 0] {
+        []
+    }
+}
+
+#[cfg(all(test, feature = "digital-voice"))]
+mod tests {
+    use super::*;
+    use novasdr_core::dv::{dmr, Mode, SYMBOL_RATE};
+
+    const RATE: usize = 12_000;
+
+    /// Discriminator output for DMR voice headers repeated on one timeslot.
+    fn dmr_headers(slots: usize) -> Vec<f32> {
+        let lc = dmr::FullLc::group_call(9, 1_234_567);
+        let burst = dmr::encode_lc_burst(
+            dmr::BS_DATA_SYNC,
+            1,
+            dmr::DATA_TYPE_VOICE_HEADER,
+            &lc,
+            dmr::VOICE_HEADER_MASK,
+        );
+        let mut levels = Vec::new();
+        let mut seed = 7u32;
+        for _ in 0..slots {
+            let mut filler = || {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                dmr::level((seed >> 30) as u8)
+            };
+            levels.extend((0..12).map(|_| filler()));
+            levels.extend(burst.iter().map(|&d| dmr::level(d)));
+            levels.extend((0..144).map(|_| filler()));
+        }
+        let sps = RATE as f64 / SYMBOL_RATE;
+        let scale = std::f32::consts::TAU * 648.0 / RATE as f32;
+        (0..((levels.len() - 1) as f64 * sps) as usize)
+            .map(|i| levels[(i as f64 / sps) as usize] * scale)
+            .collect()
+    }
+
+    #[test]
+    fn a_dmr_call_is_reported_and_its_noise_muted() {
+        let mut dv = DigitalVoice::new(RATE);
+        let mut updates = Vec::new();
+        let mut last = Vec::new();
+        for chunk in dmr_headers(20).chunks(256) {
+            let mut audio = chunk.to_vec();
+            dv.process(&mut audio);
+            updates.extend(std::iter::from_fn(|| dv.take_update()));
+            last = audio;
+        }
+        let call = updates.last().expect("call update");
+        assert_eq!(call.mode, Mode::Dmr);
+        assert_eq!(call.source, Some(1_234_567));
+        assert_eq!(call.destination, Some(9));
+        assert!(last.iter().all(|&s| s == 0.0));
+
+        // A squelch closing or a retune ends the call.
+        dv.reset();
+        let end = dv.take_update().expect("end of call");
+        assert!(!end.active);
+    }
+
+    #[test]
+    fn analog_fm_passes_through() {
+        let mut dv = DigitalVoice::new(RATE);
+        let tone: Vec<f32> = (0..RATE)
+            .map(|i| 0.3 * (std::f32::consts::TAU * 800.0 * i as f32 / RATE as f32).sin())
+            .collect();
+        let mut audio = tone.clone();
+        dv.process(&mut audio);
+        assert_eq!(audio, tone);
+        assert_eq!(dv.take_update(), None);
+    }
+}
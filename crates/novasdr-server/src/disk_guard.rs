@@ -0,0 +1,334 @@
+//! Free-space watch over `capture.dir` and the log directory (`disk_guard`): a filesystem above
+//! its high water mark holds back new recordings, has its oldest files deleted per the cleanup
+//! policy and is reported to the log and the webhook, so a full disk stops the recorders rather
+//! than the whole server.
+
+use crate::{recordings, shutdown, state::AppState};
+use novasdr_core::config::{DiskCleanup, DiskGuard};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Files deleted per check at most; the rest waits for the next one, in case something else is
+/// filling the disk faster than deleting frees it.
+const MAX_DELETIONS_PER_CHECK: usize = 1000;
+/// Prefix of the daily log files, as given to the file appender in `main`.
+const LOG_FILE_PREFIX: &str = "novasdr.";
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+/// Last reading of each watched directory, for `/metrics`.
+static USAGE: Mutex<Vec<(&'static str, f64)>> = Mutex::new(Vec::new());
+
+/// Whether new recordings are held back because `capture.dir` is above the high water mark.
+pub fn recordings_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+/// Used share in percent of the filesystem under each watched directory, from the last check.
+pub fn usage() -> Vec<(&'static str, f64)> {
+    usage_lock().clone()
+}
+
+fn usage_lock() -> std::sync::MutexGuard<'static, Vec<(&'static str, f64)>> {
+    match USAGE.lock() {
+        Ok(g) => g,
+        Err(poisoned) => {
+            tracing::error!("disk guard mutex poisoned; recovering");
+            poisoned.into_inner()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Normal,
+    High,
+}
+
+/// Level after a reading of `used` percent: up at the high water mark, down only below the low
+/// one, so usage hovering around a mark does not flap.
+fn next_level(level: Level, used: f64, guard: &DiskGuard) -> Level {
+    match level {
+        Level::Normal if used >= guard.high_water_percent => Level::High,
+        Level::High if used < guard.low_water_percent => Level::Normal,
+        level => level,
+    }
+}
+
+struct Watch {
+    name: &'static str,
+    dir: PathBuf,
+    level: Level,
+}
+
+/// Used share of the filesystem holding `path`, in percent. Space reserved for root counts as
+/// used, as `df` shows it.
+#[cfg(unix)]
+fn used_percent(path: &Path) -> std::io::Result<f64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    // SAFETY: statvfs only writes the struct it is given; all-zero is a valid value of it.
+    let mut st: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `st` outlives the call.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut st) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let total = st.f_blocks as f64;
+    if total <= 0.0 {
+        return Ok(0.0);
+    }
+    Ok(100.0 * (1.0 - st.f_bavail as f64 / total))
+}
+
+#[cfg(not(unix))]
+fn used_percent(_path: &Path) -> std::io::Result<f64> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "free space is only read on Unix",
+    ))
+}
+
+/// `path`, or its closest existing ancestor: `capture.dir` is only created by the first recording.
+fn existing(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|p| p.exists())
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+/// Deletes the oldest rotated log file in `dir`, never the newest, which is being written.
+fn remove_oldest_log(dir: &Path) -> Option<u64> {
+    let mut logs: Vec<(PathBuf, u64)> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|f| {
+            f.file_name()
+                .to_str()
+                .is_some_and(|n| n.starts_with(LOG_FILE_PREFIX))
+        })
+        .filter_map(|f| {
+            let meta = f.metadata().ok()?;
+            meta.is_file().then(|| (f.path(), meta.len()))
+        })
+        .collect();
+    // Daily files end in the date, so name order is age order.
+    logs.sort();
+    if logs.len() < 2 {
+        return None;
+    }
+    let (path, size) = logs.swap_remove(0);
+    match std::fs::remove_file(&path) {
+        Ok(()) => {
+            tracing::warn!(path = %path.display(), "log file deleted for disk space");
+            Some(size)
+        }
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "remove log file");
+            None
+        }
+    }
+}
+
+/// Deletes files per `cleanup` until the filesystem under `watch` is below the low water mark
+/// or nothing deletable is left. Returns the usage afterwards.
+fn clean(watch: &Watch, recordings_dir: &Path, guard: &DiskGuard, mut used: f64) -> f64 {
+    let delete_recordings = guard.cleanup != DiskCleanup::None && watch.name == "recordings";
+    let delete_logs = guard.cleanup == DiskCleanup::RecordingsAndLogs && watch.name == "logs";
+    let mut freed = 0u64;
+    let mut deleted = 0usize;
+    while used >= guard.low_water_percent && deleted < MAX_DELETIONS_PER_CHECK {
+        let removed = if delete_recordings {
+            recordings::remove_oldest(recordings_dir)
+        } else if delete_logs {
+            remove_oldest_log(&watch.dir)
+        } else {
+            None
+        };
+        let Some(bytes) = removed else {
+            break;
+        };
+        freed += bytes;
+        deleted += 1;
+        match used_percent(existing(&watch.dir)) {
+            Ok(now) => used = now,
+            Err(_) => break,
+        }
+    }
+    if deleted > 0 {
+        tracing::warn!(
+            dir = %watch.dir.display(),
+            files = deleted,
+            freed_mb = freed / (1024 * 1024),
+            used_percent = used,
+            "disk guard cleanup"
+        );
+    }
+    used
+}
+
+/// Watches `capture.dir` and `log_dir` every `disk_guard.check_secs`. The section is read on each
+/// check, so a reload applies at the next one.
+pub fn spawn(state: Arc<AppState>, log_dir: Option<PathBuf>) {
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut watches = vec![Watch {
+            name: "recordings",
+            dir: PathBuf::new(),
+            level: Level::Normal,
+        }];
+        if let Some(dir) = log_dir {
+            watches.push(Watch {
+                name: "logs",
+                dir,
+                level: Level::Normal,
+            });
+        }
+        let mut unsupported_logged = false;
+        while !shutdown::is_shutdown_requested() {
+            let cfg = state.cfg();
+            let guard = cfg.disk_guard.clone();
+            let recordings_dir = PathBuf::from(&cfg.capture.dir);
+            drop(cfg);
+            if !guard.enabled {
+                PAUSED.store(false, Ordering::Relaxed);
+                usage_lock().clear();
+                tokio::time::sleep(Duration::from_secs(guard.check_secs.max(1))).await;
+                continue;
+            }
+            watches[0].dir = recordings_dir.clone();
+
+            let checked = {
+                let guard = guard.clone();
+                tokio::task::spawn_blocking(move || {
+                    let mut readings = Vec::new();
+                    for mut watch in watches {
+                        let mut crossings = Vec::new();
+                        let reading = used_percent(existing(&watch.dir)).map(|mut used| {
+                            for step in 0..2 {
+                                let next = next_level(watch.level, used, &guard);
+                                if next != watch.level {
+                                    watch.level = next;
+                                    crossings.push((next, used));
+                                }
+                                if step > 0 || watch.level != Level::High {
+                                    break;
+                                }
+                                used = clean(&watch, &recordings_dir, &guard, used);
+                            }
+                            used
+                        });
+                        readings.push((watch, crossings, reading));
+                    }
+                    readings
+                })
+                .await
+            };
+            let readings = match checked {
+                Ok(readings) => readings,
+                Err(e) => {
+                    tracing::error!(error = ?e, "disk guard task panicked");
+                    return;
+                }
+            };
+
+            let mut next_watches = Vec::with_capacity(readings.len());
+            let mut usage = Vec::with_capacity(readings.len());
+            for (watch, crossings, reading) in readings {
+                for (level, used) in crossings {
+                    report(&client, &guard, &watch, level, used).await;
+                }
+                match reading {
+                    Ok(used) => usage.push((watch.name, used)),
+                    Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {
+                        if !unsupported_logged {
+                            unsupported_logged = true;
+                            tracing::warn!(error = %e, "disk guard cannot read free space");
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!(dir = %watch.dir.display(), error = %e, "disk guard: statvfs");
+                    }
+                }
+                next_watches.push(watch);
+            }
+            watches = next_watches;
+            PAUSED.store(watches[0].level == Level::High, Ordering::Relaxed);
+            *usage_lock() = usage;
+            tokio::time::sleep(Duration::from_secs(guard.check_secs)).await;
+        }
+    });
+}
+
+/// Logs a water mark crossing of `watch` into `level` and POSTs it to `disk_guard.webhook_url`.
+async fn report(
+    client: &reqwest::Client,
+    guard: &DiskGuard,
+    watch: &Watch,
+    level: Level,
+    used: f64,
+) {
+    let high = level == Level::High;
+    if high {
+        tracing::warn!(
+            dir = %watch.dir.display(),
+            used_percent = used,
+            high_water_percent = guard.high_water_percent,
+            recordings_paused = watch.name == "recordings",
+            "disk above high water mark"
+        );
+    } else {
+        tracing::info!(
+            dir = %watch.dir.display(),
+            used_percent = used,
+            low_water_percent = guard.low_water_percent,
+            "disk back below low water mark"
+        );
+    }
+    let webhook = guard.webhook_url.trim();
+    if webhook.is_empty() {
+        return;
+    }
+    let body = json!({
+        "event": if high { "disk_high" } else { "disk_ok" },
+        "watch": watch.name,
+        "dir": watch.dir.display().to_string(),
+        "used_percent": (used * 10.0).round() / 10.0,
+        "high_water_percent": guard.high_water_percent,
+        "low_water_percent": guard.low_water_percent,
+        "recordings_paused": high && watch.name == "recordings",
+        "time": chrono::Utc::now().to_rfc3339(),
+    });
+    let res = client
+        .post(webhook)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&body)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    if let Err(e) = res {
+        tracing::warn!(error = %e, "disk guard webhook failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_rises_at_the_high_mark_and_falls_only_below_the_low_one() {
+        let guard = DiskGuard {
+            high_water_percent: 90.0,
+            low_water_percent: 80.0,
+            ..DiskGuard::default()
+        };
+        assert_eq!(next_level(Level::Normal, 89.9, &guard), Level::Normal);
+        assert_eq!(next_level(Level::Normal, 90.0, &guard), Level::High);
+        assert_eq!(next_level(Level::High, 85.0, &guard), Level::High);
+        assert_eq!(next_level(Level::High, 79.9, &guard), Level::Normal);
+        assert_eq!(next_level(Level::Normal, 85.0, &guard), Level::Normal);
+    }
+}
@@ -0,0 +1,153 @@
+//! Frequency error estimate of receivers with `accuracy.drift_carrier`: the carrier's position
+//! in the averaged spectrum against its known frequency, shown to listeners next to the stated
+//! accuracy of the receiver's reference.
+
+use crate::shutdown;
+use crate::state::{AppState, DriftEstimate, ReceiverState};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Spectrum averaged per estimate; a weak carrier needs a few seconds to stand out.
+const ESTIMATE_SECONDS: f64 = 10.0;
+/// How far the carrier has to stand above the median of the search window, as a power ratio.
+const MIN_PEAK_RATIO: f32 = 10.0;
+/// Weight of a new estimate against the previous one.
+const SMOOTHING: f64 = 0.3;
+/// Change in ppm worth telling connected listeners about.
+const NOTIFY_PPM: f64 = 0.01;
+
+pub fn spawn(state: Arc<AppState>) {
+    for receiver in state.receivers.values() {
+        if let Some(carrier) = receiver.receiver.accuracy.drift_carrier {
+            tokio::spawn(run(state.clone(), receiver.clone(), carrier));
+        }
+    }
+}
+
+async fn run(state: Arc<AppState>, receiver: Arc<ReceiverState>, carrier: i64) {
+    let receiver_id = receiver.receiver.id.as_str();
+    let rt = receiver.rt.clone();
+    let bin_hz = rt.total_bandwidth as f64 / rt.fft_result_size as f64;
+    // Complex input is rotated from bin N/2 + 1 (see `crate::rtl_tcp`).
+    let offset = if rt.is_real { 0.0 } else { 1.0 };
+    let half_width = (receiver.receiver.accuracy.drift_search_hz as f64 / bin_hz).ceil() as usize;
+    let half_width = half_width.max(2);
+    let frame_rate = rt.sps as f64 / (rt.fft_size / 2) as f64;
+    let frames_per_estimate = (frame_rate * ESTIMATE_SECONDS).ceil().max(1.0) as usize;
+    tracing::info!(receiver_id, carrier, "drift estimation");
+
+    let (tx, mut frames) = crate::state::iq_frame_channel();
+    let client_id = state.alloc_client_id();
+    receiver.iq_clients.insert(client_id, tx);
+    let mut power = vec![0f32; 2 * half_width + 1];
+    let mut averaged = 0usize;
+    let mut window_basefreq = receiver.basefreq();
+    let mut notified: Option<f64> = None;
+    while let Some(frame) = frames.recv().await {
+        if shutdown::is_shutdown_requested() {
+            break;
+        }
+        let basefreq = receiver.basefreq();
+        if basefreq != window_basefreq {
+            // A band switch moved the spectrum under the average.
+            window_basefreq = basefreq;
+            power.fill(0.0);
+            averaged = 0;
+        }
+        if !receiver.covers(carrier) {
+            continue;
+        }
+        let center = ((carrier - basefreq) as f64 / bin_hz - offset).round() as i64;
+        let start = center - half_width as i64;
+        let Some(bins) = usize::try_from(start)
+            .ok()
+            .and_then(|start| frame.bins.get(start..start + power.len()))
+        else {
+            continue;
+        };
+        for (p, b) in power.iter_mut().zip(bins) {
+            *p += b.norm_sqr();
+        }
+        averaged += 1;
+        if averaged < frames_per_estimate {
+            continue;
+        }
+        averaged = 0;
+        let peak = peak_position(&power);
+        power.fill(0.0);
+        let Some(peak) = peak else {
+            tracing::debug!(receiver_id, carrier, "drift carrier not found");
+            continue;
+        };
+        let measured = basefreq as f64 + (start as f64 + peak + offset) * bin_hz;
+        let ppm = (measured - carrier as f64) / carrier as f64 * 1e6;
+        let ppm = match receiver.drift() {
+            Some(prev) => prev.ppm + (ppm - prev.ppm) * SMOOTHING,
+            None => ppm,
+        };
+        receiver.set_drift(Some(DriftEstimate {
+            ppm,
+            measured_at: chrono::Utc::now().timestamp().max(0) as u64,
+        }));
+        if notified.is_none_or(|n| (n - ppm).abs() >= NOTIFY_PPM) {
+            notified = Some(ppm);
+            let msg = json!({
+                "type": "accuracy",
+                "receiver_id": receiver_id,
+                "accuracy": receiver.accuracy_json(),
+            })
+            .to_string();
+            for client in receiver.audio_clients.iter() {
+                let _ = client.text_tx.try_send(msg.clone());
+            }
+        }
+    }
+    receiver.iq_clients.remove(&client_id);
+}
+
+/// Fractional index of the strongest bin of `power`, interpolated on a parabola through the
+/// log powers around it; `None` when no bin inside the window stands out.
+fn peak_position(power: &[f32]) -> Option<f64> {
+    let (k, &peak) = power.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))?;
+    if k == 0 || k + 1 == power.len() {
+        return None;
+    }
+    let mut sorted = power.to_vec();
+    sorted.sort_unstable_by(f32::total_cmp);
+    let median = sorted[sorted.len() / 2];
+    if peak <= median * MIN_PEAK_RATIO {
+        return None;
+    }
+    let ln = |p: f32| f64::from(p.max(f32::MIN_POSITIVE)).ln();
+    let (a, b, c) = (ln(power[k - 1]), ln(peak), ln(power[k + 1]));
+    let denom = a - 2.0 * b + c;
+    let delta = if denom < 0.0 {
+        (0.5 * (a - c) / denom).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    Some(k as f64 + delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peak_is_interpolated_between_bins_and_noise_is_rejected() {
+        // A Gaussian main lobe is a parabola in log power.
+        let lobe = |center: f64| -> Vec<f32> {
+            (0..16)
+                .map(|i| {
+                    let d = i as f64 - center;
+                    (1e-3 + 100.0 * (-d * d / 2.0).exp()) as f32
+                })
+                .collect()
+        };
+        let peak = peak_position(&lobe(7.3)).unwrap();
+        assert!((peak - 7.3).abs() < 0.05, "peak at {peak}");
+
+        assert_eq!(peak_position(&[1.0; 16]), None);
+        assert_eq!(peak_position(&lobe(15.0)), None);
+    }
+}
@@ -1,9 +1,14 @@
-use crate::state::{AppState, ReceiverState};
+use crate::channels::{ddc_centre, ChannelBank, ChannelKey, ChannelShape, ChannelTextSent};
+use crate::state::{AppState, AudioClient, AudioParams, ReceiverState, SquelchMode};
 use anyhow::Context;
+use novasdr_core::config::{InputDriver, Runtime};
 use novasdr_core::dsp::{
+    decimate::PreDecimator,
+    demod::DemodulationMode,
     fft::{FftEngine, FftSettings},
     sample::SampleReader,
 };
+use novasdr_core::protocol::DigitalVoiceCall;
 use num_complex::Complex32;
 use std::{
     io,
@@ -12,12 +17,23 @@ use std::{
         Arc, Mutex,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::sync::mpsc::error::TrySendError as TokioTrySendError;
 
 const SAMPLE_BUFFER_POOL_DEPTH: usize = 512;
+const CHAT_BACKPRESSURE_LOG_INTERVAL_SECS: u64 = 60;
+const EVENTS_FANOUT_LOG_INTERVAL_SECS: u64 = 60;
+/// Wait before reopening a failed input, doubled after each failure up to the maximum. An input
+/// that ran for the maximum or longer starts over at the minimum.
+const INPUT_RESTART_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const INPUT_RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+/// How often `/events` and `/chat` client maps are swept for sessions that died without
+/// removing themselves.
+const STALE_CLIENT_SWEEP_INTERVAL_SECS: u64 = 30;
 
+#[cfg(feature = "clfft")]
+use novasdr_core::dsp::clfft::ClfftWaterfallQuantizer;
 #[cfg(feature = "vkfft")]
 use novasdr_core::dsp::vkfft::VkfftWaterfallQuantizer;
 
@@ -52,23 +68,62 @@ pub fn start(state: Arc<AppState>) -> anyhow::Result<()> {
         let use_waterfall_thread = waterfall_threads_budget > 0;
         waterfall_threads_budget = waterfall_threads_budget.saturating_sub(1);
         let soapy_semaphore = soapy_semaphore.clone();
+        let mut capture = crate::capture::EventCapture::new(state.clone(), &rx);
         thread::Builder::new()
             .name(format!("novasdr-dsp-{rx_id}"))
             .spawn(move || {
                 tracing::info!(receiver_id = %rx_id, "DSP thread started");
-                if let Err(e) = run_dsp_loop(
-                    state,
-                    rx,
-                    use_reader_thread,
-                    use_waterfall_thread,
-                    soapy_semaphore,
-                ) {
-                    if crate::shutdown::is_shutdown_requested() || is_expected_input_termination(&e)
-                    {
-                        tracing::info!(receiver_id = %rx_id, error = ?e, "DSP loop terminated");
-                    } else {
-                        tracing::error!(receiver_id = %rx_id, error = ?e, "DSP loop terminated");
+                let mut input = rx.receiver.clone();
+                input.input.frequency = rx.center_frequency();
+                let mut frame_num = 0u64;
+                let mut backoff = None;
+                let e = loop {
+                    let started = Instant::now();
+                    match run_dsp_loop(
+                        state.clone(),
+                        rx.clone(),
+                        &input,
+                        use_reader_thread,
+                        use_waterfall_thread,
+                        soapy_semaphore.clone(),
+                        &mut capture,
+                        &mut frame_num,
+                    ) {
+                        Ok(next) => {
+                            tracing::info!(
+                                receiver_id = %rx_id,
+                                driver = next.input.driver.as_str(),
+                                "reopening input after reload"
+                            );
+                            state.set_receiver_input_status(&rx, Some("reconfigured"));
+                            input = next;
+                        }
+                        Err(e) => {
+                            // stdin cannot be opened a second time.
+                            if crate::shutdown::is_shutdown_requested()
+                                || matches!(input.input.driver, InputDriver::Stdin { .. })
+                            {
+                                break e;
+                            }
+                            let wait = input_restart_backoff(backoff, started.elapsed());
+                            backoff = Some(wait);
+                            tracing::warn!(
+                                receiver_id = %rx_id,
+                                error = ?e,
+                                retry_in_secs = wait.as_secs(),
+                                "input failed; reopening it, listeners stay connected"
+                            );
+                            state.set_receiver_input_status(&rx, Some("input_error"));
+                            if !sleep_unless_shutdown(wait) {
+                                break e;
+                            }
+                        }
                     }
+                };
+                if crate::shutdown::is_shutdown_requested() || is_expected_input_termination(&e) {
+                    tracing::info!(receiver_id = %rx_id, error = ?e, "DSP loop terminated");
+                } else {
+                    tracing::error!(receiver_id = %rx_id, error = ?e, "DSP loop terminated");
                 }
             })?;
     }
@@ -76,6 +131,31 @@ pub fn start(state: Arc<AppState>) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Wait before reopening an input that failed after running for `ran_for`, given the previous
+/// wait (`None` on the first failure).
+fn input_restart_backoff(previous: Option<Duration>, ran_for: Duration) -> Duration {
+    match previous {
+        Some(previous) if ran_for < INPUT_RESTART_BACKOFF_MAX => {
+            (previous * 2).min(INPUT_RESTART_BACKOFF_MAX)
+        }
+        _ => INPUT_RESTART_BACKOFF_MIN,
+    }
+}
+
+/// Sleeps for `duration` in short steps; `false` when shutdown was requested meanwhile.
+fn sleep_unless_shutdown(duration: Duration) -> bool {
+    let until = Instant::now() + duration;
+    while Instant::now() < until {
+        if crate::shutdown::is_shutdown_requested() {
+            return false;
+        }
+        thread::sleep(
+            Duration::from_millis(100).min(until.saturating_duration_since(Instant::now())),
+        );
+    }
+    !crate::shutdown::is_shutdown_requested()
+}
+
 fn is_expected_input_termination(err: &anyhow::Error) -> bool {
     for cause in err.chain() {
         if let Some(ioe) = cause.downcast_ref::<io::Error>() {
@@ -91,35 +171,79 @@ fn is_expected_input_termination(err: &anyhow::Error) -> bool {
     false
 }
 
+/// Builds the FFT pipeline for `accelerator` (falling back towards the CPU as configured) and
+/// records the backend it ended up on.
+fn build_fft_engine(
+    receiver: &ReceiverState,
+    accelerator: novasdr_core::config::Accelerator,
+) -> anyhow::Result<FftEngine> {
+    let rt = &receiver.rt;
+    let settings = FftSettings {
+        fft_size: rt.fft_size,
+        is_real: rt.is_real,
+        brightness_offset: rt.brightness_offset,
+        downsample_levels: rt.downsample_levels,
+        audio_max_fft_size: rt.audio_max_fft_size,
+        accelerator,
+        vulkan_device: receiver.receiver.input.vulkan_device.clone(),
+        dc_suppression: receiver.receiver.input.dc_suppression,
+        window: receiver.receiver.input.window,
+    };
+    let fft = FftEngine::new(settings)?;
+    receiver.set_fft_backend(crate::state::FftBackendInfo {
+        backend: fft.backend(),
+        device: fft.device_name().map(str::to_string),
+    });
+    tracing::info!(
+        receiver_id = %receiver.receiver.id,
+        configured = accelerator.as_str(),
+        fft_backend = fft.backend().as_str(),
+        device = fft.device_name().unwrap_or("cpu"),
+        "FFT backend selected"
+    );
+    Ok(fft)
+}
+
+/// Runs the receiver's pipeline on the input `input_cfg` describes until the input fails, or
+/// until a reload asks for another input; that one is returned. `frame_num` carries on across
+/// reopened inputs so clients see no jump.
+#[allow(clippy::too_many_arguments)]
 fn run_dsp_loop(
     state: Arc<AppState>,
     receiver: Arc<ReceiverState>,
+    input_cfg: &novasdr_core::config::ReceiverConfig,
     use_reader_thread: bool,
     use_waterfall_thread: bool,
     soapy_semaphore: Arc<Mutex<()>>,
-) -> anyhow::Result<()> {
+    capture: &mut Option<crate::capture::EventCapture>,
+    frame_num: &mut u64,
+) -> anyhow::Result<novasdr_core::config::ReceiverConfig> {
     let stop_requested = Arc::new(AtomicBool::new(false));
     let (input, input_name) =
-        crate::input::open(&receiver.receiver, stop_requested.clone(), soapy_semaphore)?;
-    let sample_format = receiver.receiver.input.driver.get_sample_format();
+        crate::input::open(input_cfg, stop_requested.clone(), soapy_semaphore)?;
+    let sample_format = input_cfg.input.driver.get_sample_format();
     tracing::info!(
         receiver_id = %receiver.receiver.id,
         input = input_name,
         format = ?sample_format,
         "input opened"
     );
+    let rt = receiver.rt.clone();
     let mut reader = SampleReader::new(input, sample_format);
+    let decimation = receiver.receiver.input.decimation;
+    if decimation > 1 {
+        reader = reader
+            .with_decimator(PreDecimator::new(decimation, rt.is_real).context("input decimation")?);
+        tracing::info!(
+            receiver_id = %receiver.receiver.id,
+            decimation,
+            input_sps = rt.input_sps,
+            sps = rt.sps,
+            "decimating input before the FFT"
+        );
+    }
 
-    let rt = receiver.rt.clone();
-    let settings = FftSettings {
-        fft_size: rt.fft_size,
-        is_real: rt.is_real,
-        brightness_offset: rt.brightness_offset,
-        downsample_levels: rt.downsample_levels,
-        audio_max_fft_size: rt.audio_max_fft_size,
-        accelerator: receiver.receiver.input.accelerator,
-    };
-    let mut fft = FftEngine::new(settings)?;
+    let mut fft = build_fft_engine(&receiver, receiver.receiver.input.accelerator)?;
 
     let base_idx = if rt.is_real {
         0usize
@@ -152,18 +276,11 @@ fn run_dsp_loop(
         None
     };
 
-    let mut frame_num: u64 = 0;
-    let skip_num = {
-        let frame_rate = (rt.sps as f64) / ((rt.fft_size / 2) as f64);
-        // At very high sample rates, skip waterfall more aggressively to reduce load.
-        // Target ~10 waterfall updates per second maximum.
-        let target_wf_rate = 10.0;
-        let skip = (frame_rate / target_wf_rate).ceil() as u64;
-        skip.max(1)
-    };
+    let skip_num = rt.waterfall_frame_skip;
     tracing::info!(
         skip_num,
         frame_rate = ?((rt.sps as f64) / ((rt.fft_size / 2) as f64)),
+        waterfall_max_fps = receiver.receiver.input.waterfall_max_fps,
         "waterfall frame skip"
     );
 
@@ -177,6 +294,7 @@ fn run_dsp_loop(
         Threaded {
             free_tx: std::sync::mpsc::SyncSender<Vec<f32>>,
             filled_rx: std::sync::mpsc::Receiver<Vec<f32>>,
+            thread: thread::JoinHandle<()>,
         },
         Inline {
             reader: SampleReader<Box<dyn io::Read + Send>>,
@@ -195,7 +313,7 @@ fn run_dsp_loop(
         let reader_name = format!("novasdr-reader-{}", receiver.receiver.id);
         let receiver_id = receiver.receiver.id.clone();
         let free_tx_for_drop = free_tx.clone();
-        thread::Builder::new().name(reader_name).spawn(move || {
+        let thread = thread::Builder::new().name(reader_name).spawn(move || {
             let mut dropped = 0u64;
             while let Ok(mut buf) = free_rx.recv() {
                 if reader.read_f32(&mut buf).is_err() {
@@ -219,7 +337,11 @@ fn run_dsp_loop(
             }
         })?;
 
-        ReaderMode::Threaded { free_tx, filled_rx }
+        ReaderMode::Threaded {
+            free_tx,
+            filled_rx,
+            thread,
+        }
     } else {
         tracing::info!(
             receiver_id = %receiver.receiver.id,
@@ -261,14 +383,60 @@ fn run_dsp_loop(
         half_b_c.resize(complex_len, Complex32::new(0.0, 0.0));
     }
 
-    let mut audio_bins_buf: Vec<Complex32> = Vec::new();
+    // Samples are flowing; listeners waiting on a restart hear the receiver again.
+    state.set_receiver_input_status(&receiver, None);
+
+    let mut skimmer =
+        crate::skimmer::Skimmer::new(state.clone(), &receiver).context("start skimmer")?;
+
+    let mut audio_frames = AudioFramePool::default();
+    let mut audio_channels = ChannelBank::default();
     loop {
+        // The engine keeps no state between frames, so it can be swapped at a frame boundary.
+        if let Some(switch) = receiver.take_fft_switch() {
+            match build_fft_engine(&receiver, switch.accelerator) {
+                Ok(next) => {
+                    fft = next;
+                    if let Some(info) = receiver.fft_backend() {
+                        let _ = switch.reply.send(Ok(info));
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        receiver_id = %receiver.receiver.id,
+                        requested = switch.accelerator.as_str(),
+                        error = ?e,
+                        "FFT backend switch failed; keeping the current pipeline"
+                    );
+                    let _ = switch.reply.send(Err(e));
+                }
+            }
+        }
+        if let Some(next) = receiver.take_input_restart() {
+            stop_requested.store(true, Ordering::Relaxed);
+            if let ReaderMode::Threaded {
+                free_tx,
+                filled_rx,
+                thread,
+            } = reader_mode
+            {
+                // Hand the reader a buffer so it is not parked waiting for one, and let it close
+                // the input before the next one is opened.
+                drop(filled_rx);
+                let _ = free_tx.try_send(half_a);
+                drop(free_tx);
+                let _ = thread.join();
+            }
+            return Ok(next);
+        }
+
         let waterfall_clients = receiver
             .waterfall_clients
             .iter()
             .map(|m| m.len())
             .sum::<usize>();
-        let total_clients = receiver.audio_clients.len() + waterfall_clients;
+        let total_clients =
+            receiver.audio_clients.len() + waterfall_clients + receiver.iq_clients.len();
 
         if rt.is_real {
             fft.load_real_half_a(&half_a);
@@ -280,23 +448,48 @@ fn run_dsp_loop(
             fft.load_complex_half_b(&half_b_c);
         }
 
-        if total_clients > 0 {
+        if total_clients > 0
+            || skimmer.is_some()
+            || capture.is_some()
+            || receiver.replay.is_enabled()
+        {
+            let frame_start = Instant::now();
             let want_waterfall = waterfall_clients > 0 && frame_num.is_multiple_of(skip_num);
             let include_waterfall_in_fft = want_waterfall && wf.is_none();
             let res = fft.execute(include_waterfall_in_fft)?;
+            receiver
+                .dsp_fft_micros
+                .store(frame_start.elapsed().as_micros() as u64, Ordering::Relaxed);
 
             let spectrum = fft.spectrum_for_audio();
+            // One rotated copy of the bins serves audio and rtl_tcp clients alike. It is
+            // buffered before it goes out, which `crate::replay::IqSubscription` relies on.
+            let bins = (!receiver.audio_clients.is_empty()
+                || !receiver.iq_clients.is_empty()
+                || receiver.replay.is_enabled())
+            .then(|| audio_frames.fill(spectrum, base_idx));
+            if let Some(bins) = bins.as_ref() {
+                receiver.replay.push(*frame_num, bins);
+            }
             send_audio(
-                AudioSendContext {
-                    state: &state,
-                    rt: &rt,
-                    receiver: &receiver,
-                    base_idx,
-                },
-                spectrum,
-                frame_num,
-                &mut audio_bins_buf,
+                &state,
+                &receiver,
+                bins.as_ref(),
+                &mut audio_channels,
+                *frame_num,
             );
+            if let Some(bins) = bins.as_ref() {
+                send_iq(&receiver, bins, *frame_num);
+            }
+            if skimmer.is_some() || capture.is_some() {
+                let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+                if let Some(skimmer) = skimmer.as_mut() {
+                    skimmer.process(spectrum, *frame_num, now_ms);
+                }
+                if let Some(capture) = capture.as_mut() {
+                    capture.process(spectrum, *frame_num, now_ms);
+                }
+            }
 
             if let Some(wf_offload) = wf.as_mut() {
                 if want_waterfall {
@@ -306,7 +499,7 @@ fn run_dsp_loop(
                             res.quantized_level_offsets.as_ref(),
                         ) {
                             let job = WaterfallJob::Send {
-                                frame_num,
+                                frame_num: *frame_num,
                                 quantized_concat: quantized_concat.clone(),
                                 offsets: offsets.clone(),
                             };
@@ -320,7 +513,7 @@ fn run_dsp_loop(
                         if buf.len() == spectrum.len() {
                             buf.copy_from_slice(spectrum);
                             let job = WaterfallJob::QuantizeAndSend {
-                                frame_num,
+                                frame_num: *frame_num,
                                 spectrum: buf,
                                 normalize: res.normalize,
                                 base_idx,
@@ -351,15 +544,27 @@ fn run_dsp_loop(
                     res.quantized_concat.as_ref(),
                     res.quantized_level_offsets.as_ref(),
                 ) {
-                    send_waterfall(&state, &rt, &receiver, quantized_concat, offsets, frame_num);
+                    send_waterfall(
+                        &state,
+                        &rt,
+                        &receiver,
+                        quantized_concat,
+                        offsets,
+                        *frame_num,
+                    );
                 }
             }
-            frame_num = frame_num.wrapping_add(1);
+            receiver
+                .dsp_frame_micros
+                .store(frame_start.elapsed().as_micros() as u64, Ordering::Relaxed);
+            *frame_num = frame_num.wrapping_add(1);
         }
 
         // Shift buffers and get next one (reader is already reading ahead)
         match &mut reader_mode {
-            ReaderMode::Threaded { free_tx, filled_rx } => {
+            ReaderMode::Threaded {
+                free_tx, filled_rx, ..
+            } => {
                 let old_a = half_a;
                 half_a = half_b;
                 half_b = filled_rx
@@ -374,6 +579,7 @@ fn run_dsp_loop(
                     .context("read samples (half_b)")?;
             }
         }
+        receiver.count_input(&half_b);
     }
 }
 
@@ -419,27 +625,12 @@ fn spawn_waterfall_worker(
         .name(format!("novasdr-wf-{receiver_id}"))
         .spawn(move || {
             #[cfg(feature = "vkfft")]
-            let mut vkfft_quantizer: Option<VkfftWaterfallQuantizer> =
-                if matches!(
-                    receiver.receiver.input.accelerator,
-                    novasdr_core::config::Accelerator::Vkfft
-                ) {
-                    let fft_size = receiver.rt.fft_result_size;
-                    match VkfftWaterfallQuantizer::new(fft_size) {
-                        Ok(q) => Some(q),
-                        Err(e) => {
-                            tracing::warn!(
-                                receiver_id = %receiver_id,
-                                error = %e,
-                                "vkfft waterfall quantizer init failed; falling back to CPU"
-                            );
-                            None
-                        }
-                    }
-                } else {
-                    None
-                };
-            #[cfg(feature = "vkfft")]
+            let mut vkfft_quantizer: Option<VkfftWaterfallQuantizer> = None;
+            #[cfg(feature = "clfft")]
+            let mut clfft_quantizer: Option<ClfftWaterfallQuantizer> = None;
+            // Backend the quantizers were set up for; they follow the DSP thread's backend,
+            // which can change at runtime through the admin API.
+            let mut quantizer_backend: Option<novasdr_core::config::Accelerator> = None;
             let mut warned_gpu_quantize_failed = false;
 
             while let Ok(job) = work_rx.recv() {
@@ -459,49 +650,85 @@ fn spawn_waterfall_worker(
                             continue;
                         }
 
-                        let base_idx = if is_real { 0 } else { base_idx % fft_result_size };
-                        let (q, o) = {
+                        let active = receiver.fft_backend().map(|b| b.backend);
+                        if active != quantizer_backend {
+                            quantizer_backend = active;
                             #[cfg(feature = "vkfft")]
                             {
-                                if let Some(q) = vkfft_quantizer.as_mut() {
-                                    match q.quantize_and_downsample(
-                                        &spectrum,
-                                        base_idx,
-                                        downsample_levels,
-                                        size_log2,
-                                        normalize,
+                                vkfft_quantizer = None;
+                                if active == Some(novasdr_core::config::Accelerator::Vkfft) {
+                                    match VkfftWaterfallQuantizer::new(
+                                        receiver.rt.fft_result_size,
+                                        receiver.receiver.input.vulkan_device.as_ref(),
                                     ) {
-                                        Ok(res) => res,
-                                        Err(e) => {
-                                            if !warned_gpu_quantize_failed {
-                                                warned_gpu_quantize_failed = true;
-                                                tracing::warn!(
-                                                    receiver_id = %receiver_id,
-                                                    error = %e,
-                                                    "vkfft waterfall quantize failed; falling back to CPU"
-                                                );
-                                            }
-                                            novasdr_core::dsp::fft::quantize_and_downsample_cpu(
-                                                &spectrum,
-                                                normalize,
-                                                base_idx,
-                                                downsample_levels,
-                                                size_log2,
-                                            )
-                                        }
+                                        Ok(q) => vkfft_quantizer = Some(q),
+                                        Err(e) => tracing::warn!(
+                                            receiver_id = %receiver_id,
+                                            error = %e,
+                                            "vkfft waterfall quantizer init failed; falling back to CPU"
+                                        ),
                                     }
-                                } else {
-                                    novasdr_core::dsp::fft::quantize_and_downsample_cpu(
-                                        &spectrum,
-                                        normalize,
-                                        base_idx,
-                                        downsample_levels,
-                                        size_log2,
-                                    )
                                 }
                             }
-                            #[cfg(not(feature = "vkfft"))]
+                            #[cfg(feature = "clfft")]
                             {
+                                clfft_quantizer = None;
+                                if active == Some(novasdr_core::config::Accelerator::Clfft) {
+                                    match ClfftWaterfallQuantizer::new(receiver.rt.fft_result_size) {
+                                        Ok(q) => clfft_quantizer = Some(q),
+                                        Err(e) => tracing::warn!(
+                                            receiver_id = %receiver_id,
+                                            error = %e,
+                                            "clfft waterfall quantizer init failed; falling back to CPU"
+                                        ),
+                                    }
+                                }
+                            }
+                            warned_gpu_quantize_failed = false;
+                        }
+
+                        let base_idx = if is_real {
+                            0
+                        } else {
+                            base_idx % fft_result_size
+                        };
+                        let gpu_res: Option<anyhow::Result<(Vec<i8>, Vec<usize>)>> = None;
+                        #[cfg(feature = "vkfft")]
+                        let gpu_res = gpu_res.or_else(|| {
+                            vkfft_quantizer.as_mut().map(|q| {
+                                q.quantize_and_downsample(
+                                    &spectrum,
+                                    base_idx,
+                                    downsample_levels,
+                                    size_log2,
+                                    normalize,
+                                )
+                            })
+                        });
+                        #[cfg(feature = "clfft")]
+                        let gpu_res = gpu_res.or_else(|| {
+                            clfft_quantizer.as_mut().map(|q| {
+                                q.quantize_and_downsample(
+                                    &spectrum,
+                                    base_idx,
+                                    downsample_levels,
+                                    size_log2,
+                                    normalize,
+                                )
+                            })
+                        });
+                        let (q, o) = match gpu_res {
+                            Some(Ok(res)) => res,
+                            Some(Err(e)) => {
+                                if !warned_gpu_quantize_failed {
+                                    warned_gpu_quantize_failed = true;
+                                    tracing::warn!(
+                                        receiver_id = %receiver_id,
+                                        fft_backend = ?receiver.fft_backend().map(|b| b.backend),
+                                        error = %e,
+                                        "GPU waterfall quantize failed; falling back to CPU"
+                                    );
+                                }
                                 novasdr_core::dsp::fft::quantize_and_downsample_cpu(
                                     &spectrum,
                                     normalize,
@@ -510,6 +737,13 @@ fn spawn_waterfall_worker(
                                     size_log2,
                                 )
                             }
+                            None => novasdr_core::dsp::fft::quantize_and_downsample_cpu(
+                                &spectrum,
+                                normalize,
+                                base_idx,
+                                downsample_levels,
+                                size_log2,
+                            ),
                         };
                         let quantized_concat: Arc<[i8]> = q.into();
                         let offsets: Arc<[usize]> = o.into();
@@ -562,82 +796,351 @@ fn f32_iq_to_complex_into(interleaved: &[f32], out: &mut [Complex32]) {
     }
 }
 
-struct AudioSendContext<'a> {
-    state: &'a Arc<AppState>,
-    rt: &'a novasdr_core::config::Runtime,
-    receiver: &'a Arc<ReceiverState>,
-    base_idx: usize,
+/// Buffers for the shared audio frames. A buffer is refilled once no client task holds it any
+/// more, so in steady state publishing a frame does not allocate.
+#[derive(Default)]
+struct AudioFramePool {
+    buffers: Vec<Arc<[Complex32]>>,
 }
 
+/// More buffers than this means clients hold frames for long; allocate instead of growing.
+const MAX_POOLED_AUDIO_FRAMES: usize = 8;
+
+impl AudioFramePool {
+    /// Copies `spectrum`, rotated by `base_idx`, into a buffer no client holds any more.
+    fn fill(&mut self, spectrum: &[Complex32], base_idx: usize) -> Arc<[Complex32]> {
+        let n = spectrum.len();
+        self.buffers.retain(|b| b.len() == n);
+        let free = self.buffers.iter().position(|b| Arc::strong_count(b) == 1);
+        let mut buf = match free {
+            Some(i) => self.buffers.swap_remove(i),
+            None => vec![Complex32::new(0.0, 0.0); n].into(),
+        };
+        // Only the pool referenced a free buffer, and it was just taken out.
+        if let Some(dst) = Arc::get_mut(&mut buf) {
+            let split = base_idx % n.max(1);
+            dst[..n - split].copy_from_slice(&spectrum[split..]);
+            dst[n - split..].copy_from_slice(&spectrum[..split]);
+        }
+        if self.buffers.len() < MAX_POOLED_AUDIO_FRAMES {
+            self.buffers.push(buf.clone());
+        }
+        buf
+    }
+}
+
+/// Publishes one shared frame to every audio client's demodulator task, grouping clients tuned
+/// the same way into shared channels.
 fn send_audio(
-    ctx: AudioSendContext<'_>,
-    spectrum: &[Complex32],
+    state: &AppState,
+    receiver: &Arc<ReceiverState>,
+    bins: Option<&Arc<[Complex32]>>,
+    bank: &mut ChannelBank,
     frame_num: u64,
-    bins_buf: &mut Vec<Complex32>,
 ) {
-    let fft_result_size = ctx.rt.fft_result_size;
-    for entry in ctx.receiver.audio_clients.iter() {
-        let params = match entry.params.lock() {
-            Ok(g) => g.clone(),
-            Err(poisoned) => {
-                tracing::error!(
-                    unique_id = %entry.unique_id,
-                    "audio params mutex poisoned; recovering"
-                );
-                poisoned.into_inner().clone()
+    let Some(bins) = bins.filter(|_| !receiver.audio_clients.is_empty()) else {
+        bank.finish_frame();
+        receiver.shared_audio_channels.store(0, Ordering::Relaxed);
+        receiver.channelizer_channels.store(0, Ordering::Relaxed);
+        return;
+    };
+    let frame = crate::state::AudioFrame {
+        receiver: receiver.clone(),
+        frame_num,
+        bins: bins.clone(),
+        channel: None,
+        ddc: None,
+    };
+    let send =
+        |client: &AudioClient, frame: crate::state::AudioFrame| match client.frames.try_send(frame)
+        {
+            Ok(()) => {}
+            Err(TokioTrySendError::Closed(_)) => {}
+            Err(TokioTrySendError::Full(_)) => {
+                state.dropped_audio_frames.fetch_add(1, Ordering::Relaxed);
             }
         };
-        let l = params.l.max(0) as usize;
-        let r = params.r.max(0) as usize;
-        if r <= l || r > fft_result_size {
-            continue;
-        }
-        let len = r - l;
-        if len > ctx.rt.audio_max_fft_size {
-            continue;
+
+    if !receiver.receiver.input.shared_channels {
+        for entry in receiver.audio_clients.iter() {
+            send(entry.value(), frame.clone());
         }
-        let idx = (l + ctx.base_idx) % fft_result_size;
+        return;
+    }
+
+    let shape = ChannelShape::of(&receiver.rt);
+    let (clients, listeners): (Vec<Arc<AudioClient>>, Vec<_>) = receiver
+        .audio_clients
+        .iter()
+        .map(|entry| {
+            let grouping =
+                |params: &AudioParams| (ChannelKey::of(params), ddc_centre(params, shape));
+            let grouping = match entry.params.lock() {
+                Ok(g) => grouping(&g),
+                Err(poisoned) => grouping(&poisoned.into_inner()),
+            };
+            (entry.value().clone(), grouping)
+        })
+        .unzip();
+    bank.group(&listeners);
+    for (client, (key, centre)) in clients.iter().zip(&listeners) {
+        let channel = key.and_then(|key| bank.channel_for(key, shape));
+        let ddc = centre
+            .filter(|_| channel.is_none())
+            .and_then(|centre| bank.ddc_for(centre, shape));
+        send(
+            client,
+            crate::state::AudioFrame {
+                channel,
+                ddc,
+                ..frame.clone()
+            },
+        );
+    }
+    bank.finish_frame();
+    receiver
+        .shared_audio_channels
+        .store(bank.len(), Ordering::Relaxed);
+    receiver
+        .channelizer_channels
+        .store(bank.ddc_len(), Ordering::Relaxed);
+}
+
+/// Publishes one frame of rotated bins to every IQ client; a client that cannot keep up
+/// loses the frame (its IQ then has a gap, as a USB dongle's would on overrun).
+fn send_iq(receiver: &Arc<ReceiverState>, bins: &Arc<[Complex32]>, frame_num: u64) {
+    for entry in receiver.iq_clients.iter() {
+        let _ = entry.value().try_send(crate::state::IqFrame {
+            frame_num,
+            bins: bins.clone(),
+        });
+    }
+}
+
+/// Bins `l..r` of a frame of `bins` bins that `params` demodulates, widening `params` to the
+/// whole broadcast channel for WBFM. `None` for a window the receiver cannot serve.
+pub fn audio_window(rt: &Runtime, params: &mut AudioParams, bins: usize) -> Option<(usize, usize)> {
+    let max_len = if params.demodulation == DemodulationMode::Wbfm {
+        // The multiplex (pilot, stereo and RDS subcarriers) needs the whole broadcast
+        // channel, not just the passband the listener selected.
+        let (l, r) = wbfm_window(rt, params.m);
+        params.l = l as i32;
+        params.r = r as i32;
+        rt.audio_max_fft_size * rt.wbfm_decimation
+    } else {
+        rt.audio_max_fft_size
+    };
+    let l = params.l.max(0) as usize;
+    let r = params.r.max(0) as usize;
+    if r <= l || r > rt.fft_result_size || r > bins || r - l > max_len {
+        return None;
+    }
+    Some((l, r))
+}
 
-        // Pass raw unnormalized FFT bins to the audio demod path.
-        bins_buf.resize(len, Complex32::new(0.0, 0.0));
-        for k in 0..len {
-            bins_buf[k] = spectrum[(idx + k) % fft_result_size];
+/// Demodulates `frame` for one client, on that client's task, through its shared channel when it
+/// has one. The passband is a subslice of the shared bins; nothing is copied per client.
+/// `sent` is what the listener was last sent from a shared channel.
+pub fn process_audio_frame(
+    state: &AppState,
+    client: &AudioClient,
+    frame: &crate::state::AudioFrame,
+    sent: &mut ChannelTextSent,
+) {
+    let rt = frame.receiver.rt.as_ref();
+    let mut params = match client.params.lock() {
+        Ok(g) => g.clone(),
+        Err(poisoned) => {
+            tracing::error!(
+                unique_id = %client.unique_id,
+                "audio params mutex poisoned; recovering"
+            );
+            poisoned.into_inner().clone()
         }
-        let slice = bins_buf.as_slice();
-        let audio_mid_idx = params.m.floor() as i32;
+    };
+    let Some((l, r)) = audio_window(rt, &mut params, frame.bins.len()) else {
+        return;
+    };
 
-        let mut pipeline = match entry.pipeline.lock() {
+    // Pass raw unnormalized FFT bins to the audio demod path.
+    let slice = &frame.bins[l..r];
+    let audio_mid_idx = params.m.floor() as i32;
+    let pwr_gain = frame
+        .receiver
+        .smeter_calibration()
+        .power_gain(frame.receiver.bin_frequency(params.m));
+
+    let mut pipeline = match client.pipeline.lock() {
+        Ok(g) => g,
+        Err(poisoned) => {
+            tracing::error!(
+                unique_id = %client.unique_id,
+                "audio pipeline mutex poisoned; recovering"
+            );
+            poisoned.into_inner()
+        }
+    };
+    let level_squelch =
+        params.squelch_enabled && matches!(params.squelch_mode, SquelchMode::Level { .. });
+    pipeline.set_squelch_floor(level_squelch.then(|| noise_floor_around(&frame.bins, l, r)));
+    // The channel was picked from the params at publish time; they may have changed since.
+    let mut rds_update = None;
+    let mut dv_update = None;
+    let mut subtone_update = None;
+    let shared = frame.channel.as_ref().and_then(|channel| {
+        let mut channel = match channel.lock() {
             Ok(g) => g,
             Err(poisoned) => {
-                tracing::error!(
-                    unique_id = %entry.unique_id,
-                    "audio pipeline mutex poisoned; recovering"
-                );
+                tracing::error!("shared audio channel mutex poisoned; recovering");
                 poisoned.into_inner()
             }
         };
-        match pipeline.process(slice, frame_num, &params, ctx.rt.is_real, audio_mid_idx) {
-            Ok(pkts) => {
-                for pkt in pkts {
-                    ctx.state
-                        .total_audio_bits
-                        .fetch_add(pkt.len() * 8, Ordering::Relaxed);
-                    match entry.tx.try_send(pkt) {
-                        Ok(()) => {}
-                        Err(TokioTrySendError::Closed(_)) => {}
-                        Err(TokioTrySendError::Full(_)) => {
-                            ctx.state
-                                .dropped_audio_frames
-                                .fetch_add(1, Ordering::Relaxed);
-                        }
-                    }
+        if ChannelKey::of(&params) != Some(channel.key()) {
+            return None;
+        }
+        let open =
+            channel.demodulate_into(&mut pipeline, slice, frame.frame_num, &params, rt.is_real)?;
+        rds_update = channel.rds_update(sent);
+        dv_update = channel.digital_voice_update(sent);
+        subtone_update = channel.subtone_update(sent);
+        Some(open)
+    });
+    // Without a shared channel, the inverse FFT may still come from a channelizer channel.
+    if shared.is_none() {
+        if let Some(ddc) = frame.ddc.as_ref() {
+            let mut ddc = match ddc.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!("channelizer channel mutex poisoned; recovering");
+                    poisoned.into_inner()
+                }
+            };
+            if ddc_centre(&params, ChannelShape::of(rt)) == Some(ddc.centre()) {
+                let offset = i64::from(audio_mid_idx) - ddc.centre();
+                if let Some(baseband) = ddc.baseband(&frame.bins, rt.is_real, frame.frame_num) {
+                    pipeline.load_channel(baseband, offset, frame.frame_num);
                 }
             }
-            Err(e) => {
-                tracing::warn!(error = ?e, "audio pipeline error");
+        }
+    }
+    let res = match shared {
+        Some(true) => pipeline.encode(slice, frame.frame_num, &params, pwr_gain),
+        Some(false) => Ok(Vec::new()),
+        None => pipeline.process(
+            slice,
+            frame.frame_num,
+            &params,
+            rt.is_real,
+            audio_mid_idx,
+            pwr_gain,
+        ),
+    };
+    let squelch_open = shared.unwrap_or_else(|| pipeline.squelch_open());
+    client.squelch_open.store(squelch_open, Ordering::Relaxed);
+    match res {
+        Ok(pkts) => {
+            for pkt in pkts {
+                state
+                    .total_audio_bits
+                    .fetch_add(pkt.len() * 8, Ordering::Relaxed);
+                match client.tx.try_send(pkt) {
+                    Ok(()) => {}
+                    Err(TokioTrySendError::Closed(_)) => {}
+                    Err(TokioTrySendError::Full(_)) => {
+                        state.dropped_audio_frames.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
             }
         }
+        Err(e) => {
+            tracing::warn!(error = ?e, "audio pipeline error");
+        }
+    }
+    if let Some(txt) = rds_update.or_else(|| pipeline.take_rds_update()) {
+        // Station info is resent on the next change; a full queue only delays it.
+        let _ = client.text_tx.try_send(txt);
+    }
+    // The detector also runs for a tone squelch alone; only listeners that asked hear about it.
+    if let Some(txt) = subtone_update.or_else(|| pipeline.take_subtone_update()) {
+        if params.subtone {
+            let _ = client.text_tx.try_send(txt);
+        }
+    }
+    if let Some(txt) = pipeline.take_fsk_text() {
+        // Text is not repeated; a client too slow to take it misses those characters.
+        let _ = client.text_tx.try_send(txt);
     }
+    let frequency = frame.receiver.bin_frequency(params.m);
+    let dv_updates = dv_update
+        .into_iter()
+        .chain(std::iter::from_fn(|| pipeline.take_digital_voice_update()));
+    for call in dv_updates {
+        let txt = digital_voice_message(&frame.receiver.receiver.id, frequency, &call);
+        let _ = client.text_tx.try_send(txt.clone());
+        state.publish_digital_voice(&frame.receiver.receiver.id, frequency, call.active, txt);
+    }
+    while let Some(event) = pipeline.take_sstv_event() {
+        let receiver_id = &frame.receiver.receiver.id;
+        if let Some(txt) = state
+            .sstv_pictures
+            .event_message(receiver_id, frequency, event)
+        {
+            let _ = client.text_tx.try_send(txt);
+        }
+    }
+}
+
+/// `digital_voice` message: the call information, and where it was heard.
+fn digital_voice_message(receiver_id: &str, frequency: i64, call: &DigitalVoiceCall) -> String {
+    let mut msg = serde_json::json!({
+        "type": "digital_voice",
+        "receiver_id": receiver_id,
+        "frequency": frequency,
+    });
+    if let (Some(msg), Ok(serde_json::Value::Object(call))) =
+        (msg.as_object_mut(), serde_json::to_value(call))
+    {
+        msg.extend(call);
+    }
+    msg.to_string()
+}
+
+/// Spectrum around a passband that the level squelch measures the noise floor on: this many
+/// passband widths, and at least `MIN_FLOOR_SPAN_BINS` bins.
+const FLOOR_SPAN_WIDTHS: usize = 16;
+const MIN_FLOOR_SPAN_BINS: usize = 2048;
+
+/// Mean noise power per bin around `l..r`, from the median of the surrounding bins so that
+/// signals (including the one in the passband) barely move it. The power of a noise-only bin is
+/// exponentially distributed, so its median is `ln 2` times its mean.
+fn noise_floor_around(bins: &[Complex32], l: usize, r: usize) -> f32 {
+    let span = ((r - l) * FLOOR_SPAN_WIDTHS)
+        .max(MIN_FLOOR_SPAN_BINS)
+        .min(bins.len());
+    let start = ((l + r) / 2)
+        .saturating_sub(span / 2)
+        .min(bins.len() - span);
+    let mut power: Vec<f32> = bins[start..start + span]
+        .iter()
+        .map(|c| c.norm_sqr())
+        .collect();
+    if power.is_empty() {
+        return 0.0;
+    }
+    let mid = power.len() / 2;
+    let (_, median, _) = power.select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+    *median / std::f32::consts::LN_2
+}
+
+/// Bins handed to the WBFM demodulator: `audio_max_fft_size * wbfm_decimation` centred on the
+/// tuned bin, shifted inwards at the band edges.
+fn wbfm_window(rt: &Runtime, m: f64) -> (usize, usize) {
+    let width = (rt.audio_max_fft_size * rt.wbfm_decimation).min(rt.fft_result_size);
+    let centre = m.floor().max(0.0) as usize;
+    let l = centre
+        .saturating_sub(width / 2)
+        .min(rt.fft_result_size - width);
+    (l, l + width)
 }
 
 fn send_waterfall(
@@ -648,6 +1151,28 @@ fn send_waterfall(
     offsets: &Arc<[usize]>,
     frame_num: u64,
 ) {
+    let masks = receiver.waterfall_masks();
+    let masked: Arc<[i8]>;
+    let quantized_concat = if masks.is_empty() {
+        quantized_concat
+    } else {
+        let mut q = quantized_concat.to_vec();
+        novasdr_core::dsp::waterfall_mask::apply_waterfall_masks(
+            &mut q,
+            offsets,
+            rt.fft_result_size,
+            &masks,
+        );
+        masked = q.into();
+        &masked
+    };
+    let coarsest = rt.downsample_levels - 1;
+    if let Some(&offset) = offsets.get(coarsest) {
+        let len = rt.fft_result_size >> coarsest;
+        if let Some(row) = quantized_concat.get(offset..offset + len) {
+            receiver.waterfall_history.push(frame_num, row);
+        }
+    }
     for (level, offset) in offsets.iter().copied().enumerate() {
         let level_len = rt.fft_result_size >> level;
         if offset + level_len > quantized_concat.len() {
@@ -676,6 +1201,8 @@ fn send_waterfall(
                 level: p.level,
                 l: p.l,
                 r: p.r,
+                window: entry.window,
+                aggregation: p.aggregation,
                 quantized_concat: quantized_concat.clone(),
                 quantized_offset: start,
             };
@@ -696,6 +1223,8 @@ fn send_waterfall(
 fn start_events_task(state: Arc<AppState>) {
     tokio::spawn(async move {
         let mut tick: u64 = 0;
+        // Chat backpressure totals at the last log line.
+        let mut chat_logged = [0u64; 3];
         loop {
             tokio::time::sleep(Duration::from_secs(1)).await;
             tick += 1;
@@ -709,7 +1238,61 @@ fn start_events_task(state: Arc<AppState>) {
                 .audio_kbits_per_sec
                 .store(au_bits / 1000, Ordering::Relaxed);
 
-            let include_changes = state.cfg.server.otherusers > 0
+            if tick.is_multiple_of(CHAT_BACKPRESSURE_LOG_INTERVAL_SECS) {
+                let totals = state.chat_backpressure.totals();
+                let [retried, missed, resyncs] =
+                    std::array::from_fn(|i| totals[i] - chat_logged[i]);
+                chat_logged = totals;
+                if retried > 0 || missed > 0 || resyncs > 0 {
+                    tracing::info!(
+                        retried_sends = retried,
+                        missed_messages = missed,
+                        history_resyncs = resyncs,
+                        window_secs = CHAT_BACKPRESSURE_LOG_INTERVAL_SECS,
+                        "chat backpressure"
+                    );
+                }
+            }
+
+            if tick.is_multiple_of(STALE_CLIENT_SWEEP_INTERVAL_SECS) {
+                let pruned = state.prune_stale_clients();
+                if pruned > 0 {
+                    tracing::info!(pruned, "removed stale events/chat clients");
+                }
+                state.login_throttle.prune(std::time::Instant::now());
+                state.users.prune(std::time::Instant::now());
+                if let Some(freqdb) = state.freqdb.as_ref() {
+                    freqdb.prune(std::time::Instant::now());
+                }
+            }
+
+            if tick.is_multiple_of(crate::quota::ACCOUNT_INTERVAL_SECS) {
+                crate::quota::account(&state);
+            }
+            if tick.is_multiple_of(crate::quota::PERSIST_INTERVAL_SECS) {
+                let state = state.clone();
+                tokio::spawn(async move { crate::quota::persist(&state).await });
+            }
+
+            if tick.is_multiple_of(EVENTS_FANOUT_LOG_INTERVAL_SECS) {
+                let stats = state.events_fanout.take();
+                if stats.broadcasts > 0 || stats.pruned_clients > 0 {
+                    tracing::info!(
+                        broadcasts = stats.broadcasts,
+                        deliveries = stats.deliveries,
+                        latency_avg_us = stats.latency_avg_us,
+                        latency_max_us = stats.latency_max_us,
+                        max_queue_depth = stats.max_queue_depth,
+                        pruned_clients = stats.pruned_clients,
+                        event_clients = state.event_clients.len(),
+                        chat_clients = state.chat_clients.len(),
+                        window_secs = EVENTS_FANOUT_LOG_INTERVAL_SECS,
+                        "events fan-out"
+                    );
+                }
+            }
+
+            let include_changes = state.cfg().server.otherusers > 0
                 && state
                     .receivers
                     .values()
@@ -717,24 +1300,66 @@ fn start_events_task(state: Arc<AppState>) {
             if !include_changes && !tick.is_multiple_of(10) {
                 continue;
             }
-            let info = state.event_info(include_changes);
-            let json = match serde_json::to_string(&info) {
-                Ok(s) => s,
-                Err(e) => {
-                    tracing::error!(error = ?e, "failed to serialize events payload");
-                    "{}".to_string()
-                }
-            };
-            let msg: Arc<str> = Arc::from(json);
-            let mut dead = Vec::new();
-            for entry in state.event_clients.iter() {
-                if entry.value().try_send(msg.clone()).is_err() {
-                    dead.push(*entry.key());
-                }
-            }
-            for id in dead {
-                state.event_clients.remove(&id);
-            }
+            state.broadcast_event_info(include_changes);
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_floor_ignores_the_signal_in_the_passband() {
+        // Unit-power noise bins (median of an exponential distribution, undone by `ln 2`)
+        // with a strong carrier filling the passband.
+        let mut bins = vec![Complex32::new(std::f32::consts::LN_2.sqrt(), 0.0); 8192];
+        for c in &mut bins[4000..4100] {
+            *c = Complex32::new(100.0, 0.0);
+        }
+        let floor = noise_floor_around(&bins, 4000, 4100);
+        assert!((floor - 1.0).abs() < 1e-3, "{floor}");
+        // Near the band edge the span shifts inwards instead of shrinking.
+        let floor = noise_floor_around(&bins, 0, 50);
+        assert!((floor - 1.0).abs() < 1e-3, "{floor}");
+    }
+
+    #[test]
+    fn input_restart_backoff_doubles_until_an_input_stays_up() {
+        let quick = Duration::from_millis(10);
+        let mut wait = None;
+        let mut waits = Vec::new();
+        for _ in 0..7 {
+            let next = input_restart_backoff(wait, quick);
+            waits.push(next.as_secs());
+            wait = Some(next);
+        }
+        assert_eq!(waits, [1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(
+            input_restart_backoff(wait, INPUT_RESTART_BACKOFF_MAX),
+            INPUT_RESTART_BACKOFF_MIN
+        );
+    }
+
+    #[test]
+    fn audio_frame_pool_rotates_and_reuses_released_buffers() {
+        let spectrum: Vec<Complex32> = (0..8).map(|k| Complex32::new(k as f32, 0.0)).collect();
+        let mut pool = AudioFramePool::default();
+
+        let held = pool.fill(&spectrum, 6);
+        let re: Vec<f32> = held.iter().map(|c| c.re).collect();
+        assert_eq!(re, [6.0, 7.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        // A buffer a client still holds is never overwritten.
+        let second = pool.fill(&spectrum, 0);
+        assert!(!Arc::ptr_eq(&held, &second));
+        assert_eq!(held[0].re, 6.0);
+
+        drop(held);
+        drop(second);
+        let third = pool.fill(&spectrum, 1);
+        assert_eq!(pool.buffers.len(), 2);
+        assert!(pool.buffers.iter().any(|b| Arc::ptr_eq(b, &third)));
+        assert_eq!(third[0].re, 1.0);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{shutdown, state::AppState};
+use anyhow::Context;
+use novasdr_core::config::ExportFormat;
+use std::fmt::Write as _;
+use std::sync::{atomic::Ordering, Arc};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One exported value; `tags` identify the receiver and, for watch levels, the capture trigger.
+struct Sample {
+    name: &'static str,
+    tags: Vec<(&'static str, String)>,
+    value: f64,
+}
+
+impl Sample {
+    fn global(name: &'static str, value: f64) -> Self {
+        Self {
+            name,
+            tags: Vec::new(),
+            value,
+        }
+    }
+}
+
+fn collect(state: &AppState) -> Vec<Sample> {
+    let mut out = vec![
+        Sample::global("audio_clients", state.total_audio_clients() as f64),
+        Sample::global("waterfall_clients", state.total_waterfall_clients() as f64),
+        Sample::global("events_clients", state.event_clients.len() as f64),
+        Sample::global("chat_clients", state.chat_clients.len() as f64),
+        Sample::global(
+            "audio_kbits_per_second",
+            state.audio_kbits_per_sec.load(Ordering::Relaxed) as f64,
+        ),
+        Sample::global(
+            "waterfall_kbits_per_second",
+            state.waterfall_kbits_per_sec.load(Ordering::Relaxed) as f64,
+        ),
+        Sample::global(
+            "dropped_audio_frames_total",
+            state.dropped_audio_frames.load(Ordering::Relaxed) as f64,
+        ),
+        Sample::global(
+            "dropped_waterfall_frames_total",
+            state.dropped_waterfall_frames.load(Ordering::Relaxed) as f64,
+        ),
+    ];
+    for pool in crate::buffer_pool::stats() {
+        let tags = vec![("pool", pool.name.to_string())];
+        let values = [
+            ("buffer_pool_hits_total", pool.hits as f64),
+            ("buffer_pool_misses_total", pool.misses as f64),
+            ("buffer_pool_in_use_bytes", pool.in_use_bytes as f64),
+            (
+                "buffer_pool_peak_in_use_bytes",
+                pool.peak_in_use_bytes as f64,
+            ),
+            ("buffer_pool_idle_bytes", pool.idle_bytes as f64),
+        ];
+        for (name, value) in values {
+            out.push(Sample {
+                name,
+                tags: tags.clone(),
+                value,
+            });
+        }
+    }
+
+    let mut ids: Vec<&String> = state.receivers.keys().collect();
+    ids.sort();
+    for id in ids {
+        let rx = &state.receivers[id];
+        let tags = vec![("receiver", id.clone())];
+        let per_receiver = [
+            ("receiver_audio_clients", rx.audio_clients.len() as f64),
+            (
+                "receiver_waterfall_clients",
+                rx.waterfall_connections() as f64,
+            ),
+            (
+                "dsp_fft_micros",
+                rx.dsp_fft_micros.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "dsp_frame_micros",
+                rx.dsp_frame_micros.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "shared_audio_channels",
+                rx.shared_audio_channels.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "channelizer_channels",
+                rx.channelizer_channels.load(Ordering::Relaxed) as f64,
+            ),
+        ];
+        for (name, value) in per_receiver {
+            out.push(Sample {
+                name,
+                tags: tags.clone(),
+                value,
+            });
+        }
+
+        let mut watches: Vec<_> = rx
+            .watch_levels
+            .iter()
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect();
+        watches.sort_by(|a, b| a.0.cmp(&b.0));
+        for (trigger, level) in watches {
+            let tags = vec![("receiver", id.clone()), ("trigger", trigger)];
+            out.push(Sample {
+                name: "watch_peak_db",
+                tags: tags.clone(),
+                value: level.peak_db as f64,
+            });
+            out.push(Sample {
+                name: "watch_floor_db",
+                tags,
+                value: level.floor_db as f64,
+            });
+        }
+    }
+    out
+}
+
+/// Commas, spaces and equals signs are significant in line-protocol tags.
+fn escape_influx_tag(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, ',' | ' ' | '=' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Graphite paths are dot-separated; anything but `[A-Za-z0-9_-]` in a component becomes `_`.
+fn graphite_component(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Non-finite values (a watch range that saw no signal yet) are skipped by both formats.
+fn influx_lines(prefix: &str, samples: &[Sample], time_unix: i64) -> String {
+    let mut out = String::new();
+    let ts_ns = time_unix.saturating_mul(1_000_000_000);
+    for s in samples.iter().filter(|s| s.value.is_finite()) {
+        let _ = write!(
+            out,
+            "{}",
+            escape_influx_tag(&format!("{prefix}_{}", s.name))
+        );
+        for (key, value) in s.tags.iter() {
+            let _ = write!(out, ",{key}={}", escape_influx_tag(value));
+        }
+        let _ = writeln!(out, " value={} {ts_ns}", s.value);
+    }
+    out
+}
+
+fn graphite_lines(prefix: &str, samples: &[Sample], time_unix: i64) -> String {
+    let mut out = String::new();
+    for s in samples.iter().filter(|s| s.value.is_finite()) {
+        let mut path = prefix.trim_end_matches('.').to_string();
+        for (_, value) in s.tags.iter() {
+            path.push('.');
+            path.push_str(&graphite_component(value));
+        }
+        let _ = writeln!(out, "{path}.{} {} {time_unix}", s.name, s.value);
+    }
+    out
+}
+
+/// Pushes client counts, bitrates, DSP timings and capture watch levels to InfluxDB or Graphite
+/// every `export.interval_secs`, for setups that do not scrape `/metrics`.
+pub fn spawn(state: Arc<AppState>) {
+    let cfg = state.cfg().export.clone();
+    if cfg.format == ExportFormat::None {
+        return;
+    }
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!(error = ?e, "metrics export disabled: cannot build HTTP client");
+            return;
+        }
+    };
+    tracing::info!(format = ?cfg.format, export_target = %cfg.target, "metrics export enabled");
+
+    tokio::spawn(async move {
+        let mut failing = false;
+        while !shutdown::is_shutdown_requested() {
+            tokio::time::sleep(Duration::from_secs(cfg.interval_secs)).await;
+            let samples = collect(&state);
+            let now = chrono::Utc::now().timestamp();
+            let res = match cfg.format {
+                ExportFormat::Influxdb => {
+                    let body = influx_lines(&cfg.prefix, &samples, now);
+                    push_influx(&client, &cfg.target, &cfg.token, body).await
+                }
+                ExportFormat::Graphite => {
+                    let body = graphite_lines(&cfg.prefix, &samples, now);
+                    push_graphite(&cfg.target, body).await
+                }
+                ExportFormat::None => Ok(()),
+            };
+            // Log transitions only, so an unreachable database does not flood the log.
+            match res {
+                Ok(()) if failing => {
+                    failing = false;
+                    tracing::info!(export_target = %cfg.target, "metrics export recovered");
+                }
+                Ok(()) => {}
+                Err(e) if !failing => {
+                    failing = true;
+                    tracing::warn!(error = ?e, export_target = %cfg.target, "metrics export failed");
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+async fn push_influx(
+    client: &reqwest::Client,
+    url: &str,
+    token: &str,
+    body: String,
+) -> anyhow::Result<()> {
+    let mut req = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(body);
+    if !token.is_empty() {
+        req = req.header(reqwest::header::AUTHORIZATION, format!("Token {token}"));
+    }
+    req.send()
+        .await
+        .with_context(|| format!("POST {url}"))?
+        .error_for_status()
+        .context("InfluxDB rejected write")?;
+    Ok(())
+}
+
+async fn push_graphite(addr: &str, body: String) -> anyhow::Result<()> {
+    let connect = tokio::net::TcpStream::connect(addr);
+    let mut stream = tokio::time::timeout(REQUEST_TIMEOUT, connect)
+        .await
+        .with_context(|| format!("connect {addr}: timed out"))?
+        .with_context(|| format!("connect {addr}"))?;
+    stream
+        .write_all(body.as_bytes())
+        .await
+        .with_context(|| format!("write to {addr}"))?;
+    stream.shutdown().await.ok();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<Sample> {
+        vec![
+            Sample::global("audio_clients", 3.0),
+            Sample {
+                name: "watch_peak_db",
+                tags: vec![
+                    ("receiver", "rx0".to_string()),
+                    ("trigger", "6m es, low".to_string()),
+                ],
+                value: -71.5,
+            },
+            Sample::global("dsp_frame_micros", f64::NEG_INFINITY),
+        ]
+    }
+
+    #[test]
+    fn influx_lines_escape_tags_and_use_nanoseconds() {
+        assert_eq!(
+            influx_lines("novasdr", &samples(), 1_700_000_000),
+            "novasdr_audio_clients value=3 1700000000000000000\n\
+             novasdr_watch_peak_db,receiver=rx0,trigger=6m\\ es\\,\\ low value=-71.5 1700000000000000000\n"
+        );
+    }
+
+    #[test]
+    fn graphite_lines_sanitize_path_components() {
+        assert_eq!(
+            graphite_lines("novasdr", &samples(), 1_700_000_000),
+            "novasdr.audio_clients 3 1700000000\n\
+             novasdr.rx0.6m_es__low.watch_peak_db -71.5 1700000000\n"
+        );
+    }
+}
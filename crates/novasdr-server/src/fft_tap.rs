@@ -0,0 +1,168 @@
+use crate::auth::constant_time_eq;
+use crate::state::{AppState, ClientId, ReceiverState};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+
+/// Time a client gets to send its token.
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_TOKEN_LEN: u64 = 256;
+
+/// Listens for FFT tap clients on every receiver with `fft_tap.enabled`.
+pub fn spawn(state: Arc<AppState>) {
+    for receiver in state.receivers.values() {
+        let cfg = receiver.receiver.fft_tap.clone();
+        if !cfg.enabled {
+            continue;
+        }
+        let state = state.clone();
+        let receiver = receiver.clone();
+        let slots = Arc::new(Semaphore::new(cfg.max_clients));
+        tokio::spawn(async move {
+            let receiver_id = receiver.receiver.id.as_str();
+            let addr = format!("{}:{}", cfg.host, cfg.port);
+            let listener = match TcpListener::bind(addr.as_str()).await {
+                Ok(l) => l,
+                Err(e) => {
+                    tracing::error!(bind = %addr, receiver_id = %receiver_id, error = ?e, "fft tap disabled: cannot bind");
+                    return;
+                }
+            };
+            tracing::info!(bind = %addr, receiver_id = %receiver_id, "fft tap listening");
+            loop {
+                let (socket, peer) = match listener.accept().await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        tracing::warn!(error = ?e, "fft tap accept failed");
+                        continue;
+                    }
+                };
+                let Ok(slot) = slots.clone().try_acquire_owned() else {
+                    tracing::info!(%peer, receiver_id = %receiver_id, "fft tap client refused: all slots in use");
+                    continue;
+                };
+                let state = state.clone();
+                let receiver = receiver.clone();
+                let token = cfg.token.clone();
+                let decimation = u64::from(cfg.decimation.max(1));
+                tokio::spawn(async move {
+                    let _slot = slot;
+                    let client_id = state.alloc_client_id();
+                    if let Err(e) =
+                        serve_client(socket, &receiver, client_id, peer, &token, decimation).await
+                    {
+                        tracing::debug!(client_id, error = ?e, "fft tap client ended");
+                    }
+                    if receiver.iq_clients.remove(&client_id).is_some() {
+                        tracing::info!(client_id, %peer, "fft tap client disconnected");
+                    }
+                });
+            }
+        });
+    }
+}
+
+/// Stream description sent once, as a JSON line, before the first frame.
+fn header(receiver: &ReceiverState, decimation: u64) -> serde_json::Value {
+    let rt = &receiver.rt;
+    let bin_hz = rt.total_bandwidth as f64 / rt.fft_result_size as f64;
+    // Complex input is rotated from bin N/2 + 1 (see `crate::rtl_tcp`).
+    let offset = if rt.is_real { 0.0 } else { 1.0 };
+    let frame_rate = rt.sps as f64 / (rt.fft_size / 2) as f64;
+    serde_json::json!({
+        "receiver_id": receiver.receiver.id,
+        "format": "cf32_le",
+        "bins": rt.fft_result_size,
+        "start_hz": receiver.basefreq() as f64 + offset * bin_hz,
+        "bin_hz": bin_hz,
+        "fft_size": rt.fft_size,
+        "frame_rate": frame_rate / decimation as f64,
+        "decimation": decimation,
+    })
+}
+
+async fn serve_client(
+    socket: TcpStream,
+    receiver: &Arc<ReceiverState>,
+    client_id: ClientId,
+    peer: SocketAddr,
+    token: &str,
+    decimation: u64,
+) -> anyhow::Result<()> {
+    socket.set_nodelay(true)?;
+    let (reader, mut writer) = socket.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut provided = String::new();
+    let mut limited = (&mut reader).take(MAX_TOKEN_LEN);
+    tokio::time::timeout(TOKEN_TIMEOUT, limited.read_line(&mut provided)).await??;
+    if !constant_time_eq(provided.trim().as_bytes(), token.trim().as_bytes()) {
+        tracing::info!(client_id, %peer, "fft tap client refused: invalid token");
+        return Ok(());
+    }
+
+    let mut line = header(receiver, decimation).to_string();
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+
+    let (tx, mut frames) = crate::state::iq_frame_channel();
+    receiver.iq_clients.insert(client_id, tx);
+    tracing::info!(client_id, %peer, receiver_id = %receiver.receiver.id, "fft tap client connected");
+
+    let mut input = [0u8; 256];
+    let mut out: Vec<u8> = Vec::new();
+    loop {
+        let frame = tokio::select! {
+            // Nothing is expected from the client; reading only tells when it hangs up.
+            read = reader.read(&mut input) => {
+                if read? == 0 {
+                    return Ok(());
+                }
+                continue;
+            }
+            frame = frames.recv() => frame,
+        };
+        let Some(frame) = frame else {
+            return Ok(());
+        };
+        if !frame.frame_num.is_multiple_of(decimation) {
+            continue;
+        }
+        encode_frame(frame.frame_num, &frame.bins, &mut out);
+        writer.write_all(&out).await?;
+    }
+}
+
+/// Frame number (u64) followed by every bin as two f32, all little-endian.
+fn encode_frame(frame_num: u64, bins: &[num_complex::Complex32], out: &mut Vec<u8>) {
+    out.clear();
+    out.reserve(8 + bins.len() * 8);
+    out.extend_from_slice(&frame_num.to_le_bytes());
+    for b in bins {
+        out.extend_from_slice(&b.re.to_le_bytes());
+        out.extend_from_slice(&b.im.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex32;
+
+    #[test]
+    fn frames_are_numbered_little_endian_complex_floats() {
+        let mut out = Vec::new();
+        encode_frame(
+            3,
+            &[Complex32::new(1.0, -2.0), Complex32::new(0.5, 0.0)],
+            &mut out,
+        );
+        assert_eq!(out.len(), 8 + 2 * 8);
+        assert_eq!(out[..8], 3u64.to_le_bytes());
+        assert_eq!(out[8..12], 1.0f32.to_le_bytes());
+        assert_eq!(out[12..16], (-2.0f32).to_le_bytes());
+        assert_eq!(out[16..20], 0.5f32.to_le_bytes());
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::Context;
+use dashmap::DashMap;
+use novasdr_core::config::FreqDb;
+use novasdr_core::freqdb::{self, FreqEntry};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupError {
+    /// This client IP used up its lookups for the minute.
+    RateLimited,
+}
+
+impl LookupError {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RateLimited => "rate_limited",
+        }
+    }
+}
+
+/// Known users and allocations for "what is this?" lookups: the `freqdb.csv` entries plus, with
+/// `freqdb.url_template`, answers from an HTTP database, cached and rate limited so listeners
+/// cannot hammer it.
+pub struct FrequencyDatabase {
+    cfg: FreqDb,
+    entries: Vec<FreqEntry>,
+    http: Option<reqwest::Client>,
+    /// Upstream answers per `tolerance_hz`-wide bucket, with the time they were fetched.
+    cache: DashMap<i64, (Instant, Arc<Vec<FreqEntry>>)>,
+    upstream_window: Mutex<(u32, Instant)>,
+    client_windows: DashMap<IpAddr, (u32, Instant)>,
+}
+
+impl FrequencyDatabase {
+    /// `csv` is the resolved `freqdb.csv` path, if one is configured.
+    pub fn open(cfg: &FreqDb, csv: Option<&Path>) -> anyhow::Result<Self> {
+        let entries = match csv {
+            Some(path) => {
+                let raw = std::fs::read_to_string(path)
+                    .with_context(|| format!("read {}", path.display()))?;
+                freqdb::parse_csv(&raw).with_context(|| format!("parse {}", path.display()))?
+            }
+            None => Vec::new(),
+        };
+        let http = if cfg.url_template.trim().is_empty() {
+            None
+        } else {
+            Some(
+                reqwest::Client::builder()
+                    .timeout(REQUEST_TIMEOUT)
+                    .build()
+                    .context("build frequency database HTTP client")?,
+            )
+        };
+        Ok(Self {
+            cfg: cfg.clone(),
+            entries,
+            http,
+            cache: DashMap::new(),
+            upstream_window: Mutex::new((0, Instant::now())),
+            client_windows: DashMap::new(),
+        })
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn admit_client(&self, ip: IpAddr, now: Instant) -> bool {
+        let mut entry = self.client_windows.entry(ip).or_insert((0, now));
+        if now.duration_since(entry.1) >= RATE_WINDOW {
+            *entry = (0, now);
+        }
+        if entry.0 >= self.cfg.lookups_per_minute {
+            return false;
+        }
+        entry.0 += 1;
+        true
+    }
+
+    fn admit_upstream(&self, now: Instant) -> bool {
+        let mut window = match self.upstream_window.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("frequency database rate mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        if now.duration_since(window.1) >= RATE_WINDOW {
+            *window = (0, now);
+        }
+        if window.0 >= self.cfg.upstream_per_minute {
+            return false;
+        }
+        window.0 += 1;
+        true
+    }
+
+    /// Entries near `hz`, nearest first. An upstream failure or an exhausted upstream budget
+    /// only leaves out the upstream answers.
+    pub async fn lookup(&self, ip: IpAddr, hz: i64) -> Result<Vec<FreqEntry>, LookupError> {
+        let now = Instant::now();
+        if !self.admit_client(ip, now) {
+            return Err(LookupError::RateLimited);
+        }
+        let tolerance = self.cfg.tolerance_hz;
+        let mut found = freqdb::lookup(&self.entries, hz, tolerance, self.cfg.max_results);
+        if let Some(upstream) = self.upstream(hz, now).await {
+            found.extend(freqdb::lookup(
+                &upstream,
+                hz,
+                tolerance,
+                self.cfg.max_results,
+            ));
+            found.sort_by_key(|e| (e.distance(hz), e.end_hz - e.start_hz));
+            found.dedup();
+            found.truncate(self.cfg.max_results);
+        }
+        Ok(found)
+    }
+
+    async fn upstream(&self, hz: i64, now: Instant) -> Option<Arc<Vec<FreqEntry>>> {
+        let http = self.http.as_ref()?;
+        // Lookups within the same bucket share one upstream query; the bucket centre is what
+        // gets asked, so its answer covers the whole bucket within the tolerance.
+        let width = self.cfg.tolerance_hz.max(1);
+        let bucket = hz.div_euclid(width);
+        let ttl = Duration::from_secs(self.cfg.cache_secs);
+        if let Some(hit) = self.cache.get(&bucket) {
+            if now.duration_since(hit.0) < ttl {
+                return Some(hit.1.clone());
+            }
+        }
+        if !self.admit_upstream(now) {
+            tracing::debug!(hz, "frequency database upstream budget used up");
+            return None;
+        }
+
+        let url = freqdb::expand_url(self.cfg.url_template.trim(), bucket * width + width / 2);
+        let fetched = async {
+            let body = http
+                .get(&url)
+                .send()
+                .await
+                .with_context(|| format!("GET {url}"))?
+                .error_for_status()
+                .context("frequency database rejected lookup")?
+                .text()
+                .await
+                .context("read frequency database response")?;
+            freqdb::parse_csv(&body).context("parse frequency database response")
+        }
+        .await;
+        match fetched {
+            Ok(entries) => {
+                let entries = Arc::new(entries);
+                self.cache.insert(bucket, (now, entries.clone()));
+                Some(entries)
+            }
+            Err(e) => {
+                tracing::warn!(error = ?e, "frequency database lookup failed");
+                None
+            }
+        }
+    }
+
+    /// Forgets expired rate windows and cache entries; called from the periodic sweep.
+    pub fn prune(&self, now: Instant) {
+        self.client_windows
+            .retain(|_, (_, since)| now.duration_since(*since) < RATE_WINDOW);
+        let ttl = Duration::from_secs(self.cfg.cache_secs);
+        self.cache
+            .retain(|_, (fetched, _)| now.duration_since(*fetched) < ttl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_lookups_are_limited_per_ip_and_window() {
+        let cfg = FreqDb {
+            lookups_per_minute: 2,
+            ..FreqDb::default()
+        };
+        let db = FrequencyDatabase::open(&cfg, None).expect("open");
+        let (a, b): (IpAddr, IpAddr) = ("192.0.2.1".parse().unwrap(), "192.0.2.2".parse().unwrap());
+        let t0 = Instant::now();
+        assert!(db.admit_client(a, t0));
+        assert!(db.admit_client(a, t0));
+        assert!(!db.admit_client(a, t0));
+        assert!(db.admit_client(b, t0));
+        assert!(db.admit_client(a, t0 + RATE_WINDOW));
+
+        db.prune(t0 + RATE_WINDOW * 2);
+        assert!(db.client_windows.is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use anyhow::Context;
+use dashmap::DashMap;
+use novasdr_core::config;
+use std::{
+    collections::{BTreeMap, HashSet},
+    net::IpAddr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Stats key for addresses the database has no country for.
+pub const UNKNOWN_COUNTRY: &str = "unknown";
+
+/// Allow/deny lists from `geoip`, checked at WebSocket upgrade.
+#[derive(Debug, Clone, Default)]
+pub struct CountryFilter {
+    allow: HashSet<String>,
+    deny: HashSet<String>,
+    deny_unknown: bool,
+}
+
+fn parse_codes(key: &str, codes: &[String]) -> anyhow::Result<HashSet<String>> {
+    codes
+        .iter()
+        .map(|code| {
+            let code = code.trim().to_ascii_uppercase();
+            if code.len() != 2 || !code.bytes().all(|b| b.is_ascii_uppercase()) {
+                anyhow::bail!("geoip.{key}: {code:?} is not an ISO 3166-1 alpha-2 country code");
+            }
+            Ok(code)
+        })
+        .collect()
+}
+
+impl CountryFilter {
+    pub fn from_config(cfg: &config::GeoIp) -> anyhow::Result<Self> {
+        Ok(Self {
+            allow: parse_codes("allow_countries", &cfg.allow_countries)?,
+            deny: parse_codes("deny_countries", &cfg.deny_countries)?,
+            deny_unknown: cfg.deny_unknown,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty() && !self.deny_unknown
+    }
+
+    /// The deny list wins over the allow list; unknown countries pass unless `deny_unknown` is set.
+    pub fn permits(&self, country: Option<&str>) -> bool {
+        match country {
+            None => !self.deny_unknown,
+            Some(code) => {
+                !self.deny.contains(code) && (self.allow.is_empty() || self.allow.contains(code))
+            }
+        }
+    }
+}
+
+/// Country lookups for client addresses plus connection counters for `/metrics`.
+pub struct GeoIp {
+    reader: maxminddb::Reader<Vec<u8>>,
+    filter: CountryFilter,
+    /// Accepted new client IPs per country since startup.
+    connections: DashMap<String, u64>,
+    rejected: AtomicU64,
+}
+
+impl GeoIp {
+    pub fn open(path: &std::path::Path, cfg: &config::GeoIp) -> anyhow::Result<Self> {
+        let filter = CountryFilter::from_config(cfg)?;
+        let reader = maxminddb::Reader::open_readfile(path)
+            .with_context(|| format!("open GeoIP database {}", path.display()))?;
+        Ok(Self {
+            reader,
+            filter,
+            connections: DashMap::new(),
+            rejected: AtomicU64::new(0),
+        })
+    }
+
+    /// Upper-case ISO code of the country `ip` is located in, falling back to the country the
+    /// network is registered in.
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        let record = self.reader.lookup::<maxminddb::geoip2::Country>(ip).ok()?;
+        record
+            .country
+            .and_then(|c| c.iso_code)
+            .or_else(|| record.registered_country.and_then(|c| c.iso_code))
+            .map(str::to_ascii_uppercase)
+    }
+
+    /// Looks up a newly seen client IP, applies the country filter and counts the result.
+    pub fn admit(&self, ip: IpAddr) -> Result<Option<String>, Option<String>> {
+        let country = self.country(ip);
+        if !self.filter.permits(country.as_deref()) {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(country);
+        }
+        let key = country.as_deref().unwrap_or(UNKNOWN_COUNTRY).to_string();
+        *self.connections.entry(key).or_insert(0) += 1;
+        Ok(country)
+    }
+
+    pub fn connections_by_country(&self) -> BTreeMap<String, u64> {
+        self.connections
+            .iter()
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect()
+    }
+
+    pub fn rejected_connections(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(allow: &[&str], deny: &[&str], deny_unknown: bool) -> CountryFilter {
+        CountryFilter::from_config(&config::GeoIp {
+            database: String::new(),
+            allow_countries: allow.iter().map(|s| s.to_string()).collect(),
+            deny_countries: deny.iter().map(|s| s.to_string()).collect(),
+            deny_unknown,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn deny_list_and_unknown_countries() {
+        let f = filter(&[], &["ru", " KP "], false);
+        assert!(f.permits(Some("DE")));
+        assert!(!f.permits(Some("RU")));
+        assert!(!f.permits(Some("KP")));
+        assert!(f.permits(None));
+        assert!(!filter(&[], &[], true).permits(None));
+        assert!(filter(&[], &[], false).is_empty());
+    }
+
+    #[test]
+    fn allow_list_admits_only_listed_countries_minus_denied() {
+        let f = filter(&["DE", "AT", "CH"], &["CH"], false);
+        assert!(f.permits(Some("AT")));
+        assert!(!f.permits(Some("CH")));
+        assert!(!f.permits(Some("US")));
+        assert!(f.permits(None));
+    }
+
+    #[test]
+    fn invalid_country_codes_are_rejected() {
+        for bad in ["DEU", "D", "1A", ""] {
+            let cfg = config::GeoIp {
+                deny_countries: vec![bad.to_string()],
+                ..config::GeoIp::default()
+            };
+            assert!(CountryFilter::from_config(&cfg).is_err(), "{bad:?}");
+        }
+    }
+}
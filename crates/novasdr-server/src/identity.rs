@@ -0,0 +1,187 @@
+use crate::state::AppState;
+use anyhow::Context;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use serde::Serialize;
+use std::{path::Path, sync::Arc};
+
+/// Path of the public identity document, relative to the server root.
+pub const WELL_KNOWN_PATH: &str = "/.well-known/novasdr-identity.json";
+/// First line of every signed message; bumping it invalidates signatures made under an older
+/// layout instead of letting them verify against a different meaning.
+const SIGNING_CONTEXT: &str = "novasdr-identity-v1";
+
+/// Operator key pair. Directories pin the public key of a listing and only accept updates
+/// signed by it, so copying another server's hostname into a registration no longer works.
+pub struct ServerIdentity {
+    key_pair: Ed25519KeyPair,
+    public_key: String,
+}
+
+/// The server metadata covered by a signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedMetadata<'a> {
+    pub hostname: &'a str,
+    pub port: u16,
+    pub name: &'a str,
+    /// Unix seconds; lets verifiers reject replays of old payloads.
+    pub signed_at: i64,
+}
+
+impl SignedMetadata<'_> {
+    /// Canonical byte string that is signed and verified:
+    /// `novasdr-identity-v1\nhostname=..\nport=..\nname=..\nsigned_at=..`.
+    pub fn message(&self) -> String {
+        format!(
+            "{SIGNING_CONTEXT}\nhostname={}\nport={}\nname={}\nsigned_at={}",
+            self.hostname, self.port, self.name, self.signed_at
+        )
+    }
+}
+
+/// Signature block attached to registration payloads and the well-known document.
+#[derive(Debug, Clone, Serialize)]
+pub struct IdentityProof {
+    pub algorithm: &'static str,
+    pub public_key: String,
+    pub signed_at: i64,
+    pub signature: String,
+}
+
+impl ServerIdentity {
+    /// Loads the PKCS#8 key at `path`, generating and saving a new one if the file does not exist.
+    pub fn load_or_create(path: &Path) -> anyhow::Result<Self> {
+        let pkcs8 = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let doc = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                    .map_err(|_| anyhow::anyhow!("generate identity key"))?;
+                write_private(path, doc.as_ref())?;
+                tracing::info!(path = %path.display(), "generated server identity key");
+                doc.as_ref().to_vec()
+            }
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        };
+        let key_pair = Ed25519KeyPair::from_pkcs8(&pkcs8)
+            .map_err(|e| anyhow::anyhow!("parse identity key {}: {e}", path.display()))?;
+        let public_key = BASE64.encode(key_pair.public_key().as_ref());
+        Ok(Self {
+            key_pair,
+            public_key,
+        })
+    }
+
+    /// Base64 (standard alphabet) of the raw 32-byte Ed25519 public key.
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    pub fn sign(&self, metadata: &SignedMetadata<'_>) -> IdentityProof {
+        let signature = self.key_pair.sign(metadata.message().as_bytes());
+        IdentityProof {
+            algorithm: "ed25519",
+            public_key: self.public_key.clone(),
+            signed_at: metadata.signed_at,
+            signature: BASE64.encode(signature.as_ref()),
+        }
+    }
+}
+
+fn write_private(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("create {}", path.display()))?;
+    std::io::Write::write_all(&mut file, bytes).with_context(|| format!("write {}", path.display()))
+}
+
+/// Metadata of this server as advertised to directories, signed now.
+pub fn sign_server_metadata(state: &AppState, identity: &ServerIdentity) -> IdentityProof {
+    let websdr = &state.cfg().websdr;
+    identity.sign(&SignedMetadata {
+        hostname: websdr.hostname.as_str(),
+        port: websdr.public_port.unwrap_or(state.cfg().server.port),
+        name: websdr.name.as_str(),
+        signed_at: novasdr_core::clock::now().timestamp(),
+    })
+}
+
+/// `GET /.well-known/novasdr-identity.json`: the public key plus a fresh signature over the
+/// advertised metadata, so a directory can check a listing by fetching it from the listed host.
+pub async fn well_known(State(state): State<Arc<AppState>>) -> Response {
+    let Some(identity) = state.identity.as_deref() else {
+        return (StatusCode::NOT_FOUND, "server identity disabled").into_response();
+    };
+    let websdr = &state.cfg().websdr;
+    let proof = sign_server_metadata(&state, identity);
+    Json(serde_json::json!({
+        "hostname": websdr.hostname,
+        "port": websdr.public_port.unwrap_or(state.cfg().server.port),
+        "name": websdr.name,
+        "identity": proof,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{UnparsedPublicKey, ED25519};
+
+    fn temp_key_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "novasdr_identity_{}_{}.key",
+            std::process::id(),
+            rand::random::<u32>()
+        ))
+    }
+
+    #[test]
+    fn key_is_generated_once_and_signatures_verify() {
+        let path = temp_key_path();
+        let first = ServerIdentity::load_or_create(&path).expect("generate");
+        let second = ServerIdentity::load_or_create(&path).expect("reload");
+        assert_eq!(first.public_key(), second.public_key());
+
+        let metadata = SignedMetadata {
+            hostname: "sdr.example.org",
+            port: 9002,
+            name: "Test SDR",
+            signed_at: 1_700_000_000,
+        };
+        let proof = second.sign(&metadata);
+        let public_key = BASE64.decode(&proof.public_key).expect("public key");
+        let signature = BASE64.decode(&proof.signature).expect("signature");
+        let verifier = UnparsedPublicKey::new(&ED25519, public_key);
+        assert!(verifier
+            .verify(metadata.message().as_bytes(), &signature)
+            .is_ok());
+
+        let spoofed = SignedMetadata {
+            hostname: "other.example.org",
+            ..metadata
+        };
+        assert!(verifier
+            .verify(spoofed.message().as_bytes(), &signature)
+            .is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,7 +1,11 @@
+mod ka9q;
+mod rx888;
 #[cfg(feature = "soapysdr")]
 mod soapysdr;
+mod synthetic;
 
-use novasdr_core::config::{InputDriver, ReceiverConfig};
+use novasdr_core::config::{InputDriver, ReceiverConfig, SoapySdrDriver, Transmit};
+use std::collections::BTreeMap;
 use std::io::Read;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
@@ -24,11 +28,29 @@ pub fn open(
             ),
             driver_name,
         )),
+        InputDriver::Ka9q(driver) => Ok((
+            ka9q::open(driver, &receiver.input, stop_requested)?,
+            driver_name,
+        )),
+        InputDriver::Rx888(driver) => Ok((
+            rx888::open(driver, &receiver.input, stop_requested)?,
+            driver_name,
+        )),
+        InputDriver::Synthetic { scenario } => Ok((
+            synthetic::open(scenario, &receiver.input, stop_requested)?,
+            driver_name,
+        )),
         InputDriver::SoapySdr(driver) => {
             #[cfg(feature = "soapysdr")]
             {
                 Ok((
-                    soapysdr::open(driver, &receiver.input, stop_requested, soapy_semaphore)?,
+                    soapysdr::open(
+                        receiver.id.as_str(),
+                        driver,
+                        &receiver.input,
+                        stop_requested,
+                        soapy_semaphore,
+                    )?,
                     driver_name,
                 ))
             }
@@ -36,10 +58,177 @@ pub fn open(
             #[cfg(not(feature = "soapysdr"))]
             {
                 let _ = (driver, stop_requested, soapy_semaphore);
-                anyhow::bail!(
-                    "SoapySDR input support is disabled (rebuild with Cargo feature \"soapysdr\")"
-                )
+                anyhow::bail!(SOAPYSDR_DISABLED)
             }
         }
     }
 }
+
+#[cfg(not(feature = "soapysdr"))]
+const SOAPYSDR_DISABLED: &str =
+    "SoapySDR input support is disabled (rebuild with Cargo feature \"soapysdr\")";
+
+/// Frequency correction in ppm that `receiver_id`'s SoapySDR input runs with.
+pub fn ppm_correction(receiver_id: &str, driver: &SoapySdrDriver) -> anyhow::Result<f64> {
+    #[cfg(feature = "soapysdr")]
+    {
+        Ok(soapysdr::ppm_correction(receiver_id, driver))
+    }
+
+    #[cfg(not(feature = "soapysdr"))]
+    {
+        let _ = (receiver_id, driver);
+        anyhow::bail!(SOAPYSDR_DISABLED)
+    }
+}
+
+/// Changes the frequency correction of `receiver_id`'s SoapySDR input without reopening it.
+pub fn set_ppm_correction(
+    receiver_id: &str,
+    driver: &SoapySdrDriver,
+    ppm: f64,
+) -> anyhow::Result<()> {
+    #[cfg(feature = "soapysdr")]
+    {
+        soapysdr::set_ppm_correction(receiver_id, driver, ppm)
+    }
+
+    #[cfg(not(feature = "soapysdr"))]
+    {
+        let _ = (receiver_id, driver, ppm);
+        anyhow::bail!(SOAPYSDR_DISABLED)
+    }
+}
+
+/// Readings of `driver.reference_sensors`, such as a GPSDO's lock state.
+pub fn reference_sensors(
+    receiver_id: &str,
+    driver: &SoapySdrDriver,
+) -> anyhow::Result<BTreeMap<String, String>> {
+    #[cfg(feature = "soapysdr")]
+    {
+        soapysdr::reference_sensors(receiver_id, driver)
+    }
+
+    #[cfg(not(feature = "soapysdr"))]
+    {
+        let _ = (receiver_id, driver);
+        anyhow::bail!(SOAPYSDR_DISABLED)
+    }
+}
+
+/// Antenna ports and bias tee of a running SoapySDR input.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AntennaState {
+    pub antenna: String,
+    /// Ports the device offers on the receiver's channel.
+    pub antennas: Vec<String>,
+    /// `None` when the device has no bias tee setting.
+    pub bias_tee: Option<bool>,
+    pub bias_tee_setting: Option<String>,
+}
+
+/// Antenna and bias tee of `receiver_id`'s running SoapySDR input.
+pub fn antenna_state(receiver_id: &str, driver: &SoapySdrDriver) -> anyhow::Result<AntennaState> {
+    #[cfg(feature = "soapysdr")]
+    {
+        soapysdr::antenna_state(receiver_id, driver)
+    }
+
+    #[cfg(not(feature = "soapysdr"))]
+    {
+        let _ = (receiver_id, driver);
+        anyhow::bail!(SOAPYSDR_DISABLED)
+    }
+}
+
+/// Switches the antenna port of `receiver_id`'s running SoapySDR input; reopens keep it.
+pub fn set_antenna(
+    receiver_id: &str,
+    driver: &SoapySdrDriver,
+    antenna: &str,
+) -> anyhow::Result<()> {
+    #[cfg(feature = "soapysdr")]
+    {
+        soapysdr::set_antenna_name(receiver_id, driver, antenna)
+    }
+
+    #[cfg(not(feature = "soapysdr"))]
+    {
+        let _ = (receiver_id, driver, antenna);
+        anyhow::bail!(SOAPYSDR_DISABLED)
+    }
+}
+
+/// Powers the bias tee of `receiver_id`'s running SoapySDR input on or off; reopens keep it.
+pub fn set_bias_tee(receiver_id: &str, driver: &SoapySdrDriver, on: bool) -> anyhow::Result<()> {
+    #[cfg(feature = "soapysdr")]
+    {
+        soapysdr::set_bias_tee_on(receiver_id, driver, on)
+    }
+
+    #[cfg(not(feature = "soapysdr"))]
+    {
+        let _ = (receiver_id, driver, on);
+        anyhow::bail!(SOAPYSDR_DISABLED)
+    }
+}
+
+/// Overall gain range in dB of `receiver_id`'s running SoapySDR input.
+pub fn gain_range(receiver_id: &str) -> anyhow::Result<(f64, f64)> {
+    #[cfg(feature = "soapysdr")]
+    {
+        soapysdr::gain_range(receiver_id)
+    }
+
+    #[cfg(not(feature = "soapysdr"))]
+    {
+        let _ = receiver_id;
+        anyhow::bail!(SOAPYSDR_DISABLED)
+    }
+}
+
+/// Changes the overall gain of `receiver_id`'s running SoapySDR input; reopens keep it.
+pub fn set_gain(receiver_id: &str, driver: &SoapySdrDriver, gain: f64) -> anyhow::Result<()> {
+    #[cfg(feature = "soapysdr")]
+    {
+        soapysdr::set_gain_db(receiver_id, driver, gain)
+    }
+
+    #[cfg(not(feature = "soapysdr"))]
+    {
+        let _ = (receiver_id, driver, gain);
+        anyhow::bail!(SOAPYSDR_DISABLED)
+    }
+}
+
+/// The TX side of a receiver's SoapySDR device, owned by one `/tx` session (`crate::tx`).
+/// Dropping it stops transmitting and releases PTT.
+pub trait Transmitter: Send {
+    /// Moves the carrier to `frequency` Hz.
+    fn tune(&mut self, frequency: f64) -> anyhow::Result<()>;
+    /// TX gain range in dB.
+    fn gain_range(&self) -> anyhow::Result<(f64, f64)>;
+    fn set_gain(&mut self, gain_db: f64) -> anyhow::Result<()>;
+    /// Starts transmitting with PTT asserted, or stops and releases PTT.
+    fn key(&mut self, on: bool) -> anyhow::Result<()>;
+    /// Sends baseband at `tx.sample_rate`; blocks while the device's buffers are full.
+    fn write(&mut self, samples: &[num_complex::Complex32]) -> anyhow::Result<()>;
+}
+
+#[cfg(not(feature = "tx"))]
+const TX_DISABLED: &str = "transmit support is disabled (rebuild with Cargo feature \"tx\")";
+
+/// Opens the TX channel `tx.channel` on the device `receiver_id`'s running input streams from.
+pub fn open_transmitter(receiver_id: &str, tx: &Transmit) -> anyhow::Result<Box<dyn Transmitter>> {
+    #[cfg(feature = "tx")]
+    {
+        Ok(Box::new(soapysdr::SoapyTransmitter::open(receiver_id, tx)?))
+    }
+
+    #[cfg(not(feature = "tx"))]
+    {
+        let _ = (receiver_id, tx);
+        anyhow::bail!(TX_DISABLED)
+    }
+}
@@ -0,0 +1,476 @@
+use anyhow::Context;
+use novasdr_core::config::{ByteOrder, Ka9qDriver, ReceiverInput, SampleFormat, SignalType};
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const RTP_VERSION: u8 = 2;
+const RTP_HEADER_LEN: usize = 12;
+/// Largest UDP datagram.
+const MAX_DATAGRAM: usize = 65_536;
+/// Socket receive buffer requested for wide IQ channels; the kernel may cap it.
+const RECV_BUFFER_BYTES: usize = 8 << 20;
+/// How often a blocked read wakes up to check for shutdown.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+/// Largest jump of the RTP clock, in seconds, treated as packet loss (filled with silence) or a
+/// late packet (dropped). Bigger jumps mean radiod restarted the channel and the clock restarts.
+const MAX_CLOCK_STEP_SECONDS: f64 = 0.5;
+/// Interval over which the received sample rate is compared with `receiver.input.sps`.
+const RATE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Relative sample-rate error reported as a configuration mismatch.
+const RATE_TOLERANCE: f64 = 0.01;
+
+pub fn open(
+    driver: &Ka9qDriver,
+    input: &ReceiverInput,
+    stop_requested: Arc<AtomicBool>,
+) -> anyhow::Result<Box<dyn Read + Send>> {
+    let layout = PayloadLayout::new(driver.format, driver.byte_order)?;
+    let expected_signal = if layout.complex {
+        SignalType::Iq
+    } else {
+        SignalType::Real
+    };
+    anyhow::ensure!(
+        input.signal == expected_signal,
+        "ka9q format {:?} requires receiver.input.signal = {:?}",
+        driver.format,
+        expected_signal
+    );
+
+    let group = driver
+        .group
+        .to_socket_addrs()
+        .with_context(|| format!("resolve ka9q group {:?}", driver.group))?
+        .next()
+        .with_context(|| format!("ka9q group {:?} did not resolve", driver.group))?;
+    let socket = bind(group, driver.interface)
+        .with_context(|| format!("listen for ka9q stream on {group}"))?;
+    tracing::info!(%group, ssrc = ?driver.ssrc, "ka9q: listening for RTP stream");
+
+    let max_clock_step = (input.sps as f64 * MAX_CLOCK_STEP_SECONDS) as u32;
+    Ok(Box::new(Ka9qRead {
+        socket,
+        group,
+        stop_requested,
+        depacketizer: Depacketizer::new(driver.ssrc, driver.payload_type, layout, max_clock_step),
+        rate: RateMonitor::new(input.sps),
+        datagram: vec![0; MAX_DATAGRAM],
+        pending: Vec::new(),
+        read_pos: 0,
+        stalled: false,
+    }))
+}
+
+fn bind(group: SocketAddr, interface: Option<Ipv4Addr>) -> anyhow::Result<UdpSocket> {
+    let socket = Socket::new(Domain::for_address(group), Type::DGRAM, Some(Protocol::UDP))
+        .context("create UDP socket")?;
+    // radiod's own tools (monitor, pcmrecord) may be listening on the same port.
+    socket.set_reuse_address(true).context("set SO_REUSEADDR")?;
+    if let Err(e) = socket.set_recv_buffer_size(RECV_BUFFER_BYTES) {
+        tracing::warn!(error = ?e, "ka9q: could not enlarge the socket receive buffer");
+    }
+    let local = match group.ip() {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    socket
+        .bind(&SockAddr::from(SocketAddr::new(local, group.port())))
+        .context("bind UDP socket")?;
+    match group.ip() {
+        IpAddr::V4(ip) if ip.is_multicast() => socket
+            .join_multicast_v4(&ip, &interface.unwrap_or(Ipv4Addr::UNSPECIFIED))
+            .context("join IPv4 multicast group")?,
+        IpAddr::V6(ip) if ip.is_multicast() => socket
+            .join_multicast_v6(&ip, 0)
+            .context("join IPv6 multicast group")?,
+        _ => {}
+    }
+    socket
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .context("set socket read timeout")?;
+    Ok(socket.into())
+}
+
+/// How RTP payload bytes map onto the samples NovaSDR reads.
+#[derive(Debug, Clone, Copy)]
+struct PayloadLayout {
+    complex: bool,
+    /// Bytes per real component.
+    sample_bytes: usize,
+    /// The payload byte order differs from the host's.
+    swap: bool,
+}
+
+impl PayloadLayout {
+    fn new(format: SampleFormat, byte_order: Option<ByteOrder>) -> anyhow::Result<Self> {
+        let (complex, sample_bytes, default_order) = match format {
+            SampleFormat::S16 => (false, 2, ByteOrder::Big),
+            SampleFormat::Cs16 => (true, 2, ByteOrder::Big),
+            SampleFormat::F32 => (false, 4, ByteOrder::Little),
+            SampleFormat::Cf32 => (true, 4, ByteOrder::Little),
+            other => anyhow::bail!(
+                "ka9q input only supports format \"s16\", \"cs16\", \"f32\" or \"cf32\" (got {other:?})"
+            ),
+        };
+        let host = if cfg!(target_endian = "big") {
+            ByteOrder::Big
+        } else {
+            ByteOrder::Little
+        };
+        Ok(Self {
+            complex,
+            sample_bytes,
+            swap: byte_order.unwrap_or(default_order) != host,
+        })
+    }
+
+    /// Bytes per RTP timestamp tick: one sample, or one I/Q pair.
+    fn frame_bytes(self) -> usize {
+        self.sample_bytes * if self.complex { 2 } else { 1 }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct RtpPacket<'a> {
+    payload_type: u8,
+    timestamp: u32,
+    ssrc: u32,
+    payload: &'a [u8],
+}
+
+fn parse_rtp(buf: &[u8]) -> Option<RtpPacket<'_>> {
+    if buf.len() < RTP_HEADER_LEN || buf[0] >> 6 != RTP_VERSION {
+        return None;
+    }
+    let csrc_count = (buf[0] & 0x0f) as usize;
+    let mut start = RTP_HEADER_LEN + 4 * csrc_count;
+    if buf[0] & 0x10 != 0 {
+        let ext = buf.get(start..start + 4)?;
+        start += 4 + 4 * u16::from_be_bytes([ext[2], ext[3]]) as usize;
+    }
+    let mut end = buf.len();
+    if buf[0] & 0x20 != 0 {
+        end = end.checked_sub(*buf.last()? as usize)?;
+    }
+    Some(RtpPacket {
+        payload_type: buf[1] & 0x7f,
+        timestamp: u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]),
+        ssrc: u32::from_be_bytes([buf[8], buf[9], buf[10], buf[11]]),
+        payload: buf.get(start..end)?,
+    })
+}
+
+/// Turns RTP packets of one radiod channel into a continuous sample stream. The RTP timestamp
+/// counts sample frames, so lost packets are replaced by the same number of silent frames and the
+/// sample clock seen by the DSP never slips.
+struct Depacketizer {
+    ssrc: Option<u32>,
+    payload_type: Option<u8>,
+    layout: PayloadLayout,
+    max_clock_step: u32,
+    /// Timestamp expected on the next packet.
+    next_timestamp: Option<u32>,
+    warned_payload_type: bool,
+    warned_payload_len: bool,
+}
+
+impl Depacketizer {
+    fn new(
+        ssrc: Option<u32>,
+        payload_type: Option<u8>,
+        layout: PayloadLayout,
+        max_clock_step: u32,
+    ) -> Self {
+        Self {
+            ssrc,
+            payload_type,
+            layout,
+            max_clock_step,
+            next_timestamp: None,
+            warned_payload_type: false,
+            warned_payload_len: false,
+        }
+    }
+
+    /// Appends the frames carried by `datagram`, converted to host byte order, to `out`, and
+    /// returns how many frames were appended (including filled gaps).
+    fn push(&mut self, datagram: &[u8], out: &mut Vec<u8>) -> usize {
+        let Some(packet) = parse_rtp(datagram) else {
+            return 0;
+        };
+        // Several radiod channels can share a group; the first one heard is ours unless the
+        // config names one.
+        match self.ssrc {
+            Some(ssrc) if ssrc != packet.ssrc => return 0,
+            Some(_) => {}
+            None => {
+                tracing::info!(
+                    ssrc = packet.ssrc,
+                    payload_type = packet.payload_type,
+                    "ka9q: locked to RTP stream"
+                );
+                self.ssrc = Some(packet.ssrc);
+            }
+        }
+        match self.payload_type {
+            Some(pt) if pt != packet.payload_type => {
+                if !std::mem::replace(&mut self.warned_payload_type, true) {
+                    tracing::warn!(
+                        expected = pt,
+                        got = packet.payload_type,
+                        "ka9q: dropping packets with an unexpected RTP payload type (channel encoding changed?)"
+                    );
+                }
+                return 0;
+            }
+            Some(_) => {}
+            None => self.payload_type = Some(packet.payload_type),
+        }
+
+        let frame_bytes = self.layout.frame_bytes();
+        if packet.payload.len() % frame_bytes != 0 {
+            if !std::mem::replace(&mut self.warned_payload_len, true) {
+                tracing::warn!(
+                    len = packet.payload.len(),
+                    frame_bytes,
+                    "ka9q: dropping packets whose payload is not a whole number of frames (check format)"
+                );
+            }
+            return 0;
+        }
+        let frames = packet.payload.len() / frame_bytes;
+
+        let mut gap = 0usize;
+        if let Some(expected) = self.next_timestamp {
+            let step = packet.timestamp.wrapping_sub(expected) as i32;
+            if step < 0 && step.unsigned_abs() <= self.max_clock_step {
+                // Duplicate or reordered packet; its slot was already filled.
+                return 0;
+            }
+            if step > 0 && step.unsigned_abs() <= self.max_clock_step {
+                gap = step as usize;
+            } else if step != 0 {
+                tracing::info!(step, "ka9q: RTP clock jumped; restarting the sample clock");
+            }
+        }
+        self.next_timestamp = Some(packet.timestamp.wrapping_add(frames as u32));
+
+        out.resize(out.len() + gap * frame_bytes, 0);
+        let n = self.layout.sample_bytes;
+        if self.layout.swap {
+            out.extend(
+                packet
+                    .payload
+                    .chunks_exact(n)
+                    .flat_map(|s| s.iter().rev().copied()),
+            );
+        } else {
+            out.extend_from_slice(packet.payload);
+        }
+        gap + frames
+    }
+}
+
+/// Warns once when the stream's sample rate is far from `receiver.input.sps`, which would put
+/// every frequency on the waterfall off by the same ratio.
+struct RateMonitor {
+    sps: f64,
+    started: Option<Instant>,
+    frames: u64,
+    warned: bool,
+}
+
+impl RateMonitor {
+    fn new(sps: i64) -> Self {
+        Self {
+            sps: sps as f64,
+            started: None,
+            frames: 0,
+            warned: false,
+        }
+    }
+
+    fn record(&mut self, frames: usize) {
+        let Some(started) = self.started else {
+            // Start timing after the first packet so socket setup is not counted.
+            self.started = Some(Instant::now());
+            return;
+        };
+        self.frames += frames as u64;
+        let elapsed = started.elapsed();
+        if elapsed < RATE_CHECK_INTERVAL {
+            return;
+        }
+        let measured = self.frames as f64 / elapsed.as_secs_f64();
+        if !self.warned && (measured - self.sps).abs() > self.sps * RATE_TOLERANCE {
+            self.warned = true;
+            tracing::warn!(
+                measured_sps = measured.round(),
+                configured_sps = self.sps,
+                "ka9q: stream sample rate does not match receiver.input.sps"
+            );
+        }
+        self.started = Some(Instant::now());
+        self.frames = 0;
+    }
+}
+
+/// Blocking `Read` over the depacketized stream, matching the behavior of stdin/pipe inputs.
+struct Ka9qRead {
+    socket: UdpSocket,
+    group: SocketAddr,
+    stop_requested: Arc<AtomicBool>,
+    depacketizer: Depacketizer,
+    rate: RateMonitor,
+    datagram: Vec<u8>,
+    pending: Vec<u8>,
+    read_pos: usize,
+    /// No packet arrived within the last read timeout.
+    stalled: bool,
+}
+
+impl Ka9qRead {
+    /// Waits for the next packet of our stream and appends its frames to `pending`.
+    fn refill(&mut self) -> std::io::Result<()> {
+        loop {
+            if self.stop_requested.load(Ordering::Relaxed)
+                || crate::shutdown::is_shutdown_requested()
+            {
+                return Err(std::io::Error::other("shutdown"));
+            }
+            match self.socket.recv(&mut self.datagram) {
+                Ok(n) => {
+                    let frames = self
+                        .depacketizer
+                        .push(&self.datagram[..n], &mut self.pending);
+                    if frames == 0 {
+                        continue;
+                    }
+                    if std::mem::take(&mut self.stalled) {
+                        tracing::info!(group = %self.group, "ka9q: RTP stream resumed");
+                    }
+                    self.rate.record(frames);
+                    return Ok(());
+                }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock
+                            | std::io::ErrorKind::TimedOut
+                            | std::io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    if !std::mem::replace(&mut self.stalled, true) {
+                        tracing::warn!(group = %self.group, "ka9q: no RTP packets received");
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Read for Ka9qRead {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+        if self.read_pos >= self.pending.len() {
+            self.pending.clear();
+            self.read_pos = 0;
+            self.refill()?;
+        }
+        let to_copy = (self.pending.len() - self.read_pos).min(out.len());
+        out[..to_copy].copy_from_slice(&self.pending[self.read_pos..self.read_pos + to_copy]);
+        self.read_pos += to_copy;
+        Ok(to_copy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SSRC: u32 = 0x1234_5678;
+
+    fn rtp(payload_type: u8, timestamp: u32, ssrc: u32, payload: &[u8]) -> Vec<u8> {
+        let mut p = vec![0x80, payload_type];
+        p.extend_from_slice(&0u16.to_be_bytes());
+        p.extend_from_slice(&timestamp.to_be_bytes());
+        p.extend_from_slice(&ssrc.to_be_bytes());
+        p.extend_from_slice(payload);
+        p
+    }
+
+    fn cs16_be() -> Depacketizer {
+        let layout = PayloadLayout::new(SampleFormat::Cs16, None).unwrap();
+        Depacketizer::new(None, None, layout, 1000)
+    }
+
+    fn host_i16(bytes: &[u8]) -> Vec<i16> {
+        bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_ne_bytes([b[0], b[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn parse_rtp_skips_csrcs_extension_and_padding() {
+        let mut p = vec![0xB1, 97];
+        p.extend_from_slice(&7u16.to_be_bytes());
+        p.extend_from_slice(&42u32.to_be_bytes());
+        p.extend_from_slice(&SSRC.to_be_bytes());
+        p.extend_from_slice(&[0; 4]); // CSRC
+        p.extend_from_slice(&[0xBE, 0xDE, 0, 1, 0, 0, 0, 0]); // one-word extension
+        p.extend_from_slice(&[1, 2, 3, 4]);
+        p.extend_from_slice(&[0, 2]); // two bytes of padding
+        assert_eq!(
+            parse_rtp(&p),
+            Some(RtpPacket {
+                payload_type: 97,
+                timestamp: 42,
+                ssrc: SSRC,
+                payload: &[1, 2, 3, 4],
+            })
+        );
+        assert_eq!(parse_rtp(&p[..8]), None);
+    }
+
+    #[test]
+    fn depacketizer_converts_byte_order_and_fills_gaps() {
+        let mut d = cs16_be();
+        let mut out = Vec::new();
+        let frame = [0x01, 0x02, 0xFF, 0xFE];
+        assert_eq!(d.push(&rtp(97, 100, SSRC, &frame), &mut out), 1);
+        assert_eq!(host_i16(&out), [0x0102, -2]);
+
+        // Timestamps 101 and 102 were lost: two silent frames keep the clock aligned.
+        out.clear();
+        assert_eq!(d.push(&rtp(97, 103, SSRC, &frame), &mut out), 3);
+        assert_eq!(host_i16(&out), [0, 0, 0, 0, 0x0102, -2]);
+
+        // A late duplicate is dropped; other channels and payload types are ignored.
+        out.clear();
+        assert_eq!(d.push(&rtp(97, 101, SSRC, &frame), &mut out), 0);
+        assert_eq!(d.push(&rtp(97, 104, SSRC + 1, &frame), &mut out), 0);
+        assert_eq!(d.push(&rtp(98, 104, SSRC, &frame), &mut out), 0);
+        assert_eq!(d.push(&rtp(97, 104, SSRC, &frame[..3]), &mut out), 0);
+        assert!(out.is_empty());
+
+        // A jump beyond the fill limit restarts the clock without padding.
+        assert_eq!(d.push(&rtp(97, 50_000, SSRC, &frame), &mut out), 1);
+        assert_eq!(d.push(&rtp(97, 50_001, SSRC, &frame), &mut out), 1);
+    }
+
+    #[test]
+    fn payload_layout_rejects_unsupported_formats() {
+        assert!(PayloadLayout::new(SampleFormat::U8, None).is_err());
+        let f32_le = PayloadLayout::new(SampleFormat::Cf32, None).unwrap();
+        assert_eq!(f32_le.frame_bytes(), 8);
+        assert_eq!(f32_le.swap, cfg!(target_endian = "big"));
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::Context;
+use novasdr_core::config::{ReceiverInput, Rx888Driver, SignalType};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+pub fn open(
+    driver: &Rx888Driver,
+    input: &ReceiverInput,
+    stop_requested: Arc<AtomicBool>,
+) -> anyhow::Result<Box<dyn Read + Send>> {
+    anyhow::ensure!(
+        input.signal == SignalType::Real,
+        "rx888 delivers real ADC samples; set receiver.input.signal = \"real\""
+    );
+    let mut child = Command::new(&driver.command)
+        .args(&driver.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("start rx888 capture program {:?}", driver.command))?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("rx888 capture program has no stdout")?;
+    if let Some(stderr) = child.stderr.take() {
+        let command = driver.command.clone();
+        std::thread::Builder::new()
+            .name("novasdr-rx888-log".to_string())
+            .spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    tracing::info!(command = %command, "rx888: {line}");
+                }
+            })
+            .context("spawn rx888 log thread")?;
+    }
+    tracing::info!(
+        command = %driver.command,
+        pid = child.id(),
+        randomizer = driver.randomizer,
+        "rx888: capture program started"
+    );
+    Ok(Box::new(Rx888Read {
+        child,
+        stdout,
+        stop_requested,
+        derandomizer: driver.randomizer.then(Derandomizer::default),
+    }))
+}
+
+struct Rx888Read {
+    child: Child,
+    stdout: ChildStdout,
+    stop_requested: Arc<AtomicBool>,
+    derandomizer: Option<Derandomizer>,
+}
+
+impl Read for Rx888Read {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.stop_requested.load(Ordering::Relaxed) {
+            return Ok(0);
+        }
+        let n = self.stdout.read(buf)?;
+        if n == 0 {
+            match self.child.try_wait() {
+                Ok(Some(status)) => tracing::warn!(%status, "rx888: capture program exited"),
+                _ => tracing::warn!("rx888: capture program closed its output"),
+            }
+        }
+        if let Some(d) = self.derandomizer.as_mut() {
+            d.apply(&mut buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+impl Drop for Rx888Read {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Undoes the ADC randomizer on little-endian `s16` bytes: a sample with its LSB set has all
+/// other bits inverted. Reads may end mid-sample, so the byte position carries over.
+#[derive(Default)]
+struct Derandomizer {
+    /// The next byte is a high byte.
+    high: bool,
+    /// The low byte before it had its LSB set.
+    invert: bool,
+}
+
+impl Derandomizer {
+    fn apply(&mut self, bytes: &mut [u8]) {
+        for b in bytes {
+            if self.high {
+                if self.invert {
+                    *b ^= 0xff;
+                }
+            } else {
+                self.invert = *b & 1 != 0;
+                if self.invert {
+                    *b ^= 0xfe;
+                }
+            }
+            self.high = !self.high;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derandomizer_inverts_odd_samples_across_split_reads() {
+        let randomized: [i16; 5] = [0x1234, 0x1235, -2, -1, 1];
+        let mut bytes: Vec<u8> = randomized.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut d = Derandomizer::default();
+        let (a, b) = bytes.split_at_mut(3);
+        d.apply(a);
+        d.apply(b);
+        let plain: Vec<i16> = bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(plain, vec![0x1234, -0x1235, -2, 1, -1]);
+    }
+}
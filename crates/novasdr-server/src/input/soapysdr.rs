@@ -1,9 +1,191 @@
 use anyhow::Context;
-use novasdr_core::config::{ReceiverInput, SampleFormat, SignalType, SoapySdrDriver};
+use novasdr_core::config::{
+    ReceiverInput, SampleFormat, SignalType, SoapySdrDriver, BIAS_TEE_SETTINGS,
+};
 use soapysdr::StreamSample;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Read;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+use std::time::{Duration, Instant};
+
+/// No samples for this long means the device is gone: an unplugged dongle often just stops
+/// delivering, without the driver reporting an error.
+const STALL_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often an absent device is looked for while waiting for it to be plugged back in.
+const PRESENCE_POLL: Duration = Duration::from_secs(1);
+/// How long one open waits for an absent device before it counts as failed.
+const PRESENCE_WAIT: Duration = Duration::from_secs(30);
+
+/// Serial of the device each receiver first opened for its `device` string. Reopens ask for
+/// that serial, so a replugged dongle is found again even when `device` names none, and another
+/// dongle of the same kind is never picked up in its place.
+static SERIALS: OnceLock<Mutex<HashMap<(String, String), String>>> = OnceLock::new();
+
+fn serials() -> MutexGuard<'static, HashMap<(String, String), String>> {
+    lock(SERIALS.get_or_init(Default::default), "serials")
+}
+
+/// Open devices by serial (or by device string when the driver reports none). Receivers that
+/// name the same device each stream one of its channels through this one handle: most
+/// multi-channel devices (LimeSDR, USRP B210) cannot be opened twice.
+static DEVICES: OnceLock<Mutex<HashMap<String, Weak<SharedDevice>>>> = OnceLock::new();
+
+/// Device and channel each receiver streams from, for changes while it runs.
+static STREAMING: OnceLock<Mutex<HashMap<String, (Weak<SharedDevice>, usize)>>> = OnceLock::new();
+
+/// Settings changed through the admin API, by receiver.
+static OVERRIDES: OnceLock<Mutex<HashMap<String, Overrides>>> = OnceLock::new();
+
+/// Each value is kept with the configured one it replaced: reopens keep it until a reload
+/// configures another value.
+#[derive(Default)]
+struct Overrides {
+    ppm_correction: Option<(Option<f64>, f64)>,
+    gain: Option<(Option<f64>, f64)>,
+    antenna: Option<(Option<String>, String)>,
+    /// With the configured value of the bias tee setting it replaced.
+    bias_tee: Option<(Option<String>, bool)>,
+}
+
+fn overrides() -> MutexGuard<'static, HashMap<String, Overrides>> {
+    lock(OVERRIDES.get_or_init(Default::default), "overrides")
+}
+
+/// `set` while it still replaces `configured`, else `configured`.
+fn overridden(set: Option<(Option<f64>, f64)>, configured: Option<f64>) -> Option<f64> {
+    match set {
+        Some((replaced, value)) if replaced == configured => Some(value),
+        _ => configured,
+    }
+}
+
+fn lock<'a, T>(mutex: &'a Mutex<T>, what: &str) -> MutexGuard<'a, T> {
+    match mutex.lock() {
+        Ok(g) => g,
+        Err(poisoned) => {
+            tracing::error!("SoapySDR {what} mutex poisoned; recovering");
+            poisoned.into_inner()
+        }
+    }
+}
+
+/// A device handle shared by the receivers streaming its channels.
+struct SharedDevice {
+    device: soapysdr::Device,
+    /// Set by the receiver that opened the device; the channels of a device share their ADC
+    /// clock, so every other receiver has to ask for the same rate.
+    sample_rate: i64,
+    channels: Mutex<HashSet<usize>>,
+    /// Set when a stream of the device failed, so reopens get a fresh handle rather than one of
+    /// a device that is gone, while receivers still holding it run into the failure themselves.
+    failed: AtomicBool,
+}
+
+/// The open device for `key`, or `args` opened now; `channel` is claimed until the returned
+/// claim is dropped.
+fn shared_device(
+    receiver_id: &str,
+    key: &str,
+    args: &str,
+    channel: usize,
+    sample_rate: i64,
+) -> anyhow::Result<ChannelClaim> {
+    let mut devices = lock(DEVICES.get_or_init(Default::default), "devices");
+    devices.retain(|_, dev| dev.strong_count() > 0);
+    let open = devices
+        .get(key)
+        .and_then(Weak::upgrade)
+        .filter(|dev| !dev.failed.load(Ordering::Relaxed));
+    let device = match open {
+        Some(device) => {
+            anyhow::ensure!(
+                device.sample_rate == sample_rate,
+                "SoapySDR device {args:?} already runs at {} sps for another receiver",
+                device.sample_rate
+            );
+            tracing::info!(
+                receiver_id,
+                device = args,
+                channel,
+                "sharing open SoapySDR device"
+            );
+            device
+        }
+        None => {
+            let device = Arc::new(SharedDevice {
+                device: soapysdr::Device::new(args).context("open SoapySDR device")?,
+                sample_rate,
+                channels: Mutex::new(HashSet::new()),
+                failed: AtomicBool::new(false),
+            });
+            devices.insert(key.to_string(), Arc::downgrade(&device));
+            device
+        }
+    };
+    anyhow::ensure!(
+        lock(&device.channels, "channels").insert(channel),
+        "channel {channel} of SoapySDR device {args:?} is already streamed by another receiver"
+    );
+    Ok(ChannelClaim { device, channel })
+}
+
+/// One channel of a [`SharedDevice`], released when the receiver's stream is dropped.
+struct ChannelClaim {
+    device: Arc<SharedDevice>,
+    channel: usize,
+}
+
+impl Drop for ChannelClaim {
+    fn drop(&mut self) {
+        lock(&self.device.channels, "channels").remove(&self.channel);
+    }
+}
+
+/// `device` narrowed to `serial`, unless it names a serial already.
+fn device_args(device: &str, serial: Option<&str>) -> String {
+    let names_serial = device
+        .split(',')
+        .any(|kv| kv.split('=').next().is_some_and(|k| k.trim() == "serial"));
+    match serial {
+        Some(serial) if !names_serial => {
+            if device.trim().is_empty() {
+                format!("serial={serial}")
+            } else {
+                format!("{device},serial={serial}")
+            }
+        }
+        _ => device.to_string(),
+    }
+}
+
+/// The first device matching `args`, polling for up to [`PRESENCE_WAIT`] while there is none.
+fn wait_for_device(receiver_id: &str, args: &str) -> anyhow::Result<soapysdr::Args> {
+    let until = Instant::now() + PRESENCE_WAIT;
+    let mut waited = false;
+    loop {
+        let found = soapysdr::enumerate(args).context("enumerate SoapySDR devices")?;
+        if let Some(found) = found.into_iter().next() {
+            if waited {
+                tracing::info!(receiver_id, device = args, "SoapySDR device is back");
+            }
+            return Ok(found);
+        }
+        if !waited {
+            tracing::warn!(
+                receiver_id,
+                device = args,
+                "SoapySDR device not present; waiting for it to be plugged in"
+            );
+            waited = true;
+        }
+        anyhow::ensure!(
+            Instant::now() < until && !crate::shutdown::is_shutdown_requested(),
+            "SoapySDR device {args:?} not present"
+        );
+        std::thread::sleep(PRESENCE_POLL);
+    }
+}
 
 fn to_stream_args(driver: &SoapySdrDriver) -> anyhow::Result<soapysdr::Args> {
     let mut args = soapysdr::Args::new();
@@ -22,6 +204,7 @@ fn to_stream_args(driver: &SoapySdrDriver) -> anyhow::Result<soapysdr::Args> {
 }
 
 pub fn open(
+    receiver_id: &str,
     driver: &SoapySdrDriver,
     input: &ReceiverInput,
     stop_requested: Arc<AtomicBool>,
@@ -32,18 +215,259 @@ pub fn open(
         "soapysdr input currently requires receiver.input.signal = \"iq\""
     );
 
+    // Waited for outside the lock, so an unplugged device does not hold up other receivers.
+    let key = (receiver_id.to_string(), driver.device.clone());
+    let pinned = serials().get(&key).cloned();
+    let found = wait_for_device(receiver_id, &device_args(&driver.device, pinned.as_deref()))?;
+    let serial = found.get("serial").map(str::to_string);
+    let args = device_args(&driver.device, pinned.as_deref().or(serial.as_deref()));
+    let device_key = pinned
+        .clone()
+        .or_else(|| serial.clone())
+        .unwrap_or_else(|| args.clone());
+
     // This lock is used to create one SoapySdr device at a time.
     // Otherwise, multiple soapy devices are created in parallel using multiple threads.
     // This leads to the appearance of errors that are difficult to reproduce and debug.
     let _guard = soapy_semaphore.lock();
 
-    match driver.format {
-        SampleFormat::Cs16 => open_fmt::<num_complex::Complex<i16>>(driver, input, stop_requested),
-        SampleFormat::Cf32 => open_fmt::<num_complex::Complex<f32>>(driver, input, stop_requested),
+    let claim = shared_device(receiver_id, &device_key, &args, driver.channel, input.sps)?;
+    if let Some(ppm) = set_correction(receiver_id, driver) {
+        apply_ppm_correction(&claim.device.device, driver.channel, ppm)?;
+    }
+    let device = claim.device.clone();
+    lock(STREAMING.get_or_init(Default::default), "streaming").insert(
+        receiver_id.to_string(),
+        (Arc::downgrade(&device), driver.channel),
+    );
+    let reader = match driver.format {
+        SampleFormat::Cs16 => {
+            open_fmt::<num_complex::Complex<i16>>(claim, driver, input, stop_requested)?
+        }
+        SampleFormat::Cf32 => {
+            open_fmt::<num_complex::Complex<f32>>(claim, driver, input, stop_requested)?
+        }
         other => anyhow::bail!(
             "soapysdr input only supports format \"cs16\" or \"cf32\" (got {other:?})"
         ),
+    };
+    // After `gain` and `gains` of the config, which an auto gain result replaces.
+    if let Some(gain) = set_gain(receiver_id, driver) {
+        device
+            .device
+            .set_gain(soapysdr::Direction::Rx, driver.channel, gain)
+            .context("set SoapySDR gain")?;
     }
+    if let Some(antenna) = set_antenna(receiver_id, driver) {
+        device
+            .device
+            .set_antenna(soapysdr::Direction::Rx, driver.channel, antenna.as_str())
+            .context("set SoapySDR RX antenna")?;
+    }
+    if overrides()
+        .get(receiver_id)
+        .is_some_and(|o| o.bias_tee.is_some())
+    {
+        if let Some(key) = bias_tee_setting(&device.device, driver) {
+            if let Some(on) = set_bias_tee(receiver_id, driver, &key) {
+                write_bias_tee(&device.device, &key, on)?;
+            }
+        }
+    }
+    if let (None, Some(serial)) = (pinned, serial) {
+        tracing::info!(receiver_id, serial = %serial, "SoapySDR device opened; reopens use its serial");
+        serials().insert(key, serial);
+    }
+    Ok(reader)
+}
+
+/// Correction set for `receiver_id` through the admin API, else the configured one.
+fn set_correction(receiver_id: &str, driver: &SoapySdrDriver) -> Option<f64> {
+    let set = overrides().get(receiver_id).and_then(|o| o.ppm_correction);
+    overridden(set, driver.ppm_correction)
+}
+
+/// Gain set for `receiver_id` through the admin API; `None` leaves the configured gains.
+fn set_gain(receiver_id: &str, driver: &SoapySdrDriver) -> Option<f64> {
+    match overrides().get(receiver_id).and_then(|o| o.gain) {
+        Some((replaced, gain)) if replaced == driver.gain => Some(gain),
+        _ => None,
+    }
+}
+
+/// Antenna chosen for `receiver_id` through the admin API; `None` leaves the configured one.
+fn set_antenna(receiver_id: &str, driver: &SoapySdrDriver) -> Option<String> {
+    match overrides().get(receiver_id).and_then(|o| o.antenna.clone()) {
+        Some((replaced, antenna)) if replaced == driver.antenna => Some(antenna),
+        _ => None,
+    }
+}
+
+/// Bias tee state set for `receiver_id` through the admin API, while `settings` still
+/// configures `key` as it did then; `None` leaves the configured settings.
+fn set_bias_tee(receiver_id: &str, driver: &SoapySdrDriver, key: &str) -> Option<bool> {
+    let (replaced, on) = overrides()
+        .get(receiver_id)
+        .and_then(|o| o.bias_tee.clone())?;
+    (replaced.as_ref() == driver.settings.get(key)).then_some(on)
+}
+
+/// The device setting that switches the bias tee: `bias_tee_setting`, else the first of
+/// [`BIAS_TEE_SETTINGS`] the device reports.
+fn bias_tee_setting(device: &soapysdr::Device, driver: &SoapySdrDriver) -> Option<String> {
+    if let Some(key) = driver.bias_tee_setting.as_ref() {
+        return Some(key.clone());
+    }
+    // Drivers answer unknown settings with an empty string.
+    BIAS_TEE_SETTINGS
+        .iter()
+        .find(|key| device.read_setting(**key).is_ok_and(|v| !v.is_empty()))
+        .map(|key| key.to_string())
+}
+
+fn write_bias_tee(device: &soapysdr::Device, key: &str, on: bool) -> anyhow::Result<()> {
+    device
+        .write_setting(key, if on { "true" } else { "false" })
+        .with_context(|| format!("write SoapySDR setting {key:?}"))
+}
+
+/// Antennas and bias tee of `receiver_id`'s running input.
+pub fn antenna_state(
+    receiver_id: &str,
+    driver: &SoapySdrDriver,
+) -> anyhow::Result<super::AntennaState> {
+    let (device, channel) = streaming_device(receiver_id).context("input is not running")?;
+    let device = &device.device;
+    let direction = soapysdr::Direction::Rx;
+    let bias_tee_setting = bias_tee_setting(device, driver);
+    let bias_tee = match bias_tee_setting.as_deref() {
+        Some(key) => {
+            let value = device
+                .read_setting(key)
+                .with_context(|| format!("read SoapySDR setting {key:?}"))?;
+            Some(matches!(value.trim(), "true" | "1"))
+        }
+        None => None,
+    };
+    Ok(super::AntennaState {
+        antenna: device
+            .antenna(direction, channel)
+            .context("query SoapySDR RX antenna")?,
+        antennas: device
+            .antennas(direction, channel)
+            .context("list SoapySDR RX antennas")?,
+        bias_tee,
+        bias_tee_setting,
+    })
+}
+
+/// Switches `receiver_id`'s running input to `antenna` and keeps it for reopens.
+pub fn set_antenna_name(
+    receiver_id: &str,
+    driver: &SoapySdrDriver,
+    antenna: &str,
+) -> anyhow::Result<()> {
+    let (device, channel) = streaming_device(receiver_id).context("input is not running")?;
+    device
+        .device
+        .set_antenna(soapysdr::Direction::Rx, channel, antenna)
+        .context("set SoapySDR RX antenna")?;
+    overrides()
+        .entry(receiver_id.to_string())
+        .or_default()
+        .antenna = Some((driver.antenna.clone(), antenna.to_string()));
+    Ok(())
+}
+
+/// Powers the bias tee of `receiver_id`'s running input on or off and keeps that for reopens.
+pub fn set_bias_tee_on(receiver_id: &str, driver: &SoapySdrDriver, on: bool) -> anyhow::Result<()> {
+    let (device, _) = streaming_device(receiver_id).context("input is not running")?;
+    let key = bias_tee_setting(&device.device, driver).context("the device has no bias tee")?;
+    write_bias_tee(&device.device, &key, on)?;
+    let configured = driver.settings.get(&key).cloned();
+    overrides()
+        .entry(receiver_id.to_string())
+        .or_default()
+        .bias_tee = Some((configured, on));
+    Ok(())
+}
+
+/// Overall gain range of `receiver_id`'s running input, in dB.
+pub fn gain_range(receiver_id: &str) -> anyhow::Result<(f64, f64)> {
+    let (device, channel) = streaming_device(receiver_id).context("input is not running")?;
+    let range = device
+        .device
+        .gain_range(soapysdr::Direction::Rx, channel)
+        .context("query SoapySDR gain range")?;
+    Ok((range.minimum, range.maximum))
+}
+
+/// Sets the overall gain of `receiver_id`'s running input and keeps it for reopens.
+pub fn set_gain_db(receiver_id: &str, driver: &SoapySdrDriver, gain: f64) -> anyhow::Result<()> {
+    let (device, channel) = streaming_device(receiver_id).context("input is not running")?;
+    device
+        .device
+        .set_gain(soapysdr::Direction::Rx, channel, gain)
+        .context("set SoapySDR gain")?;
+    overrides().entry(receiver_id.to_string()).or_default().gain = Some((driver.gain, gain));
+    Ok(())
+}
+
+/// Frequency correction `receiver_id`'s input runs with, in ppm.
+pub fn ppm_correction(receiver_id: &str, driver: &SoapySdrDriver) -> f64 {
+    set_correction(receiver_id, driver).unwrap_or(0.0)
+}
+
+/// Applies `ppm` to the running input of `receiver_id` and keeps it for reopens. Without a
+/// running input it only takes effect at the next open.
+pub fn set_ppm_correction(
+    receiver_id: &str,
+    driver: &SoapySdrDriver,
+    ppm: f64,
+) -> anyhow::Result<()> {
+    if let Some((device, channel)) = streaming_device(receiver_id) {
+        apply_ppm_correction(&device.device, channel, ppm)?;
+    }
+    overrides()
+        .entry(receiver_id.to_string())
+        .or_default()
+        .ppm_correction = Some((driver.ppm_correction, ppm));
+    Ok(())
+}
+
+/// Current readings of `driver.reference_sensors` on `receiver_id`'s running input.
+pub fn reference_sensors(
+    receiver_id: &str,
+    driver: &SoapySdrDriver,
+) -> anyhow::Result<BTreeMap<String, String>> {
+    let (device, _) = streaming_device(receiver_id).context("input is not running")?;
+    let mut readings = BTreeMap::new();
+    for name in driver.reference_sensors.iter() {
+        anyhow::ensure!(
+            !name.contains('\0'),
+            "soapysdr sensor name must not contain NUL"
+        );
+        let value = device
+            .device
+            .read_sensor(name.as_str())
+            .with_context(|| format!("read SoapySDR sensor {name:?}"))?;
+        readings.insert(name.clone(), value);
+    }
+    Ok(readings)
+}
+
+fn streaming_device(receiver_id: &str) -> Option<(Arc<SharedDevice>, usize)> {
+    let streaming = lock(STREAMING.get_or_init(Default::default), "streaming");
+    let (device, channel) = streaming.get(receiver_id)?;
+    Some((device.upgrade()?, *channel))
+}
+
+/// Sets the device's frequency correction through its `CORR` tuning component, in ppm; this is
+/// what SoapySDR's frequency-correction call resolves to for drivers such as RTL-SDR.
+fn apply_ppm_correction(device: &soapysdr::Device, channel: usize, ppm: f64) -> anyhow::Result<()> {
+    device
+        .set_component_frequency(soapysdr::Direction::Rx, channel, "CORR", ppm, ())
+        .context("set SoapySDR frequency correction (the device may not support one)")
 }
 
 fn apply_gain_and_settings(
@@ -110,6 +534,7 @@ fn apply_gain_and_settings(
 }
 
 fn open_fmt<E>(
+    claim: ChannelClaim,
     driver: &SoapySdrDriver,
     input: &ReceiverInput,
     stop_requested: Arc<AtomicBool>,
@@ -117,7 +542,7 @@ fn open_fmt<E>(
 where
     E: StreamSample + Copy + Default + Send + 'static,
 {
-    let device = soapysdr::Device::new(driver.device.as_str()).context("open SoapySDR device")?;
+    let device = &claim.device.device;
 
     if let Some(ant) = driver.antenna.as_deref() {
         device
@@ -137,7 +562,7 @@ where
         )
         .context("set SoapySDR frequency")?;
 
-    apply_gain_and_settings(driver, &device)?;
+    apply_gain_and_settings(driver, device)?;
 
     let stream_args = to_stream_args(driver).context("build SoapySDR stream args")?;
     let mut stream = device
@@ -151,6 +576,7 @@ where
     // SoapySDR will fill what it can per read; we accumulate until the caller is satisfied.
     Ok(Box::new(SoapyRead::new(
         stream,
+        claim,
         driver.rx_buffer_samples,
         stop_requested,
     )))
@@ -161,6 +587,7 @@ where
 /// returns 0 (which would signal EOF to `read_exact`).
 struct SoapyRead<T: soapysdr::StreamSample> {
     stream: soapysdr::RxStream<T>,
+    claim: ChannelClaim,
     stop_requested: Arc<AtomicBool>,
     /// Internal sample buffer; we read from SoapySDR into this, then serve bytes to callers.
     buf: Vec<T>,
@@ -168,20 +595,25 @@ struct SoapyRead<T: soapysdr::StreamSample> {
     read_pos: usize,
     /// Valid data length in `buf`, measured in bytes.
     data_len: usize,
+    /// When the stream last delivered anything, for [`STALL_TIMEOUT`].
+    last_samples: Instant,
 }
 
 impl<T: soapysdr::StreamSample + Copy + Default> SoapyRead<T> {
     fn new(
         stream: soapysdr::RxStream<T>,
+        claim: ChannelClaim,
         buf_samples: usize,
         stop_requested: Arc<AtomicBool>,
     ) -> Self {
         Self {
             stream,
+            claim,
             stop_requested,
             buf: vec![T::default(); buf_samples.max(1024)],
             read_pos: 0,
             data_len: 0,
+            last_samples: Instant::now(),
         }
     }
 
@@ -197,12 +629,23 @@ impl<T: soapysdr::StreamSample + Copy + Default> SoapyRead<T> {
             {
                 return Err(std::io::Error::new(std::io::ErrorKind::Other, "shutdown"));
             }
+            if self.last_samples.elapsed() >= STALL_TIMEOUT {
+                self.claim.device.failed.store(true, Ordering::Relaxed);
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!(
+                        "no samples from SoapySDR for {} s (device unplugged?)",
+                        STALL_TIMEOUT.as_secs()
+                    ),
+                ));
+            }
             let mut bufs = [self.buf.as_mut_slice()];
             // Long timeout (1 second) to avoid busy-spinning; SoapySDR returns early when data arrives.
             match self.stream.read(&mut bufs, 1_000_000) {
                 Ok(n) if n > 0 => {
                     self.data_len = (n as usize) * std::mem::size_of::<T>();
                     self.read_pos = 0;
+                    self.last_samples = Instant::now();
                     return Ok(());
                 }
                 Ok(_) => {
@@ -214,11 +657,13 @@ impl<T: soapysdr::StreamSample + Copy + Default> SoapyRead<T> {
                         std::thread::sleep(std::time::Duration::from_micros(500));
                     }
                     soapysdr::ErrorCode::Overflow => {
+                        self.last_samples = Instant::now();
                         if !WARNED_OVERFLOW.swap(true, Ordering::Relaxed) {
                             tracing::warn!(error = ?e, "SoapySDR RX overflow (samples dropped)");
                         }
                     }
                     soapysdr::ErrorCode::Corruption => {
+                        self.last_samples = Instant::now();
                         if !WARNED_CORRUPTION.swap(true, Ordering::Relaxed) {
                             tracing::warn!(error = ?e, "SoapySDR RX corruption (samples dropped)");
                         }
@@ -229,6 +674,7 @@ impl<T: soapysdr::StreamSample + Copy + Default> SoapyRead<T> {
                         }
                     }
                     _ => {
+                        self.claim.device.failed.store(true, Ordering::Relaxed);
                         return Err(std::io::Error::new(
                             std::io::ErrorKind::Other,
                             format!("soapysdr read: {e:?}"),
@@ -265,3 +711,128 @@ impl<T: soapysdr::StreamSample + Copy + Default> Read for SoapyRead<T> {
         Ok(to_copy)
     }
 }
+
+/// TX stream on the device of a receiver's running input; see [`super::Transmitter`].
+#[cfg(feature = "tx")]
+pub struct SoapyTransmitter {
+    device: Arc<SharedDevice>,
+    channel: usize,
+    stream: soapysdr::TxStream<num_complex::Complex<f32>>,
+    ptt_setting: Option<String>,
+    keyed: bool,
+}
+
+#[cfg(feature = "tx")]
+impl SoapyTransmitter {
+    pub fn open(receiver_id: &str, tx: &novasdr_core::config::Transmit) -> anyhow::Result<Self> {
+        let (device, _) = streaming_device(receiver_id).context("input is not running")?;
+        let dev = &device.device;
+        let direction = soapysdr::Direction::Tx;
+        anyhow::ensure!(
+            tx.channel
+                < dev
+                    .num_channels(direction)
+                    .context("count SoapySDR TX channels")?,
+            "the device has no TX channel {}",
+            tx.channel
+        );
+        if let Some(antenna) = tx.antenna.as_deref() {
+            dev.set_antenna(direction, tx.channel, antenna)
+                .context("set SoapySDR TX antenna")?;
+        }
+        dev.set_sample_rate(direction, tx.channel, f64::from(tx.sample_rate))
+            .context("set SoapySDR TX sample rate")?;
+        let stream = dev
+            .tx_stream::<num_complex::Complex<f32>>(&[tx.channel])
+            .context("create SoapySDR TX stream")?;
+        let transmitter = Self {
+            device: device.clone(),
+            channel: tx.channel,
+            stream,
+            ptt_setting: tx.ptt_setting.clone(),
+            keyed: false,
+        };
+        // Start from the lowest gain; the session raises it within the band's limit.
+        let (min, _) = super::Transmitter::gain_range(&transmitter)?;
+        dev.set_gain(direction, tx.channel, min)
+            .context("set SoapySDR TX gain")?;
+        Ok(transmitter)
+    }
+
+    fn write_ptt(&self, on: bool) -> anyhow::Result<()> {
+        let Some(key) = self.ptt_setting.as_deref() else {
+            return Ok(());
+        };
+        self.device
+            .device
+            .write_setting(key, if on { "true" } else { "false" })
+            .with_context(|| format!("write SoapySDR setting {key:?}"))
+    }
+}
+
+#[cfg(feature = "tx")]
+impl super::Transmitter for SoapyTransmitter {
+    fn tune(&mut self, frequency: f64) -> anyhow::Result<()> {
+        self.device
+            .device
+            .set_frequency(soapysdr::Direction::Tx, self.channel, frequency, ())
+            .context("set SoapySDR TX frequency")
+    }
+
+    fn gain_range(&self) -> anyhow::Result<(f64, f64)> {
+        let range = self
+            .device
+            .device
+            .gain_range(soapysdr::Direction::Tx, self.channel)
+            .context("query SoapySDR TX gain range")?;
+        Ok((range.minimum, range.maximum))
+    }
+
+    fn set_gain(&mut self, gain_db: f64) -> anyhow::Result<()> {
+        self.device
+            .device
+            .set_gain(soapysdr::Direction::Tx, self.channel, gain_db)
+            .context("set SoapySDR TX gain")
+    }
+
+    fn key(&mut self, on: bool) -> anyhow::Result<()> {
+        if on == self.keyed {
+            return Ok(());
+        }
+        // PTT goes on after the stream starts and off before it stops, so the amplifier never
+        // keys into a stream that is not running.
+        if on {
+            self.stream
+                .activate(None)
+                .context("activate SoapySDR TX stream")?;
+            self.keyed = true;
+            if let Err(e) = self.write_ptt(true) {
+                let _ = self.key(false);
+                return Err(e);
+            }
+            return Ok(());
+        }
+        self.keyed = false;
+        let ptt = self.write_ptt(false);
+        self.stream
+            .deactivate(None)
+            .context("deactivate SoapySDR TX stream")?;
+        ptt
+    }
+
+    fn write(&mut self, samples: &[num_complex::Complex32]) -> anyhow::Result<()> {
+        anyhow::ensure!(self.keyed, "not transmitting");
+        self.stream
+            .write_all(&[samples], None, false, 1_000_000)
+            .context("write SoapySDR TX stream")
+    }
+}
+
+#[cfg(feature = "tx")]
+impl Drop for SoapyTransmitter {
+    fn drop(&mut self) {
+        if let Err(e) = super::Transmitter::key(self, false) {
+            tracing::error!(error = ?e, "failed to stop SoapySDR transmission");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use novasdr_core::config::{ReceiverInput, SignalType, SyntheticScenario};
+use novasdr_core::dsp::synthetic::Generator;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Samples generated per block: 10 ms.
+const BLOCKS_PER_SECOND: usize = 100;
+/// How far behind the sample clock generation may fall before it stops catching up.
+const MAX_LAG: Duration = Duration::from_secs(1);
+
+pub fn open(
+    scenario: &SyntheticScenario,
+    input: &ReceiverInput,
+    stop_requested: Arc<AtomicBool>,
+) -> anyhow::Result<Box<dyn Read + Send>> {
+    let is_real = input.signal == SignalType::Real;
+    let sample_rate = usize::try_from(input.sps)?;
+    let generator = Generator::new(scenario, input.frequency, sample_rate, is_real)?;
+    let samples = (sample_rate / BLOCKS_PER_SECOND).max(1);
+    tracing::info!(
+        signals = scenario.signals.len(),
+        noise_bands = scenario.noise_bands.len(),
+        seed = scenario.seed,
+        "synthetic: generating input"
+    );
+    Ok(Box::new(SyntheticRead {
+        generator,
+        block: vec![0.0; if is_real { samples } else { 2 * samples }],
+        bytes: Vec::new(),
+        read: 0,
+        sample_rate: sample_rate as f64,
+        block_samples: samples,
+        started: None,
+        produced: 0,
+        stop_requested,
+    }))
+}
+
+/// Generated samples as native-endian `f32` bytes, delivered at the input's sample rate like
+/// a device would.
+struct SyntheticRead {
+    generator: Generator,
+    block: Vec<f32>,
+    bytes: Vec<u8>,
+    read: usize,
+    sample_rate: f64,
+    block_samples: usize,
+    started: Option<Instant>,
+    /// Samples generated since `started`.
+    produced: u64,
+    stop_requested: Arc<AtomicBool>,
+}
+
+impl SyntheticRead {
+    fn render(&mut self) {
+        let now = Instant::now();
+        let started = *self.started.get_or_insert(now);
+        let due = started + Duration::from_secs_f64(self.produced as f64 / self.sample_rate);
+        if due > now {
+            std::thread::sleep(due - now);
+        } else if now - due > MAX_LAG {
+            // A stalled consumer gets the stream from now on, not a burst of the backlog.
+            tracing::debug!("synthetic: generation fell behind; restarting the clock");
+            self.started = Some(now);
+            self.produced = 0;
+        }
+        self.generator.fill(&mut self.block);
+        self.produced += self.block_samples as u64;
+        self.bytes.clear();
+        self.bytes
+            .extend_from_slice(bytemuck::cast_slice(self.block.as_slice()));
+        self.read = 0;
+    }
+}
+
+impl Read for SyntheticRead {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.stop_requested.load(Ordering::Relaxed) {
+            return Ok(0);
+        }
+        if self.read == self.bytes.len() {
+            self.render();
+        }
+        let n = buf.len().min(self.bytes.len() - self.read);
+        buf[..n].copy_from_slice(&self.bytes[self.read..self.read + n]);
+        self.read += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use novasdr_core::config::ReceiverConfig;
+    use novasdr_core::dsp::sample::SampleReader;
+    use rustfft::num_complex::Complex32;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn receiver_reads_the_scenario_through_the_input() {
+        let receiver: ReceiverConfig = serde_json::from_str(
+            r#"{ "id": "demo", "input": { "sps": 96000, "frequency": 14100000, "signal": "iq",
+              "driver": { "kind": "synthetic", "scenario": { "signals": [
+                { "type": "carrier", "frequency": 14112000, "level_db": -20 } ] } } } }"#,
+        )
+        .unwrap();
+        let (input, name) = crate::input::open(
+            &receiver,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(Mutex::new(())),
+        )
+        .unwrap();
+        assert_eq!(name, "synthetic");
+        let format = receiver.input.driver.get_sample_format();
+        let mut reader = SampleReader::new(input, format);
+        let mut raw = vec![0.0f32; 2 * 4096];
+        reader.read_f32(&mut raw).unwrap();
+
+        let mut spectrum: Vec<Complex32> = raw
+            .chunks_exact(2)
+            .map(|p| Complex32::new(p[0], p[1]))
+            .collect();
+        rustfft::FftPlanner::new()
+            .plan_fft_forward(spectrum.len())
+            .process(&mut spectrum);
+        let peak = (0..spectrum.len())
+            .max_by(|&a, &b| spectrum[a].norm().total_cmp(&spectrum[b].norm()))
+            .unwrap();
+        // 12 kHz above the centre, in 96000 / 4096 Hz bins.
+        assert_eq!(peak, 512);
+    }
+}
@@ -14,6 +14,9 @@ pub struct LoggingConfig {
     pub debug: bool,
     pub log_dir: Option<PathBuf>,
     pub log_file_prefix: String,
+    /// Log to stderr (and print the banner). Off when running as a service, where nothing
+    /// reads the console.
+    pub console: bool,
 }
 
 impl Default for LoggingConfig {
@@ -22,6 +25,7 @@ impl Default for LoggingConfig {
             debug: false,
             log_dir: None,
             log_file_prefix: "novasdr".to_string(),
+            console: true,
         }
     }
 }
@@ -35,15 +39,17 @@ pub fn init(cfg: &LoggingConfig) -> anyhow::Result<LoggingGuards> {
         EnvFilter::new("info")
     };
 
-    let stderr_layer = tracing_subscriber::fmt::layer()
-        .with_target(true)
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .with_ansi(std::io::stderr().is_terminal())
-        .with_writer(std::io::stderr)
-        .with_filter(FilterFn::new(|meta| meta.target() != "novasdr_banner"));
+    let stderr_layer = cfg.console.then(|| {
+        tracing_subscriber::fmt::layer()
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_thread_names(true)
+            .with_ansi(std::io::stderr().is_terminal())
+            .with_writer(std::io::stderr)
+            .with_filter(FilterFn::new(|meta| meta.target() != "novasdr_banner"))
+    });
 
-    let banner_layer = BannerLayer::new();
+    let banner_layer = cfg.console.then(BannerLayer::new);
 
     let (file_layer, file_guard) = match &cfg.log_dir {
         None => (None, None),
@@ -72,8 +78,10 @@ pub fn init(cfg: &LoggingConfig) -> anyhow::Result<LoggingGuards> {
         Some(layer) => registry.with(layer).init(),
     }
 
+    crate::crash::set_default_dir(cfg.log_dir.as_deref());
     std::panic::set_hook(Box::new(|panic_info| {
         tracing::error!(panic = %panic_info, "panic");
+        crate::crash::write_report(panic_info);
     }));
 
     Ok(LoggingGuards {
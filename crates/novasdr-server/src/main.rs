@@ -1,17 +1,63 @@
+mod acars;
+mod admin;
 mod app;
+mod audio_listener;
+mod auth;
+mod auto_gain;
+mod band_schedule;
 mod banner;
 mod benchmark;
+mod black_box;
+mod buffer_pool;
 mod build_info;
+mod capture;
+mod channel_dump;
+mod channels;
 mod cli;
+mod codec_compare;
+mod colormap;
+mod crash;
+mod digital_voice;
+mod disk_guard;
+mod drift;
 mod dsp_runner;
+mod export;
+mod fft_tap;
+mod freqdb;
+mod geoip;
+mod identity;
 mod input;
 mod logging;
+mod marker_suggestions;
+mod markers;
+mod metrics;
 mod overlays;
+mod overload;
+mod probe;
+mod quota;
+mod recordings;
 mod registration;
+mod reload;
+mod replay;
+mod rtl_tcp;
+mod schedule;
+mod service;
 mod setup;
 mod shutdown;
+mod skimmer;
+mod snapshot;
+mod sstv;
 mod state;
+mod stream;
+mod tx;
+mod udp_output;
 mod update_check;
+mod users;
+mod waterfall_compress;
+mod waterfall_dictionary;
+mod waterfall_history;
+mod waterfall_masks;
+mod waterfall_png;
 mod ws;
 
 use anyhow::Context;
@@ -86,9 +132,24 @@ fn main() -> anyhow::Result<()> {
             iterations,
             fftsize,
         }) => return benchmark::run_benchmark(kind, iterations, fftsize),
+        Some(cli::Command::ProbeInput {
+            ref path,
+            ref tcp,
+            seconds,
+        }) => return probe::run(&args, path.as_deref(), tcp.as_deref(), seconds),
         None => {}
     }
 
+    if args.service {
+        service::ensure_supported()?;
+        if args.no_file_log {
+            anyhow::bail!("--service logs only to files; remove --no-file-log");
+        }
+        service::enter_install_dir()?;
+    }
+    // A service has no console to run the setup wizard on.
+    let interactive = !args.service && std::io::stdin().is_terminal();
+
     let config_source = matches.value_source("config");
     let receivers_source = matches.value_source("receivers");
 
@@ -115,12 +176,27 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
+    // Before the files are checked, so a fresh host can start from a snapshot alone.
+    let imported = match args.import_snapshot.as_deref() {
+        Some(archive) => {
+            let paths = state::ConfigPaths {
+                config: config_path.clone(),
+                receivers: receivers_path.clone(),
+                overlays: overlays::overlay_paths_for_config(&config_path).dir,
+            };
+            Some(
+                snapshot::import(archive, &paths)
+                    .with_context(|| format!("import snapshot {}", archive.display()))?,
+            )
+        }
+        None => None,
+    };
+
     let config_exists = config_path.exists();
     let receivers_exists = receivers_path.exists();
     let receivers_has_entries = receivers_exists && receivers_file_has_receivers(&receivers_path);
     let receivers_usable = receivers_exists && receivers_has_entries;
     if !config_exists || !receivers_usable {
-        let interactive = std::io::stdin().is_terminal();
         if !interactive {
             anyhow::bail!(
                 "missing config files: config={}, receivers={} (run `novasdr-server setup` in a terminal)",
@@ -158,12 +234,22 @@ fn main() -> anyhow::Result<()> {
         debug: args.debug,
         log_dir,
         log_file_prefix: "novasdr".to_string(),
+        console: !args.service,
     };
     let _log_guards = logging::init(&log_cfg)?;
 
     banner::log_startup_banner();
     tracing::info!(version = env!("CARGO_PKG_VERSION"), "starting");
 
+    if let Some(imported) = imported {
+        tracing::info!(
+            created = %imported.created,
+            files = ?imported.files,
+            backups = imported.backups,
+            "snapshot imported"
+        );
+    }
+
     if using_legacy_default_paths {
         tracing::warn!(
             config = %config_path.display(),
@@ -183,10 +269,10 @@ fn main() -> anyhow::Result<()> {
                     r.input.audio_compression = config::AudioCompression::Adpcm;
                 }
             }
+            crash::configure(&cfg);
             Arc::new(cfg)
         }
         Err(e) => {
-            let interactive = std::io::stdin().is_terminal();
             if interactive && setup::ask_to_run_setup_for_invalid_config(&args, &e)? {
                 return run_setup(&args, setup::RunMode::Prompted);
             }
@@ -226,19 +312,26 @@ fn main() -> anyhow::Result<()> {
                 }
                 tracing::info!(receiver_id = %r.id, "accelerator: vkfft");
             }
+            config::Accelerator::Auto => {
+                tracing::info!(receiver_id = %r.id, "accelerator: auto (vkfft, clfft, cpu)");
+            }
             config::Accelerator::Unsupported => {
                 anyhow::bail!("receiver {}: unsupported accelerator configured", r.id);
             }
         }
-        if r.input.waterfall_compression != config::WaterfallCompression::Zstd {
-            anyhow::bail!(
-                "receiver {}: only waterfall_compression = \"zstd\" is supported",
-                r.id
-            );
-        }
         match &r.input.driver {
-            config::InputDriver::Stdin { .. } => {}
+            config::InputDriver::Stdin { .. } => {
+                if args.service {
+                    anyhow::bail!(
+                        "receiver {}: input.driver.kind = \"stdin\" has no input when running as a service; use \"fifo\", \"ka9q\", \"rx888\" or \"soapysdr\"",
+                        r.id
+                    );
+                }
+            }
             config::InputDriver::Fifo { .. } => {}
+            config::InputDriver::Ka9q(_)
+            | config::InputDriver::Rx888(_)
+            | config::InputDriver::Synthetic { .. } => {}
             config::InputDriver::SoapySdr(_) => {
                 if !cfg!(feature = "soapysdr") {
                     anyhow::bail!(
@@ -288,41 +381,77 @@ fn main() -> anyhow::Result<()> {
     }
     .max(1);
 
-    tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
-        .worker_threads(worker_threads)
-        .thread_name("novasdr-tokio")
-        .build()
-        .context("build tokio runtime")?
-        .block_on(async move {
-            let state = Arc::new(
-                state::AppState::new(cfg.clone(), resolved_html_root).context("init app state")?,
-            );
-            let active = state.active_receiver_state();
-            tracing::info!(
-                receiver_id = %cfg.active_receiver_id,
-                sps = active.rt.sps,
-                fft_size = active.rt.fft_size,
-                fft_result_size = active.rt.fft_result_size,
-                is_real = active.rt.is_real,
-                basefreq = active.rt.basefreq,
-                total_bandwidth = active.rt.total_bandwidth,
-                audio_max_fft_size = active.rt.audio_max_fft_size,
-                min_waterfall_fft = active.rt.min_waterfall_fft,
-                downsample_levels = active.rt.downsample_levels,
-                "active receiver runtime derived"
-            );
+    let run_server = move || -> anyhow::Result<()> {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .worker_threads(worker_threads)
+            .thread_name("novasdr-tokio")
+            .build()
+            .context("build tokio runtime")?
+            .block_on(async move {
+                let state = Arc::new(
+                    state::AppState::new(
+                        cfg.clone(),
+                        resolved_html_root,
+                        state::ConfigPaths {
+                            config: config_path.clone(),
+                            receivers: receivers_path.clone(),
+                            overlays: overlays::overlay_paths_for_config(&config_path).dir,
+                        },
+                    )
+                    .context("init app state")?,
+                );
+                let active = state.active_receiver_state();
+                tracing::info!(
+                    receiver_id = %cfg.active_receiver_id,
+                    sps = active.rt.sps,
+                    fft_size = active.rt.fft_size,
+                    fft_result_size = active.rt.fft_result_size,
+                    is_real = active.rt.is_real,
+                    basefreq = active.rt.basefreq,
+                    total_bandwidth = active.rt.total_bandwidth,
+                    audio_max_fft_size = active.rt.audio_max_fft_size,
+                    min_waterfall_fft = active.rt.min_waterfall_fft,
+                    downsample_levels = active.rt.downsample_levels,
+                    "active receiver runtime derived"
+                );
 
-            let overlays =
-                overlays::ensure_default_overlays(&config_path).context("ensure overlays")?;
-            state::load_overlays_once(state.clone(), overlays.dir.clone()).await;
-            state::spawn_marker_watcher(state.clone(), overlays.dir.clone());
-            state::spawn_bands_watcher(state.clone(), overlays.dir.clone());
-            state::spawn_header_panel_watcher(state.clone(), overlays.dir);
-            registration::spawn(state.clone());
-            update_check::spawn(state.clone());
-            dsp_runner::start(state.clone()).context("start DSP runner")?;
+                let overlays =
+                    overlays::ensure_default_overlays(&config_path).context("ensure overlays")?;
+                state::load_overlays_once(state.clone(), overlays.dir.clone()).await;
+                marker_suggestions::load(&state).await;
+                state::spawn_overlay_watcher(state.clone(), overlays.dir);
+                reload::spawn_signal_listener(state.clone());
+                registration::spawn(state.clone());
+                update_check::spawn(state.clone());
+                crash::spawn_upload(state.clone());
+                skimmer::pskreporter::spawn(state.clone());
+                skimmer::wsprnet::spawn(state.clone());
+                skimmer::rbn::spawn(state.clone());
+                export::spawn(state.clone());
+                recordings::spawn_retention(state.clone());
+                disk_guard::spawn(state.clone(), log_cfg.log_dir.clone());
+                overload::spawn(state.clone());
+                schedule::spawn(state.clone());
+                band_schedule::apply_initial(&state);
+                band_schedule::spawn(state.clone());
+                dsp_runner::start(state.clone()).context("start DSP runner")?;
+                rtl_tcp::spawn(state.clone());
+                fft_tap::spawn(state.clone());
+                acars::spawn(state.clone());
+                channel_dump::spawn(state.clone());
+                udp_output::spawn(state.clone());
+                black_box::spawn(state.clone());
+                drift::spawn(state.clone());
+                ws::audio_pool::spawn_warmup(state.clone());
 
-            app::serve(state).await
-        })
+                app::serve(state).await
+            })
+    };
+
+    #[cfg(windows)]
+    if args.service {
+        return service::run(run_server);
+    }
+    run_server()
 }
@@ -0,0 +1,330 @@
+use crate::{markers, state::AppState};
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Pending suggestions, next to `markers.json` in the overlays directory.
+pub const FILE_NAME: &str = "marker_suggestions.json";
+/// Queue size; further suggestions are refused until the operator works through it.
+const MAX_PENDING: usize = 200;
+/// Pending suggestions per client IP, so one listener cannot fill the queue by changing the
+/// `user_id` it sends.
+const MAX_PENDING_PER_IP: usize = 5;
+const MAX_NAME_LEN: usize = 40;
+const MAX_MODE_LEN: usize = 8;
+const MAX_USERNAME_LEN: usize = 14;
+
+/// A frequency label proposed by a listener, waiting for the operator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarkerSuggestion {
+    pub id: String,
+    pub frequency: i64,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Client-generated id (the chat `user_id`).
+    pub user_id: String,
+    pub username: String,
+    /// Address of the `/chat` connection, used for the per-submitter limit. Absent in queues
+    /// written by older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<IpAddr>,
+    /// Unix seconds.
+    pub submitted_at: i64,
+}
+
+impl MarkerSuggestion {
+    /// The marker added to `markers.json` on approval.
+    pub fn to_marker(&self) -> serde_json::Value {
+        let mut marker = json!({ "frequency": self.frequency, "name": self.name });
+        if let Some(mode) = &self.mode {
+            marker["mode"] = json!(mode);
+        }
+        marker
+    }
+}
+
+/// Listener input for [`SuggestionQueue::submit`].
+#[derive(Debug, Clone)]
+pub struct NewSuggestion {
+    pub frequency: i64,
+    pub name: String,
+    pub mode: Option<String>,
+    pub user_id: String,
+    pub username: String,
+    pub ip: IpAddr,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SuggestionQueue {
+    #[serde(default)]
+    suggestions: Vec<MarkerSuggestion>,
+}
+
+impl SuggestionQueue {
+    /// Reads the queue file; a missing file is an empty queue.
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let raw = match std::fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        };
+        serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        let value = serde_json::to_value(self).context("serialize marker suggestions")?;
+        novasdr_core::config::write_json_atomic(path, &value)
+    }
+
+    pub fn pending(&self) -> &[MarkerSuggestion] {
+        &self.suggestions
+    }
+
+    /// Validates and queues a suggestion. Errors are short reasons suitable for the listener.
+    pub fn submit(
+        &mut self,
+        new: NewSuggestion,
+        now: i64,
+    ) -> Result<MarkerSuggestion, &'static str> {
+        let name = new.name.trim();
+        if name.is_empty() {
+            return Err("name is required");
+        }
+        if name.chars().count() > MAX_NAME_LEN {
+            return Err("name too long");
+        }
+        if new.frequency <= 0 {
+            return Err("frequency must be positive");
+        }
+        let mode = new
+            .mode
+            .map(|m| m.trim().to_ascii_uppercase())
+            .filter(|m| !m.is_empty());
+        if mode.as_ref().is_some_and(|m| m.len() > MAX_MODE_LEN) {
+            return Err("mode too long");
+        }
+        let user_id = new.user_id.trim();
+        if user_id.is_empty() {
+            return Err("user id is required");
+        }
+        if self.suggestions.len() >= MAX_PENDING {
+            return Err("suggestion queue is full");
+        }
+        if self
+            .suggestions
+            .iter()
+            .filter(|s| s.ip == Some(new.ip))
+            .count()
+            >= MAX_PENDING_PER_IP
+        {
+            return Err("too many pending suggestions");
+        }
+        if self
+            .suggestions
+            .iter()
+            .any(|s| s.frequency == new.frequency && s.name.eq_ignore_ascii_case(name))
+        {
+            return Err("already suggested");
+        }
+
+        let mut username: String = new.username.trim().chars().take(MAX_USERNAME_LEN).collect();
+        if username.is_empty() {
+            username = "user".to_string();
+        }
+        let suggestion = MarkerSuggestion {
+            id: novasdr_core::util::generate_unique_id()[..12].to_string(),
+            frequency: new.frequency,
+            name: name.to_string(),
+            mode,
+            user_id: user_id.to_string(),
+            username,
+            ip: Some(new.ip),
+            submitted_at: now,
+        };
+        self.suggestions.push(suggestion.clone());
+        Ok(suggestion)
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<MarkerSuggestion> {
+        let idx = self.suggestions.iter().position(|s| s.id == id)?;
+        Some(self.suggestions.remove(idx))
+    }
+}
+
+pub async fn load(state: &AppState) {
+    let path = state.config_paths.overlays.join(FILE_NAME);
+    match tokio::task::spawn_blocking(move || SuggestionQueue::load(&path)).await {
+        Ok(Ok(queue)) => *state.marker_suggestions.lock().await = queue,
+        Ok(Err(e)) => tracing::warn!(error = ?e, "failed to load marker suggestions"),
+        Err(e) => tracing::warn!(error = ?e, "marker suggestions load task failed"),
+    }
+}
+
+async fn persist(state: &AppState, queue: &SuggestionQueue) -> anyhow::Result<()> {
+    let path = state.config_paths.overlays.join(FILE_NAME);
+    let snapshot = queue.clone();
+    tokio::task::spawn_blocking(move || snapshot.save(&path))
+        .await
+        .context("marker suggestions persist task")?
+}
+
+/// Queues a listener's suggestion (`suggest_marker` on `/chat`) and persists the queue.
+pub async fn submit(
+    state: &AppState,
+    new: NewSuggestion,
+) -> Result<MarkerSuggestion, &'static str> {
+    let mut queue = state.marker_suggestions.lock().await;
+    let suggestion = queue.submit(new, novasdr_core::clock::now().timestamp())?;
+    if let Err(e) = persist(state, &queue).await {
+        tracing::error!(error = ?e, "failed to persist marker suggestions");
+        queue.remove(&suggestion.id);
+        return Err("could not save suggestion");
+    }
+    tracing::info!(
+        id = %suggestion.id,
+        frequency = suggestion.frequency,
+        name = %suggestion.name,
+        user_id = %suggestion.user_id,
+        "marker suggestion queued"
+    );
+    Ok(suggestion)
+}
+
+/// `GET /admin/marker_suggestions`
+pub async fn list(State(state): State<Arc<AppState>>) -> Response {
+    let queue = state.marker_suggestions.lock().await;
+    Json(json!({ "suggestions": queue.pending() })).into_response()
+}
+
+/// `POST /admin/marker_suggestions/:id/approve`: adds the suggestion to `markers.json`.
+pub async fn approve(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    let mut queue = state.marker_suggestions.lock().await;
+    let Some(suggestion) = queue.pending().iter().find(|s| s.id == id).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown suggestion").into_response();
+    };
+    let (marker, count) = match markers::save_marker(&state, suggestion.to_marker()).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    queue.remove(&id);
+    if let Err(e) = persist(&state, &queue).await {
+        // The marker is saved; the suggestion would only reappear after a restart.
+        tracing::error!(error = ?e, "failed to persist marker suggestions");
+    }
+    tracing::info!(id = %id, marker = %marker, "admin: marker suggestion approved");
+    Json(json!({ "marker": marker, "count": count })).into_response()
+}
+
+/// `DELETE /admin/marker_suggestions/:id`: rejects the suggestion.
+pub async fn reject(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    let mut queue = state.marker_suggestions.lock().await;
+    let Some(removed) = queue.remove(&id) else {
+        return (StatusCode::NOT_FOUND, "unknown suggestion").into_response();
+    };
+    if let Err(e) = persist(&state, &queue).await {
+        tracing::error!(error = ?e, "failed to persist marker suggestions");
+        queue.suggestions.push(removed);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to persist marker suggestions",
+        )
+            .into_response();
+    }
+    tracing::info!(id = %id, "admin: marker suggestion rejected");
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggestion(ip: [u8; 4], frequency: i64, name: &str) -> NewSuggestion {
+        NewSuggestion {
+            frequency,
+            name: name.to_string(),
+            mode: Some("usb".to_string()),
+            user_id: "u1".to_string(),
+            username: "  listener ".to_string(),
+            ip: IpAddr::from(ip),
+        }
+    }
+
+    const A: [u8; 4] = [192, 0, 2, 1];
+    const B: [u8; 4] = [192, 0, 2, 2];
+
+    #[test]
+    fn submit_validates_and_limits_per_ip() {
+        let mut queue = SuggestionQueue::default();
+        let first = queue
+            .submit(suggestion(A, 7_074_000, " FT8 "), 1_700_000_000)
+            .unwrap();
+        assert_eq!(
+            (
+                first.name.as_str(),
+                first.mode.as_deref(),
+                first.username.as_str()
+            ),
+            ("FT8", Some("USB"), "listener")
+        );
+        assert_eq!(
+            first.to_marker(),
+            json!({ "frequency": 7_074_000, "name": "FT8", "mode": "USB" })
+        );
+        assert_eq!(
+            queue.submit(suggestion(B, 7_074_000, "ft8"), 0),
+            Err("already suggested")
+        );
+        assert_eq!(
+            queue.submit(suggestion(B, 0, "x"), 0),
+            Err("frequency must be positive")
+        );
+        assert_eq!(
+            queue.submit(suggestion(B, 1, " "), 0),
+            Err("name is required")
+        );
+
+        for i in 1..MAX_PENDING_PER_IP {
+            queue
+                .submit(suggestion(A, 7_000_000 + i as i64, "CW"), 0)
+                .unwrap();
+        }
+        assert_eq!(
+            queue.submit(suggestion(A, 14_074_000, "FT8"), 0),
+            Err("too many pending suggestions")
+        );
+        assert!(queue.submit(suggestion(B, 14_074_000, "FT8"), 0).is_ok());
+
+        let removed = queue.remove(&first.id).unwrap();
+        assert_eq!(removed, first);
+        assert_eq!(queue.pending().len(), MAX_PENDING_PER_IP);
+    }
+
+    #[test]
+    fn queue_round_trips_through_file() {
+        let path = std::env::temp_dir().join(format!(
+            "novasdr_marker_suggestions_{}_{}.json",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        assert_eq!(
+            SuggestionQueue::load(&path).unwrap(),
+            SuggestionQueue::default()
+        );
+        let mut queue = SuggestionQueue::default();
+        queue
+            .submit(suggestion(A, 10_136_000, "FT8 30m"), 1_700_000_000)
+            .unwrap();
+        queue.save(&path).unwrap();
+        assert_eq!(SuggestionQueue::load(&path).unwrap(), queue);
+        let _ = std::fs::remove_file(&path);
+    }
+}
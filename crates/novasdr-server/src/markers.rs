@@ -0,0 +1,493 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Query, State},
+    http::{
+        header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Markers returned by `/api/markers` when the request sets no `limit`.
+const DEFAULT_SEARCH_LIMIT: usize = 500;
+const MAX_SEARCH_LIMIT: usize = 5000;
+/// Upper bound on one marker as submitted to `POST /api/markers`, serialized.
+const MAX_MARKER_BYTES: usize = 2048;
+/// Hex digits of generated marker ids.
+const MARKER_ID_LEN: usize = 12;
+
+struct IndexedMarker {
+    frequency: Option<i64>,
+    /// Lowercased string fields, joined, for `q` matching.
+    text: String,
+    raw: serde_json::Value,
+}
+
+/// `markers.json` as loaded, plus a frequency-sorted index for `/api/markers`.
+pub struct MarkerIndex {
+    raw: serde_json::Value,
+    markers: Vec<IndexedMarker>,
+    etag: String,
+}
+
+impl Default for MarkerIndex {
+    fn default() -> Self {
+        Self::new(serde_json::Value::Null)
+    }
+}
+
+pub fn marker_frequency(marker: &serde_json::Value) -> Option<i64> {
+    match marker.get("frequency")? {
+        serde_json::Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+impl MarkerIndex {
+    /// Accepts the `{ "markers": [...] }` wrapper or a bare array.
+    pub fn new(raw: serde_json::Value) -> Self {
+        let list = raw
+            .get("markers")
+            .and_then(|v| v.as_array())
+            .or_else(|| raw.as_array());
+        let mut markers: Vec<IndexedMarker> = list
+            .into_iter()
+            .flatten()
+            .map(|m| IndexedMarker {
+                frequency: marker_frequency(m),
+                text: m
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(_, v)| v.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .to_lowercase(),
+                raw: m.clone(),
+            })
+            .collect();
+        // Markers without a frequency sort last and only match searches without a range.
+        markers.sort_by_key(|m| m.frequency.unwrap_or(i64::MAX));
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        raw.to_string().hash(&mut hasher);
+        let etag = format!("\"{:016x}\"", hasher.finish());
+        Self { raw, markers, etag }
+    }
+
+    pub fn raw(&self) -> &serde_json::Value {
+        &self.raw
+    }
+
+    pub fn count(&self) -> usize {
+        self.markers.len()
+    }
+
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    /// Markers matching `query` in frequency order, and the number of matches before `limit`.
+    pub fn search(&self, query: &MarkerSearch) -> (Vec<&serde_json::Value>, usize) {
+        let needle = query
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(str::to_lowercase);
+        let ranged = query.min.is_some() || query.max.is_some();
+        let start = match query.min {
+            Some(min) => self
+                .markers
+                .partition_point(|m| m.frequency.unwrap_or(i64::MAX) < min),
+            None => 0,
+        };
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .min(MAX_SEARCH_LIMIT);
+        let mut out = Vec::new();
+        let mut total = 0usize;
+        for m in &self.markers[start..] {
+            match (m.frequency, query.max) {
+                (None, _) if ranged => break,
+                (Some(f), Some(max)) if f > max => break,
+                _ => {}
+            }
+            if needle.as_deref().is_some_and(|n| !m.text.contains(n)) {
+                continue;
+            }
+            total += 1;
+            if out.len() < limit {
+                out.push(&m.raw);
+            }
+        }
+        (out, total)
+    }
+
+    /// The marker closest to `frequency`, if one lies within `tolerance` Hz of it.
+    pub fn nearest(&self, frequency: i64, tolerance: i64) -> Option<&serde_json::Value> {
+        let start = self
+            .markers
+            .partition_point(|m| m.frequency.unwrap_or(i64::MAX) < frequency - tolerance);
+        self.markers[start..]
+            .iter()
+            .map_while(|m| {
+                let f = m.frequency.filter(|&f| f <= frequency + tolerance)?;
+                Some((f, m))
+            })
+            .min_by_key(|(f, _)| (f - frequency).abs())
+            .map(|(_, m)| &m.raw)
+    }
+}
+
+fn marker_list(raw: &serde_json::Value) -> Vec<serde_json::Value> {
+    raw.get("markers")
+        .and_then(|v| v.as_array())
+        .or_else(|| raw.as_array())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// `raw` with its marker list replaced, always in the `{ "markers": [...] }` form; other
+/// top-level keys of an object file are kept.
+fn with_marker_list(raw: &serde_json::Value, list: Vec<serde_json::Value>) -> serde_json::Value {
+    let mut out = match raw {
+        serde_json::Value::Object(map) => map.clone(),
+        _ => serde_json::Map::new(),
+    };
+    out.insert("markers".to_string(), serde_json::Value::Array(list));
+    serde_json::Value::Object(out)
+}
+
+/// Inserts `marker`, or replaces the stored marker with the same `id`. Markers without an `id` get
+/// a generated one. Returns the new file content and the marker as stored.
+pub fn upsert_marker(
+    raw: &serde_json::Value,
+    mut marker: serde_json::Value,
+) -> Result<(serde_json::Value, serde_json::Value), &'static str> {
+    if marker.to_string().len() > MAX_MARKER_BYTES {
+        return Err("marker too large");
+    }
+    let Some(fields) = marker.as_object_mut() else {
+        return Err("marker must be a JSON object");
+    };
+    let name = fields.get("name").and_then(|v| v.as_str()).unwrap_or("");
+    if name.trim().is_empty() {
+        return Err("marker needs a non-empty name");
+    }
+    let id = match fields.get("id") {
+        Some(serde_json::Value::String(id)) if !id.trim().is_empty() => id.trim().to_string(),
+        Some(_) => return Err("marker id must be a non-empty string"),
+        None => novasdr_core::util::generate_unique_id()[..MARKER_ID_LEN].to_string(),
+    };
+    fields.insert("id".to_string(), json!(id));
+    if marker_frequency(&marker).unwrap_or(0) <= 0 {
+        return Err("marker needs a positive frequency in Hz");
+    }
+
+    let mut list = marker_list(raw);
+    match list
+        .iter_mut()
+        .find(|m| m.get("id").and_then(|v| v.as_str()) == Some(id.as_str()))
+    {
+        Some(existing) => *existing = marker.clone(),
+        None => list.push(marker.clone()),
+    }
+    Ok((with_marker_list(raw, list), marker))
+}
+
+/// Which markers `DELETE /api/markers` removes: the one with `id`, or every marker at exactly
+/// `frequency` (for markers written by hand without an id).
+#[derive(Debug, Default, Deserialize)]
+pub struct MarkerSelector {
+    pub id: Option<String>,
+    pub frequency: Option<i64>,
+}
+
+/// Returns the new file content and the number of markers removed.
+pub fn remove_markers(
+    raw: &serde_json::Value,
+    selector: &MarkerSelector,
+) -> Result<(serde_json::Value, usize), &'static str> {
+    let matches = |m: &serde_json::Value| match (&selector.id, selector.frequency) {
+        (Some(id), None) => m.get("id").and_then(|v| v.as_str()) == Some(id.as_str()),
+        (None, Some(f)) => marker_frequency(m) == Some(f),
+        _ => false,
+    };
+    if selector.id.is_some() == selector.frequency.is_some() {
+        return Err("pass exactly one of id or frequency");
+    }
+    let mut list = marker_list(raw);
+    let before = list.len();
+    list.retain(|m| !matches(m));
+    let removed = before - list.len();
+    Ok((with_marker_list(raw, list), removed))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MarkerSearch {
+    /// Lowest frequency in Hz, inclusive.
+    pub min: Option<i64>,
+    /// Highest frequency in Hz, inclusive.
+    pub max: Option<i64>,
+    /// Case-insensitive substring matched against the marker's string fields.
+    pub q: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Upgrade query by which `/audio` and `/waterfall` clients declare (`?markers=api`) that they
+/// fetch markers from `/api/markers`, so the settings message can leave them out.
+#[derive(Debug, Default, Deserialize)]
+pub struct MarkerSupport {
+    #[serde(default)]
+    markers: Option<String>,
+}
+
+impl MarkerSupport {
+    pub fn uses_api(&self) -> bool {
+        self.markers.as_deref() == Some("api")
+    }
+}
+
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+pub async fn search(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<MarkerSearch>,
+) -> Response {
+    let index = state.markers.read().await.clone();
+    // Clients must revalidate, which is cheap: the tag only changes when markers.json does.
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    if let Ok(etag) = HeaderValue::from_str(index.etag()) {
+        response_headers.insert(ETAG, etag);
+    }
+
+    if etag_matches(&headers, index.etag()) {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+    let (markers, total) = index.search(&query);
+    (
+        response_headers,
+        Json(json!({
+            "total": total,
+            "truncated": markers.len() < total,
+            "markers": markers,
+        })),
+    )
+        .into_response()
+}
+
+/// Writes `next` to `markers.json`, swaps it into the index and notifies `/events` clients. The
+/// caller holds the marker lock, so concurrent edits cannot interleave.
+async fn commit_markers(
+    state: &AppState,
+    current: &mut Arc<MarkerIndex>,
+    next: serde_json::Value,
+) -> Result<(), Response> {
+    let path = state.config_paths.overlays.join("markers.json");
+    let value = next.clone();
+    let write =
+        tokio::task::spawn_blocking(move || novasdr_core::config::write_json_atomic(&path, &value))
+            .await;
+    match write {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            tracing::error!(error = ?e, "failed to persist markers.json");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to persist markers.json",
+            )
+                .into_response());
+        }
+        Err(e) => {
+            tracing::error!(error = ?e, "markers persist task failed");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    }
+    *current = Arc::new(MarkerIndex::new(next));
+    state.notify_overlays_updated(&["markers"]);
+    Ok(())
+}
+
+/// `POST /api/markers` (admin token): adds a marker or replaces the one with the same `id`.
+pub async fn upsert(
+    State(state): State<Arc<AppState>>,
+    Json(marker): Json<serde_json::Value>,
+) -> Response {
+    match save_marker(&state, marker).await {
+        Ok((stored, count)) => {
+            tracing::info!(marker = %stored, "admin: marker saved");
+            Json(json!({ "marker": stored, "count": count })).into_response()
+        }
+        Err(resp) => resp,
+    }
+}
+
+/// Adds or replaces `marker` (see [`upsert_marker`]) and persists it. Returns the marker as stored
+/// and the new marker count.
+pub async fn save_marker(
+    state: &AppState,
+    marker: serde_json::Value,
+) -> Result<(serde_json::Value, usize), Response> {
+    let mut current = state.markers.write().await;
+    let (next, stored) = upsert_marker(current.raw(), marker)
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg).into_response())?;
+    commit_markers(state, &mut current, next).await?;
+    Ok((stored, current.count()))
+}
+
+/// `DELETE /api/markers?id=..` or `?frequency=..` (admin token).
+pub async fn remove(
+    State(state): State<Arc<AppState>>,
+    Query(selector): Query<MarkerSelector>,
+) -> Response {
+    let mut current = state.markers.write().await;
+    let (next, removed) = match remove_markers(current.raw(), &selector) {
+        Ok(v) => v,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+    if removed == 0 {
+        return (StatusCode::NOT_FOUND, "no matching marker").into_response();
+    }
+    if let Err(resp) = commit_markers(&state, &mut current, next).await {
+        return resp;
+    }
+    tracing::info!(removed, ?selector, "admin: markers removed");
+    Json(json!({ "removed": removed, "count": current.count() })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> MarkerIndex {
+        MarkerIndex::new(json!({ "markers": [
+            { "frequency": 14074000, "name": "FT8", "mode": "USB" },
+            { "frequency": 7074000, "name": "FT8", "mode": "USB" },
+            { "frequency": "10136000", "name": "FT8 30m" },
+            { "frequency": 7040000, "name": "WSPR" },
+            { "name": "No frequency" },
+        ]}))
+    }
+
+    fn frequencies(found: &[&serde_json::Value]) -> Vec<String> {
+        found
+            .iter()
+            .map(|m| m["frequency"].to_string().trim_matches('"').to_string())
+            .collect()
+    }
+
+    #[test]
+    fn nearest_picks_the_closest_marker_within_tolerance() {
+        let index = index();
+        let name = |f, tol| index.nearest(f, tol).map(|m| m["name"].as_str().unwrap());
+        assert_eq!(name(7_073_000, 2_000), Some("FT8"));
+        assert_eq!(name(7_050_000, 30_000), Some("WSPR"));
+        assert_eq!(name(7_060_000, 30_000), Some("FT8"));
+        assert_eq!(name(10_136_500, 1_000), Some("FT8 30m"));
+        assert_eq!(name(7_100_000, 2_000), None);
+    }
+
+    #[test]
+    fn search_filters_by_range_and_text() {
+        let index = index();
+        let (all, total) = index.search(&MarkerSearch::default());
+        assert_eq!((all.len(), total), (5, 5));
+
+        let (found, _) = index.search(&MarkerSearch {
+            min: Some(7_000_000),
+            max: Some(10_200_000),
+            ..Default::default()
+        });
+        assert_eq!(frequencies(&found), ["7040000", "7074000", "10136000"]);
+
+        let (found, total) = index.search(&MarkerSearch {
+            min: Some(7_000_000),
+            q: Some("ft8".to_string()),
+            limit: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(frequencies(&found), ["7074000", "10136000"]);
+        assert_eq!(total, 3);
+    }
+
+    #[test]
+    fn upsert_assigns_id_and_replaces_by_id() {
+        let raw = json!({ "markers": [{ "frequency": 7074000, "name": "FT8" }], "version": 1 });
+        let (raw, stored) =
+            upsert_marker(&raw, json!({ "frequency": 14074000, "name": "FT8 20m" })).unwrap();
+        let id = stored["id"].as_str().unwrap().to_string();
+        assert_eq!(id.len(), MARKER_ID_LEN);
+        assert_eq!(raw["version"], 1);
+        assert_eq!(MarkerIndex::new(raw.clone()).count(), 2);
+
+        let (raw, _) = upsert_marker(
+            &raw,
+            json!({ "id": id, "frequency": 14075000, "name": "moved" }),
+        )
+        .unwrap();
+        let index = MarkerIndex::new(raw);
+        assert_eq!(index.count(), 2);
+        let (found, _) = index.search(&MarkerSearch {
+            q: Some("moved".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(frequencies(&found), ["14075000"]);
+
+        assert!(upsert_marker(&json!([]), json!({ "name": "no frequency" })).is_err());
+        assert!(upsert_marker(&json!([]), json!({ "frequency": 7000000 })).is_err());
+    }
+
+    #[test]
+    fn remove_by_id_or_frequency() {
+        let raw = json!([
+            { "id": "a", "frequency": 7074000, "name": "FT8" },
+            { "frequency": 7040000, "name": "WSPR" },
+            { "frequency": 7040000, "name": "WSPR 2" },
+        ]);
+        let by_id = MarkerSelector {
+            id: Some("a".to_string()),
+            frequency: None,
+        };
+        let (next, removed) = remove_markers(&raw, &by_id).unwrap();
+        assert_eq!((removed, MarkerIndex::new(next).count()), (1, 2));
+
+        let by_frequency = MarkerSelector {
+            id: None,
+            frequency: Some(7_040_000),
+        };
+        assert_eq!(remove_markers(&raw, &by_frequency).unwrap().1, 2);
+        assert!(remove_markers(&raw, &MarkerSelector::default()).is_err());
+    }
+
+    #[test]
+    fn etag_tracks_marker_content() {
+        assert_eq!(index().etag(), index().etag());
+        assert_ne!(index().etag(), MarkerIndex::default().etag());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"x\", {}", index().etag())).unwrap(),
+        );
+        assert!(etag_matches(&headers, index().etag()));
+        assert!(!etag_matches(&HeaderMap::new(), index().etag()));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::state::AppState;
+use axum::{
+    extract::State,
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+use std::fmt::Write as _;
+use std::sync::{atomic::Ordering, Arc};
+
+/// `GET /metrics`: client counts, bitrates and GeoIP statistics in the Prometheus text format.
+pub async fn handler(State(state): State<Arc<AppState>>) -> Response {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        render(&state),
+    )
+        .into_response()
+}
+
+fn render(state: &AppState) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, f64)]| {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{name}{labels} {value}");
+        }
+    };
+    let single = |value: f64| [(String::new(), value)];
+
+    metric(
+        "novasdr_audio_clients",
+        "gauge",
+        "Connected audio clients.",
+        &single(state.total_audio_clients() as f64),
+    );
+    metric(
+        "novasdr_waterfall_clients",
+        "gauge",
+        "Connected waterfall clients.",
+        &single(state.total_waterfall_clients() as f64),
+    );
+    metric(
+        "novasdr_events_clients",
+        "gauge",
+        "Connected events clients.",
+        &single(state.event_clients.len() as f64),
+    );
+    metric(
+        "novasdr_chat_clients",
+        "gauge",
+        "Connected chat clients.",
+        &single(state.chat_clients.len() as f64),
+    );
+    metric(
+        "novasdr_audio_kbits_per_second",
+        "gauge",
+        "Audio bitrate sent to all clients over the last second.",
+        &single(state.audio_kbits_per_sec.load(Ordering::Relaxed) as f64),
+    );
+    metric(
+        "novasdr_waterfall_kbits_per_second",
+        "gauge",
+        "Waterfall bitrate sent to all clients over the last second.",
+        &single(state.waterfall_kbits_per_sec.load(Ordering::Relaxed) as f64),
+    );
+    metric(
+        "novasdr_dropped_audio_frames_total",
+        "counter",
+        "Audio frames dropped because a client queue was full.",
+        &single(state.dropped_audio_frames.load(Ordering::Relaxed) as f64),
+    );
+    metric(
+        "novasdr_dropped_waterfall_frames_total",
+        "counter",
+        "Waterfall frames dropped because a client queue was full.",
+        &single(state.dropped_waterfall_frames.load(Ordering::Relaxed) as f64),
+    );
+    let [retried, missed, resyncs] = state.chat_backpressure.totals();
+    metric(
+        "novasdr_chat_retried_sends_total",
+        "counter",
+        "Chat messages sent a second time because a client queue was full.",
+        &single(retried as f64),
+    );
+    metric(
+        "novasdr_chat_missed_messages_total",
+        "counter",
+        "Chat messages a lagging client skipped, to be caught up on from the history.",
+        &single(missed as f64),
+    );
+    metric(
+        "novasdr_chat_history_resyncs_total",
+        "counter",
+        "History snapshots sent to chat clients that missed messages.",
+        &single(resyncs as f64),
+    );
+    let rejected: Vec<_> = crate::ws::audio::WindowRejection::ALL
+        .iter()
+        .map(|&reason| {
+            let n = state.rejected_windows[reason as usize].load(Ordering::Relaxed);
+            (format!("{{reason=\"{}\"}}", reason.as_str()), n as f64)
+        })
+        .collect();
+    metric(
+        "novasdr_rejected_windows_total",
+        "counter",
+        "Audio window commands refused, by the constraint they broke.",
+        &rejected,
+    );
+    let mut ids: Vec<&String> = state.receivers.keys().collect();
+    ids.sort();
+    let shared: Vec<_> = ids
+        .into_iter()
+        .map(|id| {
+            let n = state.receivers[id]
+                .shared_audio_channels
+                .load(Ordering::Relaxed);
+            (receiver_label(id), n as f64)
+        })
+        .collect();
+    metric(
+        "novasdr_shared_audio_channels",
+        "gauge",
+        "Audio channels demodulated once for several listeners tuned the same way.",
+        &shared,
+    );
+    let mut ids: Vec<&String> = state.receivers.keys().collect();
+    ids.sort();
+    let channelizer: Vec<_> = ids
+        .into_iter()
+        .map(|id| {
+            let n = state.receivers[id]
+                .channelizer_channels
+                .load(Ordering::Relaxed);
+            (receiver_label(id), n as f64)
+        })
+        .collect();
+    metric(
+        "novasdr_channelizer_channels",
+        "gauge",
+        "Channelizer channels brought to baseband once for several listeners tuned within them.",
+        &channelizer,
+    );
+    let mut ids: Vec<&String> = state.receivers.keys().collect();
+    ids.sort();
+    let per_receiver = |value: fn(&crate::state::ReceiverState) -> f64| -> Vec<(String, f64)> {
+        ids.iter()
+            .map(|id| (receiver_label(id), value(&state.receivers[*id])))
+            .collect()
+    };
+    metric(
+        "novasdr_input_clipped_samples_total",
+        "counter",
+        "Input samples at the ADC's full scale.",
+        &per_receiver(|r| r.clipped_samples.load(Ordering::Relaxed) as f64),
+    );
+    metric(
+        "novasdr_input_overload",
+        "gauge",
+        "1 while the input clips or runs close to full scale.",
+        &per_receiver(|r| f64::from(u8::from(r.overload().is_some()))),
+    );
+    metric(
+        "novasdr_tx_keyed",
+        "gauge",
+        "1 while the receiver's device transmits (`receivers[].tx`).",
+        &per_receiver(|r| f64::from(u8::from(r.tx.keyed()))),
+    );
+    let pools = crate::buffer_pool::stats();
+    let pool_metric = |value: fn(&crate::buffer_pool::PoolStats) -> f64| -> Vec<(String, f64)> {
+        pools
+            .iter()
+            .map(|p| (format!("{{pool=\"{}\"}}", p.name), value(p)))
+            .collect()
+    };
+    metric(
+        "novasdr_buffer_pool_hits_total",
+        "counter",
+        "DSP scratch buffers handed out from the pool.",
+        &pool_metric(|p| p.hits as f64),
+    );
+    metric(
+        "novasdr_buffer_pool_misses_total",
+        "counter",
+        "DSP scratch buffers allocated because the pool had none of the size.",
+        &pool_metric(|p| p.misses as f64),
+    );
+    metric(
+        "novasdr_buffer_pool_in_use_bytes",
+        "gauge",
+        "Bytes of DSP scratch buffers currently borrowed.",
+        &pool_metric(|p| p.in_use_bytes as f64),
+    );
+    metric(
+        "novasdr_buffer_pool_peak_in_use_bytes",
+        "gauge",
+        "Most bytes of DSP scratch buffers borrowed at once since start.",
+        &pool_metric(|p| p.peak_in_use_bytes as f64),
+    );
+    metric(
+        "novasdr_buffer_pool_idle_bytes",
+        "gauge",
+        "Bytes of DSP scratch buffers kept in the pool for reuse.",
+        &pool_metric(|p| p.idle_bytes as f64),
+    );
+    let (compressed, shared) = crate::waterfall_compress::counts();
+    metric(
+        "novasdr_waterfall_packets_compressed_total",
+        "counter",
+        "Live waterfall packets compressed.",
+        &single(compressed as f64),
+    );
+    metric(
+        "novasdr_waterfall_packets_shared_total",
+        "counter",
+        "Live waterfall packets sent as compressed for another client with the same view.",
+        &single(shared as f64),
+    );
+    let disk: Vec<_> = crate::disk_guard::usage()
+        .into_iter()
+        .map(|(dir, used)| (format!("{{dir=\"{dir}\"}}"), used))
+        .collect();
+    metric(
+        "novasdr_disk_used_percent",
+        "gauge",
+        "Used share of the filesystem under capture.dir and the log directory.",
+        &disk,
+    );
+    metric(
+        "novasdr_recordings_paused",
+        "gauge",
+        "1 while new recordings are held back by the disk guard.",
+        &single(f64::from(u8::from(crate::disk_guard::recordings_paused()))),
+    );
+
+    if let Some(geoip) = state.geoip.as_ref() {
+        let listeners: Vec<_> = state
+            .listeners_by_country()
+            .into_iter()
+            .map(|(country, n)| (country_label(&country), n as f64))
+            .collect();
+        metric(
+            "novasdr_listeners",
+            "gauge",
+            "Connected client IPs by country.",
+            &listeners,
+        );
+        let connections: Vec<_> = geoip
+            .connections_by_country()
+            .into_iter()
+            .map(|(country, n)| (country_label(&country), n as f64))
+            .collect();
+        metric(
+            "novasdr_connections_total",
+            "counter",
+            "Newly connected client IPs by country.",
+            &connections,
+        );
+        metric(
+            "novasdr_country_rejections_total",
+            "counter",
+            "Connections refused by the GeoIP country filter.",
+            &single(geoip.rejected_connections() as f64),
+        );
+    }
+    out
+}
+
+fn receiver_label(id: &str) -> String {
+    let escaped = id.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("{{receiver=\"{escaped}\"}}")
+}
+
+/// Country codes are ASCII letters or `unknown`, so they need no escaping.
+fn country_label(country: &str) -> String {
+    format!("{{country=\"{country}\"}}")
+}
@@ -11,6 +11,7 @@ pub struct OverlayPaths {
     pub markers: PathBuf,
     pub bands: PathBuf,
     pub header_panel: PathBuf,
+    pub waterfall_masks: PathBuf,
 }
 
 pub fn overlay_paths_for_config(config_path: &Path) -> OverlayPaths {
@@ -23,6 +24,7 @@ pub fn overlay_paths_for_config(config_path: &Path) -> OverlayPaths {
         markers: dir.join("markers.json"),
         bands: dir.join("bands.json"),
         header_panel: dir.join("header_panel.json"),
+        waterfall_masks: dir.join("waterfall_masks.json"),
         dir,
     }
 }
@@ -43,6 +45,8 @@ pub fn ensure_default_overlays(config_path: &Path) -> anyhow::Result<OverlayPath
 
     write_json_if_missing(&paths.header_panel, &default_header_panel_value())
         .context("ensure overlays header_panel.json")?;
+    write_json_if_missing(&paths.waterfall_masks, &default_waterfall_masks_value())
+        .context("ensure overlays waterfall_masks.json")?;
 
     Ok(paths)
 }
@@ -51,6 +55,10 @@ pub fn default_markers_value() -> serde_json::Value {
     json!({ "markers": [] })
 }
 
+pub fn default_waterfall_masks_value() -> serde_json::Value {
+    json!({ "masks": [] })
+}
+
 pub fn default_bands_value() -> anyhow::Result<serde_json::Value> {
     let v = serde_json::from_str::<serde_json::Value>(DEFAULT_BANDS_RAW)
         .context("parse default bands json")?;
@@ -135,6 +143,10 @@ mod tests {
             paths.header_panel.exists(),
             "header_panel.json should exist"
         );
+        assert!(
+            paths.waterfall_masks.exists(),
+            "waterfall_masks.json should exist"
+        );
 
         let markers: serde_json::Value =
             serde_json::from_str(&std::fs::read_to_string(&paths.markers).unwrap()).unwrap();
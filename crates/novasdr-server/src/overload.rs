@@ -0,0 +1,137 @@
+//! Input overload detection: every second each receiver's share of samples at the ADC's full
+//! scale and its input level are checked, and audio clients, waterfall clients and `/events` are
+//! told when the spectrum stops, or starts again, being trustworthy.
+
+use crate::shutdown;
+use crate::state::{AppState, Overload, ReceiverState};
+use novasdr_core::protocol::EventTopics;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+const INTERVAL: Duration = Duration::from_secs(1);
+/// Share of clipped samples, in parts per million, from which the input counts as clipping: a
+/// few per million already spread intermodulation products across the spectrum.
+const CLIPPED_PPM: f64 = 10.0;
+/// Input level from which the input counts as overloaded before it clips: noise-like signals
+/// peak some 10 dB above their average, so the ADC is at its edge.
+const STRONG_SIGNAL_DBFS: f64 = -10.0;
+/// Clean checks before the warning is withdrawn, so bursty signals do not make it flicker.
+const HOLD_CHECKS: u32 = 5;
+
+/// Counters of one receiver at the previous check.
+#[derive(Default)]
+struct Track {
+    samples: u64,
+    clipped: u64,
+    clean_checks: u32,
+}
+
+impl Track {
+    /// Overload state after a check that saw `clipped_ppm` and `level_dbfs`, given the current
+    /// one: it starts at once and ends after [`HOLD_CHECKS`] clean checks.
+    fn update(
+        &mut self,
+        current: Option<Overload>,
+        clipped_ppm: f64,
+        level_dbfs: f64,
+    ) -> Option<Overload> {
+        let reason = if clipped_ppm >= CLIPPED_PPM {
+            Some("clipping")
+        } else if level_dbfs >= STRONG_SIGNAL_DBFS {
+            Some("strong_signal")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            self.clean_checks = 0;
+            return Some(Overload {
+                reason,
+                clipped_ppm,
+                level_dbfs,
+            });
+        }
+        let current = current?;
+        self.clean_checks += 1;
+        (self.clean_checks < HOLD_CHECKS).then_some(Overload {
+            clipped_ppm,
+            level_dbfs,
+            ..current
+        })
+    }
+}
+
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut tracks: HashMap<String, Track> = HashMap::new();
+        let mut interval = tokio::time::interval(INTERVAL);
+        while !shutdown::is_shutdown_requested() {
+            interval.tick().await;
+            for receiver in state.receivers.values() {
+                let track = tracks.entry(receiver.receiver.id.clone()).or_default();
+                check(&state, receiver, track);
+            }
+        }
+    });
+}
+
+fn check(state: &AppState, receiver: &ReceiverState, track: &mut Track) {
+    let samples = receiver.input_samples.load(Ordering::Relaxed);
+    let clipped = receiver.clipped_samples.load(Ordering::Relaxed);
+    let new_samples = samples.saturating_sub(track.samples);
+    let new_clipped = clipped.saturating_sub(track.clipped);
+    track.samples = samples;
+    track.clipped = clipped;
+    if new_samples == 0 {
+        // The input is not delivering; nothing to judge.
+        return;
+    }
+    let clipped_ppm = new_clipped as f64 / new_samples as f64 * 1e6;
+    let level = f32::from_bits(receiver.input_level.load(Ordering::Relaxed));
+    let level_dbfs = 10.0 * f64::from(level).max(1e-12).log10();
+
+    let next = track.update(receiver.overload(), clipped_ppm, level_dbfs);
+    if !receiver.set_overload(next) {
+        return;
+    }
+    let receiver_id = receiver.receiver.id.as_str();
+    match next {
+        Some(o) => tracing::warn!(
+            receiver_id,
+            reason = o.reason,
+            clipped_ppm = o.clipped_ppm,
+            level_dbfs = o.level_dbfs,
+            "input overload"
+        ),
+        None => tracing::info!(receiver_id, level_dbfs, "input overload cleared"),
+    }
+    let msg = receiver.overload_message();
+    for client in receiver.audio_clients.iter() {
+        let _ = client.text_tx.try_send(msg.clone());
+    }
+    state.broadcast_event_json(EventTopics::MAINTENANCE, msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overload_starts_at_once_and_ends_after_the_hold() {
+        let mut track = Track::default();
+        assert_eq!(track.update(None, 0.0, -30.0), None);
+
+        let on = track.update(None, 50.0, -12.0).unwrap();
+        assert_eq!(on.reason, "clipping");
+        let hot = track.update(Some(on), 0.0, -8.0).unwrap();
+        assert_eq!(hot.reason, "strong_signal");
+
+        let mut current = Some(hot);
+        for _ in 1..HOLD_CHECKS {
+            current = track.update(current, 0.0, -30.0);
+            assert_eq!(current.map(|o| o.reason), Some("strong_signal"));
+        }
+        assert_eq!(track.update(current, 0.0, -30.0), None);
+    }
+}
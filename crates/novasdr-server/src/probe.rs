@@ -0,0 +1,148 @@
+use anyhow::Context;
+use novasdr_core::{
+    config::{self, InputDriver},
+    dsp::format_probe::{probe_format, FormatProbe, MIN_PROBE_BYTES},
+};
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Enough for a few seconds of 8 MS/s complex 16-bit input.
+const MAX_PROBE_BYTES: usize = 128 * 1024 * 1024;
+const READ_CHUNK: usize = 64 * 1024;
+/// rtl_tcp sends `RTL0`, the tuner type and its gain count before the samples.
+const RTL_TCP_HEADER_LEN: usize = 12;
+const DC_WARN: f32 = 0.1;
+const CLIP_WARN: f32 = 0.01;
+const SILENT_DBFS: f32 = -100.0;
+
+/// Reads until `seconds` passed, [`MAX_PROBE_BYTES`] were read or the source ended. The elapsed
+/// time is only meaningful for a live source that delivers samples at their real rate.
+fn capture(mut source: impl Read, seconds: u64) -> anyhow::Result<(Vec<u8>, Duration, bool)> {
+    let deadline = Duration::from_secs(seconds);
+    let started = Instant::now();
+    let mut bytes = Vec::new();
+    let mut chunk = vec![0u8; READ_CHUNK];
+    let mut ended = false;
+    while started.elapsed() < deadline && bytes.len() < MAX_PROBE_BYTES {
+        let n = source.read(&mut chunk).context("read input")?;
+        if n == 0 {
+            ended = true;
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..n]);
+    }
+    Ok((bytes, started.elapsed(), ended))
+}
+
+fn round_rate(rate: f64) -> i64 {
+    ((rate / 1000.0).round() * 1000.0) as i64
+}
+
+fn print_report(probe: &FormatProbe, bytes: usize, elapsed: Duration, ended: bool) {
+    let names: Vec<&str> = probe.formats.iter().map(|f| f.as_str()).collect();
+    println!(
+        "likely format: {} ({} byte(s) per sample)",
+        names.join(" or "),
+        probe.bytes_per_sample
+    );
+    if probe.formats.len() > 1 {
+        println!(
+            "  {} cannot be told apart by their bytes; pick the complex one for IQ sources",
+            names.join(" and ")
+        );
+    }
+    println!(
+        "level: {:.1} dBFS rms, DC offset {:+.3}, {:.2}% clipped",
+        probe.rms_dbfs,
+        probe.dc_offset,
+        probe.clipped_fraction * 100.0
+    );
+
+    if ended {
+        println!(
+            "rate: unknown (the input ended after {bytes} bytes; probe a live source for the rate)"
+        );
+    } else {
+        let samples_per_sec = bytes as f64 / probe.bytes_per_sample as f64 / elapsed.as_secs_f64();
+        println!(
+            "rate: ~{} samples/s over {:.1} s, i.e. sps ~{} for \"iq\" or ~{} for \"real\"",
+            round_rate(samples_per_sec),
+            elapsed.as_secs_f64(),
+            round_rate(samples_per_sec / 2.0),
+            round_rate(samples_per_sec)
+        );
+    }
+
+    if probe.rms_dbfs < SILENT_DBFS {
+        println!("warning: the input is (nearly) silent; check that the SDR is running and tuned");
+    }
+    if probe.dc_offset.abs() > DC_WARN {
+        println!("warning: large DC offset; the signedness may be wrong (u8 vs s8, u16 vs s16)");
+    }
+    if probe.clipped_fraction > CLIP_WARN {
+        println!("warning: the input clips; lower the SDR gain");
+    }
+}
+
+/// Compares the guess with stdin/FIFO receivers in the config, when it loads.
+fn print_config_comparison(args: &crate::cli::Args, probe: &FormatProbe) {
+    let Ok(cfg) = config::load_from_files(&args.config, &args.receivers) else {
+        return;
+    };
+    for r in cfg.receivers.iter().filter(|r| r.enabled) {
+        if !matches!(
+            r.input.driver,
+            InputDriver::Stdin { .. } | InputDriver::Fifo { .. }
+        ) {
+            continue;
+        }
+        let configured = r.input.driver.get_sample_format();
+        let verdict = if probe.formats.contains(&configured) {
+            "matches"
+        } else {
+            "differs"
+        };
+        println!(
+            "receiver {:?}: configured format {} {verdict}",
+            r.id,
+            configured.as_str()
+        );
+    }
+}
+
+/// `probe-input`: samples stdin, a file/FIFO or a TCP stream and prints the likely sample format,
+/// rate and level, for inputs that show only noise because `format` is wrong.
+pub fn run(
+    args: &crate::cli::Args,
+    path: Option<&Path>,
+    tcp: Option<&str>,
+    seconds: u64,
+) -> anyhow::Result<()> {
+    let (mut bytes, elapsed, ended) = if let Some(addr) = tcp {
+        let stream =
+            std::net::TcpStream::connect(addr).with_context(|| format!("connect {addr}"))?;
+        eprintln!("sampling {addr} for {seconds} s ...");
+        capture(stream, seconds)?
+    } else if let Some(path) = path {
+        let file = std::fs::File::open(path).with_context(|| format!("open {}", path.display()))?;
+        eprintln!("sampling {} for {seconds} s ...", path.display());
+        capture(file, seconds)?
+    } else {
+        eprintln!("sampling stdin for {seconds} s ...");
+        capture(std::io::stdin().lock(), seconds)?
+    };
+    if tcp.is_some() && bytes.starts_with(b"RTL0") && bytes.len() >= RTL_TCP_HEADER_LEN {
+        bytes.drain(..RTL_TCP_HEADER_LEN);
+    }
+
+    let probe = probe_format(&bytes).with_context(|| {
+        format!(
+            "only {} bytes read; need at least {MIN_PROBE_BYTES}",
+            bytes.len()
+        )
+    })?;
+    print_report(&probe, bytes.len(), elapsed, ended);
+    print_config_comparison(args, &probe);
+    Ok(())
+}
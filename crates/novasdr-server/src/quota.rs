@@ -0,0 +1,373 @@
+use crate::state::AppState;
+use anyhow::Context;
+use dashmap::DashMap;
+use novasdr_core::config::Limits;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::watch;
+
+/// Listening time per IP for the current day, resolved next to `receivers.json`.
+pub const FILE_NAME: &str = "quota_usage.json";
+/// Seconds between accounting passes; listening time is charged in steps of this size.
+pub const ACCOUNT_INTERVAL_SECS: u64 = 10;
+/// Seconds between writes of [`FILE_NAME`]; nothing is written while usage is unchanged.
+pub const PERSIST_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaRejection {
+    TooManyStreams,
+    ListeningTimeUsed,
+}
+
+impl QuotaRejection {
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::TooManyStreams => "too many audio streams from this IP",
+            Self::ListeningTimeUsed => "daily listening time used up",
+        }
+    }
+}
+
+/// On-disk form of the ledger.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageSnapshot {
+    /// UTC day (`YYYY-MM-DD`) the counters belong to.
+    pub day: String,
+    #[serde(default)]
+    pub listened_secs: BTreeMap<IpAddr, u64>,
+}
+
+struct IpUsage {
+    listened_secs: u64,
+    audio_streams: usize,
+    /// Flips to `true` when the IP reaches its daily quota; open audio streams close on it.
+    exhausted: watch::Sender<bool>,
+}
+
+impl IpUsage {
+    fn new(listened_secs: u64) -> Self {
+        Self {
+            listened_secs,
+            audio_streams: 0,
+            exhausted: watch::channel(false).0,
+        }
+    }
+}
+
+fn quota_secs(limits: &Limits) -> Option<u64> {
+    (limits.listen_minutes_per_day > 0).then(|| limits.listen_minutes_per_day.saturating_mul(60))
+}
+
+/// Audio streams and listening time per client IP.
+#[derive(Default)]
+pub struct QuotaLedger {
+    day: std::sync::Mutex<String>,
+    usage: DashMap<IpAddr, IpUsage>,
+    dirty: AtomicBool,
+}
+
+impl QuotaLedger {
+    pub fn from_snapshot(snapshot: UsageSnapshot) -> Self {
+        let usage = snapshot
+            .listened_secs
+            .into_iter()
+            .map(|(ip, secs)| (ip, IpUsage::new(secs)))
+            .collect();
+        Self {
+            day: std::sync::Mutex::new(snapshot.day),
+            usage,
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Reads the usage file; a missing file is an empty ledger.
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let raw = match std::fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        };
+        let snapshot: UsageSnapshot =
+            serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display()))?;
+        Ok(Self::from_snapshot(snapshot))
+    }
+
+    pub fn snapshot(&self) -> UsageSnapshot {
+        let day = match self.day.lock() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        let listened_secs = self
+            .usage
+            .iter()
+            .filter(|u| u.listened_secs > 0)
+            .map(|u| (*u.key(), u.listened_secs))
+            .collect();
+        UsageSnapshot { day, listened_secs }
+    }
+
+    /// Starts a new day's counters when `today` differs from the ledger's day.
+    fn roll_over(&self, today: &str) {
+        let mut day = match self.day.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("quota day mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        if day.as_str() == today {
+            return;
+        }
+        *day = today.to_string();
+        self.usage.retain(|_, u| {
+            u.listened_secs = 0;
+            u.exhausted.send_replace(false);
+            u.audio_streams > 0
+        });
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    fn used_up(&self, ip: IpAddr, limits: &Limits) -> bool {
+        let Some(quota) = quota_secs(limits) else {
+            return false;
+        };
+        self.usage
+            .get(&ip)
+            .map(|u| u.listened_secs >= quota)
+            .unwrap_or(false)
+    }
+
+    /// Registers an audio stream for `ip`. The receiver turns `true` once the IP runs out of
+    /// listening time while the stream is open.
+    pub fn admit_audio(
+        &self,
+        ip: IpAddr,
+        limits: &Limits,
+        today: &str,
+    ) -> Result<watch::Receiver<bool>, QuotaRejection> {
+        self.roll_over(today);
+        if self.used_up(ip, limits) {
+            return Err(QuotaRejection::ListeningTimeUsed);
+        }
+        let mut usage = self.usage.entry(ip).or_insert_with(|| IpUsage::new(0));
+        if limits.audio_per_ip > 0 && usage.audio_streams >= limits.audio_per_ip {
+            return Err(QuotaRejection::TooManyStreams);
+        }
+        usage.audio_streams += 1;
+        Ok(usage.exhausted.subscribe())
+    }
+
+    pub fn release_audio(&self, ip: IpAddr) {
+        if let Some(mut usage) = self.usage.get_mut(&ip) {
+            usage.audio_streams = usage.audio_streams.saturating_sub(1);
+        }
+        self.usage
+            .remove_if(&ip, |_, u| u.audio_streams == 0 && u.listened_secs == 0);
+    }
+
+    /// Whether `ip` may open a waterfall: always, unless it used up its listening time and
+    /// `limits.quota_waterfall_only` is off.
+    pub fn allows_waterfall(&self, ip: IpAddr, limits: &Limits, today: &str) -> bool {
+        self.roll_over(today);
+        limits.quota_waterfall_only || !self.used_up(ip, limits)
+    }
+
+    /// Charges `elapsed_secs` of listening time to every IP with an open audio stream and
+    /// returns the IPs that reached their quota in this pass.
+    pub fn account(&self, elapsed_secs: u64, limits: &Limits, today: &str) -> Vec<IpAddr> {
+        self.roll_over(today);
+        let Some(quota) = quota_secs(limits) else {
+            return Vec::new();
+        };
+        let mut exhausted = Vec::new();
+        for mut usage in self.usage.iter_mut() {
+            if usage.audio_streams == 0 {
+                continue;
+            }
+            let was_under = usage.listened_secs < quota;
+            usage.listened_secs = usage.listened_secs.saturating_add(elapsed_secs);
+            self.dirty.store(true, Ordering::Relaxed);
+            if was_under && usage.listened_secs >= quota {
+                usage.exhausted.send_replace(true);
+                exhausted.push(*usage.key());
+            }
+        }
+        exhausted
+    }
+}
+
+/// Today's UTC date, the key of the daily counters.
+pub fn today() -> String {
+    chrono::Utc::now().date_naive().to_string()
+}
+
+/// Holds an audio stream slot of one IP; released on drop.
+pub struct AudioQuotaGuard {
+    state: Arc<AppState>,
+    ip: IpAddr,
+    /// Turns `true` when the IP runs out of listening time.
+    pub exhausted: watch::Receiver<bool>,
+}
+
+impl Drop for AudioQuotaGuard {
+    fn drop(&mut self) {
+        self.state.quota.release_audio(self.ip);
+    }
+}
+
+pub fn acquire_audio(state: &Arc<AppState>, ip: IpAddr) -> Result<AudioQuotaGuard, QuotaRejection> {
+    let exhausted = state.quota.admit_audio(ip, &state.cfg().limits, &today())?;
+    Ok(AudioQuotaGuard {
+        state: state.clone(),
+        ip,
+        exhausted,
+    })
+}
+
+pub fn allows_waterfall(state: &AppState, ip: IpAddr) -> bool {
+    state
+        .quota
+        .allows_waterfall(ip, &state.cfg().limits, &today())
+}
+
+/// Periodic accounting pass, called from the events task every [`ACCOUNT_INTERVAL_SECS`].
+pub fn account(state: &AppState) {
+    for ip in state
+        .quota
+        .account(ACCOUNT_INTERVAL_SECS, &state.cfg().limits, &today())
+    {
+        tracing::info!(%ip, "daily listening quota reached");
+    }
+}
+
+/// Writes the usage file if anything changed since the last write.
+pub async fn persist(state: &AppState) {
+    if !state.quota.dirty.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    let path = state.config_paths.resolve(FILE_NAME);
+    let snapshot = state.quota.snapshot();
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+        let value = serde_json::to_value(&snapshot).context("serialize quota usage")?;
+        novasdr_core::config::write_json_atomic(&path, &value)
+    })
+    .await;
+    let err = match result {
+        Ok(Ok(())) => return,
+        Ok(Err(e)) => e,
+        Err(e) => anyhow::Error::new(e).context("quota persist task"),
+    };
+    tracing::error!(error = ?err, "failed to persist quota usage");
+    state.quota.dirty.store(true, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: &str = "2026-01-01";
+
+    fn limits(audio_per_ip: usize, minutes: u64, waterfall_only: bool) -> Limits {
+        Limits {
+            audio_per_ip,
+            listen_minutes_per_day: minutes,
+            quota_waterfall_only: waterfall_only,
+            ..Limits::default()
+        }
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn audio_streams_are_limited_per_ip() {
+        let ledger = QuotaLedger::default();
+        let limits = limits(2, 0, true);
+        let a = ip("192.0.2.1");
+        assert!(ledger.admit_audio(a, &limits, DAY).is_ok());
+        assert!(ledger.admit_audio(a, &limits, DAY).is_ok());
+        assert_eq!(
+            ledger.admit_audio(a, &limits, DAY).unwrap_err(),
+            QuotaRejection::TooManyStreams
+        );
+        assert!(ledger.admit_audio(ip("192.0.2.2"), &limits, DAY).is_ok());
+        ledger.release_audio(a);
+        assert!(ledger.admit_audio(a, &limits, DAY).is_ok());
+    }
+
+    #[test]
+    fn listening_time_exhausts_open_streams_and_resets_daily() {
+        let ledger = QuotaLedger::default();
+        let limits = limits(0, 1, false);
+        let a = ip("2001:db8::1");
+        let mut exhausted = ledger.admit_audio(a, &limits, DAY).unwrap();
+
+        assert!(ledger.account(30, &limits, DAY).is_empty());
+        assert!(!*exhausted.borrow_and_update());
+        assert_eq!(ledger.account(30, &limits, DAY), vec![a]);
+        assert!(*exhausted.borrow_and_update());
+        assert!(ledger.account(30, &limits, DAY).is_empty());
+
+        assert_eq!(
+            ledger.admit_audio(a, &limits, DAY).unwrap_err(),
+            QuotaRejection::ListeningTimeUsed
+        );
+        assert!(!ledger.allows_waterfall(a, &limits, DAY));
+        let waterfall_only = Limits {
+            quota_waterfall_only: true,
+            ..limits.clone()
+        };
+        assert!(ledger.allows_waterfall(a, &waterfall_only, DAY));
+
+        assert!(ledger.allows_waterfall(a, &limits, "2026-01-02"));
+        assert!(!*exhausted.borrow_and_update());
+        assert!(ledger.admit_audio(a, &limits, "2026-01-02").is_ok());
+    }
+
+    #[test]
+    fn idle_ips_are_not_charged() {
+        let ledger = QuotaLedger::default();
+        let limits = limits(0, 1, true);
+        let a = ip("192.0.2.1");
+        ledger.admit_audio(a, &limits, DAY).unwrap();
+        ledger.release_audio(a);
+        ledger.account(120, &limits, DAY);
+        assert!(ledger.admit_audio(a, &limits, DAY).is_ok());
+        assert_eq!(ledger.snapshot().listened_secs.len(), 0);
+    }
+
+    #[test]
+    fn usage_round_trips_through_file() {
+        let path = std::env::temp_dir().join(format!(
+            "novasdr_quota_{}_{}.json",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        assert_eq!(
+            QuotaLedger::load(&path).unwrap().snapshot(),
+            UsageSnapshot::default()
+        );
+
+        let ledger = QuotaLedger::default();
+        let limits = limits(0, 5, true);
+        ledger.admit_audio(ip("192.0.2.1"), &limits, DAY).unwrap();
+        ledger.admit_audio(ip("2001:db8::1"), &limits, DAY).unwrap();
+        ledger.account(40, &limits, DAY);
+        let snapshot = ledger.snapshot();
+        novasdr_core::config::write_json_atomic(&path, &serde_json::to_value(&snapshot).unwrap())
+            .unwrap();
+
+        let reloaded = QuotaLedger::load(&path).unwrap();
+        assert_eq!(reloaded.snapshot(), snapshot);
+        assert_eq!(snapshot.listened_secs.values().sum::<u64>(), 80);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{shutdown, state::AppState};
+use axum::{
+    body::Body,
+    extract::{Path as UrlPath, Query, State},
+    http::{header, HeaderValue, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+
+const DEFAULT_LIST_LIMIT: usize = 200;
+const MAX_LIST_LIMIT: usize = 1000;
+const RETENTION_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// A file under `capture.dir/<receiver_id>/`, with the `.json` sidecar of the same stem when
+/// there is one. A sidecar without its file is a recording of its own.
+#[derive(Debug, Clone)]
+struct Recording {
+    receiver_id: String,
+    name: String,
+    path: PathBuf,
+    sidecar: Option<PathBuf>,
+    /// File and sidecar together.
+    size: u64,
+    modified: SystemTime,
+}
+
+fn is_sidecar(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some("json")
+}
+
+/// All recordings under `dir`, newest first.
+fn scan(dir: &Path) -> Vec<Recording> {
+    let mut out = Vec::new();
+    let Ok(receivers) = std::fs::read_dir(dir) else {
+        return out;
+    };
+    for receiver in receivers.flatten() {
+        let Some(receiver_id) = receiver.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let Ok(files) = std::fs::read_dir(receiver.path()) else {
+            continue;
+        };
+        let mut files: Vec<(PathBuf, std::fs::Metadata)> = files
+            .flatten()
+            .filter_map(|f| Some((f.path(), f.metadata().ok()?)))
+            .filter(|(_, meta)| meta.is_file())
+            .collect();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut by_stem: BTreeMap<String, Recording> = BTreeMap::new();
+        let mut sidecars = Vec::new();
+        for (path, meta) in files {
+            let Some(name) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            if name.starts_with('.') {
+                continue;
+            }
+            if is_sidecar(&path) {
+                sidecars.push((path, meta));
+                continue;
+            }
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(&name)
+                .to_string();
+            by_stem.insert(
+                stem,
+                Recording {
+                    receiver_id: receiver_id.clone(),
+                    name,
+                    path,
+                    sidecar: None,
+                    size: meta.len(),
+                    modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                },
+            );
+        }
+        for (path, meta) in sidecars {
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+            match by_stem.get_mut(&stem) {
+                Some(rec) => {
+                    rec.size += meta.len();
+                    rec.sidecar = Some(path);
+                }
+                None => out.push(Recording {
+                    receiver_id: receiver_id.clone(),
+                    name: format!("{stem}.json"),
+                    path,
+                    sidecar: None,
+                    size: meta.len(),
+                    modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                }),
+            }
+        }
+        out.extend(by_stem.into_values());
+    }
+    out.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.name.cmp(&b.name)));
+    out
+}
+
+/// Indices into `recordings` (newest first, as `(modified, size)`) that the retention policy
+/// removes: everything older than `max_age`, then the oldest until the rest fits in `max_total`.
+fn expired(
+    recordings: &[(SystemTime, u64)],
+    now: SystemTime,
+    max_age: Option<Duration>,
+    max_total: Option<u64>,
+) -> Vec<usize> {
+    let mut total = 0u64;
+    recordings
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &(modified, size))| {
+            let too_old =
+                max_age.is_some_and(|max| now.duration_since(modified).is_ok_and(|age| age > max));
+            if too_old {
+                return Some(i);
+            }
+            total = total.saturating_add(size);
+            max_total.is_some_and(|max| total > max).then_some(i)
+        })
+        .collect()
+}
+
+fn remove_expired(dir: &Path, max_age_days: u64, max_total_mb: u64) {
+    let recordings = scan(dir);
+    let keys: Vec<(SystemTime, u64)> = recordings.iter().map(|r| (r.modified, r.size)).collect();
+    let max_age = (max_age_days > 0).then(|| Duration::from_secs(max_age_days * 86_400));
+    let max_total = (max_total_mb > 0).then(|| max_total_mb * 1024 * 1024);
+    for i in expired(&keys, SystemTime::now(), max_age, max_total) {
+        let rec = &recordings[i];
+        for path in std::iter::once(&rec.path).chain(rec.sidecar.as_ref()) {
+            match std::fs::remove_file(path) {
+                Ok(()) => tracing::info!(path = %path.display(), "recording expired"),
+                Err(e) => tracing::warn!(path = %path.display(), error = %e, "remove recording"),
+            }
+        }
+    }
+}
+
+/// Deletes the oldest recording under `dir` with its sidecar, for the disk guard. Returns the
+/// bytes freed, `None` when there was nothing left to delete.
+pub fn remove_oldest(dir: &Path) -> Option<u64> {
+    let rec = scan(dir).pop()?;
+    for path in std::iter::once(&rec.path).chain(rec.sidecar.as_ref()) {
+        match std::fs::remove_file(path) {
+            Ok(()) => tracing::warn!(path = %path.display(), "recording deleted for disk space"),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "remove recording");
+                return None;
+            }
+        }
+    }
+    Some(rec.size)
+}
+
+/// Applies `recordings.max_age_days` and `recordings.max_total_mb` to `capture.dir` at start
+/// and every hour after.
+pub fn spawn_retention(state: Arc<AppState>) {
+    let cfg = state.cfg().recordings.clone();
+    if cfg.max_age_days == 0 && cfg.max_total_mb == 0 {
+        return;
+    }
+    let dir = PathBuf::from(&state.cfg().capture.dir);
+    tracing::info!(
+        dir = %dir.display(),
+        max_age_days = cfg.max_age_days,
+        max_total_mb = cfg.max_total_mb,
+        "recording retention enabled"
+    );
+    tokio::spawn(async move {
+        while !shutdown::is_shutdown_requested() {
+            let dir = dir.clone();
+            let res = tokio::task::spawn_blocking(move || {
+                remove_expired(&dir, cfg.max_age_days, cfg.max_total_mb)
+            })
+            .await;
+            if let Err(e) = res {
+                tracing::error!(error = ?e, "recording retention task panicked");
+            }
+            tokio::time::sleep(RETENTION_INTERVAL).await;
+        }
+    });
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListQuery {
+    #[serde(default)]
+    receiver_id: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+fn read_sidecar(path: &Path) -> serde_json::Value {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or(serde_json::Value::Null)
+}
+
+pub async fn list(State(state): State<Arc<AppState>>, Query(query): Query<ListQuery>) -> Response {
+    if !state.cfg().recordings.enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let dir = PathBuf::from(&state.cfg().capture.dir);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .min(MAX_LIST_LIMIT);
+    let res = tokio::task::spawn_blocking(move || {
+        let mut recordings = scan(&dir);
+        if let Some(id) = query.receiver_id.as_deref() {
+            recordings.retain(|r| r.receiver_id == id);
+        }
+        let total = recordings.len();
+        let items: Vec<serde_json::Value> = recordings
+            .into_iter()
+            .take(limit)
+            .map(|r| {
+                let modified = chrono::DateTime::<chrono::Utc>::from(r.modified);
+                json!({
+                    "id": format!("{}/{}", r.receiver_id, r.name),
+                    "receiver_id": r.receiver_id,
+                    "name": r.name,
+                    "size": r.size,
+                    "modified": modified.to_rfc3339(),
+                    "metadata": r.sidecar.as_deref().map_or(serde_json::Value::Null, read_sidecar),
+                })
+            })
+            .collect();
+        json!({ "total": total, "recordings": items })
+    })
+    .await;
+    match res {
+        Ok(body) => Json(body).into_response(),
+        Err(e) => {
+            tracing::error!(error = ?e, "recordings listing task panicked");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// A single plain file or directory name: no separators, no `.`/`..`, no hidden files.
+fn plain_component(s: &str) -> bool {
+    !s.is_empty() && !s.starts_with('.') && !s.contains(['/', '\\', '\0'])
+}
+
+/// Serves one file, with `Range`, `If-Modified-Since` and a download name.
+pub async fn download(
+    State(state): State<Arc<AppState>>,
+    UrlPath((receiver_id, name)): UrlPath<(String, String)>,
+    req: Request<Body>,
+) -> Response {
+    if !state.cfg().recordings.enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    if !plain_component(&receiver_id) || !plain_component(&name) {
+        return (StatusCode::BAD_REQUEST, "invalid recording id").into_response();
+    }
+    let path = PathBuf::from(&state.cfg().capture.dir)
+        .join(&receiver_id)
+        .join(&name);
+    let mut response = match ServeFile::new(path).oneshot(req).await {
+        Ok(r) => r.map(Body::new),
+        Err(e) => match e {},
+    };
+    if response.status().is_success() {
+        let disposition = format!("attachment; filename=\"{}\"", name.replace('"', "_"));
+        if let Ok(v) = HeaderValue::from_str(&disposition) {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_DISPOSITION, v);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retention_drops_old_files_then_oldest_over_budget() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 86_400);
+        let days_ago = |d: u64| now - Duration::from_secs(d * 86_400);
+        let newest_first = [
+            (days_ago(0), 40),
+            (days_ago(1), 40),
+            (days_ago(2), 40),
+            (days_ago(10), 1),
+        ];
+        assert!(expired(&newest_first, now, None, None).is_empty());
+        let week = Some(Duration::from_secs(7 * 86_400));
+        assert_eq!(expired(&newest_first, now, week, None), vec![3]);
+        assert_eq!(expired(&newest_first, now, None, Some(100)), vec![2, 3]);
+        assert_eq!(expired(&newest_first, now, week, Some(80)), vec![2, 3]);
+    }
+
+    #[test]
+    fn recording_ids_are_single_components() {
+        assert!(plain_component("rx0"));
+        assert!(plain_component("20260101-beacon.png"));
+        assert!(!plain_component(".."));
+        assert!(!plain_component(".hidden"));
+        assert!(!plain_component("a/b"));
+        assert!(!plain_component("a\\b"));
+        assert!(!plain_component(""));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{shutdown, state::AppState};
+use crate::{identity, shutdown, state::AppState};
 use anyhow::Context;
 use reqwest::header::{HeaderMap, HeaderValue, HOST, USER_AGENT};
 use serde::Serialize;
@@ -27,15 +27,19 @@ struct SdrListUpdate {
     receiver_id: String,
     range_start_hz: i64,
     range_end_hz: i64,
+    maintenance: bool,
+    /// Signature over the advertised metadata when `websdr.identity_key` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    identity: Option<identity::IdentityProof>,
 }
 
 pub fn spawn(state: Arc<AppState>) {
-    if !state.cfg.websdr.register_online {
+    if !state.cfg().websdr.register_online {
         tracing::info!("SDR list registration disabled (set websdr.register_online=true)");
         return;
     }
 
-    let url = state.cfg.websdr.register_url.clone();
+    let url = state.cfg().websdr.register_url.clone();
     tracing::info!(%url, "SDR list registration enabled");
 
     tokio::spawn(async move {
@@ -73,7 +77,7 @@ pub fn spawn(state: Arc<AppState>) {
 }
 
 fn build_payloads(state: &AppState, id: &str) -> Vec<SdrListUpdate> {
-    let cfg = &state.cfg;
+    let cfg = state.cfg();
     let receiver_count = state
         .receivers
         .values()
@@ -91,13 +95,16 @@ fn build_payloads(state: &AppState, id: &str) -> Vec<SdrListUpdate> {
     if enabled_receivers.is_empty() {
         enabled_receivers.push(state.active_receiver_state());
     }
+    let proof = state
+        .identity
+        .as_deref()
+        .map(|id| identity::sign_server_metadata(state, id));
 
     enabled_receivers
         .into_iter()
         .map(|receiver| {
-            let rt = receiver.rt.as_ref();
-            let range_start_hz = rt.basefreq;
-            let range_end_hz = rt.basefreq.saturating_add(rt.total_bandwidth);
+            let range_start_hz = receiver.basefreq();
+            let range_end_hz = range_start_hz.saturating_add(receiver.rt.total_bandwidth);
             let bandwidth = range_end_hz.saturating_sub(range_start_hz);
             let center_frequency = range_start_hz.saturating_add(bandwidth / 2);
 
@@ -110,7 +117,11 @@ fn build_payloads(state: &AppState, id: &str) -> Vec<SdrListUpdate> {
                 center_frequency,
                 grid_locator: cfg.websdr.grid_locator.clone(),
                 hostname: cfg.websdr.hostname.clone(),
-                max_users: cfg.limits.audio,
+                max_users: receiver
+                    .settings()
+                    .limits
+                    .audio
+                    .map_or(cfg.limits.audio, |cap| cap.min(cfg.limits.audio)),
                 port: cfg.websdr.public_port.unwrap_or(cfg.server.port),
                 software: "NovaSDR".to_string(),
                 backend: "novasdr-server".to_string(),
@@ -119,6 +130,8 @@ fn build_payloads(state: &AppState, id: &str) -> Vec<SdrListUpdate> {
                 receiver_id: receiver.receiver.id.clone(),
                 range_start_hz,
                 range_end_hz,
+                maintenance: receiver.maintenance().enabled,
+                identity: proof.clone(),
             }
         })
         .collect()
@@ -190,6 +203,63 @@ fn compute_backoff(attempt: u32) -> Duration {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::ConfigPaths;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+    use ring::signature::{UnparsedPublicKey, ED25519};
+
+    #[test]
+    fn payloads_are_signed_with_the_current_time() {
+        let dir = std::env::temp_dir().join(format!(
+            "novasdr_registration_{}",
+            novasdr_core::util::generate_unique_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths = ConfigPaths {
+            config: dir.join("config.json"),
+            receivers: dir.join("receivers.json"),
+            overlays: dir.join("overlays"),
+        };
+        std::fs::write(
+            &paths.config,
+            r#"{
+  "server": { "port": 9002 },
+  "websdr": { "name": "Test SDR", "hostname": "sdr.example.org", "identity_key": "identity.key" },
+  "active_receiver_id": "rx0"
+}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &paths.receivers,
+            r#"{ "receivers": [
+  { "id": "rx0", "input": { "sps": 2048000, "frequency": 100000000, "signal": "iq",
+      "driver": { "kind": "stdin", "format": "u8" } } }
+] }"#,
+        )
+        .unwrap();
+        let cfg = novasdr_core::config::load_from_files(&paths.config, &paths.receivers).unwrap();
+        let state = AppState::new(Arc::new(cfg), dir.clone(), paths).unwrap();
+
+        let before = novasdr_core::clock::now().timestamp();
+        let payloads = build_payloads(&state, "1");
+        let after = novasdr_core::clock::now().timestamp();
+        let proof = payloads[0].identity.clone().expect("signed payload");
+        assert!((before..=after).contains(&proof.signed_at));
+
+        let metadata = identity::SignedMetadata {
+            hostname: "sdr.example.org",
+            port: 9002,
+            name: "Test SDR",
+            signed_at: proof.signed_at,
+        };
+        let verifier = UnparsedPublicKey::new(&ED25519, BASE64.decode(&proof.public_key).unwrap());
+        assert!(verifier
+            .verify(
+                metadata.message().as_bytes(),
+                &BASE64.decode(&proof.signature).unwrap()
+            )
+            .is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 
     #[test]
     fn backoff_is_monotonic_and_capped() {
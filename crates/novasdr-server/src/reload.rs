@@ -0,0 +1,203 @@
+//! Reloading `config.json` and `receivers.json` without a restart, on SIGHUP or
+//! `POST /admin/reload`. Sections that are read on use apply at once, as do the name, caps,
+//! maintenance notice, default tune, band schedule and UDP output of a receiver. A receiver whose input
+//! driver changed reopens its input; its listeners stay connected. Anything else is reported as
+//! needing a restart and keeps its current value until then.
+
+use crate::state::{AppState, ReceiverSettings, ReceiverState};
+use anyhow::Context;
+use novasdr_core::config::{self, ReceiverConfig, ReceiverReload, WebSdr};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Sections of `config.json` read on use rather than at startup.
+pub const LIVE_SECTIONS: [&str; 8] = [
+    "websdr",
+    "limits",
+    "admin",
+    "capture",
+    "time_shift",
+    "disk_guard",
+    "diversity",
+    "crash_reports",
+];
+
+/// One reload at a time; a second SIGHUP waits for the first to finish.
+static RELOADING: Mutex<()> = Mutex::const_new(());
+
+/// What a reload did, as returned by the admin API.
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    /// Sections of `config.json` whose new settings are in effect.
+    pub applied: Vec<&'static str>,
+    /// Per receiver: `unchanged`, `updated`, `input_restarted` or `restart_required`.
+    pub receivers: BTreeMap<String, &'static str>,
+    /// Changed settings that keep their old value until the server restarts.
+    pub restart_required: Vec<String>,
+}
+
+/// A change to one running receiver, worked out before any of them is applied.
+struct ReceiverChange {
+    receiver: Arc<ReceiverState>,
+    next: ReceiverConfig,
+    settings: ReceiverSettings,
+    restart_input: bool,
+}
+
+/// Reads both files again and applies what can change while running. A file that fails to
+/// load or validate leaves everything as it was.
+pub async fn reload(state: &AppState) -> anyhow::Result<Summary> {
+    let _guard = RELOADING.lock().await;
+    let paths = state.config_paths.clone();
+    let next = tokio::task::spawn_blocking(move || {
+        config::load_from_files(&paths.config, &paths.receivers)
+    })
+    .await
+    .context("config reload task")??;
+    let current = state.cfg();
+    let mut applied = (*current).clone();
+    let mut summary = Summary::default();
+
+    for section in current.changed_sections(&next) {
+        if !LIVE_SECTIONS.contains(&section) {
+            summary.restart_required.push(section.to_string());
+            continue;
+        }
+        match section {
+            "websdr" => {
+                // The key is loaded once; clients pin the public key they saw first.
+                applied.websdr = WebSdr {
+                    identity_key: current.websdr.identity_key.clone(),
+                    ..next.websdr.clone()
+                };
+                if applied.websdr != next.websdr {
+                    summary
+                        .restart_required
+                        .push("websdr.identity_key".to_string());
+                }
+            }
+            "limits" => applied.limits = next.limits.clone(),
+            "admin" => applied.admin = next.admin.clone(),
+            "capture" => applied.capture = next.capture.clone(),
+            "time_shift" => applied.time_shift = next.time_shift.clone(),
+            "disk_guard" => applied.disk_guard = next.disk_guard.clone(),
+            "diversity" => applied.diversity = next.diversity.clone(),
+            "crash_reports" => applied.crash_reports = next.crash_reports.clone(),
+            _ => {}
+        }
+        summary.applied.push(section);
+    }
+
+    let mut changes = Vec::new();
+    for old in current.receivers.iter() {
+        if !next.receivers.iter().any(|r| r.id == old.id) {
+            summary.receivers.insert(old.id.clone(), "restart_required");
+            summary
+                .restart_required
+                .push(format!("receivers.{}", old.id));
+        }
+    }
+    for new in next.receivers.iter() {
+        let old = current.receivers.iter().find(|r| r.id == new.id);
+        let (Some(old), Some(receiver)) = (old, state.receiver_state(new.id.as_str())) else {
+            summary.receivers.insert(new.id.clone(), "restart_required");
+            summary
+                .restart_required
+                .push(format!("receivers.{}", new.id));
+            continue;
+        };
+        let outcome = match old.reload_to(new) {
+            ReceiverReload::Unchanged => "unchanged",
+            ReceiverReload::Settings => "updated",
+            ReceiverReload::Input if new.enabled => "input_restarted",
+            ReceiverReload::Input => "updated",
+            ReceiverReload::Restart => {
+                summary
+                    .restart_required
+                    .push(format!("receivers.{}", new.id));
+                "restart_required"
+            }
+        };
+        summary.receivers.insert(new.id.clone(), outcome);
+        if !matches!(outcome, "updated" | "input_restarted") {
+            continue;
+        }
+        let rt = next
+            .runtime_for(new.id.as_str())
+            .with_context(|| format!("derive runtime for receiver {}", new.id))?;
+        changes.push(ReceiverChange {
+            receiver: receiver.clone(),
+            next: new.clone(),
+            settings: ReceiverSettings::new(new, &rt),
+            restart_input: outcome == "input_restarted",
+        });
+    }
+
+    for change in changes {
+        let id = change.next.id.as_str();
+        let old = current.receivers.iter().find(|r| r.id == id);
+        if old.is_some_and(|old| old.maintenance != change.next.maintenance) {
+            change
+                .receiver
+                .set_maintenance(change.next.maintenance.clone());
+        }
+        if old.is_some_and(|old| old.udp_output != change.next.udp_output) {
+            change
+                .receiver
+                .udp_output
+                .set(change.next.udp_output.clone());
+        }
+        change.receiver.set_settings(change.settings);
+        if change.restart_input {
+            // The band the schedule moved the receiver to, not `input.frequency`.
+            let mut next = change.next.clone();
+            next.input.frequency = change.receiver.center_frequency();
+            change.receiver.request_input_restart(next);
+        }
+        if let Some(entry) = applied.receivers.iter_mut().find(|r| r.id == id) {
+            *entry = change.next;
+        }
+    }
+    crate::crash::configure(&applied);
+    state.set_cfg(Arc::new(applied));
+    state.broadcast_event_info(false);
+
+    tracing::info!(
+        applied = ?summary.applied,
+        receivers = ?summary.receivers,
+        restart_required = ?summary.restart_required,
+        "configuration reloaded"
+    );
+    if !summary.restart_required.is_empty() {
+        tracing::warn!(
+            settings = ?summary.restart_required,
+            "some changed settings take effect only after a restart"
+        );
+    }
+    Ok(summary)
+}
+
+/// Reloads the configuration on every SIGHUP.
+pub fn spawn_signal_listener(state: Arc<AppState>) {
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!(error = ?e, "cannot listen for SIGHUP; reload through the admin API");
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            tracing::info!("SIGHUP received; reloading configuration");
+            if let Err(e) = reload(&state).await {
+                tracing::error!(error = ?e, "configuration reload failed; nothing changed");
+            }
+        }
+    });
+    #[cfg(not(unix))]
+    let _ = state;
+}
@@ -0,0 +1,262 @@
+use crate::state::{AppState, AudioParams, ClientId, IqFrame, ReceiverState};
+use crate::ws::audio_pool::{self, PipelineShape};
+use novasdr_core::config::{ReceiverConfig, Replay as ReplayConfig, Runtime};
+use novasdr_core::sstv;
+use num_complex::Complex32;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+/// Most recent rotated bins of one receiver (`receivers[].replay`), so an IQ consumer started
+/// now can first work through the seconds before it started.
+pub struct Replay {
+    capacity: usize,
+    /// Signal time one frame adds, in seconds.
+    frame_secs: f64,
+    frames: std::sync::Mutex<VecDeque<IqFrame>>,
+}
+
+impl Replay {
+    pub fn new(receiver: &ReceiverConfig, rt: &Runtime) -> Self {
+        let frame_secs = (rt.fft_size / 2) as f64 / rt.sps as f64;
+        let frame_bytes = rt.fft_result_size * std::mem::size_of::<Complex32>();
+        let capacity = capacity(&receiver.replay, frame_secs, frame_bytes);
+        if capacity > 0 {
+            tracing::info!(
+                receiver_id = %receiver.id,
+                seconds = capacity as f64 * frame_secs,
+                mb = (capacity * frame_bytes) >> 20,
+                "iq replay buffer enabled"
+            );
+        }
+        Self {
+            capacity,
+            frame_secs,
+            frames: std::sync::Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<IqFrame>> {
+        match self.frames.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("iq replay mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Frames the buffer holds when full.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn frame_secs(&self) -> f64 {
+        self.frame_secs
+    }
+
+    /// Adds one frame, dropping the oldest once full. Called by the DSP thread before the frame
+    /// goes out to `iq_clients`.
+    pub fn push(&self, frame_num: u64, bins: &Arc<[Complex32]>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut frames = self.lock();
+        if frames.len() >= self.capacity {
+            frames.pop_front();
+        }
+        frames.push_back(IqFrame {
+            frame_num,
+            bins: bins.clone(),
+        });
+    }
+
+    /// Buffered frames, oldest first.
+    pub fn frames(&self) -> Vec<IqFrame> {
+        self.lock().iter().cloned().collect()
+    }
+}
+
+/// Frames covering `seconds`, fewer when they would take more than `max_mb`.
+fn capacity(cfg: &ReplayConfig, frame_secs: f64, frame_bytes: usize) -> usize {
+    if cfg.seconds == 0 || frame_secs <= 0.0 {
+        return 0;
+    }
+    let wanted = (f64::from(cfg.seconds) / frame_secs).ceil() as usize;
+    let affordable = (cfg.max_mb as usize) * (1 << 20) / frame_bytes.max(1);
+    wanted.min(affordable)
+}
+
+/// An `iq_clients` entry that, when asked to replay, first yields the frames buffered before
+/// it subscribed. Unsubscribes when dropped.
+pub struct IqSubscription {
+    receiver: Arc<ReceiverState>,
+    client_id: ClientId,
+    history: std::vec::IntoIter<IqFrame>,
+    /// Live frames up to this one were already yielded from the buffer.
+    replayed_until: Option<u64>,
+    replayed: usize,
+    frames: mpsc::Receiver<IqFrame>,
+}
+
+impl IqSubscription {
+    pub fn new(state: &AppState, receiver: &Arc<ReceiverState>, replay: bool) -> Self {
+        let backlog = if replay {
+            receiver.replay.capacity()
+        } else {
+            0
+        };
+        let (tx, frames) = crate::state::iq_frame_channel_with_backlog(backlog);
+        let client_id = state.alloc_client_id();
+        // Registered before the buffer is read: the DSP thread buffers each frame before
+        // sending it, so every frame is in the buffer, in the queue, or both.
+        receiver.iq_clients.insert(client_id, tx);
+        let history = if replay {
+            receiver.replay.frames()
+        } else {
+            Vec::new()
+        };
+        Self {
+            receiver: receiver.clone(),
+            client_id,
+            replayed_until: history.last().map(|f| f.frame_num),
+            replayed: history.len(),
+            history: history.into_iter(),
+            frames,
+        }
+    }
+
+    /// Seconds of signal from before the subscription.
+    pub fn replayed_secs(&self) -> f64 {
+        self.replayed as f64 * self.receiver.replay.frame_secs()
+    }
+
+    /// Next frame in order; `None` once the receiver stops publishing.
+    pub async fn recv(&mut self) -> Option<IqFrame> {
+        if let Some(frame) = self.history.next() {
+            return Some(frame);
+        }
+        loop {
+            let frame = self.frames.recv().await?;
+            if self
+                .replayed_until
+                .is_some_and(|last| frame.frame_num <= last)
+            {
+                continue;
+            }
+            return Some(frame);
+        }
+    }
+
+    /// Like [`Self::recv`], but `None` as soon as no frame is waiting.
+    pub fn try_recv(&mut self) -> Option<IqFrame> {
+        if let Some(frame) = self.history.next() {
+            return Some(frame);
+        }
+        loop {
+            let frame = self.frames.try_recv().ok()?;
+            if self
+                .replayed_until
+                .is_some_and(|last| frame.frame_num <= last)
+            {
+                continue;
+            }
+            return Some(frame);
+        }
+    }
+}
+
+impl Drop for IqSubscription {
+    fn drop(&mut self) {
+        self.receiver.iq_clients.remove(&self.client_id);
+    }
+}
+
+/// Runs the receiver's buffered frames, and any that arrived meanwhile, through an SSTV
+/// decoder at the listener's tune, and sends it with the last frame number it saw to the
+/// listener's pipeline (`AudioPipeline::expect_primed_sstv`). Dropping `primed` instead tells
+/// the pipeline to start from scratch.
+pub fn spawn_sstv_preroll(
+    state: Arc<AppState>,
+    receiver: Arc<ReceiverState>,
+    params: AudioParams,
+    primed: oneshot::Sender<(sstv::Decoder, u64)>,
+) {
+    tokio::spawn(async move {
+        let shape = PipelineShape::of(&receiver);
+        let mut pipeline = match audio_pool::acquire(&state, shape).await {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::warn!(error = ?e, "no pipeline for the sstv pre-roll");
+                return;
+            }
+        };
+        let mut frames = IqSubscription::new(&state, &receiver, true);
+        let rt = receiver.rt.clone();
+        let res = tokio::task::spawn_blocking(move || {
+            let res = sstv_preroll(&mut pipeline, &rt, params, &mut frames);
+            (pipeline, res)
+        })
+        .await;
+        let Ok((pipeline, res)) = res else {
+            return;
+        };
+        state.audio_pipelines.release(shape, pipeline);
+        if let Some((decoder, fed_until, seconds)) = res {
+            tracing::debug!(
+                receiver_id = %receiver.receiver.id,
+                seconds,
+                "sstv decoder primed from the replay buffer"
+            );
+            let _ = primed.send((decoder, fed_until));
+        }
+    });
+}
+
+/// The decoder, the last frame it was fed and the seconds of signal that took.
+fn sstv_preroll(
+    pipeline: &mut crate::ws::audio::AudioPipeline,
+    rt: &Runtime,
+    mut params: AudioParams,
+    frames: &mut IqSubscription,
+) -> Option<(sstv::Decoder, u64, f64)> {
+    // The squelch would need the same frames to settle; SSTV is decoded below it anyway.
+    params.squelch_enabled = false;
+    params.sstv = false;
+    let mut decoder = sstv::Decoder::new(pipeline.audio_rate() as f64);
+    let mut fed_until = None;
+    let mut count = 0usize;
+    while let Some(frame) = frames.try_recv() {
+        let (l, r) = crate::dsp_runner::audio_window(rt, &mut params, frame.bins.len())?;
+        let mid = params.m.floor() as i32;
+        if pipeline.demodulate(&frame.bins[l..r], frame.frame_num, &params, rt.is_real, mid) {
+            decoder.process(pipeline.demodulated().0);
+        }
+        fed_until = Some(frame.frame_num);
+        count += 1;
+    }
+    let seconds = count as f64 * (rt.fft_size / 2) as f64 / rt.sps as f64;
+    Some((decoder, fed_until?, seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_is_capped_by_memory() {
+        let cfg = ReplayConfig {
+            seconds: 10,
+            max_mb: 1,
+        };
+        // 0.1 s frames of 64 KiB: 100 frames wanted, 16 fit in 1 MiB.
+        assert_eq!(capacity(&cfg, 0.1, 64 << 10), 16);
+        assert_eq!(capacity(&cfg, 0.1, 1024), 100);
+        let off = ReplayConfig { seconds: 0, ..cfg };
+        assert_eq!(capacity(&off, 0.1, 1024), 0);
+    }
+}
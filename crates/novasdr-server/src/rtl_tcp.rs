@@ -0,0 +1,247 @@
+use crate::state::{AppState, ClientId, IqFrame, ReceiverState};
+use novasdr_core::dsp::iq_extract::IqExtractor;
+use num_complex::Complex32;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{tcp::OwnedReadHalf, TcpListener, TcpStream};
+use tokio::sync::{mpsc, Semaphore};
+
+/// Tuner type announced in the greeting; R820T is what clients know best.
+const TUNER_R820T: u32 = 5;
+const TUNER_GAIN_COUNT: u32 = 29;
+/// Rate a client gets until it sets one.
+const DEFAULT_SAMPLE_RATE: u32 = 2_048_000;
+
+const CMD_SET_FREQUENCY: u8 = 0x01;
+const CMD_SET_SAMPLE_RATE: u8 = 0x02;
+
+/// Listens for rtl_tcp clients (SDR#, SDR++, GQRX, HDSDR, ...) when `rtl_tcp.enabled`.
+pub fn spawn(state: Arc<AppState>) {
+    let cfg = state.cfg().rtl_tcp.clone();
+    if !cfg.enabled {
+        return;
+    }
+    let receiver_id = if cfg.receiver_id.trim().is_empty() {
+        state.active_receiver_id().to_string()
+    } else {
+        cfg.receiver_id.trim().to_string()
+    };
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        tracing::error!(receiver_id = %receiver_id, "rtl_tcp disabled: unknown receiver");
+        return;
+    };
+    let slots = Arc::new(Semaphore::new(cfg.max_clients));
+
+    tokio::spawn(async move {
+        let addr = format!("{}:{}", cfg.host, cfg.port);
+        let listener = match TcpListener::bind(addr.as_str()).await {
+            Ok(l) => l,
+            Err(e) => {
+                tracing::error!(bind = %addr, error = ?e, "rtl_tcp disabled: cannot bind");
+                return;
+            }
+        };
+        tracing::info!(bind = %addr, receiver_id = %receiver_id, "rtl_tcp listening");
+        loop {
+            let (socket, peer) = match listener.accept().await {
+                Ok(v) => v,
+                Err(e) => {
+                    tracing::warn!(error = ?e, "rtl_tcp accept failed");
+                    continue;
+                }
+            };
+            let Ok(slot) = slots.clone().try_acquire_owned() else {
+                tracing::info!(%peer, "rtl_tcp client refused: all slots in use");
+                continue;
+            };
+            let ip_guard = match state.try_acquire_ws_ip(peer.ip()) {
+                Ok(guard) => guard,
+                Err(_) => {
+                    tracing::info!(%peer, "rtl_tcp client refused by connection limits");
+                    continue;
+                }
+            };
+            let state = state.clone();
+            let receiver = receiver.clone();
+            let max_sample_rate = cfg.max_sample_rate;
+            tokio::spawn(async move {
+                let _slot = slot;
+                let _ip_guard = ip_guard;
+                let client_id = state.alloc_client_id();
+                tracing::info!(client_id, %peer, "rtl_tcp client connected");
+                if let Err(e) =
+                    serve_client(socket, &receiver, client_id, peer, max_sample_rate).await
+                {
+                    tracing::debug!(client_id, error = ?e, "rtl_tcp client ended");
+                }
+                receiver.iq_clients.remove(&client_id);
+                tracing::info!(client_id, %peer, "rtl_tcp client disconnected");
+            });
+        }
+    });
+}
+
+/// Frequency and rate a client asked for, mapped onto the receiver's bins.
+pub struct Tuning {
+    /// Nearest bin to the tuned frequency, in the rotated spectrum.
+    center: i64,
+    /// Remainder in bins, removed by a fine frequency shift.
+    fraction: f64,
+    extractor: IqExtractor,
+    /// Phase of the fine shift, in cycles.
+    phase: f64,
+}
+
+impl Tuning {
+    pub fn new(receiver: &ReceiverState, frequency: i64, sample_rate: u32, max_rate: u32) -> Self {
+        let rt = &receiver.rt;
+        let bin_hz = bin_hz(receiver);
+        let size = iq_size(sample_rate, max_rate, bin_hz, rt.fft_result_size);
+        // Complex input is rotated from bin N/2 + 1, so rotated index i sits at
+        // `basefreq + (i + 1) * bin_hz`; real input starts at `basefreq` itself.
+        let offset = if rt.is_real { 0.0 } else { 1.0 };
+        let exact = (frequency - receiver.basefreq()) as f64 / bin_hz - offset;
+        let center = exact.round() as i64;
+        Self {
+            center,
+            fraction: exact - center as f64,
+            extractor: IqExtractor::new(size, rt.fft_size),
+            phase: 0.0,
+        }
+    }
+
+    /// Samples per second of the IQ this tuning yields.
+    pub fn sample_rate(&self, receiver: &ReceiverState) -> f64 {
+        self.extractor.size() as f64 * bin_hz(receiver)
+    }
+
+    /// Replaces `iq` with the samples of one frame, centred on the tuned frequency.
+    pub fn process(&mut self, frame: &IqFrame, is_real: bool, iq: &mut Vec<Complex32>) {
+        iq.clear();
+        self.extractor
+            .process(&frame.bins, self.center, is_real, frame.frame_num, iq);
+        let step = -self.fraction / self.extractor.size() as f64;
+        for s in iq.iter_mut() {
+            let (sin, cos) = (std::f64::consts::TAU * self.phase).sin_cos();
+            *s *= Complex32::new(cos as f32, sin as f32);
+            self.phase = (self.phase + step).rem_euclid(1.0);
+        }
+    }
+}
+
+fn bin_hz(receiver: &ReceiverState) -> f64 {
+    receiver.rt.total_bandwidth as f64 / receiver.rt.fft_result_size as f64
+}
+
+/// Even bin count whose IQ rate is closest to `sample_rate`, within the receiver's bins and
+/// `max_rate`. The rate the client gets is off by less than one bin's width.
+fn iq_size(sample_rate: u32, max_rate: u32, bin_hz: f64, bins: usize) -> usize {
+    let rate = sample_rate.min(max_rate) as f64;
+    let max_size = ((max_rate as f64 / bin_hz) as usize).min(bins) & !1;
+    (((rate / bin_hz / 2.0).round() as usize) * 2).clamp(2, max_size.max(2))
+}
+
+async fn serve_client(
+    socket: TcpStream,
+    receiver: &Arc<ReceiverState>,
+    client_id: ClientId,
+    peer: SocketAddr,
+    max_sample_rate: u32,
+) -> anyhow::Result<()> {
+    socket.set_nodelay(true)?;
+    let (reader, mut writer) = socket.into_split();
+
+    let mut greeting = Vec::with_capacity(12);
+    greeting.extend_from_slice(b"RTL0");
+    greeting.extend_from_slice(&TUNER_R820T.to_be_bytes());
+    greeting.extend_from_slice(&TUNER_GAIN_COUNT.to_be_bytes());
+    writer.write_all(&greeting).await?;
+
+    let (commands_tx, mut commands) = mpsc::channel::<(u8, u32)>(16);
+    let read_task = tokio::spawn(read_commands(reader, commands_tx));
+
+    let rt = receiver.rt.clone();
+    let mut frequency = receiver.basefreq() + rt.total_bandwidth / 2;
+    let mut sample_rate = DEFAULT_SAMPLE_RATE;
+    let mut tuning = Tuning::new(receiver, frequency, sample_rate, max_sample_rate);
+
+    let (tx, mut frames) = crate::state::iq_frame_channel();
+    receiver.iq_clients.insert(client_id, tx);
+
+    let mut iq: Vec<Complex32> = Vec::new();
+    let mut out: Vec<u8> = Vec::new();
+    let result = loop {
+        tokio::select! {
+            cmd = commands.recv() => {
+                let Some((cmd, param)) = cmd else {
+                    break Ok(());
+                };
+                match cmd {
+                    CMD_SET_FREQUENCY => frequency = i64::from(param),
+                    CMD_SET_SAMPLE_RATE => sample_rate = param,
+                    // Gain, AGC, PPM and the rest belong to the operator's hardware.
+                    _ => continue,
+                }
+                tuning = Tuning::new(receiver, frequency, sample_rate, max_sample_rate);
+                tracing::debug!(
+                    client_id,
+                    %peer,
+                    frequency,
+                    requested_rate = sample_rate,
+                    rate = tuning.sample_rate(receiver),
+                    "rtl_tcp tuned"
+                );
+            }
+            frame = frames.recv() => {
+                let Some(frame) = frame else {
+                    break Ok(());
+                };
+                tuning.process(&frame, rt.is_real, &mut iq);
+                out.clear();
+                for s in iq.iter() {
+                    out.push(to_u8(s.re));
+                    out.push(to_u8(s.im));
+                }
+                if let Err(e) = writer.write_all(&out).await {
+                    break Err(e.into());
+                }
+            }
+        }
+    };
+    read_task.abort();
+    result
+}
+
+async fn read_commands(mut reader: OwnedReadHalf, commands: mpsc::Sender<(u8, u32)>) {
+    let mut buf = [0u8; 5];
+    while reader.read_exact(&mut buf).await.is_ok() {
+        let param = u32::from_be_bytes([buf[1], buf[2], buf[3], buf[4]]);
+        if commands.send((buf[0], param)).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn to_u8(v: f32) -> u8 {
+    (v * 127.5 + 127.5).round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_rates_round_to_even_bin_counts_within_limits() {
+        // 2.048 MS/s over 131072 bins: 15.625 Hz per bin.
+        let bin_hz = 15.625;
+        assert_eq!(iq_size(2_048_000, 2_048_000, bin_hz, 131_072), 131_072);
+        assert_eq!(iq_size(250_000, 2_048_000, bin_hz, 131_072), 16_000);
+        // Above the configured ceiling or the receiver's band: clamped.
+        assert_eq!(iq_size(3_200_000, 1_024_000, bin_hz, 131_072), 65_536);
+        assert_eq!(iq_size(2_048_000, 8_000_000, bin_hz, 65_536), 65_536);
+        // 1 kS/s is 64 bins; rounding keeps the count even.
+        assert_eq!(iq_size(1_000, 2_048_000, bin_hz, 131_072), 64);
+        assert_eq!(iq_size(1_020, 2_048_000, bin_hz, 131_072), 66);
+    }
+}
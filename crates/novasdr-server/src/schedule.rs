@@ -0,0 +1,609 @@
+use crate::audio_listener::HeadlessListener;
+use crate::replay::IqSubscription;
+use crate::rtl_tcp::Tuning;
+use crate::{shutdown, state::AppState, state::ReceiverState};
+use anyhow::Context;
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDateTime, NaiveTime, Utc, Weekday};
+use novasdr_core::clock::{DailyTime, Zone};
+use novasdr_core::codec::wav;
+use novasdr_core::dsp::demod::DemodulationMode;
+use num_complex::Complex32;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt, BufWriter};
+
+/// Recording schedule, resolved next to `receivers.json`.
+pub const FILE_NAME: &str = "schedules.json";
+/// How often the scheduler looks at the clock and at the file.
+const POLL: Duration = Duration::from_secs(1);
+/// How often a growing recording's WAV header is brought up to date, so a recording cut short
+/// by a crash still plays.
+pub const HEADER_UPDATE: Duration = Duration::from_secs(10);
+const DEFAULT_IQ_SAMPLE_RATE: u32 = 48_000;
+/// Repeating schedules must fit between two starts.
+const MAX_REPEATING_MINUTES: u32 = 24 * 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Repeat {
+    #[default]
+    Once,
+    Daily,
+    Weekly,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    #[default]
+    Audio,
+    Iq,
+}
+
+impl Format {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Audio => "audio",
+            Self::Iq => "iq",
+        }
+    }
+}
+
+/// One entry of `schedules.json`.
+#[derive(Debug, Clone, Deserialize)]
+struct Entry {
+    id: String,
+    #[serde(default)]
+    name: String,
+    /// Empty for the active receiver.
+    #[serde(default)]
+    receiver_id: String,
+    frequency: i64,
+    /// Demodulation of audio recordings; the receiver's default mode when unset.
+    #[serde(default)]
+    mode: Option<String>,
+    /// RFC 3339 time for `once` (the offset may be left out to use `timezone`), `HH:MM`
+    /// otherwise.
+    start: String,
+    /// IANA zone of `start`; UTC when unset.
+    #[serde(default)]
+    timezone: String,
+    duration_minutes: u32,
+    #[serde(default)]
+    repeat: Repeat,
+    /// Weekdays of `weekly` schedules (`mon`, `tuesday`, ...).
+    #[serde(default)]
+    days: Vec<String>,
+    #[serde(default)]
+    format: Format,
+    /// Rate of IQ recordings.
+    #[serde(default)]
+    sample_rate: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rule {
+    Once(DateTime<Utc>),
+    Repeating(DailyTime),
+}
+
+/// A schedule entry, checked and ready to run.
+#[derive(Debug, Clone)]
+struct Plan {
+    id: String,
+    name: String,
+    receiver_id: String,
+    frequency: i64,
+    mode: DemodulationMode,
+    format: Format,
+    sample_rate: u32,
+    rule: Rule,
+    duration: ChronoDuration,
+}
+
+fn parse_rule(entry: &Entry) -> Result<Rule, String> {
+    let zone = Zone::parse(&entry.timezone)?;
+    let start = entry.start.trim();
+    let time = || {
+        NaiveTime::parse_from_str(start, "%H:%M")
+            .map_err(|_| format!("start {:?} is not HH:MM", entry.start))
+    };
+    let days = match entry.repeat {
+        Repeat::Once => {
+            return DateTime::parse_from_rfc3339(start)
+                .map(|t| t.with_timezone(&Utc))
+                .or_else(|_| {
+                    NaiveDateTime::parse_from_str(start, "%Y-%m-%dT%H:%M:%S")
+                        .or_else(|_| NaiveDateTime::parse_from_str(start, "%Y-%m-%dT%H:%M"))
+                        .map(|t| zone.to_utc(t))
+                })
+                .map(Rule::Once)
+                .map_err(|_| format!("start {:?} is not an RFC 3339 time", entry.start));
+        }
+        Repeat::Daily => None,
+        Repeat::Weekly => {
+            let days = entry
+                .days
+                .iter()
+                .map(|d| {
+                    d.trim()
+                        .parse::<Weekday>()
+                        .map_err(|_| format!("unknown weekday {d:?}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if days.is_empty() {
+                return Err("weekly schedule needs days".to_string());
+            }
+            Some(days)
+        }
+    };
+    Ok(Rule::Repeating(DailyTime {
+        time: time()?,
+        zone,
+        days,
+    }))
+}
+
+fn plan(state: &AppState, entry: &Entry) -> Result<Plan, String> {
+    if entry.id.trim().is_empty() {
+        return Err("id must not be empty".to_string());
+    }
+    let rule = parse_rule(entry)?;
+    if entry.duration_minutes == 0 {
+        return Err("duration_minutes must be positive".to_string());
+    }
+    if !matches!(rule, Rule::Once(_)) && entry.duration_minutes > MAX_REPEATING_MINUTES {
+        return Err(format!(
+            "repeating schedules last at most {MAX_REPEATING_MINUTES} minutes"
+        ));
+    }
+    let receiver_id = if entry.receiver_id.trim().is_empty() {
+        state.active_receiver_id().to_string()
+    } else {
+        entry.receiver_id.trim().to_string()
+    };
+    let receiver = state
+        .receiver_state(&receiver_id)
+        .ok_or_else(|| format!("unknown receiver {receiver_id:?}"))?;
+    if !receiver.covers(entry.frequency) {
+        return Err(format!(
+            "frequency {} is outside receiver {receiver_id:?}",
+            entry.frequency
+        ));
+    }
+    let mode = match entry.mode.as_deref() {
+        Some(m) => DemodulationMode::from_str_upper(&m.trim().to_ascii_uppercase())
+            .ok_or_else(|| format!("unknown mode {m:?}"))?,
+        None => DemodulationMode::from_str_upper(receiver.settings().default_mode_str.as_str())
+            .unwrap_or(DemodulationMode::Usb),
+    };
+    Ok(Plan {
+        id: entry.id.trim().to_string(),
+        name: entry.name.trim().to_string(),
+        receiver_id,
+        frequency: entry.frequency,
+        mode,
+        format: entry.format,
+        sample_rate: entry.sample_rate.unwrap_or(DEFAULT_IQ_SAMPLE_RATE).max(1),
+        rule,
+        duration: ChronoDuration::minutes(i64::from(entry.duration_minutes)),
+    })
+}
+
+/// Start of the occurrence of `rule` that is running at `now`, if one is.
+fn running_occurrence(
+    rule: &Rule,
+    duration: ChronoDuration,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    match rule {
+        Rule::Once(start) => (*start <= now && now < *start + duration).then_some(*start),
+        Rule::Repeating(daily) => daily.running(duration, now),
+    }
+}
+
+async fn load(state: &AppState, path: &Path) -> Vec<Plan> {
+    let text = match tokio::fs::read_to_string(path).await {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "cannot read schedules");
+            return Vec::new();
+        }
+    };
+    let value: serde_json::Value = match serde_json::from_str(&text) {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "cannot parse schedules");
+            return Vec::new();
+        }
+    };
+    let mut plans = Vec::new();
+    let entries = value.get("schedules").and_then(|v| v.as_array());
+    for v in entries.into_iter().flatten() {
+        let res = serde_json::from_value::<Entry>(v.clone())
+            .map_err(|e| e.to_string())
+            .and_then(|entry| plan(state, &entry));
+        match res {
+            Ok(p) => plans.push(p),
+            Err(e) => tracing::warn!(schedule = %v, error = %e, "skipping schedule"),
+        }
+    }
+    tracing::info!(path = %path.display(), schedules = plans.len(), "schedules loaded");
+    plans
+}
+
+async fn file_fingerprint(path: &Path) -> Option<(std::time::SystemTime, u64)> {
+    let meta = tokio::fs::metadata(path).await.ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Records audio or IQ at the times set in `schedules.json`, into `capture.dir` where
+/// `/api/recordings` lists them. The file is reread whenever it changes.
+pub fn spawn(state: Arc<AppState>) {
+    let path = state.config_paths.resolve(FILE_NAME);
+    tokio::spawn(async move {
+        let mut fingerprint = None;
+        let mut plans = Vec::new();
+        // Occurrence each schedule last started, so it is recorded once.
+        let mut started: HashMap<String, DateTime<Utc>> = HashMap::new();
+        let mut interval = tokio::time::interval(POLL);
+        while !shutdown::is_shutdown_requested() {
+            interval.tick().await;
+            let current = file_fingerprint(&path).await;
+            if current != fingerprint {
+                fingerprint = current;
+                plans = load(&state, &path).await;
+            }
+            let now = Utc::now();
+            for plan in &plans {
+                let Some(start) = running_occurrence(&plan.rule, plan.duration, now) else {
+                    continue;
+                };
+                if started.get(&plan.id) == Some(&start) {
+                    continue;
+                }
+                started.insert(plan.id.clone(), start);
+                tokio::spawn(record(state.clone(), plan.clone(), start));
+            }
+        }
+    });
+}
+
+/// A WAV file of unknown final length whose header is rewritten as it grows.
+pub struct WavWriter {
+    file: BufWriter<tokio::fs::File>,
+    sample_rate: u32,
+    channels: u16,
+    data_len: u64,
+}
+
+impl WavWriter {
+    pub async fn create(path: &Path, sample_rate: u32, channels: u16) -> anyhow::Result<Self> {
+        let file = tokio::fs::File::create(path)
+            .await
+            .with_context(|| format!("create {}", path.display()))?;
+        let mut w = Self {
+            file: BufWriter::new(file),
+            sample_rate,
+            channels,
+            data_len: 0,
+        };
+        w.file
+            .write_all(&wav::header(sample_rate, channels, 0))
+            .await?;
+        Ok(w)
+    }
+
+    pub async fn write(&mut self, samples: &[u8]) -> anyhow::Result<()> {
+        self.file.write_all(samples).await?;
+        self.data_len += samples.len() as u64;
+        Ok(())
+    }
+
+    pub async fn update_header(&mut self) -> anyhow::Result<()> {
+        let header = wav::header(self.sample_rate, self.channels, self.data_len);
+        self.file.seek(std::io::SeekFrom::Start(0)).await?;
+        self.file.write_all(&header).await?;
+        self.file.seek(std::io::SeekFrom::End(0)).await?;
+        self.file.flush().await?;
+        Ok(())
+    }
+}
+
+async fn record(state: Arc<AppState>, plan: Plan, start: DateTime<Utc>) {
+    let Some(receiver) = state.receiver_state(&plan.receiver_id).cloned() else {
+        return;
+    };
+    if crate::disk_guard::recordings_paused() {
+        tracing::warn!(
+            schedule = %plan.id,
+            receiver_id = %plan.receiver_id,
+            "scheduled recording skipped: disk above its high water mark"
+        );
+        return;
+    }
+    let end = start + plan.duration;
+    let label = if plan.name.is_empty() {
+        &plan.id
+    } else {
+        &plan.name
+    };
+    let stem = format!(
+        "{}_{}",
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        crate::capture::file_safe(label)
+    );
+    let dir = PathBuf::from(&state.cfg().capture.dir).join(&plan.receiver_id);
+    let path = dir.join(format!("{stem}.wav"));
+    tracing::info!(
+        schedule = %plan.id,
+        receiver_id = %plan.receiver_id,
+        frequency = plan.frequency,
+        format = plan.format.as_str(),
+        until = %end.to_rfc3339(),
+        path = %path.display(),
+        "scheduled recording started"
+    );
+    let res = async {
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("create {}", dir.display()))?;
+        match plan.format {
+            Format::Audio => record_audio(&state, &receiver, &plan, &path, end).await,
+            Format::Iq => record_iq(&state, &receiver, &plan, &path, end).await,
+        }
+    }
+    .await;
+    let written = match res {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!(schedule = %plan.id, error = ?e, "scheduled recording failed");
+            return;
+        }
+    };
+    let meta = json!({
+        "schedule": plan.id,
+        "name": plan.name,
+        "receiver_id": plan.receiver_id,
+        "frequency": plan.frequency,
+        "mode": (plan.format == Format::Audio).then(|| plan.mode.as_str()),
+        "format": plan.format.as_str(),
+        "sample_rate": written.sample_rate,
+        "channels": written.channels,
+        "scheduled_start": start.to_rfc3339(),
+        "pre_roll_seconds": written.pre_roll_secs,
+        "end": Utc::now().to_rfc3339(),
+        "file": format!("{stem}.wav"),
+    });
+    let meta_path = dir.join(format!("{stem}.json"));
+    let res = async {
+        let mut text = serde_json::to_string_pretty(&meta)?;
+        text.push('\n');
+        tokio::fs::write(&meta_path, text).await?;
+        anyhow::Ok(())
+    }
+    .await;
+    if let Err(e) = res {
+        tracing::warn!(path = %meta_path.display(), error = ?e, "cannot write recording metadata");
+    }
+    tracing::info!(schedule = %plan.id, path = %path.display(), "scheduled recording finished");
+}
+
+/// Layout of a finished recording, for its sidecar.
+struct Written {
+    sample_rate: u32,
+    channels: u16,
+    /// Signal from before the scheduled start at the head of the file.
+    pre_roll_secs: f64,
+}
+
+/// How long until `end`, as a deadline for the runtime's clock.
+fn deadline(end: DateTime<Utc>) -> tokio::time::Instant {
+    let left = (end - Utc::now()).to_std().unwrap_or_default();
+    tokio::time::Instant::now() + left
+}
+
+/// Mono 16-bit audio at the receiver's audio rate.
+async fn record_audio(
+    state: &Arc<AppState>,
+    receiver: &Arc<ReceiverState>,
+    plan: &Plan,
+    path: &Path,
+    end: DateTime<Utc>,
+) -> anyhow::Result<Written> {
+    let sample_rate = receiver.rt.audio_max_sps as u32;
+    let mut out = WavWriter::create(path, sample_rate, 1).await?;
+    let (listener, mut pcm) =
+        HeadlessListener::start(state.clone(), receiver.clone(), plan.frequency, plan.mode).await?;
+    let res = async {
+        let until = tokio::time::sleep_until(deadline(end));
+        tokio::pin!(until);
+        let mut header = tokio::time::interval(HEADER_UPDATE);
+        loop {
+            tokio::select! {
+                _ = &mut until => break,
+                _ = header.tick() => out.update_header().await?,
+                buf = pcm.recv() => match buf {
+                    Some(buf) => out.write(&buf).await?,
+                    None => break,
+                },
+            }
+            if shutdown::is_shutdown_requested() {
+                break;
+            }
+        }
+        out.update_header().await
+    }
+    .await;
+    listener.stop().await;
+    res.map(|()| Written {
+        sample_rate,
+        channels: 1,
+        pre_roll_secs: 0.0,
+    })
+}
+
+/// Complex IQ as 16-bit stereo (I left, Q right), the layout SDR programs play back.
+async fn record_iq(
+    state: &Arc<AppState>,
+    receiver: &Arc<ReceiverState>,
+    plan: &Plan,
+    path: &Path,
+    end: DateTime<Utc>,
+) -> anyhow::Result<Written> {
+    let max_rate = u32::try_from(receiver.rt.total_bandwidth).unwrap_or(u32::MAX);
+    let mut tuning = Tuning::new(receiver, plan.frequency, plan.sample_rate, max_rate);
+    let sample_rate = tuning.sample_rate(receiver).round() as u32;
+    let mut out = WavWriter::create(path, sample_rate, 2).await?;
+    // The receiver's replay buffer, when it has one, puts the first seconds before `start`.
+    let mut frames = IqSubscription::new(state, receiver, true);
+    let pre_roll_secs = frames.replayed_secs();
+    let res = async {
+        let until = tokio::time::sleep_until(deadline(end));
+        tokio::pin!(until);
+        let mut header = tokio::time::interval(HEADER_UPDATE);
+        let mut iq: Vec<Complex32> = Vec::new();
+        let mut bytes: Vec<u8> = Vec::new();
+        loop {
+            tokio::select! {
+                _ = &mut until => break,
+                _ = header.tick() => out.update_header().await?,
+                frame = frames.recv() => {
+                    let Some(frame) = frame else {
+                        break;
+                    };
+                    tuning.process(&frame, receiver.rt.is_real, &mut iq);
+                    bytes.clear();
+                    for s in &iq {
+                        bytes.extend_from_slice(&to_i16(s.re).to_le_bytes());
+                        bytes.extend_from_slice(&to_i16(s.im).to_le_bytes());
+                    }
+                    out.write(&bytes).await?;
+                }
+            }
+            if shutdown::is_shutdown_requested() {
+                break;
+            }
+        }
+        out.update_header().await
+    }
+    .await;
+    res.map(|()| Written {
+        sample_rate,
+        channels: 2,
+        pre_roll_secs,
+    })
+}
+
+/// Full-scale float sample as 16-bit PCM, clipped.
+pub fn to_i16(v: f32) -> i16 {
+    (v * 32767.0).round().clamp(-32768.0, 32767.0) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn entry(start: &str, repeat: Repeat, days: &[&str]) -> Entry {
+        Entry {
+            id: "x".to_string(),
+            name: String::new(),
+            receiver_id: String::new(),
+            frequency: 0,
+            mode: None,
+            start: start.to_string(),
+            timezone: String::new(),
+            duration_minutes: 60,
+            repeat,
+            days: days.iter().map(|d| d.to_string()).collect(),
+            format: Format::Audio,
+            sample_rate: None,
+        }
+    }
+
+    #[test]
+    fn occurrences_are_found_while_running() {
+        let hour = ChronoDuration::hours(1);
+        let once = parse_rule(&entry("2026-03-01T06:00:00Z", Repeat::Once, &[])).unwrap();
+        assert_eq!(
+            running_occurrence(&once, hour, at("2026-03-01T06:30:00Z")),
+            Some(at("2026-03-01T06:00:00Z"))
+        );
+        assert_eq!(
+            running_occurrence(&once, hour, at("2026-03-01T07:00:00Z")),
+            None
+        );
+
+        // Across midnight: the occurrence started yesterday.
+        let daily = parse_rule(&entry("23:30", Repeat::Daily, &[])).unwrap();
+        assert_eq!(
+            running_occurrence(&daily, hour, at("2026-03-02T00:10:00Z")),
+            Some(at("2026-03-01T23:30:00Z"))
+        );
+        assert_eq!(
+            running_occurrence(&daily, hour, at("2026-03-02T12:00:00Z")),
+            None
+        );
+
+        // 2026-03-02 is a Monday.
+        let weekly = parse_rule(&entry("06:00", Repeat::Weekly, &["mon", "Friday"])).unwrap();
+        assert_eq!(
+            running_occurrence(&weekly, hour, at("2026-03-02T06:15:00Z")),
+            Some(at("2026-03-02T06:00:00Z"))
+        );
+        assert_eq!(
+            running_occurrence(&weekly, hour, at("2026-03-03T06:15:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn malformed_rules_are_rejected() {
+        assert!(parse_rule(&entry("06:00", Repeat::Once, &[])).is_err());
+        assert!(parse_rule(&entry("6 am", Repeat::Daily, &[])).is_err());
+        assert!(parse_rule(&entry("06:00", Repeat::Weekly, &[])).is_err());
+        assert!(parse_rule(&entry("06:00", Repeat::Weekly, &["someday"])).is_err());
+        let mut bad_zone = entry("06:00", Repeat::Daily, &[]);
+        bad_zone.timezone = "Europe/Atlantis".to_string();
+        assert!(parse_rule(&bad_zone).is_err());
+    }
+
+    #[test]
+    fn local_schedules_keep_their_wall_clock_time() {
+        let hour = ChronoDuration::hours(1);
+        let mut daily = entry("07:00", Repeat::Daily, &[]);
+        daily.timezone = "America/New_York".to_string();
+        let daily = parse_rule(&daily).unwrap();
+        // Clocks went forward on 2026-03-08.
+        assert_eq!(
+            running_occurrence(&daily, hour, at("2026-03-07T12:30:00Z")),
+            Some(at("2026-03-07T12:00:00Z"))
+        );
+        assert_eq!(
+            running_occurrence(&daily, hour, at("2026-03-09T11:30:00Z")),
+            Some(at("2026-03-09T11:00:00Z"))
+        );
+
+        let mut once = entry("2026-11-01T01:30", Repeat::Once, &[]);
+        once.timezone = "America/New_York".to_string();
+        // 01:30 happens twice that night; the EDT one is meant.
+        assert_eq!(
+            parse_rule(&once).unwrap(),
+            Rule::Once(at("2026-11-01T05:30:00Z"))
+        );
+        once.start = "2026-11-01T01:30:00-05:00".to_string();
+        assert_eq!(
+            parse_rule(&once).unwrap(),
+            Rule::Once(at("2026-11-01T06:30:00Z"))
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! `--service`: running under the Windows service control manager.
+//!
+//! The SCM starts the binary, which must hand its main thread to the service dispatcher within
+//! a few seconds. The dispatcher calls `service_main` on another thread; that reports
+//! `Running`, runs the server, and reports `Stopped` once it returns. Stop and shutdown controls
+//! go through [`crate::shutdown::request_shutdown`], the same path as ctrl-c.
+
+/// Services start in `%SystemRoot%\system32`; switch to the directory of the executable so the
+/// relative default paths (`config/`, `logs/`, `frontend/dist`) keep working.
+pub fn enter_install_dir() -> anyhow::Result<()> {
+    use anyhow::Context;
+    let exe = std::env::current_exe().context("locate executable")?;
+    let dir = exe
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("executable has no parent directory"))?;
+    std::env::set_current_dir(dir).with_context(|| format!("enter {}", dir.display()))
+}
+
+/// Fails early on platforms without a service control manager.
+pub fn ensure_supported() -> anyhow::Result<()> {
+    if !cfg!(windows) {
+        anyhow::bail!(
+            "--service is only supported on Windows; use a systemd unit elsewhere (see docs/OPERATIONS.md)"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub use windows::run;
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::OsString;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use windows_service::{
+        define_windows_service,
+        service::{
+            ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus,
+            ServiceType,
+        },
+        service_control_handler::{self, ServiceControlHandlerResult},
+        service_dispatcher,
+    };
+
+    /// Service name used when registering with the SCM (`sc.exe create NovaSDR ...`).
+    const SERVICE_NAME: &str = "NovaSDR";
+
+    type Server = Box<dyn FnOnce() -> anyhow::Result<()> + Send>;
+
+    /// The server to run once the dispatcher calls back, and its result for [`run`].
+    static SERVER: Mutex<Option<Server>> = Mutex::new(None);
+    static RESULT: Mutex<Option<anyhow::Result<()>>> = Mutex::new(None);
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    /// Runs `server` as the `NovaSDR` service; returns after the SCM stopped it.
+    pub fn run(server: impl FnOnce() -> anyhow::Result<()> + Send + 'static) -> anyhow::Result<()> {
+        *lock(&SERVER) = Some(Box::new(server));
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main).map_err(|e| {
+            anyhow::anyhow!("start service dispatcher (was the binary started by the SCM?): {e}")
+        })?;
+        lock(&RESULT)
+            .take()
+            .unwrap_or_else(|| Err(anyhow::anyhow!("service exited without running the server")))
+    }
+
+    fn lock<T>(m: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+        match m.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("service mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    fn status(state: ServiceState, exit_code: ServiceExitCode) -> ServiceStatus {
+        let controls_accepted = if state == ServiceState::Running {
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+        } else {
+            ServiceControlAccept::empty()
+        };
+        ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted,
+            exit_code,
+            checkpoint: 0,
+            wait_hint: Duration::from_secs(10),
+            process_id: None,
+        }
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        let result = run_registered();
+        if let Err(e) = &result {
+            tracing::error!(error = ?e, "service failed");
+        }
+        *lock(&RESULT) = Some(result);
+    }
+
+    fn run_registered() -> anyhow::Result<()> {
+        let handle = service_control_handler::register(SERVICE_NAME, |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                tracing::info!(?control, "service stop requested");
+                crate::shutdown::request_shutdown();
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        })
+        .map_err(|e| anyhow::anyhow!("register service control handler: {e}"))?;
+
+        let set = |state, exit_code| {
+            if let Err(e) = handle.set_service_status(status(state, exit_code)) {
+                tracing::warn!(error = %e, ?state, "failed to report service status");
+            }
+        };
+        set(ServiceState::Running, ServiceExitCode::Win32(0));
+        tracing::info!(service = SERVICE_NAME, "running as a Windows service");
+
+        let Some(server) = lock(&SERVER).take() else {
+            set(ServiceState::Stopped, ServiceExitCode::ServiceSpecific(1));
+            anyhow::bail!("service started twice");
+        };
+        let result = server();
+        let exit_code = match result {
+            Ok(()) => ServiceExitCode::Win32(0),
+            Err(_) => ServiceExitCode::ServiceSpecific(1),
+        };
+        set(ServiceState::Stopped, exit_code);
+        result
+    }
+}
@@ -1118,6 +1118,7 @@ fn edit_receiver(receiver: &mut Value) -> anyhow::Result<()> {
 
         let mut labels = Vec::<String>::new();
         labels.push("none".to_string());
+        labels.push("auto".to_string());
 
         if cfg!(feature = "clfft") {
             labels.push("clfft".to_string());
@@ -1152,6 +1153,8 @@ fn edit_receiver(receiver: &mut Value) -> anyhow::Result<()> {
                 );
             }
             "vkfft"
+        } else if selected == "auto" {
+            "auto"
         } else {
             "none"
         };
@@ -1217,6 +1220,9 @@ fn edit_receiver(receiver: &mut Value) -> anyhow::Result<()> {
         }
     }
 
+    let real_signal = input.get("signal").and_then(Value::as_str) == Some("real");
+    let sps = input.get("sps").and_then(Value::as_i64).unwrap_or(0);
+    let frequency = input.get("frequency").and_then(Value::as_i64).unwrap_or(0);
     let Some(driver) = input.get_mut("driver").and_then(Value::as_object_mut) else {
         anyhow::bail!("receiver.input.driver must be an object");
     };
@@ -1227,6 +1233,9 @@ fn edit_receiver(receiver: &mut Value) -> anyhow::Result<()> {
             "stdin".to_string(),
             "fifo".to_string(),
             "soapysdr".to_string(),
+            "ka9q".to_string(),
+            "rx888".to_string(),
+            "synthetic".to_string(),
         ],
     )
     .prompt()
@@ -1242,6 +1251,7 @@ fn edit_receiver(receiver: &mut Value) -> anyhow::Result<()> {
             driver.remove("gain");
             driver.remove("gains");
             driver.remove("settings");
+            remove_ka9q_keys(driver);
 
             let format = Select::new(
                 "Sample format",
@@ -1281,6 +1291,8 @@ fn edit_receiver(receiver: &mut Value) -> anyhow::Result<()> {
                 );
             }
 
+            remove_ka9q_keys(driver);
+
             let device =
                 prompt_soapysdr_device(driver.get("device").and_then(Value::as_str).unwrap_or(""))?;
             driver.insert("device".to_string(), json!(device));
@@ -1347,12 +1359,162 @@ fn edit_receiver(receiver: &mut Value) -> anyhow::Result<()> {
             configure_gain_elements(driver)?;
             configure_device_settings(driver)?;
         }
+        "ka9q" => {
+            for key in [
+                "path", "device", "channel", "antenna", "agc", "gain", "gains", "settings",
+            ] {
+                driver.remove(key);
+            }
+
+            let group = Text::new("radiod data stream (host:port)")
+                .with_default(
+                    driver
+                        .get("group")
+                        .and_then(Value::as_str)
+                        .unwrap_or("239.1.2.3:5004"),
+                )
+                .prompt()
+                .context("prompt ka9q group")?;
+            driver.insert("group".to_string(), json!(group.trim()));
+
+            let ssrc_default = driver
+                .get("ssrc")
+                .and_then(Value::as_u64)
+                .map(|v| v.to_string())
+                .unwrap_or_default();
+            let ssrc_raw = Text::new("Channel SSRC (blank to use the first stream heard)")
+                .with_default(ssrc_default.as_str())
+                .prompt()
+                .context("prompt ka9q ssrc")?;
+            let ssrc_raw = ssrc_raw.trim();
+            if ssrc_raw.is_empty() {
+                driver.remove("ssrc");
+            } else {
+                let ssrc: u32 = ssrc_raw.parse().context("parse ssrc as number")?;
+                driver.insert("ssrc".to_string(), json!(ssrc));
+            }
+
+            let formats = if real_signal {
+                vec!["s16".to_string(), "f32".to_string()]
+            } else {
+                vec!["cs16".to_string(), "cf32".to_string()]
+            };
+            let format = Select::new("Stream sample format", formats)
+                .prompt()
+                .context("prompt ka9q format")?;
+            driver.insert("format".to_string(), json!(format));
+        }
+        "rx888" => {
+            for key in [
+                "path", "device", "channel", "antenna", "agc", "gain", "gains", "settings",
+                "format",
+            ] {
+                driver.remove(key);
+            }
+            remove_ka9q_keys(driver);
+            if !real_signal {
+                ui::line("Note: the RX888 delivers real samples; set the signal type to \"real\".");
+            }
+
+            let command = Text::new("Capture program (writes s16 samples to stdout)")
+                .with_default(
+                    driver
+                        .get("command")
+                        .and_then(Value::as_str)
+                        .unwrap_or("rx888_stream"),
+                )
+                .prompt()
+                .context("prompt rx888 command")?;
+            driver.insert("command".to_string(), json!(command.trim()));
+
+            let args_default = driver
+                .get("args")
+                .and_then(Value::as_array)
+                .map(|a| {
+                    a.iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .unwrap_or_default();
+            let args = Text::new("Arguments (space separated)")
+                .with_default(args_default.as_str())
+                .prompt()
+                .context("prompt rx888 args")?;
+            driver.insert(
+                "args".to_string(),
+                json!(args.split_whitespace().collect::<Vec<_>>()),
+            );
+
+            let randomizer = Confirm::new("ADC randomizer (RAND) enabled?")
+                .with_default(
+                    driver
+                        .get("randomizer")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                )
+                .prompt()
+                .context("prompt rx888 randomizer")?;
+            driver.insert("randomizer".to_string(), json!(randomizer));
+        }
+        "synthetic" => {
+            for key in [
+                "path",
+                "device",
+                "channel",
+                "antenna",
+                "agc",
+                "gain",
+                "gains",
+                "settings",
+                "format",
+                "command",
+                "args",
+                "randomizer",
+            ] {
+                driver.remove(key);
+            }
+            remove_ka9q_keys(driver);
+            if !driver.contains_key("scenario") {
+                let low = if real_signal {
+                    frequency
+                } else {
+                    frequency - sps / 2
+                };
+                let span = if real_signal { sps / 2 } else { sps };
+                driver.insert(
+                    "scenario".to_string(),
+                    demo_scenario(low, span).context("build demo scenario")?,
+                );
+                ui::line("Added a demo scenario; edit receivers.json to change its signals.");
+            }
+        }
         _ => {}
     }
 
     Ok(())
 }
 
+/// A carrier, an SSB voice and an FM tone spread across `span` Hz from `low`.
+fn demo_scenario(low: i64, span: i64) -> anyhow::Result<Value> {
+    anyhow::ensure!(span > 0, "set the sample rate before the synthetic driver");
+    let at = |fraction: i64| low + span * fraction / 8;
+    Ok(json!({
+        "noise_floor_db": -80,
+        "signals": [
+            { "type": "carrier", "frequency": at(2), "level_db": -40 },
+            { "type": "ssb", "frequency": at(4) + 1_000, "level_db": -30 },
+            { "type": "fm", "frequency": at(6), "level_db": -35 },
+        ],
+    }))
+}
+
+fn remove_ka9q_keys(driver: &mut serde_json::Map<String, Value>) {
+    for key in ["group", "interface", "ssrc", "payload_type", "byte_order"] {
+        driver.remove(key);
+    }
+}
+
 fn prompt_soapysdr_device(current: &str) -> anyhow::Result<String> {
     if !cfg!(feature = "soapysdr") {
         return Text::new("SoapySDR device string")
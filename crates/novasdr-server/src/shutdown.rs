@@ -1,12 +1,25 @@
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 use std::time::Duration;
+use tokio::sync::Notify;
 
 static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+static SHUTDOWN_NOTIFY: OnceLock<Notify> = OnceLock::new();
+
+fn notify() -> &'static Notify {
+    SHUTDOWN_NOTIFY.get_or_init(Notify::new)
+}
 
 pub fn is_shutdown_requested() -> bool {
     SHUTDOWN_REQUESTED.load(Ordering::Relaxed)
 }
 
+/// Asks the server to stop as if it had received ctrl-c (used by the Windows service handler).
+#[cfg(windows)]
+pub fn request_shutdown() {
+    notify().notify_one();
+}
+
 pub async fn shutdown_signal() {
     let ctrl_c = async {
         if let Err(e) = tokio::signal::ctrl_c().await {
@@ -30,6 +43,7 @@ pub async fn shutdown_signal() {
     tokio::select! {
         _ = ctrl_c => {},
         _ = terminate => {},
+        _ = notify().notified() => {},
     }
 
     SHUTDOWN_REQUESTED.store(true, Ordering::Relaxed);
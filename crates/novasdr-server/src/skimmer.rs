@@ -0,0 +1,770 @@
+pub mod pskreporter;
+pub mod rbn;
+pub mod wsprnet;
+
+use crate::state::{AppState, ReceiverState};
+use axum::{extract::State, response::IntoResponse, Json};
+use novasdr_core::{
+    config::{Runtime, SkimmerMode},
+    cw,
+    ft8::{self, DecodeOptions, Protocol},
+    protocol::EventTopics,
+    wspr,
+};
+use num_complex::Complex32;
+use realfft::{ComplexToReal, RealFftPlanner};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+use tokio::sync::broadcast;
+
+/// Highest audio frequency kept from the wideband spectrum; covers the 100-3000 Hz decode window.
+const PASSBAND_HZ: f64 = 3200.0;
+/// A slot joined later than this after its boundary is skipped rather than zero-padded.
+const MAX_LATE_JOIN_MS: u64 = 1000;
+/// Drift between the sample clock and wall clock that forces a resync to the next slot.
+const MAX_SLOT_DRIFT_MS: u64 = 1000;
+const DECODE_QUEUE_DEPTH: usize = 16;
+const RECENT_SPOTS: usize = 500;
+/// Unuploaded spots kept when PSK Reporter is unreachable.
+const PENDING_SPOTS: usize = 5000;
+/// Spots a slow live subscriber (the RBN feed) may fall behind before it skips ahead.
+const LIVE_SPOTS: usize = 256;
+/// Audio kept clear at either end of a CW chunk, where the extractor's edges are.
+const CW_EDGE_HZ: f64 = 200.0;
+/// A callsign heard again this close to where it was spotted is not spotted again for
+/// [`CW_RESPOT_MS`]; neighbouring chunks and analysis bins hear the same signal.
+const CW_SAME_SIGNAL_HZ: i64 = 300;
+const CW_RESPOT_MS: u64 = 10 * 60 * 1000;
+const CW_SPOTTED_MAX: usize = 5000;
+
+/// Band name and FT8, FT4 and WSPR dial frequencies in Hz.
+const BANDS: &[(&str, i64, Option<i64>, i64)] = &[
+    ("160m", 1_840_000, None, 1_836_600),
+    ("80m", 3_573_000, Some(3_575_000), 3_568_600),
+    ("60m", 5_357_000, None, 5_287_200),
+    ("40m", 7_074_000, Some(7_047_500), 7_038_600),
+    ("30m", 10_136_000, Some(10_140_000), 10_138_700),
+    ("20m", 14_074_000, Some(14_080_000), 14_095_600),
+    ("17m", 18_100_000, Some(18_104_000), 18_104_600),
+    ("15m", 21_074_000, Some(21_140_000), 21_094_600),
+    ("12m", 24_915_000, Some(24_919_000), 24_924_600),
+    ("10m", 28_074_000, Some(28_180_000), 28_124_600),
+    ("6m", 50_313_000, Some(50_318_000), 50_293_000),
+    ("2m", 144_174_000, None, 144_489_000),
+];
+
+/// Band name and the CW segment the CW skimmer watches, in Hz.
+const CW_SEGMENTS: &[(&str, i64, i64)] = &[
+    ("160m", 1_810_000, 1_838_000),
+    ("80m", 3_500_000, 3_570_000),
+    ("40m", 7_000_000, 7_040_000),
+    ("30m", 10_100_000, 10_130_000),
+    ("20m", 14_000_000, 14_070_000),
+    ("17m", 18_068_000, 18_095_000),
+    ("15m", 21_000_000, 21_070_000),
+    ("12m", 24_890_000, 24_915_000),
+    ("10m", 28_000_000, 28_070_000),
+    ("6m", 50_000_000, 50_100_000),
+    ("2m", 144_025_000, 144_110_000),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Ft(Protocol),
+    Wspr,
+}
+
+impl Mode {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ft(protocol) => protocol.name(),
+            Self::Wspr => "WSPR",
+        }
+    }
+
+    fn slot_ms(self) -> u64 {
+        match self {
+            Self::Ft(protocol) => protocol.slot_ms(),
+            Self::Wspr => wspr::SLOT_MS,
+        }
+    }
+}
+
+/// Upload service a spot is queued for.
+#[derive(Debug, Clone, Copy)]
+pub enum Uploader {
+    PskReporter,
+    Wsprnet,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Spot {
+    pub callsign: String,
+    pub locator: Option<String>,
+    pub frequency_hz: i64,
+    pub snr_db: i32,
+    pub mode: &'static str,
+    pub band: &'static str,
+    pub time_unix: i64,
+    /// Frame start relative to the nominal start in seconds (FT8/FT4 and WSPR only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dt_s: Option<f32>,
+    /// Keying speed (CW only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wpm: Option<u32>,
+    /// Reported transmit power (WSPR only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power_dbm: Option<i32>,
+    /// Frequency change over the transmission in Hz (WSPR only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift_hz: Option<i32>,
+    pub message: String,
+}
+
+/// Spots decoded by all receivers: a bounded recent list for the HTTP endpoint, one queue per
+/// uploader and a live feed.
+pub struct SpotLog {
+    recent: Mutex<VecDeque<Spot>>,
+    pskreporter_pending: Mutex<Vec<Spot>>,
+    wsprnet_pending: Mutex<Vec<Spot>>,
+    live: broadcast::Sender<Spot>,
+}
+
+impl Default for SpotLog {
+    fn default() -> Self {
+        Self {
+            recent: Mutex::default(),
+            pskreporter_pending: Mutex::default(),
+            wsprnet_pending: Mutex::default(),
+            live: broadcast::channel(LIVE_SPOTS).0,
+        }
+    }
+}
+
+impl SpotLog {
+    fn pending(&self, uploader: Uploader) -> &Mutex<Vec<Spot>> {
+        match uploader {
+            Uploader::PskReporter => &self.pskreporter_pending,
+            Uploader::Wsprnet => &self.wsprnet_pending,
+        }
+    }
+
+    fn push(&self, spot: Spot, upload: Option<Uploader>) {
+        if let Some(uploader) = upload {
+            let mut pending = match self.pending(uploader).lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!("skimmer pending mutex poisoned; recovering");
+                    poisoned.into_inner()
+                }
+            };
+            if pending.len() < PENDING_SPOTS {
+                pending.push(spot.clone());
+            }
+        }
+        let mut recent = match self.recent.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("skimmer recent mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        if recent.len() >= RECENT_SPOTS {
+            recent.pop_front();
+        }
+        recent.push_back(spot.clone());
+        // Nobody listening live is not an error.
+        let _ = self.live.send(spot);
+    }
+
+    /// Spots from now on, as they are decoded.
+    pub fn subscribe(&self) -> broadcast::Receiver<Spot> {
+        self.live.subscribe()
+    }
+
+    pub fn take_pending(&self, uploader: Uploader) -> Vec<Spot> {
+        match self.pending(uploader).lock() {
+            Ok(mut g) => std::mem::take(&mut *g),
+            Err(poisoned) => {
+                tracing::error!("skimmer pending mutex poisoned; recovering");
+                std::mem::take(&mut *poisoned.into_inner())
+            }
+        }
+    }
+
+    fn recent(&self) -> Vec<Spot> {
+        match self.recent.lock() {
+            Ok(g) => g.iter().cloned().collect(),
+            Err(poisoned) => {
+                tracing::error!("skimmer recent mutex poisoned; recovering");
+                poisoned.into_inner().iter().cloned().collect()
+            }
+        }
+    }
+}
+
+pub async fn spots(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.skimmer_spots.recent())
+}
+
+/// Logs `spot`, queues it for `upload` and sends it to `/events` clients.
+fn publish(state: &AppState, receiver_id: &str, spot: Spot, upload: Option<Uploader>) {
+    if let Ok(serde_json::Value::Object(mut msg)) = serde_json::to_value(&spot) {
+        msg.insert("type".to_string(), "spot".into());
+        msg.insert("receiver_id".to_string(), receiver_id.into());
+        state.broadcast_event_json(
+            EventTopics::SPOTS,
+            serde_json::Value::Object(msg).to_string(),
+        );
+    }
+    state.skimmer_spots.push(spot, upload);
+}
+
+/// Turns a window of the wideband spectrum into real audio, using the same overlap-add C2R
+/// path as USB demodulation in `ws::audio`.
+struct SubbandExtractor {
+    start_bin: usize,
+    bins: usize,
+    flip_odd_frames: bool,
+    scale: f32,
+    c2r: Arc<dyn ComplexToReal<f32>>,
+    scratch: Vec<Complex32>,
+    buf_in: Vec<Complex32>,
+    real: Vec<f32>,
+    real_prev: Vec<f32>,
+}
+
+impl SubbandExtractor {
+    fn new(rt: &Runtime, start_bin: usize, bins: usize) -> Self {
+        let n = rt.audio_max_fft_size;
+        let mut planner = RealFftPlanner::<f32>::new();
+        let c2r = planner.plan_fft_inverse(n);
+        let scratch = c2r.make_scratch_vec();
+        let m_even = start_bin.is_multiple_of(2);
+        Self {
+            start_bin,
+            bins: bins.min(n / 2),
+            flip_odd_frames: (m_even && !rt.is_real) || (!m_even && rt.is_real),
+            scale: 1.0 / rt.fft_size as f32,
+            c2r,
+            scratch,
+            buf_in: vec![Complex32::new(0.0, 0.0); n / 2 + 1],
+            real: vec![0.0; n],
+            real_prev: vec![0.0; n / 2],
+        }
+    }
+
+    fn process(&mut self, spectrum: &[Complex32], base_idx: usize, frame_num: u64) -> &[f32] {
+        let len = spectrum.len();
+        let half = self.real.len() / 2;
+        self.buf_in.fill(Complex32::new(0.0, 0.0));
+        // Bin 0 is the dial frequency itself; leaving it empty keeps the C2R input hermitian.
+        for k in 1..self.bins {
+            self.buf_in[k] = spectrum[(self.start_bin + k + base_idx) % len] * self.scale;
+        }
+        let _ = self
+            .c2r
+            .process_with_scratch(&mut self.buf_in, &mut self.real, &mut self.scratch);
+        if frame_num % 2 == 1 && self.flip_odd_frames {
+            for v in self.real.iter_mut() {
+                *v = -*v;
+            }
+        }
+        for (dst, prev) in self.real[..half].iter_mut().zip(self.real_prev.iter()) {
+            *dst += prev;
+        }
+        self.real_prev.copy_from_slice(&self.real[half..]);
+        &self.real[..half]
+    }
+}
+
+/// Streaming Catmull-Rom resampler to the decoder rate. The input is already band-limited to
+/// [`PASSBAND_HZ`], so no extra anti-alias filter is needed.
+struct Resampler {
+    step: f64,
+    pos: f64,
+    history: Vec<f32>,
+}
+
+impl Resampler {
+    fn new(in_rate: f64) -> Self {
+        Self {
+            step: in_rate / ft8::SAMPLE_RATE as f64,
+            pos: 1.0,
+            history: vec![0.0],
+        }
+    }
+
+    fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        self.history.extend_from_slice(input);
+        while self.pos + 2.0 < self.history.len() as f64 {
+            let i = self.pos as usize;
+            let t = (self.pos - i as f64) as f32;
+            let (p0, p1, p2, p3) = (
+                self.history[i - 1],
+                self.history[i],
+                self.history[i + 1],
+                self.history[i + 2],
+            );
+            let a = -0.5 * p0 + 1.5 * p1 - 1.5 * p2 + 0.5 * p3;
+            let b = p0 - 2.5 * p1 + 2.0 * p2 - 0.5 * p3;
+            let c = -0.5 * p0 + 0.5 * p2;
+            out.push(((a * t + b) * t + c) * t + p1);
+            self.pos += self.step;
+        }
+        let consumed = (self.pos as usize).saturating_sub(1);
+        self.history.drain(..consumed);
+        self.pos -= consumed as f64;
+    }
+}
+
+/// Cuts a continuous 12 kHz stream into receive slots aligned to UTC.
+struct SlotCutter {
+    slot_ms: u64,
+    start_ms: Option<u64>,
+    buf: Vec<f32>,
+}
+
+impl SlotCutter {
+    fn new(slot_ms: u64) -> Self {
+        Self {
+            slot_ms,
+            start_ms: None,
+            buf: Vec::new(),
+        }
+    }
+
+    fn slot_samples(&self) -> usize {
+        (self.slot_ms * ft8::SAMPLE_RATE as u64 / 1000) as usize
+    }
+
+    /// Feeds samples whose first sample was captured at `t0_ms`; returns finished slots.
+    fn push(&mut self, samples: &[f32], t0_ms: u64, out: &mut Vec<(u64, Vec<f32>)>) {
+        let rate = ft8::SAMPLE_RATE as u64;
+        let slot_samples = self.slot_samples();
+        let mut pos = 0usize;
+        while pos < samples.len() {
+            let t = t0_ms + pos as u64 * 1000 / rate;
+            match self.start_ms {
+                Some(start) => {
+                    let expected = start + self.buf.len() as u64 * 1000 / rate;
+                    if expected.abs_diff(t) > MAX_SLOT_DRIFT_MS {
+                        self.start_ms = None;
+                        self.buf.clear();
+                        continue;
+                    }
+                    let take = (slot_samples - self.buf.len()).min(samples.len() - pos);
+                    self.buf.extend_from_slice(&samples[pos..pos + take]);
+                    pos += take;
+                    if self.buf.len() >= slot_samples {
+                        let done =
+                            std::mem::replace(&mut self.buf, Vec::with_capacity(slot_samples));
+                        out.push((start, done));
+                        self.start_ms = Some(start + self.slot_ms);
+                    }
+                }
+                None => {
+                    let into_slot = t % self.slot_ms;
+                    if into_slot > MAX_LATE_JOIN_MS {
+                        let skip = ((self.slot_ms - into_slot) * rate / 1000).max(1) as usize;
+                        pos += skip;
+                        continue;
+                    }
+                    self.buf.clear();
+                    self.buf.resize((into_slot * rate / 1000) as usize, 0.0);
+                    self.start_ms = Some(t - into_slot);
+                }
+            }
+        }
+    }
+}
+
+struct Channel {
+    band: &'static str,
+    mode: Mode,
+    /// Frequency of the first extracted bin; decoded audio offsets are relative to it.
+    dial_hz: i64,
+    extractor: SubbandExtractor,
+    resampler: Resampler,
+    cutter: SlotCutter,
+    audio: Vec<f32>,
+}
+
+/// A stretch of a CW segment, extracted as audio and decoded as it streams.
+struct CwChannel {
+    band: &'static str,
+    dial_hz: i64,
+    extractor: SubbandExtractor,
+    skimmer: cw::Skimmer,
+}
+
+struct SlotJob {
+    band: &'static str,
+    mode: Mode,
+    dial_hz: i64,
+    start_ms: u64,
+    samples: Vec<f32>,
+}
+
+/// Per-receiver skimmer driven from the DSP loop.
+pub struct Skimmer {
+    state: Arc<AppState>,
+    receiver_id: String,
+    channels: Vec<Channel>,
+    cw_channels: Vec<CwChannel>,
+    /// Where and when (ms) each callsign was last spotted in CW.
+    cw_spotted: HashMap<String, (i64, u64)>,
+    base_idx: usize,
+    frame_ms: f64,
+    work_tx: mpsc::SyncSender<SlotJob>,
+    dropped_slots: u64,
+    slots: Vec<(u64, Vec<f32>)>,
+}
+
+impl Skimmer {
+    /// Returns `None` when the receiver has the skimmer disabled or no configured band fits.
+    pub fn new(state: Arc<AppState>, receiver: &ReceiverState) -> anyhow::Result<Option<Self>> {
+        let cfg = &receiver.receiver.skimmer;
+        if !cfg.enabled {
+            return Ok(None);
+        }
+        let rt = &receiver.rt;
+        let receiver_id = receiver.receiver.id.clone();
+        let bin_hz = rt.total_bandwidth as f64 / rt.fft_result_size as f64;
+        let audio_rate = rt.audio_max_fft_size as f64 * bin_hz;
+        if audio_rate < 2.0 * PASSBAND_HZ {
+            tracing::warn!(
+                receiver_id = %receiver_id,
+                audio_rate,
+                "skimmer disabled: receiver audio rate too low for digital modes"
+            );
+            return Ok(None);
+        }
+        let passband_bins = (PASSBAND_HZ / bin_hz).ceil() as usize + 1;
+
+        for name in cfg.bands.iter() {
+            if !BANDS
+                .iter()
+                .any(|(band, _, _, _)| band.eq_ignore_ascii_case(name))
+            {
+                tracing::warn!(receiver_id = %receiver_id, band = %name, "skimmer: unknown band");
+            }
+        }
+
+        let mut channels = Vec::new();
+        for &(band, ft8_hz, ft4_hz, wspr_hz) in BANDS.iter() {
+            if !cfg.bands.is_empty() && !cfg.bands.iter().any(|b| b.eq_ignore_ascii_case(band)) {
+                continue;
+            }
+            for mode in cfg.modes.iter() {
+                let (mode, dial) = match mode {
+                    SkimmerMode::Ft8 => (Mode::Ft(Protocol::Ft8), Some(ft8_hz)),
+                    SkimmerMode::Ft4 => (Mode::Ft(Protocol::Ft4), ft4_hz),
+                    SkimmerMode::Wspr => (Mode::Wspr, Some(wspr_hz)),
+                    SkimmerMode::Cw => continue,
+                };
+                let Some(dial) = dial else {
+                    continue;
+                };
+                if dial < rt.basefreq
+                    || dial as f64 + PASSBAND_HZ > (rt.basefreq + rt.total_bandwidth) as f64
+                {
+                    continue;
+                }
+                let start_bin = ((dial - rt.basefreq) as f64 / bin_hz).floor() as usize;
+                channels.push(Channel {
+                    band,
+                    mode,
+                    dial_hz: rt.basefreq + (start_bin as f64 * bin_hz).round() as i64,
+                    extractor: SubbandExtractor::new(rt, start_bin, passband_bins),
+                    resampler: Resampler::new(audio_rate),
+                    cutter: SlotCutter::new(mode.slot_ms()),
+                    audio: Vec::new(),
+                });
+            }
+        }
+        let mut cw_channels = Vec::new();
+        if cfg.modes.contains(&SkimmerMode::Cw) {
+            // Each chunk is as wide as one extractor's audio.
+            let chunk_bins = rt.audio_max_fft_size / 2 - 1;
+            let top = (rt.basefreq + rt.total_bandwidth) as f64;
+            for &(band, start, end) in CW_SEGMENTS.iter() {
+                if !cfg.bands.is_empty() && !cfg.bands.iter().any(|b| b.eq_ignore_ascii_case(band))
+                {
+                    continue;
+                }
+                let (mut low, end) = ((start.max(rt.basefreq)) as f64, (end as f64).min(top));
+                while low < end {
+                    let start_bin = ((low - CW_EDGE_HZ - rt.basefreq as f64) / bin_hz)
+                        .floor()
+                        .max(0.0) as usize;
+                    let dial_hz = rt.basefreq as f64 + start_bin as f64 * bin_hz;
+                    let bins = chunk_bins.min(rt.fft_result_size - start_bin);
+                    let high = (bins as f64 * bin_hz - CW_EDGE_HZ).min(end - dial_hz);
+                    if high <= low - dial_hz {
+                        break;
+                    }
+                    cw_channels.push(CwChannel {
+                        band,
+                        dial_hz: dial_hz.round() as i64,
+                        extractor: SubbandExtractor::new(rt, start_bin, bins),
+                        skimmer: cw::Skimmer::new(audio_rate, low - dial_hz, high),
+                    });
+                    low = dial_hz + high;
+                }
+            }
+        }
+        if channels.is_empty() && cw_channels.is_empty() {
+            tracing::warn!(receiver_id = %receiver_id, "skimmer enabled but no band fits the receiver range");
+            return Ok(None);
+        }
+        for ch in channels.iter() {
+            tracing::info!(
+                receiver_id = %receiver_id,
+                band = ch.band,
+                mode = ch.mode.name(),
+                dial_hz = ch.dial_hz,
+                "skimmer channel"
+            );
+        }
+        for ch in cw_channels.iter() {
+            tracing::info!(
+                receiver_id = %receiver_id,
+                band = ch.band,
+                mode = "CW",
+                dial_hz = ch.dial_hz,
+                "skimmer channel"
+            );
+        }
+
+        let (work_tx, work_rx) = mpsc::sync_channel::<SlotJob>(DECODE_QUEUE_DEPTH);
+        let worker_id = receiver_id.clone();
+        let worker_state = state.clone();
+        thread::Builder::new()
+            .name(format!("novasdr-skimmer-{receiver_id}"))
+            .spawn(move || decode_worker(worker_state, worker_id, work_rx))?;
+
+        Ok(Some(Self {
+            state,
+            receiver_id,
+            channels,
+            cw_channels,
+            cw_spotted: HashMap::new(),
+            base_idx: if rt.is_real { 0 } else { rt.fft_size / 2 + 1 },
+            frame_ms: (rt.fft_size / 2) as f64 * 1000.0 / rt.sps as f64,
+            work_tx,
+            dropped_slots: 0,
+            slots: Vec::new(),
+        }))
+    }
+
+    /// Feeds one FFT frame; `now_ms` is the wall-clock time the frame finished.
+    pub fn process(&mut self, spectrum: &[Complex32], frame_num: u64, now_ms: u64) {
+        for ch in self.channels.iter_mut() {
+            let audio = ch.extractor.process(spectrum, self.base_idx, frame_num);
+            ch.audio.clear();
+            ch.resampler.process(audio, &mut ch.audio);
+            let t0 = now_ms.saturating_sub(self.frame_ms as u64);
+            ch.cutter.push(&ch.audio, t0, &mut self.slots);
+            for (start_ms, samples) in self.slots.drain(..) {
+                let job = SlotJob {
+                    band: ch.band,
+                    mode: ch.mode,
+                    dial_hz: ch.dial_hz,
+                    start_ms,
+                    samples,
+                };
+                if let Err(mpsc::TrySendError::Full(_)) = self.work_tx.try_send(job) {
+                    self.dropped_slots = self.dropped_slots.saturating_add(1);
+                    if self.dropped_slots == 1 || self.dropped_slots.is_power_of_two() {
+                        tracing::warn!(
+                            receiver_id = %self.receiver_id,
+                            dropped_slots = self.dropped_slots,
+                            "skimmer decoder cannot keep up; dropping slots"
+                        );
+                    }
+                }
+            }
+        }
+        for ch in self.cw_channels.iter_mut() {
+            let audio = ch.extractor.process(spectrum, self.base_idx, frame_num);
+            ch.skimmer.process(audio);
+            while let Some(spot) = ch.skimmer.pop_spot() {
+                let frequency_hz = ch.dial_hz + spot.freq_hz.round() as i64;
+                let respot = self
+                    .cw_spotted
+                    .get(&spot.callsign)
+                    .is_some_and(|&(hz, at)| {
+                        (hz - frequency_hz).abs() <= CW_SAME_SIGNAL_HZ
+                            && now_ms.saturating_sub(at) < CW_RESPOT_MS
+                    });
+                if respot {
+                    continue;
+                }
+                if self.cw_spotted.len() >= CW_SPOTTED_MAX {
+                    self.cw_spotted
+                        .retain(|_, (_, at)| now_ms.saturating_sub(*at) < CW_RESPOT_MS);
+                }
+                self.cw_spotted
+                    .insert(spot.callsign.clone(), (frequency_hz, now_ms));
+                tracing::debug!(
+                    receiver_id = %self.receiver_id,
+                    band = ch.band,
+                    mode = "CW",
+                    snr_db = spot.snr_db,
+                    freq_hz = spot.freq_hz,
+                    text = %spot.text,
+                    "skimmer decode"
+                );
+                let spot = Spot {
+                    callsign: spot.callsign,
+                    locator: None,
+                    frequency_hz,
+                    snr_db: spot.snr_db,
+                    mode: "CW",
+                    band: ch.band,
+                    time_unix: (now_ms / 1000) as i64,
+                    dt_s: None,
+                    wpm: Some(spot.wpm),
+                    power_dbm: None,
+                    drift_hz: None,
+                    message: spot.text,
+                };
+                publish(&self.state, &self.receiver_id, spot, None);
+            }
+        }
+    }
+}
+
+fn decode_worker(state: Arc<AppState>, receiver_id: String, work_rx: mpsc::Receiver<SlotJob>) {
+    let ft_opts = DecodeOptions::default();
+    let wspr_opts = wspr::DecodeOptions::default();
+    let pskreporter = state
+        .cfg()
+        .pskreporter
+        .enabled
+        .then_some(Uploader::PskReporter);
+    let wsprnet = state.cfg().wsprnet.enabled.then_some(Uploader::Wsprnet);
+    while let Ok(job) = work_rx.recv() {
+        let time_unix = (job.start_ms / 1000) as i64;
+        let spots: Vec<Spot> = match job.mode {
+            Mode::Ft(protocol) => ft8::decode(protocol, &job.samples, &ft_opts)
+                .into_iter()
+                .filter_map(|d| {
+                    tracing::debug!(
+                        receiver_id = %receiver_id,
+                        band = job.band,
+                        mode = protocol.name(),
+                        snr_db = d.snr_db,
+                        freq_hz = d.freq_hz,
+                        text = %d.message.text,
+                        "skimmer decode"
+                    );
+                    Some(Spot {
+                        callsign: d.message.sender?,
+                        locator: d.message.locator,
+                        frequency_hz: job.dial_hz + d.freq_hz.round() as i64,
+                        snr_db: d.snr_db,
+                        mode: protocol.name(),
+                        band: job.band,
+                        time_unix,
+                        dt_s: Some(d.dt_s),
+                        wpm: None,
+                        power_dbm: None,
+                        drift_hz: None,
+                        message: d.message.text,
+                    })
+                })
+                .collect(),
+            Mode::Wspr => wspr::decode(&job.samples, &wspr_opts)
+                .into_iter()
+                .map(|d| {
+                    let message = d.message.text();
+                    tracing::debug!(
+                        receiver_id = %receiver_id,
+                        band = job.band,
+                        mode = "WSPR",
+                        snr_db = d.snr_db,
+                        freq_hz = d.freq_hz,
+                        text = %message,
+                        "skimmer decode"
+                    );
+                    Spot {
+                        callsign: d.message.callsign,
+                        locator: Some(d.message.locator),
+                        frequency_hz: job.dial_hz + d.freq_hz.round() as i64,
+                        snr_db: d.snr_db,
+                        mode: job.mode.name(),
+                        band: job.band,
+                        time_unix,
+                        dt_s: Some(d.dt_s),
+                        wpm: None,
+                        power_dbm: Some(d.message.power_dbm),
+                        drift_hz: Some(d.drift_hz.round() as i32),
+                        message,
+                    }
+                })
+                .collect(),
+        };
+        let upload = match job.mode {
+            Mode::Ft(_) => pskreporter,
+            Mode::Wspr => wsprnet,
+        };
+        for spot in spots {
+            publish(&state, &receiver_id, spot, upload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_cutter_aligns_to_slot_boundaries() {
+        let mut cutter = SlotCutter::new(Protocol::Ft4.slot_ms());
+        let mut out = Vec::new();
+        let chunk = vec![1.0f32; 1200];
+        // Join 300 ms into the slot starting at 7.5 s.
+        let mut t = 7_800u64;
+        for _ in 0..200 {
+            cutter.push(&chunk, t, &mut out);
+            t += 100;
+        }
+        assert!(out.len() >= 2);
+        assert_eq!(out[0].0, 7_500);
+        assert_eq!(out[1].0, 15_000);
+        assert!(out[0].1[..3600].iter().all(|&v| v == 0.0));
+        assert_eq!(out[0].1.len(), cutter.slot_samples());
+    }
+
+    #[test]
+    fn slot_cutter_skips_slots_joined_late() {
+        let mut cutter = SlotCutter::new(Protocol::Ft8.slot_ms());
+        let mut out = Vec::new();
+        let chunk = vec![1.0f32; 1200];
+        let mut t = 5_000u64;
+        for _ in 0..260 {
+            cutter.push(&chunk, t, &mut out);
+            t += 100;
+        }
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].0, 15_000);
+        assert!(out[0].1.iter().all(|&v| v == 1.0));
+    }
+
+    #[test]
+    fn resampler_preserves_duration() {
+        let mut r = Resampler::new(16_000.0);
+        let mut out = Vec::new();
+        for _ in 0..10 {
+            r.process(&[0.5f32; 1600], &mut out);
+        }
+        assert!((out.len() as i64 - 12_000).abs() <= 2, "{}", out.len());
+        // Only the first samples see the zero the stream is primed with.
+        assert!(out[4..].iter().all(|v| (v - 0.5).abs() < 1e-4));
+    }
+}
@@ -0,0 +1,330 @@
+use super::Spot;
+use crate::{shutdown, state::AppState};
+use anyhow::Context;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const IPFIX_VERSION: u16 = 0x000a;
+const PSKREPORTER_ENTERPRISE: u32 = 30351;
+const RECEIVER_TEMPLATE_ID: u16 = 0x9992;
+const SENDER_TEMPLATE_ID: u16 = 0x9993;
+const OPTIONS_TEMPLATE_SET_ID: u16 = 3;
+const TEMPLATE_SET_ID: u16 = 2;
+const VARIABLE_LENGTH: u16 = 0xffff;
+/// IANA `flowStartSeconds`.
+const FLOW_START_SECONDS: u16 = 150;
+/// `informationSource`: spot produced automatically by a skimmer.
+const INFORMATION_SOURCE_AUTOMATIC: u8 = 1;
+
+const MAX_PACKET_BYTES: usize = 1400;
+const UPLOAD_INTERVAL: Duration = Duration::from_secs(300);
+const TEMPLATE_REFRESH: Duration = Duration::from_secs(3600);
+/// Templates are repeated on the first packets so a lost datagram does not drop the session.
+const INITIAL_TEMPLATE_PACKETS: u32 = 3;
+
+struct ReceiverInfo {
+    callsign: String,
+    locator: String,
+    software: String,
+    antenna: String,
+}
+
+fn push_field(buf: &mut Vec<u8>, id: u16, len: u16) {
+    buf.extend_from_slice(&(id | 0x8000).to_be_bytes());
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(&PSKREPORTER_ENTERPRISE.to_be_bytes());
+}
+
+fn push_string(buf: &mut Vec<u8>, s: &str) {
+    let bytes = &s.as_bytes()[..s.len().min(254)];
+    buf.push(bytes.len() as u8);
+    buf.extend_from_slice(bytes);
+}
+
+/// Wraps a set body in its header and pads it to a 4-byte boundary.
+fn finish_set(set_id: u16, body: &[u8]) -> Vec<u8> {
+    let padded_len = (4 + body.len()).div_ceil(4) * 4;
+    let mut out = Vec::with_capacity(padded_len);
+    out.extend_from_slice(&set_id.to_be_bytes());
+    out.extend_from_slice(&(padded_len as u16).to_be_bytes());
+    out.extend_from_slice(body);
+    out.resize(padded_len, 0);
+    out
+}
+
+fn templates() -> Vec<u8> {
+    let mut receiver = Vec::new();
+    receiver.extend_from_slice(&RECEIVER_TEMPLATE_ID.to_be_bytes());
+    receiver.extend_from_slice(&4u16.to_be_bytes());
+    receiver.extend_from_slice(&0u16.to_be_bytes());
+    push_field(&mut receiver, 2, VARIABLE_LENGTH); // receiverCallsign
+    push_field(&mut receiver, 4, VARIABLE_LENGTH); // receiverLocator
+    push_field(&mut receiver, 8, VARIABLE_LENGTH); // decoderSoftware
+    push_field(&mut receiver, 9, VARIABLE_LENGTH); // antennaInformation
+
+    let mut sender = Vec::new();
+    sender.extend_from_slice(&SENDER_TEMPLATE_ID.to_be_bytes());
+    sender.extend_from_slice(&7u16.to_be_bytes());
+    push_field(&mut sender, 1, VARIABLE_LENGTH); // senderCallsign
+    push_field(&mut sender, 3, VARIABLE_LENGTH); // senderLocator
+    push_field(&mut sender, 5, 4); // frequency
+    push_field(&mut sender, 6, 1); // sNR
+    push_field(&mut sender, 10, VARIABLE_LENGTH); // mode
+    push_field(&mut sender, 11, 1); // informationSource
+    sender.extend_from_slice(&FLOW_START_SECONDS.to_be_bytes());
+    sender.extend_from_slice(&4u16.to_be_bytes());
+
+    let mut out = finish_set(OPTIONS_TEMPLATE_SET_ID, &receiver);
+    out.extend_from_slice(&finish_set(TEMPLATE_SET_ID, &sender));
+    out
+}
+
+fn receiver_set(info: &ReceiverInfo) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_string(&mut body, &info.callsign);
+    push_string(&mut body, &info.locator);
+    push_string(&mut body, &info.software);
+    push_string(&mut body, &info.antenna);
+    finish_set(RECEIVER_TEMPLATE_ID, &body)
+}
+
+fn sender_record(spot: &Spot) -> Vec<u8> {
+    let mut rec = Vec::new();
+    push_string(&mut rec, &spot.callsign);
+    push_string(&mut rec, spot.locator.as_deref().unwrap_or(""));
+    rec.extend_from_slice(&(spot.frequency_hz.clamp(0, u32::MAX as i64) as u32).to_be_bytes());
+    rec.push(spot.snr_db.clamp(i8::MIN as i32, i8::MAX as i32) as i8 as u8);
+    push_string(&mut rec, spot.mode);
+    rec.push(INFORMATION_SOURCE_AUTOMATIC);
+    rec.extend_from_slice(&(spot.time_unix.max(0) as u32).to_be_bytes());
+    rec
+}
+
+struct Session {
+    info: ReceiverInfo,
+    observation_domain: u32,
+    sequence: u32,
+    packets_sent: u32,
+    templates_sent_at: Option<Instant>,
+}
+
+impl Session {
+    fn wants_templates(&self) -> bool {
+        self.packets_sent < INITIAL_TEMPLATE_PACKETS
+            || self
+                .templates_sent_at
+                .is_none_or(|at| at.elapsed() >= TEMPLATE_REFRESH)
+    }
+
+    /// Splits spots into datagrams no larger than [`MAX_PACKET_BYTES`].
+    fn build_packets(&mut self, spots: &[Spot], export_time: u32) -> Vec<Vec<u8>> {
+        let records: Vec<Vec<u8>> = spots.iter().map(sender_record).collect();
+        let mut packets = Vec::new();
+        let mut idx = 0usize;
+        while idx < records.len() {
+            let mut body = Vec::new();
+            if self.wants_templates() {
+                body.extend_from_slice(&templates());
+                self.templates_sent_at = Some(Instant::now());
+            }
+            body.extend_from_slice(&receiver_set(&self.info));
+
+            let budget = MAX_PACKET_BYTES.saturating_sub(16 + body.len() + 4 + 3);
+            let mut data = Vec::new();
+            while idx < records.len()
+                && (data.is_empty() || data.len() + records[idx].len() <= budget)
+            {
+                data.extend_from_slice(&records[idx]);
+                idx += 1;
+            }
+            body.extend_from_slice(&finish_set(SENDER_TEMPLATE_ID, &data));
+
+            let mut packet = Vec::with_capacity(16 + body.len());
+            packet.extend_from_slice(&IPFIX_VERSION.to_be_bytes());
+            packet.extend_from_slice(&((16 + body.len()) as u16).to_be_bytes());
+            packet.extend_from_slice(&export_time.to_be_bytes());
+            packet.extend_from_slice(&self.sequence.to_be_bytes());
+            packet.extend_from_slice(&self.observation_domain.to_be_bytes());
+            packet.extend_from_slice(&body);
+            self.sequence = self.sequence.wrapping_add(1);
+            self.packets_sent = self.packets_sent.saturating_add(1);
+            packets.push(packet);
+        }
+        packets
+    }
+}
+
+/// Keeps one spot per callsign, band and mode, as PSK Reporter asks of automatic spotters.
+fn dedup(spots: Vec<Spot>) -> Vec<Spot> {
+    let mut seen = std::collections::HashSet::new();
+    spots
+        .into_iter()
+        .filter(|s| seen.insert((s.callsign.clone(), s.band, s.mode)))
+        .collect()
+}
+
+pub fn spawn(state: Arc<AppState>) {
+    let cfg = state.cfg();
+    if !cfg.pskreporter.enabled {
+        return;
+    }
+    let pick = |own: &str, fallback: &str| {
+        if own.trim().is_empty() {
+            fallback.trim().to_string()
+        } else {
+            own.trim().to_string()
+        }
+    };
+    let info = ReceiverInfo {
+        callsign: cfg.pskreporter.callsign.trim().to_ascii_uppercase(),
+        locator: pick(&cfg.pskreporter.locator, &cfg.websdr.grid_locator),
+        software: format!("NovaSDR {}", env!("CARGO_PKG_VERSION")),
+        antenna: pick(&cfg.pskreporter.antenna, &cfg.websdr.antenna),
+    };
+    let host = cfg.pskreporter.host.clone();
+    tracing::info!(%host, callsign = %info.callsign, "PSK Reporter upload enabled");
+
+    tokio::spawn(async move {
+        let mut session = Session {
+            info,
+            observation_domain: rand::random(),
+            sequence: 0,
+            packets_sent: 0,
+            templates_sent_at: None,
+        };
+        while !shutdown::is_shutdown_requested() {
+            tokio::time::sleep(UPLOAD_INTERVAL).await;
+            let spots = dedup(
+                state
+                    .skimmer_spots
+                    .take_pending(super::Uploader::PskReporter),
+            );
+            if spots.is_empty() {
+                continue;
+            }
+            match upload(&host, &mut session, &spots).await {
+                Ok(packets) => {
+                    tracing::info!(spots = spots.len(), packets, "PSK Reporter upload sent");
+                }
+                Err(e) => {
+                    tracing::warn!(error = ?e, spots = spots.len(), "PSK Reporter upload failed");
+                }
+            }
+        }
+    });
+}
+
+async fn upload(host: &str, session: &mut Session, spots: &[Spot]) -> anyhow::Result<usize> {
+    let addr = tokio::net::lookup_host(host)
+        .await
+        .with_context(|| format!("resolve {host}"))?
+        .next()
+        .with_context(|| format!("no address for {host}"))?;
+    let bind = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = tokio::net::UdpSocket::bind(bind)
+        .await
+        .context("bind UDP socket")?;
+    let export_time = chrono::Utc::now().timestamp().max(0) as u32;
+    let packets = session.build_packets(spots, export_time);
+    for packet in packets.iter() {
+        socket
+            .send_to(packet, addr)
+            .await
+            .with_context(|| format!("send to {addr}"))?;
+    }
+    Ok(packets.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spot(call: &str) -> Spot {
+        Spot {
+            callsign: call.to_string(),
+            locator: Some("FN42".to_string()),
+            frequency_hz: 14_075_234,
+            snr_db: -12,
+            mode: "FT8",
+            band: "20m",
+            time_unix: 1_700_000_000,
+            dt_s: Some(0.1),
+            wpm: None,
+            power_dbm: None,
+            drift_hz: None,
+            message: format!("CQ {call} FN42"),
+        }
+    }
+
+    fn session() -> Session {
+        Session {
+            info: ReceiverInfo {
+                callsign: "N0CALL".to_string(),
+                locator: "JO22".to_string(),
+                software: "NovaSDR test".to_string(),
+                antenna: "dipole".to_string(),
+            },
+            observation_domain: 42,
+            sequence: 0,
+            packets_sent: 0,
+            templates_sent_at: None,
+        }
+    }
+
+    /// Walks the sets of a packet and returns `(set_id, set_len)` pairs.
+    fn sets(packet: &[u8]) -> Vec<(u16, usize)> {
+        let mut out = Vec::new();
+        let mut pos = 16usize;
+        while pos < packet.len() {
+            let id = u16::from_be_bytes([packet[pos], packet[pos + 1]]);
+            let len = u16::from_be_bytes([packet[pos + 2], packet[pos + 3]]) as usize;
+            out.push((id, len));
+            pos += len;
+        }
+        assert_eq!(pos, packet.len());
+        out
+    }
+
+    #[test]
+    fn first_packet_carries_templates_and_valid_lengths() {
+        let mut s = session();
+        let packets = s.build_packets(&[spot("K1ABC"), spot("W9XYZ")], 1_700_000_100);
+        assert_eq!(packets.len(), 1);
+        let p = &packets[0];
+        assert_eq!(u16::from_be_bytes([p[0], p[1]]), IPFIX_VERSION);
+        assert_eq!(u16::from_be_bytes([p[2], p[3]]) as usize, p.len());
+        let ids: Vec<u16> = sets(p).iter().map(|(id, _)| *id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                OPTIONS_TEMPLATE_SET_ID,
+                TEMPLATE_SET_ID,
+                RECEIVER_TEMPLATE_ID,
+                SENDER_TEMPLATE_ID
+            ]
+        );
+        assert!(sets(p).iter().all(|(_, len)| len % 4 == 0));
+    }
+
+    #[test]
+    fn large_batches_are_split_below_the_datagram_limit() {
+        let mut s = session();
+        s.packets_sent = INITIAL_TEMPLATE_PACKETS;
+        s.templates_sent_at = Some(Instant::now());
+        let spots: Vec<Spot> = (0..200).map(|i| spot(&format!("K{}ABC", i % 10))).collect();
+        let packets = s.build_packets(&spots, 0);
+        assert!(packets.len() > 1);
+        assert!(packets.iter().all(|p| p.len() <= MAX_PACKET_BYTES));
+        assert_eq!(s.sequence as usize, packets.len());
+    }
+
+    #[test]
+    fn dedup_keeps_one_spot_per_call_band_and_mode() {
+        let spots = vec![spot("K1ABC"), spot("K1ABC"), spot("W9XYZ")];
+        assert_eq!(dedup(spots).len(), 2);
+    }
+}
@@ -0,0 +1,168 @@
+use super::Spot;
+use crate::{shutdown, state::AppState};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+
+/// Time a client gets to send its callsign, as CW Skimmer's telnet server asks for one.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_LOGIN_LEN: usize = 64;
+
+/// Serves CW spots in the telnet format of CW Skimmer, which the Reverse Beacon Network
+/// aggregator reads, when `rbn.enabled`.
+pub fn spawn(state: Arc<AppState>) {
+    let cfg = state.cfg().rbn.clone();
+    if !cfg.enabled {
+        return;
+    }
+    let callsign = cfg.callsign.trim().to_ascii_uppercase();
+
+    tokio::spawn(async move {
+        let addr = format!("{}:{}", cfg.host, cfg.port);
+        let listener = match TcpListener::bind(addr.as_str()).await {
+            Ok(l) => l,
+            Err(e) => {
+                tracing::error!(bind = %addr, error = ?e, "rbn feed disabled: cannot bind");
+                return;
+            }
+        };
+        tracing::info!(bind = %addr, callsign = %callsign, "rbn feed listening");
+        loop {
+            let (socket, peer) = match listener.accept().await {
+                Ok(v) => v,
+                Err(e) => {
+                    tracing::warn!(error = ?e, "rbn accept failed");
+                    continue;
+                }
+            };
+            let ip_guard = match state.try_acquire_ws_ip(peer.ip()) {
+                Ok(guard) => guard,
+                Err(_) => {
+                    tracing::info!(%peer, "rbn client refused by connection limits");
+                    continue;
+                }
+            };
+            let state = state.clone();
+            let callsign = callsign.clone();
+            tokio::spawn(async move {
+                let _ip_guard = ip_guard;
+                if let Err(e) = serve_client(socket, peer, &state, &callsign).await {
+                    tracing::debug!(%peer, error = ?e, "rbn client ended");
+                }
+                tracing::info!(%peer, "rbn client disconnected");
+            });
+        }
+    });
+}
+
+async fn serve_client(
+    socket: TcpStream,
+    peer: SocketAddr,
+    state: &AppState,
+    callsign: &str,
+) -> anyhow::Result<()> {
+    let (reader, mut writer) = socket.into_split();
+    let mut reader = BufReader::new(reader);
+    writer.write_all(b"Please enter your callsign: ").await?;
+    let mut login = String::new();
+    let mut limited = (&mut reader).take(MAX_LOGIN_LEN as u64);
+    tokio::time::timeout(LOGIN_TIMEOUT, limited.read_line(&mut login)).await??;
+    let login = login.trim();
+    tracing::info!(%peer, login = %login, "rbn client connected");
+    writer
+        .write_all(
+            format!("\r\nHello {login}, this is {callsign} running NovaSDR\r\n\r\n{callsign}>\r\n")
+                .as_bytes(),
+        )
+        .await?;
+
+    let mut spots = state.skimmer_spots.subscribe();
+    // Anything the client sends after logging in is ignored; reading it only tells when it
+    // hangs up.
+    let mut input = [0u8; 256];
+    while !shutdown::is_shutdown_requested() {
+        let spot = tokio::select! {
+            read = reader.read(&mut input) => {
+                if read? == 0 {
+                    break;
+                }
+                continue;
+            }
+            spot = spots.recv() => spot,
+        };
+        match spot {
+            Ok(spot) if spot.mode == "CW" => {
+                writer
+                    .write_all(spot_line(callsign, &spot).as_bytes())
+                    .await?;
+            }
+            Ok(_) => {}
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::debug!(%peer, skipped, "rbn client fell behind");
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+    Ok(())
+}
+
+/// One spot as CW Skimmer prints it.
+fn spot_line(callsign: &str, spot: &Spot) -> String {
+    let kind = if spot
+        .message
+        .split_whitespace()
+        .any(|w| w == "CQ" || w == "TEST")
+    {
+        "CQ"
+    } else {
+        "DX"
+    };
+    let time = chrono::DateTime::from_timestamp(spot.time_unix, 0)
+        .map(|t| t.format("%H%M").to_string())
+        .unwrap_or_default();
+    format!(
+        "DX de {:<10} {:>8.1}  {:<12} CW {:>4} dB {:>3} WPM  {:<2}  {}Z\r\n",
+        format!("{callsign}-#:"),
+        spot.frequency_hz as f64 / 1e3,
+        spot.callsign,
+        spot.snr_db,
+        spot.wpm.unwrap_or_default(),
+        kind,
+        time,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spot_line_matches_cw_skimmer() {
+        let spot = Spot {
+            callsign: "K1ABC".to_string(),
+            locator: None,
+            frequency_hz: 14_025_130,
+            snr_db: 24,
+            mode: "CW",
+            band: "20m",
+            time_unix: 1_700_000_040,
+            dt_s: None,
+            wpm: Some(22),
+            power_dbm: None,
+            drift_hz: None,
+            message: "CQ CQ DE K1ABC".to_string(),
+        };
+        assert_eq!(
+            spot_line("N0CALL", &spot),
+            "DX de N0CALL-#:   14025.1  K1ABC        CW   24 dB  22 WPM  CQ  2214Z\r\n"
+        );
+        let spot = Spot {
+            message: "TU DE K1ABC".to_string(),
+            ..spot
+        };
+        assert!(spot_line("N0CALL", &spot).contains(" WPM  DX  "));
+    }
+}
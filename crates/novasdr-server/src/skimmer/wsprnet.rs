@@ -0,0 +1,150 @@
+use super::{Spot, Uploader, BANDS};
+use crate::{shutdown, state::AppState};
+use anyhow::Context;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Decodes land at the end of each 2-minute slot; polling more often only shortens the delay.
+const UPLOAD_INTERVAL: Duration = Duration::from_secs(30);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+/// `mode=2` selects the 2-minute WSPR-2 protocol.
+const WSPR2_MODE: &str = "2";
+
+struct Reporter {
+    callsign: String,
+    locator: String,
+    version: String,
+}
+
+fn mhz(hz: i64) -> String {
+    format!("{:.6}", hz as f64 / 1e6)
+}
+
+/// Query parameters of the wsprnet.org single-spot `post` API.
+fn spot_query(reporter: &Reporter, spot: &Spot) -> Option<Vec<(&'static str, String)>> {
+    let dial_hz = BANDS.iter().find(|b| b.0 == spot.band).map(|b| b.3)?;
+    let time = chrono::DateTime::from_timestamp(spot.time_unix, 0)?;
+    Some(vec![
+        ("function", "wspr".to_string()),
+        ("rcall", reporter.callsign.clone()),
+        ("rgrid", reporter.locator.clone()),
+        ("rqrg", mhz(dial_hz)),
+        ("date", time.format("%y%m%d").to_string()),
+        ("time", time.format("%H%M").to_string()),
+        ("sig", spot.snr_db.to_string()),
+        ("dt", format!("{:.1}", spot.dt_s.unwrap_or_default())),
+        ("drift", spot.drift_hz.unwrap_or(0).to_string()),
+        ("tqrg", mhz(spot.frequency_hz)),
+        ("tcall", spot.callsign.clone()),
+        ("tgrid", spot.locator.clone().unwrap_or_default()),
+        ("dbm", spot.power_dbm.unwrap_or(0).to_string()),
+        ("version", reporter.version.clone()),
+        ("mode", WSPR2_MODE.to_string()),
+    ])
+}
+
+pub fn spawn(state: Arc<AppState>) {
+    let cfg = state.cfg();
+    if !cfg.wsprnet.enabled {
+        return;
+    }
+    let locator = if cfg.wsprnet.locator.trim().is_empty() {
+        cfg.websdr.grid_locator.trim()
+    } else {
+        cfg.wsprnet.locator.trim()
+    };
+    let reporter = Reporter {
+        callsign: cfg.wsprnet.callsign.trim().to_ascii_uppercase(),
+        locator: locator.to_string(),
+        version: format!("NovaSDR {}", env!("CARGO_PKG_VERSION")),
+    };
+    let url = cfg.wsprnet.url.clone();
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!(error = ?e, "wsprnet upload disabled: cannot build HTTP client");
+            return;
+        }
+    };
+    tracing::info!(%url, callsign = %reporter.callsign, "wsprnet upload enabled");
+
+    tokio::spawn(async move {
+        while !shutdown::is_shutdown_requested() {
+            tokio::time::sleep(UPLOAD_INTERVAL).await;
+            let spots = state.skimmer_spots.take_pending(Uploader::Wsprnet);
+            if spots.is_empty() {
+                continue;
+            }
+            let mut sent = 0usize;
+            for spot in spots.iter() {
+                match upload(&client, &url, &reporter, spot).await {
+                    Ok(()) => sent += 1,
+                    Err(e) => {
+                        tracing::warn!(error = ?e, callsign = %spot.callsign, "wsprnet upload failed");
+                    }
+                }
+            }
+            tracing::info!(spots = spots.len(), sent, "wsprnet upload sent");
+        }
+    });
+}
+
+async fn upload(
+    client: &reqwest::Client,
+    url: &str,
+    reporter: &Reporter,
+    spot: &Spot,
+) -> anyhow::Result<()> {
+    let query = spot_query(reporter, spot).context("spot outside the WSPR band plan")?;
+    client
+        .get(url)
+        .query(&query)
+        .send()
+        .await
+        .with_context(|| format!("GET {url}"))?
+        .error_for_status()
+        .context("wsprnet rejected spot")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spot_query_uses_wsprnet_units() {
+        let reporter = Reporter {
+            callsign: "N0CALL".to_string(),
+            locator: "JO22".to_string(),
+            version: "NovaSDR test".to_string(),
+        };
+        let spot = Spot {
+            callsign: "K1ABC".to_string(),
+            locator: Some("FN42".to_string()),
+            frequency_hz: 14_097_100,
+            snr_db: -21,
+            mode: "WSPR",
+            band: "20m",
+            time_unix: 1_700_000_040,
+            dt_s: Some(0.34),
+            wpm: None,
+            power_dbm: Some(37),
+            drift_hz: Some(-1),
+            message: "K1ABC FN42 37".to_string(),
+        };
+        let query = spot_query(&reporter, &spot).expect("query");
+        let get = |key: &str| {
+            query
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("rqrg"), Some("14.095600"));
+        assert_eq!(get("tqrg"), Some("14.097100"));
+        assert_eq!(get("date"), Some("231114"));
+        assert_eq!(get("time"), Some("2214"));
+        assert_eq!(get("dt"), Some("0.3"));
+        assert_eq!(get("drift"), Some("-1"));
+        assert_eq!(get("dbm"), Some("37"));
+    }
+}
@@ -0,0 +1,377 @@
+//! Server state snapshots: `config.json`, `receivers.json`, the overlays (markers, bands, masks,
+//! marker suggestions), user accounts with their bookmarks, schedules, S-meter calibrations, the
+//! identity key and the frequency database, bundled into one JSON archive. `GET /admin/snapshot`
+//! exports it; `--import-snapshot <file>` writes it back at startup, before the configuration
+//! is loaded, to move a configured receiver to another host or restore a backup.
+
+use crate::state::{AppState, ConfigPaths};
+use anyhow::Context;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use novasdr_core::config::Config;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+const FORMAT: &str = "novasdr-snapshot";
+const VERSION: u32 = 1;
+/// Replaced files are kept next to the new ones with this suffix.
+const BACKUP_SUFFIX: &str = ".pre-import";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    format: String,
+    version: u32,
+    /// RFC 3339 time of the export.
+    created: String,
+    server_version: String,
+    files: Vec<SnapshotFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SnapshotFile {
+    /// `config.json`, `receivers.json`, `overlays/<name>`, or a path relative to the directory
+    /// of `receivers.json`, like the other files the configuration names.
+    path: String,
+    /// Contents, base64.
+    data: String,
+}
+
+/// What [`import`] wrote.
+#[derive(Debug, PartialEq)]
+pub struct Imported {
+    pub created: String,
+    pub files: Vec<String>,
+    /// Existing files that differed, kept with [`BACKUP_SUFFIX`].
+    pub backups: usize,
+}
+
+/// `GET /admin/snapshot`: the archive as a download.
+pub async fn export(State(state): State<Arc<AppState>>) -> Response {
+    let cfg = state.cfg();
+    let paths = state.config_paths.clone();
+    let snapshot = tokio::task::spawn_blocking(move || collect(&cfg, &paths)).await;
+    let snapshot = match snapshot {
+        Ok(Ok(snapshot)) => snapshot,
+        Ok(Err(e)) => {
+            tracing::error!(error = ?e, "snapshot export failed");
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")).into_response();
+        }
+        Err(e) => {
+            tracing::error!(error = ?e, "snapshot export task failed");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let body = match serde_json::to_vec_pretty(&snapshot) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!(error = ?e, "snapshot serialize failed");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    tracing::info!(
+        files = snapshot.files.len(),
+        bytes = body.len(),
+        "admin: snapshot exported"
+    );
+    let name = format!(
+        "novasdr-snapshot-{}.json",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    );
+    (
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{name}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Reads every file of the server's state that exists. Files the configuration names outside
+/// its own directory (absolute paths, `..`) are left out with a warning: they would not land
+/// in the same place on another host.
+pub fn collect(cfg: &Config, paths: &ConfigPaths) -> anyhow::Result<Snapshot> {
+    let mut files = Vec::new();
+    for (name, path) in [
+        ("config.json", &paths.config),
+        ("receivers.json", &paths.receivers),
+    ] {
+        let data = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        files.push(SnapshotFile {
+            path: name.to_string(),
+            data: BASE64.encode(data),
+        });
+    }
+
+    let mut overlays = Vec::new();
+    if let Ok(dir) = std::fs::read_dir(&paths.overlays) {
+        for entry in dir.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type().is_ok_and(|t| t.is_file()) && !name.ends_with(".tmp") {
+                overlays.push(format!("overlays/{name}"));
+            }
+        }
+    }
+    overlays.sort();
+
+    let mut named = vec![
+        crate::users::FILE_NAME.to_string(),
+        crate::schedule::FILE_NAME.to_string(),
+    ];
+    for r in cfg.receivers.iter() {
+        named.push(match r.input.smeter_calibration.as_deref() {
+            Some(path) => path.trim().to_string(),
+            None => format!("smeter_calibration_{}.json", r.id),
+        });
+    }
+    named.push(cfg.websdr.identity_key.trim().to_string());
+    named.push(cfg.freqdb.csv.trim().to_string());
+
+    for name in overlays.into_iter().chain(named) {
+        if name.is_empty() || files.iter().any(|f| f.path == name) {
+            continue;
+        }
+        if !is_relative_name(&name) {
+            tracing::warn!(path = %name, "snapshot: file outside the config directory left out");
+            continue;
+        }
+        let path = destination(paths, &name);
+        match std::fs::read(&path) {
+            Ok(data) => files.push(SnapshotFile {
+                path: name,
+                data: BASE64.encode(data),
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        }
+    }
+
+    Ok(Snapshot {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        created: chrono::Utc::now().to_rfc3339(),
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+        files,
+    })
+}
+
+/// Writes the files of the archive at `archive` to where `paths` puts them. The configuration
+/// in it must load; otherwise nothing is written.
+pub fn import(archive: &Path, paths: &ConfigPaths) -> anyhow::Result<Imported> {
+    let raw = std::fs::read(archive).with_context(|| format!("read {}", archive.display()))?;
+    let snapshot: Snapshot =
+        serde_json::from_slice(&raw).with_context(|| format!("parse {}", archive.display()))?;
+    anyhow::ensure!(
+        snapshot.format == FORMAT,
+        "{} is not a NovaSDR snapshot",
+        archive.display()
+    );
+    anyhow::ensure!(
+        snapshot.version <= VERSION,
+        "snapshot version {} is newer than this server supports ({VERSION})",
+        snapshot.version
+    );
+
+    let mut files = Vec::with_capacity(snapshot.files.len());
+    for file in snapshot.files.iter() {
+        anyhow::ensure!(
+            is_relative_name(&file.path),
+            "snapshot file {:?} is not a relative path",
+            file.path
+        );
+        let data = BASE64
+            .decode(file.data.as_bytes())
+            .with_context(|| format!("decode snapshot file {}", file.path))?;
+        files.push((file.path.as_str(), data));
+    }
+    let contents = |name: &str| {
+        files
+            .iter()
+            .find(|(path, _)| *path == name)
+            .map(|(_, data)| data.as_slice())
+            .with_context(|| format!("snapshot has no {name}"))
+    };
+    check_config(contents("config.json")?, contents("receivers.json")?)?;
+
+    let mut backups = 0;
+    for (name, data) in files.iter() {
+        let path = destination(paths, name);
+        match std::fs::read(&path) {
+            Ok(existing) if existing == *data => continue,
+            Ok(_) => {
+                let mut backup = path.clone().into_os_string();
+                backup.push(BACKUP_SUFFIX);
+                std::fs::copy(&path, &backup)
+                    .with_context(|| format!("back up {}", path.display()))?;
+                backups += 1;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        }
+        write_file(&path, data)?;
+    }
+    Ok(Imported {
+        created: snapshot.created,
+        files: files.iter().map(|(name, _)| name.to_string()).collect(),
+        backups,
+    })
+}
+
+/// Loads the archived configuration from a scratch directory, so a snapshot that would not
+/// start never replaces a working one.
+fn check_config(config: &[u8], receivers: &[u8]) -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!(
+        "novasdr_snapshot_{}",
+        novasdr_core::util::generate_unique_id()
+    ));
+    std::fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+    let res = (|| {
+        std::fs::write(dir.join("config.json"), config)?;
+        std::fs::write(dir.join("receivers.json"), receivers)?;
+        novasdr_core::config::load_from_files(&dir.join("config.json"), &dir.join("receivers.json"))
+            .context("the snapshot's configuration does not load")
+    })();
+    let _ = std::fs::remove_dir_all(&dir);
+    res.map(|_| ())
+}
+
+/// Where the archive entry `name` belongs.
+fn destination(paths: &ConfigPaths, name: &str) -> PathBuf {
+    match name {
+        "config.json" => paths.config.clone(),
+        "receivers.json" => paths.receivers.clone(),
+        _ => match name.strip_prefix("overlays/") {
+            Some(rest) => paths.overlays.join(rest),
+            None => paths.resolve(name),
+        },
+    }
+}
+
+/// Whether `name` stays below the directory it is resolved against.
+fn is_relative_name(name: &str) -> bool {
+    let path = Path::new(name);
+    path.components().next().is_some()
+        && path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn write_file(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, data).with_context(|| format!("write {}", Path::new(&tmp).display()))?;
+    // std::fs::rename does not reliably replace existing files on Windows.
+    std::fs::copy(&tmp, path).with_context(|| format!("write {}", path.display()))?;
+    let _ = std::fs::remove_file(&tmp);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{ "server": { "port": 9002 }, "websdr": { "name": "NovaSDR" } }"#;
+    const RECEIVERS: &str = r#"{ "receivers": [ { "id": "rx0", "input": { "sps": 2048000,
+        "frequency": 100000000, "signal": "iq", "driver": { "kind": "stdin", "format": "u8" } } } ] }"#;
+
+    fn host() -> (PathBuf, ConfigPaths) {
+        let dir = std::env::temp_dir().join(format!(
+            "novasdr_snapshot_test_{}",
+            novasdr_core::util::generate_unique_id()
+        ));
+        let config = dir.join("config/config.json");
+        let paths = ConfigPaths {
+            receivers: dir.join("config/receivers.json"),
+            overlays: crate::overlays::overlay_paths_for_config(&config).dir,
+            config,
+        };
+        (dir, paths)
+    }
+
+    #[test]
+    fn snapshot_moves_state_to_another_host() {
+        let (old_dir, old) = host();
+        write_file(&old.config, CONFIG.as_bytes()).unwrap();
+        write_file(&old.receivers, RECEIVERS.as_bytes()).unwrap();
+        write_file(&old.overlays.join("markers.json"), b"{\"markers\":[]}").unwrap();
+        write_file(&old.resolve("users.json"), b"{\"accounts\":[]}").unwrap();
+        write_file(&old.resolve("smeter_calibration_rx0.json"), b"{}").unwrap();
+        let cfg = novasdr_core::config::load_from_files(&old.config, &old.receivers).unwrap();
+        let snapshot = collect(&cfg, &old).unwrap();
+        let archive = old_dir.join("snapshot.json");
+        std::fs::write(&archive, serde_json::to_vec(&snapshot).unwrap()).unwrap();
+
+        let (new_dir, new) = host();
+        write_file(&new.config, b"{}").unwrap();
+        let imported = import(&archive, &new).unwrap();
+        assert_eq!(
+            imported.files,
+            [
+                "config.json",
+                "receivers.json",
+                "overlays/markers.json",
+                "users.json",
+                "smeter_calibration_rx0.json"
+            ]
+        );
+        assert_eq!(imported.backups, 1);
+        assert_eq!(
+            std::fs::read(&new.config).unwrap(),
+            std::fs::read(&old.config).unwrap()
+        );
+        assert_eq!(
+            std::fs::read_to_string(new.overlays.join("markers.json")).unwrap(),
+            "{\"markers\":[]}"
+        );
+        let mut backup = new.config.clone().into_os_string();
+        backup.push(BACKUP_SUFFIX);
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), "{}");
+
+        let _ = std::fs::remove_dir_all(old_dir);
+        let _ = std::fs::remove_dir_all(new_dir);
+    }
+
+    #[test]
+    fn unsafe_or_broken_snapshots_write_nothing() {
+        let (dir, paths) = host();
+        let archive = dir.join("snapshot.json");
+        let snapshot = |files: &[(&str, &str)]| Snapshot {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            created: String::new(),
+            server_version: String::new(),
+            files: files
+                .iter()
+                .map(|(path, data)| SnapshotFile {
+                    path: path.to_string(),
+                    data: BASE64.encode(data),
+                })
+                .collect(),
+        };
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for files in [
+            vec![
+                ("config.json", CONFIG),
+                ("receivers.json", RECEIVERS),
+                ("../escape.json", "{}"),
+            ],
+            vec![("config.json", CONFIG), ("receivers.json", "{}")],
+            vec![("config.json", CONFIG)],
+        ] {
+            std::fs::write(&archive, serde_json::to_vec(&snapshot(&files)).unwrap()).unwrap();
+            assert!(import(&archive, &paths).is_err(), "{files:?}");
+            assert!(!paths.config.exists());
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
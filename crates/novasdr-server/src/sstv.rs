@@ -0,0 +1,186 @@
+//! SSTV pictures decoded from listeners' audio: encoded as PNG once and pushed to every `/sstv`
+//! client, with the last few kept for clients that connect later.
+
+use novasdr_core::codec::png;
+use novasdr_core::sstv::{Event, Image, Mode};
+use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Pictures kept for `/sstv` clients that connect later.
+const RECENT_PICTURES: usize = 8;
+/// Pictures a slow `/sstv` client may fall behind before it skips ahead.
+const CHANNEL_DEPTH: usize = 8;
+/// Listeners tuned this close to each other decode the same picture; it is published once.
+const SAME_SIGNAL_HZ: i64 = 3_000;
+const SAME_PICTURE_WITHIN: Duration = Duration::from_secs(30);
+
+/// A finished picture: its `sstv_image` description and the PNG file.
+pub struct Picture {
+    pub receiver_id: String,
+    frequency: i64,
+    mode: Mode,
+    received: Instant,
+    pub json: String,
+    pub png: Vec<u8>,
+}
+
+pub struct Pictures {
+    next_id: AtomicU64,
+    recent: Mutex<VecDeque<Arc<Picture>>>,
+    tx: broadcast::Sender<Arc<Picture>>,
+}
+
+impl Default for Pictures {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            recent: Mutex::default(),
+            tx: broadcast::channel(CHANNEL_DEPTH).0,
+        }
+    }
+}
+
+impl Pictures {
+    fn recent(&self) -> MutexGuard<'_, VecDeque<Arc<Picture>>> {
+        match self.recent.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("sstv pictures mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    /// The recent pictures, oldest first, and the ones that follow.
+    pub fn subscribe(&self) -> (Vec<Arc<Picture>>, broadcast::Receiver<Arc<Picture>>) {
+        // Under the lock `publish` holds, so no picture is in both or neither.
+        let recent = self.recent();
+        (recent.iter().cloned().collect(), self.tx.subscribe())
+    }
+
+    /// Encodes and publishes `image`; returns its `sstv_image` description. A picture another
+    /// listener already published is not published again.
+    fn publish(&self, receiver_id: &str, frequency: i64, image: &Image) -> Option<String> {
+        // Held throughout, so two listeners finishing together cannot both publish.
+        let mut recent = self.recent();
+        let published = recent.iter().rev().find(|p| {
+            p.receiver_id == receiver_id
+                && p.mode == image.mode
+                && (p.frequency - frequency).abs() <= SAME_SIGNAL_HZ
+                && p.received.elapsed() < SAME_PICTURE_WITHIN
+        });
+        if let Some(picture) = published {
+            return Some(picture.json.clone());
+        }
+        let png = match png::encode_rgb(image.width as u32, image.height as u32, &image.rgb) {
+            Ok(png) => png,
+            Err(e) => {
+                tracing::warn!(error = ?e, "sstv picture encoding failed");
+                return None;
+            }
+        };
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let json = json!({
+            "type": "sstv_image",
+            "id": id,
+            "receiver_id": receiver_id,
+            "frequency": frequency,
+            "mode": image.mode.name(),
+            "width": image.width,
+            "height": image.height,
+            "complete": image.complete(),
+            "time": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        })
+        .to_string();
+        tracing::info!(
+            receiver_id,
+            frequency,
+            mode = %image.mode,
+            lines = image.height,
+            "sstv picture received"
+        );
+        let picture = Arc::new(Picture {
+            receiver_id: receiver_id.to_string(),
+            frequency,
+            mode: image.mode,
+            received: Instant::now(),
+            json: json.clone(),
+            png,
+        });
+        if recent.len() >= RECENT_PICTURES {
+            recent.pop_front();
+        }
+        recent.push_back(picture.clone());
+        // No `/sstv` clients is not an error.
+        let _ = self.tx.send(picture);
+        Some(json)
+    }
+
+    /// `sstv` message for the listener whose decoder produced `event`; a finished picture is
+    /// published first.
+    pub fn event_message(&self, receiver_id: &str, frequency: i64, event: Event) -> Option<String> {
+        let (mode, lines) = match event {
+            Event::Started(mode) => (mode, 0),
+            Event::Progress(mode, lines) => (mode, lines),
+            Event::Finished(image) => {
+                let json = self.publish(receiver_id, frequency, &image)?;
+                let mut msg: serde_json::Value = serde_json::from_str(&json).ok()?;
+                msg["type"] = "sstv".into();
+                msg["state"] = "done".into();
+                return Some(msg.to_string());
+            }
+        };
+        Some(
+            json!({
+                "type": "sstv",
+                "state": "receiving",
+                "mode": mode.name(),
+                "line": lines,
+                "lines": mode.height(),
+            })
+            .to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished(mode: Mode) -> Event {
+        Event::Finished(Image {
+            mode,
+            width: mode.width(),
+            height: 32,
+            rgb: vec![128; mode.width() * 32 * 3],
+        })
+    }
+
+    #[test]
+    fn listeners_on_one_signal_publish_one_picture() {
+        let pictures = Pictures::default();
+        let (_, mut rx) = pictures.subscribe();
+        let first = pictures
+            .event_message("hf", 14_230_000, finished(Mode::Martin1))
+            .unwrap();
+        let second = pictures
+            .event_message("hf", 14_231_000, finished(Mode::Martin1))
+            .unwrap();
+        assert_eq!(first, second);
+        assert!(first.contains(r#""state":"done""#));
+        pictures.event_message("vhf", 14_230_000, finished(Mode::Martin1));
+        pictures.event_message("hf", 14_230_000, finished(Mode::Scottie1));
+
+        let (recent, _) = pictures.subscribe();
+        assert_eq!(recent.len(), 3);
+        assert!(recent[0].png.starts_with(b"\x89PNG"));
+        for _ in 0..3 {
+            rx.try_recv().unwrap();
+        }
+        assert!(rx.try_recv().is_err());
+    }
+}
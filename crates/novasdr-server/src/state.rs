@@ -3,26 +3,35 @@ use axum::{extract::State, response::IntoResponse, Json};
 use dashmap::DashMap;
 use novasdr_core::{
     config,
-    protocol::{json_stringify_value, EventsInfo},
+    dsp::waterfall_mask::WaterfallMask,
+    protocol::{json_stringify_value, EventTopics, EventsInfo, PresenceEntry},
+    smeter::SmeterCalibration,
 };
+use num_complex::Complex32;
 use serde_json::json;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     net::IpAddr,
     path::Path,
     sync::{
-        atomic::{AtomicU64, AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
+    time::{Duration, Instant},
 };
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, watch, RwLock};
 use tracing::warn;
 
 // Audio packets can be bursty (GC pauses, GPU sync, OS scheduler jitter). A slightly deeper queue
 // smooths transient stalls without changing steady-state throughput.
 const AUDIO_QUEUE_CAPACITY: usize = 128;
 const WATERFALL_QUEUE_CAPACITY: usize = 8;
+/// Spectrum frames waiting for a client's demodulator; a client that falls further behind drops
+/// frames rather than delaying everyone else.
+const AUDIO_FRAME_QUEUE_CAPACITY: usize = 4;
 const TEXT_QUEUE_CAPACITY: usize = 64;
+/// Frequencies whose last digital voice call is remembered; ended calls go first.
+const MAX_DIGITAL_VOICE_CALLS: usize = 256;
 
 pub type ClientId = u64;
 
@@ -78,44 +87,631 @@ pub struct HeaderPanelLookups {
     pub shortwave_info: bool,
 }
 
+/// FFT backend and GPU a receiver's DSP thread runs on, for `/server-info.json`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FftBackendInfo {
+    #[serde(serialize_with = "serialize_accelerator")]
+    pub backend: config::Accelerator,
+    /// GPU name; `None` on the CPU.
+    pub device: Option<String>,
+}
+
+fn serialize_accelerator<S: serde::Serializer>(
+    backend: &config::Accelerator,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(backend.as_str())
+}
+
 pub struct ReceiverState {
     pub receiver: config::ReceiverConfig,
     pub rt: Arc<config::Runtime>,
     pub audio_clients: DashMap<ClientId, Arc<AudioClient>>,
+    /// rtl_tcp connections (`crate::rtl_tcp`), IQ recordings (`crate::schedule`, through
+    /// `crate::replay::IqSubscription`), FFT tap clients (`crate::fft_tap`) and ACARS channels
+    /// (`crate::acars`), fed the same rotated bins as audio clients.
+    pub iq_clients: DashMap<ClientId, mpsc::Sender<IqFrame>>,
     pub waterfall_clients: Vec<DashMap<ClientId, Arc<WaterfallClient>>>,
     pub signal_changes: DashMap<String, (i32, f64, i32)>,
+    maintenance: std::sync::Mutex<config::ReceiverMaintenance>,
+    /// Name, caps and default tune, replaced on reload.
+    settings: std::sync::Mutex<Arc<ReceiverSettings>>,
+    /// Input settings a reload asked the DSP thread to reopen the input with.
+    input_restart: std::sync::Mutex<Option<config::ReceiverConfig>>,
+    /// Why the input is being reopened (`input_error` or `reconfigured`); `None` while samples
+    /// flow.
+    input_restarting: std::sync::Mutex<Option<&'static str>>,
+    smeter_calibration: std::sync::Mutex<Arc<SmeterCalibration>>,
+    /// Display-only attenuation of known interference (`waterfall_masks.json`).
+    waterfall_masks: std::sync::Mutex<Arc<[WaterfallMask]>>,
+    /// FFT backend the DSP thread ended up on; `None` until it started.
+    fft_backend: std::sync::Mutex<Option<FftBackendInfo>>,
+    /// Backend switch requested through the admin API, picked up by the DSP thread.
+    fft_switch: std::sync::Mutex<Option<FftSwitchRequest>>,
+    /// Wall time of the last processed FFT frame, and of its FFT alone, in microseconds.
+    pub dsp_frame_micros: AtomicU64,
+    pub dsp_fft_micros: AtomicU64,
+    /// Input samples converted, and those of them at the ADC's full scale.
+    pub input_samples: AtomicU64,
+    pub clipped_samples: AtomicU64,
+    /// Mean square of the last input buffer, as `f32` bits.
+    pub input_level: AtomicU32,
+    /// Audio channels currently shared by several listeners (`crate::channels`).
+    pub shared_audio_channels: AtomicUsize,
+    /// Channelizer channels whose baseband several listeners are currently tuned from.
+    pub channelizer_channels: AtomicUsize,
+    /// Latest level of each capture trigger's range, keyed by trigger name.
+    pub watch_levels: DashMap<String, WatchLevel>,
+    /// Averaged full-band rows for waterfall catch-up requests.
+    pub waterfall_history: crate::waterfall_history::WaterfallHistory,
+    /// Dictionary for `zstd-dict` waterfall clients, trained on first demand.
+    pub waterfall_dictionary: Arc<crate::waterfall_dictionary::DictionaryTrainer>,
+    /// Live waterfall packets, compressed once for every client with the same view.
+    pub waterfall_packets: crate::waterfall_compress::PacketCache,
+    /// Transmit session and keying (`receivers[].tx`, `crate::tx`).
+    pub tx: Arc<crate::tx::TxState>,
+    /// Recent bins for IQ consumers that start in the past (`receivers[].replay`).
+    pub replay: crate::replay::Replay,
+    /// Audio sent to a local decoder over UDP (`receivers[].udp_output`).
+    pub udp_output: crate::udp_output::UdpOutputState,
+    /// Centre frequency the input is tuned to; differs from `input.frequency` while
+    /// `band_schedule` moved the receiver to another band (`crate::band_schedule`).
+    center_frequency: AtomicI64,
+    /// Latest frequency error measured on `accuracy.drift_carrier` (`crate::drift`).
+    drift: std::sync::Mutex<Option<DriftEstimate>>,
+    /// Set while the input clips or runs too hot (`crate::overload`).
+    overload: watch::Sender<Option<Overload>>,
+}
+
+/// Frequency error of a receiver measured on a carrier of known frequency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftEstimate {
+    /// Positive when signals show above their true frequency.
+    pub ppm: f64,
+    /// Unix time of the measurement, in seconds.
+    pub measured_at: u64,
+}
+
+/// Why a receiver's spectrum cannot be trusted right now, with the readings behind it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Overload {
+    /// `clipping` when samples sit at the ADC's full scale, `strong_signal` when the input runs
+    /// so hot that its peaks are about to.
+    pub reason: &'static str,
+    /// Share of input samples at full scale over the last second, in parts per million.
+    pub clipped_ppm: f64,
+    /// Input power relative to full scale.
+    pub level_dbfs: f64,
+}
+
+/// The parts of a receiver's `receivers.json` entry that a reload applies without touching its
+/// input (`crate::reload`).
+#[derive(Debug, Clone)]
+pub struct ReceiverSettings {
+    pub name: String,
+    pub limits: config::ReceiverLimits,
+    pub defaults: config::ReceiverDefaults,
+    /// Tune of a new listener, from `defaults` and the spectrum layout.
+    pub default_frequency: i64,
+    pub accuracy: config::Accuracy,
+    pub default_mode_str: String,
+    pub default_l: i32,
+    pub default_m: f64,
+    pub default_r: i32,
+}
+
+impl ReceiverSettings {
+    pub fn new(receiver: &config::ReceiverConfig, rt: &config::Runtime) -> Self {
+        Self {
+            name: receiver.name.clone(),
+            limits: receiver.limits.clone(),
+            defaults: receiver.input.defaults.clone(),
+            default_frequency: rt.default_frequency,
+            accuracy: receiver.accuracy.clone(),
+            default_mode_str: rt.default_mode_str.clone(),
+            default_l: rt.default_l,
+            default_m: rt.default_m,
+            default_r: rt.default_r,
+        }
+    }
+}
+
+/// Runtime FFT backend change; `reply` gets the backend the rebuilt pipeline ended up on, or why
+/// the rebuild failed (the old pipeline then stays in place).
+pub struct FftSwitchRequest {
+    pub accelerator: config::Accelerator,
+    pub reply: tokio::sync::oneshot::Sender<anyhow::Result<FftBackendInfo>>,
+}
+
+/// Strongest bin and the noise floor of a capture trigger's range, S-meter calibrated, in dB.
+#[derive(Debug, Clone, Copy)]
+pub struct WatchLevel {
+    pub peak_db: f32,
+    pub floor_db: f32,
 }
 
 impl ReceiverState {
-    pub fn new(receiver: config::ReceiverConfig, rt: Arc<config::Runtime>) -> Self {
+    pub fn new(
+        receiver: config::ReceiverConfig,
+        rt: Arc<config::Runtime>,
+        smeter_calibration: SmeterCalibration,
+    ) -> Self {
         let mut waterfall_clients = Vec::with_capacity(rt.downsample_levels);
         for _ in 0..rt.downsample_levels {
             waterfall_clients.push(DashMap::new());
         }
 
+        let maintenance = std::sync::Mutex::new(receiver.maintenance.clone());
+        let settings = std::sync::Mutex::new(Arc::new(ReceiverSettings::new(&receiver, &rt)));
+        let waterfall_history = crate::waterfall_history::WaterfallHistory::new(&rt);
+        let replay = crate::replay::Replay::new(&receiver, &rt);
+        let center_frequency = AtomicI64::new(receiver.input.frequency);
+        let udp_output = crate::udp_output::UdpOutputState::new(receiver.udp_output.clone());
         Self {
             receiver,
             rt,
             audio_clients: DashMap::new(),
+            iq_clients: DashMap::new(),
             waterfall_clients,
             signal_changes: DashMap::new(),
+            maintenance,
+            settings,
+            input_restart: std::sync::Mutex::new(None),
+            input_restarting: std::sync::Mutex::new(None),
+            smeter_calibration: std::sync::Mutex::new(Arc::new(smeter_calibration)),
+            waterfall_masks: std::sync::Mutex::new(Arc::from([])),
+            fft_backend: std::sync::Mutex::new(None),
+            fft_switch: std::sync::Mutex::new(None),
+            dsp_frame_micros: AtomicU64::new(0),
+            dsp_fft_micros: AtomicU64::new(0),
+            input_samples: AtomicU64::new(0),
+            clipped_samples: AtomicU64::new(0),
+            input_level: AtomicU32::new(0),
+            shared_audio_channels: AtomicUsize::new(0),
+            channelizer_channels: AtomicUsize::new(0),
+            watch_levels: DashMap::new(),
+            waterfall_history,
+            waterfall_dictionary: Arc::default(),
+            waterfall_packets: Default::default(),
+            tx: Arc::default(),
+            replay,
+            udp_output,
+            center_frequency,
+            drift: std::sync::Mutex::new(None),
+            overload: watch::channel(None).0,
+        }
+    }
+
+    /// Waterfall WebSockets on this receiver; extra windows of a connection are not counted.
+    pub fn waterfall_connections(&self) -> usize {
+        self.waterfall_clients
+            .iter()
+            .map(|m| m.iter().filter(|c| c.window == 0).count())
+            .sum()
+    }
+
+    /// Whether the receiver's own audio cap (`limits.audio` in `receivers.json`) is reached.
+    pub fn audio_full(&self) -> bool {
+        self.settings()
+            .limits
+            .audio
+            .is_some_and(|cap| self.audio_clients.len() >= cap)
+    }
+
+    /// Whether the receiver's own waterfall cap is reached.
+    pub fn waterfall_full(&self) -> bool {
+        self.settings()
+            .limits
+            .waterfall
+            .is_some_and(|cap| self.waterfall_connections() >= cap)
+    }
+
+    /// `receiver_full` text message for a client refused by one of these caps; `stream` is
+    /// `audio` or `waterfall`.
+    pub fn full_message(&self, stream: &str) -> String {
+        json!({
+            "type": "receiver_full",
+            "receiver_id": self.receiver.id,
+            "stream": stream,
+        })
+        .to_string()
+    }
+
+    /// `receiver_status` text message: `restarting` with the reason while the input is being
+    /// reopened, `running` otherwise.
+    pub fn status_message(&self) -> String {
+        match self.input_restarting() {
+            Some(reason) => json!({
+                "type": "receiver_status",
+                "receiver_id": self.receiver.id,
+                "state": "restarting",
+                "reason": reason,
+            }),
+            None => json!({
+                "type": "receiver_status",
+                "receiver_id": self.receiver.id,
+                "state": "running",
+            }),
+        }
+        .to_string()
+    }
+
+    pub fn input_restarting(&self) -> Option<&'static str> {
+        match self.input_restarting.lock() {
+            Ok(g) => *g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "input status mutex poisoned; recovering");
+                *poisoned.into_inner()
+            }
+        }
+    }
+
+    /// Records why the input is being reopened, or `None` once it delivers samples again.
+    /// Returns whether that changed anything.
+    fn set_input_restarting(&self, next: Option<&'static str>) -> bool {
+        let mut cur = match self.input_restarting.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "input status mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        std::mem::replace(&mut *cur, next) != next
+    }
+
+    pub fn smeter_calibration(&self) -> Arc<SmeterCalibration> {
+        match self.smeter_calibration.lock() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "S-meter calibration mutex poisoned; recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    pub fn set_smeter_calibration(&self, next: SmeterCalibration) {
+        let mut cur = match self.smeter_calibration.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "S-meter calibration mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        *cur = Arc::new(next);
+    }
+
+    pub fn waterfall_masks(&self) -> Arc<[WaterfallMask]> {
+        match self.waterfall_masks.lock() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "waterfall masks mutex poisoned; recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    pub fn set_waterfall_masks(&self, next: Vec<WaterfallMask>) {
+        let mut cur = match self.waterfall_masks.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "waterfall masks mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        *cur = next.into();
+    }
+
+    pub fn center_frequency(&self) -> i64 {
+        self.center_frequency.load(Ordering::Relaxed)
+    }
+
+    pub fn set_center_frequency(&self, frequency: i64) {
+        self.center_frequency.store(frequency, Ordering::Relaxed);
+    }
+
+    /// Frequency in Hz of FFT bin 0 as currently tuned: `rt.basefreq` moved along with the
+    /// centre frequency, since a band switch keeps the spectrum layout.
+    pub fn basefreq(&self) -> i64 {
+        self.rt.basefreq + self.center_frequency() - self.receiver.input.frequency
+    }
+
+    /// Absolute frequency in Hz of FFT bin `bin` (fractional bins allowed).
+    pub fn bin_frequency(&self, bin: f64) -> i64 {
+        let bin_hz = self.rt.total_bandwidth as f64 / self.rt.fft_result_size as f64;
+        self.basefreq() + (bin * bin_hz).round() as i64
+    }
+
+    /// Fractional FFT bin of `frequency` (Hz); the inverse of [`Self::bin_frequency`].
+    pub fn frequency_bin(&self, frequency: i64) -> f64 {
+        let bin_hz = self.rt.total_bandwidth as f64 / self.rt.fft_result_size as f64;
+        (frequency - self.basefreq()) as f64 / bin_hz
+    }
+
+    /// Whether `frequency` (Hz) lies in the band the receiver is tuned to, edges included.
+    pub fn covers(&self, frequency: i64) -> bool {
+        let basefreq = self.basefreq();
+        (basefreq..=basefreq + self.rt.total_bandwidth).contains(&frequency)
+    }
+
+    /// Counts a buffer of converted input samples towards `input_samples` and `clipped_samples`,
+    /// and keeps its level in `input_level`.
+    pub fn count_input(&self, samples: &[f32]) {
+        let clipped = novasdr_core::dsp::sample::count_clipped(samples);
+        let level = novasdr_core::dsp::sample::mean_square(samples);
+        self.input_level.store(level.to_bits(), Ordering::Relaxed);
+        self.input_samples
+            .fetch_add(samples.len() as u64, Ordering::Relaxed);
+        if clipped > 0 {
+            self.clipped_samples
+                .fetch_add(clipped as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn drift(&self) -> Option<DriftEstimate> {
+        match self.drift.lock() {
+            Ok(g) => *g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "drift mutex poisoned; recovering");
+                *poisoned.into_inner()
+            }
+        }
+    }
+
+    pub fn set_drift(&self, next: Option<DriftEstimate>) {
+        match self.drift.lock() {
+            Ok(mut g) => *g = next,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "drift mutex poisoned; recovering");
+                *poisoned.into_inner() = next;
+            }
+        }
+    }
+
+    /// `accuracy` of the basic info: the stated reference and error, and the latest estimate
+    /// when `accuracy.drift_carrier` is set.
+    pub fn accuracy_json(&self) -> serde_json::Value {
+        let settings = self.settings();
+        let drift = self.drift();
+        json!({
+            "reference": settings.accuracy.reference.as_str(),
+            "ppm": settings.accuracy.ppm,
+            "measured_ppm": drift.map(|d| d.ppm),
+            "measured_at": drift.map(|d| d.measured_at),
+        })
+    }
+
+    pub fn overload(&self) -> Option<Overload> {
+        *self.overload.borrow()
+    }
+
+    /// Notified on every change of [`Self::overload`].
+    pub fn overload_changes(&self) -> watch::Receiver<Option<Overload>> {
+        self.overload.subscribe()
+    }
+
+    /// Records the overload state; returns whether it turned on or off.
+    pub fn set_overload(&self, next: Option<Overload>) -> bool {
+        let mut toggled = false;
+        self.overload.send_if_modified(|cur| {
+            toggled = cur.is_some() != next.is_some();
+            let changed = *cur != next;
+            *cur = next;
+            // Only a toggle wakes the waterfall sessions; readings change every second.
+            changed && toggled
+        });
+        toggled
+    }
+
+    pub fn overload_json(&self) -> serde_json::Value {
+        match self.overload() {
+            Some(o) => json!({
+                "active": true,
+                "reason": o.reason,
+                "clipped_ppm": (o.clipped_ppm * 10.0).round() / 10.0,
+                "level_dbfs": (o.level_dbfs * 10.0).round() / 10.0,
+            }),
+            None => json!({ "active": false }),
+        }
+    }
+
+    /// `overload` text message with [`Self::overload_json`].
+    pub fn overload_message(&self) -> String {
+        json!({
+            "type": "overload",
+            "receiver_id": self.receiver.id,
+            "overload": self.overload_json(),
+        })
+        .to_string()
+    }
+
+    pub fn maintenance(&self) -> config::ReceiverMaintenance {
+        match self.maintenance.lock() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "maintenance mutex poisoned; recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    pub fn set_maintenance(&self, next: config::ReceiverMaintenance) {
+        let mut cur = match self.maintenance.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "maintenance mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        *cur = next;
+    }
+
+    pub fn settings(&self) -> Arc<ReceiverSettings> {
+        match self.settings.lock() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "settings mutex poisoned; recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    pub fn set_settings(&self, next: ReceiverSettings) {
+        let mut cur = match self.settings.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "settings mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        *cur = Arc::new(next);
+    }
+
+    /// Asks the DSP thread to reopen the input with `next`; a request still pending is replaced.
+    pub fn request_input_restart(&self, next: config::ReceiverConfig) {
+        let mut cur = match self.input_restart.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "input restart mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        *cur = Some(next);
+    }
+
+    pub fn take_input_restart(&self) -> Option<config::ReceiverConfig> {
+        let mut cur = match self.input_restart.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "input restart mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        cur.take()
+    }
+
+    pub fn fft_backend(&self) -> Option<FftBackendInfo> {
+        match self.fft_backend.lock() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "FFT backend mutex poisoned; recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    pub fn set_fft_backend(&self, backend: FftBackendInfo) {
+        let mut cur = match self.fft_backend.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "FFT backend mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        *cur = Some(backend);
+    }
+
+    /// Queues a backend switch for the DSP thread. A request still pending is replaced; its
+    /// caller sees the reply channel close.
+    pub fn request_fft_switch(&self, request: FftSwitchRequest) {
+        let mut cur = match self.fft_switch.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "FFT switch mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        *cur = Some(request);
+    }
+
+    pub fn take_fft_switch(&self) -> Option<FftSwitchRequest> {
+        let mut cur = match self.fft_switch.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(receiver_id = %self.receiver.id, "FFT switch mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        cur.take()
+    }
+}
+
+/// Paths of the configuration files the server was started with. Runtime changes made through the
+/// admin API are persisted back to these files.
+#[derive(Debug, Clone)]
+pub struct ConfigPaths {
+    pub config: std::path::PathBuf,
+    pub receivers: std::path::PathBuf,
+    /// Overlays directory (`markers.json`, `bands.json`, ...).
+    pub overlays: std::path::PathBuf,
+}
+
+impl ConfigPaths {
+    /// Resolves a configured file path against the directory of `receivers.json`.
+    pub fn resolve(&self, path: &str) -> std::path::PathBuf {
+        let dir = self
+            .receivers
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."));
+        dir.join(path)
+    }
+
+    /// S-meter calibration file of `receiver`: `input.smeter_calibration`, or
+    /// `smeter_calibration_<id>.json` when unset, resolved with [`Self::resolve`].
+    pub fn smeter_calibration(&self, receiver: &config::ReceiverConfig) -> std::path::PathBuf {
+        match receiver.input.smeter_calibration.as_deref() {
+            Some(path) => self.resolve(path),
+            None => self.resolve(&format!("smeter_calibration_{}.json", receiver.id)),
         }
     }
 }
 
 pub struct AppState {
-    pub cfg: Arc<config::Config>,
+    /// Replaced on reload (`crate::reload`); read it through [`Self::cfg`].
+    cfg: std::sync::RwLock<Arc<config::Config>>,
     pub html_root: std::path::PathBuf,
+    pub config_paths: ConfigPaths,
     pub receivers: HashMap<String, Arc<ReceiverState>>,
     pub active_receiver: Arc<ReceiverState>,
-    pub markers: Arc<RwLock<serde_json::Value>>,
+    pub markers: Arc<RwLock<Arc<crate::markers::MarkerIndex>>>,
+    /// Listener-suggested markers awaiting operator approval.
+    pub marker_suggestions: tokio::sync::Mutex<crate::marker_suggestions::SuggestionQueue>,
     pub bands: Arc<RwLock<serde_json::Value>>,
+    /// Bumped on every overlay change pushed to clients as `overlays_updated`.
+    pub overlays_version: AtomicU64,
     pub header_panel: Arc<RwLock<HeaderPanelOverlay>>,
-
-    pub event_clients: DashMap<ClientId, mpsc::Sender<Arc<str>>>,
-    pub chat_clients: DashMap<ClientId, mpsc::Sender<Arc<str>>>,
+    /// `waterfall_masks.json` as loaded; each receiver holds its compiled share.
+    pub waterfall_masks: RwLock<serde_json::Value>,
+
+    pub event_clients: DashMap<ClientId, EventClient>,
+    pub chat_clients: DashMap<ClientId, Arc<ChatClient>>,
+    pub chat_backpressure: ChatBackpressure,
+    pub events_fanout: EventsFanout,
+    /// Last `digital_voice` message per receiver and frequency, and whether its call is still
+    /// on; every listener on a frequency reports the same call.
+    pub digital_voice_calls: DashMap<(String, i64), (bool, String)>,
     pub chat_history: tokio::sync::Mutex<Vec<ChatMessage>>,
-    ws_ip_counts: DashMap<IpAddr, usize>,
+    pub skimmer_spots: crate::skimmer::SpotLog,
+    pub sstv_pictures: crate::sstv::Pictures,
+    pub acars_messages: crate::acars::Messages,
+    pub audio_pipelines: crate::ws::audio_pool::AudioPipelinePool,
+    /// Set when `websdr.identity_key` is configured.
+    pub identity: Option<Arc<crate::identity::ServerIdentity>>,
+    pub sessions: crate::auth::SessionSigner,
+    pub login_throttle: crate::auth::LoginThrottle,
+    /// Per-IP audio streams and daily listening time (`limits.audio_per_ip`,
+    /// `limits.listen_minutes_per_day`).
+    pub quota: crate::quota::QuotaLedger,
+    /// Set when `geoip.database` is configured.
+    pub geoip: Option<crate::geoip::GeoIp>,
+    /// Set when `freqdb.csv` or `freqdb.url_template` is configured.
+    pub freqdb: Option<crate::freqdb::FrequencyDatabase>,
+    /// Listener accounts (`accounts.enabled`); empty when disabled.
+    pub users: crate::users::UserAccounts,
+    /// Open `/stream/...` responses, up to `streams.max_clients`.
+    pub stream_slots: Arc<tokio::sync::Semaphore>,
+    /// Open `/iq` connections, up to `iq.max_clients`.
+    pub iq_slots: Arc<tokio::sync::Semaphore>,
+    ws_ip_counts: DashMap<IpAddr, IpConnections>,
 
     pub total_waterfall_bits: AtomicUsize,
     pub total_audio_bits: AtomicUsize,
@@ -123,37 +719,125 @@ pub struct AppState {
     pub audio_kbits_per_sec: AtomicU64,
     pub dropped_waterfall_frames: AtomicU64,
     pub dropped_audio_frames: AtomicU64,
+    /// `window` commands refused, by [`crate::ws::audio::WindowRejection`].
+    pub rejected_windows: [AtomicU64; crate::ws::audio::WindowRejection::ALL.len()],
 
     pub next_client_id: AtomicU64,
 }
 
 impl AppState {
-    pub fn new(cfg: Arc<config::Config>, html_root: std::path::PathBuf) -> anyhow::Result<Self> {
+    pub fn new(
+        cfg: Arc<config::Config>,
+        html_root: std::path::PathBuf,
+        config_paths: ConfigPaths,
+    ) -> anyhow::Result<Self> {
         let mut receivers = HashMap::new();
         for r in cfg.receivers.iter() {
             let rt = Arc::new(
                 cfg.runtime_for(r.id.as_str())
                     .with_context(|| format!("derive runtime for receiver {}", r.id))?,
             );
-            receivers.insert(r.id.clone(), Arc::new(ReceiverState::new(r.clone(), rt)));
+            let calibration_path = config_paths.smeter_calibration(r);
+            let calibration = SmeterCalibration::load(&calibration_path)
+                .with_context(|| format!("load S-meter calibration for receiver {}", r.id))?;
+            receivers.insert(
+                r.id.clone(),
+                Arc::new(ReceiverState::new(r.clone(), rt, calibration)),
+            );
         }
 
+        let identity = if cfg.websdr.identity_key.trim().is_empty() {
+            None
+        } else {
+            let path = config_paths.resolve(cfg.websdr.identity_key.trim());
+            let identity = crate::identity::ServerIdentity::load_or_create(&path)
+                .context("load server identity key")?;
+            tracing::info!(public_key = %identity.public_key(), "server identity loaded");
+            Some(Arc::new(identity))
+        };
+
+        let geoip_database = cfg.geoip.database.trim();
+        let geoip = if geoip_database.is_empty() {
+            if !crate::geoip::CountryFilter::from_config(&cfg.geoip)?.is_empty() {
+                anyhow::bail!("geoip country lists need geoip.database");
+            }
+            None
+        } else {
+            let path = config_paths.resolve(geoip_database);
+            let geoip = crate::geoip::GeoIp::open(&path, &cfg.geoip)?;
+            tracing::info!(path = %path.display(), "GeoIP database loaded");
+            Some(geoip)
+        };
+
+        let freqdb = if cfg.freqdb.enabled() {
+            let csv = cfg.freqdb.csv.trim();
+            let csv = (!csv.is_empty()).then(|| config_paths.resolve(csv));
+            let db = crate::freqdb::FrequencyDatabase::open(&cfg.freqdb, csv.as_deref())
+                .context("load frequency database")?;
+            tracing::info!(
+                entries = db.entry_count(),
+                upstream = !cfg.freqdb.url_template.trim().is_empty(),
+                "frequency database loaded"
+            );
+            Some(db)
+        } else {
+            None
+        };
+
+        // Unlike the quota ledger, a broken accounts file is not replaced by an empty one.
+        let users = if cfg.accounts.enabled {
+            let path = config_paths.resolve(crate::users::FILE_NAME);
+            crate::users::UserStore::load(&path).context("load user accounts")?
+        } else {
+            crate::users::UserStore::default()
+        };
+        let users = crate::users::UserAccounts::new(users);
+
+        let quota_path = config_paths.resolve(crate::quota::FILE_NAME);
+        let quota = crate::quota::QuotaLedger::load(&quota_path).unwrap_or_else(|e| {
+            warn!(error = ?e, path = %quota_path.display(), "failed to load quota usage; starting empty");
+            crate::quota::QuotaLedger::default()
+        });
+
         let active_receiver = receivers
             .get(cfg.active_receiver_id.as_str())
             .cloned()
             .ok_or_else(|| anyhow!("active_receiver_id missing from receiver map"))?;
 
+        let stream_slots = Arc::new(tokio::sync::Semaphore::new(cfg.streams.max_clients));
+        let iq_slots = Arc::new(tokio::sync::Semaphore::new(cfg.iq.max_clients));
+
         Ok(Self {
-            cfg,
+            cfg: std::sync::RwLock::new(cfg),
             html_root,
+            config_paths,
             receivers,
             active_receiver,
-            markers: Arc::new(RwLock::new(serde_json::Value::Null)),
+            markers: Arc::new(RwLock::new(Arc::default())),
+            marker_suggestions: tokio::sync::Mutex::default(),
             bands: Arc::new(RwLock::new(serde_json::Value::Null)),
+            overlays_version: AtomicU64::new(0),
             header_panel: Arc::new(RwLock::new(HeaderPanelOverlay::default())),
+            waterfall_masks: RwLock::new(serde_json::Value::Null),
             event_clients: DashMap::new(),
             chat_clients: DashMap::new(),
+            chat_backpressure: ChatBackpressure::default(),
+            events_fanout: EventsFanout::default(),
+            digital_voice_calls: DashMap::new(),
             chat_history: tokio::sync::Mutex::new(load_chat_history()),
+            skimmer_spots: crate::skimmer::SpotLog::default(),
+            sstv_pictures: crate::sstv::Pictures::default(),
+            acars_messages: crate::acars::Messages::default(),
+            audio_pipelines: crate::ws::audio_pool::AudioPipelinePool::default(),
+            identity,
+            sessions: crate::auth::SessionSigner::new()?,
+            login_throttle: crate::auth::LoginThrottle::default(),
+            quota,
+            geoip,
+            freqdb,
+            users,
+            stream_slots,
+            iq_slots,
             ws_ip_counts: DashMap::new(),
             total_waterfall_bits: AtomicUsize::new(0),
             total_audio_bits: AtomicUsize::new(0),
@@ -161,6 +845,7 @@ impl AppState {
             audio_kbits_per_sec: AtomicU64::new(0),
             dropped_waterfall_frames: AtomicU64::new(0),
             dropped_audio_frames: AtomicU64::new(0),
+            rejected_windows: Default::default(),
             next_client_id: AtomicU64::new(1),
         })
     }
@@ -169,12 +854,34 @@ impl AppState {
         self.next_client_id.fetch_add(1, Ordering::Relaxed)
     }
 
+    /// Configuration in effect; sections that reload live change between calls.
+    pub fn cfg(&self) -> Arc<config::Config> {
+        match self.cfg.read() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => {
+                tracing::error!("config lock poisoned; recovering");
+                poisoned.into_inner().clone()
+            }
+        }
+    }
+
+    pub fn set_cfg(&self, next: Arc<config::Config>) {
+        let mut cur = match self.cfg.write() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("config lock poisoned; recovering");
+                poisoned.into_inner()
+            }
+        };
+        *cur = next;
+    }
+
     pub fn receiver_state(&self, receiver_id: &str) -> Option<&Arc<ReceiverState>> {
         self.receivers.get(receiver_id)
     }
 
     pub fn active_receiver_id(&self) -> &str {
-        self.cfg.active_receiver_id.as_str()
+        self.active_receiver.receiver.id.as_str()
     }
 
     pub fn active_receiver_state(&self) -> &Arc<ReceiverState> {
@@ -188,21 +895,63 @@ impl AppState {
             .sum::<usize>()
     }
 
+    /// Audio clients that asked for DX (low-latency) mode.
+    pub fn low_latency_audio_clients(&self) -> usize {
+        self.receivers
+            .values()
+            .map(|r| {
+                r.audio_clients
+                    .iter()
+                    .filter(|c| match c.params.lock() {
+                        Ok(p) => p.low_latency,
+                        Err(poisoned) => poisoned.into_inner().low_latency,
+                    })
+                    .count()
+            })
+            .sum::<usize>()
+    }
+
     pub fn total_waterfall_clients(&self) -> usize {
         self.receivers
             .values()
-            .map(|r| r.waterfall_clients.iter().map(|m| m.len()).sum::<usize>())
+            .map(|r| r.waterfall_connections())
             .sum::<usize>()
     }
 
-    pub fn try_acquire_ws_ip(self: &Arc<Self>, ip: IpAddr) -> Option<WsIpGuard> {
-        let limit = self.cfg.limits.ws_per_ip.max(1);
-        let mut entry = self.ws_ip_counts.entry(ip).or_insert(0);
-        if *entry >= limit {
-            return None;
+    /// Counts a WebSocket connection from `ip`. The first connection of an IP is checked against
+    /// the GeoIP country filter; later ones reuse its result.
+    pub fn try_acquire_ws_ip(self: &Arc<Self>, ip: IpAddr) -> Result<WsIpGuard, WsRejection> {
+        let limit = self.cfg().limits.ws_per_ip.max(1);
+        let mut entry = match self.ws_ip_counts.entry(ip) {
+            dashmap::Entry::Occupied(e) => e.into_ref(),
+            dashmap::Entry::Vacant(e) => {
+                let country = match self.geoip.as_ref().map(|g| g.admit(ip)) {
+                    None => None,
+                    Some(Ok(country)) => {
+                        tracing::info!(
+                            %ip,
+                            country = country.as_deref().unwrap_or(crate::geoip::UNKNOWN_COUNTRY),
+                            "client connected"
+                        );
+                        country
+                    }
+                    Some(Err(country)) => {
+                        tracing::info!(
+                            %ip,
+                            country = country.as_deref().unwrap_or(crate::geoip::UNKNOWN_COUNTRY),
+                            "connection refused by country filter"
+                        );
+                        return Err(WsRejection::CountryBlocked);
+                    }
+                };
+                e.insert(IpConnections { count: 0, country })
+            }
+        };
+        if entry.count >= limit {
+            return Err(WsRejection::TooManyConnections);
         }
-        *entry += 1;
-        Some(WsIpGuard {
+        entry.count += 1;
+        Ok(WsIpGuard {
             state: self.clone(),
             ip,
         })
@@ -210,61 +959,73 @@ impl AppState {
 
     fn release_ws_ip(&self, ip: IpAddr) {
         if let Some(mut entry) = self.ws_ip_counts.get_mut(&ip) {
-            if *entry > 1 {
-                *entry -= 1;
+            if entry.count > 1 {
+                entry.count -= 1;
                 return;
             }
         }
         self.ws_ip_counts.remove(&ip);
     }
 
-    pub async fn basic_info_json(&self, receiver_id: &str) -> String {
+    /// Connected client IPs per country (ISO code or [`crate::geoip::UNKNOWN_COUNTRY`]). Empty
+    /// without a GeoIP database.
+    pub fn listeners_by_country(&self) -> BTreeMap<String, usize> {
+        let mut out = BTreeMap::new();
+        if self.geoip.is_none() {
+            return out;
+        }
+        for entry in self.ws_ip_counts.iter() {
+            let country = entry
+                .country
+                .as_deref()
+                .unwrap_or(crate::geoip::UNKNOWN_COUNTRY);
+            *out.entry(country.to_string()).or_insert(0) += 1;
+        }
+        out
+    }
+
+    /// Settings message for `receiver_id`. Clients that use `/api/markers` get only the marker
+    /// count instead of the full `markers` string.
+    pub async fn basic_info_json(&self, receiver_id: &str, markers_api: bool) -> String {
         let Some(receiver) = self.receiver_state(receiver_id) else {
             return "{}".to_string();
         };
-        let grid_locator = self.cfg.websdr.grid_locator.clone();
-        let markers = self.markers.read().await;
-        let markers_str = json_stringify_value(&markers);
+        let grid_locator = self.cfg().websdr.grid_locator.clone();
+        let markers = self.markers.read().await.clone();
         let bands = self.bands.read().await;
         let bands_str = json_stringify_value(&bands);
 
-        let ssb_lowcut_hz = receiver
-            .receiver
-            .input
-            .defaults
-            .ssb_lowcut_hz
-            .unwrap_or(100)
-            .max(0);
-        let ssb_highcut_hz = receiver
-            .receiver
-            .input
+        let settings = receiver.settings();
+        let ssb_lowcut_hz = settings.defaults.ssb_lowcut_hz.unwrap_or(100).max(0);
+        let ssb_highcut_hz = settings
             .defaults
             .ssb_highcut_hz
             .unwrap_or(2800)
             .max(ssb_lowcut_hz.saturating_add(1));
 
         let defaults = json!({
-            "frequency": receiver.rt.default_frequency,
-            "modulation": receiver.rt.default_mode_str,
-            "l": receiver.rt.default_l,
-            "m": receiver.rt.default_m,
-            "r": receiver.rt.default_r,
+            "frequency": settings.default_frequency + receiver.basefreq() - receiver.rt.basefreq,
+            "modulation": settings.default_mode_str,
+            "l": settings.default_l,
+            "m": settings.default_m,
+            "r": settings.default_r,
             "ssb_lowcut_hz": ssb_lowcut_hz,
             "ssb_highcut_hz": ssb_highcut_hz,
-            "squelch_enabled": receiver.receiver.input.defaults.squelch_enabled,
-            "colormap": receiver.receiver.input.defaults.colormap,
+            "squelch_enabled": settings.defaults.squelch_enabled,
+            "colormap": settings.defaults.colormap,
         });
 
-        let out = json!({
+        let mut out = json!({
             "receiver_id": receiver.receiver.id,
-            "receiver_name": receiver.receiver.name,
+            "receiver_name": settings.name,
             "sps": receiver.rt.sps,
+            "input_sps": receiver.rt.input_sps,
             "audio_max_sps": receiver.rt.audio_max_sps,
             "audio_max_fft": receiver.rt.audio_max_fft_size,
             "fft_size": receiver.rt.fft_size,
             "fft_result_size": receiver.rt.fft_result_size,
             "waterfall_size": receiver.rt.min_waterfall_fft,
-            "basefreq": receiver.rt.basefreq,
+            "basefreq": receiver.basefreq(),
             "total_bandwidth": receiver.rt.total_bandwidth,
             "overlap": receiver.rt.fft_size / 2,
             "fft_overlap": receiver.rt.fft_size / 2,
@@ -273,9 +1034,31 @@ impl AppState {
             "audio_compression": receiver.rt.audio_compression_str,
             "grid_locator": grid_locator,
             "smeter_offset": receiver.receiver.input.smeter_offset,
-            "markers": markers_str,
             "bands": bands_str,
+            "maintenance": receiver.maintenance(),
+            "accuracy": receiver.accuracy_json(),
+            "overload": receiver.overload_json(),
         });
+        if let serde_json::Value::Object(map) = &mut out {
+            if let Some(identity) = self.identity.as_deref() {
+                map.insert(
+                    "identity_public_key".to_string(),
+                    json!(identity.public_key()),
+                );
+            }
+            map.insert(
+                "overlays_version".to_string(),
+                json!(self.overlays_version.load(Ordering::Relaxed)),
+            );
+            if markers_api {
+                map.insert("markers_count".to_string(), json!(markers.count()));
+            } else {
+                map.insert(
+                    "markers".to_string(),
+                    json!(json_stringify_value(markers.raw())),
+                );
+            }
+        }
 
         match serde_json::to_string(&out) {
             Ok(s) => s,
@@ -309,29 +1092,242 @@ impl AppState {
         }
     }
 
-    pub fn event_info(&self, include_changes: bool) -> EventsInfo {
-        let waterfall_clients = self.total_waterfall_clients();
-        let signal_clients = self.total_audio_clients();
-
-        let show_other_users = self.cfg.server.otherusers > 0;
-        let signal_changes = if include_changes && show_other_users {
+    /// The periodic events payload, limited to `topics`; only those parts are computed.
+    pub fn event_info(&self, include_changes: bool, topics: EventTopics) -> EventsInfo {
+        let mut info = EventsInfo::default();
+        if topics.contains(EventTopics::USERS) {
+            info.waterfall_clients = Some(self.total_waterfall_clients());
+            info.signal_clients = Some(self.total_audio_clients());
+            info.low_latency_clients = Some(self.low_latency_audio_clients());
+            info.countries = self.listeners_by_country();
+        }
+        if topics.contains(EventTopics::BANDWIDTH) {
+            info.waterfall_kbits =
+                Some(self.waterfall_kbits_per_sec.load(Ordering::Relaxed) as f64);
+            info.audio_kbits = Some(self.audio_kbits_per_sec.load(Ordering::Relaxed) as f64);
+        }
+        if topics.contains(EventTopics::DSP) && include_changes && self.cfg().server.otherusers > 0
+        {
             let mut map = HashMap::new();
             for (rx_id, rx) in self.receivers.iter() {
                 for entry in rx.signal_changes.iter() {
                     map.insert(format!("{rx_id}:{}", entry.key()), *entry.value());
                 }
             }
-            Some(map)
-        } else {
-            None
+            info.signal_changes = Some(map);
+        }
+        if topics.contains(EventTopics::CHAT) {
+            info.chat_clients = Some(self.chat_clients.len());
+            if self.cfg().server.otherusers > 0 {
+                info.presence = Some(self.presence_list());
+            }
+        }
+        if topics.contains(EventTopics::MAINTENANCE) {
+            info.maintenance = self
+                .receivers
+                .iter()
+                .filter_map(|(rx_id, rx)| {
+                    let m = rx.maintenance();
+                    m.enabled.then(|| (rx_id.clone(), m.message))
+                })
+                .collect();
+        }
+        info
+    }
+
+    /// Listeners who opted into the presence list, by nickname, with their current tune.
+    fn presence_list(&self) -> Vec<PresenceEntry> {
+        let mut list = Vec::new();
+        for (rx_id, rx) in self.receivers.iter() {
+            for client in rx.audio_clients.iter() {
+                let nickname = match client.presence.lock() {
+                    Ok(g) => g.clone(),
+                    Err(poisoned) => {
+                        tracing::error!(unique_id = %client.unique_id, "presence mutex poisoned; recovering");
+                        poisoned.into_inner().clone()
+                    }
+                };
+                let Some(nickname) = nickname else {
+                    continue;
+                };
+                let (m, mode) = match client.params.lock() {
+                    Ok(g) => (g.m, g.demodulation),
+                    Err(poisoned) => {
+                        tracing::error!(unique_id = %client.unique_id, "audio params mutex poisoned; recovering");
+                        let g = poisoned.into_inner();
+                        (g.m, g.demodulation)
+                    }
+                };
+                list.push(PresenceEntry {
+                    nickname,
+                    receiver_id: rx_id.clone(),
+                    frequency: rx.bin_frequency(m),
+                    mode: mode.as_str(),
+                });
+            }
+        }
+        list.sort_by(|a, b| a.nickname.cmp(&b.nickname));
+        list
+    }
+
+    /// Pushes the current events payload to every `/events` client immediately instead of waiting
+    /// for the periodic tick. The payload is built once per distinct topic selection; clients that
+    /// only follow typed messages get nothing. Clients whose queue is full or closed are dropped.
+    pub fn broadcast_event_info(&self, include_changes: bool) {
+        let mut selections: Vec<EventTopics> = Vec::new();
+        for entry in self.event_clients.iter() {
+            let periodic = entry.topics.intersection(EventTopics::PERIODIC);
+            if !periodic.is_empty() && !selections.contains(&periodic) {
+                selections.push(periodic);
+            }
+        }
+        let queued_at = Instant::now();
+        let payloads: Vec<(EventTopics, EventMessage)> = selections
+            .into_iter()
+            .map(|topics| {
+                let info = self.event_info(include_changes, topics);
+                let json = match serde_json::to_string(&info) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        tracing::error!(error = ?e, "failed to serialize events payload");
+                        "{}".to_string()
+                    }
+                };
+                let msg = EventMessage {
+                    json: Arc::from(json),
+                    queued_at,
+                };
+                (topics, msg)
+            })
+            .collect();
+        self.fan_out(|topics| {
+            let periodic = topics.intersection(EventTopics::PERIODIC);
+            payloads
+                .iter()
+                .find(|(t, _)| *t == periodic)
+                .map(|(_, msg)| msg.clone())
+        });
+    }
+
+    /// Marks `receiver`'s input as being reopened for `reason`, or running again with `None`, and
+    /// tells its audio clients and `/events` (`maintenance` topic). Repeats are not sent again.
+    pub fn set_receiver_input_status(
+        &self,
+        receiver: &ReceiverState,
+        reason: Option<&'static str>,
+    ) {
+        if !receiver.set_input_restarting(reason) {
+            return;
+        }
+        let msg = receiver.status_message();
+        for client in receiver.audio_clients.iter() {
+            let _ = client.text_tx.try_send(msg.clone());
+        }
+        self.broadcast_event_json(EventTopics::MAINTENANCE, msg);
+    }
+
+    /// Bumps the overlays version and tells `/events` clients which overlays (`markers`, `bands`,
+    /// `header_panel`) to refetch.
+    pub fn notify_overlays_updated(&self, changed: &[&str]) {
+        let version = self.overlays_version.fetch_add(1, Ordering::Relaxed) + 1;
+        let msg = json!({ "type": "overlays_updated", "version": version, "overlays": changed });
+        self.broadcast_event_json(EventTopics::OVERLAYS, msg.to_string());
+    }
+
+    /// Sends a `digital_voice` message to `/events`, unless another listener on the same
+    /// frequency already sent it.
+    pub fn publish_digital_voice(
+        &self,
+        receiver_id: &str,
+        frequency: i64,
+        active: bool,
+        json: String,
+    ) {
+        let key = (receiver_id.to_string(), frequency);
+        if self
+            .digital_voice_calls
+            .get(&key)
+            .is_some_and(|last| last.1 == json)
+        {
+            return;
+        }
+        if self.digital_voice_calls.len() >= MAX_DIGITAL_VOICE_CALLS {
+            self.digital_voice_calls.retain(|_, (active, _)| *active);
+        }
+        self.digital_voice_calls.insert(key, (active, json.clone()));
+        self.broadcast_event_json(EventTopics::DIGITAL_VOICE, json);
+    }
+
+    /// Pushes a text payload to the `/events` clients subscribed to `topic`. Clients whose queue
+    /// is full or closed are dropped.
+    pub fn broadcast_event_json(&self, topic: EventTopics, json: String) {
+        let msg = EventMessage {
+            json: Arc::from(json),
+            queued_at: Instant::now(),
         };
+        self.fan_out(|topics| topics.contains(topic).then(|| msg.clone()));
+    }
 
-        EventsInfo {
-            waterfall_clients,
-            signal_clients,
-            signal_changes,
-            waterfall_kbits: (self.waterfall_kbits_per_sec.load(Ordering::Relaxed) as f64) / 1.0,
-            audio_kbits: (self.audio_kbits_per_sec.load(Ordering::Relaxed) as f64) / 1.0,
+    /// Queues `payload_for(client topics)` to every `/events` client it returns a message for.
+    fn fan_out(&self, payload_for: impl Fn(EventTopics) -> Option<EventMessage>) {
+        let mut dead = Vec::new();
+        let mut max_depth = 0;
+        for entry in self.event_clients.iter() {
+            let Some(msg) = payload_for(entry.topics) else {
+                continue;
+            };
+            let tx = &entry.tx;
+            max_depth = max_depth.max(tx.max_capacity() - tx.capacity());
+            if tx.try_send(msg).is_err() {
+                dead.push(*entry.key());
+            }
+        }
+        self.events_fanout.record_broadcast(max_depth);
+        for id in dead {
+            self.event_clients.remove(&id);
+        }
+    }
+
+    /// Drops `/events` and `/chat` entries whose connection task has already gone away (its queue
+    /// receiver is dropped). Sessions normally remove themselves; this catches the ones that did
+    /// not, so broadcasts stop iterating over dead senders. Returns the number of entries removed.
+    pub fn prune_stale_clients(&self) -> usize {
+        let before = self.event_clients.len() + self.chat_clients.len();
+        self.event_clients
+            .retain(|_, client| !client.tx.is_closed());
+        self.chat_clients.retain(|_, client| !client.tx.is_closed());
+        let pruned = before.saturating_sub(self.event_clients.len() + self.chat_clients.len());
+        self.events_fanout
+            .pruned_clients
+            .fetch_add(pruned as u64, Ordering::Relaxed);
+        pruned
+    }
+}
+
+struct IpConnections {
+    count: usize,
+    country: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WsRejection {
+    TooManyConnections,
+    CountryBlocked,
+}
+
+impl IntoResponse for WsRejection {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            Self::TooManyConnections => (
+                axum::http::StatusCode::TOO_MANY_REQUESTS,
+                "too many connections from this IP",
+            )
+                .into_response(),
+            Self::CountryBlocked => (
+                axum::http::StatusCode::FORBIDDEN,
+                "connections from your country are not accepted",
+            )
+                .into_response(),
         }
     }
 }
@@ -361,6 +1357,107 @@ pub struct ChatMessage {
     pub reply_to_username: String,
 }
 
+pub struct ChatClient {
+    pub tx: mpsc::Sender<Arc<str>>,
+    /// Messages this client missed because its queue stayed full. While non-zero, the client is
+    /// resynced with the chat history instead of receiving individual messages.
+    pub missed: AtomicU64,
+}
+
+impl ChatClient {
+    pub fn new(tx: mpsc::Sender<Arc<str>>) -> Self {
+        Self {
+            tx,
+            missed: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Totals of chat fan-out backpressure since startup, exported on `/metrics`.
+#[derive(Default)]
+pub struct ChatBackpressure {
+    pub retried_sends: AtomicU64,
+    pub missed_messages: AtomicU64,
+    pub history_resyncs: AtomicU64,
+}
+
+impl ChatBackpressure {
+    pub fn totals(&self) -> [u64; 3] {
+        [
+            self.retried_sends.load(Ordering::Relaxed),
+            self.missed_messages.load(Ordering::Relaxed),
+            self.history_resyncs.load(Ordering::Relaxed),
+        ]
+    }
+}
+
+/// An `/events` connection: its queue and the topics it asked for.
+pub struct EventClient {
+    pub tx: mpsc::Sender<EventMessage>,
+    pub topics: EventTopics,
+}
+
+/// An `/events` payload together with the time it was queued, so the connection task can report
+/// how long fan-out took once the write completes.
+#[derive(Clone)]
+pub struct EventMessage {
+    pub json: Arc<str>,
+    pub queued_at: Instant,
+}
+
+/// Counters for the `/events` fan-out path, drained by the periodic stats log.
+#[derive(Default)]
+pub struct EventsFanout {
+    pub broadcasts: AtomicU64,
+    pub deliveries: AtomicU64,
+    /// Sum and maximum of the time from broadcast to the completed socket write, in microseconds.
+    /// The maximum is the latency of the slowest (last) client.
+    pub latency_sum_us: AtomicU64,
+    pub latency_max_us: AtomicU64,
+    /// Deepest client queue seen when a broadcast was enqueued.
+    pub max_queue_depth: AtomicU64,
+    pub pruned_clients: AtomicU64,
+}
+
+/// One drained window of [`EventsFanout`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct EventsFanoutStats {
+    pub broadcasts: u64,
+    pub deliveries: u64,
+    pub latency_avg_us: u64,
+    pub latency_max_us: u64,
+    pub max_queue_depth: u64,
+    pub pruned_clients: u64,
+}
+
+impl EventsFanout {
+    pub fn record_broadcast(&self, max_queue_depth: usize) {
+        self.broadcasts.fetch_add(1, Ordering::Relaxed);
+        self.max_queue_depth
+            .fetch_max(max_queue_depth as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_delivery(&self, latency: Duration) {
+        let us = latency.as_micros().min(u64::MAX as u128) as u64;
+        self.deliveries.fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_us.fetch_add(us, Ordering::Relaxed);
+        self.latency_max_us.fetch_max(us, Ordering::Relaxed);
+    }
+
+    pub fn take(&self) -> EventsFanoutStats {
+        let deliveries = self.deliveries.swap(0, Ordering::Relaxed);
+        let latency_sum_us = self.latency_sum_us.swap(0, Ordering::Relaxed);
+        EventsFanoutStats {
+            broadcasts: self.broadcasts.swap(0, Ordering::Relaxed),
+            deliveries,
+            latency_avg_us: latency_sum_us.checked_div(deliveries).unwrap_or(0),
+            latency_max_us: self.latency_max_us.swap(0, Ordering::Relaxed),
+            max_queue_depth: self.max_queue_depth.swap(0, Ordering::Relaxed),
+            pruned_clients: self.pruned_clients.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
 fn load_chat_history() -> Vec<ChatMessage> {
     let path = Path::new("chat_history.json");
     let Ok(raw) = std::fs::read_to_string(path) else {
@@ -391,9 +1488,64 @@ pub async fn append_chat_message(state: &AppState, msg: ChatMessage) {
 
 pub struct AudioClient {
     pub unique_id: String,
+    /// Spectrum frames for this client's demodulator task.
+    pub frames: mpsc::Sender<AudioFrame>,
     pub tx: mpsc::Sender<Vec<u8>>,
+    /// JSON text messages (RDS station information) for the same socket.
+    pub text_tx: mpsc::Sender<String>,
     pub params: std::sync::Mutex<AudioParams>,
     pub pipeline: std::sync::Mutex<crate::ws::audio::AudioPipeline>,
+    /// Squelch state after the last demodulated frame, for the scanner.
+    pub squelch_open: AtomicBool,
+    /// Marker the client is tuned to, refreshed by its frame task.
+    pub now_playing: std::sync::Mutex<Option<crate::audio_listener::NowPlaying>>,
+    /// `window` commands of this client that were refused.
+    pub rejected_windows: AtomicU64,
+    /// Nickname the listener shows up under in the presence list; `None` until it opts in.
+    pub presence: std::sync::Mutex<Option<String>>,
+}
+
+/// Where the FSK tones of an RTTY or NAVTEX signal lie in the audio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FskParams {
+    pub mode: novasdr_core::rtty::Mode,
+    /// Audio frequency halfway between mark and space, in Hz.
+    pub center_hz: f64,
+    pub shift_hz: f64,
+    /// Mark below space in USB (above in LSB), as some stations key.
+    pub reverse: bool,
+}
+
+impl FskParams {
+    /// Default audio centre, where most RTTY software puts the signal.
+    pub const DEFAULT_CENTER_HZ: f64 = 1_000.0;
+
+    /// Audio frequencies of the mark and space tones while demodulating with `lsb`, which
+    /// mirrors the signal.
+    pub fn tones(&self, lsb: bool) -> (f64, f64) {
+        let (high, low) = (
+            self.center_hz + self.shift_hz / 2.0,
+            self.center_hz - self.shift_hz / 2.0,
+        );
+        if lsb != self.reverse {
+            (low, high)
+        } else {
+            (high, low)
+        }
+    }
+}
+
+/// Loopback tone of an `/audio` client, for telling network or client decoding trouble from the
+/// DSP chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestTone {
+    pub freq_hz: f64,
+    /// Peak level in dBFS.
+    pub level_db: f64,
+}
+
+impl TestTone {
+    pub const DEFAULT_LEVEL_DB: f64 = -20.0;
 }
 
 #[derive(Debug, Clone)]
@@ -403,10 +1555,34 @@ pub struct AudioParams {
     pub r: i32,
     pub mute: bool,
     pub squelch_enabled: bool,
+    pub squelch_mode: SquelchMode,
+    /// Decode RDS while demodulating WBFM.
+    pub rds: bool,
+    /// Decode WBFM stereo and send two-channel audio frames.
+    pub stereo: bool,
+    /// Decode digital voice while demodulating FM.
+    pub digital_voice: bool,
+    /// Report the CTCSS tone or DCS code while demodulating FM.
+    pub subtone: bool,
+    /// FM audio only passes while this tone or code is heard.
+    pub tone_squelch: Option<novasdr_core::dsp::subtone::Subtone>,
+    /// Decode SSTV pictures from the demodulated audio.
+    pub sstv: bool,
+    /// Decode RTTY or NAVTEX from the demodulated audio.
+    pub fsk: Option<FskParams>,
+    /// Sine sent in place of the demodulated audio.
+    pub test_tone: Option<TestTone>,
     pub demodulation: novasdr_core::dsp::demod::DemodulationMode,
     pub agc_speed: AgcSpeed,
     pub agc_attack_ms: Option<f32>,
     pub agc_release_ms: Option<f32>,
+    pub filter_sharpness: FilterSharpness,
+    /// DX mode: shorter packets sent as soon as they are encoded, at a higher packet rate.
+    pub low_latency: bool,
+    /// Listener volume in dB, applied server-side before the 16-bit conversion.
+    pub volume_db: f32,
+    /// Frequency lowering applied after AGC, for listeners with high-frequency hearing loss.
+    pub hearing: novasdr_core::dsp::hearing::HearingProfile,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -432,10 +1608,173 @@ impl AgcSpeed {
     }
 }
 
+/// What opens the audio squelch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SquelchMode {
+    /// Spectral variation across the passband; needs no threshold.
+    #[default]
+    Variance,
+    /// Passband power over the surrounding noise floor, in whole dB: opens at `open_db` and
+    /// closes once it has stayed `hysteresis_db` below that for a few frames. Holds through
+    /// NBFM flutter that upsets the variance test.
+    Level { open_db: i16, hysteresis_db: i16 },
+}
+
+impl SquelchMode {
+    pub const DEFAULT_OPEN_DB: i16 = 10;
+    pub const DEFAULT_HYSTERESIS_DB: i16 = 3;
+    const MAX_OPEN_DB: f32 = 60.0;
+
+    /// `variance` or `level`; thresholds left out keep the current level settings or the
+    /// defaults. `None` for an unknown mode.
+    pub fn parse(
+        raw: &str,
+        current: Self,
+        open_db: Option<f32>,
+        hysteresis_db: Option<f32>,
+    ) -> Option<Self> {
+        match raw {
+            "variance" => Some(Self::Variance),
+            "level" => {
+                let (cur_open, cur_hyst) = match current {
+                    Self::Level {
+                        open_db,
+                        hysteresis_db,
+                    } => (open_db, hysteresis_db),
+                    Self::Variance => (Self::DEFAULT_OPEN_DB, Self::DEFAULT_HYSTERESIS_DB),
+                };
+                let open = open_db
+                    .filter(|v| v.is_finite())
+                    .map_or(cur_open, |v| v.clamp(1.0, Self::MAX_OPEN_DB).round() as i16);
+                let hyst = hysteresis_db
+                    .filter(|v| v.is_finite())
+                    .map_or(cur_hyst, |v| v.max(0.0).round() as i16)
+                    .min(open);
+                Some(Self::Level {
+                    open_db: open,
+                    hysteresis_db: hyst,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// How steeply the audio pipeline's FIR stage cuts off outside the selected passband.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterSharpness {
+    /// Only the FFT bin window; the skirts leak.
+    Off,
+    Soft,
+    Normal,
+    Sharp,
+}
+
+impl FilterSharpness {
+    pub fn parse(raw: &str) -> Self {
+        match raw {
+            "off" => Self::Off,
+            "soft" => Self::Soft,
+            "sharp" => Self::Sharp,
+            _ => Self::Normal,
+        }
+    }
+
+    /// Width of the passband skirts in Hz. Narrower skirts need proportionally longer filters,
+    /// which add latency: (taps - 1) / 2 samples.
+    pub fn transition_hz(self) -> Option<f32> {
+        match self {
+            Self::Off => None,
+            Self::Soft => Some(400.0),
+            Self::Normal => Some(200.0),
+            Self::Sharp => Some(60.0),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn squelch_mode_parses_and_keeps_level_settings() {
+        let level = SquelchMode::parse("level", SquelchMode::Variance, None, None).unwrap();
+        assert_eq!(
+            level,
+            SquelchMode::Level {
+                open_db: 10,
+                hysteresis_db: 3
+            }
+        );
+        let tuned = SquelchMode::parse("level", level, Some(14.4), None).unwrap();
+        assert_eq!(
+            tuned,
+            SquelchMode::Level {
+                open_db: 14,
+                hysteresis_db: 3
+            }
+        );
+        // Hysteresis cannot exceed the opening threshold; thresholds are clamped.
+        assert_eq!(
+            SquelchMode::parse("level", tuned, Some(-5.0), Some(9.0)).unwrap(),
+            SquelchMode::Level {
+                open_db: 1,
+                hysteresis_db: 1
+            }
+        );
+        assert_eq!(
+            SquelchMode::parse("variance", tuned, Some(20.0), None),
+            Some(SquelchMode::Variance)
+        );
+        assert_eq!(SquelchMode::parse("carrier", tuned, None, None), None);
+    }
+
+    #[test]
+    fn watched_file_reloads_only_after_changes_settle() {
+        let t0 = Instant::now();
+        let v1 = (std::time::SystemTime::UNIX_EPOCH, 10);
+        let v2 = (std::time::SystemTime::UNIX_EPOCH, 20);
+        let mut file = WatchedFile::new("markers.json", Some(v1));
+        assert!(!file.poll(Some(v1), t0));
+
+        assert!(!file.poll(Some(v2), t0));
+        assert!(!file.poll(Some(v2), t0 + OVERLAY_DEBOUNCE / 2));
+        // Still being written: the debounce restarts.
+        assert!(!file.poll(None, t0 + OVERLAY_DEBOUNCE / 2));
+        assert!(!file.poll(Some(v1), t0 + OVERLAY_DEBOUNCE));
+        assert!(file.poll(Some(v1), t0 + OVERLAY_DEBOUNCE * 2));
+        assert!(!file.poll(Some(v1), t0 + OVERLAY_DEBOUNCE * 3));
+    }
+
+    #[test]
+    fn events_fanout_take_reports_window_and_resets() {
+        let fanout = EventsFanout::default();
+        fanout.record_broadcast(3);
+        fanout.record_broadcast(1);
+        fanout.record_delivery(Duration::from_micros(100));
+        fanout.record_delivery(Duration::from_micros(300));
+        assert_eq!(
+            fanout.take(),
+            EventsFanoutStats {
+                broadcasts: 2,
+                deliveries: 2,
+                latency_avg_us: 200,
+                latency_max_us: 300,
+                max_queue_depth: 3,
+                pruned_clients: 0,
+            }
+        );
+        assert_eq!(fanout.take(), EventsFanoutStats::default());
+    }
+
+    #[test]
+    fn filter_sharpness_parse_defaults_to_normal() {
+        assert_eq!(FilterSharpness::parse("off"), FilterSharpness::Off);
+        assert_eq!(FilterSharpness::parse("sharp"), FilterSharpness::Sharp);
+        assert_eq!(FilterSharpness::parse("bogus"), FilterSharpness::Normal);
+        assert_eq!(FilterSharpness::Off.transition_hz(), None);
+    }
+
     #[test]
     fn agc_speed_parse_maps_known_values_and_defaults() {
         assert_eq!(AgcSpeed::parse("off"), AgcSpeed::Off);
@@ -449,8 +1788,14 @@ mod tests {
     }
 }
 
+/// Windows one waterfall connection can subscribe to at once, its primary window included.
+pub const MAX_WATERFALL_WINDOWS: usize = 4;
+
+/// One subscribed window. A connection's extra windows are registered under their own ids and
+/// share its `tx`; only window 0 counts as a client.
 pub struct WaterfallClient {
     pub tx: mpsc::Sender<WaterfallWorkItem>,
+    pub window: u8,
     pub params: std::sync::Mutex<WaterfallParams>,
 }
 
@@ -458,12 +1803,51 @@ pub fn audio_channel() -> (mpsc::Sender<Vec<u8>>, mpsc::Receiver<Vec<u8>>) {
     mpsc::channel(AUDIO_QUEUE_CAPACITY)
 }
 
+/// One FFT frame of raw bins, shared by every audio client of a receiver. `bins` is rotated so
+/// that index 0 is the lowest frequency, so a client's passband `l..r` is a plain subslice.
+#[derive(Clone)]
+pub struct AudioFrame {
+    pub receiver: Arc<ReceiverState>,
+    pub frame_num: u64,
+    pub bins: Arc<[Complex32]>,
+    /// Channel this client shares with others tuned the same way; `None` to demodulate alone.
+    pub channel: Option<Arc<std::sync::Mutex<crate::channels::SharedChannel>>>,
+    /// Channelizer channel this client is tuned from when it shares no channel; `None` to run
+    /// its own inverse FFT.
+    pub ddc: Option<Arc<std::sync::Mutex<novasdr_core::dsp::channelizer::ChannelDdc>>>,
+}
+
+/// One FFT frame of rotated bins for an `iq_clients` entry.
+#[derive(Clone)]
+pub struct IqFrame {
+    pub frame_num: u64,
+    pub bins: Arc<[Complex32]>,
+}
+
+pub fn iq_frame_channel() -> (mpsc::Sender<IqFrame>, mpsc::Receiver<IqFrame>) {
+    mpsc::channel(AUDIO_FRAME_QUEUE_CAPACITY)
+}
+
+/// Queue of an IQ client that first works through `backlog` replayed frames
+/// (`crate::replay`); live frames wait behind them instead of being dropped.
+pub fn iq_frame_channel_with_backlog(
+    backlog: usize,
+) -> (mpsc::Sender<IqFrame>, mpsc::Receiver<IqFrame>) {
+    mpsc::channel(AUDIO_FRAME_QUEUE_CAPACITY + backlog)
+}
+
+pub fn audio_frame_channel() -> (mpsc::Sender<AudioFrame>, mpsc::Receiver<AudioFrame>) {
+    mpsc::channel(AUDIO_FRAME_QUEUE_CAPACITY)
+}
+
 #[derive(Debug, Clone)]
 pub struct WaterfallWorkItem {
     pub frame_num: u64,
     pub level: usize,
     pub l: usize,
     pub r: usize,
+    pub window: u8,
+    pub aggregation: WaterfallAggregation,
     pub quantized_concat: Arc<[i8]>,
     pub quantized_offset: usize,
 }
@@ -479,15 +1863,47 @@ pub fn text_channel() -> (mpsc::Sender<Arc<str>>, mpsc::Receiver<Arc<str>>) {
     mpsc::channel(TEXT_QUEUE_CAPACITY)
 }
 
+pub fn event_channel() -> (mpsc::Sender<EventMessage>, mpsc::Receiver<EventMessage>) {
+    mpsc::channel(TEXT_QUEUE_CAPACITY)
+}
+
 #[derive(Debug, Clone)]
 pub struct WaterfallParams {
     pub level: usize,
     pub l: usize,
     pub r: usize,
+    pub aggregation: WaterfallAggregation,
+}
+
+/// Frames per aggregated waterfall row when the client does not say.
+pub const DEFAULT_AGGREGATE_FRAMES: u8 = 4;
+pub const MAX_AGGREGATE_FRAMES: u8 = 16;
+
+/// How a waterfall client's rows are combined before sending: one row per `n` frames, holding
+/// their mean or their maximum per bin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaterfallAggregation {
+    #[default]
+    None,
+    Average(u8),
+    PeakHold(u8),
+}
+
+impl WaterfallAggregation {
+    pub fn parse(raw: &str, frames: Option<u32>) -> Self {
+        let n = frames
+            .map(|f| f.clamp(2, MAX_AGGREGATE_FRAMES as u32) as u8)
+            .unwrap_or(DEFAULT_AGGREGATE_FRAMES);
+        match raw {
+            "average" => Self::Average(n),
+            "peak" => Self::PeakHold(n),
+            _ => Self::None,
+        }
+    }
 }
 
 pub async fn server_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let cfg = &state.cfg;
+    let cfg = state.cfg();
     let header = state.header_panel.read().await.clone();
 
     let normalize_image_ref = |raw: &str| -> Option<String> {
@@ -520,6 +1936,20 @@ pub async fn server_info(State(state): State<Arc<AppState>>) -> impl IntoRespons
         .map(|i| json!({ "label": i.label, "value": i.value }))
         .collect();
 
+    let fft_backends: std::collections::BTreeMap<&str, Option<FftBackendInfo>> = state
+        .receivers
+        .iter()
+        .map(|(id, rx)| (id.as_str(), rx.fft_backend()))
+        .collect();
+    let fallbacks: std::collections::BTreeMap<&str, &str> = state
+        .receivers
+        .iter()
+        .filter_map(|(id, rx)| {
+            let configured = rx.receiver.input.accelerator_fallback?;
+            Some((id.as_str(), configured.as_str()))
+        })
+        .collect();
+
     Json(json!({
         "serverName": cfg.websdr.name,
         "location": cfg.websdr.grid_locator,
@@ -527,6 +1957,15 @@ pub async fn server_info(State(state): State<Arc<AppState>>) -> impl IntoRespons
         "email": cfg.websdr.email,
         "chatEnabled": cfg.websdr.chat_enabled,
         "version": env!("CARGO_PKG_VERSION"),
+        "fftBackends": fft_backends,
+        "fftCapabilities": {
+            "os": std::env::consts::OS,
+            "accelerators": config::Accelerator::built_in()
+                .into_iter()
+                .map(config::Accelerator::as_str)
+                .collect::<Vec<_>>(),
+            "cpuFallbacks": fallbacks,
+        },
         "headerPanel": {
             "enabled": header.enabled,
             "title": header.title,
@@ -550,7 +1989,7 @@ pub async fn server_info(State(state): State<Arc<AppState>>) -> impl IntoRespons
 }
 
 pub async fn receivers_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let cfg = &state.cfg;
+    let cfg = state.cfg();
     let receivers = cfg
         .receivers
         .iter()
@@ -558,14 +1997,18 @@ pub async fn receivers_info(State(state): State<Arc<AppState>>) -> impl IntoResp
         .map(|r| {
             let rt = state
                 .receiver_state(r.id.as_str())
-                .map(|rx| rx.rt.as_ref())
-                .map(|rt| (rt.basefreq, rt.basefreq + rt.total_bandwidth));
+                .map(|rx| (rx.basefreq(), rx.basefreq() + rx.rt.total_bandwidth));
+            let maintenance = state
+                .receiver_state(r.id.as_str())
+                .map(|rx| rx.maintenance())
+                .unwrap_or_else(|| r.maintenance.clone());
             json!({
                 "id": r.id,
                 "name": r.name,
                 "driver": r.input.driver.as_str(),
                 "min_hz": rt.map(|(min, _)| min),
                 "max_hz": rt.map(|(_, max)| max),
+                "maintenance": maintenance,
             })
         })
         .collect::<Vec<_>>();
@@ -589,7 +2032,7 @@ pub async fn load_overlays_once(state: Arc<AppState>, overlays_dir: std::path::P
     let markers_path = overlays_dir.join("markers.json");
     if let Some(v) = maybe_load_json(&markers_path).await {
         let mut cur = state.markers.write().await;
-        *cur = v;
+        *cur = Arc::new(crate::markers::MarkerIndex::new(v));
     }
 
     let bands_path = overlays_dir.join("bands.json");
@@ -603,49 +2046,146 @@ pub async fn load_overlays_once(state: Arc<AppState>, overlays_dir: std::path::P
         let mut cur = state.header_panel.write().await;
         *cur = v;
     }
+
+    let masks_path = overlays_dir.join("waterfall_masks.json");
+    if let Some(v) = maybe_load_json(&masks_path).await {
+        let mut cur = state.waterfall_masks.write().await;
+        crate::waterfall_masks::apply(&state, &v);
+        *cur = v;
+    }
 }
 
-pub fn spawn_marker_watcher(state: Arc<AppState>, overlays_dir: std::path::PathBuf) {
-    tokio::spawn(async move {
-        loop {
-            let path = overlays_dir.join("markers.json");
-            if let Some(v) = maybe_load_json(&path).await {
-                let mut cur = state.markers.write().await;
-                if *cur != v {
-                    *cur = v;
-                }
+/// How often the overlay files are checked for changes.
+const OVERLAY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// A changed file is reloaded only after it has stayed unchanged this long, so an editor or a
+/// copy that writes in several steps is not read half-written.
+const OVERLAY_DEBOUNCE: Duration = Duration::from_millis(1500);
+
+/// Modification time and length; cheap to poll and changes on every save.
+type FileFingerprint = (std::time::SystemTime, u64);
+
+async fn file_fingerprint(path: &Path) -> Option<FileFingerprint> {
+    let meta = tokio::fs::metadata(path).await.ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Debounce state of one watched overlay file.
+struct WatchedFile {
+    name: &'static str,
+    fingerprint: Option<FileFingerprint>,
+    changed_at: Option<Instant>,
+}
+
+impl WatchedFile {
+    fn new(name: &'static str, fingerprint: Option<FileFingerprint>) -> Self {
+        Self {
+            name,
+            fingerprint,
+            changed_at: None,
+        }
+    }
+
+    /// Returns true once the file has changed and then stayed unchanged for [`OVERLAY_DEBOUNCE`].
+    fn poll(&mut self, fingerprint: Option<FileFingerprint>, now: Instant) -> bool {
+        if fingerprint != self.fingerprint {
+            self.fingerprint = fingerprint;
+            self.changed_at = Some(now);
+            return false;
+        }
+        match self.changed_at {
+            Some(at) if now.duration_since(at) >= OVERLAY_DEBOUNCE => {
+                self.changed_at = None;
+                true
             }
-            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            _ => false,
         }
-    });
+    }
 }
 
-pub fn spawn_bands_watcher(state: Arc<AppState>, overlays_dir: std::path::PathBuf) {
-    tokio::spawn(async move {
-        loop {
-            let path = overlays_dir.join("bands.json");
-            if let Some(v) = maybe_load_json(&path).await {
-                let mut cur = state.bands.write().await;
-                if *cur != v {
-                    *cur = v;
-                }
+/// Reloads `name` from `overlays_dir`; returns whether the served content changed.
+async fn reload_overlay(state: &AppState, overlays_dir: &Path, name: &str) -> bool {
+    let path = overlays_dir.join(name);
+    match name {
+        "markers.json" => {
+            let Some(v) = maybe_load_json(&path).await else {
+                return false;
+            };
+            let mut cur = state.markers.write().await;
+            if *cur.raw() == v {
+                return false;
             }
-            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            *cur = Arc::new(crate::markers::MarkerIndex::new(v));
+            true
         }
-    });
+        "bands.json" => {
+            let Some(v) = maybe_load_json(&path).await else {
+                return false;
+            };
+            let mut cur = state.bands.write().await;
+            if *cur == v {
+                return false;
+            }
+            *cur = v;
+            true
+        }
+        "header_panel.json" => {
+            let Some(v) = maybe_load_header_panel(&path).await else {
+                return false;
+            };
+            let mut cur = state.header_panel.write().await;
+            if *cur == v {
+                return false;
+            }
+            *cur = v;
+            true
+        }
+        // Masks only change what the DSP threads send, so clients are not told to refetch.
+        "waterfall_masks.json" => {
+            let Some(v) = maybe_load_json(&path).await else {
+                return false;
+            };
+            let mut cur = state.waterfall_masks.write().await;
+            if *cur != v {
+                crate::waterfall_masks::apply(state, &v);
+                *cur = v;
+                tracing::info!("waterfall masks reloaded from disk");
+            }
+            false
+        }
+        _ => false,
+    }
 }
 
-pub fn spawn_header_panel_watcher(state: Arc<AppState>, overlays_dir: std::path::PathBuf) {
+/// Watches `markers.json`, `bands.json`, `header_panel.json` and `waterfall_masks.json` and pushes
+/// `overlays_updated` to `/events` clients when edits on disk change what is served.
+pub fn spawn_overlay_watcher(state: Arc<AppState>, overlays_dir: std::path::PathBuf) {
     tokio::spawn(async move {
+        let mut files = Vec::new();
+        for name in [
+            "markers.json",
+            "bands.json",
+            "header_panel.json",
+            "waterfall_masks.json",
+        ] {
+            let fingerprint = file_fingerprint(&overlays_dir.join(name)).await;
+            files.push(WatchedFile::new(name, fingerprint));
+        }
+        let mut interval = tokio::time::interval(OVERLAY_POLL_INTERVAL);
         loop {
-            let path = overlays_dir.join("header_panel.json");
-            if let Some(v) = maybe_load_header_panel(&path).await {
-                let mut cur = state.header_panel.write().await;
-                if *cur != v {
-                    *cur = v;
+            interval.tick().await;
+            let mut changed = Vec::new();
+            for file in files.iter_mut() {
+                let fingerprint = file_fingerprint(&overlays_dir.join(file.name)).await;
+                if file.poll(fingerprint, Instant::now())
+                    && reload_overlay(&state, &overlays_dir, file.name).await
+                {
+                    changed.push(file.name.trim_end_matches(".json"));
                 }
             }
-            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            if !changed.is_empty() {
+                tracing::info!(overlays = ?changed, "overlays reloaded from disk");
+                state.notify_overlays_updated(&changed);
+            }
         }
     });
 }
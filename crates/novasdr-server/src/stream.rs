@@ -0,0 +1,409 @@
+use crate::audio_listener::HeadlessListener;
+use crate::state::{AppState, WsIpGuard};
+use axum::{
+    body::{Body, Bytes},
+    extract::{connect_info::ConnectInfo, Path, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::IntoResponse,
+};
+use interop::opus;
+use novasdr_core::codec::{ogg_opus::OggOpusWriter, wav};
+use novasdr_core::dsp::demod::DemodulationMode;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
+
+/// Opus frame length of `.ogg` streams.
+const OPUS_FRAME_MS: usize = 20;
+/// Large enough for one 20 ms Opus packet at any bitrate the encoder is set to.
+const OPUS_PACKET_MAX: usize = 4000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Wav,
+    OggOpus,
+}
+
+/// Splits `usb.ogg` into mode and container.
+fn parse_file(file: &str) -> Result<(DemodulationMode, Container), (StatusCode, &'static str)> {
+    let Some((mode, ext)) = file.rsplit_once('.') else {
+        return Err((StatusCode::NOT_FOUND, "expected <mode>.wav or <mode>.ogg"));
+    };
+    let container = match ext.to_ascii_lowercase().as_str() {
+        "wav" => Container::Wav,
+        "ogg" | "opus" => Container::OggOpus,
+        "mp3" => {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "mp3 is not available; use .ogg or .wav",
+            ))
+        }
+        _ => return Err((StatusCode::NOT_FOUND, "expected <mode>.wav or <mode>.ogg")),
+    };
+    let mode = DemodulationMode::from_str_upper(&mode.to_ascii_uppercase())
+        .ok_or((StatusCode::NOT_FOUND, "unknown mode"))?;
+    Ok((mode, container))
+}
+
+/// `GET /stream/:receiver_id/:frequency/:file`: endless audio of one fixed tune, for players,
+/// recorders and scripts that do not speak the `/audio` protocol. `frequency` is in Hz. Players
+/// that send `Icy-MetaData: 1` get the tuned marker's name as ICY `StreamTitle`.
+pub async fn handler(
+    Path((receiver_id, frequency, file)): Path<(String, String, String)>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<Arc<AppState>>,
+) -> axum::response::Response {
+    if !state.cfg().streams.enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let (mode, container) = match parse_file(&file) {
+        Ok(v) => v,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let Some(receiver) = state.receiver_state(receiver_id.as_str()).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let Ok(frequency) = frequency.parse::<i64>() else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "frequency must be an integer in Hz",
+        )
+            .into_response();
+    };
+    let rt = receiver.rt.as_ref();
+    if !receiver.covers(frequency) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "frequency outside this receiver's band",
+        )
+            .into_response();
+    }
+    let sample_rate = rt.audio_max_sps as usize;
+    let encoder = match container {
+        Container::Wav => None,
+        Container::OggOpus => {
+            match crate::ws::audio::new_opus_encoder(sample_rate, opus::Channels::Mono) {
+                Ok(e) => Some(e),
+                Err(e) => {
+                    tracing::warn!(error = ?e, "audio stream: no Opus encoder");
+                    return (
+                        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                        "Opus is not available for this receiver; use .wav",
+                    )
+                        .into_response();
+                }
+            }
+        }
+    };
+
+    let Ok(slot) = state.stream_slots.clone().try_acquire_owned() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "all stream slots in use").into_response();
+    };
+    let ip_guard = match state.try_acquire_ws_ip(addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => return rejection.into_response(),
+    };
+    if state.total_audio_clients() >= state.cfg().limits.audio {
+        return (StatusCode::TOO_MANY_REQUESTS, "too many audio clients").into_response();
+    }
+    if receiver.audio_full() {
+        return (StatusCode::TOO_MANY_REQUESTS, "receiver is full").into_response();
+    }
+    let quota = match crate::quota::acquire_audio(&state, addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => {
+            return (StatusCode::TOO_MANY_REQUESTS, rejection.message()).into_response();
+        }
+    };
+
+    let (listener, pcm) =
+        match HeadlessListener::start(state.clone(), receiver, frequency, mode).await {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!(error = ?e, "audio stream: cannot start listener");
+                return (StatusCode::SERVICE_UNAVAILABLE, "audio unavailable").into_response();
+            }
+        };
+    tracing::info!(
+        ip = %addr.ip(),
+        receiver_id = %receiver_id,
+        frequency,
+        mode = mode.as_str(),
+        "audio stream started"
+    );
+
+    let (head, format) = match encoder {
+        None => (wav::streaming_header(sample_rate as u32, 1), Format::Wav),
+        Some(encoder) => {
+            let mut ogg = OggOpusWriter::new(rand::random(), sample_rate as u32, 1);
+            let head = ogg.headers(&format!("NovaSDR {}", crate::build_info::version()));
+            let frame = sample_rate * OPUS_FRAME_MS / 1000;
+            let format = Format::Ogg {
+                encoder,
+                ogg,
+                frame,
+                pending: Vec::with_capacity(frame * 2),
+                packet: vec![0; OPUS_PACKET_MAX],
+            };
+            (head, format)
+        }
+    };
+    let icy = headers
+        .get("icy-metadata")
+        .is_some_and(|v| v.as_bytes().trim_ascii() == b"1");
+    let stream = AudioStream {
+        listener: ListenerGuard(Some(listener)),
+        head: Some(head),
+        pcm,
+        format,
+        icy: icy.then(Icy::default),
+        fallback_title: format!("{:.3} kHz {}", frequency as f64 / 1000.0, mode.as_str()),
+        quota,
+        _slot: slot,
+        _ip_guard: ip_guard,
+    };
+    let body = futures::stream::unfold(stream, |mut s| async move {
+        let chunk = s.next_chunk().await?;
+        Some((Ok::<_, std::io::Error>(Bytes::from(chunk)), s))
+    });
+    let content_type = match container {
+        Container::Wav => "audio/wav",
+        Container::OggOpus => "audio/ogg",
+    };
+    let mut response = Body::from_stream(body).into_response();
+    let out = response.headers_mut();
+    out.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    out.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("no-cache, no-store"),
+    );
+    if icy {
+        out.insert(
+            HeaderName::from_static("icy-metaint"),
+            HeaderValue::from(ICY_METAINT),
+        );
+        if let Ok(name) = HeaderValue::from_str(state.cfg().websdr.name.as_str()) {
+            out.insert(HeaderName::from_static("icy-name"), name);
+        }
+    }
+    response
+}
+
+enum Format {
+    Wav,
+    Ogg {
+        encoder: opus::Encoder,
+        ogg: OggOpusWriter,
+        /// Samples per Opus frame.
+        frame: usize,
+        pending: Vec<i16>,
+        packet: Vec<u8>,
+    },
+}
+
+impl Format {
+    /// Turns little-endian PCM into container bytes; empty while an Opus frame is incomplete.
+    fn push(&mut self, pcm: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let Self::Ogg {
+            encoder,
+            ogg,
+            frame,
+            pending,
+            packet,
+        } = self
+        else {
+            return Ok(pcm);
+        };
+        pending.extend(
+            pcm.chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        );
+        let mut out = Vec::new();
+        let mut start = 0;
+        while pending.len() - start >= *frame {
+            let size = encoder
+                .encode(&pending[start..start + *frame], packet)
+                .map_err(|e| anyhow::anyhow!("Opus encode error: {e}"))?;
+            out.extend(ogg.packet(&packet[..size], *frame));
+            start += *frame;
+        }
+        pending.drain(..start);
+        Ok(out)
+    }
+}
+
+struct AudioStream {
+    listener: ListenerGuard,
+    /// Container header, sent first.
+    head: Option<Vec<u8>>,
+    pcm: mpsc::Receiver<Vec<u8>>,
+    format: Format,
+    icy: Option<Icy>,
+    /// ICY title while no marker matches.
+    fallback_title: String,
+    quota: crate::quota::AudioQuotaGuard,
+    _slot: OwnedSemaphorePermit,
+    _ip_guard: WsIpGuard,
+}
+
+impl AudioStream {
+    /// The next non-empty chunk, or `None` once the stream should end.
+    async fn next_chunk(&mut self) -> Option<Vec<u8>> {
+        let chunk = match self.head.take() {
+            Some(head) => head,
+            None => self.next_audio().await?,
+        };
+        let title = self.icy.is_some().then(|| self.title());
+        match (self.icy.as_mut(), title) {
+            (Some(icy), Some(title)) => Some(icy.interleave(&chunk, &title)),
+            _ => Some(chunk),
+        }
+    }
+
+    fn title(&self) -> String {
+        self.listener
+            .0
+            .as_ref()
+            .and_then(HeadlessListener::now_playing)
+            .map_or_else(|| self.fallback_title.clone(), |np| np.name)
+    }
+
+    async fn next_audio(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let pcm = tokio::select! {
+                pcm = self.pcm.recv() => pcm?,
+                _ = self.quota.exhausted.wait_for(|exhausted| *exhausted) => {
+                    tracing::info!("audio stream ended: listening quota used up");
+                    return None;
+                }
+            };
+            match self.format.push(pcm) {
+                Ok(chunk) if chunk.is_empty() => continue,
+                Ok(chunk) => return Some(chunk),
+                Err(e) => {
+                    tracing::warn!(error = ?e, "audio stream ended");
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Audio bytes between ICY metadata blocks.
+const ICY_METAINT: u16 = 16_000;
+/// Longest title sent, in characters; a metadata block holds at most 4080 bytes.
+const ICY_MAX_TITLE: usize = 256;
+
+/// Shoutcast-style in-band metadata: after every [`ICY_METAINT`] bytes of audio, a length byte
+/// (in 16-byte units) and that much `StreamTitle='...';` text, or a zero byte when the title is
+/// unchanged.
+struct Icy {
+    until_block: usize,
+    sent: Option<String>,
+}
+
+impl Default for Icy {
+    fn default() -> Self {
+        Self {
+            until_block: usize::from(ICY_METAINT),
+            sent: None,
+        }
+    }
+}
+
+impl Icy {
+    fn interleave(&mut self, data: &[u8], title: &str) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len() + 1);
+        let mut rest = data;
+        while rest.len() >= self.until_block {
+            let (audio, next) = rest.split_at(self.until_block);
+            out.extend_from_slice(audio);
+            out.extend(self.block(title));
+            rest = next;
+            self.until_block = usize::from(ICY_METAINT);
+        }
+        out.extend_from_slice(rest);
+        self.until_block -= rest.len();
+        out
+    }
+
+    fn block(&mut self, title: &str) -> Vec<u8> {
+        if self.sent.as_deref() == Some(title) {
+            return vec![0];
+        }
+        self.sent = Some(title.to_string());
+        // The format has no escape for the quote that ends the title.
+        let title: String = title
+            .chars()
+            .take(ICY_MAX_TITLE)
+            .map(|c| if c == '\'' { '`' } else { c })
+            .collect();
+        let text = format!("StreamTitle='{title}';");
+        let units = text.len().div_ceil(16);
+        let mut block = Vec::with_capacity(1 + units * 16);
+        block.push(units as u8);
+        block.extend_from_slice(text.as_bytes());
+        block.resize(1 + units * 16, 0);
+        block
+    }
+}
+
+/// Stops the listener when the response body is dropped, which is how a disconnect shows up.
+struct ListenerGuard(Option<HeadlessListener>);
+
+impl Drop for ListenerGuard {
+    fn drop(&mut self) {
+        if let Some(listener) = self.0.take() {
+            tracing::info!("audio stream closed");
+            tokio::spawn(listener.stop());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_pick_mode_and_container() {
+        assert_eq!(
+            parse_file("usb.wav"),
+            Ok((DemodulationMode::Usb, Container::Wav))
+        );
+        assert_eq!(
+            parse_file("FM.ogg"),
+            Ok((DemodulationMode::Fm, Container::OggOpus))
+        );
+        assert_eq!(
+            parse_file("am.opus"),
+            Ok((DemodulationMode::Am, Container::OggOpus))
+        );
+        assert_eq!(
+            parse_file("usb.mp3").unwrap_err().0,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        assert_eq!(parse_file("xyz.wav").unwrap_err().0, StatusCode::NOT_FOUND);
+        assert_eq!(parse_file("usb").unwrap_err().0, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn icy_blocks_follow_every_metaint_bytes_and_repeat_only_changes() {
+        let metaint = usize::from(ICY_METAINT);
+        let mut icy = Icy::default();
+        let mut out = icy.interleave(&vec![1u8; metaint - 10], "Radio 'One'");
+        out.extend(icy.interleave(&[1u8; 20], "Radio 'One'"));
+        out.extend(icy.interleave(&vec![1u8; metaint], "Radio 'One'"));
+        out.extend(icy.interleave(&[1u8; 5], "Radio Two"));
+
+        let first = &out[metaint..];
+        let text = b"StreamTitle='Radio `One`';";
+        assert_eq!(usize::from(first[0]), text.len().div_ceil(16));
+        assert_eq!(&first[1..1 + text.len()], text);
+        let after = &first[1 + usize::from(first[0]) * 16..];
+        assert!(after[..metaint].iter().all(|&b| b == 1));
+        // Same title again: an empty block.
+        assert_eq!(after[metaint], 0);
+        assert_eq!(after.len(), metaint + 1 + 15);
+    }
+}
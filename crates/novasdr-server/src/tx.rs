@@ -0,0 +1,477 @@
+//! Transmitting through a receiver's SoapySDR device (`receivers[].tx`). The operator's `/tx`
+//! connection (`crate::ws::tx`) feeds a [`Session`] running on a thread of its own, which owns
+//! the transmitter and keeps every transmission inside the configured bands, gain limits and
+//! time-out timer, whatever the client asks for.
+
+use crate::input::Transmitter;
+use novasdr_core::config::Transmit;
+use novasdr_core::dsp::tx::{Modulator, TxMode, TX_AUDIO_RATE};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+/// A transmission that gets no audio for this long is ended, so a client that stalled or went
+/// away never leaves the carrier on.
+const AUDIO_STALL: Duration = Duration::from_secs(1);
+/// Audio queued ahead of the transmitter at most, in samples; more is dropped.
+const MAX_QUEUED_SAMPLES: usize = TX_AUDIO_RATE;
+/// How often the session thread checks its timers while no command arrives.
+const TICK: Duration = Duration::from_millis(100);
+
+/// A receiver's transmit side, as seen by its clients and `/metrics`.
+#[derive(Default)]
+pub struct TxState {
+    /// Set while an operator session holds the transmitter.
+    claimed: AtomicBool,
+    keyed: AtomicBool,
+}
+
+impl TxState {
+    /// Claims the transmitter for one session; `None` while another one has it.
+    pub fn claim(self: &Arc<Self>) -> Option<TxClaim> {
+        (!self.claimed.swap(true, Ordering::AcqRel)).then(|| TxClaim(self.clone()))
+    }
+
+    pub fn keyed(&self) -> bool {
+        self.keyed.load(Ordering::Relaxed)
+    }
+}
+
+/// Held by the session that owns the transmitter; released when dropped.
+pub struct TxClaim(Arc<TxState>);
+
+impl Drop for TxClaim {
+    fn drop(&mut self) {
+        self.0.keyed.store(false, Ordering::Relaxed);
+        self.0.claimed.store(false, Ordering::Release);
+    }
+}
+
+pub enum Command {
+    Tune {
+        frequency: i64,
+        mode: TxMode,
+    },
+    Power {
+        percent: f64,
+    },
+    Ptt {
+        on: bool,
+    },
+    /// Samples at [`TX_AUDIO_RATE`], full scale ±1.
+    Audio(Vec<f32>),
+}
+
+/// What the session reports after each change, sent to the client as `{"type":"tx",...}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Status {
+    pub keyed: bool,
+    pub frequency: Option<i64>,
+    pub mode: Option<&'static str>,
+    pub band: Option<String>,
+    /// Requested output, in percent of the band's gain limit.
+    pub power: f64,
+    pub gain_db: Option<f64>,
+    /// Why the last command was refused or the transmission ended on its own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One operator's transmit session over a [`Transmitter`].
+pub struct Session {
+    tx: Transmit,
+    transmitter: Box<dyn Transmitter>,
+    modulator: Option<Modulator>,
+    status: Status,
+    gain_limit_db: f64,
+    keyed_at: Option<Instant>,
+    last_audio: Instant,
+    /// Set when the device failed a call, as opposed to a refused command; it unkeys.
+    device_failed: bool,
+    baseband: Vec<num_complex::Complex32>,
+}
+
+impl Session {
+    pub fn new(tx: Transmit, transmitter: Box<dyn Transmitter>) -> Self {
+        Self {
+            tx,
+            transmitter,
+            modulator: None,
+            status: Status::default(),
+            gain_limit_db: 0.0,
+            keyed_at: None,
+            last_audio: Instant::now(),
+            device_failed: false,
+            baseband: Vec::new(),
+        }
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Applies one command; returns the status to report, with the reason if it was refused.
+    pub fn handle(&mut self, command: Command, now: Instant) -> Option<Status> {
+        let result = match command {
+            Command::Tune { frequency, mode } => self.tune(frequency, mode),
+            Command::Power { percent } => self.power(percent),
+            Command::Ptt { on } => self.ptt(on, now),
+            Command::Audio(samples) => {
+                let written = self.audio(&samples, now);
+                self.device_failed = false;
+                return written.err().map(|e| self.stop(e));
+            }
+        };
+        Some(match result {
+            Ok(()) => self.status.clone(),
+            Err(e) if std::mem::take(&mut self.device_failed) => self.stop(e),
+            Err(e) => self.status.clone().with_error(e),
+        })
+    }
+
+    /// Ends a transmission that ran into the time-out timer or lost its audio.
+    pub fn tick(&mut self, now: Instant) -> Option<Status> {
+        let keyed_at = self.keyed_at?;
+        if now.duration_since(keyed_at) >= Duration::from_secs(u64::from(self.tx.max_seconds)) {
+            return Some(self.stop(format!(
+                "time-out: transmissions are limited to {} s",
+                self.tx.max_seconds
+            )));
+        }
+        if now.duration_since(self.last_audio) >= AUDIO_STALL {
+            return Some(self.stop("no audio".to_string()));
+        }
+        None
+    }
+
+    fn tune(&mut self, frequency: i64, mode: TxMode) -> Result<(), String> {
+        let (low, high) = mode.occupied_hz();
+        let band = self
+            .tx
+            .band_for(frequency + low, frequency + high)
+            .ok_or_else(|| {
+                format!(
+                    "{} at {frequency} Hz is outside the transmit bands",
+                    mode.as_str()
+                )
+            })?
+            .clone();
+        if self.status.keyed {
+            return Err("unkey before retuning".to_string());
+        }
+        let limit = self.tx.gain_limit(&band);
+        // Lower the gain before moving into a band with a lower limit, raise it after.
+        let gain = self.gain_for(self.status.power, limit)?;
+        let lower = gain <= self.status.gain_db.unwrap_or(f64::INFINITY);
+        if lower {
+            self.set_gain(gain)?;
+        }
+        let tuned = self.transmitter.tune(frequency as f64);
+        self.device(tuned)?;
+        if !lower {
+            self.set_gain(gain)?;
+        }
+        if self.status.mode != Some(mode.as_str()) {
+            self.modulator = Some(Modulator::new(mode, self.tx.sample_rate as usize));
+        }
+        self.gain_limit_db = limit;
+        self.status.frequency = Some(frequency);
+        self.status.mode = Some(mode.as_str());
+        self.status.band = Some(band.name);
+        Ok(())
+    }
+
+    fn power(&mut self, percent: f64) -> Result<(), String> {
+        if !(0.0..=100.0).contains(&percent) {
+            return Err("power must be 0–100 %".to_string());
+        }
+        if self.status.frequency.is_some() {
+            let gain = self.gain_for(percent, self.gain_limit_db)?;
+            self.set_gain(gain)?;
+        }
+        self.status.power = percent;
+        Ok(())
+    }
+
+    fn ptt(&mut self, on: bool, now: Instant) -> Result<(), String> {
+        if on == self.status.keyed {
+            return Ok(());
+        }
+        if on {
+            if self.status.frequency.is_none() {
+                return Err("tune before keying".to_string());
+            }
+            if let Some(modulator) = self.modulator.as_mut() {
+                modulator.reset();
+            }
+        }
+        let keyed = self.transmitter.key(on);
+        self.device(keyed)?;
+        self.status.keyed = on;
+        self.keyed_at = on.then_some(now);
+        self.last_audio = now;
+        Ok(())
+    }
+
+    fn audio(&mut self, samples: &[f32], now: Instant) -> Result<(), String> {
+        if !self.status.keyed {
+            return Ok(());
+        }
+        let Some(modulator) = self.modulator.as_mut() else {
+            return Ok(());
+        };
+        self.last_audio = now;
+        self.baseband.clear();
+        modulator.process(samples, &mut self.baseband);
+        let written = self.transmitter.write(&self.baseband);
+        self.device(written)
+    }
+
+    /// Unkeys after `reason` and reports it.
+    fn stop(&mut self, reason: String) -> Status {
+        if let Err(e) = self.transmitter.key(false) {
+            tracing::error!(error = ?e, "failed to unkey transmitter");
+        }
+        self.status.keyed = false;
+        self.keyed_at = None;
+        self.status.clone().with_error(reason)
+    }
+
+    /// Device gain for `percent` of the output at `limit_db`, never above the limit.
+    fn gain_for(&mut self, percent: f64, limit_db: f64) -> Result<f64, String> {
+        let range = self.transmitter.gain_range();
+        gain_for(percent, limit_db, self.device(range)?)
+    }
+
+    fn set_gain(&mut self, gain: f64) -> Result<(), String> {
+        let set = self.transmitter.set_gain(gain);
+        self.device(set)?;
+        self.status.gain_db = Some(gain);
+        Ok(())
+    }
+
+    fn device<T>(&mut self, result: anyhow::Result<T>) -> Result<T, String> {
+        result.map_err(|e| {
+            self.device_failed = true;
+            format!("{e:#}")
+        })
+    }
+}
+
+impl Status {
+    fn with_error(mut self, error: String) -> Self {
+        self.error = Some(error);
+        self
+    }
+}
+
+/// Gain for `percent` of the power at `limit_db`: the limit less the power ratio in dB, within
+/// the device's `range`. Refused when even the device's lowest gain is above the limit.
+pub fn gain_for(percent: f64, limit_db: f64, range: (f64, f64)) -> Result<f64, String> {
+    let (min, max) = range;
+    if limit_db < min {
+        return Err(format!(
+            "the band's gain limit {limit_db} dB is below the device's lowest gain {min} dB"
+        ));
+    }
+    if percent <= 0.0 {
+        return Ok(min);
+    }
+    let gain = limit_db + 10.0 * (percent / 100.0).log10();
+    Ok(gain.clamp(min, limit_db.min(max)))
+}
+
+/// Handle of a running session thread; dropping it ends the session and unkeys.
+pub struct SessionHandle {
+    commands: mpsc::Sender<Command>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl SessionHandle {
+    /// Starts `session` on a thread of its own, which holds `claim` until the transmitter is
+    /// closed; its statuses go to `status`.
+    pub fn spawn(
+        session: Session,
+        claim: TxClaim,
+        status: tokio::sync::mpsc::UnboundedSender<Status>,
+    ) -> anyhow::Result<Self> {
+        let (commands, rx) = mpsc::channel();
+        let queued = Arc::new(AtomicUsize::new(0));
+        let counter = queued.clone();
+        std::thread::Builder::new()
+            .name("novasdr-tx".to_string())
+            .spawn(move || run(session, rx, &counter, claim, &status))?;
+        Ok(Self { commands, queued })
+    }
+
+    /// Passes `command` on; audio beyond [`MAX_QUEUED_SAMPLES`] is dropped.
+    pub fn send(&self, command: Command) -> bool {
+        if let Command::Audio(samples) = &command {
+            let n = samples.len();
+            if self.queued.load(Ordering::Relaxed) + n > MAX_QUEUED_SAMPLES {
+                return false;
+            }
+            self.queued.fetch_add(n, Ordering::Relaxed);
+        }
+        self.commands.send(command).is_ok()
+    }
+}
+
+fn run(
+    mut session: Session,
+    commands: mpsc::Receiver<Command>,
+    queued: &AtomicUsize,
+    claim: TxClaim,
+    status: &tokio::sync::mpsc::UnboundedSender<Status>,
+) {
+    let state = &claim.0;
+    loop {
+        let report = match commands.recv_timeout(TICK) {
+            Ok(command) => {
+                if let Command::Audio(samples) = &command {
+                    queued.fetch_sub(samples.len(), Ordering::Relaxed);
+                }
+                session.handle(command, Instant::now())
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        let report = report.or_else(|| session.tick(Instant::now()));
+        state.keyed.store(session.status().keyed, Ordering::Relaxed);
+        if let Some(report) = report {
+            let _ = status.send(report);
+        }
+    }
+    // The transmitter unkeys when dropped, before the claim lets another session open it.
+    drop(session);
+    drop(claim);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use novasdr_core::config::TxBand;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Log {
+        keyed: bool,
+        gain: f64,
+        frequency: f64,
+        samples: usize,
+    }
+
+    struct Fake(Arc<Mutex<Log>>);
+
+    impl Transmitter for Fake {
+        fn tune(&mut self, frequency: f64) -> anyhow::Result<()> {
+            self.0.lock().unwrap().frequency = frequency;
+            Ok(())
+        }
+        fn gain_range(&self) -> anyhow::Result<(f64, f64)> {
+            Ok((0.0, 60.0))
+        }
+        fn set_gain(&mut self, gain_db: f64) -> anyhow::Result<()> {
+            self.0.lock().unwrap().gain = gain_db;
+            Ok(())
+        }
+        fn key(&mut self, on: bool) -> anyhow::Result<()> {
+            self.0.lock().unwrap().keyed = on;
+            Ok(())
+        }
+        fn write(&mut self, samples: &[num_complex::Complex32]) -> anyhow::Result<()> {
+            self.0.lock().unwrap().samples += samples.len();
+            Ok(())
+        }
+    }
+
+    fn session() -> (Session, Arc<Mutex<Log>>) {
+        let tx = Transmit {
+            enabled: true,
+            sample_rate: 48_000,
+            max_gain_db: 40.0,
+            max_seconds: 10,
+            bands: vec![
+                TxBand {
+                    name: "20m".to_string(),
+                    start: 14_000_000,
+                    end: 14_350_000,
+                    max_gain_db: None,
+                },
+                TxBand {
+                    name: "40m".to_string(),
+                    start: 7_000_000,
+                    end: 7_200_000,
+                    max_gain_db: Some(20.0),
+                },
+            ],
+            ..Transmit::default()
+        };
+        let log = Arc::new(Mutex::new(Log::default()));
+        (Session::new(tx, Box::new(Fake(log.clone()))), log)
+    }
+
+    #[test]
+    fn tuning_stays_inside_the_bands_with_their_gain_limits() {
+        let (mut s, log) = session();
+        let now = Instant::now();
+        let tune = |frequency, mode| Command::Tune { frequency, mode };
+
+        let st = s.handle(tune(14_348_000, TxMode::Usb), now).unwrap();
+        assert!(st.error.unwrap().contains("outside"));
+        let st = s.handle(Command::Ptt { on: true }, now).unwrap();
+        assert_eq!(st.error.as_deref(), Some("tune before keying"));
+
+        s.handle(Command::Power { percent: 100.0 }, now);
+        let st = s.handle(tune(14_200_000, TxMode::Usb), now).unwrap();
+        assert_eq!((st.error, st.gain_db), (None, Some(40.0)));
+        let st = s.handle(tune(7_100_000, TxMode::Usb), now).unwrap();
+        assert_eq!(st.gain_db, Some(20.0));
+        assert_eq!(log.lock().unwrap().frequency, 7_100_000.0);
+        let st = s.handle(Command::Power { percent: 10.0 }, now).unwrap();
+        assert_eq!(st.gain_db, Some(10.0));
+        assert_eq!(st.band.as_deref(), Some("40m"));
+    }
+
+    #[test]
+    fn time_out_and_missing_audio_unkey() {
+        let (mut s, log) = session();
+        let start = Instant::now();
+        s.handle(
+            Command::Tune {
+                frequency: 14_200_000,
+                mode: TxMode::Fm,
+            },
+            start,
+        );
+        assert!(s.handle(Command::Ptt { on: true }, start).unwrap().keyed);
+        let mut at = start;
+        for _ in 0..15 {
+            at += Duration::from_millis(900);
+            s.handle(Command::Audio(vec![0.0; 120]), at);
+            if let Some(st) = s.tick(at) {
+                assert!(st.error.unwrap().starts_with("time-out"));
+                break;
+            }
+        }
+        assert!(at.duration_since(start) >= Duration::from_secs(10));
+        assert!(!log.lock().unwrap().keyed);
+        // 120 samples at 12 kHz are 480 at 48 kHz, sent until the timer ran out at 10.8 s.
+        assert_eq!(log.lock().unwrap().samples, 12 * 480);
+
+        s.handle(Command::Ptt { on: true }, at);
+        assert!(s.tick(at + Duration::from_millis(500)).is_none());
+        let st = s.tick(at + AUDIO_STALL).unwrap();
+        assert_eq!((st.keyed, st.error.as_deref()), (false, Some("no audio")));
+    }
+
+    #[test]
+    fn power_maps_to_gain_below_the_limit() {
+        assert_eq!(gain_for(100.0, 30.0, (0.0, 60.0)), Ok(30.0));
+        assert_eq!(gain_for(50.0, 30.0, (0.0, 60.0)).map(f64::round), Ok(27.0));
+        assert_eq!(gain_for(0.0, 30.0, (5.0, 60.0)), Ok(5.0));
+        assert_eq!(gain_for(100.0, 90.0, (0.0, 60.0)), Ok(60.0));
+        assert!(gain_for(100.0, -5.0, (0.0, 60.0)).is_err());
+    }
+}
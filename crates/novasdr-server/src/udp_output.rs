@@ -0,0 +1,237 @@
+//! UDP audio output (`receivers[].udp_output`): one fixed channel, demodulated by a headless
+//! listener whether or not anyone listens, sent as raw 16-bit mono PCM datagrams like a virtual
+//! audio cable, for a decoder such as direwolf (`ADEVICE udp:7355`) or multimon-ng. The operator
+//! retunes or switches it through `/admin/receivers/:receiver_id/udp_output`, and reloads apply
+//! at once.
+
+use crate::audio_listener::HeadlessListener;
+use crate::shutdown;
+use crate::state::{AppState, ReceiverState};
+use anyhow::Context;
+use novasdr_core::config::UdpOutput;
+use novasdr_core::dsp::demod::DemodulationMode;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::watch;
+
+/// How long a failed output waits before it starts again, unless the settings change first.
+const RETRY: Duration = Duration::from_secs(10);
+/// How often the loop checks for shutdown while no audio arrives.
+const POLL: Duration = Duration::from_secs(1);
+
+/// A receiver's UDP output, as set in `receivers.json` or through the admin API.
+pub struct UdpOutputState {
+    settings: watch::Sender<UdpOutput>,
+    /// Datagrams sent since startup.
+    packets: AtomicU64,
+    /// Why the output is not running although enabled.
+    error: std::sync::Mutex<Option<String>>,
+}
+
+impl UdpOutputState {
+    pub fn new(settings: UdpOutput) -> Self {
+        Self {
+            settings: watch::channel(settings).0,
+            packets: AtomicU64::new(0),
+            error: std::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn settings(&self) -> UdpOutput {
+        self.settings.borrow().clone()
+    }
+
+    /// Restarts the output with `next`, unless it already runs with those settings.
+    pub fn set(&self, next: UdpOutput) {
+        self.settings.send_if_modified(|cur| {
+            let changed = *cur != next;
+            *cur = next;
+            changed
+        });
+    }
+
+    pub fn packets(&self) -> u64 {
+        self.packets.load(Ordering::Relaxed)
+    }
+
+    pub fn error(&self) -> Option<String> {
+        match self.error.lock() {
+            Ok(g) => g.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    fn set_error(&self, error: Option<String>) {
+        match self.error.lock() {
+            Ok(mut g) => *g = error,
+            Err(poisoned) => *poisoned.into_inner() = error,
+        }
+    }
+}
+
+/// Starts the output task of every receiver; it idles until `udp_output.enabled` is set.
+pub fn spawn(state: Arc<AppState>) {
+    for receiver in state.receivers.values() {
+        tokio::spawn(run(state.clone(), receiver.clone()));
+    }
+}
+
+async fn run(state: Arc<AppState>, receiver: Arc<ReceiverState>) {
+    let receiver_id = receiver.receiver.id.as_str();
+    let output = &receiver.udp_output;
+    let mut settings = output.settings.subscribe();
+    let mut poll = tokio::time::interval(POLL);
+    while !shutdown::is_shutdown_requested() {
+        let current = settings.borrow_and_update().clone();
+        let mut wait = None;
+        if current.enabled {
+            match send(&state, &receiver, &current, &mut settings).await {
+                Ok(()) => {
+                    output.set_error(None);
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!(receiver_id, error = ?e, "udp output failed");
+                    output.set_error(Some(format!("{e:#}")));
+                    wait = Some(tokio::time::Instant::now() + RETRY);
+                }
+            }
+        } else {
+            output.set_error(None);
+        }
+        // Disabled, or failed: wait for new settings (or the retry).
+        loop {
+            tokio::select! {
+                changed = settings.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    break;
+                }
+                _ = poll.tick() => {
+                    if shutdown::is_shutdown_requested()
+                        || wait.is_some_and(|at| tokio::time::Instant::now() >= at)
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sends the channel until the settings change or the server shuts down.
+async fn send(
+    state: &Arc<AppState>,
+    receiver: &Arc<ReceiverState>,
+    output: &UdpOutput,
+    settings: &mut watch::Receiver<UdpOutput>,
+) -> anyhow::Result<()> {
+    let receiver_id = receiver.receiver.id.as_str();
+    output.check().map_err(anyhow::Error::msg)?;
+    let target: SocketAddr = output.target.trim().parse()?;
+    let defaults = receiver.settings();
+    let frequency = output.frequency.unwrap_or(defaults.default_frequency);
+    anyhow::ensure!(
+        receiver.covers(frequency),
+        "frequency {frequency} is outside the receiver range"
+    );
+    let mode = output
+        .demodulation()
+        .or_else(|| DemodulationMode::from_str_upper(defaults.default_mode_str.as_str()))
+        .unwrap_or(DemodulationMode::Usb);
+    let bind: SocketAddr = if target.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = UdpSocket::bind(bind).await.context("bind udp socket")?;
+    let sample_rate = receiver.rt.audio_max_sps;
+    let mut packets = Packetizer::new(sample_rate as usize * output.packet_ms as usize / 1000);
+
+    let (listener, mut pcm) =
+        HeadlessListener::start(state.clone(), receiver.clone(), frequency, mode).await?;
+    tracing::info!(
+        receiver_id,
+        %target,
+        frequency,
+        mode = mode.as_str(),
+        sample_rate,
+        "udp output started"
+    );
+    let mut poll = tokio::time::interval(POLL);
+    let res = loop {
+        tokio::select! {
+            _ = settings.changed() => break Ok(()),
+            _ = poll.tick() => if shutdown::is_shutdown_requested() {
+                break Ok(());
+            },
+            buf = pcm.recv() => {
+                let Some(buf) = buf else {
+                    break Err(anyhow::anyhow!("audio listener ended"));
+                };
+                packets.push(&buf);
+                while let Some(packet) = packets.next_packet() {
+                    // Nobody listening on the target is not an error: the decoder may start later.
+                    match socket.send_to(&packet, target).await {
+                        Ok(_) => {
+                            receiver.udp_output.packets.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(e) => tracing::trace!(receiver_id, error = ?e, "udp output send failed"),
+                    }
+                }
+            }
+        }
+    };
+    listener.stop().await;
+    tracing::info!(receiver_id, "udp output stopped");
+    res
+}
+
+/// Cuts a stream of 16-bit PCM into datagrams of a fixed number of samples.
+struct Packetizer {
+    bytes_per_packet: usize,
+    pending: Vec<u8>,
+}
+
+impl Packetizer {
+    fn new(samples_per_packet: usize) -> Self {
+        Self {
+            bytes_per_packet: 2 * samples_per_packet.max(1),
+            pending: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, pcm: &[u8]) {
+        self.pending.extend_from_slice(pcm);
+    }
+
+    fn next_packet(&mut self) -> Option<Vec<u8>> {
+        if self.pending.len() < self.bytes_per_packet {
+            return None;
+        }
+        let rest = self.pending.split_off(self.bytes_per_packet);
+        Some(std::mem::replace(&mut self.pending, rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audio_is_sent_in_whole_packets() {
+        let mut packets = Packetizer::new(3);
+        packets.push(&[1, 0, 2, 0]);
+        assert_eq!(packets.next_packet(), None);
+        packets.push(&[3, 0, 4, 0, 5, 0, 6, 0, 7, 0]);
+        assert_eq!(packets.next_packet(), Some(vec![1, 0, 2, 0, 3, 0]));
+        assert_eq!(packets.next_packet(), Some(vec![4, 0, 5, 0, 6, 0]));
+        assert_eq!(packets.next_packet(), None);
+        packets.push(&[8, 0]);
+        assert_eq!(packets.next_packet(), None);
+    }
+}
@@ -5,7 +5,7 @@ use std::cmp::Ordering;
 use std::time::Duration;
 
 pub fn spawn(state: std::sync::Arc<state::AppState>) {
-    if !state.cfg.updates.check_on_startup {
+    if !state.cfg().updates.check_on_startup {
         return;
     }
 
@@ -18,7 +18,8 @@ pub fn spawn(state: std::sync::Arc<state::AppState>) {
 }
 
 async fn check_once(state: &state::AppState) -> anyhow::Result<()> {
-    let repo = state.cfg.updates.github_repo.trim();
+    let cfg = state.cfg();
+    let repo = cfg.updates.github_repo.trim();
     if repo.is_empty() {
         return Ok(());
     }
@@ -0,0 +1,585 @@
+use crate::state::AppState;
+use anyhow::Context;
+use axum::{
+    extract::{connect_info::ConnectInfo, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine as _};
+use dashmap::DashMap;
+use ring::{digest, rand::SecureRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Listener accounts, resolved next to `receivers.json`.
+pub const FILE_NAME: &str = "users.json";
+const SIGNUP_WINDOW: Duration = Duration::from_secs(3600);
+/// Serialized size of `preferences`; the frontend's settings fit in a fraction of this.
+const MAX_PREFERENCES_BYTES: usize = 16 * 1024;
+const MAX_BOOKMARKS: usize = 500;
+const MAX_BOOKMARK_NAME_LEN: usize = 40;
+const MAX_MODE_LEN: usize = 8;
+const MAX_RECEIVER_ID_LEN: usize = 64;
+const MAX_USERNAME_LEN: usize = 14;
+const MAX_USER_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub frequency: i64,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receiver_id: Option<String>,
+}
+
+/// What the listener shows up as in chat (`chat` command `username`/`user_id`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatIdentity {
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+    pub id: String,
+    /// SHA-256 of the token secret; the token itself is only shown to the listener.
+    token_hash: String,
+    /// Unix seconds.
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Opaque frontend settings (theme, waterfall colours, audio options, ...).
+    #[serde(default)]
+    pub preferences: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    pub chat: ChatIdentity,
+}
+
+impl Account {
+    /// The account as returned to its owner.
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "id": self.id,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+            "preferences": self.preferences,
+            "bookmarks": self.bookmarks,
+            "chat": self.chat,
+        })
+    }
+}
+
+/// `PUT /api/user` body; fields that are present replace the stored ones.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AccountUpdate {
+    #[serde(default)]
+    pub preferences: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(default)]
+    pub bookmarks: Option<Vec<Bookmark>>,
+    #[serde(default)]
+    pub chat: Option<ChatIdentity>,
+}
+
+fn hash_secret(secret: &str) -> String {
+    BASE64URL.encode(digest::digest(&digest::SHA256, secret.as_bytes()))
+}
+
+fn clean_bookmark(b: Bookmark) -> Result<Bookmark, &'static str> {
+    let name = b.name.trim();
+    if name.is_empty() {
+        return Err("bookmark name is required");
+    }
+    if name.chars().count() > MAX_BOOKMARK_NAME_LEN {
+        return Err("bookmark name too long");
+    }
+    if b.frequency <= 0 {
+        return Err("bookmark frequency must be positive");
+    }
+    let mode = b
+        .mode
+        .map(|m| m.trim().to_ascii_uppercase())
+        .filter(|m| !m.is_empty());
+    if mode.as_ref().is_some_and(|m| m.len() > MAX_MODE_LEN) {
+        return Err("bookmark mode too long");
+    }
+    let receiver_id = b
+        .receiver_id
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+    if receiver_id
+        .as_ref()
+        .is_some_and(|r| r.len() > MAX_RECEIVER_ID_LEN)
+    {
+        return Err("bookmark receiver_id too long");
+    }
+    Ok(Bookmark {
+        frequency: b.frequency,
+        name: name.to_string(),
+        mode,
+        receiver_id,
+    })
+}
+
+/// All accounts, keyed by id.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserStore {
+    #[serde(default)]
+    accounts: BTreeMap<String, Account>,
+}
+
+impl UserStore {
+    /// Reads the accounts file; a missing file is an empty store.
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let raw = match std::fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        };
+        serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        let value = serde_json::to_value(self).context("serialize user accounts")?;
+        novasdr_core::config::write_json_atomic(path, &value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Creates an empty account and returns it with its token, `<id>.<secret>`.
+    pub fn create(
+        &mut self,
+        rng: &dyn SecureRandom,
+        now: i64,
+    ) -> anyhow::Result<(Account, String)> {
+        let mut secret = [0u8; 32];
+        rng.fill(&mut secret)
+            .map_err(|_| anyhow::anyhow!("generate account token"))?;
+        let secret = BASE64URL.encode(secret);
+        let id = novasdr_core::util::generate_unique_id()[..16].to_string();
+        let account = Account {
+            id: id.clone(),
+            token_hash: hash_secret(&secret),
+            created_at: now,
+            updated_at: now,
+            preferences: serde_json::Map::new(),
+            bookmarks: Vec::new(),
+            chat: ChatIdentity::default(),
+        };
+        self.accounts.insert(id.clone(), account.clone());
+        Ok((account, format!("{id}.{secret}")))
+    }
+
+    /// Id of the account `token` belongs to.
+    pub fn authenticate(&self, token: &str) -> Option<String> {
+        let (id, secret) = token.split_once('.')?;
+        let account = self.accounts.get(id)?;
+        crate::auth::constant_time_eq(
+            hash_secret(secret).as_bytes(),
+            account.token_hash.as_bytes(),
+        )
+        .then(|| account.id.clone())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Account> {
+        self.accounts.get(id)
+    }
+
+    /// Validates `update` and applies it. Errors are short reasons suitable for the listener.
+    pub fn update(
+        &mut self,
+        id: &str,
+        update: AccountUpdate,
+        now: i64,
+    ) -> Result<(), &'static str> {
+        let account = self.accounts.get_mut(id).ok_or("unknown account")?;
+        if let Some(preferences) = update.preferences.as_ref() {
+            let size = serde_json::to_vec(preferences).map_or(usize::MAX, |v| v.len());
+            if size > MAX_PREFERENCES_BYTES {
+                return Err("preferences too large");
+            }
+        }
+        let bookmarks = match update.bookmarks {
+            Some(bookmarks) if bookmarks.len() > MAX_BOOKMARKS => return Err("too many bookmarks"),
+            Some(bookmarks) => Some(
+                bookmarks
+                    .into_iter()
+                    .map(clean_bookmark)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            None => None,
+        };
+        let chat = match update.chat {
+            Some(chat) => {
+                let username = chat.username.trim();
+                let user_id = chat.user_id.trim();
+                if username.chars().count() > MAX_USERNAME_LEN {
+                    return Err("chat username too long");
+                }
+                if user_id.len() > MAX_USER_ID_LEN {
+                    return Err("chat user_id too long");
+                }
+                Some(ChatIdentity {
+                    username: username.to_string(),
+                    user_id: user_id.to_string(),
+                })
+            }
+            None => None,
+        };
+
+        if let Some(preferences) = update.preferences {
+            account.preferences = preferences;
+        }
+        if let Some(bookmarks) = bookmarks {
+            account.bookmarks = bookmarks;
+        }
+        if let Some(chat) = chat {
+            account.chat = chat;
+        }
+        account.updated_at = now;
+        Ok(())
+    }
+
+    /// Replaces the token of `id`, signing out every other device. Returns the new token.
+    pub fn rotate_token(
+        &mut self,
+        id: &str,
+        rng: &dyn SecureRandom,
+        now: i64,
+    ) -> anyhow::Result<Option<String>> {
+        let Some(account) = self.accounts.get_mut(id) else {
+            return Ok(None);
+        };
+        let mut secret = [0u8; 32];
+        rng.fill(&mut secret)
+            .map_err(|_| anyhow::anyhow!("generate account token"))?;
+        let secret = BASE64URL.encode(secret);
+        account.token_hash = hash_secret(&secret);
+        account.updated_at = now;
+        Ok(Some(format!("{id}.{secret}")))
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<Account> {
+        self.accounts.remove(id)
+    }
+}
+
+/// The account store plus per-IP sign-up windows (`accounts.signups_per_hour`).
+pub struct UserAccounts {
+    pub store: tokio::sync::Mutex<UserStore>,
+    signups: DashMap<IpAddr, (u32, Instant)>,
+    rng: ring::rand::SystemRandom,
+}
+
+impl UserAccounts {
+    pub fn new(store: UserStore) -> Self {
+        Self {
+            store: tokio::sync::Mutex::new(store),
+            signups: DashMap::new(),
+            rng: ring::rand::SystemRandom::new(),
+        }
+    }
+
+    fn admit_signup(&self, ip: IpAddr, limit: u32, now: Instant) -> bool {
+        let mut entry = self.signups.entry(ip).or_insert((0, now));
+        if now.duration_since(entry.1) >= SIGNUP_WINDOW {
+            *entry = (0, now);
+        }
+        if entry.0 >= limit {
+            return false;
+        }
+        entry.0 += 1;
+        true
+    }
+
+    /// Forgets expired sign-up windows; called from the periodic sweep.
+    pub fn prune(&self, now: Instant) {
+        self.signups
+            .retain(|_, (_, since)| now.duration_since(*since) < SIGNUP_WINDOW);
+    }
+}
+
+async fn persist(state: &AppState, store: &UserStore) -> anyhow::Result<()> {
+    let path = state.config_paths.resolve(FILE_NAME);
+    let snapshot = store.clone();
+    tokio::task::spawn_blocking(move || snapshot.save(&path))
+        .await
+        .context("user accounts persist task")?
+}
+
+fn bearer(headers: &HeaderMap) -> &str {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("")
+}
+
+/// Reported as missing (404) when `accounts.enabled` is off, like the admin API.
+fn disabled() -> Response {
+    (StatusCode::NOT_FOUND, "accounts disabled").into_response()
+}
+
+fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, "invalid account token").into_response()
+}
+
+fn persist_failed(e: anyhow::Error) -> Response {
+    tracing::error!(error = ?e, "failed to persist user accounts");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "failed to persist user accounts",
+    )
+        .into_response()
+}
+
+/// `POST /api/user`: creates an account and returns its token.
+pub async fn create(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Response {
+    let cfg = &state.cfg().accounts;
+    if !cfg.enabled {
+        return disabled();
+    }
+    let ip = addr.ip();
+    if !state
+        .users
+        .admit_signup(ip, cfg.signups_per_hour, Instant::now())
+    {
+        return (StatusCode::TOO_MANY_REQUESTS, "too many sign-ups").into_response();
+    }
+    let mut store = state.users.store.lock().await;
+    if store.len() >= cfg.max_accounts {
+        return (StatusCode::FORBIDDEN, "account limit reached").into_response();
+    }
+    let (account, token) = match store.create(&state.users.rng, chrono::Utc::now().timestamp()) {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::error!(error = ?e, "failed to create user account");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if let Err(e) = persist(&state, &store).await {
+        store.remove(&account.id);
+        return persist_failed(e);
+    }
+    tracing::info!(%ip, id = %account.id, "user account created");
+    (
+        StatusCode::CREATED,
+        Json(json!({ "token": token, "account": account.to_json() })),
+    )
+        .into_response()
+}
+
+/// `GET /api/user`
+pub async fn get(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if !state.cfg().accounts.enabled {
+        return disabled();
+    }
+    let store = state.users.store.lock().await;
+    let Some(id) = store.authenticate(bearer(&headers)) else {
+        return unauthorized();
+    };
+    match store.get(&id) {
+        Some(account) => Json(account.to_json()).into_response(),
+        None => unauthorized(),
+    }
+}
+
+/// `PUT /api/user`: replaces the fields present in the body.
+pub async fn update(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<AccountUpdate>,
+) -> Response {
+    if !state.cfg().accounts.enabled {
+        return disabled();
+    }
+    let mut store = state.users.store.lock().await;
+    let Some(id) = store.authenticate(bearer(&headers)) else {
+        return unauthorized();
+    };
+    let previous = store.clone();
+    if let Err(reason) = store.update(&id, body, chrono::Utc::now().timestamp()) {
+        return (StatusCode::BAD_REQUEST, reason).into_response();
+    }
+    if let Err(e) = persist(&state, &store).await {
+        *store = previous;
+        return persist_failed(e);
+    }
+    match store.get(&id) {
+        Some(account) => Json(account.to_json()).into_response(),
+        None => unauthorized(),
+    }
+}
+
+/// `DELETE /api/user`
+pub async fn remove(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if !state.cfg().accounts.enabled {
+        return disabled();
+    }
+    let mut store = state.users.store.lock().await;
+    let Some(id) = store.authenticate(bearer(&headers)) else {
+        return unauthorized();
+    };
+    let Some(removed) = store.remove(&id) else {
+        return unauthorized();
+    };
+    if let Err(e) = persist(&state, &store).await {
+        store.accounts.insert(id, removed);
+        return persist_failed(e);
+    }
+    tracing::info!(id = %id, "user account deleted");
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// `POST /api/user/token`: issues a new token; the old one stops working everywhere.
+pub async fn rotate_token(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if !state.cfg().accounts.enabled {
+        return disabled();
+    }
+    let mut store = state.users.store.lock().await;
+    let Some(id) = store.authenticate(bearer(&headers)) else {
+        return unauthorized();
+    };
+    let previous = store.clone();
+    let token = match store.rotate_token(&id, &state.users.rng, chrono::Utc::now().timestamp()) {
+        Ok(Some(token)) => token,
+        Ok(None) => return unauthorized(),
+        Err(e) => {
+            tracing::error!(error = ?e, "failed to rotate user account token");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if let Err(e) = persist(&state, &store).await {
+        *store = previous;
+        return persist_failed(e);
+    }
+    Json(json!({ "token": token })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(frequency: i64, name: &str) -> Bookmark {
+        Bookmark {
+            frequency,
+            name: name.to_string(),
+            mode: Some(" usb ".to_string()),
+            receiver_id: None,
+        }
+    }
+
+    #[test]
+    fn tokens_authenticate_their_account_until_rotated() {
+        let rng = ring::rand::SystemRandom::new();
+        let mut store = UserStore::default();
+        let (a, token_a) = store.create(&rng, 100).unwrap();
+        let (b, token_b) = store.create(&rng, 100).unwrap();
+        assert_eq!(store.authenticate(&token_a), Some(a.id.clone()));
+        assert_eq!(store.authenticate(&token_b), Some(b.id.clone()));
+
+        let forged = format!("{}.{}", a.id, token_b.split_once('.').unwrap().1);
+        assert_eq!(store.authenticate(&forged), None);
+        assert_eq!(store.authenticate(""), None);
+        assert_eq!(store.authenticate(&a.id), None);
+
+        let rotated = store.rotate_token(&a.id, &rng, 200).unwrap().unwrap();
+        assert_eq!(store.authenticate(&token_a), None);
+        assert_eq!(store.authenticate(&rotated), Some(a.id));
+    }
+
+    #[test]
+    fn update_validates_and_replaces_present_fields() {
+        let rng = ring::rand::SystemRandom::new();
+        let mut store = UserStore::default();
+        let (account, _) = store.create(&rng, 100).unwrap();
+        let id = account.id.as_str();
+
+        let mut preferences = serde_json::Map::new();
+        preferences.insert("theme".to_string(), json!("dark"));
+        let update = AccountUpdate {
+            preferences: Some(preferences),
+            bookmarks: Some(vec![bookmark(7_074_000, " FT8 ")]),
+            chat: None,
+        };
+        store.update(id, update, 150).unwrap();
+        let stored = store.get(id).unwrap();
+        assert_eq!(stored.preferences["theme"], json!("dark"));
+        assert_eq!(stored.bookmarks[0].name, "FT8");
+        assert_eq!(stored.bookmarks[0].mode.as_deref(), Some("USB"));
+        assert_eq!(stored.updated_at, 150);
+
+        let chat_only = AccountUpdate {
+            chat: Some(ChatIdentity {
+                username: "op".to_string(),
+                user_id: "u1".to_string(),
+            }),
+            ..AccountUpdate::default()
+        };
+        store.update(id, chat_only, 160).unwrap();
+        assert_eq!(store.get(id).unwrap().bookmarks.len(), 1);
+        assert_eq!(store.get(id).unwrap().chat.username, "op");
+
+        let bad = AccountUpdate {
+            bookmarks: Some(vec![bookmark(7_074_000, "ok"), bookmark(0, "bad")]),
+            ..AccountUpdate::default()
+        };
+        assert!(store.update(id, bad, 170).is_err());
+        assert_eq!(store.get(id).unwrap().updated_at, 160);
+
+        let mut huge = serde_json::Map::new();
+        huge.insert("x".to_string(), json!("a".repeat(MAX_PREFERENCES_BYTES)));
+        let too_large = AccountUpdate {
+            preferences: Some(huge),
+            ..AccountUpdate::default()
+        };
+        assert_eq!(
+            store.update(id, too_large, 180),
+            Err("preferences too large")
+        );
+    }
+
+    #[test]
+    fn store_round_trips_through_file() {
+        let rng = ring::rand::SystemRandom::new();
+        let mut store = UserStore::default();
+        let (_, token) = store.create(&rng, 100).unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "novasdr_users_{}.json",
+            novasdr_core::util::generate_unique_id()
+        ));
+        store.save(&path).unwrap();
+        let loaded = UserStore::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded, store);
+        assert!(loaded.authenticate(&token).is_some());
+    }
+
+    #[test]
+    fn signups_are_limited_per_ip_and_window() {
+        let users = UserAccounts::new(UserStore::default());
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let t0 = Instant::now();
+        assert!(users.admit_signup(ip, 2, t0));
+        assert!(users.admit_signup(ip, 2, t0));
+        assert!(!users.admit_signup(ip, 2, t0));
+        assert!(users.admit_signup(ip, 2, t0 + SIGNUP_WINDOW));
+        users.prune(t0 + SIGNUP_WINDOW * 2);
+        assert!(users.signups.is_empty());
+    }
+}
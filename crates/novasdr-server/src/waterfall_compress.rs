@@ -0,0 +1,238 @@
+//! Shared waterfall compression: packets are compressed on a small pool of worker threads, off
+//! the clients' send tasks, each as a self-contained zstd frame or LZ4 block. Since such a packet
+//! does not depend on what the connection sent before, clients with the same view of the same
+//! live frame share one compressed buffer instead of each compressing it again.
+
+use crate::waterfall_dictionary::WaterfallDictionary;
+use novasdr_core::codec::{lz4_block, zstd_stream::ZstdStreamEncoder};
+use std::collections::hash_map::{Entry, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use tokio::sync::{oneshot, OnceCell};
+
+const ZSTD_LEVEL: i32 = 3;
+/// Worker threads at most; a waterfall row compresses in microseconds, so a few cover hundreds
+/// of clients.
+const MAX_WORKERS: usize = 4;
+/// Frames behind the newest one whose packets are kept for clients lagging behind.
+const KEEP_FRAMES: u64 = 32;
+
+static COMPRESSED: AtomicU64 = AtomicU64::new(0);
+static SHARED: AtomicU64 = AtomicU64::new(0);
+
+/// Live packets compressed, and live packets sent from another client's compression, since start.
+pub fn counts() -> (u64, u64) {
+    (
+        COMPRESSED.load(Ordering::Relaxed),
+        SHARED.load(Ordering::Relaxed),
+    )
+}
+
+#[derive(Clone)]
+pub enum Codec {
+    Zstd,
+    ZstdDict(Arc<WaterfallDictionary>),
+    Lz4,
+}
+
+impl Codec {
+    /// Identifies the codec in cache keys; dictionaries by their ID.
+    fn key(&self) -> (u8, u32) {
+        match self {
+            Self::Zstd => (0, 0),
+            Self::ZstdDict(dictionary) => (1, dictionary.id),
+            Self::Lz4 => (2, 0),
+        }
+    }
+
+    /// Compresses with the worker's encoder for this codec, created on first use.
+    fn compress(
+        &self,
+        encoders: &mut HashMap<(u8, u32), ZstdStreamEncoder>,
+        input: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        if let Self::Lz4 = self {
+            return Ok(lz4_block::compress_packet(input));
+        }
+        let encoder = match encoders.entry(self.key()) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(match self {
+                Self::ZstdDict(dictionary) => {
+                    ZstdStreamEncoder::with_dictionary(ZSTD_LEVEL, &dictionary.bytes)?
+                }
+                _ => ZstdStreamEncoder::new(ZSTD_LEVEL)?,
+            }),
+        };
+        encoder.compress_frame(input)
+    }
+}
+
+struct Job {
+    codec: Codec,
+    input: Vec<u8>,
+    reply: oneshot::Sender<anyhow::Result<Vec<u8>>>,
+}
+
+/// The workers' queue, started on first use; `None` if no worker thread could be started.
+fn pool() -> Option<&'static mpsc::Sender<Job>> {
+    static POOL: OnceLock<Option<mpsc::Sender<Job>>> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        let wanted = std::thread::available_parallelism()
+            .map_or(1, |n| n.get() / 2)
+            .clamp(1, MAX_WORKERS);
+        let mut started = 0;
+        for i in 0..wanted {
+            let rx = rx.clone();
+            match std::thread::Builder::new()
+                .name(format!("novasdr-waterfall-zstd-{i}"))
+                .spawn(move || worker(&rx))
+            {
+                Ok(_) => started += 1,
+                Err(e) => tracing::error!(error = %e, "spawn waterfall compression worker"),
+            }
+        }
+        (started > 0).then_some(tx)
+    })
+    .as_ref()
+}
+
+fn worker(jobs: &Mutex<mpsc::Receiver<Job>>) {
+    let mut encoders = HashMap::new();
+    loop {
+        let job = match jobs.lock() {
+            Ok(g) => g.recv(),
+            Err(poisoned) => {
+                tracing::error!("waterfall compression queue mutex poisoned; recovering");
+                poisoned.into_inner().recv()
+            }
+        };
+        let Ok(job) = job else {
+            return;
+        };
+        let _ = job
+            .reply
+            .send(job.codec.compress(&mut encoders, &job.input));
+    }
+}
+
+/// Compresses one packet on the worker pool.
+pub async fn compress(codec: Codec, input: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    let Some(pool) = pool() else {
+        return codec.compress(&mut HashMap::new(), &input);
+    };
+    let (reply, result) = oneshot::channel();
+    pool.send(Job {
+        codec,
+        input,
+        reply,
+    })
+    .map_err(|_| anyhow::anyhow!("waterfall compression workers stopped"))?;
+    result
+        .await
+        .map_err(|_| anyhow::anyhow!("waterfall compression worker dropped the packet"))?
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PacketKey {
+    pub frame_num: u64,
+    pub window: u8,
+    pub level: usize,
+    pub l: usize,
+    pub r: usize,
+}
+
+type Slot = Arc<OnceCell<Arc<[u8]>>>;
+/// Codec and view of a cached packet.
+type SlotKey = ((u8, u32), PacketKey);
+
+/// One receiver's recently compressed live packets, keyed by codec and view.
+#[derive(Default)]
+pub struct PacketCache {
+    packets: Mutex<HashMap<SlotKey, Slot>>,
+}
+
+impl PacketCache {
+    /// The packet for `key`, compressed by the first client to ask while the others wait for
+    /// it. `packet` builds the uncompressed packet and only runs for that first client.
+    pub async fn get_or_compress(
+        &self,
+        codec: &Codec,
+        key: PacketKey,
+        packet: impl FnOnce() -> anyhow::Result<Vec<u8>>,
+    ) -> anyhow::Result<Arc<[u8]>> {
+        let slot = {
+            let mut packets = match self.packets.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!("waterfall packet cache mutex poisoned; recovering");
+                    poisoned.into_inner()
+                }
+            };
+            // Frame numbers only grow, across input restarts too.
+            packets.retain(|(_, k), _| k.frame_num + KEEP_FRAMES >= key.frame_num);
+            packets.entry((codec.key(), key)).or_default().clone()
+        };
+        let mut compressed = false;
+        let out = slot
+            .get_or_try_init(|| async {
+                compressed = true;
+                compress(codec.clone(), packet()?).await.map(Arc::from)
+            })
+            .await?;
+        if compressed {
+            COMPRESSED.fetch_add(1, Ordering::Relaxed);
+        } else {
+            SHARED.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(out.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn equal_views_share_one_compression() {
+        let cache = PacketCache::default();
+        let key = PacketKey {
+            frame_num: 7,
+            window: 0,
+            level: 1,
+            l: 0,
+            r: 512,
+        };
+        let mut built = 0;
+        let first = cache
+            .get_or_compress(&Codec::Zstd, key, || {
+                built += 1;
+                Ok(vec![5; 512])
+            })
+            .await
+            .unwrap();
+        let second = cache
+            .get_or_compress(&Codec::Zstd, key, || unreachable!())
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Another codec or a narrower view is its own packet.
+        cache
+            .get_or_compress(&Codec::Lz4, key, || {
+                built += 1;
+                Ok(vec![5; 512])
+            })
+            .await
+            .unwrap();
+        cache
+            .get_or_compress(&Codec::Zstd, PacketKey { r: 256, ..key }, || {
+                built += 1;
+                Ok(vec![5; 256])
+            })
+            .await
+            .unwrap();
+        assert_eq!(built, 3);
+    }
+}
@@ -0,0 +1,80 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use novasdr_core::codec::zstd_stream;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, OnceLock,
+};
+
+/// Packets collected before training (about 25 s of one client at 20 fps).
+const TRAINING_SAMPLES: usize = 500;
+const MAX_DICTIONARY_BYTES: usize = 16 * 1024;
+
+/// A trained waterfall dictionary, as sent to clients.
+pub struct WaterfallDictionary {
+    pub id: u32,
+    pub bytes: Vec<u8>,
+    pub base64: String,
+}
+
+/// Trains one receiver's zstd dictionary from its own uncompressed waterfall packets, on first
+/// demand. Clients asking for `zstd-dict` before it is ready get plain `zstd` and contribute
+/// their packets as samples.
+#[derive(Default)]
+pub struct DictionaryTrainer {
+    dictionary: OnceLock<Arc<WaterfallDictionary>>,
+    samples: Mutex<Vec<Vec<u8>>>,
+    training: AtomicBool,
+}
+
+impl DictionaryTrainer {
+    pub fn get(&self) -> Option<Arc<WaterfallDictionary>> {
+        self.dictionary.get().cloned()
+    }
+
+    /// Records one packet; the last sample needed starts training on the blocking pool.
+    pub fn offer(self: &Arc<Self>, receiver_id: &str, packet: &[u8]) {
+        if self.dictionary.get().is_some() || self.training.load(Ordering::Relaxed) {
+            return;
+        }
+        let samples = {
+            let mut samples = match self.samples.lock() {
+                Ok(g) => g,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            samples.push(packet.to_vec());
+            if samples.len() < TRAINING_SAMPLES || self.training.swap(true, Ordering::Relaxed) {
+                return;
+            }
+            std::mem::take(&mut *samples)
+        };
+        let trainer = self.clone();
+        let receiver_id = receiver_id.to_string();
+        tokio::task::spawn_blocking(move || {
+            match zstd_stream::train_dictionary(&samples, MAX_DICTIONARY_BYTES) {
+                Ok(bytes) => {
+                    let id = zstd_stream::dictionary_id(&bytes);
+                    tracing::info!(
+                        receiver_id = %receiver_id,
+                        dictionary_id = id,
+                        bytes = bytes.len(),
+                        "waterfall zstd dictionary trained"
+                    );
+                    let base64 = BASE64.encode(&bytes);
+                    let _ =
+                        trainer
+                            .dictionary
+                            .set(Arc::new(WaterfallDictionary { id, bytes, base64 }));
+                }
+                Err(e) => {
+                    // Collect a fresh set and try again.
+                    tracing::warn!(
+                        receiver_id = %receiver_id,
+                        error = ?e,
+                        "waterfall zstd dictionary training failed"
+                    );
+                }
+            }
+            trainer.training.store(false, Ordering::Relaxed);
+        });
+    }
+}
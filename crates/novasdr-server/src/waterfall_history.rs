@@ -0,0 +1,177 @@
+use novasdr_core::config::Runtime;
+use std::collections::VecDeque;
+
+/// Target spacing of history rows.
+const ROW_INTERVAL_MS: f64 = 1000.0;
+/// Rows kept (about 10 minutes at the target row rate).
+const HISTORY_ROWS: usize = 600;
+/// Most lines a single catch-up request is answered with.
+pub const MAX_CATCHUP_LINES: usize = 64;
+
+/// One averaged history row of the coarsest waterfall level (the whole band).
+#[derive(Debug, Clone)]
+pub struct HistoryRow {
+    /// Last frame that went into the row.
+    pub frame_num: u64,
+    pub data: Box<[i8]>,
+}
+
+struct Inner {
+    acc: Vec<i32>,
+    acc_frames: u64,
+    rows: VecDeque<HistoryRow>,
+}
+
+/// Recent waterfall of one receiver, kept at low time and frequency resolution so clients
+/// whose frames were dropped (a sleeping browser tab) can fill the gap, and for
+/// `/waterfall.png` (`crate::waterfall_png`).
+pub struct WaterfallHistory {
+    frames_per_row: u64,
+    /// Time one row spans, in milliseconds.
+    row_ms: f64,
+    inner: std::sync::Mutex<Inner>,
+}
+
+impl WaterfallHistory {
+    pub fn new(rt: &Runtime) -> Self {
+        let frame_ms =
+            (rt.fft_size / 2) as f64 * 1000.0 / rt.sps as f64 * rt.waterfall_frame_skip as f64;
+        let frames_per_row = ((ROW_INTERVAL_MS / frame_ms).round() as u64).max(1);
+        Self::with_frames_per_row(frames_per_row, frames_per_row as f64 * frame_ms)
+    }
+
+    fn with_frames_per_row(frames_per_row: u64, row_ms: f64) -> Self {
+        Self {
+            frames_per_row,
+            row_ms,
+            inner: std::sync::Mutex::new(Inner {
+                acc: Vec::new(),
+                acc_frames: 0,
+                rows: VecDeque::with_capacity(HISTORY_ROWS),
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        match self.inner.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("waterfall history mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    /// Adds one sent frame of the coarsest level.
+    pub fn push(&self, frame_num: u64, row: &[i8]) {
+        let mut inner = self.lock();
+        if inner.acc.len() != row.len() {
+            inner.acc = vec![0; row.len()];
+            inner.acc_frames = 0;
+        }
+        for (a, &v) in inner.acc.iter_mut().zip(row) {
+            *a += i32::from(v);
+        }
+        inner.acc_frames += 1;
+        if inner.acc_frames < self.frames_per_row {
+            return;
+        }
+        let data = average(&inner.acc, inner.acc_frames);
+        inner.acc.fill(0);
+        inner.acc_frames = 0;
+        if inner.rows.len() >= HISTORY_ROWS {
+            inner.rows.pop_front();
+        }
+        inner.rows.push_back(HistoryRow { frame_num, data });
+    }
+
+    /// The rows of the last `duration`, oldest first, and the time one row spans.
+    pub fn recent(&self, duration: std::time::Duration) -> (Vec<HistoryRow>, std::time::Duration) {
+        let wanted = (duration.as_secs_f64() * 1000.0 / self.row_ms)
+            .ceil()
+            .max(1.0) as usize;
+        let inner = self.lock();
+        let skip = inner.rows.len().saturating_sub(wanted);
+        let rows = inner.rows.iter().skip(skip).cloned().collect();
+        (
+            rows,
+            std::time::Duration::from_secs_f64(self.row_ms / 1000.0),
+        )
+    }
+
+    /// How far back the history reaches once full.
+    pub fn span(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f64(self.row_ms * HISTORY_ROWS as f64 / 1000.0)
+    }
+
+    /// Rows for frames strictly between `after` and `before`, averaged down to at most `lines`.
+    pub fn summary(&self, after: u64, before: u64, lines: usize) -> Vec<HistoryRow> {
+        let inner = self.lock();
+        let rows: Vec<&HistoryRow> = inner
+            .rows
+            .iter()
+            .filter(|r| r.frame_num > after && r.frame_num < before)
+            .collect();
+        let lines = lines.clamp(1, MAX_CATCHUP_LINES);
+        if rows.is_empty() {
+            return Vec::new();
+        }
+        let groups = rows.len().min(lines);
+        (0..groups)
+            .filter_map(|g| {
+                let group = &rows[g * rows.len() / groups..(g + 1) * rows.len() / groups];
+                let last = group.last()?;
+                let width = last.data.len();
+                let mut acc = vec![0i32; width];
+                let mut count = 0u64;
+                // A row of another width cannot be averaged in; `push` keeps them equal anyway.
+                for row in group.iter().filter(|r| r.data.len() == width) {
+                    for (a, &v) in acc.iter_mut().zip(row.data.iter()) {
+                        *a += i32::from(v);
+                    }
+                    count += 1;
+                }
+                Some(HistoryRow {
+                    frame_num: last.frame_num,
+                    data: average(&acc, count),
+                })
+            })
+            .collect()
+    }
+}
+
+fn average(acc: &[i32], frames: u64) -> Box<[i8]> {
+    acc.iter()
+        .map(|&a| (a as f32 / frames as f32).round() as i8)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_average_frames_and_summaries_compress_the_gap() {
+        let history = WaterfallHistory::with_frames_per_row(2, 100.0);
+        for frame in 1..=20u64 {
+            history.push(frame, &[frame as i8, -(frame as i8)]);
+        }
+        // Rows end at frames 2, 4, ..., 20 and hold the mean of their two frames.
+        let all = history.summary(0, u64::MAX, MAX_CATCHUP_LINES);
+        assert_eq!(all.len(), 10);
+        assert_eq!(all[0].frame_num, 2);
+        assert_eq!(&*all[0].data, &[2, -2]);
+
+        // Frames 5..=16 are missed: rows 6..=16 (six of them), squeezed into three lines.
+        let gap = history.summary(4, 17, 3);
+        let frames: Vec<u64> = gap.iter().map(|r| r.frame_num).collect();
+        assert_eq!(frames, vec![8, 12, 16]);
+        assert_eq!(&*gap[1].data, &[11, -11]);
+
+        // Rows span 100 ms: the last 250 ms are the last three rows.
+        let (recent, row) = history.recent(std::time::Duration::from_millis(250));
+        let frames: Vec<u64> = recent.iter().map(|r| r.frame_num).collect();
+        assert_eq!(frames, vec![16, 18, 20]);
+        assert_eq!(row, std::time::Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use novasdr_core::dsp::waterfall_mask::WaterfallMask;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Hex digits of generated mask ids.
+const MASK_ID_LEN: usize = 12;
+/// Enough to push any quantized level to the bottom of the scale.
+const MAX_ATTENUATION_DB: u8 = 128;
+
+/// One entry of `waterfall_masks.json`: a frequency range the waterfall shows dimmed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MaskEntry {
+    #[serde(default)]
+    pub id: String,
+    /// Receiver the mask applies to; empty for every receiver.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub receiver_id: String,
+    pub start_hz: i64,
+    pub end_hz: i64,
+    pub attenuation_db: u8,
+    /// Operator's note, e.g. what the interference is.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+}
+
+/// Entries of `raw` (`{ "masks": [...] }`); malformed ones are skipped.
+fn entries(raw: &serde_json::Value) -> Vec<MaskEntry> {
+    raw.get("masks")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| match serde_json::from_value::<MaskEntry>(v.clone()) {
+            Ok(e) => Some(e),
+            Err(e) => {
+                tracing::warn!(mask = %v, error = %e, "waterfall_masks.json: skipping entry");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Level-0 column ranges of the masks for `receiver_id`; `to_bin` maps Hz to a fractional bin.
+fn masks_for(
+    entries: &[MaskEntry],
+    receiver_id: &str,
+    len0: usize,
+    to_bin: impl Fn(i64) -> f64,
+) -> Vec<WaterfallMask> {
+    entries
+        .iter()
+        .filter(|e| e.receiver_id.is_empty() || e.receiver_id == receiver_id)
+        .filter_map(|e| {
+            let start = to_bin(e.start_hz).floor().clamp(0.0, len0 as f64) as usize;
+            let end = to_bin(e.end_hz).ceil().clamp(0.0, len0 as f64) as usize;
+            (end > start).then_some(WaterfallMask {
+                start,
+                end,
+                attenuation_db: e.attenuation_db,
+            })
+        })
+        .collect()
+}
+
+/// Hands every receiver its masks from `raw`.
+pub fn apply(state: &AppState, raw: &serde_json::Value) {
+    let entries = entries(raw);
+    for (id, receiver) in &state.receivers {
+        let masks = masks_for(&entries, id, receiver.rt.fft_result_size, |hz| {
+            receiver.frequency_bin(hz)
+        });
+        receiver.set_waterfall_masks(masks);
+    }
+}
+
+/// Inserts `mask`, or replaces the stored mask with the same `id`. Masks without an `id` get a
+/// generated one. Returns the new entries and the mask as stored.
+fn upsert_mask(
+    current: Vec<MaskEntry>,
+    mut mask: MaskEntry,
+) -> Result<(Vec<MaskEntry>, MaskEntry), &'static str> {
+    if mask.end_hz <= mask.start_hz {
+        return Err("mask needs end_hz > start_hz");
+    }
+    if mask.attenuation_db == 0 || mask.attenuation_db > MAX_ATTENUATION_DB {
+        return Err("mask needs attenuation_db between 1 and 128");
+    }
+    mask.id = mask.id.trim().to_string();
+    if mask.id.is_empty() {
+        mask.id = novasdr_core::util::generate_unique_id()[..MASK_ID_LEN].to_string();
+    }
+    let mut list = current;
+    match list.iter_mut().find(|m| m.id == mask.id) {
+        Some(existing) => *existing = mask.clone(),
+        None => list.push(mask.clone()),
+    }
+    Ok((list, mask))
+}
+
+/// Writes `list` to `waterfall_masks.json` and applies it. The caller holds the mask lock.
+async fn commit(
+    state: &AppState,
+    current: &mut serde_json::Value,
+    list: Vec<MaskEntry>,
+) -> Result<(), Response> {
+    let next = json!({ "masks": list });
+    let path = state.config_paths.overlays.join("waterfall_masks.json");
+    let value = next.clone();
+    let write =
+        tokio::task::spawn_blocking(move || novasdr_core::config::write_json_atomic(&path, &value))
+            .await;
+    match write {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            tracing::error!(error = ?e, "failed to persist waterfall_masks.json");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to persist waterfall_masks.json",
+            )
+                .into_response());
+        }
+        Err(e) => {
+            tracing::error!(error = ?e, "waterfall masks persist task failed");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    }
+    apply(state, &next);
+    *current = next;
+    Ok(())
+}
+
+/// `GET /api/waterfall_masks` (admin token).
+pub async fn list(State(state): State<Arc<AppState>>) -> Response {
+    let current = state.waterfall_masks.read().await;
+    Json(json!({ "masks": entries(&current) })).into_response()
+}
+
+/// `POST /api/waterfall_masks` (admin token): adds a mask or replaces the one with the same `id`.
+pub async fn upsert(State(state): State<Arc<AppState>>, Json(mask): Json<MaskEntry>) -> Response {
+    if !mask.receiver_id.is_empty() && !state.receivers.contains_key(&mask.receiver_id) {
+        return (StatusCode::BAD_REQUEST, "unknown receiver_id").into_response();
+    }
+    let mut current = state.waterfall_masks.write().await;
+    let (list, stored) = match upsert_mask(entries(&current), mask) {
+        Ok(v) => v,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+    let count = list.len();
+    if let Err(resp) = commit(&state, &mut current, list).await {
+        return resp;
+    }
+    tracing::info!(mask = ?stored, "admin: waterfall mask saved");
+    Json(json!({ "mask": stored, "count": count })).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaskSelector {
+    pub id: String,
+}
+
+/// `DELETE /api/waterfall_masks?id=..` (admin token).
+pub async fn remove(
+    State(state): State<Arc<AppState>>,
+    Query(selector): Query<MaskSelector>,
+) -> Response {
+    let mut current = state.waterfall_masks.write().await;
+    let mut list = entries(&current);
+    let before = list.len();
+    list.retain(|m| m.id != selector.id);
+    if list.len() == before {
+        return (StatusCode::NOT_FOUND, "no matching mask").into_response();
+    }
+    let count = list.len();
+    if let Err(resp) = commit(&state, &mut current, list).await {
+        return resp;
+    }
+    tracing::info!(id = %selector.id, "admin: waterfall mask removed");
+    Json(json!({ "removed": 1, "count": count })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(receiver_id: &str, start_hz: i64, end_hz: i64) -> MaskEntry {
+        MaskEntry {
+            id: String::new(),
+            receiver_id: receiver_id.to_string(),
+            start_hz,
+            end_hz,
+            attenuation_db: 40,
+            name: String::new(),
+        }
+    }
+
+    #[test]
+    fn masks_cover_their_range_on_matching_receivers() {
+        let entries = [
+            entry("", 1_050, 1_120),
+            entry("rx1", 1_000, 1_100),
+            entry("rx0", 500, 1_010),
+            entry("rx0", 5_000, 6_000),
+        ];
+        // 10 Hz bins from 1000 Hz, 100 bins.
+        let to_bin = |hz: i64| (hz - 1_000) as f64 / 10.0;
+        let masks = masks_for(&entries, "rx0", 100, to_bin);
+        let ranges: Vec<(usize, usize)> = masks.iter().map(|m| (m.start, m.end)).collect();
+        assert_eq!(ranges, vec![(5, 12), (0, 1)]);
+    }
+
+    #[test]
+    fn upsert_validates_and_replaces_by_id() {
+        let (list, stored) = upsert_mask(Vec::new(), entry("rx0", 100, 200)).unwrap();
+        assert_eq!(stored.id.len(), MASK_ID_LEN);
+        let moved = MaskEntry {
+            start_hz: 300,
+            end_hz: 400,
+            ..stored.clone()
+        };
+        let (list, _) = upsert_mask(list, moved).unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].start_hz, 300);
+
+        assert!(upsert_mask(Vec::new(), entry("rx0", 200, 200)).is_err());
+        let loud = MaskEntry {
+            attenuation_db: 0,
+            ..entry("rx0", 100, 200)
+        };
+        assert!(upsert_mask(Vec::new(), loud).is_err());
+    }
+}
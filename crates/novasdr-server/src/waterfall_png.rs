@@ -0,0 +1,375 @@
+//! `GET /waterfall.png`: the recent waterfall of a receiver rendered on the server, for bots,
+//! dashboards and SDR directory previews that do not run the web client. It draws from the
+//! receiver's waterfall history (`crate::waterfall_history`), one row a second over the whole
+//! band, so it costs nothing while nobody asks. Only a few pictures are drawn at once, and the
+//! last one of each view is kept until the history gains a row, so polling clients share it.
+
+use crate::capture::{DISPLAY_RANGE_DB, FLOOR_MARGIN_DB};
+use crate::colormap::Colormap;
+use crate::state::AppState;
+use crate::waterfall_history::HistoryRow;
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use novasdr_core::{codec::png, dsp::peaks::noise_floor_db};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+const DEFAULT_DURATION: Duration = Duration::from_secs(60);
+const DEFAULT_WIDTH: u32 = 1024;
+const DEFAULT_HEIGHT: u32 = 256;
+const MAX_WIDTH: u32 = 4096;
+const MAX_HEIGHT: u32 = 2048;
+/// History gains a row a second; a client polling faster gets the same picture.
+const CACHE_CONTROL: &str = "public, max-age=5";
+/// Pictures drawn at the same time; further requests wait for a turn instead of each taking a
+/// blocking thread.
+const MAX_CONCURRENT_RENDERS: usize = 2;
+/// Pictures kept for reuse, across receivers and views.
+const MAX_CACHED: usize = 64;
+
+static RENDERS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_RENDERS);
+
+/// The last picture of each view, while it still shows the newest history row.
+static CACHE: Mutex<Vec<(CacheKey, Bytes)>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WaterfallPngQuery {
+    /// The active receiver when absent.
+    #[serde(default, alias = "receiver_id")]
+    receiver: Option<String>,
+    /// Centre of the picture in Hz; the centre of the receiver when absent.
+    #[serde(default)]
+    frequency: Option<i64>,
+    /// Width of the picture in Hz (`200000`, `200k`, `2.4M`); the whole band when absent.
+    #[serde(default)]
+    span: Option<String>,
+    /// How far back the picture reaches (`30s`, `5m`, or seconds).
+    #[serde(default)]
+    duration: Option<String>,
+    /// The receiver's `input.defaults.colormap` when absent.
+    #[serde(default)]
+    colormap: Option<String>,
+    #[serde(default)]
+    width: Option<u32>,
+    #[serde(default)]
+    height: Option<u32>,
+}
+
+/// What to draw, resolved against the receiver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct View {
+    /// Bins of the history rows, `start..end`.
+    start: usize,
+    end: usize,
+    width: usize,
+    height: usize,
+    colormap: Colormap,
+}
+
+/// Everything a picture depends on: the view, and the history rows it is drawn from, which are
+/// the same while the newest row and the row count are.
+#[derive(Debug, Clone, PartialEq)]
+struct CacheKey {
+    receiver_id: String,
+    newest_frame: u64,
+    rows: usize,
+    view: View,
+}
+
+fn cache() -> MutexGuard<'static, Vec<(CacheKey, Bytes)>> {
+    match CACHE.lock() {
+        Ok(g) => g,
+        Err(poisoned) => {
+            tracing::error!("waterfall png cache mutex poisoned; recovering");
+            poisoned.into_inner()
+        }
+    }
+}
+
+fn cached(key: &CacheKey) -> Option<Bytes> {
+    cache()
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, image)| image.clone())
+}
+
+/// Keeps `image` for `key`, dropping the receiver's pictures of older rows.
+fn store(key: CacheKey, image: Bytes) {
+    let mut cache = cache();
+    cache.retain(|(k, _)| k.receiver_id != key.receiver_id || k.newest_frame == key.newest_frame);
+    if cache.len() >= MAX_CACHED {
+        cache.remove(0);
+    }
+    cache.push((key, image));
+}
+
+fn png_response(image: Bytes) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "image/png"),
+            (header::CACHE_CONTROL, CACHE_CONTROL),
+        ],
+        image,
+    )
+        .into_response()
+}
+
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WaterfallPngQuery>,
+) -> Response {
+    let receiver_id = query
+        .receiver
+        .clone()
+        .unwrap_or_else(|| state.active_receiver_id().to_string());
+    let Some(receiver) = state.receiver_state(&receiver_id).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let duration = match query.duration.as_deref() {
+        None => DEFAULT_DURATION,
+        Some(raw) => match parse_duration(raw) {
+            Some(d) if !d.is_zero() => d.min(receiver.waterfall_history.span()),
+            _ => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    "duration must look like 30s, 5m or 90",
+                )
+                    .into_response()
+            }
+        },
+    };
+    let colormap = match query.colormap.as_deref() {
+        Some(raw) => match Colormap::parse(raw) {
+            Some(c) => c,
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    "colormap must be default, gray, viridis, inferno or turbo",
+                )
+                    .into_response()
+            }
+        },
+        None => receiver
+            .settings()
+            .defaults
+            .colormap
+            .as_deref()
+            .and_then(Colormap::parse)
+            .unwrap_or_default(),
+    };
+
+    let (rows, _) = receiver.waterfall_history.recent(duration);
+    let Some((bins, newest_frame)) = rows.last().map(|r| (r.data.len(), r.frame_num)) else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "no waterfall yet").into_response();
+    };
+    let basefreq = receiver.basefreq();
+    let bandwidth = receiver.rt.total_bandwidth;
+    let span = match query.span.as_deref() {
+        None => bandwidth,
+        Some(raw) => match parse_hz(raw) {
+            Some(span) if span > 0 => span.min(bandwidth),
+            _ => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    "span must be a width in Hz, such as 200k",
+                )
+                    .into_response()
+            }
+        },
+    };
+    let centre = query.frequency.unwrap_or(basefreq + bandwidth / 2);
+    if !receiver.covers(centre) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "frequency outside the receiver's range",
+        )
+            .into_response();
+    }
+    // The window slides inside the band rather than running off its edge.
+    let low = (centre - span / 2).clamp(basefreq, basefreq + bandwidth - span);
+    let bin_hz = bandwidth as f64 / bins as f64;
+    let start = (((low - basefreq) as f64 / bin_hz).floor() as usize).min(bins - 1);
+    let end = (((low + span - basefreq) as f64 / bin_hz).ceil() as usize).clamp(start + 1, bins);
+    let view = View {
+        start,
+        end,
+        width: query
+            .width
+            .unwrap_or(DEFAULT_WIDTH.min((end - start) as u32))
+            .clamp(1, MAX_WIDTH) as usize,
+        height: query.height.unwrap_or(DEFAULT_HEIGHT).clamp(1, MAX_HEIGHT) as usize,
+        colormap,
+    };
+
+    let key = CacheKey {
+        receiver_id: receiver_id.clone(),
+        newest_frame,
+        rows: rows.len(),
+        view,
+    };
+    if let Some(image) = cached(&key) {
+        return png_response(image);
+    }
+    // The semaphore is never closed.
+    let Ok(_turn) = RENDERS.acquire().await else {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    };
+    // Drawn by a request that was waiting ahead of this one.
+    if let Some(image) = cached(&key) {
+        return png_response(image);
+    }
+
+    let image = tokio::task::spawn_blocking(move || render(&rows, &view)).await;
+    match image {
+        Ok(Ok(image)) => {
+            let image = Bytes::from(image);
+            store(key, image.clone());
+            png_response(image)
+        }
+        Ok(Err(e)) => {
+            tracing::warn!(receiver_id = %receiver_id, error = ?e, "waterfall png failed");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(e) => {
+            tracing::warn!(receiver_id = %receiver_id, error = ?e, "waterfall png task failed");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn render(rows: &[HistoryRow], view: &View) -> anyhow::Result<Vec<u8>> {
+    png::encode_rgb(view.width as u32, view.height as u32, &draw(rows, view))
+}
+
+/// RGB pixels of `rows` (oldest first), newest at the top like the live waterfall. Columns
+/// keep the strongest bin they cover, so narrow carriers survive the downscaling; the colour
+/// scale starts just below the picture's noise floor.
+fn draw(rows: &[HistoryRow], view: &View) -> Vec<u8> {
+    let bins = view.end - view.start;
+    let mut pixels = Vec::with_capacity(view.width * rows.len());
+    for row in rows.iter() {
+        for x in 0..view.width {
+            let from = view.start + x * bins / view.width;
+            let to = (view.start + (x + 1) * bins / view.width).max(from + 1);
+            let level = row
+                .data
+                .get(from..to.min(row.data.len()))
+                .and_then(|cell| cell.iter().max())
+                .copied()
+                .unwrap_or(i8::MIN);
+            pixels.push(f32::from(level));
+        }
+    }
+    let low = noise_floor_db(&pixels) - FLOOR_MARGIN_DB;
+
+    let mut rgb = Vec::with_capacity(view.width * view.height * 3);
+    for y in 0..view.height {
+        let row = rows.len() - 1 - y * rows.len() / view.height;
+        for &db in pixels[row * view.width..(row + 1) * view.width].iter() {
+            rgb.extend_from_slice(&view.colormap.rgb((db - low) / DISPLAY_RANGE_DB));
+        }
+    }
+    rgb
+}
+
+/// `30s`, `5m`, `1h` or plain seconds.
+fn parse_duration(raw: &str) -> Option<Duration> {
+    let raw = raw.trim();
+    let (number, scale) = match raw.char_indices().last()? {
+        (i, 's') => (&raw[..i], 1.0),
+        (i, 'm') => (&raw[..i], 60.0),
+        (i, 'h') => (&raw[..i], 3600.0),
+        _ => (raw, 1.0),
+    };
+    let seconds = number.trim().parse::<f64>().ok()? * scale;
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// `200000`, `200k`, `2.4M` in Hz.
+fn parse_hz(raw: &str) -> Option<i64> {
+    let raw = raw.trim();
+    let (number, scale) = match raw.char_indices().last()? {
+        (i, 'k' | 'K') => (&raw[..i], 1e3),
+        (i, 'M') => (&raw[..i], 1e6),
+        (i, 'G') => (&raw[..i], 1e9),
+        _ => (raw, 1.0),
+    };
+    let hz = number.trim().parse::<f64>().ok()? * scale;
+    hz.is_finite().then_some(hz.round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_values_parse_with_units() {
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_hz("200k"), Some(200_000));
+        assert_eq!(parse_hz("2.4M"), Some(2_400_000));
+        assert_eq!(parse_hz("12500"), Some(12_500));
+        assert_eq!(parse_hz("wide"), None);
+    }
+
+    #[test]
+    fn newest_row_is_drawn_on_top_and_carriers_survive_downscaling() {
+        let row = |level: i8, carrier: usize| HistoryRow {
+            frame_num: 0,
+            data: (0..8)
+                .map(|bin| if bin == carrier { 100 } else { level })
+                .collect(),
+        };
+        // Old rows quiet, the newest with a carrier in bin 5.
+        let rows = [row(0, 99), row(0, 99), row(0, 5)];
+        let view = View {
+            start: 0,
+            end: 8,
+            width: 4,
+            height: 3,
+            colormap: Colormap::Gray,
+        };
+        let image = render(&rows, &view).unwrap();
+        assert_eq!(image[..4], [0x89, b'P', b'N', b'G']);
+
+        // Floor 0 dB, so the quiet rows sit 5 dB into the 50 dB scale: 10% grey.
+        let rgb = draw(&rows, &view);
+        let grey: Vec<u8> = rgb.chunks_exact(3).map(|p| p[0]).collect();
+        assert_eq!(grey, [26, 26, 255, 26, 26, 26, 26, 26, 26, 26, 26, 26]);
+    }
+
+    #[test]
+    fn cached_pictures_last_until_the_history_gains_a_row() {
+        let receiver_id = format!("rx-{}", novasdr_core::util::generate_unique_id());
+        let key = |newest_frame: u64, width: usize| CacheKey {
+            receiver_id: receiver_id.clone(),
+            newest_frame,
+            rows: 60,
+            view: View {
+                start: 0,
+                end: 8,
+                width,
+                height: 3,
+                colormap: Colormap::Gray,
+            },
+        };
+        store(key(10, 4), Bytes::from_static(b"narrow"));
+        store(key(10, 8), Bytes::from_static(b"wide"));
+        assert_eq!(cached(&key(10, 4)), Some(Bytes::from_static(b"narrow")));
+        assert_eq!(cached(&key(10, 8)), Some(Bytes::from_static(b"wide")));
+
+        // A picture of the next row replaces every one of the older row.
+        store(key(20, 4), Bytes::from_static(b"newer"));
+        assert_eq!(cached(&key(20, 4)), Some(Bytes::from_static(b"newer")));
+        assert_eq!(cached(&key(10, 4)), None);
+        assert_eq!(cached(&key(10, 8)), None);
+    }
+}
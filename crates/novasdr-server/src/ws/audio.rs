@@ -1,31 +1,53 @@
-use crate::state::{AgcSpeed, AppState, AudioClient, AudioParams};
+use super::audio_pool::{self, PipelineShape};
+use super::scanner::{ScanPlan, Scanner};
+use super::time_shift::{self, TimeShift};
+use crate::audio_listener;
+use crate::buffer_pool;
+use crate::digital_voice::DigitalVoice;
+use crate::state::{
+    AgcSpeed, AppState, AudioClient, AudioParams, FilterSharpness, FskParams, SquelchMode, TestTone,
+};
 use axum::{
     extract::connect_info::ConnectInfo,
-    extract::{ws, State, WebSocketUpgrade},
-    http::StatusCode,
+    extract::{ws, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
 use futures::{SinkExt, StreamExt};
 use interop::opus;
 use novasdr_core::{
+    codec::ima_adpcm,
     config::AudioCompression,
     dsp::{
         agc::Agc,
+        channelizer::ChannelTuner,
         dc_blocker::DcBlocker,
         demod::{
             add_complex, add_f32, am_envelope, float_to_i16_centered, negate_complex, negate_f32,
             polar_discriminator_fm, sam_demod, DemodulationMode,
         },
+        fm_level::{FmLeveler, NFM_DEVIATION_HZ, WBFM_DEVIATION_HZ},
+        hearing::{FrequencyLowering, HearingProfile},
+        passband::PassbandFilter,
+        subtone::{Subtone, SubtoneDetector},
+        volume::{LoudnessVolume, MAX_VOLUME_DB, MIN_VOLUME_DB},
+        wbfm::WbfmDemodulator,
     },
+    protocol::DigitalVoiceCall,
+    rtty, sstv,
     util::generate_unique_id,
 };
 use num_complex::Complex32;
 use realfft::{ComplexToReal, RealFftPlanner};
 use rustfft::{Fft as RustFft, FftPlanner};
 use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
 use std::{mem, net::SocketAddr};
+use tokio::sync::oneshot;
+use tokio::time::Instant;
 
 fn with_audio_unique_id(basic_info: String, unique_id: &str) -> String {
     let Ok(mut v) = serde_json::from_str::<serde_json::Value>(&basic_info) else {
@@ -118,6 +140,8 @@ const AUDIO_FRAME_MAGIC: [u8; 4] = *b"NSDA";
 const AUDIO_FRAME_END_MARK: u16 = 0xaabb;
 const AUDIO_FRAME_VERSION: u8 = 2;
 const AUDIO_FRAME_HEADER_LEN: usize = 40;
+/// Header flag: payloads carry two channels (left, right).
+const AUDIO_FRAME_FLAG_STEREO: u16 = 1;
 
 #[derive(Clone, Copy, Debug)]
 #[repr(u8)]
@@ -126,8 +150,24 @@ enum AudioWireCodec {
     Opus = 2,
 }
 
-fn build_audio_frame_multi(
+#[derive(Clone, Copy, Debug)]
+struct AudioWireFormat {
     codec: AudioWireCodec,
+    stereo: bool,
+}
+
+impl AudioWireFormat {
+    fn flags(self) -> u16 {
+        if self.stereo {
+            AUDIO_FRAME_FLAG_STEREO
+        } else {
+            0
+        }
+    }
+}
+
+fn build_audio_frame_multi(
+    format: AudioWireFormat,
     frame_num: u64,
     l: i32,
     m: f64,
@@ -141,8 +181,8 @@ fn build_audio_frame_multi(
     let mut out = Vec::with_capacity(expected_capacity);
     out.extend_from_slice(&AUDIO_FRAME_MAGIC);
     out.push(AUDIO_FRAME_VERSION);
-    out.push(codec as u8);
-    out.extend_from_slice(&0u16.to_le_bytes());
+    out.push(format.codec as u8);
+    out.extend_from_slice(&format.flags().to_le_bytes());
     out.extend_from_slice(&frame_num.to_le_bytes());
     out.extend_from_slice(&l.to_le_bytes());
     out.extend_from_slice(&m.to_le_bytes());
@@ -158,95 +198,18 @@ fn build_audio_frame_multi(
     out
 }
 
-mod ima_adpcm {
-    const INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
-
-    const STEP_TABLE: [i32; 89] = [
-        7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60,
-        66, 73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371,
-        408, 449, 494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878,
-        2066, 2272, 2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845,
-        8630, 9493, 10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086,
-        29794, 32767,
-    ];
-
-    pub fn encode_block_i16_mono(samples: &[i16]) -> Vec<u8> {
-        if samples.is_empty() {
-            return Vec::new();
-        }
-
-        let mut predictor = samples[0] as i32;
-        let mut index = if samples.len() >= 2 {
-            let diff = (samples[1] as i32 - samples[0] as i32).abs();
-            let mut best = 0usize;
-            for (i, &step) in STEP_TABLE.iter().enumerate() {
-                if step >= diff {
-                    best = i;
-                    break;
-                }
-                best = i;
-            }
-            best as i32
-        } else {
-            0i32
-        };
-
-        let codes = samples.len().saturating_sub(1);
-        let mut out = Vec::with_capacity(6 + codes.div_ceil(2));
-        out.extend_from_slice(&(samples[0]).to_le_bytes());
-        out.push(index as u8);
-        out.push(0);
-        out.extend_from_slice(&(samples.len() as u16).to_le_bytes());
-
-        let mut pending: Option<u8> = None;
-
-        for &sample in &samples[1..] {
-            let step = STEP_TABLE[index as usize];
-            let diff = (sample as i32) - predictor;
-            let sign = if diff < 0 { 8 } else { 0 };
-            let mut delta = diff.abs();
-
-            let mut code = 0i32;
-            let mut vpdiff = step >> 3;
-            if delta >= step {
-                code |= 4;
-                delta -= step;
-                vpdiff += step;
-            }
-            if delta >= (step >> 1) {
-                code |= 2;
-                delta -= step >> 1;
-                vpdiff += step >> 1;
-            }
-            if delta >= (step >> 2) {
-                code |= 1;
-                vpdiff += step >> 2;
-            }
-
-            if sign != 0 {
-                predictor -= vpdiff;
-            } else {
-                predictor += vpdiff;
-            }
-            predictor = predictor.clamp(i16::MIN as i32, i16::MAX as i32);
-
-            code |= sign;
-            index += INDEX_TABLE[code as usize];
-            index = index.clamp(0, (STEP_TABLE.len() - 1) as i32);
-
-            let nibble = (code as u8) & 0x0f;
-            match pending.take() {
-                Some(low) => out.push(low | (nibble << 4)),
-                None => pending = Some(nibble),
-            }
-        }
-
-        if let Some(low) = pending {
-            out.push(low);
-        }
+/// Frames the level squelch must stay below its closing threshold before it closes, so short
+/// flutter dips do not chop the audio.
+const LEVEL_SQUELCH_CLOSE_FRAMES: u8 = 3;
 
-        out
+/// Passband power over the noise floor in dB; `floor` is the mean power per bin around it.
+fn passband_snr_db(bins: &[Complex32], floor: Option<f32>) -> Option<f32> {
+    let floor = floor.filter(|f| f.is_finite() && *f > 0.0)?;
+    if bins.is_empty() {
+        return None;
     }
+    let mean = bins.iter().map(|c| c.norm_sqr()).sum::<f32>() / bins.len() as f32;
+    Some(10.0 * (mean.max(f32::MIN_POSITIVE) / floor).log10())
 }
 
 #[derive(Debug, Clone)]
@@ -279,7 +242,8 @@ impl SquelchState {
         self.close_hits = 0;
     }
 
-    fn update(&mut self, enabled: bool, features: SquelchFeatures) -> bool {
+    /// Handles switching the squelch on (closed until a signal shows) and off (open).
+    fn track_enabled(&mut self, enabled: bool) -> bool {
         if enabled && !self.was_enabled {
             self.reset_closed();
         }
@@ -287,7 +251,41 @@ impl SquelchState {
             self.reset_open();
         }
         self.was_enabled = enabled;
-        if !enabled {
+        enabled
+    }
+
+    /// Level squelch: `snr_db` is the passband over the noise floor, `None` when there is no
+    /// floor to compare with (the squelch then stays open).
+    fn update_level(
+        &mut self,
+        enabled: bool,
+        snr_db: Option<f32>,
+        open_db: f32,
+        hysteresis_db: f32,
+    ) -> bool {
+        if !self.track_enabled(enabled) {
+            return true;
+        }
+        let Some(snr_db) = snr_db else {
+            self.reset_open();
+            return true;
+        };
+        if snr_db >= open_db {
+            self.open = true;
+            self.close_hits = 0;
+        } else if self.open && snr_db < open_db - hysteresis_db {
+            self.close_hits = self.close_hits.saturating_add(1);
+            if self.close_hits >= LEVEL_SQUELCH_CLOSE_FRAMES {
+                self.reset_closed();
+            }
+        } else {
+            self.close_hits = 0;
+        }
+        self.open
+    }
+
+    fn update(&mut self, enabled: bool, features: SquelchFeatures) -> bool {
+        if !self.track_enabled(enabled) {
             return true;
         }
 
@@ -346,44 +344,90 @@ impl SquelchState {
 
 pub async fn upgrade(
     ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(marker_support): Query<crate::markers::MarkerSupport>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<Arc<AppState>>,
 ) -> axum::response::Response {
-    let Some(ip_guard) = state.try_acquire_ws_ip(addr.ip()) else {
-        return (
-            StatusCode::TOO_MANY_REQUESTS,
-            "too many connections from this IP",
-        )
-            .into_response();
+    let Some(protocol) = super::subprotocol::negotiate(&headers, "audio") else {
+        return (StatusCode::BAD_REQUEST, "unsupported websocket subprotocol").into_response();
+    };
+    let ip_guard = match state.try_acquire_ws_ip(addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => return rejection.into_response(),
     };
-    if state.total_audio_clients() >= state.cfg.limits.audio {
+    if state.total_audio_clients() >= state.cfg().limits.audio {
         return (StatusCode::TOO_MANY_REQUESTS, "too many audio clients").into_response();
     }
-    ws.on_upgrade(|socket| handle(socket, state, ip_guard))
+    let receiver = state.active_receiver_state();
+    if receiver.audio_full() {
+        let message_json = receiver.full_message("audio");
+        return ws
+            .protocols([protocol.as_str()])
+            .on_upgrade(move |socket| super::refuse(socket, message_json));
+    }
+    let quota = match crate::quota::acquire_audio(&state, addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => {
+            return (StatusCode::TOO_MANY_REQUESTS, rejection.message()).into_response();
+        }
+    };
+    let markers_api = marker_support.uses_api();
+    ws.protocols([protocol.as_str()]).on_upgrade(move |socket| {
+        handle(
+            socket,
+            state,
+            addr.ip(),
+            ip_guard,
+            quota,
+            protocol,
+            markers_api,
+        )
+    })
 }
 
 enum AudioOutbound {
-    Switch { settings_json: String },
+    Switch {
+        settings_json: String,
+    },
+    /// Sends a final text message and closes the socket.
+    Close {
+        message_json: String,
+    },
+    TimeShift(time_shift::Command),
 }
 
-async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::state::WsIpGuard) {
+/// Pending text messages per audio client; RDS updates arrive a few times per second at most.
+const AUDIO_TEXT_QUEUE: usize = 8;
+/// `buffer` command value that selects DX (low-latency) mode.
+const LOW_LATENCY_BUFFER: &str = "dx";
+/// Longest text command accepted; a `scan` list is the largest.
+const MAX_COMMAND_LEN: usize = 16 * 1024;
+
+async fn handle(
+    socket: ws::WebSocket,
+    state: Arc<AppState>,
+    ip: std::net::IpAddr,
+    _ip_guard: crate::state::WsIpGuard,
+    mut quota: crate::quota::AudioQuotaGuard,
+    protocol: super::subprotocol::ProtocolVersion,
+    markers_api: bool,
+) {
     let client_id = state.alloc_client_id();
-    tracing::info!(client_id, "audio ws connected");
+    tracing::info!(client_id, %protocol, "audio ws connected");
 
     let mut receiver_id = state.active_receiver_id().to_string();
     let mut receiver = state.active_receiver_state().clone();
 
-    let audio_fft_size = receiver.rt.audio_max_fft_size;
-    let sample_rate = receiver.rt.audio_max_sps as usize;
-    let compression = receiver.receiver.input.audio_compression;
-    let pipeline = match AudioPipeline::new(sample_rate, audio_fft_size, compression) {
+    let mut shape = PipelineShape::of(&receiver);
+    let pipeline = match audio_pool::acquire(&state, shape).await {
         Ok(p) => p,
         Err(e) => {
             tracing::warn!(
                 client_id,
                 receiver_id = %receiver_id,
-                sample_rate,
-                audio_fft_size,
+                sample_rate = shape.sample_rate,
+                audio_fft_size = shape.audio_fft_size,
                 error = ?e,
                 "audio pipeline init failed"
             );
@@ -391,51 +435,75 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
         }
     };
 
-    let (tx, mut audio_rx) = crate::state::audio_channel();
     let (out_tx, mut out_rx) = tokio::sync::mpsc::channel::<AudioOutbound>(8);
-
     let unique_id = generate_unique_id();
-    let params = AudioParams {
-        l: receiver.rt.default_l,
-        m: receiver.rt.default_m,
-        r: receiver.rt.default_r,
-        mute: false,
-        squelch_enabled: receiver.receiver.input.defaults.squelch_enabled,
-        demodulation: DemodulationMode::from_str_upper(receiver.rt.default_mode_str.as_str())
-            .unwrap_or(DemodulationMode::Usb),
-        agc_speed: AgcSpeed::Default,
-        agc_attack_ms: None,
-        agc_release_ms: None,
-    };
-    let client = Arc::new(AudioClient {
-        unique_id: unique_id.clone(),
-        tx,
-        params: std::sync::Mutex::new(params),
-        pipeline: std::sync::Mutex::new(pipeline),
-    });
+    let (client, queues) = audio_listener::new_client(
+        unique_id.clone(),
+        audio_listener::default_params(&receiver),
+        pipeline,
+        AUDIO_TEXT_QUEUE,
+    );
+    let mut audio_rx = queues.audio;
+    let mut text_rx = queues.text;
+    let frame_task =
+        audio_listener::spawn_frame_task(state.clone(), client_id, client.clone(), queues.frames);
 
     let (mut ws_sender, mut ws_receiver) = socket.split();
-    let send_task = tokio::spawn(async move {
+    let time_shift_window = Duration::from_secs(u64::from(state.cfg().time_shift.minutes) * 60);
+    let mut send_task = tokio::spawn(async move {
         let mut ping_interval = tokio::time::interval(Duration::from_secs(30));
         ping_interval.tick().await; // consume immediate first tick
+        let mut shift = TimeShift::new(time_shift_window);
         loop {
+            let replay_at = shift.next_due();
             tokio::select! {
                 biased;
                 Some(cmd) = out_rx.recv() => {
                     match cmd {
                         AudioOutbound::Switch { settings_json } => {
                             while audio_rx.try_recv().is_ok() {}
+                            // Buffered audio belongs to the old receiver's pipeline.
+                            shift.clear();
                             if ws_sender.send(ws::Message::Text(settings_json)).await.is_err() {
                                 break;
                             }
                         }
+                        AudioOutbound::Close { message_json } => {
+                            let _ = ws_sender.send(ws::Message::Text(message_json)).await;
+                            let _ = ws_sender.send(ws::Message::Close(None)).await;
+                            break;
+                        }
+                        AudioOutbound::TimeShift(command) => {
+                            let now = Instant::now();
+                            shift.apply(now, command);
+                            let status = shift.status(now);
+                            if ws_sender.send(ws::Message::Text(status)).await.is_err() {
+                                break;
+                            }
+                        }
                     }
                 }
                 Some(bytes) = audio_rx.recv() => {
+                    let Some(bytes) = shift.push(Instant::now(), bytes) else {
+                        continue;
+                    };
+                    if ws_sender.send(ws::Message::Binary(bytes)).await.is_err() {
+                        break;
+                    }
+                }
+                _ = tokio::time::sleep_until(replay_at.unwrap_or_else(Instant::now)), if replay_at.is_some() => {
+                    let Some(bytes) = shift.due(Instant::now()) else {
+                        continue;
+                    };
                     if ws_sender.send(ws::Message::Binary(bytes)).await.is_err() {
                         break;
                     }
                 }
+                Some(txt) = text_rx.recv() => {
+                    if ws_sender.send(ws::Message::Text(txt)).await.is_err() {
+                        break;
+                    }
+                }
                 _ = ping_interval.tick() => {
                     if ws_sender.send(ws::Message::Ping(Vec::new())).await.is_err() {
                         break;
@@ -447,7 +515,9 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
     });
 
     let basic_info = with_audio_unique_id(
-        state.basic_info_json(receiver_id.as_str()).await,
+        state
+            .basic_info_json(receiver_id.as_str(), markers_api)
+            .await,
         &unique_id,
     );
     if out_tx
@@ -457,25 +527,47 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
         .await
         .is_err()
     {
+        frame_task.abort();
         send_task.abort();
         return;
     }
 
     receiver.audio_clients.insert(client_id, client.clone());
+    if receiver.input_restarting().is_some() {
+        let _ = client.text_tx.try_send(receiver.status_message());
+    }
+    let settings = receiver.settings();
     state.broadcast_signal_changes(
         receiver_id.as_str(),
         &unique_id,
-        receiver.rt.default_l,
-        receiver.rt.default_m,
-        receiver.rt.default_r,
+        settings.default_l,
+        settings.default_m,
+        settings.default_r,
     );
 
     let idle_timeout = Duration::from_secs(90);
+    let mut quota_closed = false;
+    let mut scanner: Option<Scanner> = None;
     loop {
-        let maybe_msg = match tokio::time::timeout(idle_timeout, ws_receiver.next()).await {
-            Ok(v) => v,
-            Err(_) => {
-                tracing::info!(client_id, %unique_id, "audio ws idle timeout");
+        let maybe_msg = tokio::select! {
+            next = tokio::time::timeout(idle_timeout, ws_receiver.next()) => match next {
+                Ok(v) => v,
+                Err(_) => {
+                    tracing::info!(client_id, %unique_id, "audio ws idle timeout");
+                    break;
+                }
+            },
+            true = async { quota.exhausted.wait_for(|exhausted| *exhausted).await.is_ok() } => {
+                tracing::info!(client_id, %unique_id, "audio ws closed: daily listening time used up");
+                let message_json = serde_json::json!({
+                    "type": "quota_exceeded",
+                    "waterfall": state.cfg().limits.quota_waterfall_only,
+                })
+                .to_string();
+                quota_closed = out_tx
+                    .send(AudioOutbound::Close { message_json })
+                    .await
+                    .is_ok();
                 break;
             }
         };
@@ -484,7 +576,7 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
         };
         match msg {
             ws::Message::Text(txt) => {
-                if txt.len() > 1024 {
+                if txt.len() > MAX_COMMAND_LEN {
                     continue;
                 }
                 let Ok(cmd) = serde_json::from_str::<novasdr_core::protocol::ClientCommand>(&txt)
@@ -499,21 +591,25 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                         if next_id.is_empty() {
                             continue;
                         }
+                        stop_scan(&mut scanner, &client).await;
 
                         if next_id == receiver_id {
+                            let settings = receiver.settings();
                             let settings_json = with_audio_unique_id(
-                                state.basic_info_json(receiver_id.as_str()).await,
+                                state
+                                    .basic_info_json(receiver_id.as_str(), markers_api)
+                                    .await,
                                 &unique_id,
                             );
                             if let Ok(mut p) = client.params.lock() {
-                                p.l = receiver.rt.default_l;
-                                p.m = receiver.rt.default_m;
-                                p.r = receiver.rt.default_r;
+                                p.l = settings.default_l;
+                                p.m = settings.default_m;
+                                p.r = settings.default_r;
                                 p.mute = false;
-                                p.squelch_enabled =
-                                    receiver.receiver.input.defaults.squelch_enabled;
+                                p.squelch_enabled = settings.defaults.squelch_enabled;
+                                p.squelch_mode = SquelchMode::default();
                                 p.demodulation = DemodulationMode::from_str_upper(
-                                    receiver.rt.default_mode_str.as_str(),
+                                    settings.default_mode_str.as_str(),
                                 )
                                 .unwrap_or(DemodulationMode::Usb);
                                 p.agc_speed = AgcSpeed::Default;
@@ -523,9 +619,9 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                             state.broadcast_signal_changes(
                                 receiver_id.as_str(),
                                 &unique_id,
-                                receiver.rt.default_l,
-                                receiver.rt.default_m,
-                                receiver.rt.default_r,
+                                settings.default_l,
+                                settings.default_m,
+                                settings.default_r,
                             );
 
                             if out_tx
@@ -541,15 +637,14 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                         else {
                             continue;
                         };
+                        if next_receiver.audio_full() {
+                            // The client stays where it is.
+                            let _ = client.text_tx.try_send(next_receiver.full_message("audio"));
+                            continue;
+                        }
 
-                        let next_audio_fft_size = next_receiver.rt.audio_max_fft_size;
-                        let next_sample_rate = next_receiver.rt.audio_max_sps as usize;
-                        let next_compression = next_receiver.receiver.input.audio_compression;
-                        let next_pipeline = match AudioPipeline::new(
-                            next_sample_rate,
-                            next_audio_fft_size,
-                            next_compression,
-                        ) {
+                        let next_shape = PipelineShape::of(&next_receiver);
+                        let next_pipeline = match audio_pool::acquire(&state, next_shape).await {
                             Ok(p) => p,
                             Err(e) => {
                                 tracing::warn!(receiver_id = %next_id, error = ?e, "failed to build audio pipeline for receiver switch");
@@ -557,7 +652,7 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                             }
                         };
                         let next_basic_info = with_audio_unique_id(
-                            state.basic_info_json(next_id.as_str()).await,
+                            state.basic_info_json(next_id.as_str(), markers_api).await,
                             &unique_id,
                         );
 
@@ -568,6 +663,7 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                             .insert(client_id, client.clone());
                         receiver_id = next_id;
                         receiver = next_receiver;
+                        let settings = receiver.settings();
 
                         {
                             let mut p = match client.params.lock() {
@@ -580,11 +676,11 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                                     poisoned.into_inner()
                                 }
                             };
-                            p.l = receiver.rt.default_l;
-                            p.m = receiver.rt.default_m;
-                            p.r = receiver.rt.default_r;
+                            p.l = settings.default_l;
+                            p.m = settings.default_m;
+                            p.r = settings.default_r;
                             p.demodulation = DemodulationMode::from_str_upper(
-                                receiver.rt.default_mode_str.as_str(),
+                                settings.default_mode_str.as_str(),
                             )
                             .unwrap_or(DemodulationMode::Usb);
                         }
@@ -599,7 +695,9 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                                     poisoned.into_inner()
                                 }
                             };
-                            *pipeline = next_pipeline;
+                            let old_pipeline = std::mem::replace(&mut *pipeline, next_pipeline);
+                            state.audio_pipelines.release(shape, old_pipeline);
+                            shape = next_shape;
                         }
 
                         state.broadcast_signal_changes(
@@ -612,9 +710,9 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                         state.broadcast_signal_changes(
                             receiver_id.as_str(),
                             &unique_id,
-                            receiver.rt.default_l,
-                            receiver.rt.default_m,
-                            receiver.rt.default_r,
+                            settings.default_l,
+                            settings.default_m,
+                            settings.default_r,
                         );
 
                         if out_tx
@@ -626,8 +724,132 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                         {
                             break;
                         }
+                        if receiver.input_restarting().is_some() {
+                            let _ = client.text_tx.try_send(receiver.status_message());
+                        }
+                    }
+                    novasdr_core::protocol::ClientCommand::Presence { nickname } => {
+                        let nickname = nickname
+                            .as_deref()
+                            .and_then(crate::ws::chat::presence_nickname);
+                        let changed = {
+                            let mut presence = match client.presence.lock() {
+                                Ok(g) => g,
+                                Err(poisoned) => {
+                                    tracing::error!(
+                                        unique_id = %client.unique_id,
+                                        "presence mutex poisoned; recovering"
+                                    );
+                                    poisoned.into_inner()
+                                }
+                            };
+                            let changed = *presence != nickname;
+                            *presence = nickname;
+                            changed
+                        };
+                        if changed {
+                            state.broadcast_event_info(false);
+                        }
+                    }
+                    novasdr_core::protocol::ClientCommand::Lookup { frequency } => {
+                        // Upstream lookups can take seconds; the reply goes out on the text
+                        // queue so audio keeps flowing meanwhile.
+                        let state = state.clone();
+                        let text_tx = client.text_tx.clone();
+                        tokio::spawn(async move {
+                            let reply = match state.freqdb.as_ref() {
+                                None => json!({
+                                    "type": "lookup",
+                                    "frequency": frequency,
+                                    "error": "disabled",
+                                }),
+                                Some(db) => match db.lookup(ip, frequency).await {
+                                    Ok(entries) => json!({
+                                        "type": "lookup",
+                                        "frequency": frequency,
+                                        "entries": entries,
+                                    }),
+                                    Err(e) => json!({
+                                        "type": "lookup",
+                                        "frequency": frequency,
+                                        "error": e.as_str(),
+                                    }),
+                                },
+                            };
+                            let _ = text_tx.send(reply.to_string()).await;
+                        });
+                    }
+                    novasdr_core::protocol::ClientCommand::Scan {
+                        action,
+                        channels,
+                        markers,
+                        dwell_ms,
+                        hang_ms,
+                    } => match action.as_str() {
+                        "start" => {
+                            stop_scan(&mut scanner, &client).await;
+                            let default_mode = match client.params.lock() {
+                                Ok(g) => g.demodulation,
+                                Err(poisoned) => poisoned.into_inner().demodulation,
+                            };
+                            let marker_index = state.markers.read().await.clone();
+                            match ScanPlan::build(
+                                &receiver,
+                                &marker_index,
+                                &channels,
+                                markers.as_deref(),
+                                default_mode,
+                                dwell_ms,
+                                hang_ms,
+                            ) {
+                                Ok(plan) => {
+                                    scanner = Some(Scanner::start(
+                                        state.clone(),
+                                        receiver_id.clone(),
+                                        receiver.clone(),
+                                        client.clone(),
+                                        plan,
+                                    ));
+                                }
+                                Err(reason) => {
+                                    let msg = json!({
+                                        "type": "scan",
+                                        "state": "stopped",
+                                        "error": reason,
+                                    });
+                                    let _ = client.text_tx.try_send(msg.to_string());
+                                }
+                            }
+                        }
+                        "skip" => {
+                            if let Some(scanner) = scanner.as_ref() {
+                                scanner.skip();
+                            }
+                        }
+                        "stop" => stop_scan(&mut scanner, &client).await,
+                        _ => {}
+                    },
+                    novasdr_core::protocol::ClientCommand::Timeshift { action, seconds } => {
+                        let Some(command) = time_shift::Command::parse(&action, seconds) else {
+                            continue;
+                        };
+                        if out_tx
+                            .send(AudioOutbound::TimeShift(command))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
                     }
                     other => {
+                        // Tuning by hand ends a scan.
+                        if matches!(
+                            other,
+                            novasdr_core::protocol::ClientCommand::Window { .. }
+                                | novasdr_core::protocol::ClientCommand::Demodulation { .. }
+                        ) {
+                            stop_scan(&mut scanner, &client).await;
+                        }
                         apply_command(&state, receiver_id.as_str(), &receiver, &client, other);
                     }
                 }
@@ -638,12 +860,96 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
         }
     }
 
+    if let Some(scanner) = scanner.take() {
+        scanner.stop().await;
+    }
     receiver.audio_clients.remove(&client_id);
     state.broadcast_signal_changes(receiver_id.as_str(), &unique_id, -1, -1.0, -1);
-    tracing::info!(client_id, %unique_id, "audio ws disconnected");
+    let rejected_windows = client.rejected_windows.load(Ordering::Relaxed);
+    audio_listener::release(&state, client, frame_task, shape).await;
+    tracing::info!(
+        client_id,
+        %unique_id,
+        rejected_windows,
+        "audio ws disconnected"
+    );
+    if quota_closed {
+        // Let the send task deliver the `quota_exceeded` message before the socket goes away.
+        let _ = tokio::time::timeout(Duration::from_secs(1), &mut send_task).await;
+    }
     send_task.abort();
 }
 
+/// Ends a running scan and tells the client; the channel it was on stays tuned.
+async fn stop_scan(scanner: &mut Option<Scanner>, client: &AudioClient) {
+    let Some(running) = scanner.take() else {
+        return;
+    };
+    running.stop().await;
+    let msg = json!({ "type": "scan", "state": "stopped" });
+    let _ = client.text_tx.try_send(msg.to_string());
+}
+
+/// Why a `window` command was refused; `as usize` indexes [`AppState::rejected_windows`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowRejection {
+    /// No `m`.
+    MissingCenter,
+    /// `l` or `r` below bin 0.
+    Negative,
+    /// `l` above `r`.
+    Reversed,
+    /// `r` past the last bin of the receiver.
+    OutOfRange,
+    /// Wider than the audio pipeline's FFT.
+    TooWide,
+}
+
+impl WindowRejection {
+    pub const ALL: [Self; 5] = [
+        Self::MissingCenter,
+        Self::Negative,
+        Self::Reversed,
+        Self::OutOfRange,
+        Self::TooWide,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MissingCenter => "missing_m",
+            Self::Negative => "negative",
+            Self::Reversed => "reversed",
+            Self::OutOfRange => "out_of_range",
+            Self::TooWide => "too_wide",
+        }
+    }
+}
+
+/// The window's `m` if a receiver with `bins` FFT bins can demodulate `l..=r` in an audio
+/// pipeline at most `max_width` bins wide.
+fn check_window(
+    l: i32,
+    r: i32,
+    m: Option<f64>,
+    bins: usize,
+    max_width: i32,
+) -> Result<f64, WindowRejection> {
+    let m = m.ok_or(WindowRejection::MissingCenter)?;
+    if l < 0 || r < 0 {
+        return Err(WindowRejection::Negative);
+    }
+    if l > r {
+        return Err(WindowRejection::Reversed);
+    }
+    if r as usize >= bins {
+        return Err(WindowRejection::OutOfRange);
+    }
+    if r - l > max_width {
+        return Err(WindowRejection::TooWide);
+    }
+    Ok(m)
+}
+
 fn apply_command(
     state: &Arc<AppState>,
     receiver_id: &str,
@@ -655,14 +961,34 @@ fn apply_command(
     match cmd {
         novasdr_core::protocol::ClientCommand::Receiver { .. } => {}
         novasdr_core::protocol::ClientCommand::Window { l, r, m, .. } => {
-            let Some(m) = m else { return };
-            if l < 0 || r < 0 || l > r || r as usize >= rt.fft_result_size {
-                return;
-            }
-            let audio_fft_size = rt.audio_max_fft_size as i32;
-            if r - l > audio_fft_size {
-                return;
-            }
+            let max_width = rt.audio_max_fft_size as i32;
+            let m = match check_window(l, r, m, rt.fft_result_size, max_width) {
+                Ok(m) => m,
+                Err(reason) => {
+                    state.rejected_windows[reason as usize].fetch_add(1, Ordering::Relaxed);
+                    let rejected = client.rejected_windows.fetch_add(1, Ordering::Relaxed) + 1;
+                    tracing::debug!(
+                        unique_id = %client.unique_id,
+                        l,
+                        r,
+                        ?m,
+                        reason = reason.as_str(),
+                        "window rejected"
+                    );
+                    let msg = json!({
+                        "type": "window_rejected",
+                        "reason": reason.as_str(),
+                        "l": l,
+                        "m": m,
+                        "r": r,
+                        "max_bin": rt.fft_result_size.saturating_sub(1),
+                        "max_width": max_width,
+                        "rejected": rejected,
+                    });
+                    let _ = client.text_tx.try_send(msg.to_string());
+                    return;
+                }
+            };
             let mut p = match client.params.lock() {
                 Ok(g) => g,
                 Err(poisoned) => {
@@ -691,6 +1017,19 @@ fn apply_command(
             };
             if let Some(mode) = DemodulationMode::from_str_upper(demodulation.as_str()) {
                 p.demodulation = mode;
+                // A window left over from the previous mode (e.g. a WBFM-wide window after
+                // switching to USB) would be demodulated as garbage until the client sends a
+                // new one.
+                if let Some((l, r)) = rt.fit_window_to_mode(mode, p.l, p.r, p.m) {
+                    p.l = l;
+                    p.r = r;
+                    let m = p.m;
+                    state.broadcast_signal_changes(receiver_id, &client.unique_id, l, m, r);
+                    let msg = json!({ "type": "window", "l": l, "m": m, "r": r });
+                    // The client keeps its stale passband if the queue is full; audio is
+                    // already correct.
+                    let _ = client.text_tx.try_send(msg.to_string());
+                }
             }
             let mut pipeline = match client.pipeline.lock() {
                 Ok(g) => g,
@@ -717,7 +1056,12 @@ fn apply_command(
             };
             p.mute = mute;
         }
-        novasdr_core::protocol::ClientCommand::Squelch { enabled } => {
+        novasdr_core::protocol::ClientCommand::Squelch {
+            enabled,
+            mode,
+            open_db,
+            hysteresis_db,
+        } => {
             let mut p = match client.params.lock() {
                 Ok(g) => g,
                 Err(poisoned) => {
@@ -729,12 +1073,24 @@ fn apply_command(
                 }
             };
             p.squelch_enabled = enabled;
+            let mode = match mode.as_deref() {
+                Some(mode) => Some(mode),
+                // Thresholds alone retune the level squelch.
+                None if matches!(p.squelch_mode, SquelchMode::Level { .. }) => Some("level"),
+                None => None,
+            };
+            if let Some(mode) = mode {
+                match SquelchMode::parse(mode, p.squelch_mode, open_db, hysteresis_db) {
+                    Some(next) => p.squelch_mode = next,
+                    None => tracing::debug!(
+                        unique_id = %client.unique_id,
+                        mode,
+                        "unknown squelch mode ignored"
+                    ),
+                }
+            }
         }
-        novasdr_core::protocol::ClientCommand::Agc {
-            speed,
-            attack,
-            release,
-        } => {
+        novasdr_core::protocol::ClientCommand::Rds { enabled } => {
             let mut p = match client.params.lock() {
                 Ok(g) => g,
                 Err(poisoned) => {
@@ -745,112 +1101,499 @@ fn apply_command(
                     poisoned.into_inner()
                 }
             };
-            p.agc_speed = AgcSpeed::parse(speed.as_str());
-            p.agc_attack_ms = attack;
-            p.agc_release_ms = release;
+            p.rds = enabled;
         }
-        novasdr_core::protocol::ClientCommand::Userid { .. } => {}
-        novasdr_core::protocol::ClientCommand::Buffer { .. } => {}
-        novasdr_core::protocol::ClientCommand::Chat { .. } => {}
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn features_for_test(scaled_relative_variance: f32) -> SquelchFeatures {
-        SquelchFeatures {
-            scaled_relative_variance,
-            active_bins: 64,
-            max_active_run: 32,
-            len: 1024,
+        novasdr_core::protocol::ClientCommand::Stereo { enabled } => {
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.stereo = enabled;
         }
-    }
-
-    #[test]
-    fn scaled_relative_variance_power_is_zero_for_empty_or_dc() {
-        assert_eq!(squelch_features(&[]).scaled_relative_variance, 0.0);
-        assert_eq!(
-            squelch_features(&[Complex32::new(1.0, 0.0)]).scaled_relative_variance,
-            0.0
-        );
-        let bins = vec![Complex32::new(2.0, 0.0); 128];
-        let scaled = squelch_features(&bins).scaled_relative_variance;
-        let expected = -((bins.len() as f32).sqrt());
-        assert!(
-            (scaled - expected).abs() < 1e-3,
-            "expected scaled ~ {expected}, got {scaled}"
-        );
-    }
-
-    #[test]
-    fn squelch_disabled_is_always_open() {
-        let mut s = SquelchState::new();
-        for v in [0.0, 1.0, 10.0, 100.0] {
-            assert!(s.update(false, features_for_test(v)));
+        novasdr_core::protocol::ClientCommand::DigitalVoice { enabled } => {
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.digital_voice = enabled;
         }
-    }
-
-    #[test]
-    fn squelch_closes_after_sustained_low_variation() {
-        let mut s = SquelchState::new();
-        assert!(
-            s.update(true, features_for_test(20.0)),
-            "strong variation should open squelch"
-        );
-        for _ in 0..9 {
-            assert!(
-                s.update(true, features_for_test(0.0)),
-                "should remain open until close hysteresis triggers"
-            );
+        novasdr_core::protocol::ClientCommand::Subtone { enabled } => {
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.subtone = enabled;
         }
-        assert!(
-            !s.update(true, features_for_test(0.0)),
-            "should close after sustained low variance"
-        );
-    }
-
-    #[test]
-    fn squelch_opens_immediately_on_strong_variation() {
-        let mut s = SquelchState::new();
+        novasdr_core::protocol::ClientCommand::ToneSquelch { tone } => {
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.tone_squelch = tone;
+        }
+        novasdr_core::protocol::ClientCommand::Sstv { enabled } => {
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            let starting = enabled && !p.sstv;
+            p.sstv = enabled;
+            // With a replay buffer the decoder starts in the past, catching a picture whose
+            // header went out before the listener asked.
+            if starting && receiver.replay.is_enabled() {
+                let (primed_tx, primed_rx) = oneshot::channel();
+                let mut pipeline = match client.pipeline.lock() {
+                    Ok(g) => g,
+                    Err(poisoned) => {
+                        tracing::error!(
+                            unique_id = %client.unique_id,
+                            "audio pipeline mutex poisoned; recovering"
+                        );
+                        poisoned.into_inner()
+                    }
+                };
+                pipeline.expect_primed_sstv(primed_rx);
+                crate::replay::spawn_sstv_preroll(
+                    state.clone(),
+                    receiver.clone(),
+                    p.clone(),
+                    primed_tx,
+                );
+            }
+        }
+        novasdr_core::protocol::ClientCommand::TestTone { freq, level } => {
+            let test_tone = match freq {
+                None => None,
+                Some(freq_hz) => {
+                    let level_db = level.unwrap_or(TestTone::DEFAULT_LEVEL_DB);
+                    let nyquist = rt.audio_max_sps as f64 / 2.0;
+                    if !(freq_hz > 0.0 && freq_hz < nyquist && level_db <= 0.0) {
+                        return;
+                    }
+                    Some(TestTone { freq_hz, level_db })
+                }
+            };
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.test_tone = test_tone;
+        }
+        novasdr_core::protocol::ClientCommand::Fsk {
+            mode,
+            center,
+            shift,
+            reverse,
+        } => {
+            let fsk = match mode.as_deref() {
+                None => None,
+                Some(raw) => {
+                    let Some(mode) = rtty::Mode::parse(raw) else {
+                        return;
+                    };
+                    let center_hz = center.unwrap_or(FskParams::DEFAULT_CENTER_HZ);
+                    let shift_hz = shift.unwrap_or(rtty::SHIFT_HZ);
+                    if !(center_hz.is_finite() && shift_hz.is_finite())
+                        || shift_hz <= 0.0
+                        || center_hz - shift_hz / 2.0 <= 0.0
+                    {
+                        return;
+                    }
+                    Some(FskParams {
+                        mode,
+                        center_hz,
+                        shift_hz,
+                        reverse,
+                    })
+                }
+            };
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.fsk = fsk;
+        }
+        novasdr_core::protocol::ClientCommand::Agc {
+            speed,
+            attack,
+            release,
+        } => {
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.agc_speed = AgcSpeed::parse(speed.as_str());
+            p.agc_attack_ms = attack;
+            p.agc_release_ms = release;
+        }
+        novasdr_core::protocol::ClientCommand::Filter { sharpness } => {
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.filter_sharpness = FilterSharpness::parse(sharpness.as_str());
+        }
+        novasdr_core::protocol::ClientCommand::Userid { .. } => {}
+        novasdr_core::protocol::ClientCommand::Buffer { size } => {
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.low_latency = size == LOW_LATENCY_BUFFER;
+        }
+        novasdr_core::protocol::ClientCommand::Volume { db } => {
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            if db.is_finite() {
+                p.volume_db = db.clamp(MIN_VOLUME_DB, MAX_VOLUME_DB);
+            }
+        }
+        novasdr_core::protocol::ClientCommand::Hearing { profile } => {
+            let Some(profile) = HearingProfile::parse(profile.trim()) else {
+                return;
+            };
+            let mut p = match client.params.lock() {
+                Ok(g) => g,
+                Err(poisoned) => {
+                    tracing::error!(
+                        unique_id = %client.unique_id,
+                        "audio params mutex poisoned; recovering"
+                    );
+                    poisoned.into_inner()
+                }
+            };
+            p.hearing = profile;
+        }
+        novasdr_core::protocol::ClientCommand::Chat { .. } => {}
+        novasdr_core::protocol::ClientCommand::SuggestMarker { .. } => {}
+        novasdr_core::protocol::ClientCommand::Lookup { .. } => {}
+        novasdr_core::protocol::ClientCommand::Presence { .. } => {}
+        novasdr_core::protocol::ClientCommand::WaterfallRate { .. } => {}
+        novasdr_core::protocol::ClientCommand::Catchup { .. } => {}
+        novasdr_core::protocol::ClientCommand::CloseWindow { .. } => {}
+        novasdr_core::protocol::ClientCommand::Scan { .. } => {}
+        novasdr_core::protocol::ClientCommand::Timeshift { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_are_rejected_with_the_broken_constraint() {
+        let check = |l, r, m| check_window(l, r, m, 1024, 256);
+        assert_eq!(check(100, 200, Some(150.0)), Ok(150.0));
+        assert_eq!(check(100, 200, None), Err(WindowRejection::MissingCenter));
+        assert_eq!(check(-1, 200, Some(0.0)), Err(WindowRejection::Negative));
+        assert_eq!(check(200, 100, Some(150.0)), Err(WindowRejection::Reversed));
+        assert_eq!(
+            check(900, 1024, Some(950.0)),
+            Err(WindowRejection::OutOfRange)
+        );
+        assert_eq!(check(0, 257, Some(100.0)), Err(WindowRejection::TooWide));
+        assert_eq!(check(0, 256, Some(100.0)), Ok(100.0));
+    }
+
+    fn features_for_test(scaled_relative_variance: f32) -> SquelchFeatures {
+        SquelchFeatures {
+            scaled_relative_variance,
+            active_bins: 64,
+            max_active_run: 32,
+            len: 1024,
+        }
+    }
+
+    #[test]
+    fn scaled_relative_variance_power_is_zero_for_empty_or_dc() {
+        assert_eq!(squelch_features(&[]).scaled_relative_variance, 0.0);
+        assert_eq!(
+            squelch_features(&[Complex32::new(1.0, 0.0)]).scaled_relative_variance,
+            0.0
+        );
+        let bins = vec![Complex32::new(2.0, 0.0); 128];
+        let scaled = squelch_features(&bins).scaled_relative_variance;
+        let expected = -((bins.len() as f32).sqrt());
+        assert!(
+            (scaled - expected).abs() < 1e-3,
+            "expected scaled ~ {expected}, got {scaled}"
+        );
+    }
+
+    #[test]
+    fn squelch_disabled_is_always_open() {
+        let mut s = SquelchState::new();
+        for v in [0.0, 1.0, 10.0, 100.0] {
+            assert!(s.update(false, features_for_test(v)));
+        }
+    }
+
+    #[test]
+    fn squelch_closes_after_sustained_low_variation() {
+        let mut s = SquelchState::new();
+        assert!(
+            s.update(true, features_for_test(20.0)),
+            "strong variation should open squelch"
+        );
+        for _ in 0..9 {
+            assert!(
+                s.update(true, features_for_test(0.0)),
+                "should remain open until close hysteresis triggers"
+            );
+        }
+        assert!(
+            !s.update(true, features_for_test(0.0)),
+            "should close after sustained low variance"
+        );
+    }
+
+    #[test]
+    fn level_squelch_opens_at_threshold_and_rides_out_flutter() {
+        let mut s = SquelchState::new();
+        let level = |s: &mut SquelchState, snr: f32| s.update_level(true, Some(snr), 10.0, 3.0);
+        assert!(!level(&mut s, 9.0));
+        assert!(level(&mut s, 10.5));
+        // Inside the hysteresis band it stays open indefinitely.
+        for _ in 0..20 {
+            assert!(level(&mut s, 7.5));
+        }
+        // Dips below it shorter than the close count are bridged.
+        assert!(level(&mut s, 2.0));
+        assert!(level(&mut s, 2.0));
+        assert!(level(&mut s, 8.0));
+        assert!(level(&mut s, 2.0));
+        assert!(level(&mut s, 2.0));
+        assert!(!level(&mut s, 2.0));
+        assert!(!level(&mut s, 8.0));
+
+        // Without a noise floor there is nothing to judge by: open.
+        assert!(s.update_level(true, None, 10.0, 3.0));
+        assert!(s.update_level(false, Some(0.0), 10.0, 3.0));
+    }
+
+    #[test]
+    fn passband_snr_compares_mean_bin_power_with_the_floor() {
+        let bins = vec![Complex32::new(10.0, 0.0); 32];
+        let snr = passband_snr_db(&bins, Some(1.0)).unwrap();
+        assert!((snr - 20.0).abs() < 1e-4, "{snr}");
+        assert_eq!(passband_snr_db(&bins, Some(0.0)), None);
+        assert_eq!(passband_snr_db(&bins, None), None);
+        assert_eq!(passband_snr_db(&[], Some(1.0)), None);
+    }
+
+    #[test]
+    fn squelch_opens_immediately_on_strong_variation() {
+        let mut s = SquelchState::new();
         assert!(!s.update(true, features_for_test(0.0)));
         assert!(s.update(true, features_for_test(100.0)));
     }
 }
 
+/// Opus bitrate per channel count; 40kbps mono produces excellent quality for VoIP needs.
+const OPUS_MONO_BITRATE: i32 = 40_000;
+const OPUS_STEREO_BITRATE: i32 = 64_000;
+
+pub fn new_opus_encoder(
+    sample_rate: usize,
+    channels: opus::Channels,
+) -> anyhow::Result<opus::Encoder> {
+    let opus_sample_rate = match sample_rate {
+        8000 => opus::SampleRate::Hz8000,
+        12000 => opus::SampleRate::Hz12000,
+        16000 => opus::SampleRate::Hz16000,
+        24000 => opus::SampleRate::Hz24000,
+        48000 => opus::SampleRate::Hz48000,
+        x => return Err(anyhow::anyhow!("Unsupported sample rate {x} for Opus codec. Valid values are: [8000, 12000, 16000, 24000, 48000]")),
+    };
+    let bitrate = match channels {
+        opus::Channels::Mono => OPUS_MONO_BITRATE,
+        opus::Channels::Stereo => OPUS_STEREO_BITRATE,
+    };
+
+    let mut opus_encoder =
+        opus::Encoder::new(opus_sample_rate, channels, opus::Application::LowDelay)
+            .map_err(|e| anyhow::anyhow!("Opus create error: {e}"))?;
+
+    if let Err(e) = opus_encoder.set_bitrate(opus::Bitrate::BitsPerSecond(bitrate)) {
+        tracing::warn!(error = ?e, "opus. unsuccess set_bitrate");
+    }
+
+    if let Err(e) = opus_encoder.set_complexity(2) {
+        tracing::warn!(error = ?e, "opus. unsuccess set_complexity");
+    }
+    Ok(opus_encoder)
+}
+
+fn default_agc(sample_rate: usize) -> Agc {
+    // Match reference defaults.
+    Agc::new(0.1, 100.0, 30.0, 100.0, sample_rate as f32)
+}
+
+/// Level the AGC settles at; FM is leveled to the same loudness.
+const AUDIO_TARGET_LEVEL: f32 = 0.1;
+
+/// Longest stretch of audio held back for a primed SSTV decoder.
+const SSTV_PREROLL_HOLD_SECS: usize = 10;
+
+/// Audio of frames a primed SSTV decoder may not have seen yet, by frame number.
+struct SstvPreroll {
+    primed: oneshot::Receiver<(sstv::Decoder, u64)>,
+    held: VecDeque<(u64, Vec<f32>)>,
+}
+
 pub struct AudioPipeline {
     compression: AudioCompression,
     audio_rate: usize,
     audio_fft_size: usize,
     ifft: Arc<dyn RustFft<f32>>,
     c2r_ifft: Arc<dyn ComplexToReal<f32>>,
-    c2r_scratch: Vec<Complex32>,
-    scratch: Vec<Complex32>,
-    buf_in: Vec<Complex32>,
-    baseband: Vec<Complex32>,
-    carrier: Vec<Complex32>,
     baseband_prev: Vec<Complex32>,
     carrier_prev: Vec<Complex32>,
     real: Vec<f32>,
     real_prev: Vec<f32>,
+    /// Shapes the passband edges; the FFT bin window alone leaves leaky skirts.
+    passband: PassbandFilter,
+    /// SSB audio as complex samples for `passband`.
+    analytic: Vec<Complex32>,
+    /// Delays the SAM carrier by the passband filter's group delay.
+    carrier_delay: VecDeque<Complex32>,
+    /// Baseband of the next frame from a channelizer channel, tuned to the listener's bin;
+    /// valid while `channel_loaded` is set. See [`Self::load_channel`].
+    channel: Vec<Complex32>,
+    channel_loaded: bool,
+    /// Built when the listener is first tuned from a channel.
+    tuner: Option<ChannelTuner>,
+    /// Right channel of stereo WBFM; `real` then holds the left channel.
+    right: Vec<f32>,
+    /// Whether the last demodulated frame filled `right`.
+    demod_stereo: bool,
     pcm_frame_i16: Vec<i16>,
+    pcm_frame_right_i16: Vec<i16>,
+    /// Interleaved left/right when `accum_stereo` is set.
     pcm_accum_i16: Vec<i16>,
+    accum_stereo: bool,
     pcm_accum_offset: usize,
     packet_samples: usize,
+    /// Packet size in DX mode: one demodulated frame (ADPCM) or 10 ms (Opus).
+    low_latency_packet_samples: usize,
     dc: DcBlocker,
+    dc_right: DcBlocker,
     agc: Agc,
+    /// Replaces the AGC after an FM discriminator, which has no signal strength left to track.
+    nfm_level: FmLeveler,
+    wbfm_level: FmLeveler,
+    /// Listener volume; one per channel so stereo keeps independent shelf state.
+    volume: LoudnessVolume,
+    volume_right: LoudnessVolume,
+    /// Frequency lowering, per channel like the volume.
+    hearing: FrequencyLowering,
+    hearing_right: FrequencyLowering,
     fm_prev: Complex32,
+    wbfm_decimation: usize,
+    /// Built on first use; only WBFM listeners pay for the wide multiplex IFFT.
+    wbfm: Option<WbfmDemodulator>,
+    /// Built when an FM listener first asks for digital voice.
+    digital_voice: Option<DigitalVoice>,
+    /// CTCSS/DCS detection, while an FM listener reports tones or squelches on one; dropped
+    /// when the carrier goes.
+    subtone: Option<SubtoneDetector>,
+    /// Change of the detected tone not yet taken, `Some(None)` once it is gone.
+    subtone_update: Option<Option<Subtone>>,
+    /// Whether the tone squelch let the last frame through.
+    tone_open: bool,
+    /// Built when a listener asks for SSTV; fed the audio of every frame the squelch passes.
+    sstv: Option<sstv::Decoder>,
+    /// While a decoder primed from the receiver's replay buffer is on its way.
+    sstv_preroll: Option<SstvPreroll>,
+    /// RTTY or NAVTEX decoding, rebuilt whenever the listener's settings or sideband change.
+    fsk: Option<(FskParams, bool, rtty::Decoder)>,
+    /// Phase of the test tone in radians, while one replaces the demodulated audio.
+    test_tone_phase: Option<f64>,
     last_agc: (AgcSpeed, Option<f32>, Option<f32>),
     squelch: SquelchState,
+    /// Mean power per bin around the passband, for the level squelch; set per frame.
+    squelch_floor: Option<f32>,
     opus_encoder: Option<opus::Encoder>,
+    /// Built when a client first receives stereo.
+    opus_stereo_encoder: Option<opus::Encoder>,
     opus_wrk_buf: Vec<u8>,
+    /// Hand out PCM instead of wire packets (headless listeners, which encode themselves).
+    raw_pcm: bool,
 }
 
 impl AudioPipeline {
     pub fn new(
         sample_rate: usize,
         audio_fft_size: usize,
+        wbfm_decimation: usize,
         compression: AudioCompression,
     ) -> anyhow::Result<Self> {
         let mut planner = FftPlanner::<f32>::new();
@@ -858,11 +1601,10 @@ impl AudioPipeline {
 
         let mut real_planner = RealFftPlanner::<f32>::new();
         let c2r_ifft = real_planner.plan_fft_inverse(audio_fft_size);
-        let c2r_scratch = c2r_ifft.make_scratch_vec();
 
         let frame_samples = audio_fft_size / 2;
 
-        let packet_samples = match compression {
+        let (packet_samples, low_latency_packet_samples) = match compression {
             AudioCompression::Adpcm => {
                 // Batch ~20ms of PCM per websocket frame to reduce packet rate and browser-side scheduling
                 // overhead (too many tiny frames can stutter).
@@ -871,12 +1613,14 @@ impl AudioPipeline {
                     ((sample_rate as f64) * target_packet_sec).ceil().max(1.0) as usize;
                 let mut packet_samples = frame_samples.max(min_packet);
                 packet_samples = packet_samples.div_ceil(8) * 8;
-                packet_samples.clamp(frame_samples, 8192)
+                (packet_samples.clamp(frame_samples, 8192), frame_samples)
             }
             AudioCompression::Opus => {
                 // number of milliseconds per chunk. opus allowed values: 5, 10, 20, 40, 60.
                 let ms = 20;
-                sample_rate * ms / 1000
+                // 10 ms rather than 5: below that Opus spends most of the bitrate on overhead.
+                let low_latency_ms = 10;
+                (sample_rate * ms / 1000, sample_rate * low_latency_ms / 1000)
             }
             AudioCompression::Flac => {
                 return Err(anyhow::anyhow!(
@@ -886,33 +1630,9 @@ impl AudioPipeline {
         };
 
         let (opus_encoder, opus_wrk_buf) = if compression == AudioCompression::Opus {
-            let opus_sample_rate = match sample_rate {
-                8000 => opus::SampleRate::Hz8000,
-                12000 => opus::SampleRate::Hz12000,
-                16000 => opus::SampleRate::Hz16000,
-                24000 => opus::SampleRate::Hz24000,
-                48000 => opus::SampleRate::Hz48000,
-                x => return Err(anyhow::anyhow!("Unsupported sample rate {x} for Opus codec. Valid values are: [8000, 12000, 16000, 24000, 48000]")),
-            };
-
-            let mut opus_encoder = opus::Encoder::new(
-                opus_sample_rate,
-                opus::Channels::Mono,
-                opus::Application::LowDelay,
-            )
-            .map_err(|e| anyhow::anyhow!("Opus create error: {e}"))?;
-
-            // 40kbps Opus produces excellent quality for VoIP needs.
-            if let Err(e) = opus_encoder.set_bitrate(opus::Bitrate::BitsPerSecond(40000)) {
-                tracing::warn!(error = ?e, "opus. unsuccess set_bitrate");
-            }
-
-            if let Err(e) = opus_encoder.set_complexity(2) {
-                tracing::warn!(error = ?e, "opus. unsuccess set_complexity");
-            }
-
-            // 120ms with 48000sps, doubled. More than enough for Opus encoder output buffer.
-            let max_wrk_buf_size = 120 * 48000 * 2 / 1000;
+            let opus_encoder = new_opus_encoder(sample_rate, opus::Channels::Mono)?;
+            // 120ms with 48000sps, stereo, doubled. More than enough for Opus encoder output buffer.
+            let max_wrk_buf_size = 120 * 48000 * 2 * 2 / 1000;
             (Some(opus_encoder), vec![0; max_wrk_buf_size])
         } else {
             (None, vec![])
@@ -924,28 +1644,57 @@ impl AudioPipeline {
             audio_fft_size,
             ifft,
             c2r_ifft,
-            c2r_scratch,
-            scratch: vec![Complex32::new(0.0, 0.0); audio_fft_size],
-            buf_in: vec![Complex32::new(0.0, 0.0); audio_fft_size],
-            baseband: vec![Complex32::new(0.0, 0.0); audio_fft_size],
-            carrier: vec![Complex32::new(0.0, 0.0); audio_fft_size],
             baseband_prev: vec![Complex32::new(0.0, 0.0); frame_samples],
             carrier_prev: vec![Complex32::new(0.0, 0.0); frame_samples],
             real: vec![0.0; audio_fft_size],
             real_prev: vec![0.0; frame_samples],
+            passband: PassbandFilter::new(frame_samples, sample_rate),
+            analytic: vec![Complex32::new(0.0, 0.0); frame_samples],
+            carrier_delay: VecDeque::new(),
+            channel: vec![Complex32::new(0.0, 0.0); frame_samples],
+            channel_loaded: false,
+            tuner: None,
+            right: vec![0.0; frame_samples],
             pcm_frame_i16: vec![0; frame_samples],
-            pcm_accum_i16: Vec::with_capacity(packet_samples * 4),
+            pcm_frame_right_i16: vec![0; frame_samples],
+            pcm_accum_i16: Vec::with_capacity(packet_samples * 2 * 4),
+            accum_stereo: false,
             pcm_accum_offset: 0,
             packet_samples,
+            low_latency_packet_samples,
             // Keep the DC blocker cutoff low so AM has real low end; bass boost is frontend-only.
             dc: DcBlocker::new((sample_rate / 20).max(128)),
-            // Match reference defaults.
-            agc: Agc::new(0.1, 100.0, 30.0, 100.0, sample_rate as f32),
+            dc_right: DcBlocker::new((sample_rate / 20).max(128)),
+            agc: default_agc(sample_rate),
+            nfm_level: FmLeveler::new(NFM_DEVIATION_HZ, sample_rate as f32, AUDIO_TARGET_LEVEL),
+            wbfm_level: FmLeveler::new(
+                WBFM_DEVIATION_HZ,
+                (sample_rate * wbfm_decimation) as f32,
+                AUDIO_TARGET_LEVEL,
+            ),
+            volume: LoudnessVolume::new(sample_rate),
+            volume_right: LoudnessVolume::new(sample_rate),
+            hearing: FrequencyLowering::new(sample_rate),
+            hearing_right: FrequencyLowering::new(sample_rate),
             fm_prev: Complex32::new(0.0, 0.0),
+            wbfm_decimation,
+            wbfm: None,
+            digital_voice: None,
+            subtone: None,
+            subtone_update: None,
+            tone_open: true,
+            sstv: None,
+            sstv_preroll: None,
+            fsk: None,
+            test_tone_phase: None,
+            demod_stereo: false,
             last_agc: (AgcSpeed::Default, None, None),
             squelch: SquelchState::new(),
+            squelch_floor: None,
             opus_encoder,
+            opus_stereo_encoder: None,
             opus_wrk_buf,
+            raw_pcm: false,
         })
     }
 
@@ -953,13 +1702,49 @@ impl AudioPipeline {
         self.agc.reset();
     }
 
+    /// Returns the pipeline to its freshly built state, for reuse by another client or receiver.
+    pub fn reset(&mut self) {
+        self.reset_for_squelch_gate();
+        self.agc = default_agc(self.audio_rate);
+        self.last_agc = (AgcSpeed::Default, None, None);
+        self.passband.set_passband(None, 0.0);
+        self.accum_stereo = false;
+        self.demod_stereo = false;
+        self.squelch = SquelchState::new();
+        self.subtone_update = None;
+        self.tone_open = true;
+        self.sstv = None;
+        self.sstv_preroll = None;
+        self.fsk = None;
+        self.test_tone_phase = None;
+        self.raw_pcm = false;
+    }
+
     fn reset_for_squelch_gate(&mut self) {
         self.real_prev.fill(0.0);
         self.baseband_prev.fill(Complex32::new(0.0, 0.0));
         self.carrier_prev.fill(Complex32::new(0.0, 0.0));
         self.fm_prev = Complex32::new(0.0, 0.0);
+        self.passband.reset();
+        self.carrier_delay.clear();
+        if let Some(wbfm) = self.wbfm.as_mut() {
+            wbfm.reset();
+        }
+        if let Some(dv) = self.digital_voice.as_mut() {
+            dv.reset();
+        }
+        self.drop_subtone();
+        // The picture has a gap now; what came in so far is all there is.
+        if let Some(decoder) = self.sstv.as_mut() {
+            decoder.finish();
+        }
         self.dc.reset();
+        self.dc_right.reset();
         self.agc.reset();
+        self.volume.reset();
+        self.volume_right.reset();
+        self.hearing.reset();
+        self.hearing_right.reset();
         self.pcm_accum_i16.clear();
         self.pcm_accum_offset = 0;
     }
@@ -971,17 +1756,59 @@ impl AudioPipeline {
         params: &AudioParams,
         is_real_input: bool,
         audio_mid_idx: i32,
+        pwr_gain: f32,
     ) -> anyhow::Result<Vec<Vec<u8>>> {
-        let mut out_packets = Vec::new();
         if params.mute {
-            return Ok(out_packets);
+            return Ok(Vec::new());
+        }
+        if let Some(tone) = params.test_tone {
+            self.generate_test_tone(tone);
+            return self.encode(spectrum_slice, frame_num, params, pwr_gain);
+        }
+        if self.test_tone_phase.take().is_some() {
+            // The overlap and filter state is from before the tone.
+            self.reset_for_squelch_gate();
         }
+        if !self.demodulate(
+            spectrum_slice,
+            frame_num,
+            params,
+            is_real_input,
+            audio_mid_idx,
+        ) {
+            return Ok(Vec::new());
+        }
+        self.encode(spectrum_slice, frame_num, params, pwr_gain)
+    }
 
-        let features = squelch_features(spectrum_slice);
-        let squelch_open = self.squelch.update(params.squelch_enabled, features);
+    /// Squelch and demodulation of one frame, up to the audio that [`Self::demodulated`]
+    /// returns. `false` while the squelch is closed.
+    pub fn demodulate(
+        &mut self,
+        spectrum_slice: &[Complex32],
+        frame_num: u64,
+        params: &AudioParams,
+        is_real_input: bool,
+        audio_mid_idx: i32,
+    ) -> bool {
+        let channel_loaded = mem::take(&mut self.channel_loaded);
+        let squelch_open = match params.squelch_mode {
+            SquelchMode::Variance => self
+                .squelch
+                .update(params.squelch_enabled, squelch_features(spectrum_slice)),
+            SquelchMode::Level {
+                open_db,
+                hysteresis_db,
+            } => self.squelch.update_level(
+                params.squelch_enabled,
+                passband_snr_db(spectrum_slice, self.squelch_floor),
+                f32::from(open_db),
+                f32::from(hysteresis_db),
+            ),
+        };
         if params.squelch_enabled && !squelch_open {
             self.reset_for_squelch_gate();
-            return Ok(out_packets);
+            return false;
         }
 
         let len = spectrum_slice.len() as i32;
@@ -991,12 +1818,86 @@ impl AudioPipeline {
 
         let n = self.audio_fft_size as i32;
         let half = (self.audio_fft_size / 2) as i32;
+        let negate_frame = frame_num % 2 == 1
+            && (((audio_mid_idx % 2 == 0) && !is_real_input)
+                || ((audio_mid_idx % 2 != 0) && is_real_input));
+
+        // Passband edges in Hz relative to the tuned bin, as heard: SSB audio starts at 0 Hz and
+        // LSB is mirrored.
+        let bin_hz = self.audio_rate as f32 / self.audio_fft_size as f32;
+        let m0 = params.m.floor() as i32;
+        let transition_hz = params.filter_sharpness.transition_hz();
+        // Channel baseband has no bin window, so only the passband filter can band-limit it.
+        let from_channel = channel_loaded
+            && transition_hz.is_some()
+            && matches!(
+                mode,
+                DemodulationMode::Usb
+                    | DemodulationMode::Lsb
+                    | DemodulationMode::Am
+                    | DemodulationMode::Fm
+            );
+        let passband = match mode {
+            DemodulationMode::Wbfm => None,
+            DemodulationMode::Usb => Some(((params.l - m0).max(0), params.r - m0)),
+            // Channel baseband is not mirrored; the lower sideband stays below the tuned bin.
+            DemodulationMode::Lsb if from_channel => Some((params.l - m0, (params.r - m0).min(0))),
+            DemodulationMode::Lsb => Some(((m0 - params.r).max(0), m0 - params.l)),
+            DemodulationMode::Am | DemodulationMode::Sam | DemodulationMode::Fm => {
+                Some((params.l - m0, params.r - m0))
+            }
+        };
+        self.passband.set_passband(
+            transition_hz
+                .and(passband)
+                .map(|(lo, hi)| (lo as f32 * bin_hz, hi as f32 * bin_hz)),
+            transition_hz.unwrap_or(0.0),
+        );
+
+        if !(params.digital_voice && mode == DemodulationMode::Fm) {
+            // Ends a call that was in progress when the listener left FM or digital voice.
+            if let Some(dv) = self.digital_voice.as_mut() {
+                dv.reset();
+            }
+        }
+        let detect_subtone =
+            mode == DemodulationMode::Fm && (params.subtone || params.tone_squelch.is_some());
+        if !detect_subtone {
+            self.drop_subtone();
+        }
 
         match mode {
+            DemodulationMode::Wbfm => {
+                let wbfm = self.wbfm.get_or_insert_with(|| {
+                    WbfmDemodulator::new(self.audio_rate, self.audio_fft_size, self.wbfm_decimation)
+                });
+                let half = self.audio_fft_size / 2;
+                wbfm.process(
+                    spectrum_slice,
+                    audio_m_rel,
+                    negate_frame,
+                    params.rds,
+                    params.stereo.then_some(&mut self.right[..half]),
+                    &mut self.real[..half],
+                );
+                self.real[half..].fill(0.0);
+            }
+            DemodulationMode::Usb | DemodulationMode::Lsb if from_channel => {
+                let half = self.audio_fft_size / 2;
+                self.analytic.copy_from_slice(&self.channel);
+                self.passband.process(&mut self.analytic);
+                // Only the sideband is left, as positive (USB) or negative (LSB) frequencies;
+                // twice the real part is its real band-pass, LSB coming out mirrored.
+                for (x, a) in self.real[..half].iter_mut().zip(self.analytic.iter()) {
+                    *x = 2.0 * a.re;
+                }
+                self.real[half..].fill(0.0);
+            }
             DemodulationMode::Usb | DemodulationMode::Lsb => {
                 // C2R IFFT input: N/2+1 complex values in hermitian format
                 let c2r_len = self.audio_fft_size / 2 + 1;
-                self.buf_in[..c2r_len].fill(Complex32::new(0.0, 0.0));
+                let mut buf_in = buffer_pool::COMPLEX.take(c2r_len);
+                let mut c2r_scratch = buffer_pool::COMPLEX.take(self.c2r_ifft.get_scratch_len());
 
                 if mode == DemodulationMode::Usb {
                     let copy_l = 0.max(audio_m_rel);
@@ -1005,7 +1906,7 @@ impl AudioPipeline {
                         for i in copy_l..copy_r {
                             let dst = (i - audio_m_rel) as usize;
                             if dst < c2r_len {
-                                self.buf_in[dst] = spectrum_slice[i as usize];
+                                buf_in[dst] = spectrum_slice[i as usize];
                             }
                         }
                     }
@@ -1018,141 +1919,378 @@ impl AudioPipeline {
                         for k in 0..count {
                             let dst = dst0 + k;
                             if dst < c2r_len {
-                                self.buf_in[dst] = spectrum_slice[(copy_r as usize) - 1 - k];
+                                buf_in[dst] = spectrum_slice[(copy_r as usize) - 1 - k];
                             }
                         }
                     }
                 }
 
                 let _ = self.c2r_ifft.process_with_scratch(
-                    &mut self.buf_in[..c2r_len],
+                    &mut buf_in,
                     &mut self.real,
-                    &mut self.c2r_scratch,
+                    &mut c2r_scratch,
                 );
 
                 if mode == DemodulationMode::Lsb {
                     self.real.reverse();
                 }
 
-                if frame_num % 2 == 1
-                    && (((audio_mid_idx % 2 == 0) && !is_real_input)
-                        || ((audio_mid_idx % 2 != 0) && is_real_input))
-                {
+                if negate_frame {
                     negate_f32(&mut self.real);
                 }
                 add_f32(&mut self.real[..self.audio_fft_size / 2], &self.real_prev);
+
+                if self.passband.is_active() {
+                    let half = self.audio_fft_size / 2;
+                    for (a, &x) in self.analytic.iter_mut().zip(self.real[..half].iter()) {
+                        *a = Complex32::new(x, 0.0);
+                    }
+                    self.passband.process(&mut self.analytic);
+                    // The filter only passes positive frequencies; doubling the real part
+                    // restores the level of the real band-pass.
+                    for (x, a) in self.real[..half].iter_mut().zip(self.analytic.iter()) {
+                        *x = 2.0 * a.re;
+                    }
+                }
             }
             DemodulationMode::Am | DemodulationMode::Sam | DemodulationMode::Fm => {
                 let need_carrier = mode == DemodulationMode::Sam;
 
-                self.buf_in.fill(Complex32::new(0.0, 0.0));
-                let pos_copy_l = 0.max(audio_m_rel);
-                let pos_copy_r = len.min(audio_m_rel + half);
-                if pos_copy_r >= pos_copy_l {
-                    for i in pos_copy_l..pos_copy_r {
-                        let dst = (i - audio_m_rel) as usize;
-                        self.buf_in[dst] = spectrum_slice[i as usize];
-                    }
-                }
-                let neg_copy_l = 0.max(audio_m_rel - half + 1);
-                let neg_copy_r = len.min(audio_m_rel);
-                if neg_copy_r >= neg_copy_l {
-                    for i in neg_copy_l..neg_copy_r {
-                        let dst = (self.audio_fft_size as i32 - (audio_m_rel - i)) as usize;
-                        if dst < self.buf_in.len() {
-                            self.buf_in[dst] = spectrum_slice[i as usize];
+                let (mut baseband, mut carrier) = if from_channel {
+                    // Already overlap-added by the channel; nothing to carry into the next frame.
+                    let mut baseband = buffer_pool::COMPLEX.take(self.audio_fft_size);
+                    baseband[..self.audio_fft_size / 2].copy_from_slice(&self.channel);
+                    (baseband, None)
+                } else {
+                    let mut buf_in = buffer_pool::COMPLEX.take(self.audio_fft_size);
+                    let pos_copy_l = 0.max(audio_m_rel);
+                    let pos_copy_r = len.min(audio_m_rel + half);
+                    if pos_copy_r >= pos_copy_l {
+                        for i in pos_copy_l..pos_copy_r {
+                            let dst = (i - audio_m_rel) as usize;
+                            buf_in[dst] = spectrum_slice[i as usize];
                         }
                     }
-                }
-
-                self.baseband.copy_from_slice(&self.buf_in);
-                self.ifft
-                    .process_with_scratch(&mut self.baseband, &mut self.scratch);
-
-                if need_carrier {
-                    self.carrier.copy_from_slice(&self.buf_in);
-                    let cutoff =
-                        (500 * self.audio_fft_size / self.audio_rate).min(self.audio_fft_size / 2);
-                    for i in cutoff..(self.audio_fft_size - cutoff) {
-                        self.carrier[i] = Complex32::new(0.0, 0.0);
+                    let neg_copy_l = 0.max(audio_m_rel - half + 1);
+                    let neg_copy_r = len.min(audio_m_rel);
+                    if neg_copy_r >= neg_copy_l {
+                        for i in neg_copy_l..neg_copy_r {
+                            let dst = (self.audio_fft_size as i32 - (audio_m_rel - i)) as usize;
+                            if dst < buf_in.len() {
+                                buf_in[dst] = spectrum_slice[i as usize];
+                            }
+                        }
                     }
-                    self.ifft
-                        .process_with_scratch(&mut self.carrier, &mut self.scratch);
-                }
 
-                if frame_num % 2 == 1
-                    && (((audio_mid_idx % 2 == 0) && !is_real_input)
-                        || ((audio_mid_idx % 2 != 0) && is_real_input))
-                {
-                    negate_complex(&mut self.baseband);
-                    if need_carrier {
-                        negate_complex(&mut self.carrier);
+                    let mut scratch =
+                        buffer_pool::COMPLEX.take(self.ifft.get_inplace_scratch_len());
+                    let mut carrier = need_carrier.then(|| {
+                        let mut carrier = buffer_pool::COMPLEX.take(self.audio_fft_size);
+                        carrier.copy_from_slice(&buf_in);
+                        let cutoff = (500 * self.audio_fft_size / self.audio_rate)
+                            .min(self.audio_fft_size / 2);
+                        for i in cutoff..(self.audio_fft_size - cutoff) {
+                            carrier[i] = Complex32::new(0.0, 0.0);
+                        }
+                        self.ifft.process_with_scratch(&mut carrier, &mut scratch);
+                        carrier
+                    });
+                    let mut baseband = buf_in;
+                    self.ifft.process_with_scratch(&mut baseband, &mut scratch);
+
+                    if negate_frame {
+                        negate_complex(&mut baseband);
+                        if let Some(carrier) = carrier.as_mut() {
+                            negate_complex(carrier);
+                        }
                     }
-                }
 
-                add_complex(
-                    &mut self.baseband[..self.audio_fft_size / 2],
-                    &self.baseband_prev,
-                );
-                if need_carrier {
                     add_complex(
-                        &mut self.carrier[..self.audio_fft_size / 2],
-                        &self.carrier_prev,
+                        &mut baseband[..self.audio_fft_size / 2],
+                        &self.baseband_prev,
                     );
+                    if let Some(carrier) = carrier.as_mut() {
+                        add_complex(&mut carrier[..self.audio_fft_size / 2], &self.carrier_prev);
+                    }
+                    (baseband, carrier)
+                };
+
+                let half = self.audio_fft_size / 2;
+                self.passband.process(&mut baseband[..half]);
+                if let Some(carrier) = carrier.as_mut() {
+                    self.delay_carrier(&mut carrier[..half]);
                 }
 
                 match mode {
                     DemodulationMode::Am => {
                         am_envelope(
-                            &self.baseband[..self.audio_fft_size / 2],
+                            &baseband[..self.audio_fft_size / 2],
                             &mut self.real[..self.audio_fft_size / 2],
                         );
                     }
                     DemodulationMode::Sam => {
                         sam_demod(
-                            &self.baseband[..self.audio_fft_size / 2],
-                            &self.carrier[..self.audio_fft_size / 2],
+                            &baseband[..self.audio_fft_size / 2],
+                            &carrier.as_ref().expect("SAM carrier")[..self.audio_fft_size / 2],
                             &mut self.real[..self.audio_fft_size / 2],
                         );
                     }
                     DemodulationMode::Fm => {
                         self.fm_prev = polar_discriminator_fm(
-                            &self.baseband[..self.audio_fft_size / 2],
+                            &baseband[..self.audio_fft_size / 2],
                             self.fm_prev,
                             &mut self.real[..self.audio_fft_size / 2],
                         );
+                        if detect_subtone {
+                            let audio_rate = self.audio_rate;
+                            let detector = self
+                                .subtone
+                                .get_or_insert_with(|| SubtoneDetector::new(audio_rate));
+                            if detector.process(&self.real[..half]) {
+                                self.subtone_update = Some(detector.tone());
+                            }
+                        }
+                        if params.digital_voice {
+                            let audio_rate = self.audio_rate;
+                            self.digital_voice
+                                .get_or_insert_with(|| DigitalVoice::new(audio_rate))
+                                .process(&mut self.real[..half]);
+                        }
                     }
                     _ => {}
                 }
                 self.real[self.audio_fft_size / 2..].fill(0.0);
+                self.baseband_prev
+                    .copy_from_slice(&baseband[self.audio_fft_size / 2..]);
+                if let Some(carrier) = carrier {
+                    self.carrier_prev
+                        .copy_from_slice(&carrier[self.audio_fft_size / 2..]);
+                }
             }
         }
 
         self.real_prev
             .copy_from_slice(&self.real[self.audio_fft_size / 2..]);
-        self.baseband_prev
-            .copy_from_slice(&self.baseband[self.audio_fft_size / 2..]);
-        if mode == DemodulationMode::Sam {
-            self.carrier_prev
-                .copy_from_slice(&self.carrier[self.audio_fft_size / 2..]);
+
+        // Frames stay mono until the demodulator can actually separate the channels.
+        self.demod_stereo = mode == DemodulationMode::Wbfm
+            && params.stereo
+            && self
+                .wbfm
+                .as_ref()
+                .is_some_and(WbfmDemodulator::stereo_available);
+        // The tone squelch keeps demodulating while closed so the detector hears the tone come.
+        self.tone_open = match params.tone_squelch.filter(|_| mode == DemodulationMode::Fm) {
+            Some(tone) => self.subtone.as_ref().and_then(SubtoneDetector::tone) == Some(tone),
+            None => true,
+        };
+        self.tone_open
+    }
+
+    /// Fills the frame's audio with the test tone, continuing its phase from the last frame.
+    fn generate_test_tone(&mut self, tone: TestTone) {
+        let half = self.audio_fft_size / 2;
+        let step = std::f64::consts::TAU * tone.freq_hz / self.audio_rate as f64;
+        let amplitude = 10f64.powf(tone.level_db / 20.0);
+        let mut phase = self.test_tone_phase.unwrap_or(0.0);
+        for x in self.real[..half].iter_mut() {
+            *x = (amplitude * phase.sin()) as f32;
+            phase = (phase + step) % std::f64::consts::TAU;
+        }
+        self.test_tone_phase = Some(phase);
+        self.demod_stereo = false;
+    }
+
+    /// Forgets the sub-audio signal, reporting that a detected tone is gone.
+    fn drop_subtone(&mut self) {
+        if let Some(detector) = self.subtone.take() {
+            if detector.tone().is_some() {
+                self.subtone_update = Some(None);
+            }
+        }
+    }
+
+    /// Audio of the last [`Self::demodulate`]: the mono (or left) channel, and the right one
+    /// when it was stereo.
+    pub fn demodulated(&self) -> (&[f32], Option<&[f32]>) {
+        let half = self.audio_fft_size / 2;
+        let right = self.demod_stereo.then(|| &self.right[..half]);
+        (&self.real[..half], right)
+    }
+
+    /// Takes demodulated audio from another pipeline of the same shape (a shared channel) in
+    /// place of [`Self::demodulate`].
+    pub fn load_demodulated(&mut self, left: &[f32], right: Option<&[f32]>) {
+        let half = self.audio_fft_size / 2;
+        self.real[..half].copy_from_slice(&left[..half]);
+        if let Some(right) = right {
+            self.right[..half].copy_from_slice(&right[..half]);
+        }
+        self.demod_stereo = right.is_some();
+    }
+
+    /// Takes the next frame's baseband from a channelizer channel (`frame_num` of a
+    /// [`ChannelDdc`](novasdr_core::dsp::channelizer::ChannelDdc)), `offset` bins below the
+    /// listener's bin, in place of the inverse FFT of the bins. The next [`Self::demodulate`]
+    /// uses it for AM, FM and SSB with a filter sharpness set and ignores it otherwise.
+    pub fn load_channel(&mut self, channel: &[Complex32], offset: i64, frame_num: u64) {
+        let size = self.audio_fft_size;
+        self.tuner
+            .get_or_insert_with(|| ChannelTuner::new(size))
+            .tune(channel, offset, frame_num, &mut self.channel);
+        self.channel_loaded = true;
+    }
+
+    /// Noise floor the level squelch compares the next frame against.
+    pub fn set_squelch_floor(&mut self, floor: Option<f32>) {
+        self.squelch_floor = floor;
+    }
+
+    pub fn squelch_floor(&self) -> Option<f32> {
+        self.squelch_floor
+    }
+
+    pub fn squelch_open(&self) -> bool {
+        self.squelch.open && self.tone_open
+    }
+
+    /// Closes an enabled squelch until the next frames show a signal, after retuning.
+    pub fn restart_squelch(&mut self) {
+        if self.squelch.was_enabled {
+            self.squelch.reset_closed();
+            self.reset_for_squelch_gate();
         }
+    }
+
+    /// What a closed squelch does to the pipeline, for audio taken from a shared channel.
+    pub fn close_squelch(&mut self) {
+        self.reset_for_squelch_gate();
+    }
 
+    /// Makes [`Self::encode`] return the PCM itself, interleaved little-endian `i16`, one
+    /// buffer per frame. Cleared by [`Self::reset`].
+    pub fn set_raw_pcm(&mut self, raw_pcm: bool) {
+        self.raw_pcm = raw_pcm;
+    }
+
+    /// AGC, volume and codec stage: turns the demodulated audio into wire packets.
+    pub fn encode(
+        &mut self,
+        spectrum_slice: &[Complex32],
+        frame_num: u64,
+        params: &AudioParams,
+        pwr_gain: f32,
+    ) -> anyhow::Result<Vec<Vec<u8>>> {
+        let mut out_packets = Vec::new();
         self.apply_agc_settings(params);
 
+        let stereo = self.demod_stereo;
+        if stereo != self.accum_stereo {
+            // Samples already queued use the other layout; drop them rather than mislabel them.
+            self.pcm_accum_i16.clear();
+            self.pcm_accum_offset = 0;
+            self.accum_stereo = stereo;
+        }
+        if stereo && self.opus_encoder.is_some() && self.opus_stereo_encoder.is_none() {
+            self.opus_stereo_encoder =
+                Some(new_opus_encoder(self.audio_rate, opus::Channels::Stereo)?);
+        }
+
         let half = self.audio_fft_size / 2;
+        let tone = params.test_tone.is_some();
+        if params.sstv && !tone {
+            self.feed_sstv(frame_num);
+        } else {
+            self.sstv = None;
+            self.sstv_preroll = None;
+        }
+        match params.fsk.filter(|_| !tone) {
+            Some(fsk) => {
+                let lsb = params.demodulation == DemodulationMode::Lsb;
+                if !matches!(&self.fsk, Some((p, l, _)) if *p == fsk && *l == lsb) {
+                    let (mark, space) = fsk.tones(lsb);
+                    let decoder = rtty::Decoder::new(fsk.mode, self.audio_rate as f64, mark, space);
+                    self.fsk = Some((fsk, lsb, decoder));
+                }
+                if let Some((_, _, decoder)) = self.fsk.as_mut() {
+                    decoder.process(&self.real[..half]);
+                }
+            }
+            None => self.fsk = None,
+        }
+        let fm_level = match params.demodulation {
+            DemodulationMode::Fm => Some(&self.nfm_level),
+            DemodulationMode::Wbfm => Some(&self.wbfm_level),
+            _ => None,
+        };
         let audio_out = &mut self.real[..half];
-        self.dc.remove_dc(audio_out);
-        self.agc.process(audio_out);
-
-        float_to_i16_centered(audio_out, &mut self.pcm_frame_i16, 32768.0);
-        self.pcm_accum_i16.extend_from_slice(&self.pcm_frame_i16);
-        let pwr = spectrum_slice.iter().map(|c| c.norm_sqr()).sum::<f32>();
+        if tone {
+            // The tone reaches the codec as generated, past DC blocking, AGC and volume.
+            float_to_i16_centered(audio_out, &mut self.pcm_frame_i16, 32768.0);
+            self.pcm_accum_i16.extend_from_slice(&self.pcm_frame_i16);
+        } else if stereo {
+            self.dc.remove_dc(audio_out);
+            self.volume.set_volume_db(params.volume_db);
+            let right = &mut self.right[..half];
+            self.dc_right.remove_dc(right);
+            match fm_level {
+                Some(level) => level.process_stereo(audio_out, right),
+                None => self.agc.process_stereo(audio_out, right),
+            }
+            self.hearing.set_profile(params.hearing);
+            self.hearing.process(audio_out);
+            self.hearing_right.set_profile(params.hearing);
+            self.hearing_right.process(right);
+            self.volume.process(audio_out);
+            self.volume_right.set_volume_db(params.volume_db);
+            self.volume_right.process(right);
+            float_to_i16_centered(audio_out, &mut self.pcm_frame_i16, 32768.0);
+            float_to_i16_centered(right, &mut self.pcm_frame_right_i16, 32768.0);
+            for (&l, &r) in self.pcm_frame_i16.iter().zip(&self.pcm_frame_right_i16) {
+                self.pcm_accum_i16.extend_from_slice(&[l, r]);
+            }
+        } else {
+            self.dc.remove_dc(audio_out);
+            self.volume.set_volume_db(params.volume_db);
+            match fm_level {
+                Some(level) => level.process(audio_out),
+                None => self.agc.process(audio_out),
+            }
+            self.hearing.set_profile(params.hearing);
+            self.hearing.process(audio_out);
+            self.volume.process(audio_out);
+            float_to_i16_centered(audio_out, &mut self.pcm_frame_i16, 32768.0);
+            self.pcm_accum_i16.extend_from_slice(&self.pcm_frame_i16);
+        }
+        if self.raw_pcm {
+            let pcm = &self.pcm_accum_i16[self.pcm_accum_offset..];
+            let out = if pcm.is_empty() {
+                Vec::new()
+            } else {
+                vec![pcm.iter().flat_map(|s| s.to_le_bytes()).collect()]
+            };
+            self.pcm_accum_i16.clear();
+            self.pcm_accum_offset = 0;
+            return Ok(out);
+        }
+        // `pwr_gain` applies the receiver's S-meter calibration at the tuned frequency.
+        let pwr = spectrum_slice.iter().map(|c| c.norm_sqr()).sum::<f32>() * pwr_gain;
 
-        let audio_wire_codec = match self.compression {
-            AudioCompression::Adpcm => AudioWireCodec::AdpcmIma,
-            AudioCompression::Opus => AudioWireCodec::Opus,
-            AudioCompression::Flac => unreachable!(),
+        let audio_wire_format = AudioWireFormat {
+            codec: match self.compression {
+                AudioCompression::Adpcm => AudioWireCodec::AdpcmIma,
+                AudioCompression::Opus => AudioWireCodec::Opus,
+                AudioCompression::Flac => unreachable!(),
+            },
+            stereo,
         };
+        let channels = if stereo { 2 } else { 1 };
+        let packet_samples = if params.low_latency {
+            self.low_latency_packet_samples
+        } else {
+            self.packet_samples
+        };
+        let packet_len = packet_samples * channels;
 
         let mut acc_frames: Vec<Vec<u8>> = Vec::new();
         loop {
@@ -1160,18 +2298,30 @@ impl AudioPipeline {
                 .pcm_accum_i16
                 .len()
                 .saturating_sub(self.pcm_accum_offset);
-            if available < self.packet_samples {
+            if available < packet_len {
                 break;
             }
 
-            let end = self.pcm_accum_offset + self.packet_samples;
+            let end = self.pcm_accum_offset + packet_len;
             let block = &self.pcm_accum_i16[self.pcm_accum_offset..end];
             self.pcm_accum_offset = end;
 
             let payload = match self.compression {
+                AudioCompression::Adpcm if stereo => {
+                    let (left, right): (Vec<i16>, Vec<i16>) =
+                        block.chunks_exact(2).map(|lr| (lr[0], lr[1])).unzip();
+                    let mut payload = ima_adpcm::encode_block_i16_mono(&left);
+                    payload.extend(ima_adpcm::encode_block_i16_mono(&right));
+                    payload
+                }
                 AudioCompression::Adpcm => ima_adpcm::encode_block_i16_mono(block),
                 AudioCompression::Opus => {
-                    let Some(opus_encoder) = self.opus_encoder.as_ref() else {
+                    let encoder = if stereo {
+                        self.opus_stereo_encoder.as_ref()
+                    } else {
+                        self.opus_encoder.as_ref()
+                    };
+                    let Some(opus_encoder) = encoder else {
                         return Err(anyhow::anyhow!("Opus encoder is None. Impossible."));
                     };
                     let size = opus_encoder
@@ -1184,10 +2334,21 @@ impl AudioPipeline {
 
             let audio_frame_size_threshold = 700; // keep frame size less than N bytes if possible
             let collected = acc_frames.iter().map(|x| x.len()).sum::<usize>();
-            if collected + payload.len() > audio_frame_size_threshold {
+            if params.low_latency {
+                // DX mode: no coalescing, every packet leaves as soon as it is encoded.
+                out_packets.push(build_audio_frame_multi(
+                    audio_wire_format,
+                    frame_num,
+                    0,
+                    params.m,
+                    spectrum_slice.len() as i32,
+                    pwr,
+                    vec![payload],
+                ));
+            } else if collected + payload.len() > audio_frame_size_threshold {
                 let taken_vec = mem::replace(&mut acc_frames, vec![payload]);
                 out_packets.push(build_audio_frame_multi(
-                    audio_wire_codec,
+                    audio_wire_format,
                     frame_num,
                     0,
                     params.m,
@@ -1199,7 +2360,7 @@ impl AudioPipeline {
                 acc_frames.push(payload);
             }
 
-            if self.pcm_accum_offset >= self.packet_samples * 4 {
+            if self.pcm_accum_offset >= packet_len * 4 {
                 self.pcm_accum_i16.drain(0..self.pcm_accum_offset);
                 self.pcm_accum_offset = 0;
             }
@@ -1207,7 +2368,7 @@ impl AudioPipeline {
 
         if !acc_frames.is_empty() {
             out_packets.push(build_audio_frame_multi(
-                audio_wire_codec,
+                audio_wire_format,
                 frame_num,
                 0,
                 params.m,
@@ -1220,6 +2381,118 @@ impl AudioPipeline {
         Ok(out_packets)
     }
 
+    /// Delays `carrier` by the passband filter's group delay so SAM still sees the carrier
+    /// phase that matches the filtered baseband.
+    fn delay_carrier(&mut self, carrier: &mut [Complex32]) {
+        let delay = self.passband.delay();
+        for c in carrier.iter_mut() {
+            self.carrier_delay.push_back(*c);
+            let mut out = Complex32::new(0.0, 0.0);
+            while self.carrier_delay.len() > delay {
+                if let Some(old) = self.carrier_delay.pop_front() {
+                    out = old;
+                }
+            }
+            *c = out;
+        }
+    }
+
+    /// JSON `rds` message when the decoded station information changed since the last call.
+    pub fn take_rds_update(&mut self) -> Option<String> {
+        let info = self.wbfm.as_mut()?.take_rds_update()?;
+        Some(
+            json!({
+                "type": "rds",
+                "pi": info.pi.map(|pi| format!("{pi:04X}")),
+                "pty": info.pty,
+                "tp": info.tp,
+                "ps": info.ps,
+                "rt": info.radiotext,
+            })
+            .to_string(),
+        )
+    }
+
+    /// JSON `subtone` message when the detected CTCSS tone or DCS code changed since the last
+    /// call; `tone` is `null` once it is gone.
+    pub fn take_subtone_update(&mut self) -> Option<String> {
+        let tone = self.subtone_update.take()?;
+        Some(json!({ "type": "subtone", "tone": tone }).to_string())
+    }
+
+    /// JSON `fsk` message with the RTTY or NAVTEX text decoded since the last call, if any.
+    pub fn take_fsk_text(&mut self) -> Option<String> {
+        let (params, _, decoder) = self.fsk.as_mut()?;
+        let text = decoder.take_text();
+        if text.is_empty() {
+            return None;
+        }
+        Some(json!({ "type": "fsk", "mode": params.mode.as_str(), "text": text }).to_string())
+    }
+
+    /// The next SSTV picture start, progress report or finished picture, if any.
+    /// Starts SSTV with the decoder `primed` delivers, which has already worked through the
+    /// replay buffer up to the frame number sent with it (`crate::replay::spawn_sstv_preroll`).
+    /// Until it arrives, demodulated audio is held back for it.
+    pub fn expect_primed_sstv(&mut self, primed: oneshot::Receiver<(sstv::Decoder, u64)>) {
+        self.sstv = None;
+        self.sstv_preroll = Some(SstvPreroll {
+            primed,
+            held: VecDeque::new(),
+        });
+    }
+
+    /// Passes the frame's demodulated audio to the SSTV decoder, or holds it while a primed
+    /// decoder is still on its way. Frames the primed decoder already saw are not fed again.
+    fn feed_sstv(&mut self, frame_num: u64) {
+        let half = self.audio_fft_size / 2;
+        if let Some(preroll) = self.sstv_preroll.as_mut() {
+            let (mut decoder, fed_until) = match preroll.primed.try_recv() {
+                Ok(primed) => primed,
+                Err(oneshot::error::TryRecvError::Empty) => {
+                    let max_held = SSTV_PREROLL_HOLD_SECS * self.audio_rate / half.max(1);
+                    if preroll.held.len() >= max_held {
+                        preroll.held.pop_front();
+                    }
+                    preroll
+                        .held
+                        .push_back((frame_num, self.real[..half].to_vec()));
+                    return;
+                }
+                // Nothing to prime from; start on the audio held back.
+                Err(oneshot::error::TryRecvError::Closed) => {
+                    (sstv::Decoder::new(self.audio_rate as f64), 0)
+                }
+            };
+            for (_, audio) in preroll.held.iter().filter(|(n, _)| *n > fed_until) {
+                decoder.process(audio);
+            }
+            if frame_num > fed_until {
+                decoder.process(&self.real[..half]);
+            }
+            self.sstv = Some(decoder);
+            self.sstv_preroll = None;
+            return;
+        }
+        let audio_rate = self.audio_rate as f64;
+        self.sstv
+            .get_or_insert_with(|| sstv::Decoder::new(audio_rate))
+            .process(&self.real[..half]);
+    }
+
+    pub fn audio_rate(&self) -> usize {
+        self.audio_rate
+    }
+
+    pub fn take_sstv_event(&mut self) -> Option<sstv::Event> {
+        self.sstv.as_mut()?.pop_event()
+    }
+
+    /// The next change to the digital voice call on the channel, if any.
+    pub fn take_digital_voice_update(&mut self) -> Option<DigitalVoiceCall> {
+        self.digital_voice.as_mut()?.take_update()
+    }
+
     fn apply_agc_settings(&mut self, params: &AudioParams) {
         let current = (
             params.agc_speed,
@@ -1252,11 +2525,133 @@ impl AudioPipeline {
     }
 }
 
+#[cfg(test)]
+mod golden;
+
 #[cfg(test)]
 mod pipeline_tests {
     use super::*;
     use realfft::RealFftPlanner;
 
+    fn test_params(low_latency: bool) -> AudioParams {
+        AudioParams {
+            l: 0,
+            m: 100.0,
+            r: 256,
+            mute: false,
+            squelch_enabled: false,
+            squelch_mode: Default::default(),
+            rds: false,
+            stereo: false,
+            digital_voice: false,
+            subtone: false,
+            tone_squelch: None,
+            sstv: false,
+            fsk: None,
+            test_tone: None,
+            demodulation: DemodulationMode::Usb,
+            agc_speed: AgcSpeed::Off,
+            agc_attack_ms: None,
+            agc_release_ms: None,
+            filter_sharpness: FilterSharpness::Normal,
+            low_latency,
+            volume_db: 0.0,
+            hearing: Default::default(),
+        }
+    }
+
+    /// Payload count of every packet produced for `frames` demodulated frames.
+    fn packet_payloads(low_latency: bool, frames: u64) -> Vec<u16> {
+        let mut pipeline = AudioPipeline::new(48_000, 512, 1, AudioCompression::Adpcm).unwrap();
+        let spectrum: Vec<Complex32> = (0..256)
+            .map(|i| Complex32::from_polar(1.0, i as f32 * 0.3))
+            .collect();
+        let params = test_params(low_latency);
+        let mut payloads = Vec::new();
+        for frame_num in 0..frames {
+            for packet in pipeline
+                .process(&spectrum, frame_num, &params, false, 100, 1.0)
+                .unwrap()
+            {
+                // The payload count follows the 36 fixed header bytes.
+                payloads.push(u16::from_le_bytes([packet[36], packet[37]]));
+            }
+        }
+        payloads
+    }
+
+    #[test]
+    fn low_latency_sends_every_frame_in_its_own_packet() {
+        let default = packet_payloads(false, 16);
+        let low_latency = packet_payloads(true, 16);
+        assert!(default.len() < 8, "default packets: {default:?}");
+        assert_eq!(low_latency, vec![1; 16]);
+    }
+
+    #[test]
+    fn test_tone_replaces_audio_at_its_level_while_the_squelch_is_closed() {
+        let mut pipeline = AudioPipeline::new(48_000, 512, 1, AudioCompression::Adpcm).unwrap();
+        pipeline.set_raw_pcm(true);
+        let silence = vec![Complex32::new(0.0, 0.0); 256];
+        let mut params = test_params(false);
+        params.squelch_enabled = true;
+        params.volume_db = -30.0;
+        params.test_tone = Some(TestTone {
+            freq_hz: 1_000.0,
+            level_db: -20.0,
+        });
+        let mut peak = 0i16;
+        for frame_num in 0..8 {
+            for pcm in pipeline
+                .process(&silence, frame_num, &params, false, 100, 1.0)
+                .unwrap()
+            {
+                for s in pcm.chunks_exact(2) {
+                    peak = peak.max(i16::from_le_bytes([s[0], s[1]]));
+                }
+            }
+        }
+        // -20 dBFS is a tenth of full scale; volume and AGC leave it alone.
+        assert!((3_250..=3_280).contains(&peak), "peak {peak}");
+
+        params.test_tone = None;
+        let out = pipeline
+            .process(&silence, 8, &params, false, 100, 1.0)
+            .unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn primed_sstv_decoder_takes_over_from_the_held_audio() {
+        let mut pipeline = AudioPipeline::new(12_000, 512, 1, AudioCompression::Adpcm).unwrap();
+        let (primed_tx, primed_rx) = oneshot::channel();
+        pipeline.expect_primed_sstv(primed_rx);
+        for frame_num in 10..13 {
+            pipeline.feed_sstv(frame_num);
+        }
+        let held: Vec<u64> = pipeline
+            .sstv_preroll
+            .as_ref()
+            .map(|p| p.held.iter().map(|(n, _)| *n).collect())
+            .unwrap_or_default();
+        assert_eq!(held, [10, 11, 12]);
+        assert!(pipeline.sstv.is_none());
+
+        // The primed decoder read the buffer up to frame 11.
+        primed_tx.send((sstv::Decoder::new(12_000.0), 11)).ok();
+        pipeline.feed_sstv(13);
+        assert!(pipeline.sstv_preroll.is_none());
+        assert!(pipeline.sstv.is_some());
+
+        // A pre-roll that found nothing to prime from starts a fresh decoder.
+        let (primed_tx, primed_rx) = oneshot::channel();
+        pipeline.expect_primed_sstv(primed_rx);
+        drop(primed_tx);
+        pipeline.feed_sstv(14);
+        assert!(pipeline.sstv_preroll.is_none());
+        assert!(pipeline.sstv.is_some());
+    }
+
     #[test]
     fn realfft_inverse_is_unnormalized_like_fftw_backward() {
         // FFTW's BACKWARD inverse does not normalize by 1/N.
@@ -0,0 +1,308 @@
+//! Golden-output tests for the demodulators.
+//!
+//! Each case synthesizes complex IQ with known modulation, runs it through the CPU FFT engine
+//! and an [`AudioPipeline`] exactly like `dsp_runner` does, and compares the demodulated PCM with
+//! `tests/golden/<case>.pcm` (mono s16le). Differences up to [`MAX_SAMPLE_ERROR`] are accepted so
+//! that SIMD, FFT backend or summation-order changes do not need new goldens; anything larger is
+//! a behavior change. Run with `NOVASDR_UPDATE_GOLDEN=1` to rewrite the files after an intended
+//! change, and listen to or inspect the diff before committing them.
+
+use super::*;
+use crate::state::{AgcSpeed, AudioParams, FilterSharpness};
+use novasdr_core::config::Accelerator;
+use novasdr_core::dsp::fft::{FftEngine, FftSettings};
+use std::f32::consts::TAU;
+use std::path::PathBuf;
+
+/// Largest accepted per-sample difference from the golden output, in 16-bit LSB (about -60 dBFS).
+const MAX_SAMPLE_ERROR: i32 = 32;
+/// Largest accepted RMS difference from the golden output, in 16-bit LSB.
+const MAX_RMS_ERROR: f64 = 4.0;
+/// Demodulated frames per case.
+const FRAMES: usize = 24;
+/// Frames skipped before checking the tone, while AGC and overlap state settle.
+const SETTLE_FRAMES: usize = 6;
+const TONE_HZ: f32 = 1_000.0;
+
+struct Case {
+    name: &'static str,
+    mode: DemodulationMode,
+    sample_rate: usize,
+    fft_size: usize,
+    audio_rate: usize,
+    /// Carrier offset from the centre frequency, in Hz.
+    carrier_hz: f32,
+    /// Audio window relative to the carrier, in Hz.
+    window_hz: (f32, f32),
+    /// Complex envelope of the signal relative to the carrier at time `t` seconds.
+    envelope: fn(f32) -> Complex32,
+}
+
+impl Case {
+    fn audio_fft_size(&self) -> usize {
+        self.fft_size * self.audio_rate / self.sample_rate
+    }
+
+    fn wbfm_decimation(&self) -> usize {
+        novasdr_core::dsp::wbfm::TARGET_MPX_RATE
+            .div_ceil(self.audio_rate)
+            .clamp(1, (self.fft_size / self.audio_fft_size()).max(1))
+    }
+
+    fn bin_hz(&self) -> f32 {
+        self.sample_rate as f32 / self.fft_size as f32
+    }
+
+    /// Index into the runner's spectrum view, which starts at the most negative frequency.
+    fn spectrum_index(&self, hz: f32) -> i32 {
+        (hz / self.bin_hz()).round() as i32 + (self.fft_size / 2) as i32 - 1
+    }
+
+    fn params(&self) -> AudioParams {
+        let m = self.spectrum_index(self.carrier_hz);
+        let (l, r) = if self.mode == DemodulationMode::Wbfm {
+            let width = (self.audio_fft_size() * self.wbfm_decimation()) as i32;
+            (m - width / 2, m + width / 2)
+        } else {
+            (
+                self.spectrum_index(self.carrier_hz + self.window_hz.0),
+                self.spectrum_index(self.carrier_hz + self.window_hz.1),
+            )
+        };
+        AudioParams {
+            l,
+            m: m as f64,
+            r,
+            mute: false,
+            squelch_enabled: false,
+            squelch_mode: Default::default(),
+            rds: false,
+            stereo: false,
+            digital_voice: false,
+            subtone: false,
+            tone_squelch: None,
+            sstv: false,
+            fsk: None,
+            test_tone: None,
+            demodulation: self.mode,
+            agc_speed: AgcSpeed::Default,
+            agc_attack_ms: None,
+            agc_release_ms: None,
+            filter_sharpness: FilterSharpness::Normal,
+            low_latency: false,
+            volume_db: 0.0,
+            hearing: Default::default(),
+        }
+    }
+
+    /// Demodulated PCM for [`FRAMES`] frames.
+    fn run(&self) -> Vec<i16> {
+        let n = self.fft_size;
+        let half = n / 2;
+        let mut fft = FftEngine::new(FftSettings {
+            fft_size: n,
+            is_real: false,
+            brightness_offset: 0,
+            downsample_levels: 1,
+            audio_max_fft_size: self.audio_fft_size(),
+            accelerator: Accelerator::None,
+            vulkan_device: None,
+            dc_suppression: Default::default(),
+            window: Default::default(),
+        })
+        .expect("fft engine");
+        let mut pipeline = AudioPipeline::new(
+            self.audio_rate,
+            self.audio_fft_size(),
+            self.wbfm_decimation(),
+            AudioCompression::Adpcm,
+        )
+        .expect("audio pipeline");
+
+        let fs = self.sample_rate as f32;
+        let iq: Vec<Complex32> = (0..(FRAMES + 1) * half)
+            .map(|i| {
+                let t = i as f32 / fs;
+                // Wrap the carrier phase per sample to keep f32 precision over the whole run.
+                let cycles = (self.carrier_hz as f64 * i as f64 / fs as f64).fract() as f32;
+                (self.envelope)(t) * Complex32::from_polar(1.0, TAU * cycles)
+            })
+            .collect();
+
+        let params = self.params();
+        let (l, r) = (params.l as usize, params.r as usize);
+        let base_idx = half + 1;
+        let mut pcm = Vec::with_capacity(FRAMES * self.audio_fft_size() / 2);
+        for frame in 0..FRAMES {
+            fft.load_complex_half_a(&iq[frame * half..(frame + 1) * half]);
+            fft.load_complex_half_b(&iq[(frame + 1) * half..(frame + 2) * half]);
+            fft.execute(false).expect("fft");
+            let spectrum = fft.spectrum_for_audio();
+            let slice: Vec<Complex32> = (l..r).map(|k| spectrum[(k + base_idx) % n]).collect();
+            pipeline
+                .process(&slice, frame as u64, &params, false, params.m as i32, 1.0)
+                .expect("process");
+            pcm.extend_from_slice(&pipeline.pcm_frame_i16);
+        }
+        pcm
+    }
+}
+
+/// AM with 50% modulation depth.
+fn am_envelope_1k(t: f32) -> Complex32 {
+    Complex32::new(0.5 * (1.0 + 0.5 * (TAU * TONE_HZ * t).cos()), 0.0)
+}
+
+/// FM with `deviation_hz` peak deviation; the phase is the integral of the deviation.
+fn fm_envelope(t: f32, deviation_hz: f32) -> Complex32 {
+    Complex32::from_polar(0.5, deviation_hz / TONE_HZ * (TAU * TONE_HZ * t).sin())
+}
+
+fn cases() -> Vec<Case> {
+    let hf = |name, mode, window_hz, envelope| Case {
+        name,
+        mode,
+        sample_rate: 96_000,
+        fft_size: 4096,
+        audio_rate: 12_000,
+        carrier_hz: 12_000.0,
+        window_hz,
+        envelope,
+    };
+    vec![
+        hf("usb", DemodulationMode::Usb, (100.0, 2_800.0), |t| {
+            Complex32::from_polar(0.5, TAU * TONE_HZ * t)
+        }),
+        hf("lsb", DemodulationMode::Lsb, (-2_800.0, -100.0), |t| {
+            Complex32::from_polar(0.5, -TAU * TONE_HZ * t)
+        }),
+        hf(
+            "am",
+            DemodulationMode::Am,
+            (-4_500.0, 4_500.0),
+            am_envelope_1k,
+        ),
+        hf(
+            "sam",
+            DemodulationMode::Sam,
+            (-4_500.0, 4_500.0),
+            am_envelope_1k,
+        ),
+        hf("fm", DemodulationMode::Fm, (-5_000.0, 5_000.0), |t| {
+            fm_envelope(t, 2_500.0)
+        }),
+        Case {
+            name: "wbfm",
+            mode: DemodulationMode::Wbfm,
+            sample_rate: 384_000,
+            fft_size: 8192,
+            audio_rate: 48_000,
+            carrier_hz: 40_000.0,
+            window_hz: (0.0, 0.0),
+            envelope: |t| fm_envelope(t, 75_000.0),
+        },
+    ]
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{name}.pcm"))
+}
+
+fn read_pcm(path: &std::path::Path) -> Option<Vec<i16>> {
+    let bytes = std::fs::read(path).ok()?;
+    Some(
+        bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect(),
+    )
+}
+
+fn write_pcm(path: &std::path::Path, pcm: &[i16]) {
+    let bytes: Vec<u8> = pcm.iter().flat_map(|s| s.to_le_bytes()).collect();
+    std::fs::write(path, bytes).expect("write golden file");
+}
+
+/// Share of the signal power at `hz` (Goertzel), so a regenerated golden still has to contain
+/// the modulating tone.
+fn tone_fraction(samples: &[i16], rate: usize, hz: f32) -> f64 {
+    let w = (TAU * hz / rate as f32) as f64;
+    let coeff = 2.0 * w.cos();
+    let (mut s1, mut s2) = (0.0f64, 0.0f64);
+    let mut energy = 0.0f64;
+    for &x in samples {
+        let x = x as f64;
+        let s0 = x + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+        energy += x * x;
+    }
+    let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+    if energy <= 0.0 {
+        return 0.0;
+    }
+    2.0 * power / (samples.len() as f64 * energy)
+}
+
+#[test]
+fn demodulators_match_golden_output() {
+    let update = std::env::var_os("NOVASDR_UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+    for case in cases() {
+        let pcm = case.run();
+        let settled = &pcm[SETTLE_FRAMES * case.audio_fft_size() / 2..];
+        let tone = tone_fraction(settled, case.audio_rate, TONE_HZ);
+        assert!(
+            tone > 0.5,
+            "{}: 1 kHz tone holds only {:.0}% of the output power",
+            case.name,
+            tone * 100.0
+        );
+
+        let path = golden_path(case.name);
+        if update {
+            write_pcm(&path, &pcm);
+            continue;
+        }
+        let Some(golden) = read_pcm(&path) else {
+            failures.push(format!(
+                "{}: missing {} (run with NOVASDR_UPDATE_GOLDEN=1)",
+                case.name,
+                path.display()
+            ));
+            continue;
+        };
+        if golden.len() != pcm.len() {
+            failures.push(format!(
+                "{}: {} samples, golden has {}",
+                case.name,
+                pcm.len(),
+                golden.len()
+            ));
+            continue;
+        }
+        let max_error = pcm
+            .iter()
+            .zip(&golden)
+            .map(|(&a, &b)| (a as i32 - b as i32).abs())
+            .max()
+            .unwrap_or(0);
+        let rms_error = (pcm
+            .iter()
+            .zip(&golden)
+            .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+            .sum::<f64>()
+            / pcm.len() as f64)
+            .sqrt();
+        if max_error > MAX_SAMPLE_ERROR || rms_error > MAX_RMS_ERROR {
+            failures.push(format!(
+                "{}: max error {max_error} LSB, rms error {rms_error:.2} LSB",
+                case.name
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,162 @@
+use super::audio::AudioPipeline;
+use crate::state::{AppState, ReceiverState};
+use novasdr_core::config::AudioCompression;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Ready pipelines kept per shape, so the next connect or receiver switch never builds one inline.
+const STANDBY_PER_SHAPE: usize = 1;
+/// Idle pipelines kept per shape; pipelines returned beyond this (after a burst of disconnects)
+/// are dropped.
+const MAX_IDLE_PER_SHAPE: usize = 4;
+
+/// Everything that decides how an [`AudioPipeline`] is built. Receivers with the same shape share
+/// pooled pipelines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineShape {
+    pub sample_rate: usize,
+    pub audio_fft_size: usize,
+    pub wbfm_decimation: usize,
+    pub compression: AudioCompression,
+}
+
+impl PipelineShape {
+    pub fn of(receiver: &ReceiverState) -> Self {
+        Self {
+            sample_rate: receiver.rt.audio_max_sps as usize,
+            audio_fft_size: receiver.rt.audio_max_fft_size,
+            wbfm_decimation: receiver.rt.wbfm_decimation,
+            compression: receiver.receiver.input.audio_compression,
+        }
+    }
+
+    fn build(self) -> anyhow::Result<AudioPipeline> {
+        AudioPipeline::new(
+            self.sample_rate,
+            self.audio_fft_size,
+            self.wbfm_decimation,
+            self.compression,
+        )
+    }
+}
+
+/// Pre-built audio pipelines shared by all audio clients. Building a pipeline plans FFTs and
+/// creates the codec encoder, which is slow enough to leave an audible gap on a receiver switch;
+/// with the pool, switch-time setup is a [`AudioPipeline::reset`].
+#[derive(Default)]
+pub struct AudioPipelinePool {
+    idle: Mutex<HashMap<PipelineShape, Vec<AudioPipeline>>>,
+}
+
+impl AudioPipelinePool {
+    fn idle(&self) -> MutexGuard<'_, HashMap<PipelineShape, Vec<AudioPipeline>>> {
+        match self.idle.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!("audio pipeline pool mutex poisoned; recovering");
+                poisoned.into_inner()
+            }
+        }
+    }
+
+    fn take(&self, shape: PipelineShape) -> Option<AudioPipeline> {
+        self.idle().get_mut(&shape).and_then(Vec::pop)
+    }
+
+    fn put(&self, shape: PipelineShape, pipeline: AudioPipeline) {
+        let mut idle = self.idle();
+        let slot = idle.entry(shape).or_default();
+        if slot.len() < MAX_IDLE_PER_SHAPE {
+            slot.push(pipeline);
+        }
+    }
+
+    pub fn idle_count(&self, shape: PipelineShape) -> usize {
+        self.idle().get(&shape).map_or(0, Vec::len)
+    }
+
+    /// Hands a pipeline back once its client disconnects or switches away.
+    pub fn release(&self, shape: PipelineShape, mut pipeline: AudioPipeline) {
+        pipeline.reset();
+        self.put(shape, pipeline);
+    }
+
+    /// Builds pipelines until `shape` has its standby quota. Blocking; see [`spawn_refill`].
+    pub fn fill(&self, shape: PipelineShape) -> anyhow::Result<()> {
+        while self.idle_count(shape) < STANDBY_PER_SHAPE {
+            let pipeline = shape.build()?;
+            self.put(shape, pipeline);
+        }
+        Ok(())
+    }
+}
+
+/// Takes a ready pipeline for `shape` and schedules a replacement. Falls back to building one on
+/// the blocking pool when the standby has not been rebuilt yet.
+pub async fn acquire(state: &Arc<AppState>, shape: PipelineShape) -> anyhow::Result<AudioPipeline> {
+    let pipeline = match state.audio_pipelines.take(shape) {
+        Some(p) => p,
+        None => tokio::task::spawn_blocking(move || shape.build()).await??,
+    };
+    spawn_refill(state.clone(), shape);
+    Ok(pipeline)
+}
+
+/// Tops the standby for `shape` back up without holding up the caller.
+pub fn spawn_refill(state: Arc<AppState>, shape: PipelineShape) {
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = state.audio_pipelines.fill(shape) {
+            tracing::warn!(?shape, error = ?e, "failed to build standby audio pipeline");
+        }
+    });
+}
+
+/// Builds a standby pipeline for every configured receiver, so the first listener on each one
+/// (and every switch to it) starts from a warm pipeline.
+pub fn spawn_warmup(state: Arc<AppState>) {
+    let shapes: HashSet<PipelineShape> = state
+        .receivers
+        .values()
+        .map(|r| PipelineShape::of(r))
+        .collect();
+    for shape in shapes {
+        spawn_refill(state.clone(), shape);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape() -> PipelineShape {
+        PipelineShape {
+            sample_rate: 12_000,
+            audio_fft_size: 512,
+            wbfm_decimation: 1,
+            compression: AudioCompression::Adpcm,
+        }
+    }
+
+    #[test]
+    fn fill_builds_standby_and_take_drains_it() {
+        let pool = AudioPipelinePool::default();
+        pool.fill(shape()).expect("build");
+        assert_eq!(pool.idle_count(shape()), STANDBY_PER_SHAPE);
+        assert!(pool.take(shape()).is_some());
+        assert_eq!(pool.idle_count(shape()), STANDBY_PER_SHAPE - 1);
+        let other = PipelineShape {
+            audio_fft_size: 1024,
+            ..shape()
+        };
+        assert!(pool.take(other).is_none());
+    }
+
+    #[test]
+    fn release_caps_idle_pipelines() {
+        let pool = AudioPipelinePool::default();
+        for _ in 0..MAX_IDLE_PER_SHAPE + 2 {
+            pool.release(shape(), shape().build().expect("build"));
+        }
+        assert_eq!(pool.idle_count(shape()), MAX_IDLE_PER_SHAPE);
+    }
+}
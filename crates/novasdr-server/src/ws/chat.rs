@@ -1,55 +1,53 @@
-use crate::state::{append_chat_message, AppState, ChatMessage};
+use crate::state::{append_chat_message, AppState, ChatClient, ChatMessage};
 use axum::{
     extract::connect_info::ConnectInfo,
     extract::{ws, State, WebSocketUpgrade},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
 use futures::{SinkExt, StreamExt};
 use novasdr_core::protocol::ClientCommand;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::mpsc;
 use tokio::time::Instant;
 
 pub async fn upgrade(
     ws: WebSocketUpgrade,
+    headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<Arc<AppState>>,
 ) -> axum::response::Response {
-    if !state.cfg.websdr.chat_enabled {
+    let Some(protocol) = super::subprotocol::negotiate(&headers, "chat") else {
+        return (StatusCode::BAD_REQUEST, "unsupported websocket subprotocol").into_response();
+    };
+    if !state.cfg().websdr.chat_enabled {
         return (StatusCode::NOT_FOUND, "chat disabled").into_response();
     }
-    let Some(ip_guard) = state.try_acquire_ws_ip(addr.ip()) else {
-        return (
-            StatusCode::TOO_MANY_REQUESTS,
-            "too many connections from this IP",
-        )
-            .into_response();
+    let ip_guard = match state.try_acquire_ws_ip(addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => return rejection.into_response(),
     };
-    ws.on_upgrade(|socket| handle(socket, state, ip_guard))
+    ws.protocols([protocol.as_str()])
+        .on_upgrade(move |socket| handle(socket, state, ip_guard, addr.ip(), protocol))
 }
 
-async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::state::WsIpGuard) {
+async fn handle(
+    socket: ws::WebSocket,
+    state: Arc<AppState>,
+    _ip_guard: crate::state::WsIpGuard,
+    ip: IpAddr,
+    protocol: super::subprotocol::ProtocolVersion,
+) {
     let client_id = state.alloc_client_id();
-    tracing::info!(client_id, "chat ws connected");
-    let (tx, mut rx) = crate::state::text_channel();
-    state.chat_clients.insert(client_id, tx);
+    tracing::info!(client_id, %protocol, "chat ws connected");
+    let (tx, rx) = crate::state::text_channel();
+    let client = Arc::new(ChatClient::new(tx));
+    state.chat_clients.insert(client_id, client.clone());
 
-    let history = {
-        let hist = state.chat_history.lock().await;
-        hist.clone()
-    };
-    let history_msg = match serde_json::to_string(&serde_json::json!({
-        "type": "history",
-        "messages": history
-    })) {
-        Ok(s) => s,
-        Err(e) => {
-            tracing::error!(client_id, error = ?e, "failed to serialize chat history");
-            "{\"type\":\"history\",\"messages\":[]}".to_string()
-        }
-    };
+    let history_msg = history_message(&state, None).await;
 
     let (mut ws_sender, mut ws_receiver) = socket.split();
     if ws_sender
@@ -61,30 +59,7 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
         return;
     }
 
-    let send_task = tokio::spawn(async move {
-        let mut ping_interval = tokio::time::interval(Duration::from_secs(30));
-        ping_interval.tick().await; // consume immediate first tick
-        loop {
-            tokio::select! {
-                biased;
-                Some(msg) = rx.recv() => {
-                    if ws_sender
-                        .send(ws::Message::Text(msg.as_ref().to_string()))
-                        .await
-                        .is_err()
-                    {
-                        break;
-                    }
-                }
-                _ = ping_interval.tick() => {
-                    if ws_sender.send(ws::Message::Ping(Vec::new())).await.is_err() {
-                        break;
-                    }
-                }
-                else => break,
-            }
-        }
-    });
+    let send_task = tokio::spawn(send_loop(ws_sender, rx, state.clone(), client));
 
     let mut window_start = Instant::now();
     let mut msgs_in_window: u32 = 0;
@@ -133,7 +108,38 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
         let Ok(cmd) = serde_json::from_str::<ClientCommand>(&txt) else {
             continue;
         };
-        if let ClientCommand::Chat {
+        if let ClientCommand::SuggestMarker {
+            frequency,
+            name,
+            mode,
+            user_id,
+            username,
+        } = cmd
+        {
+            let new = crate::marker_suggestions::NewSuggestion {
+                frequency,
+                name: filter_message(&name),
+                mode,
+                user_id: user_id.unwrap_or_else(|| format!("legacy_{client_id}")),
+                username: username.unwrap_or_default(),
+                ip,
+            };
+            let reply = match crate::marker_suggestions::submit(&state, new).await {
+                Ok(s) => serde_json::json!({
+                    "type": "marker_suggestion",
+                    "status": "queued",
+                    "id": s.id,
+                }),
+                Err(reason) => serde_json::json!({
+                    "type": "marker_suggestion",
+                    "status": "rejected",
+                    "reason": reason,
+                }),
+            };
+            if let Some(client) = state.chat_clients.get(&client_id) {
+                let _ = client.tx.try_send(Arc::from(reply.to_string()));
+            }
+        } else if let ClientCommand::Chat {
             message,
             username,
             user_id,
@@ -157,16 +163,7 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                     }
                 };
                 append_chat_message(&state, chat_msg.clone()).await;
-                let msg: Arc<str> = Arc::from(json_msg);
-                let mut dead = Vec::new();
-                for entry in state.chat_clients.iter() {
-                    if entry.value().try_send(msg.clone()).is_err() {
-                        dead.push(*entry.key());
-                    }
-                }
-                for id in dead {
-                    state.chat_clients.remove(&id);
-                }
+                broadcast(&state, Arc::from(json_msg)).await;
             }
         }
     }
@@ -176,6 +173,138 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
     send_task.abort();
 }
 
+/// Writes the client's queue to its socket, with a ping every 30 s. Once the queue of a
+/// lagging client has drained it is sent one history resync, so a missed message is caught up
+/// on even if nothing is said after it; the ping tick catches a miss counted just after.
+async fn send_loop<S>(
+    mut ws_sender: S,
+    mut rx: mpsc::Receiver<Arc<str>>,
+    state: Arc<AppState>,
+    client: Arc<ChatClient>,
+) where
+    S: futures::Sink<ws::Message> + Unpin,
+{
+    let mut ping_interval = tokio::time::interval(Duration::from_secs(30));
+    ping_interval.tick().await; // consume immediate first tick
+    loop {
+        tokio::select! {
+            biased;
+            Some(msg) = rx.recv() => {
+                if ws_sender
+                    .send(ws::Message::Text(msg.as_ref().to_string()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            _ = ping_interval.tick() => {
+                if ws_sender.send(ws::Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+            else => break,
+        }
+        if rx.is_empty() {
+            if let Some(resync) = take_resync(&state, &client).await {
+                if ws_sender.send(ws::Message::Text(resync)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// The history resync owed to `client`, if it missed messages.
+async fn take_resync(state: &AppState, client: &ChatClient) -> Option<String> {
+    let missed = client.missed.swap(0, Ordering::Relaxed);
+    if missed == 0 {
+        return None;
+    }
+    state
+        .chat_backpressure
+        .history_resyncs
+        .fetch_add(1, Ordering::Relaxed);
+    Some(history_message(state, Some(missed)).await)
+}
+
+async fn history_message(state: &AppState, missed: Option<u64>) -> String {
+    let history = {
+        let hist = state.chat_history.lock().await;
+        hist.clone()
+    };
+    let mut payload = serde_json::json!({
+        "type": "history",
+        "messages": history
+    });
+    if let Some(missed) = missed {
+        payload["missed"] = serde_json::json!(missed);
+    }
+    match serde_json::to_string(&payload) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::error!(error = ?e, "failed to serialize chat history");
+            "{\"type\":\"history\",\"messages\":[]}".to_string()
+        }
+    }
+}
+
+/// Fans a chat message out to all chat clients without disconnecting slow ones.
+///
+/// A full queue is retried once after yielding to the runtime. Clients that are still full are
+/// marked as lagging: they skip individual messages until their send task has drained the queue
+/// and sent them a single history snapshot carrying the number of messages they missed.
+async fn broadcast(state: &AppState, msg: Arc<str>) {
+    let metrics = &state.chat_backpressure;
+    let mut closed = Vec::new();
+    let mut full = Vec::new();
+
+    for entry in state.chat_clients.iter() {
+        let client = entry.value();
+        // While a resync is owed, the message is left to it: it is already in the history.
+        let lagging = client
+            .missed
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |m| {
+                (m > 0).then_some(m + 1)
+            })
+            .is_ok();
+        if lagging {
+            metrics.missed_messages.fetch_add(1, Ordering::Relaxed);
+            continue;
+        }
+        match client.tx.try_send(msg.clone()) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Closed(_)) => closed.push(*entry.key()),
+            Err(mpsc::error::TrySendError::Full(_)) => full.push((*entry.key(), client.clone())),
+        }
+    }
+
+    if !full.is_empty() {
+        metrics
+            .retried_sends
+            .fetch_add(full.len() as u64, Ordering::Relaxed);
+        tokio::task::yield_now().await;
+        for (client_id, client) in full {
+            match client.tx.try_send(msg.clone()) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Closed(_)) => closed.push(client_id),
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    client.missed.fetch_add(1, Ordering::Relaxed);
+                    metrics.missed_messages.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!(
+                        client_id,
+                        "chat client queue full; will resync from history"
+                    );
+                }
+            }
+        }
+    }
+
+    for id in closed {
+        state.chat_clients.remove(&id);
+    }
+}
+
 fn build_chat_message(
     user_id: &str,
     username: &str,
@@ -222,6 +351,20 @@ fn build_chat_message(
     })
 }
 
+/// Nickname for the presence list from a `presence` command; `None` opts the listener out.
+/// Follows the chat's username rules, except that a blocked name is refused rather than
+/// replaced.
+pub(crate) fn presence_nickname(raw: &str) -> Option<String> {
+    let nickname: String = raw
+        .trim()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(14)
+        .collect();
+    let nickname = nickname.trim();
+    (!nickname.is_empty() && !is_blocked_username(nickname)).then(|| nickname.to_string())
+}
+
 fn is_blocked_username(username: &str) -> bool {
     static BLOCKED: &[&str] = &["admin", "operator", "host", "root", "system", "moderator"];
     BLOCKED.iter().any(|w| w.eq_ignore_ascii_case(username))
@@ -262,3 +405,80 @@ fn filter_message(message: &str) -> String {
     }
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::ConfigPaths;
+
+    #[tokio::test]
+    async fn missed_last_message_is_resynced_without_another_message() {
+        let dir = std::env::temp_dir().join(format!(
+            "novasdr_chat_{}",
+            novasdr_core::util::generate_unique_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths = ConfigPaths {
+            config: dir.join("config.json"),
+            receivers: dir.join("receivers.json"),
+            overlays: dir.join("overlays"),
+        };
+        std::fs::write(
+            &paths.config,
+            r#"{ "server": { "port": 9002 }, "websdr": { "name": "chat test" }, "active_receiver_id": "rx0" }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &paths.receivers,
+            r#"{ "receivers": [
+  { "id": "rx0", "input": { "sps": 2048000, "frequency": 100000000, "signal": "iq",
+      "driver": { "kind": "stdin", "format": "u8" } } }
+] }"#,
+        )
+        .unwrap();
+        let cfg = novasdr_core::config::load_from_files(&paths.config, &paths.receivers).unwrap();
+        let state = Arc::new(AppState::new(Arc::new(cfg), dir.clone(), paths).unwrap());
+
+        let (tx, rx) = crate::state::text_channel();
+        let client = Arc::new(ChatClient::new(tx));
+        state.chat_clients.insert(1, client.clone());
+
+        // The socket is stuck, so the last message no longer fits the queue.
+        let capacity = client.tx.max_capacity();
+        for i in 0..=capacity {
+            let msg = build_chat_message(
+                "u1",
+                "tester",
+                &format!("message {i}"),
+                String::new(),
+                String::new(),
+            )
+            .unwrap();
+            state.chat_history.lock().await.push(msg.clone());
+            broadcast(&state, Arc::from(serde_json::to_string(&msg).unwrap())).await;
+        }
+        assert_eq!(client.missed.load(Ordering::Relaxed), 1);
+
+        // The socket drains, and nobody writes again.
+        let (sink, mut sent) = futures::channel::mpsc::unbounded();
+        tokio::spawn(send_loop(sink, rx, state.clone(), client.clone()));
+        let mut texts = Vec::new();
+        while texts.len() <= capacity {
+            let msg = tokio::time::timeout(Duration::from_secs(5), sent.next())
+                .await
+                .expect("sent in time")
+                .expect("send loop running");
+            if let ws::Message::Text(txt) = msg {
+                texts.push(txt);
+            }
+        }
+        let resync: serde_json::Value = serde_json::from_str(&texts[capacity]).unwrap();
+        assert_eq!(resync["type"], "history");
+        assert_eq!(resync["missed"], 1);
+        let last = resync["messages"].as_array().unwrap().last().unwrap();
+        assert_eq!(last["message"], format!("message {capacity}"));
+        assert_eq!(client.missed.load(Ordering::Relaxed), 0);
+        assert_eq!(state.chat_backpressure.totals(), [1, 1, 1]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::acars::Decoded;
+use crate::state::AppState;
+use axum::{
+    extract::connect_info::ConnectInfo,
+    extract::{ws, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DigitalQuery {
+    /// Only messages from this receiver; all of them when absent.
+    #[serde(default)]
+    receiver_id: Option<String>,
+}
+
+pub async fn upgrade(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DigitalQuery>,
+) -> axum::response::Response {
+    let Some(protocol) = super::subprotocol::negotiate(&headers, "digital") else {
+        return (StatusCode::BAD_REQUEST, "unsupported websocket subprotocol").into_response();
+    };
+    let ip_guard = match state.try_acquire_ws_ip(addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => return rejection.into_response(),
+    };
+    ws.protocols([protocol.as_str()])
+        .on_upgrade(move |socket| handle(socket, state, ip_guard, protocol, query.receiver_id))
+}
+
+async fn handle(
+    socket: ws::WebSocket,
+    state: Arc<AppState>,
+    _ip_guard: crate::state::WsIpGuard,
+    protocol: super::subprotocol::ProtocolVersion,
+    receiver_id: Option<String>,
+) {
+    let client_id = state.alloc_client_id();
+    tracing::info!(client_id, %protocol, "digital ws connected");
+    let (recent, mut rx) = state.acars_messages.subscribe();
+    let wanted = move |message: &Decoded| {
+        receiver_id
+            .as_deref()
+            .is_none_or(|id| id == message.receiver_id)
+    };
+
+    let (mut ws_sender, mut ws_receiver) = socket.split();
+    let send_task = tokio::spawn(async move {
+        for message in recent.iter().filter(|m| wanted(m)) {
+            if ws_sender
+                .send(ws::Message::Text(message.json.clone()))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+        let mut ping_interval = tokio::time::interval(Duration::from_secs(30));
+        ping_interval.tick().await; // consume immediate first tick
+        loop {
+            tokio::select! {
+                biased;
+                res = rx.recv() => {
+                    let message = match res {
+                        Ok(message) => message,
+                        // A client that fell behind just misses some.
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    };
+                    if wanted(&message)
+                        && ws_sender.send(ws::Message::Text(message.json.clone())).await.is_err()
+                    {
+                        break;
+                    }
+                }
+                _ = ping_interval.tick() => {
+                    if ws_sender.send(ws::Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let idle_timeout = Duration::from_secs(90);
+    loop {
+        let maybe_msg = match tokio::time::timeout(idle_timeout, ws_receiver.next()).await {
+            Ok(v) => v,
+            Err(_) => {
+                tracing::info!(client_id, "digital ws idle timeout");
+                break;
+            }
+        };
+        let Some(Ok(msg)) = maybe_msg else {
+            break;
+        };
+        if matches!(msg, ws::Message::Close(_)) {
+            break;
+        }
+    }
+
+    tracing::info!(client_id, "digital ws disconnected");
+    send_task.abort();
+}
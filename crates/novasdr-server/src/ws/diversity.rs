@@ -0,0 +1,607 @@
+use crate::audio_listener::HeadlessListener;
+use crate::state::{AppState, ReceiverState};
+use axum::{
+    extract::connect_info::ConnectInfo,
+    extract::{ws, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use futures::{SinkExt, StreamExt};
+use interop::opus;
+use novasdr_core::dsp::demod::DemodulationMode;
+use novasdr_core::protocol::DiversityCommand;
+use num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Length of one sent frame.
+const FRAME_MS: usize = 20;
+/// Large enough for one 20 ms Opus packet.
+const OPUS_PACKET_MAX: usize = 4000;
+/// Shortest stretch of audio an offset is estimated from, in samples.
+const MIN_WINDOW: usize = 4096;
+/// Normalized correlation below which an estimate is ignored: one receiver hears only noise,
+/// or the band is quiet.
+const MIN_CORRELATION: f32 = 0.4;
+/// Offsets up to this are left alone rather than chased back and forth, in ms.
+const TOLERANCE_MS: f32 = 0.5;
+/// Audio one receiver may run ahead while the other delivers nothing before the silent one is
+/// padded, in ms; it stalled or is restarting its input.
+const MAX_BACKLOG_MS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// `a` and `b` mixed by the balance, mono.
+    Mix,
+    /// `a` left, `b` right.
+    Stereo,
+}
+
+impl Output {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "mix" => Some(Self::Mix),
+            "stereo" => Some(Self::Stereo),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Mix => "mix",
+            Self::Stereo => "stereo",
+        }
+    }
+
+    fn channels(self) -> usize {
+        match self {
+            Self::Mix => 1,
+            Self::Stereo => 2,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DiversityQuery {
+    a: String,
+    b: String,
+    /// Tuned frequency in Hz; receiver `a`'s default when absent.
+    #[serde(default)]
+    frequency: Option<i64>,
+    #[serde(default)]
+    mode: Option<String>,
+    /// `mix` (default) or `stereo`.
+    #[serde(default)]
+    output: Option<String>,
+    #[serde(default)]
+    balance: Option<f32>,
+    /// `opus` (default) or `pcm` (s16le).
+    #[serde(default)]
+    codec: Option<String>,
+}
+
+/// Pairs the two receivers' audio sample by sample. Their pipelines run behind each other by
+/// an unknown and drifting amount (input buffering, FFT sizes, separate devices), so the
+/// offset is measured by cross-correlating the envelopes of the paired output, and whichever
+/// receiver runs ahead is delayed.
+struct Aligner {
+    queues: [VecDeque<f32>; 2],
+    /// Recently paired samples, for the next estimate.
+    history: [Vec<f32>; 2],
+    window: usize,
+    max_lag: usize,
+    tolerance: usize,
+    max_backlog: usize,
+    /// Delay of `b` against `a` in samples; negative when `a` is delayed.
+    offset: i64,
+    fft: Arc<dyn Fft<f32>>,
+    ifft: Arc<dyn Fft<f32>>,
+    update: Option<(i64, f32)>,
+}
+
+impl Aligner {
+    fn new(sample_rate: usize, max_offset_ms: u32) -> Self {
+        let max_lag = (sample_rate * max_offset_ms as usize / 1000).max(1);
+        let window = (4 * max_lag).next_power_of_two().max(MIN_WINDOW);
+        let mut planner = FftPlanner::new();
+        Self {
+            queues: [VecDeque::new(), VecDeque::new()],
+            history: [Vec::with_capacity(window), Vec::with_capacity(window)],
+            window,
+            max_lag,
+            tolerance: (sample_rate as f32 * TOLERANCE_MS / 1000.0) as usize,
+            max_backlog: sample_rate * MAX_BACKLOG_MS / 1000,
+            offset: 0,
+            fft: planner.plan_fft_forward(2 * window),
+            ifft: planner.plan_fft_inverse(2 * window),
+            update: None,
+        }
+    }
+
+    /// Little-endian `i16` PCM of receiver `side` (0 for `a`).
+    fn push(&mut self, side: usize, pcm: &[u8]) {
+        self.queues[side].extend(
+            pcm.chunks_exact(2)
+                .map(|b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0),
+        );
+    }
+
+    /// The next `len` samples of both receivers, once both have them.
+    fn pop(&mut self, len: usize) -> Option<[Vec<f32>; 2]> {
+        for side in 0..2 {
+            let other = 1 - side;
+            if self.queues[side].len() >= len + self.max_backlog && self.queues[other].len() < len {
+                let missing = len - self.queues[other].len();
+                self.queues[other].extend(std::iter::repeat_n(0.0, missing));
+            }
+        }
+        if self.queues.iter().any(|q| q.len() < len) {
+            return None;
+        }
+        let pair = [
+            self.queues[0].drain(..len).collect::<Vec<f32>>(),
+            self.queues[1].drain(..len).collect::<Vec<f32>>(),
+        ];
+        for (history, samples) in self.history.iter_mut().zip(pair.iter()) {
+            let room = self.window - history.len();
+            history.extend_from_slice(&samples[..room.min(samples.len())]);
+        }
+        if self.history[0].len() == self.window {
+            self.estimate();
+            self.history[0].clear();
+            self.history[1].clear();
+        }
+        Some(pair)
+    }
+
+    /// The offset and correlation of the last estimate that moved the alignment.
+    fn take_update(&mut self) -> Option<(i64, f32)> {
+        self.update.take()
+    }
+
+    fn estimate(&mut self) {
+        let Some((lag, correlation)) = self.residual_lag() else {
+            return;
+        };
+        if correlation < MIN_CORRELATION || lag.unsigned_abs() as usize <= self.tolerance {
+            return;
+        }
+        let old = delays(self.offset);
+        self.offset = (self.offset + lag).clamp(-(self.max_lag as i64), self.max_lag as i64);
+        let new = delays(self.offset);
+        for (queue, (old, new)) in self.queues.iter_mut().zip(old.into_iter().zip(new)) {
+            if new > old {
+                for _ in old..new {
+                    queue.push_front(0.0);
+                }
+            } else {
+                let drop = (old - new).min(queue.len());
+                queue.drain(..drop);
+            }
+        }
+        self.update = Some((self.offset, correlation));
+    }
+
+    /// How many samples `b` is ahead of `a` in the history (negative when behind), and the
+    /// normalized correlation there.
+    fn residual_lag(&self) -> Option<(i64, f32)> {
+        let n = 2 * self.window;
+        let envelope = |samples: &[f32]| {
+            let mean = samples.iter().map(|x| x.abs()).sum::<f32>() / samples.len() as f32;
+            let mut buf: Vec<Complex32> = samples
+                .iter()
+                .map(|x| Complex32::new(x.abs() - mean, 0.0))
+                .collect();
+            let energy: f32 = buf.iter().map(|z| z.re * z.re).sum();
+            buf.resize(n, Complex32::new(0.0, 0.0));
+            (buf, energy)
+        };
+        let (mut a, energy_a) = envelope(&self.history[0]);
+        let (mut b, energy_b) = envelope(&self.history[1]);
+        let norm = (energy_a * energy_b).sqrt();
+        if norm <= f32::EPSILON {
+            return None;
+        }
+        self.fft.process(&mut a);
+        self.fft.process(&mut b);
+        // r[k] = sum a[i + k] b[i]: peaks at k when `a` repeats `b` k samples later.
+        let mut r: Vec<Complex32> = a.iter().zip(b.iter()).map(|(x, y)| x * y.conj()).collect();
+        self.ifft.process(&mut r);
+        let max_lag = self.max_lag as i64;
+        let (lag, value) = (-max_lag..=max_lag)
+            .map(|k| (k, r[k.rem_euclid(n as i64) as usize].re))
+            .max_by(|x, y| x.1.total_cmp(&y.1))?;
+        Some((lag, value / n as f32 / norm))
+    }
+}
+
+/// Delays of `a` and `b` in samples for `offset`.
+fn delays(offset: i64) -> [usize; 2] {
+    if offset >= 0 {
+        [0, offset as usize]
+    } else {
+        [offset.unsigned_abs() as usize, 0]
+    }
+}
+
+enum Encoder {
+    Opus {
+        encoder: opus::Encoder,
+        packet: Vec<u8>,
+    },
+    Pcm,
+}
+
+impl Encoder {
+    fn new(opus: bool, sample_rate: usize, output: Output) -> anyhow::Result<Self> {
+        if !opus {
+            return Ok(Self::Pcm);
+        }
+        let channels = match output {
+            Output::Mix => opus::Channels::Mono,
+            Output::Stereo => opus::Channels::Stereo,
+        };
+        Ok(Self::Opus {
+            encoder: crate::ws::audio::new_opus_encoder(sample_rate, channels)?,
+            packet: vec![0; OPUS_PACKET_MAX],
+        })
+    }
+
+    /// Interleaved samples of one frame.
+    fn encode(&mut self, samples: &[i16]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Opus { encoder, packet } => {
+                let size = encoder
+                    .encode(samples, packet)
+                    .map_err(|e| anyhow::anyhow!("Opus encode error: {e}"))?;
+                Ok(packet[..size].to_vec())
+            }
+            Self::Pcm => Ok(samples.iter().flat_map(|s| s.to_le_bytes()).collect()),
+        }
+    }
+}
+
+/// One frame of output: the balance mix, or `a` and `b` interleaved.
+fn render(pair: &[Vec<f32>; 2], output: Output, balance: f32) -> Vec<i16> {
+    let [a, b] = pair;
+    match output {
+        Output::Mix => a
+            .iter()
+            .zip(b.iter())
+            .map(|(x, y)| crate::schedule::to_i16((1.0 - balance) * x + balance * y))
+            .collect(),
+        Output::Stereo => a
+            .iter()
+            .zip(b.iter())
+            .flat_map(|(x, y)| [crate::schedule::to_i16(*x), crate::schedule::to_i16(*y)])
+            .collect(),
+    }
+}
+
+struct Session {
+    receivers: [Arc<ReceiverState>; 2],
+    frequency: i64,
+    mode: DemodulationMode,
+    output: Output,
+    balance: f32,
+    opus: bool,
+    sample_rate: usize,
+    max_offset_ms: u32,
+}
+
+impl Session {
+    fn status(&self, aligner: &Aligner) -> ws::Message {
+        ws::Message::Text(
+            json!({
+                "type": "diversity",
+                "a": self.receivers[0].receiver.id,
+                "b": self.receivers[1].receiver.id,
+                "frequency": self.frequency,
+                "mode": self.mode.as_str(),
+                "output": self.output.as_str(),
+                "balance": self.balance,
+                "codec": if self.opus { "opus" } else { "pcm" },
+                "sample_rate": self.sample_rate,
+                "channels": self.output.channels(),
+                "offset_ms": aligner.offset as f64 * 1000.0 / self.sample_rate as f64,
+            })
+            .to_string(),
+        )
+    }
+}
+
+fn error(reason: &str) -> ws::Message {
+    ws::Message::Text(json!({ "type": "diversity", "error": reason }).to_string())
+}
+
+pub async fn upgrade(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DiversityQuery>,
+) -> axum::response::Response {
+    let Some(protocol) = super::subprotocol::negotiate(&headers, "diversity") else {
+        return (StatusCode::BAD_REQUEST, "unsupported websocket subprotocol").into_response();
+    };
+    let cfg = state.cfg();
+    if !cfg.diversity.allows(&query.a, &query.b) {
+        return (StatusCode::NOT_FOUND, "not a diversity pair").into_response();
+    }
+    let (Some(a), Some(b)) = (
+        state.receiver_state(&query.a).cloned(),
+        state.receiver_state(&query.b).cloned(),
+    ) else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    if a.rt.audio_max_sps != b.rt.audio_max_sps {
+        return (StatusCode::CONFLICT, "the receivers' audio rates differ").into_response();
+    }
+    let settings = a.settings();
+    let frequency = query
+        .frequency
+        .unwrap_or_else(|| a.bin_frequency(settings.default_m));
+    if !a.covers(frequency) || !b.covers(frequency) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "frequency outside one of the receivers' bands",
+        )
+            .into_response();
+    }
+    let mode_raw = query.mode.as_deref().unwrap_or(&settings.default_mode_str);
+    let Some(mode) = DemodulationMode::from_str_upper(&mode_raw.to_ascii_uppercase()) else {
+        return (StatusCode::BAD_REQUEST, "unknown mode").into_response();
+    };
+    let Some(output) = Output::parse(query.output.as_deref().unwrap_or("mix")) else {
+        return (StatusCode::BAD_REQUEST, "output must be mix or stereo").into_response();
+    };
+    let opus = match query.codec.as_deref().unwrap_or("opus") {
+        "opus" => true,
+        "pcm" => false,
+        _ => return (StatusCode::BAD_REQUEST, "codec must be opus or pcm").into_response(),
+    };
+    // A diversity listener is an audio client of each receiver.
+    if state.total_audio_clients() + 2 > cfg.limits.audio || a.audio_full() || b.audio_full() {
+        return (StatusCode::TOO_MANY_REQUESTS, "too many audio clients").into_response();
+    }
+    let ip_guard = match state.try_acquire_ws_ip(addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let quota = match crate::quota::acquire_audio(&state, addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => {
+            return (StatusCode::TOO_MANY_REQUESTS, rejection.message()).into_response();
+        }
+    };
+    let sample_rate = a.rt.audio_max_sps as usize;
+    let session = Session {
+        receivers: [a, b],
+        frequency,
+        mode,
+        output,
+        balance: query.balance.unwrap_or(0.5).clamp(0.0, 1.0),
+        opus,
+        sample_rate,
+        max_offset_ms: cfg.diversity.max_offset_ms,
+    };
+    ws.protocols([protocol.as_str()])
+        .on_upgrade(move |socket| async move {
+            let _ip_guard = ip_guard;
+            let client_id = state.alloc_client_id();
+            tracing::info!(client_id, ip = %addr.ip(), a = %query.a, b = %query.b, "diversity ws connected");
+            if let Err(e) = handle(socket, &state, session, quota).await {
+                tracing::debug!(client_id, error = ?e, "diversity ws ended");
+            }
+            tracing::info!(client_id, "diversity ws disconnected");
+        })
+}
+
+async fn handle(
+    socket: ws::WebSocket,
+    state: &Arc<AppState>,
+    mut session: Session,
+    mut quota: crate::quota::AudioQuotaGuard,
+) -> anyhow::Result<()> {
+    let [a, b] = session.receivers.clone();
+    let (listener_a, mut pcm_a) =
+        HeadlessListener::start(state.clone(), a, session.frequency, session.mode).await?;
+    let (listener_b, mut pcm_b) =
+        match HeadlessListener::start(state.clone(), b, session.frequency, session.mode).await {
+            Ok(v) => v,
+            Err(e) => {
+                listener_a.stop().await;
+                return Err(e);
+            }
+        };
+    let result = run(
+        socket,
+        &mut session,
+        [&listener_a, &listener_b],
+        [&mut pcm_a, &mut pcm_b],
+        &mut quota,
+    )
+    .await;
+    listener_a.stop().await;
+    listener_b.stop().await;
+    result
+}
+
+async fn run(
+    socket: ws::WebSocket,
+    session: &mut Session,
+    listeners: [&HeadlessListener; 2],
+    pcm: [&mut mpsc::Receiver<Vec<u8>>; 2],
+    quota: &mut crate::quota::AudioQuotaGuard,
+) -> anyhow::Result<()> {
+    let [pcm_a, pcm_b] = pcm;
+    let mut aligner = Aligner::new(session.sample_rate, session.max_offset_ms);
+    let mut encoder = Encoder::new(session.opus, session.sample_rate, session.output)?;
+    let frame = session.sample_rate * FRAME_MS / 1000;
+    let (mut sender, mut messages) = socket.split();
+    sender.send(session.status(&aligner)).await?;
+    loop {
+        tokio::select! {
+            chunk = pcm_a.recv() => {
+                let Some(chunk) = chunk else { return Ok(()) };
+                aligner.push(0, &chunk);
+            }
+            chunk = pcm_b.recv() => {
+                let Some(chunk) = chunk else { return Ok(()) };
+                aligner.push(1, &chunk);
+            }
+            true = async { quota.exhausted.wait_for(|exhausted| *exhausted).await.is_ok() } => {
+                let msg = json!({ "type": "quota_exceeded" });
+                sender.send(ws::Message::Text(msg.to_string())).await?;
+                return Ok(());
+            }
+            msg = messages.next() => {
+                let text = match msg {
+                    Some(Ok(ws::Message::Text(text))) => text,
+                    Some(Ok(ws::Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                    Some(Ok(_)) => continue,
+                };
+                let Ok(command) = serde_json::from_str::<DiversityCommand>(&text) else {
+                    continue;
+                };
+                match command {
+                    DiversityCommand::Tune { frequency, mode } => {
+                        let mode = match mode.as_deref() {
+                            None => Some(session.mode),
+                            Some(m) => DemodulationMode::from_str_upper(&m.to_ascii_uppercase()),
+                        };
+                        let Some(mode) = mode else {
+                            sender.send(error("unknown mode")).await?;
+                            continue;
+                        };
+                        if !session.receivers.iter().all(|r| r.covers(frequency)) {
+                            sender.send(error("frequency outside one of the receivers' bands")).await?;
+                            continue;
+                        }
+                        session.frequency = frequency;
+                        session.mode = mode;
+                        for listener in listeners {
+                            listener.retune(frequency, mode);
+                        }
+                    }
+                    DiversityCommand::Balance { value } => {
+                        if !value.is_finite() {
+                            continue;
+                        }
+                        session.balance = value.clamp(0.0, 1.0);
+                    }
+                    DiversityCommand::Output { value } => {
+                        let Some(output) = Output::parse(&value) else {
+                            sender.send(error("output must be mix or stereo")).await?;
+                            continue;
+                        };
+                        if output != session.output {
+                            session.output = output;
+                            encoder = Encoder::new(session.opus, session.sample_rate, output)?;
+                        }
+                    }
+                }
+                sender.send(session.status(&aligner)).await?;
+                continue;
+            }
+        }
+        while let Some(pair) = aligner.pop(frame) {
+            let samples = render(&pair, session.output, session.balance);
+            sender
+                .send(ws::Message::Binary(encoder.encode(&samples)?))
+                .await?;
+        }
+        if let Some((offset, correlation)) = aligner.take_update() {
+            tracing::debug!(offset, correlation, "diversity alignment moved");
+            sender.send(session.status(&aligner)).await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Speech-like test audio: a tone keyed by an irregular envelope.
+    fn signal(len: usize) -> Vec<f32> {
+        let mut state = 0x1234_5678u32;
+        let mut level = 0.0f32;
+        (0..len)
+            .map(|n| {
+                if n % 400 == 0 {
+                    state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    level = (state >> 24) as f32 / 255.0;
+                }
+                0.5 * level * (n as f32 * 0.3).sin()
+            })
+            .collect()
+    }
+
+    fn pcm(samples: &[f32]) -> Vec<u8> {
+        samples
+            .iter()
+            .flat_map(|x| ((x * 32767.0) as i16).to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn the_receiver_running_ahead_is_delayed() {
+        let sample_rate = 12_000;
+        let lead = 300;
+        let source = signal(sample_rate * 10);
+        let mut aligner = Aligner::new(sample_rate, 250);
+        // `b` delivers the same audio `lead` samples before `a`.
+        let a: Vec<f32> = std::iter::repeat_n(0.0, lead)
+            .chain(source.iter().copied())
+            .collect();
+        let mut out: [Vec<f32>; 2] = [Vec::new(), Vec::new()];
+        for (chunk_a, chunk_b) in a.chunks(240).zip(source.chunks(240)) {
+            aligner.push(0, &pcm(chunk_a));
+            aligner.push(1, &pcm(chunk_b));
+            while let Some(pair) = aligner.pop(240) {
+                out[0].extend(pair[0].iter());
+                out[1].extend(pair[1].iter());
+            }
+        }
+        assert_eq!(aligner.offset, lead as i64);
+        let (offset, correlation) = aligner.take_update().unwrap();
+        assert_eq!(offset, lead as i64);
+        assert!(correlation > 0.9, "{correlation}");
+        let tail = out[0].len() - 4096;
+        let error: f32 = out[0][tail..]
+            .iter()
+            .zip(&out[1][tail..])
+            .map(|(x, y)| (x - y).abs())
+            .fold(0.0, f32::max);
+        assert!(error < 1e-3, "{error}");
+    }
+
+    #[test]
+    fn a_silent_receiver_is_padded_after_the_backlog() {
+        let mut aligner = Aligner::new(12_000, 250);
+        aligner.push(0, &pcm(&signal(12_000)));
+        let pair = aligner.pop(240).unwrap();
+        assert_eq!(pair[1], vec![0.0; 240]);
+        assert!(Aligner::new(12_000, 250).pop(240).is_none());
+    }
+
+    #[test]
+    fn mix_follows_the_balance_and_stereo_interleaves() {
+        let pair = [vec![0.5, 0.5], vec![-0.5, 0.25]];
+        assert_eq!(
+            render(&pair, Output::Mix, 0.0)[1],
+            crate::schedule::to_i16(0.5)
+        );
+        let all_b = render(&pair, Output::Mix, 1.0);
+        assert_eq!(all_b[0], crate::schedule::to_i16(-0.5));
+        let stereo = render(&pair, Output::Stereo, 0.5);
+        assert_eq!(stereo.len(), 4);
+        assert_eq!(stereo[1], crate::schedule::to_i16(-0.5));
+        assert_eq!(stereo[3], crate::schedule::to_i16(0.25));
+    }
+}
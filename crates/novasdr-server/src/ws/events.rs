@@ -1,41 +1,66 @@
-use crate::state::AppState;
+use crate::state::{AppState, EventClient};
 use axum::{
     extract::connect_info::ConnectInfo,
-    extract::{ws, State, WebSocketUpgrade},
-    http::StatusCode,
+    extract::{ws, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
 use futures::{SinkExt, StreamExt};
+use novasdr_core::protocol::EventTopics;
+use serde::Deserialize;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    /// Comma-separated topics (`users`, `bandwidth`, `dsp`, `chat`, `maintenance`, `overlays`);
+    /// all of them when absent.
+    #[serde(default)]
+    topics: Option<String>,
+}
+
 pub async fn upgrade(
     ws: WebSocketUpgrade,
+    headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<Arc<AppState>>,
+    Query(query): Query<EventsQuery>,
 ) -> axum::response::Response {
-    let Some(ip_guard) = state.try_acquire_ws_ip(addr.ip()) else {
-        return (
-            StatusCode::TOO_MANY_REQUESTS,
-            "too many connections from this IP",
-        )
-            .into_response();
+    let topics = match EventTopics::parse(query.topics.as_deref()) {
+        Ok(t) => t,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+    let Some(protocol) = super::subprotocol::negotiate(&headers, "events") else {
+        return (StatusCode::BAD_REQUEST, "unsupported websocket subprotocol").into_response();
+    };
+    let ip_guard = match state.try_acquire_ws_ip(addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => return rejection.into_response(),
     };
-    if state.event_clients.len() >= state.cfg.limits.events {
+    if state.event_clients.len() >= state.cfg().limits.events {
         return (StatusCode::TOO_MANY_REQUESTS, "too many events clients").into_response();
     }
-    ws.on_upgrade(|socket| handle(socket, state, ip_guard))
+    ws.protocols([protocol.as_str()])
+        .on_upgrade(move |socket| handle(socket, state, ip_guard, protocol, topics))
 }
 
-async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::state::WsIpGuard) {
+async fn handle(
+    socket: ws::WebSocket,
+    state: Arc<AppState>,
+    _ip_guard: crate::state::WsIpGuard,
+    protocol: super::subprotocol::ProtocolVersion,
+    topics: EventTopics,
+) {
     let client_id = state.alloc_client_id();
-    tracing::info!(client_id, "events ws connected");
-    let (tx, mut rx) = crate::state::text_channel();
-    state.event_clients.insert(client_id, tx);
+    tracing::info!(client_id, %protocol, "events ws connected");
+    let (tx, mut rx) = crate::state::event_channel();
+    state
+        .event_clients
+        .insert(client_id, EventClient { tx, topics });
 
-    let mut initial = state.event_info(true);
-    if state.cfg.server.otherusers > 0 {
+    let mut initial = state.event_info(true, topics);
+    if topics.contains(EventTopics::DSP) && state.cfg().server.otherusers > 0 {
         let mut snapshot = std::collections::HashMap::new();
         for rx in state.receivers.values() {
             let rx_id = rx.receiver.id.as_str();
@@ -70,6 +95,7 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
         return;
     }
 
+    let send_state = state.clone();
     let send_task = tokio::spawn(async move {
         let mut ping_interval = tokio::time::interval(Duration::from_secs(30));
         ping_interval.tick().await; // consume immediate first tick
@@ -78,12 +104,15 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                 biased;
                 Some(msg) = rx.recv() => {
                     if ws_sender
-                        .send(ws::Message::Text(msg.as_ref().to_string()))
+                        .send(ws::Message::Text(msg.json.as_ref().to_string()))
                         .await
                         .is_err()
                     {
                         break;
                     }
+                    send_state
+                        .events_fanout
+                        .record_delivery(msg.queued_at.elapsed());
                 }
                 _ = ping_interval.tick() => {
                     if ws_sender.send(ws::Message::Ping(Vec::new())).await.is_err() {
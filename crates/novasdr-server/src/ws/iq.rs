@@ -0,0 +1,270 @@
+use crate::replay::IqSubscription;
+use crate::rtl_tcp::Tuning;
+use crate::schedule::to_i16;
+use crate::state::{AppState, ReceiverState};
+use axum::{
+    extract::connect_info::ConnectInfo,
+    extract::{ws, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use futures::{SinkExt, StreamExt};
+use novasdr_core::codec::{ima_adpcm, zstd_stream::ZstdStreamEncoder};
+use novasdr_core::protocol::IqCommand;
+use num_complex::Complex32;
+use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Rate a client gets until it asks for one.
+const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct IqQuery {
+    /// The active receiver when absent.
+    #[serde(default)]
+    receiver_id: Option<String>,
+    /// Centre of the passband in Hz; the receiver's centre when absent.
+    #[serde(default)]
+    frequency: Option<i64>,
+    #[serde(default)]
+    sample_rate: Option<u32>,
+    /// `pcm16` (default), `adpcm` or `zstd`.
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IqFormat {
+    Pcm16,
+    Adpcm,
+    Zstd,
+}
+
+impl IqFormat {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "pcm16" => Some(Self::Pcm16),
+            "adpcm" => Some(Self::Adpcm),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pcm16 => "pcm16",
+            Self::Adpcm => "adpcm",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+/// Turns IQ into binary frames of one format.
+struct Encoder {
+    format: IqFormat,
+    zstd: Option<ZstdStreamEncoder>,
+    i: Vec<i16>,
+    q: Vec<i16>,
+}
+
+impl Encoder {
+    fn new(format: IqFormat) -> anyhow::Result<Self> {
+        Ok(Self {
+            format,
+            zstd: match format {
+                IqFormat::Zstd => Some(ZstdStreamEncoder::new(ZSTD_LEVEL)?),
+                _ => None,
+            },
+            i: Vec::new(),
+            q: Vec::new(),
+        })
+    }
+
+    /// One frame: interleaved 16-bit I and Q; for `adpcm` an IMA-ADPCM block of I followed by
+    /// one of Q; for `zstd` the 16-bit samples as a zstd frame of their own.
+    fn encode(&mut self, iq: &[Complex32]) -> anyhow::Result<Vec<u8>> {
+        if self.format == IqFormat::Adpcm {
+            self.i.clear();
+            self.q.clear();
+            self.i.extend(iq.iter().map(|s| to_i16(s.re)));
+            self.q.extend(iq.iter().map(|s| to_i16(s.im)));
+            let mut out = ima_adpcm::encode_block_i16_mono(&self.i);
+            out.extend_from_slice(&ima_adpcm::encode_block_i16_mono(&self.q));
+            return Ok(out);
+        }
+        let mut pcm = Vec::with_capacity(iq.len() * 4);
+        for s in iq {
+            pcm.extend_from_slice(&to_i16(s.re).to_le_bytes());
+            pcm.extend_from_slice(&to_i16(s.im).to_le_bytes());
+        }
+        match self.zstd.as_mut() {
+            Some(zstd) => zstd.compress_frame(&pcm),
+            None => Ok(pcm),
+        }
+    }
+}
+
+pub async fn upgrade(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<IqQuery>,
+) -> axum::response::Response {
+    if !state.cfg().iq.enabled {
+        return (StatusCode::NOT_FOUND, "IQ output disabled").into_response();
+    }
+    let Some(protocol) = super::subprotocol::negotiate(&headers, "iq") else {
+        return (StatusCode::BAD_REQUEST, "unsupported websocket subprotocol").into_response();
+    };
+    let receiver_id = query
+        .receiver_id
+        .unwrap_or_else(|| state.active_receiver_id().to_string());
+    let Some(receiver) = state.receiver_state(&receiver_id).cloned() else {
+        return (StatusCode::NOT_FOUND, "unknown receiver").into_response();
+    };
+    let Some(format) = IqFormat::parse(query.format.as_deref().unwrap_or("pcm16")) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "format must be pcm16, adpcm or zstd",
+        )
+            .into_response();
+    };
+    let frequency = query
+        .frequency
+        .unwrap_or(receiver.basefreq() + receiver.rt.total_bandwidth / 2);
+    if let Err(reason) = check_frequency(&receiver, frequency) {
+        return (StatusCode::BAD_REQUEST, reason).into_response();
+    }
+    let Ok(slot) = state.iq_slots.clone().try_acquire_owned() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "all IQ slots in use").into_response();
+    };
+    let ip_guard = match state.try_acquire_ws_ip(addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let sample_rate = query.sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE);
+    ws.protocols([protocol.as_str()])
+        .on_upgrade(move |socket| async move {
+            let _slot = slot;
+            let _ip_guard = ip_guard;
+            let client_id = state.alloc_client_id();
+            tracing::info!(client_id, ip = %addr.ip(), receiver_id, "iq ws connected");
+            if let Err(e) = handle(socket, &state, &receiver, format, frequency, sample_rate).await
+            {
+                tracing::debug!(client_id, error = ?e, "iq ws ended");
+            }
+            tracing::info!(client_id, "iq ws disconnected");
+        })
+}
+
+fn check_frequency(receiver: &ReceiverState, frequency: i64) -> Result<(), &'static str> {
+    let start = receiver.basefreq();
+    if (start..start + receiver.rt.total_bandwidth).contains(&frequency) {
+        Ok(())
+    } else {
+        Err("frequency outside the receiver's range")
+    }
+}
+
+/// The `{"type":"iq",...}` message describing what the binary frames hold.
+fn tuned(
+    receiver: &ReceiverState,
+    tuning: &Tuning,
+    frequency: i64,
+    format: IqFormat,
+) -> ws::Message {
+    ws::Message::Text(
+        json!({
+            "type": "iq",
+            "frequency": frequency,
+            "sample_rate": tuning.sample_rate(receiver),
+            "format": format.as_str(),
+        })
+        .to_string(),
+    )
+}
+
+async fn handle(
+    socket: ws::WebSocket,
+    state: &AppState,
+    receiver: &Arc<ReceiverState>,
+    format: IqFormat,
+    mut frequency: i64,
+    mut sample_rate: u32,
+) -> anyhow::Result<()> {
+    let max_rate = state.cfg().iq.max_sample_rate;
+    let mut tuning = Tuning::new(receiver, frequency, sample_rate, max_rate);
+    let mut encoder = Encoder::new(format)?;
+    let mut frames = IqSubscription::new(state, receiver, false);
+    let (mut sender, mut messages) = socket.split();
+    sender
+        .send(tuned(receiver, &tuning, frequency, format))
+        .await?;
+
+    let is_real = receiver.rt.is_real;
+    let mut iq: Vec<Complex32> = Vec::new();
+    loop {
+        tokio::select! {
+            frame = frames.recv() => {
+                let Some(frame) = frame else {
+                    return Ok(());
+                };
+                tuning.process(&frame, is_real, &mut iq);
+                sender.send(ws::Message::Binary(encoder.encode(&iq)?)).await?;
+            }
+            msg = messages.next() => {
+                let Some(Ok(msg)) = msg else {
+                    return Ok(());
+                };
+                let text = match msg {
+                    ws::Message::Text(text) => text,
+                    ws::Message::Close(_) => return Ok(()),
+                    _ => continue,
+                };
+                let Ok(IqCommand::Tune { frequency: f, sample_rate: rate }) =
+                    serde_json::from_str::<IqCommand>(&text)
+                else {
+                    continue;
+                };
+                if let Err(reason) = check_frequency(receiver, f) {
+                    let msg = json!({ "type": "iq", "error": reason });
+                    sender.send(ws::Message::Text(msg.to_string())).await?;
+                    continue;
+                }
+                frequency = f;
+                sample_rate = rate.unwrap_or(sample_rate);
+                tuning = Tuning::new(receiver, frequency, sample_rate, max_rate);
+                sender
+                    .send(tuned(receiver, &tuning, frequency, format))
+                    .await?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_hold_interleaved_samples_in_each_format() {
+        let iq: Vec<Complex32> = (0..100)
+            .map(|n| Complex32::from_polar(0.5, n as f32 * 0.3))
+            .collect();
+        let pcm = Encoder::new(IqFormat::Pcm16).unwrap().encode(&iq).unwrap();
+        assert_eq!(pcm.len(), 400);
+        assert_eq!(i16::from_le_bytes([pcm[2], pcm[3]]), to_i16(iq[0].im));
+
+        let zstd = Encoder::new(IqFormat::Zstd).unwrap().encode(&iq).unwrap();
+        assert_eq!(zstd[..4], [0x28, 0xb5, 0x2f, 0xfd]);
+
+        // Two blocks of a 6-byte header and 99 four-bit codes each.
+        let adpcm = Encoder::new(IqFormat::Adpcm).unwrap().encode(&iq).unwrap();
+        assert_eq!(adpcm.len(), 2 * (6 + 50));
+        assert_eq!(u16::from_le_bytes([adpcm[4], adpcm[5]]), 100);
+    }
+}
@@ -1,4 +1,24 @@
 pub mod audio;
+pub mod audio_pool;
 pub mod chat;
+pub mod digital;
+pub mod diversity;
 pub mod events;
+pub mod iq;
+pub mod scanner;
+pub mod sstv;
+pub mod subprotocol;
+#[cfg(test)]
+mod switch_tests;
+pub mod time_shift;
+pub mod tx;
 pub mod waterfall;
+
+use axum::extract::ws::{Message, WebSocket};
+
+/// Completes an upgrade only to send `message_json` and close: browsers do not expose why an
+/// upgrade was refused, so limits that a client should explain are reported this way.
+pub async fn refuse(mut socket: WebSocket, message_json: String) {
+    let _ = socket.send(Message::Text(message_json)).await;
+    let _ = socket.send(Message::Close(None)).await;
+}
@@ -0,0 +1,347 @@
+use crate::markers::{marker_frequency, MarkerIndex, MarkerSearch};
+use crate::state::{AppState, AudioClient, ReceiverState};
+use novasdr_core::dsp::demod::DemodulationMode;
+use novasdr_core::protocol::ScanChannel;
+use serde_json::json;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Longest list one `scan` command can start.
+pub const MAX_SCAN_CHANNELS: usize = 256;
+const DEFAULT_DWELL: Duration = Duration::from_millis(500);
+/// Below this the squelch has too few frames to open before the scanner moves on.
+const MIN_DWELL: Duration = Duration::from_millis(100);
+const DEFAULT_HANG: Duration = Duration::from_secs(2);
+const MAX_DWELL_OR_HANG: Duration = Duration::from_secs(60);
+/// How often the scheduler looks at the squelch.
+const POLL: Duration = Duration::from_millis(50);
+
+/// A channel as the scanner tunes it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Channel {
+    pub frequency: i64,
+    pub m: f64,
+    pub mode: DemodulationMode,
+}
+
+pub struct ScanPlan {
+    pub channels: Vec<Channel>,
+    pub dwell: Duration,
+    pub hang: Duration,
+}
+
+impl ScanPlan {
+    /// Channels from `list`, or from the markers matching `markers`, that lie in the receiver's
+    /// band. Entries without a known mode take `default_mode`.
+    pub fn build(
+        receiver: &ReceiverState,
+        marker_index: &MarkerIndex,
+        list: &[ScanChannel],
+        markers: Option<&str>,
+        default_mode: DemodulationMode,
+        dwell_ms: Option<u32>,
+        hang_ms: Option<u32>,
+    ) -> Result<Self, &'static str> {
+        let low = receiver.basefreq();
+        let high = low + receiver.rt.total_bandwidth;
+        let wanted: Vec<(i64, Option<String>)> = match markers {
+            Some(q) => {
+                let (found, _) = marker_index.search(&MarkerSearch {
+                    min: Some(low),
+                    max: Some(high),
+                    q: Some(q.to_string()),
+                    limit: Some(MAX_SCAN_CHANNELS),
+                });
+                found
+                    .into_iter()
+                    .filter_map(|m| {
+                        let mode = m.get("mode").and_then(|v| v.as_str()).map(str::to_string);
+                        Some((marker_frequency(m)?, mode))
+                    })
+                    .collect()
+            }
+            None => list.iter().map(|c| (c.frequency, c.mode.clone())).collect(),
+        };
+        if wanted.len() > MAX_SCAN_CHANNELS {
+            return Err("too many channels");
+        }
+        let channels: Vec<Channel> = wanted
+            .into_iter()
+            .filter(|&(frequency, _)| (low..=high).contains(&frequency))
+            .map(|(frequency, mode)| Channel {
+                frequency,
+                m: receiver.frequency_bin(frequency),
+                mode: mode
+                    .and_then(|m| DemodulationMode::from_str_upper(&m.to_ascii_uppercase()))
+                    .unwrap_or(default_mode),
+            })
+            .collect();
+        if channels.is_empty() {
+            return Err("no channels in this receiver's band");
+        }
+        let millis = |ms: Option<u32>, default: Duration| {
+            ms.map_or(default, |ms| Duration::from_millis(u64::from(ms)))
+                .min(MAX_DWELL_OR_HANG)
+        };
+        Ok(Self {
+            channels,
+            dwell: millis(dwell_ms, DEFAULT_DWELL).max(MIN_DWELL),
+            hang: millis(hang_ms, DEFAULT_HANG),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    /// Tune to this channel index.
+    Tune(usize),
+    /// The squelch opened; stay on the current channel.
+    Hold,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Phase {
+    Dwell { until: Instant },
+    Hold { closed_since: Option<Instant> },
+}
+
+/// When to move on: after `dwell` on a quiet channel, or `hang` after a held channel's squelch
+/// closed again.
+struct Schedule {
+    len: usize,
+    dwell: Duration,
+    hang: Duration,
+    index: usize,
+    phase: Phase,
+}
+
+impl Schedule {
+    fn new(plan: &ScanPlan, now: Instant) -> Self {
+        Self {
+            len: plan.channels.len(),
+            dwell: plan.dwell,
+            hang: plan.hang,
+            index: 0,
+            phase: Phase::Dwell {
+                until: now + plan.dwell,
+            },
+        }
+    }
+
+    fn poll(&mut self, now: Instant, open: bool) -> Option<Step> {
+        match &mut self.phase {
+            Phase::Dwell { .. } if open => {
+                self.phase = Phase::Hold { closed_since: None };
+                Some(Step::Hold)
+            }
+            Phase::Dwell { until } if now >= *until => Some(self.advance(now)),
+            Phase::Dwell { .. } => None,
+            Phase::Hold { closed_since } if open => {
+                *closed_since = None;
+                None
+            }
+            Phase::Hold { closed_since } => {
+                let since = *closed_since.get_or_insert(now);
+                (now - since >= self.hang).then(|| self.advance(now))
+            }
+        }
+    }
+
+    fn advance(&mut self, now: Instant) -> Step {
+        self.index = (self.index + 1) % self.len;
+        self.phase = Phase::Dwell {
+            until: now + self.dwell,
+        };
+        Step::Tune(self.index)
+    }
+}
+
+/// A running scan of one audio client. Dropping it stops the scan.
+pub struct Scanner {
+    task: tokio::task::JoinHandle<()>,
+    skip: Arc<Notify>,
+}
+
+impl Scanner {
+    pub fn start(
+        state: Arc<AppState>,
+        receiver_id: String,
+        receiver: Arc<ReceiverState>,
+        client: Arc<AudioClient>,
+        plan: ScanPlan,
+    ) -> Self {
+        let skip = Arc::new(Notify::new());
+        let task = tokio::spawn(run(
+            state,
+            receiver_id,
+            receiver,
+            client,
+            plan,
+            skip.clone(),
+        ));
+        Self { task, skip }
+    }
+
+    /// Moves on from the current channel, held or not.
+    pub fn skip(&self) {
+        self.skip.notify_one();
+    }
+
+    /// Stops the scan and waits until its task let go of the client.
+    pub async fn stop(mut self) {
+        self.task.abort();
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for Scanner {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run(
+    state: Arc<AppState>,
+    receiver_id: String,
+    receiver: Arc<ReceiverState>,
+    client: Arc<AudioClient>,
+    plan: ScanPlan,
+    skip: Arc<Notify>,
+) {
+    let mut schedule = Schedule::new(&plan, Instant::now());
+    tune(&state, &receiver_id, &receiver, &client, plan.channels[0]);
+    send_status(&client, "scanning", 0, &plan);
+    let mut poll = tokio::time::interval(POLL);
+    poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        let step = tokio::select! {
+            _ = poll.tick() => schedule.poll(Instant::now(), squelch_open(&client)),
+            _ = skip.notified() => Some(schedule.advance(Instant::now())),
+        };
+        match step {
+            Some(Step::Tune(index)) => {
+                tune(
+                    &state,
+                    &receiver_id,
+                    &receiver,
+                    &client,
+                    plan.channels[index],
+                );
+                send_status(&client, "scanning", index, &plan);
+            }
+            Some(Step::Hold) => send_status(&client, "holding", schedule.index, &plan),
+            None => {}
+        }
+    }
+}
+
+/// Only an enabled squelch (carrier or tone) stops the scanner; without one it steps through
+/// every `dwell`.
+fn squelch_open(client: &AudioClient) -> bool {
+    let enabled = match client.params.lock() {
+        Ok(g) => g.squelch_enabled || g.tone_squelch.is_some(),
+        Err(poisoned) => {
+            let p = poisoned.into_inner();
+            p.squelch_enabled || p.tone_squelch.is_some()
+        }
+    };
+    enabled && client.squelch_open.load(Ordering::Relaxed)
+}
+
+fn tune(
+    state: &AppState,
+    receiver_id: &str,
+    receiver: &ReceiverState,
+    client: &AudioClient,
+    channel: Channel,
+) {
+    let (l, r) = receiver.rt.mode_window(channel.mode.as_str(), channel.m);
+    {
+        let mut p = match client.params.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(
+                    unique_id = %client.unique_id,
+                    "audio params mutex poisoned; recovering"
+                );
+                poisoned.into_inner()
+            }
+        };
+        p.l = l;
+        p.m = channel.m;
+        p.r = r;
+        p.demodulation = channel.mode;
+    }
+    {
+        let mut pipeline = match client.pipeline.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::error!(
+                    unique_id = %client.unique_id,
+                    "audio pipeline mutex poisoned; recovering"
+                );
+                poisoned.into_inner()
+            }
+        };
+        pipeline.reset_agc();
+        pipeline.restart_squelch();
+    }
+    client.squelch_open.store(false, Ordering::Relaxed);
+    state.broadcast_signal_changes(receiver_id, &client.unique_id, l, channel.m, r);
+    let msg = json!({ "type": "window", "l": l, "m": channel.m, "r": r });
+    let _ = client.text_tx.try_send(msg.to_string());
+}
+
+fn send_status(client: &AudioClient, status: &str, index: usize, plan: &ScanPlan) {
+    let channel = plan.channels[index];
+    let msg = json!({
+        "type": "scan",
+        "state": status,
+        "index": index,
+        "count": plan.channels.len(),
+        "frequency": channel.frequency,
+        "mode": channel.mode.as_str(),
+    });
+    // A status lost to a full queue is superseded by the next one.
+    let _ = client.text_tx.try_send(msg.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(len: usize) -> ScanPlan {
+        let channel = Channel {
+            frequency: 0,
+            m: 0.0,
+            mode: DemodulationMode::Fm,
+        };
+        ScanPlan {
+            channels: vec![channel; len],
+            dwell: Duration::from_millis(500),
+            hang: Duration::from_secs(2),
+        }
+    }
+
+    #[test]
+    fn schedule_steps_quiet_channels_and_holds_open_ones() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut schedule = Schedule::new(&plan(3), start);
+        assert_eq!(schedule.poll(at(400), false), None);
+        assert_eq!(schedule.poll(at(500), false), Some(Step::Tune(1)));
+        // A signal on channel 1: held while open and through short gaps.
+        assert_eq!(schedule.poll(at(600), true), Some(Step::Hold));
+        assert_eq!(schedule.poll(at(5_000), true), None);
+        assert_eq!(schedule.poll(at(6_000), false), None);
+        assert_eq!(schedule.poll(at(7_000), true), None);
+        assert_eq!(schedule.poll(at(7_100), false), None);
+        assert_eq!(schedule.poll(at(9_000), false), None);
+        assert_eq!(schedule.poll(at(9_100), false), Some(Step::Tune(2)));
+        assert_eq!(schedule.poll(at(9_600), false), Some(Step::Tune(0)));
+        assert_eq!(schedule.advance(at(9_700)), Step::Tune(1));
+    }
+}
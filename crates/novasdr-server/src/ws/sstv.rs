@@ -0,0 +1,117 @@
+use crate::sstv::Picture;
+use crate::state::AppState;
+use axum::{
+    extract::connect_info::ConnectInfo,
+    extract::{ws, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SstvQuery {
+    /// Only pictures from this receiver; all of them when absent.
+    #[serde(default)]
+    receiver_id: Option<String>,
+}
+
+pub async fn upgrade(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SstvQuery>,
+) -> axum::response::Response {
+    let Some(protocol) = super::subprotocol::negotiate(&headers, "sstv") else {
+        return (StatusCode::BAD_REQUEST, "unsupported websocket subprotocol").into_response();
+    };
+    let ip_guard = match state.try_acquire_ws_ip(addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => return rejection.into_response(),
+    };
+    ws.protocols([protocol.as_str()])
+        .on_upgrade(move |socket| handle(socket, state, ip_guard, protocol, query.receiver_id))
+}
+
+/// Sends a picture as its description followed by the PNG file.
+async fn send_picture(
+    sender: &mut futures::stream::SplitSink<ws::WebSocket, ws::Message>,
+    picture: &Picture,
+) -> Result<(), axum::Error> {
+    sender.send(ws::Message::Text(picture.json.clone())).await?;
+    sender.send(ws::Message::Binary(picture.png.clone())).await
+}
+
+async fn handle(
+    socket: ws::WebSocket,
+    state: Arc<AppState>,
+    _ip_guard: crate::state::WsIpGuard,
+    protocol: super::subprotocol::ProtocolVersion,
+    receiver_id: Option<String>,
+) {
+    let client_id = state.alloc_client_id();
+    tracing::info!(client_id, %protocol, "sstv ws connected");
+    let (recent, mut rx) = state.sstv_pictures.subscribe();
+    let wanted = move |picture: &Picture| {
+        receiver_id
+            .as_deref()
+            .is_none_or(|id| id == picture.receiver_id)
+    };
+
+    let (mut ws_sender, mut ws_receiver) = socket.split();
+    let send_task = tokio::spawn(async move {
+        for picture in recent.iter().filter(|p| wanted(p)) {
+            if send_picture(&mut ws_sender, picture).await.is_err() {
+                return;
+            }
+        }
+        let mut ping_interval = tokio::time::interval(Duration::from_secs(30));
+        ping_interval.tick().await; // consume immediate first tick
+        loop {
+            tokio::select! {
+                biased;
+                res = rx.recv() => {
+                    let picture = match res {
+                        Ok(picture) => picture,
+                        // Pictures are big; a client that fell behind just misses some.
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    };
+                    if wanted(&picture) && send_picture(&mut ws_sender, &picture).await.is_err() {
+                        break;
+                    }
+                }
+                _ = ping_interval.tick() => {
+                    if ws_sender.send(ws::Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let idle_timeout = Duration::from_secs(90);
+    loop {
+        let maybe_msg = match tokio::time::timeout(idle_timeout, ws_receiver.next()).await {
+            Ok(v) => v,
+            Err(_) => {
+                tracing::info!(client_id, "sstv ws idle timeout");
+                break;
+            }
+        };
+        let Some(Ok(msg)) = maybe_msg else {
+            break;
+        };
+        if matches!(msg, ws::Message::Close(_)) {
+            break;
+        }
+    }
+
+    tracing::info!(client_id, "sstv ws disconnected");
+    send_task.abort();
+}
@@ -0,0 +1,92 @@
+use axum::http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderMap};
+
+/// Versions of the NovaSDR WebSocket protocol. A breaking change to any endpoint adds the next
+/// version here; handlers branch on the negotiated version so older frontends keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolVersion {
+    V1,
+}
+
+impl ProtocolVersion {
+    /// Supported versions, newest first; negotiation picks the newest one the client offers.
+    const SUPPORTED: &'static [Self] = &[Self::V1];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::V1 => "novasdr.v1",
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Chooses the protocol version for an upgrade request. Clients that offer no subprotocol
+/// (frontends built before negotiation existed) are served as v1; clients that only offer
+/// unknown subprotocols get the offered list back as the error.
+fn select(headers: &HeaderMap) -> Result<ProtocolVersion, Vec<String>> {
+    let offered: Vec<&str> = headers
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    if offered.is_empty() {
+        return Ok(ProtocolVersion::V1);
+    }
+    ProtocolVersion::SUPPORTED
+        .iter()
+        .copied()
+        .find(|v| offered.contains(&v.as_str()))
+        .ok_or_else(|| offered.iter().map(|p| p.to_string()).collect())
+}
+
+/// Negotiates the subprotocol for `endpoint`; `None` means the upgrade must be rejected because
+/// none of the offered subprotocols is supported. Pass the result to
+/// [`WebSocketUpgrade::protocols`] so it is echoed when the client offered one.
+pub fn negotiate(headers: &HeaderMap, endpoint: &'static str) -> Option<ProtocolVersion> {
+    match select(headers) {
+        Ok(version) => Some(version),
+        Err(offered) => {
+            tracing::warn!(
+                endpoint,
+                ?offered,
+                "websocket upgrade rejected: unsupported subprotocol"
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn offering(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn select_negotiates_supported_versions() {
+        assert_eq!(select(&HeaderMap::new()), Ok(ProtocolVersion::V1));
+        assert_eq!(select(&offering("novasdr.v1")), Ok(ProtocolVersion::V1));
+        assert_eq!(
+            select(&offering("novasdr.v9, novasdr.v1")),
+            Ok(ProtocolVersion::V1)
+        );
+        assert_eq!(
+            select(&offering("novasdr.v9")),
+            Err(vec!["novasdr.v9".to_string()])
+        );
+    }
+}
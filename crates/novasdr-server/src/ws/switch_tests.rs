@@ -0,0 +1,368 @@
+//! Receiver switches racing `window` and `demodulation` commands on `/audio` and `/waterfall`.
+//!
+//! Each test serves the real router on a loopback port with two synthetic receivers running:
+//! `a` generates loud noise and `b` quiet noise, so every frame tells which receiver it came
+//! from. The tests connect several clients at once and fire switches and tuning commands
+//! without waiting for replies. Every switch must be answered by the settings of the receiver
+//! switched to, in the order sent, and be followed only by frames of that receiver. Once the
+//! commands settled each client must be registered with exactly one receiver, with nothing left
+//! behind after it disconnects.
+
+use crate::app;
+use crate::state::{AppState, ConfigPaths};
+use futures::{SinkExt, StreamExt};
+use novasdr_core::codec::lz4_block;
+use novasdr_core::config;
+use novasdr_core::dsp::demod::DemodulationMode;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+type Socket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+const CLIENTS: usize = 4;
+/// Round trips `a -> b -> a` per client.
+const ROUNDS: usize = 15;
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+const CONFIG: &str = r#"{
+  "server": { "port": 9002, "otherusers": 1 },
+  "websdr": { "name": "switch test" },
+  "limits": { "audio": 64, "waterfall": 64, "ws_per_ip": 64 },
+  "active_receiver_id": "a"
+}"#;
+
+const RECEIVERS: &str = r#"{ "receivers": [
+  { "id": "a", "input": { "sps": 256000, "frequency": 100000000, "signal": "iq", "fft_size": 2048,
+      "driver": { "kind": "synthetic", "scenario": { "noise_floor_db": -20 } } } },
+  { "id": "b", "input": { "sps": 128000, "frequency": 7000000, "signal": "iq", "fft_size": 1024,
+      "driver": { "kind": "synthetic", "scenario": { "noise_floor_db": -100 } } } }
+] }"#;
+
+struct Server {
+    state: Arc<AppState>,
+    addr: SocketAddr,
+    dir: PathBuf,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+async fn serve() -> Server {
+    let dir = std::env::temp_dir().join(format!(
+        "novasdr_switch_{}_{}",
+        std::process::id(),
+        rand::random::<u32>()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let paths = ConfigPaths {
+        config: dir.join("config.json"),
+        receivers: dir.join("receivers.json"),
+        overlays: dir.join("overlays"),
+    };
+    std::fs::write(&paths.config, CONFIG).unwrap();
+    std::fs::write(&paths.receivers, RECEIVERS).unwrap();
+    let cfg = config::load_from_files(&paths.config, &paths.receivers).unwrap();
+    let state = Arc::new(AppState::new(Arc::new(cfg), dir.clone(), paths).unwrap());
+    crate::dsp_runner::start(state.clone()).unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = app::router(state.clone());
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+    });
+    Server { state, addr, dir }
+}
+
+async fn connect(addr: SocketAddr, path: &str) -> Socket {
+    let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}{path}"))
+        .await
+        .unwrap();
+    socket
+}
+
+async fn send(socket: &mut Socket, cmd: Value) {
+    socket.send(Message::Text(cmd.to_string())).await.unwrap();
+}
+
+/// The next settings message, and the binary frames and count of other text messages before
+/// it, so callers can check what came ahead of it.
+async fn next_settings(socket: &mut Socket) -> (Value, Vec<Vec<u8>>, usize) {
+    let mut frames = Vec::new();
+    let mut texts = 0;
+    loop {
+        let msg = tokio::time::timeout(TIMEOUT, socket.next())
+            .await
+            .expect("settings in time")
+            .expect("socket open")
+            .unwrap();
+        match msg {
+            Message::Text(txt) => {
+                let v: Value = serde_json::from_str(&txt).unwrap();
+                if v.get("basefreq").is_some() {
+                    return (v, frames, texts);
+                }
+                texts += 1;
+            }
+            Message::Binary(frame) => frames.push(frame),
+            _ => {}
+        }
+    }
+}
+
+/// The next binary frame.
+async fn next_frame(socket: &mut Socket) -> Vec<u8> {
+    loop {
+        let msg = tokio::time::timeout(TIMEOUT, socket.next())
+            .await
+            .expect("frame in time")
+            .expect("socket open")
+            .unwrap();
+        if let Message::Binary(frame) = msg {
+            return frame;
+        }
+    }
+}
+
+/// Receiver an `/audio` frame was demodulated from, by its power: `a` is 80 dB louder.
+fn audio_source(frame: &[u8]) -> &'static str {
+    assert_eq!(&frame[..4], b"NSDA");
+    let pwr = f32::from_le_bytes(frame[32..36].try_into().unwrap());
+    if 10.0 * pwr.log10() > -20.0 {
+        "a"
+    } else {
+        "b"
+    }
+}
+
+/// Receiver a `/waterfall` frame (`compression=lz4`) came from, by its mean level.
+fn waterfall_source(frame: &[u8]) -> &'static str {
+    let cbor = lz4_block::decompress_packet(frame).unwrap();
+    let packet: serde_cbor::Value = serde_cbor::from_slice(&cbor).unwrap();
+    let serde_cbor::Value::Map(fields) = packet else {
+        panic!("waterfall packet is not a map");
+    };
+    let Some(serde_cbor::Value::Bytes(data)) = fields.get(&serde_cbor::Value::Text("data".into()))
+    else {
+        panic!("waterfall packet without data");
+    };
+    let mean = data.iter().map(|&v| f64::from(v as i8)).sum::<f64>() / data.len() as f64;
+    if mean > 0.0 {
+        "a"
+    } else {
+        "b"
+    }
+}
+
+/// Polls `done` until it holds, so assertions wait for commands still in flight.
+async fn settle(what: &str, mut done: impl FnMut() -> bool) {
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while !done() {
+        assert!(tokio::time::Instant::now() < deadline, "{what}");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+/// Switches `a -> b -> a ...`, ending on `b`, with each switch followed at once by a window
+/// and a demodulation command meant for the receiver switched to. Frames between two settings
+/// messages must come from the receiver of the first.
+async fn race_audio(addr: SocketAddr) -> (Socket, String) {
+    let mut socket = connect(addr, "/audio").await;
+    let (first, _, _) = next_settings(&mut socket).await;
+    assert_eq!(first["receiver_id"], "a");
+    let unique_id = first["audio_unique_id"].as_str().unwrap().to_string();
+
+    let mut expected = Vec::new();
+    for round in 0..=ROUNDS {
+        for (id, l) in [("b", 500), ("a", 1000)] {
+            if round == ROUNDS && id == "a" {
+                break;
+            }
+            send(&mut socket, json!({ "cmd": "receiver", "receiver_id": id })).await;
+            send(
+                &mut socket,
+                json!({ "cmd": "window", "l": l, "m": f64::from(l) + 10.0, "r": l + 30 }),
+            )
+            .await;
+            send(
+                &mut socket,
+                json!({ "cmd": "demodulation", "demodulation": "LSB" }),
+            )
+            .await;
+            expected.push(id);
+        }
+    }
+    let mut current = "a";
+    for id in expected {
+        let (settings, frames, _) = next_settings(&mut socket).await;
+        for frame in &frames {
+            assert_eq!(
+                audio_source(frame),
+                current,
+                "audio before switching to {id}"
+            );
+        }
+        assert_eq!(settings["receiver_id"], id);
+        assert_eq!(settings["audio_unique_id"], unique_id.as_str());
+        current = id;
+    }
+    for _ in 0..3 {
+        assert_eq!(audio_source(&next_frame(&mut socket).await), "b");
+    }
+    (socket, unique_id)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn audio_switches_leave_each_client_on_one_receiver() {
+    let server = serve().await;
+    let a = server.state.receiver_state("a").unwrap().clone();
+    let b = server.state.receiver_state("b").unwrap().clone();
+
+    let clients = futures::future::join_all((0..CLIENTS).map(|_| race_audio(server.addr))).await;
+
+    settle("audio clients did not settle on b", || {
+        a.audio_clients.is_empty()
+            && b.audio_clients.len() == CLIENTS
+            && b.audio_clients.iter().all(|c| {
+                let p = c.params.lock().unwrap();
+                // LSB narrows the window to the bins below `m`.
+                p.m == 510.0 && p.demodulation == DemodulationMode::Lsb
+            })
+    })
+    .await;
+    for (_, unique_id) in &clients {
+        assert!(!a.signal_changes.contains_key(unique_id));
+        assert_eq!(b.signal_changes.get(unique_id).map(|e| e.1), Some(510.0));
+    }
+
+    for (mut socket, _) in clients {
+        socket.close(None).await.unwrap();
+    }
+    settle("audio clients left fan-out entries behind", || {
+        a.audio_clients.is_empty()
+            && b.audio_clients.is_empty()
+            && a.signal_changes.is_empty()
+            && b.signal_changes.is_empty()
+    })
+    .await;
+}
+
+/// Like [`race_audio`] on `/waterfall`: each switch is followed by zooming window 0 and
+/// opening window 1, which the next switch has to close again.
+async fn race_waterfall(addr: SocketAddr) -> Socket {
+    let mut socket = connect(addr, "/waterfall?compression=lz4").await;
+    let (first, _, _) = next_settings(&mut socket).await;
+    assert_eq!(first["receiver_id"], "a");
+
+    let mut expected = Vec::new();
+    for round in 0..=ROUNDS {
+        for id in ["b", "a"] {
+            if round == ROUNDS && id == "a" {
+                break;
+            }
+            send(&mut socket, json!({ "cmd": "receiver", "receiver_id": id })).await;
+            send(&mut socket, json!({ "cmd": "window", "l": 0, "r": 1024 })).await;
+            send(
+                &mut socket,
+                json!({ "cmd": "window", "l": 512, "r": 1024, "window": 1 }),
+            )
+            .await;
+            expected.push(id);
+        }
+    }
+    let mut current = "a";
+    for id in expected {
+        let (settings, frames, _) = next_settings(&mut socket).await;
+        for frame in &frames {
+            assert_eq!(
+                waterfall_source(frame),
+                current,
+                "waterfall before switching to {id}"
+            );
+        }
+        assert_eq!(settings["receiver_id"], id);
+        current = id;
+    }
+    for _ in 0..3 {
+        assert_eq!(waterfall_source(&next_frame(&mut socket).await), "b");
+    }
+    socket
+}
+
+fn waterfall_entries(receiver: &crate::state::ReceiverState) -> usize {
+    receiver
+        .waterfall_clients
+        .iter()
+        .map(|level| level.len())
+        .sum()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn waterfall_switches_leave_each_window_on_one_receiver() {
+    let server = serve().await;
+    let a = server.state.receiver_state("a").unwrap().clone();
+    let b = server.state.receiver_state("b").unwrap().clone();
+
+    let sockets =
+        futures::future::join_all((0..CLIENTS).map(|_| race_waterfall(server.addr))).await;
+
+    // Window 0 and window 1 of every client.
+    settle("waterfall windows did not settle on b", || {
+        waterfall_entries(&a) == 0 && waterfall_entries(&b) == CLIENTS * 2
+    })
+    .await;
+
+    for mut socket in sockets {
+        socket.close(None).await.unwrap();
+    }
+    settle("waterfall clients left fan-out entries behind", || {
+        waterfall_entries(&a) == 0 && waterfall_entries(&b) == 0
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn settings_come_first_while_audio_flows_and_others_switch() {
+    let server = serve().await;
+    let racing = tokio::spawn({
+        let addr = server.addr;
+        async move {
+            let audio = race_audio(addr).await;
+            let waterfall = race_waterfall(addr).await;
+            (audio, waterfall)
+        }
+    });
+    for _ in 0..CLIENTS {
+        for path in ["/audio", "/waterfall?compression=lz4"] {
+            let mut socket = connect(server.addr, path).await;
+            let (settings, frames, texts) = next_settings(&mut socket).await;
+            assert!(
+                frames.is_empty() && texts == 0,
+                "{path}: messages before the settings"
+            );
+            assert_eq!(settings["receiver_id"], "a");
+            let frame = next_frame(&mut socket).await;
+            if path == "/audio" {
+                assert_eq!(audio_source(&frame), "a");
+            } else {
+                assert_eq!(waterfall_source(&frame), "a");
+            }
+            socket.close(None).await.unwrap();
+        }
+    }
+    let ((mut audio, _), mut waterfall) = racing.await.unwrap();
+    audio.close(None).await.unwrap();
+    waterfall.close(None).await.unwrap();
+}
@@ -0,0 +1,291 @@
+use serde_json::json;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Upper bound on one client's buffer, whatever its duration; WBFM stereo ADPCM fills about
+/// 3 MiB a minute.
+const MAX_BUFFER_BYTES: usize = 8 * 1024 * 1024;
+
+/// A `timeshift` command as the send task applies it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Pause,
+    Resume,
+    /// Play from this far behind live.
+    Seek(Duration),
+    Live,
+}
+
+impl Command {
+    pub fn parse(action: &str, seconds: Option<f64>) -> Option<Self> {
+        match action {
+            "pause" => Some(Self::Pause),
+            "resume" => Some(Self::Resume),
+            "live" => Some(Self::Live),
+            "seek" => {
+                let seconds = seconds.filter(|s| s.is_finite())?.max(0.0);
+                Some(Self::Seek(Duration::from_secs_f64(seconds.min(86_400.0))))
+            }
+            _ => None,
+        }
+    }
+}
+
+struct Packet {
+    at: Instant,
+    seq: u64,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Live,
+    /// Packet `cursor` and those after it go out `delay` after they arrived.
+    Playing {
+        delay: Duration,
+        cursor: u64,
+    },
+    Paused {
+        delay: Duration,
+        since: Instant,
+        cursor: u64,
+    },
+}
+
+/// The audio packets a client was sent over the last `window`, and where in them it listens.
+/// Packets are kept as encoded, so replaying them needs nothing new from the client's decoder.
+pub struct TimeShift {
+    window: Duration,
+    packets: VecDeque<Packet>,
+    bytes: usize,
+    next_seq: u64,
+    mode: Mode,
+}
+
+impl TimeShift {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            packets: VecDeque::new(),
+            bytes: 0,
+            next_seq: 0,
+            mode: Mode::Live,
+        }
+    }
+
+    /// Buffers a packet that just arrived; returns it when the client listens live.
+    pub fn push(&mut self, now: Instant, bytes: Vec<u8>) -> Option<Vec<u8>> {
+        if self.window.is_zero() {
+            return Some(bytes);
+        }
+        self.bytes += bytes.len();
+        let out = (self.mode == Mode::Live).then(|| bytes.clone());
+        self.packets.push_back(Packet {
+            at: now,
+            seq: self.next_seq,
+            bytes,
+        });
+        self.next_seq += 1;
+        self.evict(now);
+        out
+    }
+
+    fn evict(&mut self, now: Instant) {
+        while let Some(front) = self.packets.front() {
+            if now.duration_since(front.at) <= self.window && self.bytes <= MAX_BUFFER_BYTES {
+                break;
+            }
+            self.bytes -= front.bytes.len();
+            self.packets.pop_front();
+        }
+        // A listener whose place was evicted (byte cap) picks up at the oldest packet still held.
+        let Some(oldest) = self.packets.front() else {
+            return;
+        };
+        match &mut self.mode {
+            Mode::Playing { delay, cursor } if *cursor < oldest.seq => {
+                *cursor = oldest.seq;
+                *delay = now.duration_since(oldest.at);
+            }
+            Mode::Paused { cursor, .. } if *cursor < oldest.seq => *cursor = oldest.seq,
+            _ => {}
+        }
+    }
+
+    fn oldest(&self) -> Option<&Packet> {
+        self.packets.front()
+    }
+
+    fn index_of(&self, seq: u64) -> Option<usize> {
+        let first = self.oldest()?.seq;
+        let i = usize::try_from(seq.checked_sub(first)?).ok()?;
+        (i < self.packets.len()).then_some(i)
+    }
+
+    /// The next buffered packet due by `now`, while playing behind live.
+    pub fn due(&mut self, now: Instant) -> Option<Vec<u8>> {
+        let Mode::Playing { delay, cursor } = self.mode else {
+            return None;
+        };
+        let packet = &self.packets[self.index_of(cursor)?];
+        if packet.at + delay > now {
+            return None;
+        }
+        self.mode = Mode::Playing {
+            delay,
+            cursor: cursor + 1,
+        };
+        Some(packet.bytes.clone())
+    }
+
+    /// When [`Self::due`] has the next packet, if playing behind live.
+    pub fn next_due(&self) -> Option<Instant> {
+        let Mode::Playing { delay, cursor } = self.mode else {
+            return None;
+        };
+        Some(self.packets[self.index_of(cursor)?].at + delay)
+    }
+
+    pub fn apply(&mut self, now: Instant, command: Command) {
+        self.mode = match (command, self.mode) {
+            (_, _) if self.window.is_zero() => Mode::Live,
+            (Command::Live, _) => Mode::Live,
+            (Command::Pause, Mode::Live) => Mode::Paused {
+                delay: Duration::ZERO,
+                since: now,
+                cursor: self.next_seq,
+            },
+            (Command::Pause, Mode::Playing { delay, cursor }) => Mode::Paused {
+                delay,
+                since: now,
+                cursor,
+            },
+            (
+                Command::Resume,
+                Mode::Paused {
+                    delay,
+                    since,
+                    cursor,
+                },
+            ) => {
+                match self.index_of(cursor) {
+                    // Keep the packet's place: what came after the pause is now this far behind.
+                    Some(i) => Mode::Playing {
+                        delay: now.duration_since(self.packets[i].at),
+                        cursor,
+                    },
+                    // Nothing arrived while paused.
+                    None => Mode::Playing {
+                        delay: delay + now.duration_since(since),
+                        cursor,
+                    },
+                }
+            }
+            (Command::Seek(behind), _) => match self
+                .packets
+                .iter()
+                .find(|p| now.duration_since(p.at) <= behind)
+            {
+                Some(p) if !behind.is_zero() => Mode::Playing {
+                    delay: now.duration_since(p.at),
+                    cursor: p.seq,
+                },
+                _ => Mode::Live,
+            },
+            (_, mode) => mode,
+        };
+    }
+
+    /// Drops the buffer and returns to live, e.g. when the client switches receivers.
+    pub fn clear(&mut self) {
+        self.packets.clear();
+        self.bytes = 0;
+        self.mode = Mode::Live;
+    }
+
+    /// `timeshift` status message for the client.
+    pub fn status(&self, now: Instant) -> String {
+        let (state, delay) = match self.mode {
+            Mode::Live => ("live", Duration::ZERO),
+            Mode::Playing { delay, .. } => ("playing", delay),
+            Mode::Paused { delay, since, .. } => ("paused", delay + now.duration_since(since)),
+        };
+        let buffered = self
+            .oldest()
+            .map_or(Duration::ZERO, |p| now.duration_since(p.at));
+        json!({
+            "type": "timeshift",
+            "state": state,
+            "delay_ms": delay.as_millis() as u64,
+            "buffered_ms": buffered.as_millis() as u64,
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn seek_replays_at_the_recorded_pace_and_live_skips_ahead() {
+        let t0 = Instant::now();
+        let mut shift = TimeShift::new(Duration::from_secs(60));
+        for i in 0..10u8 {
+            assert_eq!(
+                shift.push(t0 + ms(100 * u64::from(i)), vec![i]),
+                Some(vec![i])
+            );
+        }
+        let now = t0 + ms(1_000);
+        shift.apply(now, Command::Seek(ms(500)));
+        // Packet 5 arrived 500 ms ago; 6 follows 100 ms later.
+        assert_eq!(shift.due(now), Some(vec![5]));
+        assert_eq!(shift.due(now), None);
+        assert_eq!(shift.next_due(), Some(now + ms(100)));
+        // Live packets are held back while playing behind.
+        assert_eq!(shift.push(now + ms(50), vec![10]), None);
+        assert_eq!(shift.due(now + ms(100)), Some(vec![6]));
+
+        shift.apply(now + ms(150), Command::Live);
+        assert_eq!(shift.due(now + ms(150)), None);
+        assert_eq!(shift.push(now + ms(200), vec![11]), Some(vec![11]));
+    }
+
+    #[test]
+    fn pause_and_resume_continue_where_the_listener_stopped() {
+        let t0 = Instant::now();
+        let mut shift = TimeShift::new(Duration::from_secs(60));
+        shift.push(t0, vec![0]);
+        shift.apply(t0 + ms(10), Command::Pause);
+        assert_eq!(shift.push(t0 + ms(100), vec![1]), None);
+        shift.push(t0 + ms(200), vec![2]);
+        assert_eq!(shift.due(t0 + ms(300)), None);
+
+        let resumed = t0 + ms(1_100);
+        shift.apply(resumed, Command::Resume);
+        assert_eq!(shift.due(resumed), Some(vec![1]));
+        assert_eq!(shift.due(resumed + ms(99)), None);
+        assert_eq!(shift.due(resumed + ms(100)), Some(vec![2]));
+        assert!(shift.status(resumed).contains("\"delay_ms\":1000"));
+    }
+
+    #[test]
+    fn old_packets_are_evicted_and_a_zero_window_stays_live() {
+        let t0 = Instant::now();
+        let mut shift = TimeShift::new(Duration::from_secs(1));
+        shift.push(t0, vec![0]);
+        shift.push(t0 + ms(1_500), vec![1]);
+        shift.apply(t0 + ms(1_600), Command::Seek(Duration::from_secs(30)));
+        assert_eq!(shift.due(t0 + ms(1_600)), Some(vec![1]));
+
+        let mut off = TimeShift::new(Duration::ZERO);
+        off.apply(t0, Command::Pause);
+        assert_eq!(off.push(t0, vec![7]), Some(vec![7]));
+    }
+}
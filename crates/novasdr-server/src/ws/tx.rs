@@ -0,0 +1,170 @@
+use crate::state::AppState;
+use crate::tx::{Command, Session, SessionHandle};
+use axum::{
+    extract::connect_info::ConnectInfo,
+    extract::{ws, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use futures::{SinkExt, StreamExt};
+use interop::opus;
+use novasdr_core::dsp::tx::{TxMode, TX_AUDIO_RATE};
+use novasdr_core::protocol::TxCommand;
+use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest Opus frame, at [`TX_AUDIO_RATE`].
+const MAX_OPUS_FRAME: usize = TX_AUDIO_RATE * 120 / 1000;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TxQuery {
+    /// The receiver whose device transmits; the active receiver when absent.
+    #[serde(default)]
+    receiver_id: Option<String>,
+    /// `pcm` (s16le mono) or `opus`, both at 12 kHz.
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AudioFormat {
+    Pcm,
+    Opus,
+}
+
+pub async fn upgrade(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TxQuery>,
+) -> axum::response::Response {
+    // Operator only, before anything about the transmitter is revealed.
+    if let Err(rejection) = crate::auth::authorize(&state, &headers) {
+        tracing::warn!(ip = %addr.ip(), "tx connection rejected");
+        return rejection.into_response();
+    }
+    let receiver_id = query
+        .receiver_id
+        .unwrap_or_else(|| state.active_receiver_id().to_string());
+    let Some(receiver) = state
+        .receiver_state(&receiver_id)
+        .filter(|r| r.receiver.tx.enabled)
+        .cloned()
+    else {
+        return (StatusCode::NOT_FOUND, "no transmitter on this receiver").into_response();
+    };
+    let format = match query.format.as_deref().unwrap_or("pcm") {
+        "pcm" => AudioFormat::Pcm,
+        "opus" => AudioFormat::Opus,
+        _ => return (StatusCode::BAD_REQUEST, "format must be pcm or opus").into_response(),
+    };
+    let Some(protocol) = super::subprotocol::negotiate(&headers, "tx") else {
+        return (StatusCode::BAD_REQUEST, "unsupported websocket subprotocol").into_response();
+    };
+    let Some(claim) = receiver.tx.claim() else {
+        return (StatusCode::CONFLICT, "the transmitter is in use").into_response();
+    };
+    let transmitter = match crate::input::open_transmitter(&receiver_id, &receiver.receiver.tx) {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!(receiver_id, error = ?e, "failed to open transmitter");
+            return (StatusCode::SERVICE_UNAVAILABLE, format!("{e:#}")).into_response();
+        }
+    };
+    let session = Session::new(receiver.receiver.tx.clone(), transmitter);
+    let (status_tx, status_rx) = tokio::sync::mpsc::unbounded_channel();
+    let handle = match SessionHandle::spawn(session, claim, status_tx) {
+        Ok(h) => h,
+        Err(e) => {
+            tracing::error!(receiver_id, error = ?e, "failed to start tx session");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    tracing::warn!(receiver_id, ip = %addr.ip(), "operator tx session opened");
+    ws.protocols([protocol.as_str()])
+        .on_upgrade(move |socket| async move {
+            handle_socket(socket, handle, status_rx, format).await;
+            tracing::warn!(receiver_id, ip = %addr.ip(), "operator tx session closed");
+        })
+}
+
+async fn handle_socket(
+    socket: ws::WebSocket,
+    session: SessionHandle,
+    mut status_rx: tokio::sync::mpsc::UnboundedReceiver<crate::tx::Status>,
+    format: AudioFormat,
+) {
+    let (mut ws_sender, mut ws_receiver) = socket.split();
+    let send_task = tokio::spawn(async move {
+        while let Some(status) = status_rx.recv().await {
+            let mut msg = json!(status);
+            msg["type"] = json!("tx");
+            if ws_sender
+                .send(ws::Message::Text(msg.to_string()))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let mut decoder = match format {
+        AudioFormat::Pcm => None,
+        AudioFormat::Opus => {
+            match opus::Decoder::new(opus::SampleRate::Hz12000, opus::Channels::Mono) {
+                Ok(d) => Some(d),
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to create opus decoder");
+                    send_task.abort();
+                    return;
+                }
+            }
+        }
+    };
+    let mut pcm = vec![0.0f32; MAX_OPUS_FRAME];
+    let idle_timeout = Duration::from_secs(30);
+    loop {
+        let Ok(Some(Ok(msg))) = tokio::time::timeout(idle_timeout, ws_receiver.next()).await else {
+            break;
+        };
+        let command = match msg {
+            ws::Message::Text(text) => match serde_json::from_str::<TxCommand>(&text) {
+                Ok(TxCommand::Tune { frequency, mode }) => match TxMode::parse(&mode) {
+                    Some(mode) => Command::Tune { frequency, mode },
+                    None => continue,
+                },
+                Ok(TxCommand::Power { percent }) => Command::Power { percent },
+                Ok(TxCommand::Ptt { on }) => Command::Ptt { on },
+                Err(_) => continue,
+            },
+            ws::Message::Binary(bytes) => match decoder.as_mut() {
+                None => Command::Audio(
+                    bytes
+                        .chunks_exact(2)
+                        .map(|b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0)
+                        .collect(),
+                ),
+                Some(decoder) => match decoder.decode_float(&bytes, &mut pcm) {
+                    Ok(n) => Command::Audio(pcm[..n].to_vec()),
+                    Err(e) => {
+                        tracing::debug!(error = %e, "dropping undecodable tx opus packet");
+                        continue;
+                    }
+                },
+            },
+            ws::Message::Close(_) => break,
+            _ => continue,
+        };
+        // Audio beyond what the transmitter keeps up with is dropped, not queued.
+        session.send(command);
+    }
+
+    // Dropping the handle ends the session thread, which unkeys.
+    drop(session);
+    send_task.abort();
+}
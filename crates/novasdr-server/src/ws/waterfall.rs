@@ -1,54 +1,110 @@
-use crate::state::{AppState, ClientId, WaterfallClient, WaterfallParams};
+use crate::state::{
+    AppState, ClientId, ReceiverState, WaterfallAggregation, WaterfallClient, WaterfallParams,
+    MAX_WATERFALL_WINDOWS,
+};
+use crate::waterfall_compress::{Codec, PacketKey};
 use axum::{
     extract::connect_info::ConnectInfo,
-    extract::{ws, State, WebSocketUpgrade},
-    http::StatusCode,
+    extract::{ws, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
 use futures::{SinkExt, StreamExt};
-use novasdr_core::{codec::zstd_stream::ZstdStreamEncoder, protocol::WaterfallPacket};
+use novasdr_core::{config::WaterfallCompression, protocol::WaterfallPacket};
+use std::borrow::Cow;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
 pub async fn upgrade(
     ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(marker_support): Query<crate::markers::MarkerSupport>,
+    Query(preference): Query<CompressionPreference>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<Arc<AppState>>,
 ) -> axum::response::Response {
-    let Some(ip_guard) = state.try_acquire_ws_ip(addr.ip()) else {
+    let Some(protocol) = super::subprotocol::negotiate(&headers, "waterfall") else {
+        return (StatusCode::BAD_REQUEST, "unsupported websocket subprotocol").into_response();
+    };
+    let ip_guard = match state.try_acquire_ws_ip(addr.ip()) {
+        Ok(guard) => guard,
+        Err(rejection) => return rejection.into_response(),
+    };
+    if state.total_waterfall_clients() >= state.cfg().limits.waterfall {
+        return (StatusCode::TOO_MANY_REQUESTS, "too many waterfall clients").into_response();
+    }
+    let receiver = state.active_receiver_state();
+    if receiver.waterfall_full() {
+        let message_json = receiver.full_message("waterfall");
+        return ws
+            .protocols([protocol.as_str()])
+            .on_upgrade(move |socket| super::refuse(socket, message_json));
+    }
+    if !crate::quota::allows_waterfall(&state, addr.ip()) {
         return (
             StatusCode::TOO_MANY_REQUESTS,
-            "too many connections from this IP",
+            crate::quota::QuotaRejection::ListeningTimeUsed.message(),
         )
             .into_response();
-    };
-    if state.total_waterfall_clients() >= state.cfg.limits.waterfall {
-        return (StatusCode::TOO_MANY_REQUESTS, "too many waterfall clients").into_response();
     }
-    ws.on_upgrade(|socket| handle(socket, state, ip_guard))
+    let markers_api = marker_support.uses_api();
+    let requested = preference
+        .compression
+        .as_deref()
+        .and_then(WaterfallCompression::parse);
+    ws.protocols([protocol.as_str()])
+        .on_upgrade(move |socket| handle(socket, state, ip_guard, protocol, markers_api, requested))
+}
+
+/// `?compression=` on `/waterfall`: the client's preferred waterfall compression.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct CompressionPreference {
+    #[serde(default)]
+    compression: Option<String>,
 }
 
 enum WaterfallOutbound {
-    Switch { settings_json: String },
+    Switch {
+        settings_json: String,
+        encoder: WaterfallEncoder,
+    },
+    Rate {
+        interval: Option<Duration>,
+    },
+    Text(String),
+    /// History rows of the coarsest level, cropped to `l..r` of it.
+    Catchup {
+        window: u8,
+        level: usize,
+        l: usize,
+        r: usize,
+        rows: Vec<crate::waterfall_history::HistoryRow>,
+    },
 }
 
-async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::state::WsIpGuard) {
+/// Catch-up requests closer together than this are ignored.
+const CATCHUP_MIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Lowest rate a client can ask for; below this the idle timeout on slow links gets close.
+const MIN_WATERFALL_FPS: f64 = 0.5;
+
+async fn handle(
+    socket: ws::WebSocket,
+    state: Arc<AppState>,
+    _ip_guard: crate::state::WsIpGuard,
+    protocol: super::subprotocol::ProtocolVersion,
+    markers_api: bool,
+    requested: Option<WaterfallCompression>,
+) {
     let client_id = state.alloc_client_id();
-    tracing::info!(client_id, "waterfall ws connected");
+    tracing::info!(client_id, %protocol, "waterfall ws connected");
 
     let mut receiver_id = state.active_receiver_id().to_string();
     let mut receiver = state.active_receiver_state().clone();
 
     let (tx, mut rx) = crate::state::waterfall_channel();
     let (out_tx, mut out_rx) = tokio::sync::mpsc::channel::<WaterfallOutbound>(8);
-    let encoder = match WaterfallEncoder::new() {
-        Ok(e) => e,
-        Err(e) => {
-            tracing::error!(client_id, error = ?e, "waterfall encoder init failed");
-            return;
-        }
-    };
 
     let initial_level = receiver.rt.downsample_levels - 1;
     let initial_l = 0usize;
@@ -56,17 +112,22 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
 
     let client = Arc::new(WaterfallClient {
         tx,
+        window: 0,
         params: std::sync::Mutex::new(WaterfallParams {
             level: initial_level,
             l: initial_l,
             r: initial_r,
+            aggregation: WaterfallAggregation::None,
         }),
     });
 
     let (mut ws_sender, mut ws_receiver) = socket.split();
     let state_for_send = state.clone();
     let send_task = tokio::spawn(async move {
-        let mut encoder = encoder;
+        // Arrives with the first settings message, before any frame.
+        let mut encoder: Option<WaterfallEncoder> = None;
+        let mut aggregators: [RowAggregator; MAX_WATERFALL_WINDOWS] = Default::default();
+        let mut thinners: [FrameThinner; MAX_WATERFALL_WINDOWS] = Default::default();
         let mut ping_interval = tokio::time::interval(Duration::from_secs(30));
         ping_interval.tick().await; // consume immediate first tick
         loop {
@@ -74,22 +135,66 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                 biased;
                 Some(cmd) = out_rx.recv() => {
                     match cmd {
-                        WaterfallOutbound::Switch { settings_json } => {
+                        WaterfallOutbound::Switch { settings_json, encoder: next } => {
                             while rx.try_recv().is_ok() {}
-                            encoder = match WaterfallEncoder::new() {
-                                Ok(e) => e,
-                                Err(e) => {
-                                    tracing::error!(client_id, error = ?e, "waterfall encoder reinit failed");
+                            aggregators = Default::default();
+                            encoder = Some(next);
+                            if ws_sender.send(ws::Message::Text(settings_json)).await.is_err() {
+                                break;
+                            }
+                        }
+                        WaterfallOutbound::Rate { interval } => {
+                            thinners = std::array::from_fn(|_| FrameThinner { interval, next_due: None });
+                        }
+                        WaterfallOutbound::Text(message_json) => {
+                            if ws_sender.send(ws::Message::Text(message_json)).await.is_err() {
+                                break;
+                            }
+                        }
+                        WaterfallOutbound::Catchup { window, level, l, r, rows } => {
+                            let Some(encoder) = encoder.as_ref() else {
+                                continue;
+                            };
+                            let mut failed = false;
+                            for row in rows {
+                                let Some(data) = row.data.get(l..r) else {
+                                    continue;
+                                };
+                                let key = PacketKey { frame_num: row.frame_num, window, level, l, r };
+                                let pkt = match encoder.encode(key, data, false).await {
+                                    Ok(pkt) => pkt,
+                                    Err(e) => {
+                                        tracing::warn!(client_id, error = ?e, "waterfall catch-up encode failed");
+                                        break;
+                                    }
+                                };
+                                state_for_send
+                                    .total_waterfall_bits
+                                    .fetch_add(pkt.len() * 8, std::sync::atomic::Ordering::Relaxed);
+                                if ws_sender.send(ws::Message::Binary(pkt.to_vec())).await.is_err() {
+                                    failed = true;
                                     break;
                                 }
-                            };
-                            if ws_sender.send(ws::Message::Text(settings_json)).await.is_err() {
+                            }
+                            if failed {
                                 break;
                             }
                         }
                     }
                 }
                 Some(item) = rx.recv() => {
+                    let Some(encoder) = encoder.as_ref() else {
+                        continue;
+                    };
+                    let window = usize::from(item.window);
+                    let (Some(thinner), Some(aggregator)) =
+                        (thinners.get_mut(window), aggregators.get_mut(window))
+                    else {
+                        continue;
+                    };
+                    if !thinner.admit(tokio::time::Instant::now()) {
+                        continue;
+                    }
                     let want_len = item.r.saturating_sub(item.l);
                     let Some(end) = item.quantized_offset.checked_add(want_len) else {
                         tracing::warn!(
@@ -113,7 +218,18 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                         );
                         continue;
                     };
-                    let pkt = match encoder.encode(item.frame_num, item.level, item.l, item.r, data) {
+                    let Some(data) = aggregator.push(&item, data) else {
+                        continue;
+                    };
+                    let key = PacketKey {
+                        frame_num: item.frame_num,
+                        window: item.window,
+                        level: item.level,
+                        l: item.l,
+                        r: item.r,
+                    };
+                    let live = item.aggregation == WaterfallAggregation::None;
+                    let pkt = match encoder.encode(key, &data, live).await {
                         Ok(pkt) => pkt,
                         Err(e) => {
                             tracing::warn!(client_id, error = ?e, "waterfall encode failed; dropping frame");
@@ -125,7 +241,7 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                         .total_waterfall_bits
                         .fetch_add(pkt.len() * 8, std::sync::atomic::Ordering::Relaxed);
 
-                    if ws_sender.send(ws::Message::Binary(pkt)).await.is_err() {
+                    if ws_sender.send(ws::Message::Binary(pkt.to_vec())).await.is_err() {
                         break;
                     }
                 }
@@ -139,10 +255,16 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
         }
     });
 
-    let basic_info = state.basic_info_json(receiver_id.as_str()).await;
+    let encoder = WaterfallEncoder::negotiate(&receiver, requested);
+    let basic_info = encoder.settings(
+        state
+            .basic_info_json(receiver_id.as_str(), markers_api)
+            .await,
+    );
     if out_tx
         .send(WaterfallOutbound::Switch {
             settings_json: basic_info,
+            encoder,
         })
         .await
         .is_err()
@@ -152,10 +274,27 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
     }
 
     receiver.waterfall_clients[initial_level].insert(client_id, client.clone());
+    let mut extra_windows = ExtraWindows::default();
 
     let idle_timeout = Duration::from_secs(90);
+    let mut last_catchup: Option<tokio::time::Instant> = None;
+    let mut overload = receiver.overload_changes();
     loop {
-        let maybe_msg = match tokio::time::timeout(idle_timeout, ws_receiver.next()).await {
+        let next = tokio::select! {
+            next = tokio::time::timeout(idle_timeout, ws_receiver.next()) => next,
+            Ok(()) = overload.changed() => {
+                let message_json = receiver.overload_message();
+                if out_tx
+                    .send(WaterfallOutbound::Text(message_json))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                continue;
+            }
+        };
+        let maybe_msg = match next {
             Ok(v) => v,
             Err(_) => {
                 tracing::info!(client_id, "waterfall ws idle timeout");
@@ -187,7 +326,21 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                         else {
                             continue;
                         };
-                        let next_basic_info = state.basic_info_json(next_id.as_str()).await;
+                        if is_switch && next_receiver.waterfall_full() {
+                            // The client stays where it is.
+                            let message_json = next_receiver.full_message("waterfall");
+                            if out_tx
+                                .send(WaterfallOutbound::Text(message_json))
+                                .await
+                                .is_err()
+                            {
+                                break;
+                            }
+                            continue;
+                        }
+                        let next_encoder = WaterfallEncoder::negotiate(&next_receiver, requested);
+                        let next_basic_info = next_encoder
+                            .settings(state.basic_info_json(next_id.as_str(), markers_api).await);
 
                         let old_level = match client.params.lock() {
                             Ok(g) => g.level,
@@ -200,6 +353,7 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                             }
                         };
                         receiver.waterfall_clients[old_level].remove(&client_id);
+                        extra_windows.close_all(&receiver);
 
                         let next_initial_level = next_receiver.rt.downsample_levels - 1;
                         let next_initial_r = next_receiver.rt.min_waterfall_fft;
@@ -222,6 +376,7 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                         if out_tx
                             .send(WaterfallOutbound::Switch {
                                 settings_json: next_basic_info,
+                                encoder: next_encoder,
                             })
                             .await
                             .is_err()
@@ -234,11 +389,98 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
                                 .insert(client_id, client.clone());
                             receiver_id = next_id;
                             receiver = next_receiver;
+                            overload = receiver.overload_changes();
                         } else {
                             receiver.waterfall_clients[next_initial_level]
                                 .insert(client_id, client.clone());
                         }
                     }
+                    novasdr_core::protocol::ClientCommand::Catchup {
+                        after,
+                        before,
+                        lines,
+                        window,
+                    } => {
+                        let window = window.unwrap_or(0);
+                        let target = if window == 0 {
+                            client.clone()
+                        } else {
+                            match extra_windows.get(window) {
+                                Some((_, target)) => target,
+                                None => continue,
+                            }
+                        };
+                        let now = tokio::time::Instant::now();
+                        if after >= before
+                            || last_catchup.is_some_and(|t| now - t < CATCHUP_MIN_INTERVAL)
+                        {
+                            continue;
+                        }
+                        last_catchup = Some(now);
+                        let p = match target.params.lock() {
+                            Ok(g) => g.clone(),
+                            Err(poisoned) => {
+                                tracing::error!(
+                                    client_id,
+                                    "waterfall params mutex poisoned; recovering"
+                                );
+                                poisoned.into_inner().clone()
+                            }
+                        };
+                        let rt = receiver.rt.as_ref();
+                        let level = rt.downsample_levels - 1;
+                        let width = rt.fft_result_size >> level;
+                        // The client's window in bins of the coarsest level, widened to whole bins.
+                        let l = (p.l << p.level) >> level;
+                        let r = (((p.r << p.level) + (1 << level) - 1) >> level).min(width);
+                        if l >= r {
+                            continue;
+                        }
+                        let lines = lines
+                            .map_or(crate::waterfall_history::MAX_CATCHUP_LINES, |n| n as usize);
+                        let rows = receiver.waterfall_history.summary(after, before, lines);
+                        if rows.is_empty() {
+                            continue;
+                        }
+                        if out_tx
+                            .send(WaterfallOutbound::Catchup {
+                                window,
+                                level,
+                                l,
+                                r,
+                                rows,
+                            })
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    novasdr_core::protocol::ClientCommand::WaterfallRate { fps } => {
+                        let interval = (fps.is_finite() && fps > 0.0)
+                            .then(|| Duration::from_secs_f64(1.0 / fps.max(MIN_WATERFALL_FPS)));
+                        if out_tx
+                            .send(WaterfallOutbound::Rate { interval })
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    novasdr_core::protocol::ClientCommand::CloseWindow { window } => {
+                        extra_windows.close(&receiver, window);
+                    }
+                    cmd @ novasdr_core::protocol::ClientCommand::Window {
+                        window: Some(window),
+                        ..
+                    } if window > 0 => {
+                        let Some((window_id, target)) =
+                            extra_windows.open(&state, &receiver, &client.tx, window)
+                        else {
+                            continue;
+                        };
+                        apply_command(&state, &receiver, window_id, &target, cmd);
+                    }
                     other => {
                         apply_command(&state, &receiver, client_id, &client, other);
                     }
@@ -257,10 +499,75 @@ async fn handle(socket: ws::WebSocket, state: Arc<AppState>, _ip_guard: crate::s
         }
     };
     receiver.waterfall_clients[level].remove(&client_id);
+    extra_windows.close_all(&receiver);
     tracing::info!(client_id, "waterfall ws disconnected");
     send_task.abort();
 }
 
+/// Windows 1.. of a connection, each registered under its own id and sharing the connection's
+/// queue. They start on the whole band at the coarsest level, like a new connection.
+#[derive(Default)]
+struct ExtraWindows {
+    slots: [Option<(ClientId, Arc<WaterfallClient>)>; MAX_WATERFALL_WINDOWS - 1],
+}
+
+impl ExtraWindows {
+    fn get(&self, window: u8) -> Option<(ClientId, Arc<WaterfallClient>)> {
+        let slot = usize::from(window).checked_sub(1)?;
+        self.slots.get(slot)?.clone()
+    }
+
+    fn open(
+        &mut self,
+        state: &AppState,
+        receiver: &ReceiverState,
+        tx: &tokio::sync::mpsc::Sender<crate::state::WaterfallWorkItem>,
+        window: u8,
+    ) -> Option<(ClientId, Arc<WaterfallClient>)> {
+        let slot = self.slots.get_mut(usize::from(window).checked_sub(1)?)?;
+        if let Some(open) = slot.as_ref() {
+            return Some(open.clone());
+        }
+        let level = receiver.rt.downsample_levels - 1;
+        let id = state.alloc_client_id();
+        let client = Arc::new(WaterfallClient {
+            tx: tx.clone(),
+            window,
+            params: std::sync::Mutex::new(WaterfallParams {
+                level,
+                l: 0,
+                r: receiver.rt.min_waterfall_fft,
+                aggregation: WaterfallAggregation::None,
+            }),
+        });
+        receiver.waterfall_clients[level].insert(id, client.clone());
+        *slot = Some((id, client.clone()));
+        Some((id, client))
+    }
+
+    fn close(&mut self, receiver: &ReceiverState, window: u8) {
+        let Some(slot) = usize::from(window)
+            .checked_sub(1)
+            .and_then(|i| self.slots.get_mut(i))
+        else {
+            return;
+        };
+        if let Some((id, client)) = slot.take() {
+            let level = match client.params.lock() {
+                Ok(g) => g.level,
+                Err(poisoned) => poisoned.into_inner().level,
+            };
+            receiver.waterfall_clients[level].remove(&id);
+        }
+    }
+
+    fn close_all(&mut self, receiver: &ReceiverState) {
+        for window in 1..=self.slots.len() as u8 {
+            self.close(receiver, window);
+        }
+    }
+}
+
 fn apply_command(
     _state: &Arc<AppState>,
     receiver: &Arc<crate::state::ReceiverState>,
@@ -269,10 +576,28 @@ fn apply_command(
     cmd: novasdr_core::protocol::ClientCommand,
 ) {
     let rt = receiver.rt.as_ref();
-    let novasdr_core::protocol::ClientCommand::Window { l, r, .. } = cmd else {
+    let novasdr_core::protocol::ClientCommand::Window {
+        l,
+        r,
+        aggregate,
+        frames,
+        ..
+    } = cmd
+    else {
         return;
     };
 
+    if let Some(aggregate) = aggregate {
+        let aggregation = WaterfallAggregation::parse(aggregate.as_str(), frames);
+        match client.params.lock() {
+            Ok(mut g) => g.aggregation = aggregation,
+            Err(poisoned) => {
+                tracing::error!(client_id, "waterfall params mutex poisoned; recovering");
+                poisoned.into_inner().aggregation = aggregation;
+            }
+        }
+    }
+
     if l < 0 || r < 0 || l >= r {
         return;
     }
@@ -323,32 +648,328 @@ fn apply_command(
     p.r = new_r_usize;
 }
 
+/// Drops frames to hold a client to its requested rate. Due times advance by whole intervals,
+/// so a rate that does not divide the server's still averages out right.
+#[derive(Default)]
+struct FrameThinner {
+    interval: Option<Duration>,
+    next_due: Option<tokio::time::Instant>,
+}
+
+impl FrameThinner {
+    fn admit(&mut self, now: tokio::time::Instant) -> bool {
+        let Some(interval) = self.interval else {
+            return true;
+        };
+        match self.next_due {
+            Some(due) if now < due => false,
+            // After a stall, restart from now instead of bursting to catch up.
+            Some(due) if now < due + interval => {
+                self.next_due = Some(due + interval);
+                true
+            }
+            _ => {
+                self.next_due = Some(now + interval);
+                true
+            }
+        }
+    }
+}
+
+/// Combines a client's rows per [`WaterfallAggregation`]. Sums stay in `i32` so an average is
+/// rounded once, on the row that is sent.
+#[derive(Default)]
+struct RowAggregator {
+    /// Window and setting the pending frames were taken with; any change starts over.
+    key: Option<(usize, usize, usize, WaterfallAggregation)>,
+    acc: Vec<i32>,
+    count: u8,
+}
+
+impl RowAggregator {
+    /// The row to send for this frame, or `None` while frames are still being collected.
+    fn push<'a>(
+        &mut self,
+        item: &crate::state::WaterfallWorkItem,
+        data: &'a [i8],
+    ) -> Option<Cow<'a, [i8]>> {
+        let frames = match item.aggregation {
+            WaterfallAggregation::None => {
+                self.key = None;
+                return Some(Cow::Borrowed(data));
+            }
+            WaterfallAggregation::Average(n) | WaterfallAggregation::PeakHold(n) => n,
+        };
+        let key = (item.level, item.l, item.r, item.aggregation);
+        if self.key != Some(key) || self.acc.len() != data.len() {
+            self.key = Some(key);
+            self.acc.clear();
+            self.acc.extend(data.iter().map(|&v| i32::from(v)));
+            self.count = 1;
+        } else {
+            let peak = matches!(item.aggregation, WaterfallAggregation::PeakHold(_));
+            for (a, &v) in self.acc.iter_mut().zip(data) {
+                *a = if peak {
+                    (*a).max(i32::from(v))
+                } else {
+                    *a + i32::from(v)
+                };
+            }
+            self.count += 1;
+        }
+        if self.count < frames {
+            return None;
+        }
+        let divisor = match item.aggregation {
+            WaterfallAggregation::Average(_) => i32::from(self.count),
+            _ => 1,
+        };
+        let row = self
+            .acc
+            .iter()
+            .map(|&a| ((a as f32) / (divisor as f32)).round() as i8)
+            .collect();
+        self.key = None;
+        Some(Cow::Owned(row))
+    }
+}
+
 pub struct WaterfallEncoder {
-    zstd: ZstdStreamEncoder,
+    compression: WaterfallCompression,
+    codec: Codec,
+    /// Receiver whose [`PacketCache`](crate::waterfall_compress::PacketCache) live frames go
+    /// through.
+    receiver: Arc<ReceiverState>,
+    dictionary: Option<Arc<crate::waterfall_dictionary::WaterfallDictionary>>,
+    /// Set while this client wants `zstd-dict` but the receiver has no dictionary yet.
+    sample_for: Option<Arc<ReceiverState>>,
 }
 
 impl WaterfallEncoder {
-    pub fn new() -> anyhow::Result<Self> {
-        Ok(Self {
-            zstd: ZstdStreamEncoder::new(3)?,
-        })
+    /// Plain zstd, as before negotiation existed.
+    fn zstd(receiver: &Arc<ReceiverState>) -> Self {
+        Self {
+            compression: WaterfallCompression::Zstd,
+            codec: Codec::Zstd,
+            receiver: receiver.clone(),
+            dictionary: None,
+            sample_for: None,
+        }
     }
 
-    pub fn encode(
-        &mut self,
-        frame_num: u64,
-        level: usize,
-        l: usize,
-        r: usize,
+    /// Encoder for the client's `requested` compression, else the receiver's configured one.
+    /// `zstd-dict` falls back to `zstd` until the receiver's dictionary is trained.
+    pub fn negotiate(
+        receiver: &Arc<ReceiverState>,
+        requested: Option<WaterfallCompression>,
+    ) -> Self {
+        let wanted = requested.unwrap_or(receiver.receiver.input.waterfall_compression);
+        match wanted {
+            WaterfallCompression::Zstd => Self::zstd(receiver),
+            WaterfallCompression::Lz4 => Self {
+                compression: WaterfallCompression::Lz4,
+                codec: Codec::Lz4,
+                ..Self::zstd(receiver)
+            },
+            WaterfallCompression::ZstdDict => match receiver.waterfall_dictionary.get() {
+                Some(dictionary) => Self {
+                    compression: WaterfallCompression::ZstdDict,
+                    codec: Codec::ZstdDict(dictionary.clone()),
+                    dictionary: Some(dictionary),
+                    ..Self::zstd(receiver)
+                },
+                None => Self {
+                    sample_for: Some(receiver.clone()),
+                    ..Self::zstd(receiver)
+                },
+            },
+        }
+    }
+
+    /// `settings_json` with the compression this encoder uses, the ones the receiver offers
+    /// and, for `zstd-dict`, the dictionary.
+    pub fn settings(&self, settings_json: String) -> String {
+        let Ok(serde_json::Value::Object(mut map)) =
+            serde_json::from_str::<serde_json::Value>(&settings_json)
+        else {
+            return settings_json;
+        };
+        let ready = self.dictionary.is_some()
+            || self
+                .sample_for
+                .as_ref()
+                .is_some_and(|r| r.waterfall_dictionary.get().is_some());
+        let offered: Vec<&str> = WaterfallCompression::ALL
+            .into_iter()
+            .filter(|c| *c != WaterfallCompression::ZstdDict || ready)
+            .map(WaterfallCompression::as_str)
+            .collect();
+        map.insert(
+            "waterfall_compression".to_string(),
+            serde_json::json!(self.compression.as_str()),
+        );
+        map.insert(
+            "waterfall_compressions".to_string(),
+            serde_json::json!(offered),
+        );
+        if let Some(dictionary) = self.dictionary.as_deref() {
+            map.insert(
+                "waterfall_dictionary".to_string(),
+                serde_json::json!({ "id": dictionary.id, "data": dictionary.base64 }),
+            );
+        }
+        serde_json::Value::Object(map).to_string()
+    }
+
+    /// Packet of `data` for the view in `key`. A `live` row is what every client with this view
+    /// gets for the frame, so it is compressed once per receiver and shared; other rows
+    /// (aggregated, catch-up) are this client's own.
+    pub async fn encode(
+        &self,
+        key: PacketKey,
         data: &[i8],
-    ) -> anyhow::Result<Vec<u8>> {
-        let pkt = WaterfallPacket {
-            frame_num,
-            l: (l << level) as i32,
-            r: (r << level) as i32,
-            data: bytemuck::cast_slice::<i8, u8>(data),
+        live: bool,
+    ) -> anyhow::Result<Arc<[u8]>> {
+        let packet = || {
+            let cbor = packet_cbor(key, data)?;
+            if let Some(receiver) = self.sample_for.as_ref() {
+                receiver
+                    .waterfall_dictionary
+                    .offer(receiver.receiver.id.as_str(), &cbor);
+            }
+            Ok(cbor)
+        };
+        if live {
+            self.receiver
+                .waterfall_packets
+                .get_or_compress(&self.codec, key, packet)
+                .await
+        } else {
+            crate::waterfall_compress::compress(self.codec.clone(), packet()?)
+                .await
+                .map(Arc::from)
+        }
+    }
+}
+
+/// The uncompressed packet of `data` for the view in `key`; bins are given at full resolution.
+fn packet_cbor(key: PacketKey, data: &[i8]) -> anyhow::Result<Vec<u8>> {
+    let pkt = WaterfallPacket {
+        frame_num: key.frame_num,
+        l: (key.l << key.level) as i32,
+        r: (key.r << key.level) as i32,
+        window: (key.window > 0).then_some(key.window),
+        data: bytemuck::cast_slice::<i8, u8>(data),
+    };
+    Ok(serde_cbor::to_vec(&pkt)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::WaterfallWorkItem;
+
+    fn item(aggregation: WaterfallAggregation, l: usize) -> WaterfallWorkItem {
+        WaterfallWorkItem {
+            frame_num: 0,
+            level: 0,
+            l,
+            r: l + 2,
+            window: 0,
+            aggregation,
+            quantized_concat: Arc::from(Vec::new()),
+            quantized_offset: 0,
+        }
+    }
+
+    #[test]
+    fn aggregation_emits_one_row_per_n_frames() {
+        let mut agg = RowAggregator::default();
+        let avg = item(WaterfallAggregation::Average(3), 0);
+        assert!(agg.push(&avg, &[-10, 0]).is_none());
+        assert!(agg.push(&avg, &[-20, 1]).is_none());
+        assert_eq!(agg.push(&avg, &[-30, 1]).as_deref(), Some(&[-20i8, 1][..]));
+
+        let peak = item(WaterfallAggregation::PeakHold(2), 0);
+        assert!(agg.push(&peak, &[-10, 5]).is_none());
+        assert_eq!(agg.push(&peak, &[-20, 7]).as_deref(), Some(&[-10i8, 7][..]));
+
+        let none = item(WaterfallAggregation::None, 0);
+        assert_eq!(agg.push(&none, &[3, 4]).as_deref(), Some(&[3i8, 4][..]));
+    }
+
+    #[test]
+    fn aggregation_restarts_when_the_window_moves() {
+        let mut agg = RowAggregator::default();
+        assert!(agg
+            .push(&item(WaterfallAggregation::Average(2), 0), &[100, 100])
+            .is_none());
+        let moved = item(WaterfallAggregation::Average(2), 4);
+        assert!(agg.push(&moved, &[0, 0]).is_none());
+        assert_eq!(agg.push(&moved, &[-2, 2]).as_deref(), Some(&[-1i8, 1][..]));
+    }
+
+    #[test]
+    fn packets_are_tagged_with_their_window_except_the_primary_one() {
+        let window_of = |cbor: Vec<u8>| {
+            let value: serde_cbor::Value = serde_cbor::from_slice(&cbor).unwrap();
+            let serde_cbor::Value::Map(map) = value else {
+                panic!("packet is not a map");
+            };
+            map.get(&serde_cbor::Value::Text("window".to_string()))
+                .cloned()
+        };
+        let key = PacketKey {
+            frame_num: 7,
+            window: 0,
+            level: 2,
+            l: 10,
+            r: 20,
+        };
+        let primary = packet_cbor(key, &[1, 2]).unwrap();
+        assert_eq!(window_of(primary), None);
+        let detail = packet_cbor(
+            PacketKey {
+                window: 2,
+                level: 0,
+                ..key
+            },
+            &[1, 2],
+        )
+        .unwrap();
+        assert_eq!(window_of(detail), Some(serde_cbor::Value::Integer(2)));
+    }
+
+    #[test]
+    fn thinning_holds_a_rate_that_does_not_divide_the_frame_rate() {
+        let start = tokio::time::Instant::now();
+        let mut thinner = FrameThinner {
+            interval: Some(Duration::from_secs_f64(1.0 / 15.0)),
+            next_due: None,
         };
-        let cbor = serde_cbor::to_vec(&pkt)?;
-        self.zstd.compress_flush(&cbor)
+        // Two seconds of frames at 20 fps.
+        let sent = (0..40)
+            .filter(|&i| thinner.admit(start + Duration::from_millis(50 * i)))
+            .count();
+        assert!((29..=31).contains(&sent), "sent {sent}");
+
+        let mut full = FrameThinner::default();
+        assert!((0..10).all(|i| full.admit(start + Duration::from_millis(i))));
+    }
+
+    #[test]
+    fn aggregation_setting_parses_and_clamps_frames() {
+        assert_eq!(
+            WaterfallAggregation::parse("average", None),
+            WaterfallAggregation::Average(4)
+        );
+        assert_eq!(
+            WaterfallAggregation::parse("peak", Some(100)),
+            WaterfallAggregation::PeakHold(16)
+        );
+        assert_eq!(
+            WaterfallAggregation::parse("none", Some(8)),
+            WaterfallAggregation::None
+        );
     }
 }
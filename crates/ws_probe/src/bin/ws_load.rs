@@ -0,0 +1,471 @@
+use anyhow::Context;
+use clap::Parser;
+use futures_util::{SinkExt, StreamExt};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+
+/// A gap between two stream frames at least this long counts as a stall.
+const STALL: Duration = Duration::from_secs(1);
+/// Time allowed for the settings message after connecting.
+const SETTINGS_TIMEOUT: Duration = Duration::from_secs(10);
+const AUDIO_MODES: [&str; 4] = ["USB", "LSB", "AM", "FM"];
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "ws_load",
+    version,
+    about = "Open many simulated NovaSDR listeners and report how the server keeps up."
+)]
+struct Args {
+    /// Server base URL (example: ws://127.0.0.1:9002)
+    url: String,
+
+    /// Simulated listeners; each opens an /audio and a /waterfall session like a browser
+    #[arg(long, default_value_t = 10)]
+    clients: usize,
+
+    /// Only open /audio sessions
+    #[arg(long, conflicts_with = "waterfall_only")]
+    audio_only: bool,
+
+    /// Only open /waterfall sessions
+    #[arg(long)]
+    waterfall_only: bool,
+
+    /// Test length (seconds), counted from the first connection
+    #[arg(long, default_value_t = 60)]
+    duration_secs: u64,
+
+    /// Delay between starting two listeners (milliseconds), so load ramps up over the run
+    #[arg(long, default_value_t = 100)]
+    ramp_ms: u64,
+
+    /// Mean time between random tuning commands per session (milliseconds; 0 = never retune)
+    #[arg(long, default_value_t = 5000)]
+    retune_ms: u64,
+
+    /// Interval between progress reports (seconds)
+    #[arg(long, default_value_t = 5)]
+    report_secs: u64,
+
+    /// Seed for the tuning commands, to repeat a run exactly
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Audio,
+    Waterfall,
+}
+
+impl Kind {
+    fn path(self) -> &'static str {
+        match self {
+            Kind::Audio => "audio",
+            Kind::Waterfall => "waterfall",
+        }
+    }
+}
+
+/// xorshift64*: enough to spread tuning commands, and reproducible from `--seed`.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in `lo..hi`; `lo` when the range is empty.
+    fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        if hi <= lo {
+            return lo;
+        }
+        lo + (self.next() % (hi - lo) as u64) as i64
+    }
+}
+
+/// What the settings message says about the receiver's spectrum.
+#[derive(Debug, Clone, Copy)]
+struct Spectrum {
+    fft_result_size: i64,
+    audio_max_fft: i64,
+}
+
+impl Spectrum {
+    fn from_settings(text: &str) -> anyhow::Result<Self> {
+        let v: serde_json::Value = serde_json::from_str(text).context("settings json")?;
+        let field = |name: &str| v.get(name).and_then(|x| x.as_i64());
+        Ok(Self {
+            fft_result_size: field("fft_result_size").context("settings: fft_result_size")?,
+            // `/waterfall` settings do not carry it; audio windows are never built from those.
+            audio_max_fft: field("audio_max_fft").unwrap_or(0),
+        })
+    }
+}
+
+/// A random audio tuning: a mode and a window of up to half the audio FFT around `m`.
+fn audio_command(rng: &mut Rng, spectrum: Spectrum) -> serde_json::Value {
+    if rng.next().is_multiple_of(4) {
+        let mode = AUDIO_MODES[rng.next() as usize % AUDIO_MODES.len()];
+        return serde_json::json!({ "cmd": "demodulation", "demodulation": mode });
+    }
+    let width = rng.range(
+        (spectrum.audio_max_fft / 8).max(1),
+        (spectrum.audio_max_fft / 2).max(2),
+    );
+    let m = rng.range(width, spectrum.fft_result_size - width);
+    let (l, r) = match rng.next() % 3 {
+        0 => (m, m + width),
+        1 => (m - width, m),
+        _ => (m - width / 2, m + width / 2),
+    };
+    serde_json::json!({ "cmd": "window", "l": l, "r": r, "m": m as f64 })
+}
+
+/// A random waterfall view: anything from full span down to 1/64 of it.
+fn waterfall_command(rng: &mut Rng, spectrum: Spectrum) -> serde_json::Value {
+    let zoom = 1i64 << rng.range(0, 7);
+    let width = (spectrum.fft_result_size / zoom).max(1);
+    let l = rng.range(0, spectrum.fft_result_size - width + 1);
+    serde_json::json!({ "cmd": "window", "l": l, "r": l + width })
+}
+
+#[derive(Default)]
+struct Samples {
+    /// Gaps between stream frames since the last report (milliseconds).
+    gaps_ms: Vec<u32>,
+    /// Every gap of the run, for the summary.
+    all_gaps_ms: Vec<u32>,
+    /// Connect to settings message, per session.
+    connect_ms: Vec<u32>,
+}
+
+/// Counters of one session kind. Rates are taken per report interval by swapping them to zero.
+#[derive(Default)]
+struct Stats {
+    open: AtomicUsize,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+    messages: AtomicU64,
+    bytes: AtomicU64,
+    commands: AtomicU64,
+    stalls: AtomicU64,
+    total_messages: AtomicU64,
+    total_bytes: AtomicU64,
+    samples: Mutex<Samples>,
+}
+
+impl Stats {
+    fn samples(&self) -> std::sync::MutexGuard<'_, Samples> {
+        self.samples.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    fn record(&self, bytes: usize, gap: Option<Duration>) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        let Some(gap) = gap else {
+            return;
+        };
+        if gap >= STALL {
+            self.stalls.fetch_add(1, Ordering::Relaxed);
+        }
+        let ms = u32::try_from(gap.as_millis()).unwrap_or(u32::MAX);
+        self.samples().gaps_ms.push(ms);
+    }
+}
+
+/// The `q` quantile of `values` (sorted in place); 0 when empty.
+fn percentile(values: &mut [u32], q: f64) -> u32 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    let i = ((values.len() - 1) as f64 * q).round() as usize;
+    values[i.min(values.len() - 1)]
+}
+
+struct Session {
+    kind: Kind,
+    url: String,
+    retune: Option<Duration>,
+    deadline: Instant,
+    seed: u64,
+}
+
+async fn run_session(session: Session, stats: Arc<Stats>) {
+    let started = Instant::now();
+    let connected = tokio::time::timeout(
+        SETTINGS_TIMEOUT,
+        tokio_tungstenite::connect_async(session.url.as_str()),
+    )
+    .await;
+    let mut ws = match connected {
+        Ok(Ok((ws, _))) => ws,
+        Ok(Err(e)) => {
+            stats.failed.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(kind = session.kind.path(), error = %e, "connect failed");
+            return;
+        }
+        Err(_) => {
+            stats.failed.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(kind = session.kind.path(), "connect timed out");
+            return;
+        }
+    };
+    let settings = loop {
+        match tokio::time::timeout_at(started + SETTINGS_TIMEOUT, ws.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => break Spectrum::from_settings(&text),
+            Ok(Some(Ok(_))) => continue,
+            _ => break Err(anyhow::anyhow!("no settings message")),
+        }
+    };
+    let spectrum = match settings {
+        Ok(s) => s,
+        Err(e) => {
+            stats.failed.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(kind = session.kind.path(), error = %e, "session setup failed");
+            return;
+        }
+    };
+    let connect_ms = u32::try_from(started.elapsed().as_millis()).unwrap_or(u32::MAX);
+    stats.samples().connect_ms.push(connect_ms);
+    stats.open.fetch_add(1, Ordering::Relaxed);
+
+    let mut rng = Rng::new(session.seed);
+    let next_retune = |rng: &mut Rng| {
+        session.retune.map(|mean| {
+            // Uniform over 0.5..1.5 of the mean, so sessions do not retune in lockstep.
+            let ms = mean.as_millis() as i64;
+            Instant::now() + Duration::from_millis(rng.range(ms / 2, ms + ms / 2 + 1) as u64)
+        })
+    };
+    let mut retune_at = next_retune(&mut rng);
+    let mut last_frame: Option<Instant> = None;
+    let mut completed = false;
+    loop {
+        tokio::select! {
+            msg = ws.next() => match msg {
+                Some(Ok(Message::Binary(b))) => {
+                    let now = Instant::now();
+                    stats.record(b.len(), last_frame.map(|t| now - t));
+                    last_frame = Some(now);
+                }
+                Some(Ok(Message::Text(t))) => stats.record(t.len(), None),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = tokio::time::sleep_until(retune_at.unwrap_or(session.deadline)), if retune_at.is_some() => {
+                let cmd = match session.kind {
+                    Kind::Audio => audio_command(&mut rng, spectrum),
+                    Kind::Waterfall => waterfall_command(&mut rng, spectrum),
+                };
+                if ws.send(Message::Text(cmd.to_string())).await.is_err() {
+                    break;
+                }
+                stats.commands.fetch_add(1, Ordering::Relaxed);
+                retune_at = next_retune(&mut rng);
+            }
+            _ = tokio::time::sleep_until(session.deadline) => {
+                completed = true;
+                break;
+            }
+        }
+    }
+    stats.open.fetch_sub(1, Ordering::Relaxed);
+    if completed {
+        let _ = ws.close(None).await;
+    } else {
+        stats.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn report(kind: Kind, stats: &Stats, interval: Duration) {
+    let messages = stats.messages.swap(0, Ordering::Relaxed);
+    let bytes = stats.bytes.swap(0, Ordering::Relaxed);
+    stats.total_messages.fetch_add(messages, Ordering::Relaxed);
+    stats.total_bytes.fetch_add(bytes, Ordering::Relaxed);
+    let mut gaps = {
+        let mut samples = stats.samples();
+        let gaps = std::mem::take(&mut samples.gaps_ms);
+        samples.all_gaps_ms.extend_from_slice(&gaps);
+        gaps
+    };
+    let secs = interval.as_secs_f64().max(f64::EPSILON);
+    tracing::info!(
+        kind = kind.path(),
+        open = stats.open.load(Ordering::Relaxed),
+        failed = stats.failed.load(Ordering::Relaxed),
+        dropped = stats.dropped.load(Ordering::Relaxed),
+        msgs_per_s = format!("{:.1}", messages as f64 / secs),
+        kib_per_s = format!("{:.1}", bytes as f64 / 1024.0 / secs),
+        gap_p50_ms = percentile(&mut gaps, 0.5),
+        gap_p99_ms = percentile(&mut gaps, 0.99),
+        gap_max_ms = percentile(&mut gaps, 1.0),
+        stalls = stats.stalls.swap(0, Ordering::Relaxed),
+        "load"
+    );
+}
+
+fn summary(kind: Kind, stats: &Stats, elapsed: Duration) {
+    let mut samples = stats.samples();
+    let Samples {
+        gaps_ms,
+        all_gaps_ms,
+        connect_ms,
+    } = &mut *samples;
+    // Whatever arrived since the last report.
+    all_gaps_ms.append(gaps_ms);
+    let messages =
+        stats.total_messages.load(Ordering::Relaxed) + stats.messages.swap(0, Ordering::Relaxed);
+    let bytes = stats.total_bytes.load(Ordering::Relaxed) + stats.bytes.swap(0, Ordering::Relaxed);
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    tracing::info!(
+        kind = kind.path(),
+        sessions = connect_ms.len(),
+        failed = stats.failed.load(Ordering::Relaxed),
+        dropped = stats.dropped.load(Ordering::Relaxed),
+        commands = stats.commands.load(Ordering::Relaxed),
+        messages,
+        mib = format!("{:.1}", bytes as f64 / 1024.0 / 1024.0),
+        kib_per_s = format!("{:.1}", bytes as f64 / 1024.0 / secs),
+        connect_p50_ms = percentile(connect_ms, 0.5),
+        connect_p99_ms = percentile(connect_ms, 0.99),
+        gap_p50_ms = percentile(all_gaps_ms, 0.5),
+        gap_p99_ms = percentile(all_gaps_ms, 0.99),
+        gap_max_ms = percentile(all_gaps_ms, 1.0),
+        "summary"
+    );
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_target(false)
+        .with_ansi(std::io::stdout().is_terminal())
+        .with_writer(std::io::stdout)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .try_init()
+        .map_err(|e| anyhow::anyhow!("init tracing: {e}"))?;
+
+    let args = Args::parse();
+    let base = args.url.trim_end_matches('/');
+    let mut kinds = Vec::new();
+    if !args.waterfall_only {
+        kinds.push((Kind::Audio, Arc::new(Stats::default())));
+    }
+    if !args.audio_only {
+        kinds.push((Kind::Waterfall, Arc::new(Stats::default())));
+    }
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(args.duration_secs);
+    let retune = (args.retune_ms > 0).then(|| Duration::from_millis(args.retune_ms));
+    tracing::info!(
+        url = base,
+        clients = args.clients,
+        duration_secs = args.duration_secs,
+        ramp_ms = args.ramp_ms,
+        "starting load"
+    );
+
+    let launcher = {
+        let kinds = kinds.clone();
+        let base = base.to_string();
+        let (clients, ramp, seed) = (args.clients, Duration::from_millis(args.ramp_ms), args.seed);
+        tokio::spawn(async move {
+            let mut tasks = Vec::new();
+            for i in 0..clients {
+                if Instant::now() >= deadline {
+                    break;
+                }
+                for (k, (kind, stats)) in kinds.iter().enumerate() {
+                    let session = Session {
+                        kind: *kind,
+                        url: format!("{base}/{}?markers=api", kind.path()),
+                        retune,
+                        deadline,
+                        seed: seed ^ ((i as u64) << 8 | k as u64),
+                    };
+                    tasks.push(tokio::spawn(run_session(session, stats.clone())));
+                }
+                tokio::time::sleep(ramp).await;
+            }
+            for task in tasks {
+                let _ = task.await;
+            }
+        })
+    };
+
+    let interval = Duration::from_secs(args.report_secs.max(1));
+    let mut ticker = tokio::time::interval_at(start + interval, interval);
+    let mut last = start;
+    while Instant::now() < deadline {
+        tokio::select! {
+            now = ticker.tick() => {
+                for (kind, stats) in &kinds {
+                    report(*kind, stats, now - last);
+                }
+                last = now;
+            }
+            _ = tokio::time::sleep_until(deadline) => {}
+        }
+    }
+    launcher.await.context("launcher task")?;
+    let now = Instant::now();
+    for (kind, stats) in &kinds {
+        // The last tick usually lands on the deadline; skip an empty tail interval.
+        if now - last >= Duration::from_secs(1) {
+            report(*kind, stats, now - last);
+        }
+        summary(*kind, stats, now - start);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_windows_stay_inside_what_the_server_accepts() {
+        let spectrum = Spectrum {
+            fft_result_size: 8192,
+            audio_max_fft: 768,
+        };
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            let cmd = audio_command(&mut rng, spectrum);
+            if cmd["cmd"] == "window" {
+                let (l, r) = (cmd["l"].as_i64().unwrap(), cmd["r"].as_i64().unwrap());
+                assert!(0 <= l && l < r && r < spectrum.fft_result_size);
+                assert!(r - l <= spectrum.audio_max_fft);
+            }
+            let cmd = waterfall_command(&mut rng, spectrum);
+            let (l, r) = (cmd["l"].as_i64().unwrap(), cmd["r"].as_i64().unwrap());
+            assert!(0 <= l && l < r && r <= spectrum.fft_result_size);
+        }
+    }
+
+    #[test]
+    fn percentiles_pick_from_sorted_values() {
+        let mut values = vec![50, 10, 40, 20, 30];
+        assert_eq!(percentile(&mut values, 0.5), 30);
+        assert_eq!(percentile(&mut values, 1.0), 50);
+        assert_eq!(percentile(&mut [], 0.99), 0);
+    }
+}